@@ -4,6 +4,7 @@ use std::path::Path;
 
 fn main() {
     // Run the standard Tauri build
+    #[cfg(feature = "gui")]
     tauri_build::build();
 
     // Get the output directory for generated code
@@ -12,7 +13,9 @@ fn main() {
 
     // Generate embedded audio samples
     println!("cargo:rerun-if-changed=resources/samples");
-    generate_audio_samples(dest_path);
+    if cfg!(feature = "audio") {
+        generate_audio_samples(dest_path);
+    }
 }
 
 fn generate_audio_samples(out_dir: &Path) {
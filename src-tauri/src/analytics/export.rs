@@ -0,0 +1,161 @@
+// Anonymized analytics export
+// Session ids become sequential pseudonyms and wall-clock times become offsets from the session start
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::log::{AnalyticsEvent, AnalyticsRecord};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+/// Record with identifying details removed
+#[derive(Debug, Clone, Serialize)]
+struct AnonymizedRecord {
+    /// "S1", "S2", ... in order of first appearance
+    session: String,
+    /// Seconds since the session's first event
+    offset_secs: f64,
+    #[serde(flatten)]
+    event: AnalyticsEvent,
+}
+
+const CSV_HEADER: &str = "session,offset_secs,event,chord,key,tier,worksheet_type";
+
+fn anonymize(records: &[AnalyticsRecord]) -> Vec<AnonymizedRecord> {
+    let mut sessions: HashMap<&str, (String, u64)> = HashMap::new();
+
+    records
+        .iter()
+        .map(|record| {
+            let next_id = sessions.len() + 1;
+            let (session, started) = sessions
+                .entry(record.session.as_str())
+                .or_insert_with(|| (format!("S{}", next_id), record.timestamp_ms));
+            AnonymizedRecord {
+                session: session.clone(),
+                offset_secs: record.timestamp_ms.saturating_sub(*started) as f64 / 1000.0,
+                event: record.event.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Characters that make a spreadsheet read a cell as a formula when they start it
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// Quote a CSV field when it contains separators or quotes
+/// A field that would start a formula is prefixed with an apostrophe, so spreadsheets show it as text
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(FORMULA_PREFIXES) { format!("'{}", value) } else { value.to_string() };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn csv_row(record: &AnonymizedRecord) -> String {
+    let (event, chord, key, tier, worksheet_type) = match &record.event {
+        AnalyticsEvent::ChordTried { chord, key } => ("chord_tried", chord.as_str(), key.clone(), None, ""),
+        AnalyticsEvent::RecommendationAccepted { chord, key, tier } => {
+            ("recommendation_accepted", chord.as_str(), key.clone(), *tier, "")
+        }
+        AnalyticsEvent::WorksheetGenerated { worksheet_type } => {
+            ("worksheet_generated", "", None, None, worksheet_type.as_str())
+        }
+    };
+    let tier = tier
+        .and_then(|t| serde_json::to_value(t).ok())
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+
+    [
+        record.session.clone(),
+        format!("{:.3}", record.offset_secs),
+        event.to_string(),
+        chord.to_string(),
+        key.unwrap_or_default(),
+        tier,
+        worksheet_type.to_string(),
+    ]
+    .iter()
+    .map(|field| csv_field(field))
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+/// Render records in the export format with identifying details removed
+pub fn export_records(records: &[AnalyticsRecord], format: ExportFormat) -> Result<String, String> {
+    let anonymized = anonymize(records);
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&anonymized)
+            .map_err(|e| format!("Failed to serialize analytics: {}", e)),
+        ExportFormat::Csv => {
+            let mut lines = vec![CSV_HEADER.to_string()];
+            lines.extend(anonymized.iter().map(csv_row));
+            Ok(lines.join("\n") + "\n")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::types::Tier;
+
+    fn record(session: &str, timestamp_ms: u64, event: AnalyticsEvent) -> AnalyticsRecord {
+        AnalyticsRecord { session: session.to_string(), timestamp_ms, event }
+    }
+
+    fn sample() -> Vec<AnalyticsRecord> {
+        vec![
+            record("a1b2", 1_700_000_000_000, AnalyticsEvent::ChordTried { chord: "Dm7".to_string(), key: Some("C".to_string()) }),
+            record(
+                "a1b2",
+                1_700_000_002_500,
+                AnalyticsEvent::RecommendationAccepted { chord: "G7".to_string(), key: None, tier: Some(Tier::Safe) },
+            ),
+            record("c3d4", 1_800_000_000_000, AnalyticsEvent::WorksheetGenerated { worksheet_type: "chordnaming".to_string() }),
+        ]
+    }
+
+    #[test]
+    fn test_csv_export_is_anonymized() {
+        let csv = export_records(&sample(), ExportFormat::Csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines[1], "S1,0.000,chord_tried,Dm7,C,,");
+        assert_eq!(lines[2], "S1,2.500,recommendation_accepted,G7,,safe,");
+        assert_eq!(lines[3], "S2,0.000,worksheet_generated,,,,chordnaming");
+        assert!(!csv.contains("a1b2"));
+    }
+
+    #[test]
+    fn test_json_export() {
+        let json = export_records(&sample(), ExportFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value[1]["session"], "S1");
+        assert_eq!(value[1]["type"], "recommendation_accepted");
+        assert_eq!(value[1]["offset_secs"], 2.5);
+        assert!(value[0].get("timestamp_ms").is_none());
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("C,E"), "\"C,E\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        // Formulas are neutralized, quoted as well when they hold separators
+        assert_eq!(csv_field("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(csv_field("+1"), "'+1");
+        assert_eq!(csv_field("-2+3"), "'-2+3");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("C-7"), "C-7");
+    }
+}
@@ -0,0 +1,141 @@
+// Opt-in interaction log for education research
+// Records are appended as JSON lines to a local file and never leave the machine unless exported
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::music::types::Tier;
+
+/// File name of the analytics log inside the app config directory
+pub const ANALYTICS_FILE_NAME: &str = "analytics.jsonl";
+
+/// An interaction with the theory engine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnalyticsEvent {
+    ChordTried { chord: String, key: Option<String> },
+    RecommendationAccepted { chord: String, key: Option<String>, tier: Option<Tier> },
+    WorksheetGenerated { worksheet_type: String },
+}
+
+/// One logged event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalyticsRecord {
+    /// Random id for one app run
+    pub session: String,
+    /// Unix timestamp (milliseconds)
+    pub timestamp_ms: u64,
+    pub event: AnalyticsEvent,
+}
+
+/// Local analytics log, recording only while enabled
+pub struct AnalyticsLog {
+    path: PathBuf,
+    session: String,
+    enabled: bool,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+impl AnalyticsLog {
+    pub fn new(path: PathBuf, enabled: bool) -> Self {
+        Self { path, session: Uuid::new_v4().to_string(), enabled }
+    }
+
+    /// Log stored in the standard file inside a config directory
+    pub fn in_dir(config_dir: &Path, enabled: bool) -> Self {
+        Self::new(config_dir.join(ANALYTICS_FILE_NAME), enabled)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Append an event (no-op while disabled)
+    pub fn record(&self, event: AnalyticsEvent) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let record = AnalyticsRecord { session: self.session.clone(), timestamp_ms: now_ms(), event };
+        let line = serde_json::to_string(&record).map_err(|e| format!("Failed to serialize analytics event: {}", e))?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create analytics directory: {}", e))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open analytics log: {}", e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write analytics log: {}", e))
+    }
+
+    /// Read all logged records, skipping unreadable lines
+    pub fn records(&self) -> Vec<AnalyticsRecord> {
+        fs::read_to_string(&self.path)
+            .map(|text| text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Delete every logged record
+    pub fn clear(&self) -> Result<(), String> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to clear analytics log: {}", e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn chord_tried(chord: &str) -> AnalyticsEvent {
+        AnalyticsEvent::ChordTried { chord: chord.to_string(), key: Some("C".to_string()) }
+    }
+
+    #[test]
+    fn test_records_only_when_enabled() {
+        let dir = TempDir::new().unwrap();
+        let mut log = AnalyticsLog::in_dir(dir.path(), false);
+        log.record(chord_tried("F")).unwrap();
+        assert!(log.records().is_empty());
+
+        log.set_enabled(true);
+        log.record(chord_tried("G")).unwrap();
+        log.record(AnalyticsEvent::WorksheetGenerated { worksheet_type: "chordnaming".to_string() }).unwrap();
+
+        let records = log.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].event, chord_tried("G"));
+        assert_eq!(records[0].session, records[1].session);
+    }
+
+    #[test]
+    fn test_clear() {
+        let dir = TempDir::new().unwrap();
+        let log = AnalyticsLog::in_dir(dir.path(), true);
+        log.record(chord_tried("C")).unwrap();
+        log.clear().unwrap();
+        assert!(log.records().is_empty());
+        // Clearing an empty log is fine
+        log.clear().unwrap();
+    }
+}
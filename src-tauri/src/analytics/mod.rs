@@ -0,0 +1,5 @@
+mod export;
+mod log;
+
+pub use export::{export_records, ExportFormat};
+pub use log::{AnalyticsEvent, AnalyticsLog};
@@ -0,0 +1,259 @@
+// Desktop app
+// Registers the managed state and every command with Tauri, loads the stored settings at launch
+// and finishes the autosave on exit
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{Manager, RunEvent};
+use crate::{audio, commands, lilypond, music};
+use crate::documents::DocumentMap;
+use crate::analytics::AnalyticsLog;
+use crate::autosave::Autosave;
+use crate::recent_files::RecentFilesStore;
+use crate::practice_stats::PracticeStats;
+use crate::commands::analytics::AnalyticsState;
+use crate::commands::analysis::AnalysisState;
+use crate::commands::autosave::AutosaveState;
+use crate::commands::audio::{AudioState, VoicingSessionState, VoicingSessions};
+use crate::commands::documents::release_window_documents;
+use crate::commands::history::RenderHistoryState;
+use crate::commands::midi::MidiState;
+use crate::commands::music::ProgressionDbState;
+use crate::commands::ear_training::{EarTrainingSessionState, EarTrainingState, ProgressionDrillState, IntonationDrillState};
+use crate::commands::battle::{BattleState, BattleMidiState};
+use crate::commands::quiz::QuizState;
+use crate::commands::settings::SettingsState;
+use crate::commands::preview::PreviewState;
+use crate::commands::song::SongState;
+use crate::commands::policy::PolicyState;
+use crate::commands::recent_files::RecentFilesState;
+use crate::commands::practice_stats::PracticeStatsState;
+use crate::music::progression_db::ProgressionDatabase;
+use crate::render_history::RenderHistory;
+use crate::settings::{Policy, SettingsStore};
+
+/// Build and run the desktop app
+pub fn run() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_fs::init())
+        .manage(AudioState(Mutex::new(DocumentMap::default())))
+        .manage(VoicingSessionState(Mutex::new(VoicingSessions::default())))
+        .manage(AnalysisState(Mutex::new(DocumentMap::default())))
+        .manage(QuizState(Mutex::new(None)))
+        .manage(BattleState(Mutex::new(None)))
+        .manage(BattleMidiState(Mutex::new(Vec::new())))
+        .manage(EarTrainingState(Mutex::new(None)))
+        .manage(EarTrainingSessionState(Mutex::new(None)))
+        .manage(ProgressionDrillState(Mutex::new(None)))
+        .manage(IntonationDrillState(Mutex::new(None)))
+        .manage(PreviewState::default())
+        .manage(MidiState(Mutex::new(HashMap::new())))
+        .manage(SongState(Mutex::new(DocumentMap::default())))
+        .setup(|app| {
+            let config_dir = app.path().app_config_dir()?;
+            let store = SettingsStore::load_from_dir(&config_dir);
+            app.manage(PolicyState(Policy::load_from_dir(&config_dir)));
+            music::aliases::set_user_aliases(store.settings().aliases.clone());
+            music::notes::set_simplify_spellings(store.settings().simplify_spellings);
+            lilypond::set_render_timeout(store.settings().render_timeout_secs);
+            audio::set_voice_limit(store.settings().voice_limit);
+            music::tiers::set_tier_thresholds(store.settings().tier_thresholds);
+            app.manage(RecentFilesState(Mutex::new(RecentFilesStore::load_from_dir(&config_dir))));
+            app.manage(AnalyticsState(Mutex::new(AnalyticsLog::in_dir(&config_dir, store.settings().analytics_enabled))));
+            app.manage(SettingsState(Mutex::new(store)));
+            let data_dir = app.path().app_data_dir()?;
+            app.manage(RenderHistoryState(Mutex::new(RenderHistory::in_dir(&data_dir))));
+            app.manage(ProgressionDbState(Mutex::new(ProgressionDatabase::in_dir(&data_dir))));
+            app.manage(PracticeStatsState(Mutex::new(PracticeStats::in_dir(&data_dir))));
+            lilypond::set_render_cache_dir(&data_dir);
+            app.manage(AutosaveState(Mutex::new(Autosave::start_in_dir(&data_dir))));
+            commands::autosave::spawn_autosave_task(app.handle().clone());
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                release_window_documents(window.app_handle(), window.label());
+            }
+        })
+        .invoke_handler(tauri::generate_handler![
+            // LilyPond commands
+            commands::lilypond::render_lilypond,
+            commands::lilypond::cancel_render,
+            commands::lilypond::clear_render_cache,
+            // Worksheet generation commands
+            commands::worksheet::generate_worksheet,
+            commands::worksheet::generate_worksheet_pair,
+            commands::worksheet::generate_worksheet_batch,
+            commands::worksheet::generate_chord_naming_template,
+            commands::worksheet::generate_performance_template,
+            commands::worksheet::import_musicxml,
+            commands::worksheet::generate_reference_chart,
+            commands::worksheet::get_worksheet_locales,
+            commands::worksheet::generate_whole_key_template,
+            commands::preview::preview_worksheet,
+            commands::history::list_render_history,
+            commands::history::restore_render,
+            commands::library::search_library,
+            commands::library::load_library_progression,
+            commands::library::create_library_worksheet,
+            commands::tutorial::list_tutorial_steps,
+            commands::tutorial::verify_exercise,
+            // Music theory commands
+            commands::music::generate_chord_pitches,
+            commands::music::get_chord_qualities,
+            commands::music::generate_scale_pitches,
+            commands::music::get_scale_types,
+            commands::music::validate_chord,
+            commands::music::score_playability,
+            commands::music::complete_progression,
+            commands::music::compose_progressions,
+            commands::music::get_chord_recommendations,
+            commands::music::query_progression_stats,
+            commands::music::import_progression_dataset,
+            commands::music::classify_tier,
+            commands::music::explain_chord,
+            commands::music::compare_chords,
+            commands::music::diff_progressions,
+            commands::music::merge_progressions,
+            commands::music::identify_chord,
+            commands::music::detect_key,
+            commands::music::extract_guide_tones,
+            commands::music::get_voice_motions,
+            commands::music::analyze_progression_voicing,
+            // Notation layout commands
+            commands::notation::get_key_signature_layout,
+            commands::notation::get_rhythm_layout,
+            commands::notation::render_voicing,
+            commands::notation::render_keyboard_diagram,
+            commands::notation::render_guide_tones,
+            // Live analysis commands
+            commands::analysis::apply_progression_edit,
+            commands::analysis::get_progression_analysis,
+            commands::analysis::analyze_writing_habits,
+            // Song commands
+            commands::song::get_song,
+            commands::song::apply_song_edit,
+            commands::song::save_song,
+            commands::song::load_song,
+            commands::song::play_song,
+            commands::song::render_lead_sheet,
+            // Project commands
+            commands::project::save_project,
+            commands::project::load_project,
+            commands::recent_files::get_recent_projects,
+            commands::recent_files::add_recent_project,
+            commands::recent_files::pin_recent_project,
+            // Autosave commands
+            commands::autosave::update_autosave_state,
+            commands::autosave::recover_last_session,
+            // Audio playback commands
+            commands::audio::init_audio,
+            commands::audio::play_chord,
+            commands::audio::play_notes,
+            commands::audio::play_arpeggio,
+            commands::audio::stop_audio,
+            commands::audio::set_volume,
+            commands::audio::reset_voicing,
+            commands::audio::create_voicing_session,
+            commands::audio::reset_voicing_session,
+            commands::audio::drop_voicing_session,
+            commands::audio::play_one_shot,
+            commands::audio::play_sequence,
+            commands::audio::pause_sequence,
+            commands::audio::resume_sequence,
+            commands::audio::seek_sequence,
+            commands::audio::start_metronome,
+            commands::audio::set_metronome,
+            commands::audio::stop_metronome,
+            commands::audio::analyze_audio_file,
+            // Document commands
+            commands::documents::close_document,
+            // Export commands
+            commands::export::export_pdf,
+            commands::diagnostics::verify_installation,
+            commands::diagnostics::create_support_bundle,
+            commands::export::export_progression_png,
+            commands::export::export_png,
+            commands::export::export_svg,
+            commands::export::export_practice_track,
+            commands::export::export_audio,
+            commands::export::export_braille,
+            // Settings commands
+            commands::policy::get_policy,
+            commands::settings::get_chord_vocabulary,
+            commands::settings::record_chord_usage,
+            commands::settings::set_favorite_chord,
+            commands::settings::set_favorite_quality,
+            commands::settings::clear_recent_chords,
+            commands::settings::get_alias_dictionary,
+            commands::settings::export_alias_dictionary,
+            commands::settings::import_alias_dictionary,
+            commands::settings::get_simplify_spellings,
+            commands::settings::set_simplify_spellings,
+            commands::settings::get_render_timeout,
+            commands::settings::set_render_timeout,
+            commands::settings::get_voice_limit,
+            commands::settings::set_voice_limit,
+            commands::settings::get_tier_thresholds,
+            commands::settings::set_tier_thresholds,
+            // Quiz commands
+            commands::quiz::start_chord_quiz,
+            commands::quiz::next_quiz_question,
+            commands::quiz::replay_quiz_question,
+            commands::quiz::submit_quiz_answer,
+            commands::quiz::finish_chord_quiz,
+            commands::quiz::get_quiz_high_scores,
+            commands::quiz::grade_answer,
+            commands::battle::start_battle,
+            commands::battle::next_battle_question,
+            commands::battle::replay_battle_question,
+            commands::battle::submit_battle_answer,
+            commands::battle::open_battle_midi_input,
+            commands::battle::close_battle_midi_inputs,
+            commands::battle::finish_battle,
+            // Ear training commands
+            commands::ear_training::start_interval_drill,
+            commands::ear_training::next_interval_question,
+            commands::ear_training::replay_interval_question,
+            commands::ear_training::submit_interval_answer,
+            commands::ear_training::get_interval_stats,
+            commands::ear_training::start_progression_drill,
+            commands::ear_training::next_progression_question,
+            commands::ear_training::replay_progression_question,
+            commands::ear_training::submit_progression_answer,
+            commands::ear_training::start_intonation_drill,
+            commands::ear_training::next_intonation_question,
+            commands::ear_training::replay_intonation_question,
+            commands::ear_training::submit_intonation_answer,
+            commands::ear_training::start_ear_training_session,
+            commands::ear_training::generate_ear_training_question,
+            commands::ear_training::grade_ear_training_answer,
+            // Practice statistics commands
+            commands::practice_stats::record_attempt,
+            commands::practice_stats::get_progress_summary,
+            commands::practice_stats::get_streaks,
+            // MIDI input commands
+            commands::midi::list_midi_inputs,
+            commands::midi::open_midi_input,
+            commands::midi::close_midi_input,
+            // Analytics commands
+            commands::analytics::get_analytics_enabled,
+            commands::analytics::set_analytics_enabled,
+            commands::analytics::record_analytics_event,
+            commands::analytics::export_analytics,
+            commands::analytics::clear_analytics,
+            // Import commands
+            commands::ocr::import_chord_chart,
+            // Curriculum pack commands
+            commands::curriculum::export_curriculum_pack,
+            commands::curriculum::import_curriculum_pack,
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                commands::autosave::finish_autosave(app);
+            }
+        });
+}
\ No newline at end of file
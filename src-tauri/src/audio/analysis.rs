@@ -0,0 +1,261 @@
+// Chord detection from audio recordings (experimental)
+// Builds a chromagram with Goertzel filters and matches each frame against triad templates
+
+use rodio::{Decoder, Source};
+use serde::Serialize;
+use std::fs::File;
+
+/// Working sample rate after decimation - plenty for chroma up to B6
+const ANALYSIS_SAMPLE_RATE: u32 = 11025;
+
+/// Samples per analysis frame (~0.37s at 11025 Hz)
+const FRAME_SIZE: usize = 4096;
+
+/// MIDI range folded into the chromagram (C2-B6)
+const CHROMA_MIN_MIDI: u8 = 36;
+const CHROMA_MAX_MIDI: u8 = 95;
+
+/// Frames quieter than this RMS are treated as silence
+const SILENCE_RMS: f32 = 0.01;
+
+/// Segments shorter than this (seconds) are merged into their neighbour
+const MIN_SEGMENT_SECONDS: f32 = 0.7;
+
+/// Pitch-class names used for detected chords (common lead-sheet spelling)
+const PITCH_CLASS_NAMES: [&str; 12] = [
+    "C", "Db", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B",
+];
+
+/// A detected chord with its time span in the recording
+#[derive(Debug, Clone, Serialize)]
+pub struct ChordSegment {
+    pub chord: String,
+    pub start: f32,
+    pub end: f32,
+    /// Mean template similarity (0.0-1.0)
+    pub confidence: f32,
+}
+
+/// Result of analyzing an audio file
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioAnalysis {
+    pub segments: Vec<ChordSegment>,
+    /// Chord names with consecutive repeats removed
+    pub progression: Vec<String>,
+    pub duration: f32,
+}
+
+/// Decode a file into mono samples at ANALYSIS_SAMPLE_RATE (approximately)
+/// Returns the samples and their actual sample rate
+fn decode_mono(path: &str) -> Result<(Vec<f32>, u32), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+    let decoder = Decoder::try_from(file).map_err(|e| format!("Failed to decode audio file: {}", e))?;
+
+    let channels = decoder.channels().max(1) as usize;
+    let sample_rate = decoder.sample_rate();
+    let samples: Vec<f32> = decoder.collect();
+
+    let mono: Vec<f32> = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+
+    Ok(decimate(&mono, sample_rate))
+}
+
+/// Reduce the sample rate by an integer factor, averaging each block
+fn decimate(samples: &[f32], sample_rate: u32) -> (Vec<f32>, u32) {
+    let factor = (sample_rate / ANALYSIS_SAMPLE_RATE).max(1) as usize;
+    let reduced = samples
+        .chunks(factor)
+        .map(|block| block.iter().sum::<f32>() / block.len() as f32)
+        .collect();
+    (reduced, sample_rate / factor as u32)
+}
+
+/// Goertzel power of a single frequency within a frame
+fn goertzel_power(frame: &[f32], frequency: f32, sample_rate: u32) -> f32 {
+    let omega = 2.0 * std::f32::consts::PI * frequency / sample_rate as f32;
+    let coeff = 2.0 * omega.cos();
+    let (mut s1, mut s2) = (0.0_f32, 0.0_f32);
+
+    for &sample in frame {
+        let s0 = sample + coeff * s1 - s2;
+        s2 = s1;
+        s1 = s0;
+    }
+
+    s1 * s1 + s2 * s2 - coeff * s1 * s2
+}
+
+fn midi_frequency(midi: u8) -> f32 {
+    440.0 * 2.0_f32.powf((midi as f32 - 69.0) / 12.0)
+}
+
+/// Normalized 12-bin chroma vector for one frame
+fn chroma_vector(frame: &[f32], sample_rate: u32) -> [f32; 12] {
+    let mut chroma = [0.0_f32; 12];
+
+    for midi in CHROMA_MIN_MIDI..=CHROMA_MAX_MIDI {
+        let frequency = midi_frequency(midi);
+        if frequency >= sample_rate as f32 / 2.0 {
+            break;
+        }
+        chroma[(midi % 12) as usize] += goertzel_power(frame, frequency, sample_rate).sqrt();
+    }
+
+    let norm = chroma.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        chroma.iter_mut().for_each(|v| *v /= norm);
+    }
+    chroma
+}
+
+/// Best matching major/minor triad for a chroma vector: (chord name, similarity)
+fn match_chord(chroma: &[f32; 12]) -> (String, f32) {
+    let mut best = (String::new(), f32::MIN);
+
+    for root in 0..12 {
+        for (suffix, third) in [("", 4), ("m", 3)] {
+            // Cosine similarity with a unit-weight triad template
+            let score = (chroma[root] + chroma[(root + third) % 12] + chroma[(root + 7) % 12]) / 3.0_f32.sqrt();
+            if score > best.1 {
+                best = (format!("{}{}", PITCH_CLASS_NAMES[root], suffix), score);
+            }
+        }
+    }
+
+    best
+}
+
+fn rms(frame: &[f32]) -> f32 {
+    (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt()
+}
+
+/// Label each frame with a chord (None for silence)
+fn label_frames(samples: &[f32], sample_rate: u32) -> Vec<Option<(String, f32)>> {
+    samples
+        .chunks(FRAME_SIZE)
+        .filter(|frame| frame.len() == FRAME_SIZE)
+        .map(|frame| {
+            if rms(frame) < SILENCE_RMS {
+                None
+            } else {
+                Some(match_chord(&chroma_vector(frame, sample_rate)))
+            }
+        })
+        .collect()
+}
+
+/// Merge frame labels into timed segments, absorbing very short segments
+fn build_segments(labels: &[Option<(String, f32)>], frame_seconds: f32) -> Vec<ChordSegment> {
+    // (chord, first frame, one past last frame, summed score)
+    let mut runs: Vec<(String, usize, usize, f32)> = Vec::new();
+
+    for (i, label) in labels.iter().enumerate() {
+        let Some((chord, score)) = label else {
+            continue;
+        };
+
+        match runs.last_mut() {
+            Some(run) if run.0 == *chord && run.2 == i => {
+                run.2 = i + 1;
+                run.3 += score;
+            }
+            _ => runs.push((chord.clone(), i, i + 1, *score)),
+        }
+    }
+
+    let min_frames = (MIN_SEGMENT_SECONDS / frame_seconds).ceil() as usize;
+    let mut merged: Vec<(String, usize, usize, f32)> = Vec::new();
+    for run in runs {
+        match merged.last_mut() {
+            // Short blips and repeats touching the previous run extend it
+            Some(prev) if prev.2 == run.1 && (run.2 - run.1 < min_frames || prev.0 == run.0) => {
+                prev.2 = run.2;
+                prev.3 += run.3;
+            }
+            _ => merged.push(run),
+        }
+    }
+
+    merged
+        .into_iter()
+        .map(|(chord, first, last, score)| ChordSegment {
+            chord,
+            start: first as f32 * frame_seconds,
+            end: last as f32 * frame_seconds,
+            confidence: (score / (last - first) as f32).clamp(0.0, 1.0),
+        })
+        .collect()
+}
+
+/// Estimate a chord progression from already-decoded mono samples
+fn analyze_samples(samples: &[f32], sample_rate: u32) -> AudioAnalysis {
+    let frame_seconds = FRAME_SIZE as f32 / sample_rate as f32;
+    let segments = build_segments(&label_frames(samples, sample_rate), frame_seconds);
+
+    let mut progression: Vec<String> = Vec::new();
+    for segment in &segments {
+        if progression.last() != Some(&segment.chord) {
+            progression.push(segment.chord.clone());
+        }
+    }
+
+    AudioAnalysis {
+        segments,
+        progression,
+        duration: samples.len() as f32 / sample_rate as f32,
+    }
+}
+
+/// Analyze an audio file (WAV, MP3, OGG, FLAC) and estimate its chords
+pub fn analyze_file(path: &str) -> Result<AudioAnalysis, String> {
+    let (samples, sample_rate) = decode_mono(path)?;
+    if samples.len() < FRAME_SIZE {
+        return Err("Audio file is too short to analyze".to_string());
+    }
+    Ok(analyze_samples(&samples, sample_rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synth_chord(midi_notes: &[u8], seconds: f32) -> Vec<f32> {
+        let count = (seconds * ANALYSIS_SAMPLE_RATE as f32) as usize;
+        (0..count)
+            .map(|i| {
+                let t = i as f32 / ANALYSIS_SAMPLE_RATE as f32;
+                midi_notes
+                    .iter()
+                    .map(|&m| (2.0 * std::f32::consts::PI * midi_frequency(m) * t).sin() * 0.2)
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_major_and_minor_triads() {
+        let mut samples = synth_chord(&[60, 64, 67], 2.0); // C major
+        samples.extend(synth_chord(&[57, 60, 64], 2.0)); // A minor
+
+        let analysis = analyze_samples(&samples, ANALYSIS_SAMPLE_RATE);
+        assert_eq!(analysis.progression, vec!["C", "Am"]);
+        assert!(analysis.segments[1].start > 1.0);
+    }
+
+    #[test]
+    fn test_silence_produces_no_segments() {
+        let samples = vec![0.0; FRAME_SIZE * 4];
+        let analysis = analyze_samples(&samples, ANALYSIS_SAMPLE_RATE);
+        assert!(analysis.segments.is_empty());
+    }
+
+    #[test]
+    fn test_decimate_halves_rate() {
+        let (reduced, rate) = decimate(&[1.0, 3.0, 5.0, 7.0], 22050);
+        assert_eq!(rate, 11025);
+        assert_eq!(reduced, vec![2.0, 6.0]);
+    }
+}
@@ -0,0 +1,82 @@
+// Arpeggios
+// Order and timing for playing a voiced chord one note at a time
+
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::music::notes::note_index;
+use crate::music::types::AudioNote;
+
+/// Longest gap allowed between arpeggio notes
+pub const MAX_ARPEGGIO_STEP: Duration = Duration::from_secs(2);
+
+/// Order an arpeggio plays its notes in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArpeggioDirection {
+    /// Lowest note to highest
+    #[default]
+    Up,
+    /// Highest note to lowest
+    Down,
+    /// Up to the highest note and back down to the lowest, without repeating the top
+    UpDown,
+}
+
+fn pitch(note: &AudioNote) -> i32 {
+    note.octave as i32 * 12 + note_index(&note.note).map_or(0, i32::from)
+}
+
+/// Notes in the order they sound, each with its start time from the first
+pub fn arpeggiate(notes: &[AudioNote], direction: ArpeggioDirection, step: Duration) -> Vec<(AudioNote, Duration)> {
+    let mut ascending: Vec<&AudioNote> = notes.iter().collect();
+    ascending.sort_by_key(|note| pitch(note));
+
+    let order: Vec<&AudioNote> = match direction {
+        ArpeggioDirection::Up => ascending,
+        ArpeggioDirection::Down => ascending.into_iter().rev().collect(),
+        ArpeggioDirection::UpDown => {
+            let descending = ascending.iter().rev().skip(1).copied().collect::<Vec<_>>();
+            ascending.into_iter().chain(descending).collect()
+        }
+    };
+
+    order.into_iter().enumerate().map(|(index, note)| (note.clone(), step * index as u32)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(name: &str, octave: i8) -> AudioNote {
+        AudioNote { note: name.to_string(), octave, is_common_tone: false, cents: 0.0 }
+    }
+
+    fn names(arpeggio: &[(AudioNote, Duration)]) -> Vec<String> {
+        arpeggio.iter().map(|(note, _)| format!("{}{}", note.note, note.octave)).collect()
+    }
+
+    #[test]
+    fn test_arpeggio_directions() {
+        let chord = [note("G", 4), note("C", 4), note("E", 4), note("C", 5)];
+        let step = Duration::from_millis(120);
+
+        assert_eq!(names(&arpeggiate(&chord, ArpeggioDirection::Up, step)), ["C4", "E4", "G4", "C5"]);
+        assert_eq!(names(&arpeggiate(&chord, ArpeggioDirection::Down, step)), ["C5", "G4", "E4", "C4"]);
+        assert_eq!(
+            names(&arpeggiate(&chord, ArpeggioDirection::UpDown, step)),
+            ["C4", "E4", "G4", "C5", "G4", "E4", "C4"]
+        );
+    }
+
+    #[test]
+    fn test_arpeggio_timing() {
+        let chord = [note("B", 3), note("D", 4), note("F", 4)];
+        let starts: Vec<Duration> = arpeggiate(&chord, ArpeggioDirection::Up, Duration::from_millis(250))
+            .into_iter()
+            .map(|(_, start)| start)
+            .collect();
+        assert_eq!(starts, [Duration::ZERO, Duration::from_millis(250), Duration::from_millis(500)]);
+        assert!(arpeggiate(&[], ArpeggioDirection::UpDown, Duration::from_millis(250)).is_empty());
+    }
+}
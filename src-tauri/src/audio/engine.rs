@@ -1,13 +1,25 @@
+use rodio::mixer::Mixer;
 use rodio::source::LimitSettings;
-use rodio::{Decoder, OutputStreamBuilder, Sink, Source};
+#[cfg(all(feature = "playback", not(feature = "headless-audio")))]
+use rodio::OutputStreamBuilder;
+use rodio::{Decoder, Sink, Source};
 use std::io::Cursor;
-use std::sync::mpsc::{self, Receiver, Sender};
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use super::envelope::TwoStageEnvelopeExt;
+use super::arpeggio::{arpeggiate, ArpeggioDirection};
+use super::envelope::{ReleaseAfterExt, TwoStageEnvelopeExt};
+use super::metronome::{follow_schedule, Metronome, MetronomeBeat, MetronomePattern, MetronomeSource};
 use super::monitor::AudioMonitorExt;
+#[cfg(any(feature = "headless-audio", not(feature = "playback")))]
+use super::output::null::NullOutput;
+use super::output::AudioOutput;
 use super::samples::{get_sample, note_to_sample_key};
+use super::sequence::{SequenceChord, SequenceClock, SequenceListener, Timeline};
+use crate::music::tempo::TempoMap;
 use crate::music::types::AudioNote;
 
 /// Volume multiplier for chord playback (piano samples)
@@ -20,6 +32,12 @@ const ONESHOT_VOLUME_MULTIPLIER: f32 = 0.5;
 /// Removes sub-bass that muddles mobile speakers
 const CHORD_HIGHPASS_FREQ: u32 = 150;
 
+/// Playback speed that shifts a sample by the given number of cents
+/// Resampling changes pitch and length together, which is inaudible at intonation-training offsets
+fn detune_ratio(cents: f32) -> f32 {
+    2f32.powf(cents / 1200.0)
+}
+
 /// Limiter settings to prevent clipping without causing artifacts
 fn chord_limiter_settings() -> LimitSettings {
     LimitSettings::default()
@@ -36,6 +54,134 @@ const MAKEUP_GAIN: f32 = 3.0;
 /// Piano samples ring ~2 sec, so fade over most of that duration
 const TAIL_FADEOUT_DURATION: Duration = Duration::from_millis(2000);
 
+/// Volume multiplier for metronome clicks (synthesized at full scale)
+pub(super) const METRONOME_VOLUME_MULTIPLIER: f32 = 0.5;
+
+/// How often the audio thread checks for new metronome beats to report
+const METRONOME_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Voices kept ringing alongside the current chord when no limit has been set, counting earlier chords,
+/// sequence notes, the metronome and one-shots
+pub const DEFAULT_VOICE_LIMIT: usize = 32;
+
+/// Voice limits that can be set; the lowest still lets a full chord decay under the next
+pub const VOICE_LIMIT_RANGE: RangeInclusive<usize> = 8..=256;
+
+static VOICE_LIMIT: AtomicUsize = AtomicUsize::new(DEFAULT_VOICE_LIMIT);
+
+/// Set the polyphony cap of every engine (None = the default), clamped to VOICE_LIMIT_RANGE
+/// Applies from the next chord played
+pub fn set_voice_limit(voices: Option<usize>) {
+    let voices = voices.unwrap_or(DEFAULT_VOICE_LIMIT);
+    VOICE_LIMIT.store(voices.clamp(*VOICE_LIMIT_RANGE.start(), *VOICE_LIMIT_RANGE.end()), Ordering::Relaxed);
+}
+
+pub fn voice_limit() -> usize {
+    VOICE_LIMIT.load(Ordering::Relaxed)
+}
+
+/// Level of chord playback while a one-shot plays, relative to normal
+const DUCK_LEVEL: f32 = 0.4;
+
+/// Ramp down to the ducked level when a one-shot starts
+const DUCK_ATTACK: Duration = Duration::from_millis(30);
+
+/// Ramp back to full level once the one-shot has finished
+const DUCK_RELEASE: Duration = Duration::from_millis(300);
+
+/// How long to duck for a one-shot whose length the decoder can't tell
+const DUCK_DEFAULT_HOLD: Duration = Duration::from_millis(500);
+
+/// How often the audio thread steps the ducking ramps
+const DUCK_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Gain of chord playback `elapsed` into a duck that started at gain `from` and holds for `hold`
+/// Ramps down to DUCK_LEVEL, holds while the one-shot plays, then eases back to full level
+fn duck_gain(from: f32, elapsed: Duration, hold: Duration) -> f32 {
+    let hold = hold.max(DUCK_ATTACK);
+    if elapsed < DUCK_ATTACK {
+        let t = elapsed.as_secs_f32() / DUCK_ATTACK.as_secs_f32();
+        from + (DUCK_LEVEL - from) * t
+    } else if elapsed < hold {
+        DUCK_LEVEL
+    } else {
+        let t = ((elapsed - hold).as_secs_f32() / DUCK_RELEASE.as_secs_f32()).min(1.0);
+        // Smoothstep, so the restore has no corner at either end
+        DUCK_LEVEL + (1.0 - DUCK_LEVEL) * t * t * (3.0 - 2.0 * t)
+    }
+}
+
+/// Chord playback attenuated while one-shots play
+#[derive(Debug, Clone, Copy)]
+struct Ducking {
+    /// Gain when this duck started, so a one-shot during a release ramps on from where it was
+    from: f32,
+    started: Instant,
+    /// Length of the one-shot (or overlapping one-shots) being ducked for
+    hold: Duration,
+}
+
+impl Ducking {
+    /// Duck for a one-shot of the given length starting now, extending a duck already under way
+    fn start(previous: Option<&Ducking>, length: Duration, now: Instant) -> Self {
+        let (from, hold) = match previous {
+            Some(duck) => {
+                let remaining = (duck.started + duck.hold).saturating_duration_since(now);
+                (duck.gain(now), length.max(remaining))
+            }
+            None => (1.0, length),
+        };
+        Self { from, started: now, hold }
+    }
+
+    fn gain(&self, now: Instant) -> f32 {
+        duck_gain(self.from, now.saturating_duration_since(self.started), self.hold)
+    }
+
+    fn is_finished(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.started) >= self.hold.max(DUCK_ATTACK) + DUCK_RELEASE
+    }
+}
+
+/// Release at a sequence chord boundary, overlapping the next chord's attack
+const SEQUENCE_RELEASE_DURATION: Duration = Duration::from_millis(60);
+
+/// Release of a sequence chord: the last one rings out, the others make way for the next chord
+pub(super) fn sequence_release(is_last: bool) -> Duration {
+    if is_last {
+        TAIL_FADEOUT_DURATION
+    } else {
+        SEQUENCE_RELEASE_DURATION
+    }
+}
+
+/// One note of a sequence chord through the chord signal chain, starting `into` the note
+/// and released after `hold`; None when the note has no sample
+pub(super) fn sequence_note_source(
+    audio_note: &AudioNote,
+    into: Duration,
+    hold: Duration,
+    release: Duration,
+) -> Option<impl Source<Item = f32>> {
+    let sample_key = note_to_sample_key(&audio_note.note, audio_note.octave);
+    let Some(source) = get_sample(&sample_key).and_then(|bytes| Decoder::new(Cursor::new(bytes)).ok()) else {
+        eprintln!("Warning: No sample found for {}", sample_key);
+        return None;
+    };
+    let source = source.speed(detune_ratio(audio_note.cents)).skip_duration(into);
+    Some(chord_signal_chain(source).release_after(hold, release))
+}
+
+/// Signal chain of a chord note: envelope → highpass → amplify → limit → makeup
+fn chord_signal_chain(source: impl Source<Item = f32>) -> impl Source<Item = f32> {
+    source
+        .two_stage_envelope()
+        .high_pass(CHORD_HIGHPASS_FREQ)
+        .amplify(CHORD_VOLUME_MULTIPLIER)
+        .limit(chord_limiter_settings())
+        .amplify(MAKEUP_GAIN)
+}
+
 /// Quick fade-out all sinks to prevent click artifacts, then stop them
 fn fade_out_and_stop_sinks(sinks: &mut Vec<Sink>) {
     // Ramp volume down in steps rather than instant zero
@@ -70,15 +216,193 @@ fn detach_all_sinks(sinks: &mut Vec<Sink>) {
     }
 }
 
+/// Make room for `incoming` voices under the limit: drop released voices that have fully decayed,
+/// then fade out the oldest still ringing
+/// `held` voices (see `held_voices`) count toward the limit but are never stolen, and the incoming chord
+/// itself always sounds in full
+fn make_room(released: &mut Vec<Sink>, held: usize, incoming: usize, limit: usize) {
+    released.retain(|sink| !sink.empty());
+    let excess = (released.len() + held + incoming).saturating_sub(limit).min(released.len());
+    if excess > 0 {
+        let mut stolen: Vec<Sink> = released.drain(..excess).collect();
+        fade_out_and_stop_sinks(&mut stolen);
+    }
+}
+
+/// Voices sounding besides released chords: a sequence's notes that have started, the metronome and one-shots
+fn held_voices(sequence: Option<&ActiveSequence>, metronome: Option<&ActiveMetronome>, one_shots: &[Sink]) -> usize {
+    let sequence_voices = sequence.map_or(0, |active| active.sounding_voices(Instant::now()));
+    let one_shot_voices = one_shots.iter().filter(|sink| !sink.empty()).count();
+    sequence_voices + usize::from(metronome.is_some()) + one_shot_voices
+}
+
+/// Set every chord sink, including a scheduled sequence's, to its note's share of `volume`
+fn set_chord_volume(sinks: &[Sink], note_count: f32, sequence: Option<&ActiveSequence>, volume: f32) {
+    for sink in sinks {
+        sink.set_volume(volume / note_count);
+    }
+    if let Some(active) = sequence {
+        for (sink, note_count, _) in &active.sinks {
+            sink.set_volume(volume / note_count);
+        }
+    }
+}
+
 /// Commands sent to the audio thread
 pub enum AudioCommand {
     PlayNotes(Vec<AudioNote>, bool), // (notes, is_final)
+    PlayArpeggio(Vec<AudioNote>, ArpeggioDirection, Duration), // (notes, direction, step between notes)
     PlayOneShot(String),
+    PlaySequence(Vec<SequenceChord>, TempoMap, SequenceListener), // (chords, tempo, listener)
+    PauseSequence,
+    ResumeSequence,
+    SeekSequence(usize), // chord index
+    StartMetronome(MetronomePattern, MetronomeListener),
+    SetMetronome(MetronomePattern),
+    StopMetronome,
     Stop(bool),
     SetVolume(f32),
     Shutdown,
 }
 
+/// Receives each metronome beat on the audio thread
+pub type MetronomeListener = Box<dyn FnMut(MetronomeBeat) + Send>;
+
+/// Clicks following a sequence from its position, or free-running at the pattern's tempo
+fn click_source(pattern: &MetronomePattern, following: Option<&SequenceClock>) -> (Metronome, MetronomeSource) {
+    match following {
+        Some(clock) => {
+            let clicks = follow_schedule(pattern, clock.timeline(), clock.position(Instant::now()));
+            Metronome::following(pattern.clone(), clicks)
+        }
+        None => Metronome::new(pattern.clone()),
+    }
+}
+
+/// A metronome clicking on the audio thread
+/// It runs free at its own tempo, follows the tempo and meter of a playing sequence,
+/// and is silent while a sequence is paused
+struct ActiveMetronome {
+    metronome: Metronome,
+    /// Settings the metronome was started or last set with
+    pattern: MetronomePattern,
+    sink: Sink,
+    listener: MetronomeListener,
+    /// Last beat reported to the listener
+    reported: Option<u64>,
+}
+
+impl ActiveMetronome {
+    fn start(
+        pattern: MetronomePattern,
+        listener: MetronomeListener,
+        mixer: &Mixer,
+        volume: f32,
+        following: Option<&SequenceClock>,
+    ) -> Self {
+        let (metronome, source) = click_source(&pattern, following);
+        let active = Self { metronome, pattern, sink: Sink::connect_new(mixer), listener, reported: None };
+        active.set_volume(volume);
+        active.sink.append(source);
+        active
+    }
+
+    /// Replace the click source, keeping the settings and listener
+    fn restart(&mut self, mixer: &Mixer, volume: f32, following: Option<&SequenceClock>) {
+        self.silence();
+        let (metronome, source) = click_source(&self.pattern, following);
+        self.metronome = metronome;
+        self.sink = Sink::connect_new(mixer);
+        self.set_volume(volume);
+        self.sink.append(source);
+        self.reported = None;
+    }
+
+    fn silence(&self) {
+        self.metronome.stop();
+        self.sink.stop();
+    }
+
+    fn set_volume(&self, volume: f32) {
+        self.sink.set_volume(volume * self.metronome.volume() * METRONOME_VOLUME_MULTIPLIER);
+    }
+
+    /// Report the latest beat if it has not been reported yet
+    fn dispatch_beat(&mut self) {
+        if let Some((id, beat)) = self.metronome.latest_beat() {
+            if self.reported != Some(id) {
+                self.reported = Some(id);
+                (self.listener)(beat);
+            }
+        }
+    }
+
+    fn stop(self) {
+        self.silence();
+    }
+}
+
+/// A sequence scheduled on the audio thread
+struct ActiveSequence {
+    chords: Vec<SequenceChord>,
+    clock: SequenceClock,
+    listener: SequenceListener,
+    /// Sinks of the scheduled notes with the note count of their chord (for volume scaling)
+    /// and the position on the timeline where the note starts
+    sinks: Vec<(Sink, f32, Duration)>,
+}
+
+impl ActiveSequence {
+    fn stop_sinks(&mut self) {
+        let mut sinks: Vec<Sink> = self.sinks.drain(..).map(|(sink, _, _)| sink).collect();
+        fade_out_and_stop_sinks(&mut sinks);
+    }
+
+    /// Scheduled notes that have started and are still ringing at `now`
+    fn sounding_voices(&self, now: Instant) -> usize {
+        let position = self.clock.position(now);
+        self.sinks.iter().filter(|(sink, _, start)| *start <= position && !sink.empty()).count()
+    }
+
+    /// Queue every chord from the clock's position onwards, each delayed to its start
+    /// Delays are counted in samples by the mixer, so chords land exactly on their boundaries
+    fn schedule(&mut self, mixer: &Mixer, volume: f32) {
+        self.stop_sinks();
+        let timeline = self.clock.timeline().clone();
+        let position = self.clock.position(Instant::now());
+        let last = timeline.len().saturating_sub(1);
+
+        for (index, chord) in self.chords.iter().enumerate() {
+            if timeline.end(index) <= position {
+                continue;
+            }
+            let offset = timeline.start(index).saturating_sub(position);
+            let into = position.saturating_sub(timeline.start(index));
+            let hold = timeline.end(index) - timeline.start(index) - into;
+            let release = sequence_release(index == last);
+            let note_count = chord.notes.len().max(1) as f32;
+
+            for audio_note in &chord.notes {
+                let Some(source) = sequence_note_source(audio_note, into, hold, release) else {
+                    continue;
+                };
+                let source = source.delay(offset);
+                let sink = Sink::connect_new(mixer);
+                sink.set_volume(volume / note_count);
+                sink.append(source);
+                self.sinks.push((sink, note_count, timeline.start(index).max(position)));
+            }
+        }
+    }
+
+    /// Report chord boundaries that have passed
+    fn dispatch_due_events(&mut self) {
+        for event in self.clock.due_events(Instant::now()) {
+            (self.listener)(event);
+        }
+    }
+}
+
 /// Audio engine handle - sends commands to the audio thread
 pub struct AudioEngineHandle {
     sender: Sender<AudioCommand>,
@@ -105,6 +429,18 @@ impl AudioEngineHandle {
             .map_err(|e| format!("Failed to send play command: {}", e))
     }
 
+    /// Play a set of notes one at a time, `step` apart, each ringing until the last has sounded
+    pub fn play_arpeggio(
+        &self,
+        notes: Vec<AudioNote>,
+        direction: ArpeggioDirection,
+        step: Duration,
+    ) -> Result<(), String> {
+        self.sender
+            .send(AudioCommand::PlayArpeggio(notes, direction, step))
+            .map_err(|e| format!("Failed to send play_arpeggio command: {}", e))
+    }
+
     /// Stop all currently playing audio
     pub fn stop(&self, immediate: bool) -> Result<(), String> {
         self.sender
@@ -119,6 +455,60 @@ impl AudioEngineHandle {
             .map_err(|e| format!("Failed to send volume command: {}", e))
     }
 
+    /// Play chords back to back following a tempo map, replacing any scheduled sequence
+    /// The listener is called on the audio thread at each chord boundary and when the sequence ends
+    pub fn play_sequence(
+        &self,
+        chords: Vec<SequenceChord>,
+        tempo: TempoMap,
+        listener: SequenceListener,
+    ) -> Result<(), String> {
+        self.sender
+            .send(AudioCommand::PlaySequence(chords, tempo, listener))
+            .map_err(|e| format!("Failed to send play_sequence command: {}", e))
+    }
+
+    /// Pause the scheduled sequence, keeping its position
+    pub fn pause_sequence(&self) -> Result<(), String> {
+        self.sender
+            .send(AudioCommand::PauseSequence)
+            .map_err(|e| format!("Failed to send pause command: {}", e))
+    }
+
+    /// Resume the scheduled sequence where it was paused
+    pub fn resume_sequence(&self) -> Result<(), String> {
+        self.sender
+            .send(AudioCommand::ResumeSequence)
+            .map_err(|e| format!("Failed to send resume command: {}", e))
+    }
+
+    /// Jump to the start of a chord in the scheduled sequence
+    pub fn seek_sequence(&self, index: usize) -> Result<(), String> {
+        self.sender
+            .send(AudioCommand::SeekSequence(index))
+            .map_err(|e| format!("Failed to send seek command: {}", e))
+    }
+
+    /// Start the metronome, replacing a running one; the listener is called on each beat
+    pub fn start_metronome(&self, pattern: MetronomePattern, listener: MetronomeListener) -> Result<(), String> {
+        self.sender
+            .send(AudioCommand::StartMetronome(pattern, listener))
+            .map_err(|e| format!("Failed to send start_metronome command: {}", e))
+    }
+
+    /// Change the running metronome's tempo, meter, accents or subdivision
+    pub fn set_metronome(&self, pattern: MetronomePattern) -> Result<(), String> {
+        self.sender
+            .send(AudioCommand::SetMetronome(pattern))
+            .map_err(|e| format!("Failed to send set_metronome command: {}", e))
+    }
+
+    pub fn stop_metronome(&self) -> Result<(), String> {
+        self.sender
+            .send(AudioCommand::StopMetronome)
+            .map_err(|e| format!("Failed to send stop_metronome command: {}", e))
+    }
+
     /// Play a one-shot sound effect by sample name (e.g., "swoosh")
     pub fn play_one_shot(&self, sample_name: &str) -> Result<(), String> {
         self.sender
@@ -133,33 +523,138 @@ impl Drop for AudioEngineHandle {
     }
 }
 
+/// Format of the null output in headless builds
+#[cfg(any(feature = "headless-audio", not(feature = "playback")))]
+const HEADLESS_CHANNELS: u16 = 2;
+#[cfg(any(feature = "headless-audio", not(feature = "playback")))]
+const HEADLESS_SAMPLE_RATE: u32 = 48_000;
+
 /// Main function for the audio thread
 fn audio_thread_main(receiver: Receiver<AudioCommand>) {
+    // Headless builds play into a null output, rendered up to the present whenever the engine wakes
+    #[cfg(any(feature = "headless-audio", not(feature = "playback")))]
+    let output = NullOutput::new(HEADLESS_CHANNELS, HEADLESS_SAMPLE_RATE);
+
     // Initialize audio output on this thread (rodio 0.21 API)
-    let stream = match OutputStreamBuilder::open_default_stream() {
+    #[cfg(all(feature = "playback", not(feature = "headless-audio")))]
+    let output = match OutputStreamBuilder::open_default_stream() {
         Ok(s) => s,
         Err(e) => {
             eprintln!("Failed to initialize audio output: {}", e);
             return;
         }
     };
-    let mixer = stream.mixer();
-
-    // Use Vec<Sink> - one sink per note for simultaneous playback
-    let mut sinks: Vec<Sink> = Vec::new();
-    let mut volume: f32 = 1.0;
-    let mut current_note_count: f32 = 1.0; // Track for SetVolume scaling
+    let mut engine = Engine::new(output);
 
     loop {
-        match receiver.recv() {
-            Ok(AudioCommand::PlayNotes(notes, is_final)) => {
-                // Let old sinks continue playing and decay naturally
-                quick_fade_before_detach(&sinks);
+        let command = match engine.timeout() {
+            Some(timeout) => match receiver.recv_timeout(timeout) {
+                Ok(command) => command,
+                Err(RecvTimeoutError::Timeout) => {
+                    engine.poll();
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => AudioCommand::Shutdown,
+            },
+            None => receiver.recv().unwrap_or(AudioCommand::Shutdown),
+        };
+        if !engine.handle(command) {
+            break;
+        }
+    }
+}
+
+/// Playback state of the audio thread, playing into an output
+struct Engine<O: AudioOutput> {
+    output: O,
+    /// One sink per note for simultaneous playback
+    sinks: Vec<Sink>,
+    /// Sinks of earlier chords left to decay, oldest first
+    released: Vec<Sink>,
+    /// Sound effects, kept apart from the chord sinks so they are not ducked
+    one_shots: Vec<Sink>,
+    volume: f32,
+    /// Note count of the last chord, for SetVolume scaling
+    current_note_count: f32,
+    sequence: Option<ActiveSequence>,
+    metronome: Option<ActiveMetronome>,
+    ducking: Option<Ducking>,
+}
+
+impl<O: AudioOutput> Engine<O> {
+    fn new(output: O) -> Self {
+        Self {
+            output,
+            sinks: Vec::new(),
+            released: Vec::new(),
+            one_shots: Vec::new(),
+            volume: 1.0,
+            current_note_count: 1.0,
+            sequence: None,
+            metronome: None,
+            ducking: None,
+        }
+    }
+
+    /// How long the thread may wait for a command before poll is due
+    /// While a sequence plays, wake at its next chord boundary to report it;
+    /// while the metronome runs or chords are ducked, wake regularly to report beats and step the ramps
+    fn timeout(&self) -> Option<Duration> {
+        let sequence_timeout = self.sequence.as_ref().and_then(|s| s.clock.time_to_next_event(Instant::now()));
+        let metronome_timeout = self.metronome.as_ref().map(|_| METRONOME_POLL_INTERVAL);
+        let ducking_timeout = self.ducking.as_ref().map(|_| DUCK_POLL_INTERVAL);
+        sequence_timeout.into_iter().chain(metronome_timeout).chain(ducking_timeout).min()
+    }
+
+    /// Report due beats and chord boundaries, step the ducking ramps,
+    /// and let a finished sequence ring out
+    fn poll(&mut self) {
+        self.output.catch_up();
+        let mixer = self.output.mixer();
+        if let Some(duck) = self.ducking {
+            let now = Instant::now();
+            let gain = duck.gain(now);
+            set_chord_volume(&self.sinks, self.current_note_count, self.sequence.as_ref(), self.volume * gain);
+            if duck.is_finished(now) {
+                self.ducking = None;
+            }
+        }
+        if let Some(active) = self.metronome.as_mut() {
+            active.dispatch_beat();
+        }
+        if let Some(active) = self.sequence.as_mut() {
+            active.dispatch_due_events();
+            if active.clock.is_finished() {
+                // Let the last chord ring out
+                let mut sinks: Vec<Sink> = active.sinks.drain(..).map(|(sink, _, _)| sink).collect();
                 detach_all_sinks(&mut sinks);
+                self.sequence = None;
+                if let Some(active) = self.metronome.as_mut() {
+                    active.restart(mixer, self.volume, None);
+                }
+            }
+        }
+    }
+
+    /// Carry out a command; false once the engine has shut down
+    fn handle(&mut self, command: AudioCommand) -> bool {
+        self.output.catch_up();
+        let Self { output, sinks, released, one_shots, volume, current_note_count, sequence, metronome, ducking } =
+            self;
+        let mixer = output.mixer();
+        // Chords started while a one-shot plays join in at the ducked level
+        let gain = ducking.as_ref().map_or(1.0, |duck| duck.gain(Instant::now()));
+        match command {
+            AudioCommand::PlayNotes(notes, is_final) => {
+                // Let old sinks continue playing and decay naturally, within the voice limit
+                quick_fade_before_detach(sinks);
+                released.append(sinks);
+                let held = held_voices(sequence.as_ref(), metronome.as_ref(), one_shots);
+                make_room(released, held, notes.len(), voice_limit());
 
                 // Divide volume by note count AFTER limiter to prevent summed clipping
-                current_note_count = notes.len().max(1) as f32;
-                let per_note_volume = volume / current_note_count;
+                *current_note_count = notes.len().max(1) as f32;
+                let per_note_volume = *volume * gain / *current_note_count;
 
                 // Create one sink per note for simultaneous playback
                 for audio_note in &notes {
@@ -168,7 +663,8 @@ fn audio_thread_main(receiver: Receiver<AudioCommand>) {
                     if let Some(sample_bytes) = get_sample(&sample_key) {
                         let cursor = Cursor::new(sample_bytes);
                         if let Ok(source) = Decoder::new(cursor) {
-                            let sink = Sink::connect_new(&mixer);
+                            let source = source.speed(detune_ratio(audio_note.cents));
+                            let sink = Sink::connect_new(mixer);
                             // Per-note volume: limiter outputs ~0.7 max, divided by note count
                             sink.set_volume(per_note_volume);
 
@@ -187,13 +683,7 @@ fn audio_thread_main(receiver: Receiver<AudioCommand>) {
                                     .fade_out(TAIL_FADEOUT_DURATION);
                                 sink.append(source_processed);
                             } else {
-                                let source_processed = source
-                                    .two_stage_envelope()
-                                    .high_pass(CHORD_HIGHPASS_FREQ)
-                                    .amplify(CHORD_VOLUME_MULTIPLIER)
-                                    .limit(chord_limiter_settings())
-                                    .amplify(MAKEUP_GAIN);
-                                sink.append(source_processed);
+                                sink.append(chord_signal_chain(source));
                             }
                             sinks.push(sink);
                         }
@@ -202,54 +692,383 @@ fn audio_thread_main(receiver: Receiver<AudioCommand>) {
                     }
                 }
             }
-            Ok(AudioCommand::PlayOneShot(sample_name)) => {
-                // Play a one-shot sound effect without stopping other audio
+            AudioCommand::PlayArpeggio(notes, direction, step) => {
+                quick_fade_before_detach(sinks);
+                released.append(sinks);
+                let held = held_voices(sequence.as_ref(), metronome.as_ref(), one_shots);
+                make_room(released, held, notes.len(), voice_limit());
+
+                // Every note is still ringing when the last one starts, so share the volume as a chord does
+                let arpeggio = arpeggiate(&notes, direction, step);
+                *current_note_count = arpeggio.len().max(1) as f32;
+                let per_note_volume = *volume * gain / *current_note_count;
+                let last_start = arpeggio.last().map_or(Duration::ZERO, |(_, start)| *start);
+
+                // Each note gets its own sink, delayed to its place in the arpeggio
+                for (audio_note, start) in &arpeggio {
+                    let hold = last_start - *start + step;
+                    let source = sequence_note_source(audio_note, Duration::ZERO, hold, TAIL_FADEOUT_DURATION);
+                    if let Some(source) = source {
+                        let sink = Sink::connect_new(mixer);
+                        sink.set_volume(per_note_volume);
+                        sink.append(source.delay(*start));
+                        sinks.push(sink);
+                    }
+                }
+            }
+            AudioCommand::PlayOneShot(sample_name) => {
+                // Play a one-shot sound effect without stopping other audio,
+                // ducking the chords until it has finished
                 if let Some(sample_bytes) = get_sample(&sample_name) {
                     let cursor = Cursor::new(sample_bytes);
                     if let Ok(source) = Decoder::new(cursor) {
+                        let length = source.total_duration().unwrap_or(DUCK_DEFAULT_HOLD);
+                        *ducking = Some(Ducking::start(ducking.as_ref(), length, Instant::now()));
+
                         // Apply fade-in to prevent click artifacts (25ms matches chord playback)
                         let source_with_fade = source.fade_in(Duration::from_millis(25));
-                        let sink = Sink::connect_new(&mixer);
-                        sink.set_volume(volume * ONESHOT_VOLUME_MULTIPLIER);
+                        let sink = Sink::connect_new(mixer);
+                        sink.set_volume(*volume * ONESHOT_VOLUME_MULTIPLIER);
                         sink.append(source_with_fade);
-                        sinks.push(sink);
+                        one_shots.retain(|sink| !sink.empty());
+                        one_shots.push(sink);
                     }
                 } else {
                     eprintln!("Warning: No sample found for {}", sample_name);
                 }
             }
-            Ok(AudioCommand::Stop(immediate)) => {
+            AudioCommand::PlaySequence(chords, tempo, listener) => {
+                if let Some(mut previous) = sequence.take() {
+                    previous.stop_sinks();
+                }
+                let beats: Vec<f32> = chords.iter().map(|chord| chord.beats).collect();
+                let mut active = ActiveSequence {
+                    clock: SequenceClock::new(Timeline::new(&beats, tempo)),
+                    chords,
+                    listener,
+                    sinks: Vec::new(),
+                };
+                active.clock.play_from(Duration::ZERO, Instant::now());
+                active.schedule(mixer, *volume * gain);
+                if let Some(clicks) = metronome.as_mut() {
+                    clicks.restart(mixer, *volume, Some(&active.clock));
+                }
+                *sequence = Some(active);
+            }
+            AudioCommand::PauseSequence => {
+                if let Some(active) = sequence.as_mut().filter(|s| s.clock.is_playing()) {
+                    active.clock.pause(Instant::now());
+                    active.stop_sinks();
+                    if let Some(clicks) = metronome.as_ref() {
+                        clicks.silence();
+                    }
+                }
+            }
+            AudioCommand::ResumeSequence => {
+                if let Some(active) = sequence.as_mut().filter(|s| !s.clock.is_playing()) {
+                    let now = Instant::now();
+                    active.clock.play_from(active.clock.position(now), now);
+                    active.schedule(mixer, *volume * gain);
+                    if let Some(clicks) = metronome.as_mut() {
+                        clicks.restart(mixer, *volume, Some(&active.clock));
+                    }
+                }
+            }
+            AudioCommand::SeekSequence(index) => {
+                if let Some(active) = sequence.as_mut() {
+                    active.clock.seek(index, Instant::now());
+                    if active.clock.is_playing() {
+                        active.schedule(mixer, *volume * gain);
+                        if let Some(clicks) = metronome.as_mut() {
+                            clicks.restart(mixer, *volume, Some(&active.clock));
+                        }
+                    }
+                }
+            }
+            AudioCommand::StartMetronome(pattern, listener) => {
+                if let Some(previous) = metronome.take() {
+                    previous.stop();
+                }
+                let clock = sequence.as_ref().map(|active| &active.clock);
+                let active =
+                    ActiveMetronome::start(pattern, listener, mixer, *volume, clock.filter(|c| c.is_playing()));
+                if clock.is_some_and(|c| !c.is_playing()) {
+                    // Silent until the paused sequence resumes
+                    active.silence();
+                }
+                *metronome = Some(active);
+            }
+            AudioCommand::SetMetronome(pattern) => {
+                if let Some(active) = metronome.as_mut() {
+                    active.pattern = pattern.clone();
+                    match sequence.as_ref() {
+                        Some(playing) if playing.clock.is_playing() => {
+                            active.restart(mixer, *volume, Some(&playing.clock));
+                        }
+                        Some(_) => {}
+                        None => {
+                            active.metronome.set_pattern(pattern);
+                            active.set_volume(*volume);
+                        }
+                    }
+                }
+            }
+            AudioCommand::StopMetronome => {
+                if let Some(active) = metronome.take() {
+                    active.stop();
+                }
+            }
+            AudioCommand::Stop(immediate) => {
+                if let Some(mut active) = sequence.take() {
+                    active.stop_sinks();
+                    if let Some(clicks) = metronome.as_mut() {
+                        clicks.restart(mixer, *volume, None);
+                    }
+                }
                 if immediate {
-                    fade_out_and_stop_sinks(&mut sinks);
+                    released.append(sinks);
+                    fade_out_and_stop_sinks(released);
+                    fade_out_and_stop_sinks(one_shots);
                 } else {
-                    detach_all_sinks(&mut sinks);
+                    released.append(sinks);
+                    detach_all_sinks(one_shots);
                 }
+                *ducking = None;
             }
-            Ok(AudioCommand::SetVolume(v)) => {
-                volume = v.clamp(0.0, 1.0);
-                let per_note_volume = volume / current_note_count;
-                for sink in &sinks {
-                    sink.set_volume(per_note_volume);
+            AudioCommand::SetVolume(v) => {
+                *volume = v.clamp(0.0, 1.0);
+                set_chord_volume(sinks, *current_note_count, sequence.as_ref(), *volume * gain);
+                for sink in one_shots.iter() {
+                    sink.set_volume(*volume * ONESHOT_VOLUME_MULTIPLIER);
+                }
+                if let Some(active) = metronome.as_ref() {
+                    active.set_volume(*volume);
                 }
             }
-            Ok(AudioCommand::Shutdown) | Err(_) => {
-                fade_out_and_stop_sinks(&mut sinks);
-                break;
+            AudioCommand::Shutdown => {
+                if let Some(mut active) = sequence.take() {
+                    active.stop_sinks();
+                }
+                if let Some(active) = metronome.take() {
+                    active.stop();
+                }
+                released.append(sinks);
+                fade_out_and_stop_sinks(released);
+                fade_out_and_stop_sinks(one_shots);
+                return false;
             }
         }
+        true
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::audio::metronome::MetronomeSettings;
+    use crate::audio::output::capture::CaptureOutput;
+    use rodio::source::SineWave;
+
+    const RATE: u32 = 48_000;
+
+    fn engine() -> Engine<CaptureOutput> {
+        Engine::new(CaptureOutput::new(1, RATE))
+    }
+
+    fn peak(buffer: &[f32]) -> f32 {
+        buffer.iter().fold(0.0, |peak, sample| peak.max(sample.abs()))
+    }
+
+    fn ms(millis: u64) -> usize {
+        millis as usize * RATE as usize / 1000
+    }
+
+    /// Start of each sound in the buffer, in milliseconds, after at least 100ms of near silence
+    fn onsets(buffer: &[f32]) -> Vec<u64> {
+        let mut onsets = Vec::new();
+        let mut quiet = ms(100);
+        for (index, sample) in buffer.iter().enumerate() {
+            if sample.abs() > 0.01 {
+                if quiet >= ms(100) {
+                    onsets.push((index * 1000 / RATE as usize) as u64);
+                }
+                quiet = 0;
+            } else {
+                quiet += 1;
+            }
+        }
+        onsets
+    }
+
+    fn start_metronome(engine: &mut Engine<CaptureOutput>, bpm: f32) {
+        let pattern = MetronomeSettings { bpm, ..Default::default() }.resolve().unwrap();
+        assert!(engine.handle(AudioCommand::StartMetronome(pattern, Box::new(|_| {}))));
+    }
 
     #[test]
     fn test_audio_engine_creation() {
-        // Note: This test may fail in CI environments without audio output
+        // Note: This test may fail in CI environments without audio output; build with headless-audio there
         let result = AudioEngineHandle::new();
         if result.is_err() {
             eprintln!("AudioEngine creation failed (expected in headless environments)");
         }
     }
+
+    #[test]
+    fn test_chord_signal_chain() {
+        let tone = SineWave::new(440.0).take_duration(Duration::from_millis(500));
+        let buffer: Vec<f32> = chord_signal_chain(tone).collect();
+
+        // The envelope starts from near silence and reaches full level within 15ms
+        assert!(peak(&buffer[..ms(3)]) < 0.1);
+        assert!(peak(&buffer[ms(20)..ms(40)]) > 0.5);
+        // The limiter holds the amplified tone near its threshold once it has settled
+        let settled = peak(&buffer[ms(300)..]);
+        assert!(settled < CHORD_VOLUME_MULTIPLIER * MAKEUP_GAIN * 0.5, "{}", settled);
+    }
+
+    #[test]
+    fn test_metronome_clicks_on_the_beat() {
+        let mut engine = engine();
+        start_metronome(&mut engine, 120.0);
+        let buffer = engine.output.render(Duration::from_millis(1600));
+        assert_eq!(onsets(&buffer), [0, 500, 1000, 1500]);
+    }
+
+    #[test]
+    fn test_metronome_follows_the_sequence_tempo() {
+        let mut engine = engine();
+        // Two silent bars at 60 BPM: the clicks follow the sequence, not the pattern's 120 BPM,
+        // from the next beat after the sequence's position when the metronome starts
+        let chords = vec![SequenceChord { notes: Vec::new(), beats: 4.0 }; 2];
+        let tempo = TempoMap::new(60.0, "4/4").unwrap();
+        engine.handle(AudioCommand::PlaySequence(chords, tempo, Box::new(|_| {})));
+        start_metronome(&mut engine, 120.0);
+        let clicks = onsets(&engine.output.render(Duration::from_millis(2600)));
+        assert_eq!(clicks.len(), 2, "{:?}", clicks);
+        assert!(clicks.iter().zip([1000, 2000]).all(|(&click, beat)| click.abs_diff(beat) < 20), "{:?}", clicks);
+
+        // Paused, the sequence's metronome falls silent
+        engine.handle(AudioCommand::PauseSequence);
+        engine.output.render(Duration::from_millis(20));
+        assert_eq!(peak(&engine.output.render(Duration::from_millis(1500))), 0.0);
+    }
+
+    #[test]
+    fn test_stop_and_volume() {
+        let mut loud = engine();
+        start_metronome(&mut loud, 120.0);
+        let full = peak(&loud.output.render(Duration::from_millis(100)));
+
+        let mut quiet = engine();
+        quiet.handle(AudioCommand::SetVolume(0.5));
+        start_metronome(&mut quiet, 120.0);
+        let half = peak(&quiet.output.render(Duration::from_millis(100)));
+        assert!((half / full - 0.5).abs() < 0.01, "{} against {}", half, full);
+
+        // Stopped, the click sink ends within its next control update
+        assert!(loud.handle(AudioCommand::StopMetronome));
+        loud.output.render(Duration::from_millis(10));
+        assert_eq!(peak(&loud.output.render(Duration::from_millis(1000))), 0.0);
+        assert!(!loud.handle(AudioCommand::Shutdown));
+    }
+
+    #[test]
+    fn test_oldest_voices_are_stolen_past_the_limit() {
+        let mut output = CaptureOutput::new(1, RATE);
+        let voice = |volume, duration| {
+            let sink = Sink::connect_new(output.mixer());
+            sink.set_volume(volume);
+            sink.append(SineWave::new(440.0).take_duration(Duration::from_millis(duration)));
+            sink
+        };
+        // Two short voices that decay first, then six ringing ones, oldest first
+        let mut released = vec![voice(0.9, 10), voice(0.8, 10)];
+        released.extend((1..=6).map(|n| voice(n as f32 / 10.0, 10_000)));
+        output.render(Duration::from_millis(50));
+
+        // The decayed voices go without stealing; a four-note chord then takes the two oldest ringing ones
+        make_room(&mut released, 0, 4, 8);
+        let volumes: Vec<f32> = released.iter().map(Sink::volume).collect();
+        assert_eq!(volumes, [0.3, 0.4, 0.5, 0.6]);
+
+        // Held voices take their share of the limit, so a two-note chord beside three of them steals one more
+        make_room(&mut released, 3, 2, 8);
+        let volumes: Vec<f32> = released.iter().map(Sink::volume).collect();
+        assert_eq!(volumes, [0.4, 0.5, 0.6]);
+
+        // A chord bigger than the limit still sounds in full, stealing everything else
+        make_room(&mut released, 0, 10, 8);
+        assert!(released.is_empty());
+    }
+
+    #[test]
+    fn test_sequence_metronome_and_one_shots_are_held_voices() {
+        let mut engine = engine();
+        assert_eq!(held_voices(engine.sequence.as_ref(), engine.metronome.as_ref(), &engine.one_shots), 0);
+        start_metronome(&mut engine, 120.0);
+
+        // A one-bar sequence at 60 BPM with two notes sounding and one scheduled for the next bar
+        let tempo = TempoMap::new(60.0, "4/4").unwrap();
+        let mut clock = SequenceClock::new(Timeline::new(&[4.0, 4.0], tempo));
+        clock.play_from(Duration::ZERO, Instant::now());
+        let voice = |start| {
+            let sink = Sink::connect_new(engine.output.mixer());
+            sink.append(SineWave::new(440.0).take_duration(Duration::from_secs(8)));
+            (sink, 2.0, start)
+        };
+        let sinks = vec![voice(Duration::ZERO), voice(Duration::ZERO), voice(Duration::from_secs(4))];
+        let chords = vec![SequenceChord { notes: Vec::new(), beats: 4.0 }; 2];
+        engine.sequence = Some(ActiveSequence { chords, clock, listener: Box::new(|_| {}), sinks });
+
+        // A one-shot still ringing counts, a finished one does not
+        let one_shot = |duration| {
+            let sink = Sink::connect_new(engine.output.mixer());
+            sink.append(SineWave::new(880.0).take_duration(Duration::from_millis(duration)));
+            sink
+        };
+        engine.one_shots = vec![one_shot(1), one_shot(10_000)];
+        engine.output.render(Duration::from_millis(20));
+
+        let held = held_voices(engine.sequence.as_ref(), engine.metronome.as_ref(), &engine.one_shots);
+        assert_eq!(held, 2 + 1 + 1);
+    }
+
+    #[test]
+    fn test_ducking_ramps_down_holds_and_restores() {
+        let hold = Duration::from_millis(400);
+        let at = |millis| duck_gain(1.0, Duration::from_millis(millis), hold);
+        assert_eq!(at(0), 1.0);
+        assert!((at(15) - (1.0 + DUCK_LEVEL) / 2.0).abs() < 1e-6);
+        assert_eq!(at(30), DUCK_LEVEL);
+        assert_eq!(at(399), DUCK_LEVEL);
+        // The restore starts and ends gently and gets back to full level
+        let release: Vec<f32> = (400..=700).step_by(10).map(at).collect();
+        assert!(release.windows(2).all(|pair| pair[1] >= pair[0]));
+        assert!(release[1] - release[0] < release[16] - release[15]);
+        assert_eq!(at(700), 1.0);
+        assert_eq!(at(2000), 1.0);
+
+        // A second one-shot during the release ramps down from where the first left off
+        let now = Instant::now();
+        let first = Ducking { from: 1.0, started: now - Duration::from_millis(550), hold };
+        let second = Ducking::start(Some(&first), Duration::from_millis(100), now);
+        assert!((second.gain(now) - first.gain(now)).abs() < 1e-6);
+        assert!(second.gain(now) > DUCK_LEVEL && second.gain(now) < 1.0);
+        assert!(!second.is_finished(now + Duration::from_millis(399)));
+        assert!(second.is_finished(now + Duration::from_millis(400)));
+
+        // One arriving mid-hold keeps the chords down until the longer of the two has finished
+        let first = Ducking { from: 1.0, started: now - Duration::from_millis(100), hold };
+        let second = Ducking::start(Some(&first), Duration::from_millis(100), now);
+        assert_eq!(second.hold, Duration::from_millis(300));
+        assert_eq!(second.gain(now + Duration::from_millis(299)), DUCK_LEVEL);
+    }
+
+    #[test]
+    fn test_detune_ratio() {
+        assert_eq!(detune_ratio(0.0), 1.0);
+        assert!((detune_ratio(1200.0) - 2.0).abs() < 1e-6);
+        assert!((detune_ratio(-100.0) - 0.943_874_3).abs() < 1e-6);
+    }
 }
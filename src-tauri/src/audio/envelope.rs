@@ -106,3 +106,83 @@ where
     S: Source<Item = f32> + Sized,
 {
 }
+
+/// Holds a source at full volume, then ramps it linearly to silence and ends it
+/// Used to end scheduled chords at their boundary without a click
+pub struct ReleaseAfter<I> {
+    inner: I,
+    current_sample: u64,
+    /// Samples played before the release starts
+    hold_samples: u64,
+    /// Samples the release ramp lasts
+    release_samples: u64,
+}
+
+impl<I> ReleaseAfter<I>
+where
+    I: Source<Item = f32>,
+{
+    pub fn new(inner: I, hold: Duration, release: Duration) -> Self {
+        let samples_per_second = inner.sample_rate() as f64 * inner.channels() as f64;
+        Self {
+            current_sample: 0,
+            hold_samples: (samples_per_second * hold.as_secs_f64()) as u64,
+            release_samples: ((samples_per_second * release.as_secs_f64()) as u64).max(1),
+            inner,
+        }
+    }
+}
+
+impl<I> Iterator for ReleaseAfter<I>
+where
+    I: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let released = self.current_sample.saturating_sub(self.hold_samples);
+        if released >= self.release_samples {
+            return None;
+        }
+        let sample = self.inner.next()?;
+        let gain = 1.0 - released as f32 / self.release_samples as f32;
+
+        self.current_sample += 1;
+        Some(sample * gain)
+    }
+}
+
+impl<I> Source for ReleaseAfter<I>
+where
+    I: Source<Item = f32>,
+{
+    fn current_span_len(&self) -> Option<usize> {
+        self.inner.current_span_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Extension trait to add release_after to any Source
+pub trait ReleaseAfterExt: Source<Item = f32> + Sized {
+    /// Play for `hold`, then fade to silence over `release` and stop
+    fn release_after(self, hold: Duration, release: Duration) -> ReleaseAfter<Self> {
+        ReleaseAfter::new(self, hold, release)
+    }
+}
+
+impl<S> ReleaseAfterExt for S
+where
+    S: Source<Item = f32> + Sized,
+{
+}
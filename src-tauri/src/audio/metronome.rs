@@ -0,0 +1,498 @@
+// Metronome
+// Synthesized clicks on beats and subdivisions, generated sample by sample so the tempo holds
+// while settings change; mixed alongside chord playback on the document's audio thread.
+// While a sequence plays, the clicks follow its tempo and meter changes instead
+
+use rodio::Source;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::sequence::Timeline;
+use crate::notation::beaming::parse_time_signature;
+
+/// Output rate of the synthesized clicks
+const SAMPLE_RATE: u32 = 48_000;
+
+/// Length of one click; it decays to near silence within this time
+const CLICK_DURATION: Duration = Duration::from_millis(30);
+
+/// Decay time constant of a click (seconds)
+const CLICK_DECAY: f32 = 0.006;
+
+/// Tempo range accepted by the metronome
+const MIN_BPM: f32 = 20.0;
+const MAX_BPM: f32 = 400.0;
+
+/// Most clicks per beat
+const MAX_SUBDIVISION: u8 = 6;
+
+/// How a beat of the bar is clicked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BeatAccent {
+    /// Downbeat click
+    Strong,
+    /// Secondary accent, such as the middle of a 6/8 bar
+    Medium,
+    Normal,
+    /// No click on this beat (subdivisions still sound)
+    Silent,
+}
+
+impl BeatAccent {
+    const ALL: [BeatAccent; 4] = [BeatAccent::Strong, BeatAccent::Medium, BeatAccent::Normal, BeatAccent::Silent];
+
+    /// Click pitch (Hz) and level
+    fn click(self) -> Option<(f32, f32)> {
+        match self {
+            BeatAccent::Strong => Some((1760.0, 1.0)),
+            BeatAccent::Medium => Some((1320.0, 0.8)),
+            BeatAccent::Normal => Some((1100.0, 0.6)),
+            BeatAccent::Silent => None,
+        }
+    }
+}
+
+/// Click of a subdivision between beats
+const SUBDIVISION_CLICK: (f32, f32) = (880.0, 0.3);
+
+/// Metronome settings from the frontend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetronomeSettings {
+    /// Beats per minute, counted in the time signature's beat unit
+    pub bpm: f32,
+    pub time_signature: String,
+    /// Accent of each beat in the bar; empty uses the meter's usual pattern
+    pub accents: Vec<BeatAccent>,
+    /// Clicks per beat (1 = beats only, 2 = eighths in 4/4, 3 = triplets, ...)
+    pub subdivision: u8,
+    /// Click level relative to the document volume (0.0 to 1.0)
+    pub volume: f32,
+}
+
+impl Default for MetronomeSettings {
+    fn default() -> Self {
+        Self {
+            bpm: 120.0,
+            time_signature: "4/4".to_string(),
+            accents: Vec::new(),
+            subdivision: 1,
+            volume: 0.8,
+        }
+    }
+}
+
+/// Settings checked and resolved into a click pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetronomePattern {
+    pub bpm: f32,
+    /// One accent per beat of the bar
+    pub accents: Vec<BeatAccent>,
+    pub subdivision: u8,
+    pub volume: f32,
+}
+
+/// Usual accents of a meter: a strong downbeat, plus medium accents on each dotted beat of compound meters
+fn default_accents(beats: u32, unit: u32) -> Vec<BeatAccent> {
+    let compound = unit == 8 && beats.is_multiple_of(3) && beats > 3;
+    (0..beats)
+        .map(|beat| match beat {
+            0 => BeatAccent::Strong,
+            b if compound && b.is_multiple_of(3) => BeatAccent::Medium,
+            _ => BeatAccent::Normal,
+        })
+        .collect()
+}
+
+impl MetronomeSettings {
+    pub fn resolve(&self) -> Result<MetronomePattern, String> {
+        if !(MIN_BPM..=MAX_BPM).contains(&self.bpm) {
+            return Err(format!("Metronome tempo must be between {} and {} BPM", MIN_BPM, MAX_BPM));
+        }
+        if !(1..=MAX_SUBDIVISION).contains(&self.subdivision) {
+            return Err(format!("Metronome subdivision must be between 1 and {}", MAX_SUBDIVISION));
+        }
+        let (beats, unit) = parse_time_signature(&self.time_signature).map_err(|e| e.to_string())?;
+        let accents = if self.accents.is_empty() {
+            default_accents(beats, unit)
+        } else if self.accents.len() == beats as usize {
+            self.accents.clone()
+        } else {
+            return Err(format!(
+                "Accent pattern has {} beats but {} has {}",
+                self.accents.len(),
+                self.time_signature,
+                beats
+            ));
+        };
+
+        Ok(MetronomePattern {
+            bpm: self.bpm,
+            accents,
+            subdivision: self.subdivision,
+            volume: self.volume.clamp(0.0, 1.0),
+        })
+    }
+}
+
+/// A click of a metronome following a sequence
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScheduledClick {
+    /// Time from where the schedule starts
+    pub at: Duration,
+    /// Beat within the bar and its accent; None for a subdivision click
+    pub beat: Option<(u32, BeatAccent)>,
+}
+
+/// Clicks for a sequence from a position to its end, with the pattern's subdivision and level
+/// Each tempo segment starts a new bar; a meter the pattern's accents do not fit uses its usual accents
+pub fn follow_schedule(pattern: &MetronomePattern, timeline: &Timeline, from: Duration) -> Vec<ScheduledClick> {
+    let tempo = timeline.tempo();
+    let segments = tempo.segments();
+    let subdivision = pattern.subdivision as u32;
+    let mut clicks = Vec::new();
+
+    for (index, segment) in segments.iter().enumerate() {
+        let end = segments.get(index + 1).map_or(timeline.total_beats(), |next| next.beat);
+        let accents = if pattern.accents.len() == segment.beats_per_bar as usize {
+            pattern.accents.clone()
+        } else {
+            default_accents(segment.beats_per_bar, segment.beat_unit)
+        };
+
+        for tick in 0.. {
+            let beat = segment.beat + tick as f64 / subdivision as f64;
+            if beat >= end {
+                break;
+            }
+            let at = tempo.time_at(beat);
+            if at < from {
+                continue;
+            }
+            let in_bar = (tick / subdivision) % segment.beats_per_bar;
+            clicks.push(ScheduledClick {
+                at: at - from,
+                beat: tick.is_multiple_of(subdivision).then(|| (in_bar, accents[in_bar as usize])),
+            });
+        }
+    }
+    clicks
+}
+
+/// A beat that has started sounding
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MetronomeBeat {
+    /// Beat within the bar, from 0
+    pub beat: u32,
+    /// Beats clicked since the metronome started, from 0
+    pub count: u64,
+    pub accent: BeatAccent,
+}
+
+/// State shared between the click source (on the output thread) and the audio thread
+struct Shared {
+    pattern: Mutex<MetronomePattern>,
+    /// Latest beat as (count << 20 | accent << 16 | beat in bar), plus one; 0 before the first beat
+    latest_beat: AtomicU64,
+    stopped: AtomicBool,
+}
+
+/// Handle to a running metronome
+#[derive(Clone)]
+pub struct Metronome {
+    shared: Arc<Shared>,
+}
+
+impl Metronome {
+    /// A metronome and the click source to play it; the source ends once stop is called
+    pub fn new(pattern: MetronomePattern) -> (Self, MetronomeSource) {
+        Self::with_schedule(pattern, None)
+    }
+
+    /// A metronome playing scheduled clicks (see follow_schedule); the source ends after the last one
+    /// The pattern only sets the click level
+    pub fn following(pattern: MetronomePattern, clicks: Vec<ScheduledClick>) -> (Self, MetronomeSource) {
+        Self::with_schedule(pattern, Some(clicks.into()))
+    }
+
+    fn with_schedule(pattern: MetronomePattern, schedule: Option<VecDeque<ScheduledClick>>) -> (Self, MetronomeSource) {
+        let shared = Arc::new(Shared {
+            pattern: Mutex::new(pattern.clone()),
+            latest_beat: AtomicU64::new(0),
+            stopped: AtomicBool::new(false),
+        });
+        // A schedule waits for its first click; the pattern clicks at once
+        let first_click = schedule.as_ref().and_then(|clicks| clicks.front());
+        let samples_to_tick = first_click.map_or(0, |click| samples_at(click.at));
+        let source = MetronomeSource {
+            shared: shared.clone(),
+            pattern,
+            schedule,
+            samples_to_tick,
+            tick: 0,
+            beats: 0,
+            click: None,
+        };
+        (Self { shared }, source)
+    }
+
+    /// Change the settings; the new tempo and pattern apply from the next click
+    pub fn set_pattern(&self, pattern: MetronomePattern) {
+        if let Ok(mut current) = self.shared.pattern.lock() {
+            *current = pattern;
+        }
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.shared.pattern.lock().map(|p| p.volume).unwrap_or(1.0)
+    }
+
+    pub fn stop(&self) {
+        self.shared.stopped.store(true, Ordering::Relaxed);
+    }
+
+    /// Most recent beat, with a value that changes on every beat
+    pub fn latest_beat(&self) -> Option<(u64, MetronomeBeat)> {
+        let packed = self.shared.latest_beat.load(Ordering::Relaxed);
+        let value = packed.checked_sub(1)?;
+        let beat = (value & 0xFFFF) as u32;
+        let accent = BeatAccent::ALL[((value >> 16) & 0xF) as usize];
+        Some((packed, MetronomeBeat { beat, count: value >> 20, accent }))
+    }
+}
+
+/// A click being synthesized
+struct Click {
+    frequency: f32,
+    level: f32,
+    sample: u32,
+}
+
+/// Samples from the start of a schedule to a time
+fn samples_at(time: Duration) -> u64 {
+    (time.as_secs_f64() * SAMPLE_RATE as f64).round() as u64
+}
+
+/// Mono source of metronome clicks, endless unless it follows a schedule
+pub struct MetronomeSource {
+    shared: Arc<Shared>,
+    /// Pattern in use, refreshed from the shared one at each click
+    pattern: MetronomePattern,
+    /// Clicks still to play when following a sequence
+    schedule: Option<VecDeque<ScheduledClick>>,
+    samples_to_tick: u64,
+    /// Click position within the bar, counting subdivisions
+    tick: u32,
+    /// Beats started so far
+    beats: u64,
+    click: Option<Click>,
+}
+
+impl MetronomeSource {
+    /// Start the click due now, from the schedule or the pattern
+    fn start_tick(&mut self) {
+        match self.schedule.as_mut().and_then(|schedule| schedule.pop_front()) {
+            Some(click) => {
+                let sound = match click.beat {
+                    Some((beat, accent)) => self.start_beat(beat, accent),
+                    None => Some(SUBDIVISION_CLICK),
+                };
+                self.click = sound.map(|(frequency, level)| Click { frequency, level, sample: 0 });
+
+                // The last click plays out before the source ends
+                let next = self.schedule.as_ref().and_then(|schedule| schedule.front());
+                self.samples_to_tick = match next {
+                    Some(next) => samples_at(next.at) - samples_at(click.at),
+                    None => samples_at(CLICK_DURATION),
+                };
+            }
+            None => self.start_pattern_tick(),
+        }
+    }
+
+    /// Record a beat as the latest and return its click
+    fn start_beat(&mut self, beat: u32, accent: BeatAccent) -> Option<(f32, f32)> {
+        let packed = (self.beats << 20) | ((accent as u64) << 16) | beat as u64;
+        self.shared.latest_beat.store(packed + 1, Ordering::Relaxed);
+        self.beats += 1;
+        accent.click()
+    }
+
+    /// Start the pattern's click due now and schedule the next
+    fn start_pattern_tick(&mut self) {
+        if let Ok(pattern) = self.shared.pattern.try_lock() {
+            if *pattern != self.pattern {
+                // Keep the position in the bar if the new pattern still has it
+                let ticks_per_bar = pattern.accents.len() as u32 * pattern.subdivision as u32;
+                self.tick = if self.tick < ticks_per_bar { self.tick } else { 0 };
+                self.pattern = pattern.clone();
+            }
+        }
+
+        let subdivision = self.pattern.subdivision as u32;
+        let ticks_per_bar = self.pattern.accents.len() as u32 * subdivision;
+        let beat = self.tick / subdivision;
+        let sound = if self.tick.is_multiple_of(subdivision) {
+            self.start_beat(beat, self.pattern.accents[beat as usize])
+        } else {
+            Some(SUBDIVISION_CLICK)
+        };
+        self.click = sound.map(|(frequency, level)| Click { frequency, level, sample: 0 });
+
+        let seconds_per_tick = 60.0 / (self.pattern.bpm as f64 * subdivision as f64);
+        self.samples_to_tick = (seconds_per_tick * SAMPLE_RATE as f64).round() as u64;
+        self.tick = (self.tick + 1) % ticks_per_bar;
+    }
+}
+
+impl Iterator for MetronomeSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.shared.stopped.load(Ordering::Relaxed) {
+            return None;
+        }
+        if self.samples_to_tick == 0 {
+            if self.schedule.as_ref().is_some_and(|schedule| schedule.is_empty()) {
+                return None;
+            }
+            self.start_tick();
+        }
+        self.samples_to_tick -= 1;
+
+        let click_samples = (CLICK_DURATION.as_secs_f32() * SAMPLE_RATE as f32) as u32;
+        let value = match self.click.as_mut() {
+            Some(click) if click.sample < click_samples => {
+                let t = click.sample as f32 / SAMPLE_RATE as f32;
+                click.sample += 1;
+                (TAU * click.frequency * t).sin() * click.level * (-t / CLICK_DECAY).exp()
+            }
+            _ => 0.0,
+        };
+        Some(value)
+    }
+}
+
+impl Source for MetronomeSource {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::tempo::TempoMap;
+
+    fn pattern(settings: MetronomeSettings) -> MetronomePattern {
+        settings.resolve().unwrap()
+    }
+
+    #[test]
+    fn test_resolve_settings() {
+        let six_eight = pattern(MetronomeSettings { time_signature: "6/8".to_string(), ..Default::default() });
+        assert_eq!(six_eight.accents[0], BeatAccent::Strong);
+        assert_eq!(six_eight.accents[3], BeatAccent::Medium);
+        assert_eq!(six_eight.accents[4], BeatAccent::Normal);
+
+        assert!(MetronomeSettings { bpm: 5.0, ..Default::default() }.resolve().is_err());
+        assert!(MetronomeSettings { subdivision: 0, ..Default::default() }.resolve().is_err());
+        let short_pattern = MetronomeSettings { accents: vec![BeatAccent::Strong], ..Default::default() };
+        assert!(short_pattern.resolve().is_err());
+    }
+
+    #[test]
+    fn test_beats_counted_at_tempo() {
+        // 60 BPM in 3/4 with eighth subdivisions: a beat every second, a click every half second
+        let settings =
+            MetronomeSettings { bpm: 60.0, time_signature: "3/4".to_string(), subdivision: 2, ..Default::default() };
+        let (metronome, mut source) = Metronome::new(pattern(settings));
+        assert!(metronome.latest_beat().is_none());
+
+        source.next();
+        let (_, first) = metronome.latest_beat().unwrap();
+        assert_eq!((first.beat, first.count, first.accent), (0, 0, BeatAccent::Strong));
+
+        // Up to the start of the fourth beat, which wraps to the next bar
+        for _ in 0..SAMPLE_RATE * 3 {
+            source.next();
+        }
+        let (_, fourth) = metronome.latest_beat().unwrap();
+        assert_eq!((fourth.beat, fourth.count), (0, 3));
+
+        metronome.stop();
+        assert_eq!(source.next(), None);
+    }
+
+    #[test]
+    fn test_tempo_change_applies_at_next_click() {
+        let (metronome, mut source) = Metronome::new(pattern(MetronomeSettings { bpm: 60.0, ..Default::default() }));
+        source.next();
+        metronome.set_pattern(pattern(MetronomeSettings { bpm: 120.0, ..Default::default() }));
+
+        // The first beat keeps its full second; the next beats are half a second apart
+        for _ in 0..SAMPLE_RATE + SAMPLE_RATE / 2 {
+            source.next();
+        }
+        let (_, beat) = metronome.latest_beat().unwrap();
+        assert_eq!(beat.count, 2);
+    }
+
+    #[test]
+    fn test_follow_schedule_tracks_tempo_and_meter() {
+        // One bar of 2/4 at 60 BPM, then 3/4 at 120 BPM
+        let mut tempo = TempoMap::new(60.0, "2/4").unwrap();
+        tempo.change_at(2.0, Some(120.0), Some("3/4")).unwrap();
+        let timeline = Timeline::new(&[2.0, 3.0], tempo);
+        let base = pattern(MetronomeSettings { subdivision: 2, ..Default::default() });
+
+        let clicks = follow_schedule(&base, &timeline, Duration::ZERO);
+        let beats: Vec<(u64, Option<(u32, BeatAccent)>)> =
+            clicks.iter().map(|click| (click.at.as_millis() as u64, click.beat)).collect();
+        assert_eq!(beats[..5].to_vec(), vec![
+            (0, Some((0, BeatAccent::Strong))),
+            (500, None),
+            (1000, Some((1, BeatAccent::Normal))),
+            (1500, None),
+            (2000, Some((0, BeatAccent::Strong))),
+        ]);
+        assert_eq!(clicks.len(), 10);
+        assert_eq!(clicks[9].at, Duration::from_millis(3250));
+
+        // Resuming part-way drops the clicks already played and counts from the position
+        let resumed = follow_schedule(&base, &timeline, Duration::from_millis(2800));
+        assert_eq!(resumed[0].at, Duration::from_millis(200));
+        assert_eq!(resumed[0].beat, Some((2, BeatAccent::Normal)));
+    }
+
+    #[test]
+    fn test_following_source_ends_after_last_click() {
+        let clicks = vec![
+            ScheduledClick { at: Duration::from_millis(10), beat: Some((0, BeatAccent::Medium)) },
+            ScheduledClick { at: Duration::from_millis(20), beat: Some((1, BeatAccent::Normal)) },
+        ];
+        let (metronome, source) = Metronome::following(pattern(MetronomeSettings::default()), clicks);
+        let samples = source.count() as u64;
+        assert_eq!(samples, samples_at(Duration::from_millis(20)) + samples_at(CLICK_DURATION));
+
+        let (_, beat) = metronome.latest_beat().unwrap();
+        assert_eq!((beat.beat, beat.count, beat.accent), (1, 1, BeatAccent::Normal));
+    }
+}
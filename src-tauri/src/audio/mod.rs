@@ -1,6 +1,20 @@
 mod samples;
+mod arpeggio;
 mod engine;
 mod envelope;
 mod monitor;
+mod analysis;
+mod sequence;
+mod metronome;
+mod render;
+mod output;
+mod wav;
 
-pub use engine::AudioEngineHandle;
+pub use engine::{set_voice_limit, AudioEngineHandle, DEFAULT_VOICE_LIMIT, VOICE_LIMIT_RANGE};
+pub use arpeggio::{ArpeggioDirection, MAX_ARPEGGIO_STEP};
+pub use analysis::{analyze_file, AudioAnalysis};
+pub use sequence::{SequenceChord, SequenceEvent};
+pub use metronome::{MetronomeBeat, MetronomeSettings};
+pub use render::{render_stems, MixBalance, Stem, RENDER_CHANNELS, RENDER_SAMPLE_RATE};
+pub use wav::encode_wav;
+pub use samples::embedded_samples;
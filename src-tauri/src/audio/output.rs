@@ -0,0 +1,147 @@
+// Audio output
+// Where the audio thread's sinks play: the default output device, a null output that discards the
+// mix as it is rendered (headless builds and builds without playback), or (in tests) an in-memory
+// capture that renders the mix into buffers so engine behavior can be checked without a device
+
+use rodio::mixer::Mixer;
+#[cfg(feature = "playback")]
+use rodio::OutputStream;
+
+/// Destination of the audio engine's mix
+pub trait AudioOutput {
+    /// Mixer that sinks connect to
+    fn mixer(&self) -> &Mixer;
+
+    /// Bring the mix up to the present before the engine looks at its sinks
+    /// Devices pull the mix themselves, so only outputs rendered on demand need this
+    fn catch_up(&mut self) {}
+}
+
+#[cfg(feature = "playback")]
+impl AudioOutput for OutputStream {
+    fn mixer(&self) -> &Mixer {
+        OutputStream::mixer(self)
+    }
+}
+
+#[cfg(any(test, feature = "headless-audio", not(feature = "playback")))]
+pub mod null {
+    use rodio::mixer::{self, Mixer, MixerSource};
+    use std::time::{Duration, Instant};
+
+    use super::AudioOutput;
+
+    /// Output that plays nowhere: the mix is rendered on demand and discarded,
+    /// so sinks advance, finish and empty as far as it has been rendered
+    pub struct NullOutput {
+        mixer: Mixer,
+        source: MixerSource,
+        channels: u16,
+        sample_rate: u32,
+        /// When catch_up started counting, and the frames rendered since
+        started: Instant,
+        frames: u64,
+    }
+
+    impl NullOutput {
+        pub fn new(channels: u16, sample_rate: u32) -> Self {
+            let (mixer, source) = mixer::mixer(channels, sample_rate);
+            Self { mixer, source, channels, sample_rate, started: Instant::now(), frames: 0 }
+        }
+
+        /// Render and discard the next stretch of the mix, returning the number of samples it held
+        pub fn render(&mut self, duration: Duration) -> usize {
+            let frames = (duration.as_secs_f64() * self.sample_rate as f64).round() as u64;
+            let samples = frames as usize * self.channels as usize;
+            for _ in 0..samples {
+                self.source.next();
+            }
+            self.frames += frames;
+            samples
+        }
+    }
+
+    impl AudioOutput for NullOutput {
+        fn mixer(&self) -> &Mixer {
+            &self.mixer
+        }
+
+        /// Render the mix up to the time elapsed since the output was created, as a device would have
+        fn catch_up(&mut self) {
+            let rendered = Duration::from_secs_f64(self.frames as f64 / self.sample_rate as f64);
+            self.render(self.started.elapsed().saturating_sub(rendered));
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod capture {
+    use rodio::mixer::{self, Mixer, MixerSource};
+    use std::time::Duration;
+
+    use super::AudioOutput;
+
+    /// Output that plays nowhere; the mix is pulled into buffers with render
+    pub struct CaptureOutput {
+        mixer: Mixer,
+        source: MixerSource,
+        channels: u16,
+        sample_rate: u32,
+    }
+
+    impl CaptureOutput {
+        pub fn new(channels: u16, sample_rate: u32) -> Self {
+            let (mixer, source) = mixer::mixer(channels, sample_rate);
+            Self { mixer, source, channels, sample_rate }
+        }
+
+        /// Pull the next stretch of the mix as interleaved samples, silent where nothing is playing
+        pub fn render(&mut self, duration: Duration) -> Vec<f32> {
+            let frames = (duration.as_secs_f64() * self.sample_rate as f64).round() as usize;
+            (0..frames * self.channels as usize).map(|_| self.source.next().unwrap_or(0.0)).collect()
+        }
+    }
+
+    impl AudioOutput for CaptureOutput {
+        fn mixer(&self) -> &Mixer {
+            &self.mixer
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::capture::CaptureOutput;
+    use super::null::NullOutput;
+    use super::*;
+    use rodio::source::SineWave;
+    use rodio::{Sink, Source};
+    use std::time::Duration;
+
+    #[test]
+    fn test_capture_renders_the_mix() {
+        let mut output = CaptureOutput::new(2, 48_000);
+        assert!(output.render(Duration::from_millis(10)).iter().all(|&sample| sample == 0.0));
+
+        let sink = Sink::connect_new(output.mixer());
+        sink.append(SineWave::new(440.0).take_duration(Duration::from_millis(20)));
+        let buffer = output.render(Duration::from_millis(40));
+        assert_eq!(buffer.len(), 2 * 48 * 40);
+        assert!(buffer[..2 * 48 * 15].iter().any(|&sample| sample.abs() > 0.5));
+        assert!(buffer[2 * 48 * 25..].iter().all(|&sample| sample == 0.0));
+    }
+
+    #[test]
+    fn test_null_output_renders_on_demand() {
+        let mut output = NullOutput::new(2, 48_000);
+        let sink = Sink::connect_new(output.mixer());
+        sink.append(SineWave::new(440.0).take_duration(Duration::from_millis(100)));
+        assert!(!sink.empty());
+
+        // Nothing plays until the mix is rendered, then exactly the rendered stretch is consumed
+        assert_eq!(output.render(Duration::from_millis(40)), 2 * 48 * 40);
+        assert!(!sink.empty());
+        assert_eq!(output.render(Duration::from_millis(70)), 2 * 48 * 70);
+        assert!(sink.empty());
+    }
+}
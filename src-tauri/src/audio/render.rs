@@ -0,0 +1,199 @@
+// Offline rendering
+// Renders a sequence into buffers without an output device, through the same signal chain as
+// live playback, as separate stems (chords, bass line, click) or one balanced mix
+
+use rodio::source::UniformSourceIterator;
+use rodio::Source;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::engine::{sequence_note_source, sequence_release, METRONOME_VOLUME_MULTIPLIER};
+use super::metronome::{follow_schedule, Metronome, MetronomePattern};
+use super::sequence::{SequenceChord, Timeline};
+use crate::music::tempo::TempoMap;
+use crate::music::voice_leading::note_to_midi;
+
+/// Output format of rendered audio (interleaved stereo)
+pub const RENDER_SAMPLE_RATE: u32 = 48_000;
+pub const RENDER_CHANNELS: u16 = 2;
+
+/// A separately rendered part of a track
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stem {
+    /// Upper voices of each chord
+    Chords,
+    /// Lowest note of each chord
+    Bass,
+    Click,
+}
+
+impl Stem {
+    pub const ALL: [Stem; 3] = [Stem::Chords, Stem::Bass, Stem::Click];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stem::Chords => "chords",
+            Stem::Bass => "bass",
+            Stem::Click => "click",
+        }
+    }
+}
+
+/// Level of each stem in a mix (0.0 to 1.0)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MixBalance {
+    pub chords: f32,
+    pub bass: f32,
+    pub click: f32,
+}
+
+impl Default for MixBalance {
+    fn default() -> Self {
+        Self { chords: 1.0, bass: 1.0, click: 1.0 }
+    }
+}
+
+impl MixBalance {
+    fn level(&self, stem: Stem) -> f32 {
+        let level = match stem {
+            Stem::Chords => self.chords,
+            Stem::Bass => self.bass,
+            Stem::Click => self.click,
+        };
+        level.clamp(0.0, 1.0)
+    }
+}
+
+/// Rendered stems, interleaved at RENDER_SAMPLE_RATE and RENDER_CHANNELS, all of one length
+#[derive(Debug, Clone)]
+pub struct Stems {
+    pub chords: Vec<f32>,
+    pub bass: Vec<f32>,
+    /// None when rendered without a click
+    pub click: Option<Vec<f32>>,
+}
+
+impl Stems {
+    pub fn get(&self, stem: Stem) -> Option<&[f32]> {
+        match stem {
+            Stem::Chords => Some(&self.chords),
+            Stem::Bass => Some(&self.bass),
+            Stem::Click => self.click.as_deref(),
+        }
+    }
+
+    /// Sum of the stems at the given levels
+    pub fn mix(&self, balance: &MixBalance) -> Vec<f32> {
+        let mut mix = vec![0.0; self.chords.len()];
+        for stem in Stem::ALL {
+            let level = balance.level(stem);
+            for (out, sample) in mix.iter_mut().zip(self.get(stem).unwrap_or_default()) {
+                *out += sample * level;
+            }
+        }
+        mix
+    }
+}
+
+/// Add a source into a buffer from a time offset, converted to the render format
+fn add_source(buffer: &mut Vec<f32>, source: impl Source<Item = f32>, offset: Duration, gain: f32) {
+    let start = (offset.as_secs_f64() * RENDER_SAMPLE_RATE as f64).round() as usize * RENDER_CHANNELS as usize;
+    for (index, sample) in UniformSourceIterator::new(source, RENDER_CHANNELS, RENDER_SAMPLE_RATE).enumerate() {
+        if start + index >= buffer.len() {
+            buffer.resize(start + index + 1, 0.0);
+        }
+        buffer[start + index] += sample * gain;
+    }
+}
+
+/// Index of the lowest note of a chord, which goes to the bass stem
+fn bass_index(chord: &SequenceChord) -> Option<usize> {
+    if chord.notes.len() < 2 {
+        return None;
+    }
+    (0..chord.notes.len()).min_by_key(|index| {
+        let note = &chord.notes[*index];
+        note_to_midi(&note.note, note.octave).unwrap_or(u8::MAX)
+    })
+}
+
+/// Render a sequence as stems, with a click following its tempo and meter when a pattern is given
+pub fn render_stems(chords: &[SequenceChord], tempo: TempoMap, click: Option<&MetronomePattern>) -> Stems {
+    let beats: Vec<f32> = chords.iter().map(|chord| chord.beats).collect();
+    let timeline = Timeline::new(&beats, tempo);
+    let mut stems = Stems { chords: Vec::new(), bass: Vec::new(), click: None };
+
+    for (index, chord) in chords.iter().enumerate() {
+        let start = timeline.start(index);
+        let hold = timeline.end(index) - start;
+        let release = sequence_release(index + 1 == chords.len());
+        // Chords share the level of live playback, which divides it among the notes
+        let gain = 1.0 / chord.notes.len().max(1) as f32;
+        let bass = bass_index(chord);
+
+        for (note_index, audio_note) in chord.notes.iter().enumerate() {
+            let Some(source) = sequence_note_source(audio_note, Duration::ZERO, hold, release) else {
+                continue;
+            };
+            let buffer = if Some(note_index) == bass { &mut stems.bass } else { &mut stems.chords };
+            add_source(buffer, source, start, gain);
+        }
+    }
+
+    if let Some(pattern) = click {
+        let (_, source) = Metronome::following(pattern.clone(), follow_schedule(pattern, &timeline, Duration::ZERO));
+        let mut buffer = Vec::new();
+        add_source(&mut buffer, source, Duration::ZERO, pattern.volume * METRONOME_VOLUME_MULTIPLIER);
+        stems.click = Some(buffer);
+    }
+
+    // Pad every stem to the longest so they line up when imported side by side
+    let length = Stem::ALL.iter().filter_map(|stem| stems.get(*stem)).map(<[f32]>::len).max().unwrap_or(0);
+    for buffer in [Some(&mut stems.chords), Some(&mut stems.bass), stems.click.as_mut()].into_iter().flatten() {
+        buffer.resize(length, 0.0);
+    }
+    stems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::metronome::MetronomeSettings;
+
+    #[test]
+    fn test_click_stem_and_mix_balance() {
+        let chords = vec![SequenceChord { notes: Vec::new(), beats: 4.0 }];
+        let pattern = MetronomeSettings { bpm: 120.0, ..Default::default() }.resolve().unwrap();
+        let stems = render_stems(&chords, TempoMap::new(120.0, "4/4").unwrap(), Some(&pattern));
+
+        let click = stems.get(Stem::Click).unwrap();
+        assert_eq!(stems.chords.len(), click.len());
+        assert_eq!(stems.bass.len(), click.len());
+        // Four beats of a 2 second bar, the last one starting at 1.5 s
+        let frames = click.len() / RENDER_CHANNELS as usize;
+        assert!(frames > RENDER_SAMPLE_RATE as usize * 3 / 2);
+        assert!(click.iter().any(|sample| sample.abs() > 0.1));
+
+        let silent = stems.mix(&MixBalance { click: 0.0, ..Default::default() });
+        assert!(silent.iter().all(|sample| *sample == 0.0));
+        let half = stems.mix(&MixBalance { click: 0.5, ..Default::default() });
+        assert_eq!(half[100], click[100] * 0.5);
+
+        assert!(render_stems(&chords, TempoMap::new(120.0, "4/4").unwrap(), None).click.is_none());
+    }
+
+    #[test]
+    fn test_bass_is_lowest_note() {
+        let note = |name: &str, octave| crate::music::types::AudioNote {
+            note: name.to_string(),
+            octave,
+            is_common_tone: false,
+            cents: 0.0,
+        };
+        let chord = SequenceChord { notes: vec![note("E", 3), note("C", 2), note("G", 3)], beats: 4.0 };
+        assert_eq!(bass_index(&chord), Some(1));
+        assert_eq!(bass_index(&SequenceChord { notes: vec![note("C", 3)], beats: 4.0 }), None);
+    }
+}
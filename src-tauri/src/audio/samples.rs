@@ -7,6 +7,11 @@ pub fn get_sample(key: &str) -> Option<&'static [u8]> {
     SAMPLES.get(key).copied()
 }
 
+/// Every embedded sample with its key, in no particular order
+pub fn embedded_samples() -> impl Iterator<Item = (&'static str, &'static [u8])> {
+    SAMPLES.iter().map(|(key, bytes)| (*key, *bytes))
+}
+
 /// Convert a note name with sharp notation to sample key format
 /// e.g., "C#" -> "Cs", "D" -> "D"
 pub fn note_to_sample_key(note: &str, octave: i8) -> String {
@@ -0,0 +1,240 @@
+// Sequence scheduling
+// Timing for progressions played by the audio thread: where each chord starts, where playback is,
+// and which chord-boundary events are due
+
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+use crate::music::tempo::TempoMap;
+use crate::music::types::AudioNote;
+
+/// One chord of a scheduled sequence
+#[derive(Debug, Clone)]
+pub struct SequenceChord {
+    pub notes: Vec<AudioNote>,
+    /// Length in beats, counted in the beat unit of the meter it starts in
+    pub beats: f32,
+}
+
+/// Progress reported while a sequence plays
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SequenceEvent {
+    /// The chord at this index began sounding
+    ChordStarted { index: usize },
+    /// The last chord's time has run out
+    Finished,
+}
+
+/// Receives sequence events on the audio thread
+pub type SequenceListener = Box<dyn FnMut(SequenceEvent) + Send>;
+
+/// Start time and length of each chord, following the sequence's tempo and meter changes
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    starts: Vec<Duration>,
+    total: Duration,
+    tempo: TempoMap,
+    total_beats: f64,
+}
+
+impl Timeline {
+    pub fn new(beats: &[f32], tempo: TempoMap) -> Self {
+        let mut starts = Vec::with_capacity(beats.len());
+        let mut elapsed = 0.0;
+        for length in beats {
+            starts.push(tempo.time_at(elapsed));
+            elapsed += *length as f64;
+        }
+        Self { starts, total: tempo.time_at(elapsed), tempo, total_beats: elapsed }
+    }
+
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    pub fn start(&self, index: usize) -> Duration {
+        self.starts.get(index).copied().unwrap_or(self.total)
+    }
+
+    pub fn end(&self, index: usize) -> Duration {
+        self.start(index + 1)
+    }
+
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    pub fn tempo(&self) -> &TempoMap {
+        &self.tempo
+    }
+
+    pub fn total_beats(&self) -> f64 {
+        self.total_beats
+    }
+}
+
+/// Playback position of a sequence, running or paused
+#[derive(Debug, Clone)]
+pub struct SequenceClock {
+    timeline: Timeline,
+    /// Position when playback last started, or where it is paused
+    position: Duration,
+    /// When playback last started; None while paused
+    resumed_at: Option<Instant>,
+    /// Next chord whose start has not been reported
+    next_event: usize,
+    finished: bool,
+}
+
+impl SequenceClock {
+    /// A paused clock at the start of the timeline
+    pub fn new(timeline: Timeline) -> Self {
+        Self { timeline, position: Duration::ZERO, resumed_at: None, next_event: 0, finished: false }
+    }
+
+    pub fn timeline(&self) -> &Timeline {
+        &self.timeline
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.resumed_at.is_some()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn position(&self, now: Instant) -> Duration {
+        match self.resumed_at {
+            Some(resumed_at) => (self.position + now.saturating_duration_since(resumed_at)).min(self.timeline.total()),
+            None => self.position,
+        }
+    }
+
+    /// Start (or continue) playing from a position
+    /// A chord starting exactly there is reported; one already under way is not
+    pub fn play_from(&mut self, position: Duration, now: Instant) {
+        self.position = position.min(self.timeline.total());
+        self.resumed_at = Some(now);
+        self.next_event = (0..self.timeline.len())
+            .find(|index| self.timeline.start(*index) >= self.position)
+            .unwrap_or(self.timeline.len());
+        self.finished = false;
+    }
+
+    pub fn pause(&mut self, now: Instant) {
+        self.position = self.position(now);
+        self.resumed_at = None;
+    }
+
+    /// Move to the start of a chord, keeping the playing/paused state
+    pub fn seek(&mut self, index: usize, now: Instant) {
+        let position = self.timeline.start(index.min(self.timeline.len()));
+        if self.is_playing() {
+            self.play_from(position, now);
+        } else {
+            self.position = position;
+            self.next_event = index.min(self.timeline.len());
+        }
+    }
+
+    /// Time until the next event is due; None while paused or once finished
+    pub fn time_to_next_event(&self, now: Instant) -> Option<Duration> {
+        if !self.is_playing() || self.finished {
+            return None;
+        }
+        let next = self.timeline.start(self.next_event);
+        Some(next.saturating_sub(self.position(now)))
+    }
+
+    /// Events that have come due, in order
+    pub fn due_events(&mut self, now: Instant) -> Vec<SequenceEvent> {
+        let mut events = Vec::new();
+        if !self.is_playing() || self.finished {
+            return events;
+        }
+
+        let position = self.position(now);
+        while self.next_event < self.timeline.len() && self.timeline.start(self.next_event) <= position {
+            events.push(SequenceEvent::ChordStarted { index: self.next_event });
+            self.next_event += 1;
+        }
+        if self.next_event >= self.timeline.len() && position >= self.timeline.total() {
+            events.push(SequenceEvent::Finished);
+            self.finished = true;
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn timeline(beats: &[f32], bpm: f32) -> Timeline {
+        Timeline::new(beats, TempoMap::new(bpm, "4/4").unwrap())
+    }
+
+    #[test]
+    fn test_timeline_from_beats() {
+        let timeline = timeline(&[4.0, 2.0, 2.0], 120.0);
+        assert_eq!(timeline.start(1), ms(2000));
+        assert_eq!(timeline.end(1), ms(3000));
+        assert_eq!(timeline.total(), ms(4000));
+    }
+
+    #[test]
+    fn test_timeline_follows_tempo_changes() {
+        // Half time from the second chord onwards
+        let mut tempo = TempoMap::new(120.0, "4/4").unwrap();
+        tempo.change_at(4.0, Some(60.0), None).unwrap();
+        let timeline = Timeline::new(&[4.0, 2.0, 2.0], tempo);
+        assert_eq!(timeline.start(1), ms(2000));
+        assert_eq!(timeline.start(2), ms(4000));
+        assert_eq!(timeline.total(), ms(6000));
+        assert_eq!(timeline.total_beats(), 8.0);
+    }
+
+    #[test]
+    fn test_clock_reports_boundaries_in_order() {
+        let start = Instant::now();
+        let mut clock = SequenceClock::new(timeline(&[1.0, 1.0], 60.0));
+        clock.play_from(Duration::ZERO, start);
+
+        assert_eq!(clock.time_to_next_event(start), Some(Duration::ZERO));
+        assert_eq!(clock.due_events(start), vec![SequenceEvent::ChordStarted { index: 0 }]);
+        assert_eq!(clock.time_to_next_event(start + ms(400)), Some(ms(600)));
+        assert_eq!(
+            clock.due_events(start + ms(2500)),
+            vec![SequenceEvent::ChordStarted { index: 1 }, SequenceEvent::Finished]
+        );
+        assert!(clock.is_finished());
+        assert_eq!(clock.time_to_next_event(start + ms(2500)), None);
+    }
+
+    #[test]
+    fn test_pause_resume_and_seek() {
+        let start = Instant::now();
+        let mut clock = SequenceClock::new(timeline(&[2.0, 2.0, 2.0], 60.0));
+        clock.play_from(Duration::ZERO, start);
+        clock.due_events(start);
+
+        clock.pause(start + ms(1500));
+        assert_eq!(clock.position(start + ms(9000)), ms(1500));
+        assert_eq!(clock.time_to_next_event(start + ms(9000)), None);
+
+        // Resuming mid-chord does not report that chord again
+        let resumed = start + ms(9000);
+        clock.play_from(clock.position(resumed), resumed);
+        assert!(clock.due_events(resumed).is_empty());
+        assert_eq!(clock.time_to_next_event(resumed), Some(ms(500)));
+
+        clock.seek(2, resumed);
+        assert_eq!(clock.due_events(resumed), vec![SequenceEvent::ChordStarted { index: 2 }]);
+    }
+}
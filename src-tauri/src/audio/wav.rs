@@ -0,0 +1,52 @@
+// WAV encoding
+// 16-bit PCM RIFF files for rendered audio
+
+/// Bytes per encoded sample
+const BYTES_PER_SAMPLE: u16 = 2;
+
+/// Encode interleaved samples (-1.0 to 1.0, clipped beyond) as a 16-bit PCM WAV file
+pub fn encode_wav(samples: &[f32], channels: u16, sample_rate: u32) -> Vec<u8> {
+    let data_len = samples.len() as u32 * BYTES_PER_SAMPLE as u32;
+    let block_align = channels * BYTES_PER_SAMPLE;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&channels.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    wav.extend_from_slice(&block_align.to_le_bytes());
+    wav.extend_from_slice(&(BYTES_PER_SAMPLE * 8).to_le_bytes());
+
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+        wav.extend_from_slice(&value.to_le_bytes());
+    }
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wav_header_and_clipping() {
+        let wav = encode_wav(&[0.0, 1.0, -2.0, 0.5], 2, 48_000);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(wav[4..8].try_into().unwrap()), 36 + 8);
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(u16::from_le_bytes(wav[22..24].try_into().unwrap()), 2);
+        assert_eq!(u32::from_le_bytes(wav[28..32].try_into().unwrap()), 48_000 * 4);
+        assert_eq!(&wav[36..40], b"data");
+
+        let samples: Vec<i16> = wav[44..].chunks(2).map(|b| i16::from_le_bytes([b[0], b[1]])).collect();
+        assert_eq!(samples, vec![0, i16::MAX, -i16::MAX, 16384]);
+    }
+}
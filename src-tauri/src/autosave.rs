@@ -0,0 +1,170 @@
+// Autosave and crash recovery
+// The UI pushes the latest project snapshot; a background task writes it to the app data directory.
+// A marker file is kept while the app runs, so one left behind at launch means the last session
+// crashed and its snapshot is offered for recovery
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::types::project::Project;
+use crate::types::versioned;
+
+/// Directory name of the autosave inside the app data directory
+pub const AUTOSAVE_DIR: &str = "autosave";
+
+/// How often the latest snapshot is written
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+const SNAPSHOT_FILE: &str = "session.json";
+/// Present while the app runs and removed on a clean exit
+const RUNNING_MARKER: &str = "running";
+
+/// Snapshot file contents; the project is kept in its versioned envelope
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredSnapshot {
+    /// Unix timestamp (milliseconds)
+    saved_at: u64,
+    project: serde_json::Value,
+}
+
+/// The last snapshot of a session that crashed
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveredSession {
+    pub project: Project,
+    /// Unix timestamp (milliseconds)
+    pub saved_at: u64,
+}
+
+pub struct Autosave {
+    dir: PathBuf,
+    /// Latest snapshot pushed and not yet written
+    pending: Option<Project>,
+    /// Read at launch, before this session's snapshots replace it
+    recovered: Option<RecoveredSession>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn read_snapshot(dir: &Path) -> Result<Option<RecoveredSession>, String> {
+    let Ok(json) = fs::read_to_string(dir.join(SNAPSHOT_FILE)) else {
+        return Ok(None);
+    };
+    let stored: StoredSnapshot =
+        serde_json::from_str(&json).map_err(|e| format!("Failed to read autosave: {}", e))?;
+    let project = versioned::from_value::<Project>(stored.project)?;
+    Ok(Some(RecoveredSession { project: project.value, saved_at: stored.saved_at }))
+}
+
+impl Autosave {
+    /// Start a session autosaving to `dir`, picking up the snapshot of a previous session that crashed
+    pub fn start(dir: PathBuf) -> Self {
+        let recovered = if dir.join(RUNNING_MARKER).exists() {
+            read_snapshot(&dir).unwrap_or_else(|e| {
+                println!("[autosave] {}", e);
+                None
+            })
+        } else {
+            None
+        };
+        let marked = fs::create_dir_all(&dir).and_then(|_| fs::write(dir.join(RUNNING_MARKER), now_ms().to_string()));
+        if let Err(e) = marked {
+            println!("[autosave] Failed to mark session as running: {}", e);
+        }
+        Self { dir, pending: None, recovered }
+    }
+
+    /// Autosave in the standard directory inside the app data directory
+    pub fn start_in_dir(data_dir: &Path) -> Self {
+        Self::start(data_dir.join(AUTOSAVE_DIR))
+    }
+
+    /// Replace the snapshot to write next
+    pub fn update(&mut self, project: Project) {
+        self.pending = Some(project);
+    }
+
+    /// Write the pending snapshot, if any; returns whether one was written
+    /// The file is replaced by rename, so a crash mid-write leaves the previous snapshot intact
+    pub fn flush(&mut self) -> Result<bool, String> {
+        let Some(project) = self.pending.take() else {
+            return Ok(false);
+        };
+        let stored = StoredSnapshot { saved_at: now_ms(), project: versioned::to_value(&project)? };
+        let json = serde_json::to_string(&stored).map_err(|e| format!("Failed to serialize autosave: {}", e))?;
+
+        let temp = self.dir.join(format!("{}.tmp", SNAPSHOT_FILE));
+        fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&temp, json))
+            .and_then(|_| fs::rename(&temp, self.dir.join(SNAPSHOT_FILE)))
+            .map_err(|e| format!("Failed to write autosave: {}", e))?;
+        Ok(true)
+    }
+
+    /// Snapshot of the crashed session found at launch, if there was one
+    pub fn recovered(&self) -> Option<&RecoveredSession> {
+        self.recovered.as_ref()
+    }
+
+    /// End the session cleanly: nothing is left to recover at the next launch
+    pub fn finish(&mut self) -> Result<(), String> {
+        self.pending = None;
+        for file in [SNAPSHOT_FILE, RUNNING_MARKER] {
+            match fs::remove_file(self.dir.join(file)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(format!("Failed to clear autosave: {}", e));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn project(name: &str) -> Project {
+        Project { name: name.to_string(), ..Project::default() }
+    }
+
+    #[test]
+    fn test_recovers_latest_snapshot_after_crash() {
+        let dir = TempDir::new().unwrap();
+        let mut autosave = Autosave::start_in_dir(dir.path());
+        assert!(autosave.recovered().is_none());
+        assert!(!autosave.flush().unwrap());
+
+        autosave.update(project("first"));
+        autosave.update(project("second"));
+        assert!(autosave.flush().unwrap());
+        autosave.update(project("never written"));
+        drop(autosave);
+
+        let autosave = Autosave::start_in_dir(dir.path());
+        assert_eq!(autosave.recovered().unwrap().project.name, "second");
+    }
+
+    #[test]
+    fn test_clean_exit_leaves_nothing_to_recover() {
+        let dir = TempDir::new().unwrap();
+        let mut autosave = Autosave::start_in_dir(dir.path());
+        autosave.update(project("saved"));
+        autosave.flush().unwrap();
+        autosave.finish().unwrap();
+
+        let autosave = Autosave::start_in_dir(dir.path());
+        assert!(autosave.recovered().is_none());
+        // A session that crashes before its first autosave has no snapshot to offer either
+        drop(autosave);
+        assert!(Autosave::start_in_dir(dir.path()).recovered().is_none());
+    }
+}
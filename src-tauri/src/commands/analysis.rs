@@ -0,0 +1,100 @@
+// Live progression analysis commands
+// The backend keeps the edited progression and pushes only changed analysis results
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{Emitter, State, Window};
+
+use crate::documents::{self, DocumentMap};
+use crate::music::analysis::{AnalysisDelta, ChordAnalysis, ProgressionAnalyzer, ProgressionEdit};
+use crate::music::habits::{self, WritingHabits};
+use crate::types::song::Song;
+use crate::types::versioned;
+
+/// Event emitted whenever an edit changes the analysis
+pub const ANALYSIS_CHANGED_EVENT: &str = "progression-analysis-changed";
+
+/// Extension of saved song files
+const PROJECT_EXTENSION: &str = "json";
+
+/// Managed state wrapper for the progression analyzed in each open document
+pub struct AnalysisState(pub Mutex<DocumentMap<ProgressionAnalyzer>>);
+
+/// Payload of ANALYSIS_CHANGED_EVENT
+#[derive(Debug, Clone, Serialize)]
+struct AnalysisChanged<'a> {
+    /// Canvas the change belongs to, as passed to apply_progression_edit
+    document_id: Option<&'a str>,
+    #[serde(flatten)]
+    delta: &'a AnalysisDelta,
+}
+
+/// Apply an edit to a document's analyzed progression
+/// Emits ANALYSIS_CHANGED_EVENT to the calling window when anything changed, and also returns the delta
+#[tauri::command]
+pub fn apply_progression_edit(
+    window: Window,
+    state: State<'_, AnalysisState>,
+    document_id: Option<String>,
+    edit: ProgressionEdit,
+) -> Result<AnalysisDelta, String> {
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    let mut analyzers = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+
+    let delta = analyzers
+        .entry(&document)
+        .apply(edit)
+        .map_err(|e| format!("Failed to apply edit: {}", e))?;
+
+    if !delta.is_empty() {
+        let payload = AnalysisChanged { document_id: document_id.as_deref(), delta: &delta };
+        window
+            .emit_to(window.label(), ANALYSIS_CHANGED_EVENT, &payload)
+            .map_err(|e| format!("Failed to emit analysis event: {}", e))?;
+    }
+
+    Ok(delta)
+}
+
+/// Get the full analysis of a document's progression (e.g. after a window reload)
+#[tauri::command]
+pub fn get_progression_analysis(
+    window: Window,
+    state: State<'_, AnalysisState>,
+    document_id: Option<String>,
+) -> Result<Vec<ChordAnalysis>, String> {
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    let analyzers = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(analyzers.get(&document).map(|a| a.analysis().to_vec()).unwrap_or_default())
+}
+
+/// Saved song files in a folder and its subfolders
+fn project_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            project_files(&path, files)?;
+        } else if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case(PROJECT_EXTENSION)) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Report the chords, keys and progressions used most across the songs saved in a folder
+/// JSON files that aren't saved songs are skipped
+#[tauri::command]
+pub async fn analyze_writing_habits(directory: String) -> Result<WritingHabits, String> {
+    let mut files = Vec::new();
+    project_files(Path::new(&directory), &mut files)?;
+    let songs: Vec<Song> = files
+        .iter()
+        .filter_map(|path| fs::read(path).ok())
+        .filter_map(|bytes| versioned::from_slice::<Song>(&bytes).ok())
+        .map(|loaded| loaded.value)
+        .collect();
+    Ok(habits::writing_habits(&songs))
+}
@@ -0,0 +1,106 @@
+// Research analytics commands
+// Opt-in local interaction log with anonymized CSV/JSON export
+
+use std::fs;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_dialog::{DialogExt, FilePath};
+
+use super::policy::{CommandError, PolicyState};
+use super::settings::SettingsState;
+use crate::analytics::{self, AnalyticsEvent, AnalyticsLog, ExportFormat};
+use crate::settings::Feature;
+
+/// Managed state wrapper for the analytics log
+pub struct AnalyticsState(pub Mutex<AnalyticsLog>);
+
+/// Record an event if analytics are enabled, ignoring failures
+/// Analytics must never get in the way of the action being logged
+pub fn record_event(state: &AnalyticsState, event: AnalyticsEvent) {
+    if let Ok(log) = state.0.lock() {
+        if let Err(e) = log.record(event) {
+            println!("[analytics] {}", e);
+        }
+    }
+}
+
+/// Check whether the user opted in to analytics
+#[tauri::command]
+pub fn get_analytics_enabled(state: State<'_, AnalyticsState>) -> Result<bool, String> {
+    let log = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(log.is_enabled())
+}
+
+/// Opt in to or out of analytics (persisted in settings)
+#[tauri::command]
+pub fn set_analytics_enabled(
+    state: State<'_, AnalyticsState>,
+    settings: State<'_, SettingsState>,
+    policy: State<'_, PolicyState>,
+    enabled: bool,
+) -> Result<(), CommandError> {
+    policy.check(Feature::Analytics)?;
+    policy.check(Feature::Settings)?;
+
+    let mut store = settings.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    store.update(|s| s.analytics_enabled = enabled)?;
+
+    let mut log = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    log.set_enabled(enabled);
+    Ok(())
+}
+
+/// Record an interaction reported by the frontend (e.g. an accepted recommendation)
+#[tauri::command]
+pub fn record_analytics_event(state: State<'_, AnalyticsState>, event: AnalyticsEvent) -> Result<(), String> {
+    let log = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    log.record(event)
+}
+
+/// Export the anonymized log through a save dialog
+/// Returns false if the user cancelled
+#[tauri::command]
+pub async fn export_analytics(
+    app: AppHandle,
+    policy: State<'_, PolicyState>,
+    format: ExportFormat,
+) -> Result<bool, CommandError> {
+    policy.check(Feature::Export)?;
+    policy.check(Feature::Analytics)?;
+
+    let records = {
+        let state = app.state::<AnalyticsState>();
+        let log = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+        log.records()
+    };
+    let content = analytics::export_records(&records, format)?;
+
+    let (filter_name, extension) = match format {
+        ExportFormat::Csv => ("CSV File", "csv"),
+        ExportFormat::Json => ("JSON File", "json"),
+    };
+    let file_path = app
+        .dialog()
+        .file()
+        .add_filter(filter_name, &[extension])
+        .set_file_name(format!("maestro-analytics.{}", extension))
+        .set_title("Export Analytics")
+        .blocking_save_file();
+
+    let path = match file_path {
+        Some(FilePath::Path(p)) => p,
+        Some(_) => return Err("Invalid file path".into()),
+        None => return Ok(false), // User cancelled
+    };
+
+    fs::write(&path, content).map_err(|e| format!("Failed to write analytics export: {}", e))?;
+    Ok(true)
+}
+
+/// Delete all logged analytics
+#[tauri::command]
+pub fn clear_analytics(state: State<'_, AnalyticsState>, policy: State<'_, PolicyState>) -> Result<(), CommandError> {
+    policy.check(Feature::Analytics)?;
+    let log = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(log.clear()?)
+}
@@ -1,45 +1,164 @@
 // Audio playback commands for Tauri
 // These expose the Rust audio engine to the frontend
 
+use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
-use tauri::State;
+use std::time::Duration;
+use tauri::{Emitter, State, Window};
+use uuid::Uuid;
 
-use crate::audio::AudioEngineHandle;
+use crate::audio::{
+    analyze_file, ArpeggioDirection, AudioAnalysis, AudioEngineHandle, MetronomeBeat, MetronomeSettings,
+    SequenceChord, SequenceEvent, MAX_ARPEGGIO_STEP,
+};
+use crate::documents::{self, DocumentMap};
+use crate::music::tempo::TempoMap;
 use crate::music::types::AudioNote;
-use crate::music::voice_leading;
-use crate::music::types::VoicingStyle;
+use crate::music::voice_leading::{self, VoicingConfig, VoicingSession};
 use crate::music::intervals;
+use crate::types::song::MeasureChange;
 
-/// Managed state wrapper for audio engine handle
-/// The handle is Send + Sync as it only contains a channel sender
-pub struct AudioState(pub Mutex<Option<AudioEngineHandle>>);
+/// Event emitted at each chord boundary of a playing sequence and when it ends
+pub const SEQUENCE_EVENT: &str = "sequence-progress";
 
-/// Initialize audio engine (lazy initialization on first play if not called)
-#[tauri::command]
-pub fn init_audio(state: State<'_, AudioState>) -> Result<bool, String> {
-    let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+/// Payload of SEQUENCE_EVENT
+#[derive(Debug, Clone, Serialize)]
+struct SequenceProgress {
+    /// Canvas the sequence belongs to, as passed to play_sequence
+    document_id: Option<String>,
+    #[serde(flatten)]
+    event: SequenceEvent,
+}
+
+/// Event emitted on every metronome beat
+pub const METRONOME_BEAT_EVENT: &str = "metronome-beat";
+
+/// Payload of METRONOME_BEAT_EVENT
+#[derive(Debug, Clone, Serialize)]
+struct MetronomeBeatEvent {
+    /// Canvas the metronome belongs to, as passed to start_metronome
+    document_id: Option<String>,
+    #[serde(flatten)]
+    beat: MetronomeBeat,
+}
+
+/// A chord of a sequence to play
+#[derive(Debug, Clone, Deserialize)]
+pub struct SequenceChordRequest {
+    pub chord: String,
+    /// Length in beats, counted in the beat unit of the meter it starts in
+    pub beats: f32,
+}
+
+/// Meter of a sequence when play_sequence is given none
+const DEFAULT_TIME_SIGNATURE: &str = "4/4";
+
+/// Managed state wrapper for the audio engine of each open document
+/// Handles are Send + Sync as they only contain a channel sender
+pub struct AudioState(pub Mutex<DocumentMap<AudioEngineHandle>>);
+
+/// Voice leading memory of each open document and of each voicing session the frontend has created
+/// Kept in separate maps, so a session ID can never reach a document's memory or the other way round
+#[derive(Default)]
+pub struct VoicingSessions {
+    documents: DocumentMap<VoicingSession>,
+    /// Keyed by window and session ID
+    sessions: DocumentMap<VoicingSession>,
+}
+
+impl VoicingSessions {
+    /// A document's memory, created on first use
+    pub fn document(&mut self, document: &str) -> &mut VoicingSession {
+        self.documents.entry(document)
+    }
 
-    if guard.is_none() {
-        *guard = Some(AudioEngineHandle::new()?);
+    /// Forget a document's memory
+    pub fn remove_document(&mut self, document: &str) {
+        self.documents.remove(document);
     }
 
-    Ok(true)
+    /// Start a session in a window, returning its ID
+    pub fn create_session(&mut self, window_label: &str) -> String {
+        let id = Uuid::new_v4().to_string();
+        self.sessions.entry(&documents::document_id(window_label, Some(&id)));
+        id
+    }
+
+    /// A session created in the window, if there is one with this ID
+    pub fn session(&mut self, window_label: &str, id: &str) -> Option<&mut VoicingSession> {
+        self.sessions.get_mut(&documents::document_id(window_label, Some(id)))
+    }
+
+    /// Forget a session's previous chord; false if the window created no such session
+    pub fn reset_session(&mut self, window_label: &str, id: &str) -> bool {
+        self.session(window_label, id).map(VoicingSession::reset).is_some()
+    }
+
+    /// End a session; false if the window created no such session
+    pub fn drop_session(&mut self, window_label: &str, id: &str) -> bool {
+        self.sessions.remove(&documents::document_id(window_label, Some(id))).is_some()
+    }
+
+    /// Drop the memory of every document and session in a window
+    pub fn remove_window(&mut self, window_label: &str) {
+        self.documents.remove_window(window_label);
+        self.sessions.remove_window(window_label);
+    }
+}
+
+/// Managed state holding the voice leading memory, so each open progression leads from its own previous chord
+pub struct VoicingSessionState(pub Mutex<VoicingSessions>);
+
+/// Run an action on a document's audio engine, starting the engine on first use
+fn with_engine<T>(
+    state: &AudioState,
+    document: &str,
+    action: impl FnOnce(&AudioEngineHandle) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut engines = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let engine = engines.get_or_try_insert_with(document, AudioEngineHandle::new)?;
+    action(engine)
+}
+
+/// Initialize audio engine (lazy initialization on first play if not called)
+#[tauri::command]
+pub fn init_audio(
+    window: Window,
+    state: State<'_, AudioState>,
+    document_id: Option<String>,
+) -> Result<bool, String> {
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    with_engine(&state, &document, |_| Ok(true))
 }
 
 /// Play a chord with voice leading
 /// Set is_final to true for the last chord of a progression (applies fade-out)
+/// Returns the voiced notes so the UI can show held common tones
+/// voicing_preset picks the range and voice count ("piano", "guitar-friendly", "satb", "kids-keyboard");
+/// the default is the piano
+/// session_id leads from a session made by create_voicing_session instead of the document's previous chord
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn play_chord(
+    window: Window,
     state: State<'_, AudioState>,
+    sessions: State<'_, VoicingSessionState>,
+    document_id: Option<String>,
+    session_id: Option<String>,
     chord: String,
     voicing_style: String,
     base_octave: i8,
     is_final: bool,
-) -> Result<(), String> {
+    voicing_preset: Option<String>,
+) -> Result<Vec<AudioNote>, String> {
     // Validate input
     if chord.is_empty() {
         return Err("Chord cannot be empty".to_string());
     }
+    let config = match voicing_preset.as_deref() {
+        Some(name) => VoicingConfig::preset(name).ok_or_else(|| format!("Unknown voicing preset: {}", name))?,
+        None => VoicingConfig::default(),
+    };
 
     // Get notes from chord
     let notes = intervals::chord_to_notes(&chord)
@@ -52,107 +171,351 @@ pub fn play_chord(
     // Get bass note (first note of chord)
     let bass_note = notes.first().cloned().unwrap_or_default();
 
-    // Voice the chord based on style
-    let audio_notes = match voicing_style.as_str() {
-        "close" => voice_leading::voice_chord(&notes, &bass_note, base_octave, VoicingStyle::Close),
-        "wide" => voice_leading::voice_chord(&notes, &bass_note, base_octave, VoicingStyle::Wide),
-        "lead" | _ => voice_leading::voice_chord_with_leading(&notes, &bass_note, base_octave),
-    }
-    .map_err(|e| format!("Voice leading failed: {}", e))?;
+    // Voice the chord based on style, leading from the session's or this document's previous chord
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    let audio_notes = {
+        let mut sessions = sessions.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let session = match session_id {
+            Some(id) => {
+                sessions.session(window.label(), &id).ok_or_else(|| format!("Unknown voicing session: {}", id))?
+            }
+            None => sessions.document(&document),
+        };
+        voice_leading::voice_chord_by_style(session, &notes, &bass_note, base_octave, &voicing_style, &config)
+            .map_err(|e| format!("Voice leading failed: {}", e))?
+    };
 
     // Play the notes
-    play_notes_internal(state, audio_notes, is_final)
+    play_notes_internal(&state, &document, audio_notes.clone(), is_final)?;
+
+    Ok(audio_notes)
 }
 
 /// Play raw notes (for direct note playback)
 /// Set is_final to true for the last chord of a progression (applies fade-out)
 #[tauri::command]
 pub fn play_notes(
+    window: Window,
     state: State<'_, AudioState>,
+    document_id: Option<String>,
     notes: Vec<AudioNote>,
     is_final: bool,
 ) -> Result<(), String> {
-    play_notes_internal(state, notes, is_final)
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    play_notes_internal(&state, &document, notes, is_final)
 }
 
-/// Internal helper to play notes
-fn play_notes_internal(
+/// Play voiced notes one at a time (e.g. the notes returned by play_chord)
+/// step_ms is the delay between the start of one note and the next
+#[tauri::command]
+pub fn play_arpeggio(
+    window: Window,
     state: State<'_, AudioState>,
+    document_id: Option<String>,
     notes: Vec<AudioNote>,
-    is_final: bool,
+    direction: ArpeggioDirection,
+    step_ms: u64,
 ) -> Result<(), String> {
-    let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
-
-    // Lazy initialize if needed
-    if guard.is_none() {
-        *guard = Some(AudioEngineHandle::new()?);
+    if notes.is_empty() {
+        return Err("Arpeggio has no notes".to_string());
     }
-
-    if let Some(ref engine) = *guard {
-        engine.play_notes(notes, is_final)?;
+    let step = Duration::from_millis(step_ms);
+    if step.is_zero() || step > MAX_ARPEGGIO_STEP {
+        return Err(format!("Invalid arpeggio step: {} ms", step_ms));
     }
 
-    Ok(())
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    with_engine(&state, &document, |engine| engine.play_arpeggio(notes, direction, step))
 }
 
-/// Stop all currently playing audio
+/// Internal helper to play notes on a document's engine
+pub(crate) fn play_notes_internal(
+    state: &AudioState,
+    document: &str,
+    notes: Vec<AudioNote>,
+    is_final: bool,
+) -> Result<(), String> {
+    with_engine(state, document, |engine| engine.play_notes(notes, is_final))
+}
+
+/// Stop all audio currently playing for a document
 #[tauri::command]
 pub fn stop_audio(
+    window: Window,
     state: State<'_, AudioState>,
+    document_id: Option<String>,
     immediate: bool,
 ) -> Result<(), String> {
-    let guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    let engines = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
 
-    if let Some(ref engine) = *guard {
+    if let Some(engine) = engines.get(&document) {
         engine.stop(immediate)?;
     }
 
     Ok(())
 }
 
-/// Set master volume (0.0 to 1.0)
+/// Set a document's playback volume (0.0 to 1.0)
 #[tauri::command]
 pub fn set_volume(
+    window: Window,
     state: State<'_, AudioState>,
+    document_id: Option<String>,
     volume: f32,
 ) -> Result<(), String> {
-    let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    with_engine(&state, &document, |engine| engine.set_volume(volume))
+}
 
-    // Lazy initialize if needed
-    if guard.is_none() {
-        *guard = Some(AudioEngineHandle::new()?);
-    }
+/// Reset a document's voice leading state (for starting new progression)
+#[tauri::command]
+pub fn reset_voicing(
+    window: Window,
+    sessions: State<'_, VoicingSessionState>,
+    document_id: Option<String>,
+) -> Result<(), String> {
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    sessions.0.lock().map_err(|e| format!("Lock error: {}", e))?.remove_document(&document);
+    Ok(())
+}
 
-    if let Some(ref engine) = *guard {
-        engine.set_volume(volume)?;
-    }
+/// Start a voicing session for one progression, returning its ID for play_chord
+/// Sessions last until dropped or their window closes
+#[tauri::command]
+pub fn create_voicing_session(window: Window, sessions: State<'_, VoicingSessionState>) -> Result<String, String> {
+    let mut sessions = sessions.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(sessions.create_session(window.label()))
+}
 
-    Ok(())
+/// Forget a voicing session's previous chord; returns false if there is no such session
+#[tauri::command]
+pub fn reset_voicing_session(
+    window: Window,
+    sessions: State<'_, VoicingSessionState>,
+    session_id: String,
+) -> Result<bool, String> {
+    let mut sessions = sessions.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(sessions.reset_session(window.label(), &session_id))
 }
 
-/// Reset voice leading state (for starting new progression)
+/// End a voicing session; returns false if there is no such session
 #[tauri::command]
-pub fn reset_voicing() -> Result<(), String> {
-    voice_leading::reset_voicing();
-    Ok(())
+pub fn drop_voicing_session(
+    window: Window,
+    sessions: State<'_, VoicingSessionState>,
+    session_id: String,
+) -> Result<bool, String> {
+    let mut sessions = sessions.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(sessions.drop_session(window.label(), &session_id))
 }
 
 /// Play a one-shot sound effect by name (e.g., "swoosh")
 #[tauri::command]
 pub fn play_one_shot(
+    window: Window,
     state: State<'_, AudioState>,
+    document_id: Option<String>,
     sample_name: String,
 ) -> Result<(), String> {
-    let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    with_engine(&state, &document, |engine| engine.play_one_shot(&sample_name))
+}
+
+/// Tempo map of a sequence request: a tempo and meter (4/4 by default) with changes at measures
+pub(crate) fn sequence_tempo(
+    chords: &[SequenceChordRequest],
+    bpm: f32,
+    time_signature: Option<&str>,
+    changes: &[MeasureChange],
+) -> Result<TempoMap, String> {
+    let time_signature = time_signature.unwrap_or(DEFAULT_TIME_SIGNATURE);
+    let mut tempo = TempoMap::new(bpm, time_signature).map_err(|e| format!("Invalid tempo: {}", e))?;
+    let total_beats = chords.iter().map(|request| request.beats as f64).sum();
+    tempo
+        .add_measure_changes(0.0, changes, total_beats)
+        .map_err(|e| format!("Invalid tempo change: {}", e))?;
+    Ok(tempo)
+}
 
-    // Lazy initialize if needed
-    if guard.is_none() {
-        *guard = Some(AudioEngineHandle::new()?);
+/// Voice chords of (symbol, beats) and schedule them on a document's engine
+/// Emits SEQUENCE_EVENT to the window as each chord starts and when the sequence ends
+/// Returns the voiced notes of every chord
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn start_sequence(
+    window: &Window,
+    state: &AudioState,
+    sessions: &VoicingSessionState,
+    document_id: Option<String>,
+    chords: &[(String, f32)],
+    tempo: TempoMap,
+    voicing_style: &str,
+    base_octave: i8,
+) -> Result<Vec<Vec<AudioNote>>, String> {
+    if chords.is_empty() {
+        return Err("Sequence has no chords".to_string());
+    }
+    if let Some((chord, beats)) = chords.iter().find(|(_, beats)| !(*beats > 0.0 && beats.is_finite())) {
+        return Err(format!("Invalid length for {}: {} beats", chord, beats));
     }
 
-    if let Some(ref engine) = *guard {
-        engine.play_one_shot(&sample_name)?;
+    // Voice each chord leading from the previous one, as play_chord does
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    let mut sequence = Vec::with_capacity(chords.len());
+    let config = VoicingConfig::default();
+    {
+        let mut sessions = sessions.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let session = sessions.document(&document);
+        for (chord, beats) in chords {
+            let notes = intervals::chord_to_notes(chord)
+                .map_err(|e| format!("Failed to parse chord: {}", e))?;
+            let bass_note = notes.first().cloned().unwrap_or_default();
+            let audio_notes =
+                voice_leading::voice_chord_by_style(session, &notes, &bass_note, base_octave, voicing_style, &config)
+                    .map_err(|e| format!("Voice leading failed: {}", e))?;
+            sequence.push(SequenceChord { notes: audio_notes, beats: *beats });
+        }
     }
+    let voiced = sequence.iter().map(|chord| chord.notes.clone()).collect();
 
-    Ok(())
+    let target = window.clone();
+    let listener = Box::new(move |event| {
+        let payload = SequenceProgress { document_id: document_id.clone(), event };
+        if let Err(e) = target.emit_to(target.label(), SEQUENCE_EVENT, &payload) {
+            eprintln!("Failed to emit sequence event: {}", e);
+        }
+    });
+    with_engine(state, &document, |engine| engine.play_sequence(sequence, tempo, listener))?;
+
+    Ok(voiced)
+}
+
+/// Voice and play a progression on the audio thread with sample-accurate chord timing
+/// Tempo and meter can change at given measures; a running metronome follows them while the sequence plays
+/// Emits SEQUENCE_EVENT to the calling window as each chord starts and when the sequence ends
+/// Returns the voiced notes of every chord
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn play_sequence(
+    window: Window,
+    state: State<'_, AudioState>,
+    sessions: State<'_, VoicingSessionState>,
+    document_id: Option<String>,
+    chords: Vec<SequenceChordRequest>,
+    bpm: f32,
+    time_signature: Option<String>,
+    changes: Option<Vec<MeasureChange>>,
+    voicing_style: String,
+    base_octave: i8,
+) -> Result<Vec<Vec<AudioNote>>, String> {
+    let tempo = sequence_tempo(&chords, bpm, time_signature.as_deref(), &changes.unwrap_or_default())?;
+    let chords: Vec<(String, f32)> = chords.into_iter().map(|request| (request.chord, request.beats)).collect();
+    start_sequence(&window, &state, &sessions, document_id, &chords, tempo, &voicing_style, base_octave)
+}
+
+/// Pause a document's playing sequence
+#[tauri::command]
+pub fn pause_sequence(window: Window, state: State<'_, AudioState>, document_id: Option<String>) -> Result<(), String> {
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    with_engine(&state, &document, |engine| engine.pause_sequence())
+}
+
+/// Resume a document's paused sequence
+#[tauri::command]
+pub fn resume_sequence(window: Window, state: State<'_, AudioState>, document_id: Option<String>) -> Result<(), String> {
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    with_engine(&state, &document, |engine| engine.resume_sequence())
+}
+
+/// Jump to a chord (by index) of a document's sequence, playing or paused
+#[tauri::command]
+pub fn seek_sequence(
+    window: Window,
+    state: State<'_, AudioState>,
+    document_id: Option<String>,
+    index: usize,
+) -> Result<(), String> {
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    with_engine(&state, &document, |engine| engine.seek_sequence(index))
+}
+
+/// Start a document's metronome (replacing a running one), mixed in with chord playback
+/// Emits METRONOME_BEAT_EVENT to the calling window on every beat
+#[tauri::command]
+pub fn start_metronome(
+    window: Window,
+    state: State<'_, AudioState>,
+    document_id: Option<String>,
+    settings: Option<MetronomeSettings>,
+) -> Result<(), String> {
+    let pattern = settings.unwrap_or_default().resolve()?;
+    let document = documents::document_id(window.label(), document_id.as_deref());
+
+    let target = window.clone();
+    let listener = Box::new(move |beat| {
+        let payload = MetronomeBeatEvent { document_id: document_id.clone(), beat };
+        if let Err(e) = target.emit_to(target.label(), METRONOME_BEAT_EVENT, &payload) {
+            eprintln!("Failed to emit metronome beat: {}", e);
+        }
+    });
+    with_engine(&state, &document, |engine| engine.start_metronome(pattern, listener))
+}
+
+/// Change a running metronome's tempo, time signature, accents, subdivision or level
+#[tauri::command]
+pub fn set_metronome(
+    window: Window,
+    state: State<'_, AudioState>,
+    document_id: Option<String>,
+    settings: MetronomeSettings,
+) -> Result<(), String> {
+    let pattern = settings.resolve()?;
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    with_engine(&state, &document, |engine| engine.set_metronome(pattern))
+}
+
+/// Stop a document's metronome
+#[tauri::command]
+pub fn stop_metronome(window: Window, state: State<'_, AudioState>, document_id: Option<String>) -> Result<(), String> {
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    with_engine(&state, &document, |engine| engine.stop_metronome())
+}
+
+/// Estimate a rough chord progression with timestamps from an audio recording (experimental)
+/// Supports WAV, MP3, OGG, and FLAC; only major and minor triads are detected
+#[tauri::command]
+pub async fn analyze_audio_file(path: String) -> Result<AudioAnalysis, String> {
+    analyze_file(&path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sessions_and_documents_are_kept_apart() {
+        let mut voicings = VoicingSessions::default();
+        let id = voicings.create_session("main");
+        assert!(voicings.session("main", &id).is_some());
+        // Sessions belong to the window that created them
+        assert!(voicings.session("other", &id).is_none());
+
+        // A document's ID is not a session, even where the keys would coincide
+        voicings.document("main/canvas-2");
+        assert!(voicings.session("main", "canvas-2").is_none());
+        assert!(!voicings.reset_session("main", "canvas-2"));
+        assert!(!voicings.drop_session("main", "canvas-2"));
+        assert!(voicings.documents.get("main/canvas-2").is_some());
+
+        // Nor does forgetting a document touch a session
+        voicings.remove_document(&documents::document_id("main", Some(&id)));
+        assert!(voicings.reset_session("main", &id));
+        assert!(voicings.drop_session("main", &id));
+        assert!(!voicings.drop_session("main", &id));
+
+        let id = voicings.create_session("main");
+        voicings.remove_window("main");
+        assert!(voicings.session("main", &id).is_none());
+        assert!(voicings.documents.get("main/canvas-2").is_none());
+    }
 }
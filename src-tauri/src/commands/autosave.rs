@@ -0,0 +1,50 @@
+// Autosave commands
+// The UI pushes its latest project state; a background task writes it out and a crashed session can be recovered
+
+use std::sync::Mutex;
+use std::thread;
+use tauri::{AppHandle, Manager, State};
+
+use crate::autosave::{Autosave, RecoveredSession, AUTOSAVE_INTERVAL};
+use crate::types::project::Project;
+
+/// Managed state wrapper for the session's autosave
+pub struct AutosaveState(pub Mutex<Autosave>);
+
+/// Write the latest pushed snapshot every AUTOSAVE_INTERVAL for the life of the app
+pub fn spawn_autosave_task(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(AUTOSAVE_INTERVAL);
+        let state = app.state::<AutosaveState>();
+        let Ok(mut autosave) = state.0.lock() else {
+            return;
+        };
+        if let Err(e) = autosave.flush() {
+            println!("[autosave] {}", e);
+        }
+    });
+}
+
+/// Clear the autosave on a clean exit so the next launch doesn't offer recovery
+pub fn finish_autosave(app: &AppHandle) {
+    if let Ok(mut autosave) = app.state::<AutosaveState>().0.lock() {
+        if let Err(e) = autosave.finish() {
+            println!("[autosave] {}", e);
+        }
+    }
+}
+
+/// Replace the project snapshot the next autosave writes
+#[tauri::command]
+pub fn update_autosave_state(state: State<'_, AutosaveState>, project: Project) -> Result<(), String> {
+    let mut autosave = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    autosave.update(project);
+    Ok(())
+}
+
+/// The last autosaved snapshot if the previous session crashed, None after a clean exit
+#[tauri::command]
+pub fn recover_last_session(state: State<'_, AutosaveState>) -> Result<Option<RecoveredSession>, String> {
+    let autosave = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(autosave.recovered().cloned())
+}
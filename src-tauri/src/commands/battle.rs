@@ -0,0 +1,139 @@
+// Two-player chord battle commands
+// Both players answer the same question, by text or on their own MIDI keyboard (or half of a shared one)
+
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{Emitter, Manager, State, Window};
+
+use super::audio::AudioState;
+use super::quiz::play_quiz_chord;
+use crate::midi::{self, MidiInputConnection};
+use crate::training::battle::{
+    BattleAnswerResult, BattleGame, BattleInput, BattleKeyMapping, BattleSummary, Player,
+};
+use crate::training::quiz::{QuizConfig, QuizQuestion};
+
+/// Event emitted to the window when a MIDI answer is graded (payload: BattleAnswerResult)
+pub const BATTLE_ANSWER_EVENT: &str = "battle-answer";
+
+/// Managed state wrapper for the running battle (None when no game is active)
+pub struct BattleState(pub Mutex<Option<BattleGame>>);
+
+/// Managed state wrapper for the MIDI inputs answering the battle
+pub struct BattleMidiState(pub Mutex<Vec<MidiInputConnection>>);
+
+/// Start a new battle (replaces any running one)
+#[tauri::command]
+pub fn start_battle(battle: State<'_, BattleState>, config: Option<QuizConfig>) -> Result<(), String> {
+    let game = BattleGame::new(config.unwrap_or_default())?;
+    let mut guard = battle.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    *guard = Some(game);
+    Ok(())
+}
+
+/// Pick and play the chord both players answer next
+#[tauri::command]
+pub fn next_battle_question(
+    window: Window,
+    audio: State<'_, AudioState>,
+    battle: State<'_, BattleState>,
+) -> Result<QuizQuestion, String> {
+    let (question, notes) = {
+        let mut guard = battle.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let game = guard.as_mut().ok_or("No battle is running")?;
+        game.next_question(&mut rand::thread_rng())?
+    };
+
+    play_quiz_chord(&window, &audio, &notes)?;
+    Ok(question)
+}
+
+/// Play the current battle chord again
+#[tauri::command]
+pub fn replay_battle_question(
+    window: Window,
+    audio: State<'_, AudioState>,
+    battle: State<'_, BattleState>,
+) -> Result<(), String> {
+    let notes = {
+        let guard = battle.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let game = guard.as_ref().ok_or("No battle is running")?;
+        game.current_notes().ok_or("No question is waiting for an answer")?
+    };
+
+    play_quiz_chord(&window, &audio, &notes)
+}
+
+/// Submit a player's typed answer (e.g. from their half of the computer keyboard)
+#[tauri::command]
+pub fn submit_battle_answer(
+    battle: State<'_, BattleState>,
+    player: Player,
+    question_id: String,
+    answer: String,
+) -> Result<BattleAnswerResult, String> {
+    let mut guard = battle.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let game = guard.as_mut().ok_or("No battle is running")?;
+    game.answer_at(player, &question_id, &answer, Instant::now())
+}
+
+/// Open a MIDI source answering for one player, or for both on either side of a split
+/// The first chord a player plays for a question is their answer, graded and sent as a battle-answer event
+#[tauri::command]
+pub fn open_battle_midi_input(
+    window: Window,
+    state: State<'_, BattleMidiState>,
+    input_id: String,
+    mapping: BattleKeyMapping,
+) -> Result<(), String> {
+    let target = window.clone();
+    let mut input = BattleInput::new(mapping);
+
+    let connection = midi::open_input(&input_id, move |message| {
+        // Stamp the answer before waiting on the lock, so the race is decided by the keyboard
+        let now = Instant::now();
+        for (player, chord) in input.apply(message) {
+            let state = target.state::<BattleState>();
+            let Ok(mut guard) = state.0.lock() else {
+                return;
+            };
+            let Some(game) = guard.as_mut() else {
+                return;
+            };
+            let Some(question_id) = game.current_question_id().map(str::to_string) else {
+                continue;
+            };
+            // Chords played after the player has answered are ignored
+            let Ok(result) = game.answer_at(player, &question_id, &chord, now) else {
+                continue;
+            };
+            if let Err(e) = target.emit_to(target.label(), BATTLE_ANSWER_EVENT, &result) {
+                eprintln!("Failed to emit battle answer: {}", e);
+            }
+        }
+    })?;
+
+    let mut inputs = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    inputs.push(connection);
+    Ok(())
+}
+
+/// Close every MIDI input answering the battle
+#[tauri::command]
+pub fn close_battle_midi_inputs(state: State<'_, BattleMidiState>) -> Result<(), String> {
+    let mut inputs = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    inputs.clear();
+    Ok(())
+}
+
+/// End the running battle and report both players' totals and the winner
+#[tauri::command]
+pub fn finish_battle(battle: State<'_, BattleState>) -> Result<BattleSummary, String> {
+    let game = battle
+        .0
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?
+        .take()
+        .ok_or("No battle is running")?;
+    Ok(game.summary())
+}
@@ -0,0 +1,169 @@
+// Curriculum pack commands
+// Export lessons to a shareable .maestropack and import packs from other teachers
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_dialog::{DialogExt, FilePath};
+use uuid::Uuid;
+
+use super::policy::{CommandError, PolicyState};
+use crate::curriculum::{CurriculumPack, LessonContent, PackProgression, PACK_EXTENSION};
+use crate::settings::Feature;
+use crate::types::worksheet::WorksheetConfig;
+
+/// Directory imported packs are extracted to, inside the app data directory
+const CURRICULUM_DIR: &str = "curriculum";
+
+/// Lesson as assembled in the UI
+#[derive(Debug, Clone, Deserialize)]
+pub struct LessonDraft {
+    pub title: String,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub worksheets: Vec<WorksheetConfig>,
+    #[serde(default)]
+    pub progressions: Vec<PackProgression>,
+    /// Paths of audio files on disk to bundle
+    #[serde(default)]
+    pub audio_files: Vec<String>,
+}
+
+/// Pack contents as assembled in the UI, lessons in teaching order
+#[derive(Debug, Clone, Deserialize)]
+pub struct PackDraft {
+    pub title: String,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub lessons: Vec<LessonDraft>,
+}
+
+/// Lesson of an imported pack; audio paths point at the extracted files
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedLesson {
+    pub title: String,
+    pub text: String,
+    pub worksheets: Vec<WorksheetConfig>,
+    pub progressions: Vec<PackProgression>,
+    pub audio: Vec<String>,
+}
+
+/// An imported pack ready to load into the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedPack {
+    pub id: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub lessons: Vec<ImportedLesson>,
+}
+
+/// Build a pack from a draft, reading the referenced audio files
+fn build_pack(draft: PackDraft) -> Result<CurriculumPack, String> {
+    let mut pack = CurriculumPack::new(&draft.title, draft.author, draft.description);
+    for lesson in draft.lessons {
+        let audio = lesson
+            .audio_files
+            .iter()
+            .map(|path| {
+                fs::read(path)
+                    .map(|data| (path.clone(), data))
+                    .map_err(|e| format!("Failed to read audio file {}: {}", path, e))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        pack.add_lesson(LessonContent {
+            title: lesson.title,
+            text: lesson.text,
+            worksheets: lesson.worksheets,
+            progressions: lesson.progressions,
+            audio,
+        })?;
+    }
+    Ok(pack)
+}
+
+/// Export lessons as a .maestropack through a save dialog
+/// Returns false if the user cancelled
+#[tauri::command]
+pub async fn export_curriculum_pack(
+    app: AppHandle,
+    policy: State<'_, PolicyState>,
+    draft: PackDraft,
+) -> Result<bool, CommandError> {
+    policy.check(Feature::Export)?;
+
+    let file_name = format!("{}.{}", draft.title, PACK_EXTENSION);
+    let bytes = build_pack(draft)?.to_bytes()?;
+
+    let file_path = app
+        .dialog()
+        .file()
+        .add_filter("Maestro Curriculum Pack", &[PACK_EXTENSION])
+        .set_file_name(file_name)
+        .set_title("Export Curriculum Pack")
+        .blocking_save_file();
+
+    let path = match file_path {
+        Some(FilePath::Path(p)) => p,
+        Some(_) => return Err("Invalid file path".into()),
+        None => return Ok(false), // User cancelled
+    };
+
+    fs::write(&path, bytes).map_err(|e| format!("Failed to write curriculum pack: {}", e))?;
+    Ok(true)
+}
+
+/// Import and validate a .maestropack, extracting its audio into the app data directory
+#[tauri::command]
+pub fn import_curriculum_pack(app: AppHandle, path: String) -> Result<ImportedPack, String> {
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read curriculum pack: {}", e))?;
+    let pack = CurriculumPack::from_bytes(&bytes)?;
+    let manifest = &pack.manifest;
+
+    // The id names the extraction directory, so it must not be able to escape it
+    Uuid::parse_str(&manifest.id).map_err(|_| format!("Invalid pack id: {}", manifest.id))?;
+    let pack_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?
+        .join(CURRICULUM_DIR)
+        .join(&manifest.id);
+
+    let mut lessons = Vec::new();
+    for lesson in &manifest.lessons {
+        let worksheets = lesson
+            .worksheets
+            .iter()
+            .map(|p| pack.worksheet(p))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let mut audio = Vec::new();
+        for asset in &lesson.audio {
+            let target = pack_dir.join(asset);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("Failed to create pack directory: {}", e))?;
+            }
+            let data = pack.file(asset).ok_or_else(|| format!("Missing audio in pack: {}", asset))?;
+            fs::write(&target, data).map_err(|e| format!("Failed to extract {}: {}", asset, e))?;
+            audio.push(target.to_string_lossy().to_string());
+        }
+
+        lessons.push(ImportedLesson {
+            title: lesson.title.clone(),
+            text: lesson.text.clone(),
+            worksheets,
+            progressions: lesson.progressions.clone(),
+            audio,
+        });
+    }
+
+    Ok(ImportedPack {
+        id: manifest.id.clone(),
+        title: manifest.title.clone(),
+        author: manifest.author.clone(),
+        description: manifest.description.clone(),
+        lessons,
+    })
+}
@@ -0,0 +1,312 @@
+// Installation diagnostics
+// Checks the resources the app depends on, for the diagnostics screen and support requests,
+// and bundles them with settings and recent logs into a zip for bug reports
+
+use rodio::Decoder;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_dialog::{DialogExt, FilePath, MessageDialogButtons};
+use usvg::fontdb;
+
+use super::export::bravura_font_paths;
+use super::policy::{CommandError, PolicyState};
+use super::settings::SettingsState;
+use super::worksheet::build_lilypond_document;
+use crate::audio::embedded_samples;
+use crate::curriculum::write_archive;
+use crate::lilypond::{find_lilypond, lilypond_version, recent_lilypond_logs};
+use crate::settings::{Feature, Settings, SettingsStore, SETTINGS_FILE_NAME};
+use crate::types::versioned;
+use crate::types::worksheet::WorksheetConfig;
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    /// Something is missing that the app can work around (e.g. no LilyPond, but native rendering)
+    Warning,
+    Failed,
+}
+
+/// One resource checked
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallationCheck {
+    /// "samples", "font", "lilypond", "settings"
+    pub name: &'static str,
+    pub status: CheckStatus,
+    /// What was found, or what is wrong
+    pub detail: String,
+}
+
+/// Result of verify_installation
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallationReport {
+    pub version: String,
+    pub os: &'static str,
+    pub checks: Vec<InstallationCheck>,
+    /// No check failed
+    pub healthy: bool,
+}
+
+fn check(name: &'static str, status: CheckStatus, detail: String) -> InstallationCheck {
+    InstallationCheck { name, status, detail }
+}
+
+/// Every sample is embedded and decodes
+fn check_samples<'a>(samples: impl Iterator<Item = (&'a str, &'a [u8])>) -> InstallationCheck {
+    let mut count = 0;
+    let mut broken: Vec<&str> = Vec::new();
+    for (key, bytes) in samples {
+        count += 1;
+        if Decoder::new(Cursor::new(bytes.to_vec())).is_err() {
+            broken.push(key);
+        }
+    }
+    broken.sort_unstable();
+
+    match (count, broken.is_empty()) {
+        (0, _) => check("samples", CheckStatus::Failed, "No audio samples are embedded".to_string()),
+        (_, true) => check("samples", CheckStatus::Ok, format!("{} samples decode", count)),
+        (_, false) => {
+            let detail = format!("{} of {} samples fail to decode: {}", broken.len(), count, broken.join(", "));
+            check("samples", CheckStatus::Failed, detail)
+        }
+    }
+}
+
+/// The Bravura font is found and loads as a font
+fn check_font(paths: &[PathBuf]) -> InstallationCheck {
+    let Some(path) = paths.iter().find(|path| path.is_file()) else {
+        let searched: Vec<String> = paths.iter().map(|path| path.display().to_string()).collect();
+        return check("font", CheckStatus::Failed, format!("Bravura not found in: {}", searched.join(", ")));
+    };
+
+    let mut db = fontdb::Database::new();
+    match db.load_font_file(path) {
+        Ok(()) if db.faces().any(|face| face.families.iter().any(|(family, _)| family == "Bravura")) => {
+            check("font", CheckStatus::Ok, format!("Bravura loaded from {}", path.display()))
+        }
+        Ok(()) => check("font", CheckStatus::Failed, format!("{} is not the Bravura font", path.display())),
+        Err(e) => check("font", CheckStatus::Failed, format!("Failed to load {}: {}", path.display(), e)),
+    }
+}
+
+/// LilyPond is on the PATH and runs; without it only natively drawn worksheets render
+fn check_lilypond() -> InstallationCheck {
+    let Some(path) = find_lilypond() else {
+        let detail = "LilyPond not found on the PATH; only note and chord worksheets can be rendered".to_string();
+        return check("lilypond", CheckStatus::Warning, detail);
+    };
+    match lilypond_version() {
+        Ok(version) => check("lilypond", CheckStatus::Ok, format!("{} at {}", version, path.display())),
+        Err(e) => check("lilypond", CheckStatus::Failed, format!("{} does not run: {}", path.display(), e)),
+    }
+}
+
+/// The settings file, if there is one yet, can be read back
+fn check_settings(path: &Path) -> InstallationCheck {
+    match SettingsStore::verify(path) {
+        Ok(true) => check("settings", CheckStatus::Ok, format!("{} is readable", path.display())),
+        Ok(false) => check("settings", CheckStatus::Ok, "No settings saved yet".to_string()),
+        Err(e) => check("settings", CheckStatus::Failed, format!("{}; defaults are in use", e)),
+    }
+}
+
+/// Check embedded samples, the Bravura font, LilyPond and the settings file
+#[tauri::command]
+pub async fn verify_installation(app: tauri::AppHandle) -> Result<InstallationReport, String> {
+    installation_report(&app).await
+}
+
+async fn installation_report(app: &AppHandle) -> Result<InstallationReport, String> {
+    let settings_path = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve config directory: {}", e))?
+        .join(SETTINGS_FILE_NAME);
+    let font_paths = bravura_font_paths(app);
+
+    // Decoding every sample and starting LilyPond take a moment
+    let checks = tauri::async_runtime::spawn_blocking(move || {
+        vec![
+            check_samples(embedded_samples()),
+            check_font(&font_paths),
+            check_lilypond(),
+            check_settings(&settings_path),
+        ]
+    })
+    .await
+    .map_err(|e| format!("Diagnostics task failed: {}", e))?;
+
+    Ok(InstallationReport {
+        version: app.package_info().version.to_string(),
+        os: std::env::consts::OS,
+        healthy: checks.iter().all(|check| check.status != CheckStatus::Failed),
+        checks,
+    })
+}
+
+/// Replace the user's home directory with "~", and their user name wherever it is a whole path component,
+/// so a bundle doesn't give away who sent it
+fn redact_paths(text: &str, home: Option<&Path>) -> String {
+    let Some(home) = home.map(|home| home.to_string_lossy().to_string()).filter(|home| home.len() > 1) else {
+        return text.to_string();
+    };
+    let mut redacted = text.replace(&home, "~").replace(&home.replace('\\', "/"), "~");
+    let user = home.rsplit(['/', '\\']).next().unwrap_or_default();
+    if !user.is_empty() {
+        for separator in ['/', '\\'] {
+            redacted = redacted.replace(&format!("{0}{1}{0}", separator, user), &format!("{0}<user>{0}", separator));
+        }
+    }
+    redacted
+}
+
+/// Files of a support bundle by path, every text redacted
+fn support_bundle_entries(
+    report: &InstallationReport,
+    settings: &Settings,
+    logs: &[String],
+    failing_config: Option<&WorksheetConfig>,
+    home: Option<&Path>,
+) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let json =
+        |value: Result<String, serde_json::Error>| value.map_err(|e| format!("Failed to serialize bundle: {}", e));
+    let mut texts = BTreeMap::new();
+    texts.insert("installation.json".to_string(), json(serde_json::to_string_pretty(report))?);
+    texts.insert("settings.json".to_string(), json(serde_json::to_string_pretty(settings))?);
+    for (index, log) in logs.iter().enumerate() {
+        texts.insert(format!("logs/lilypond-{:02}.log", index + 1), log.clone());
+    }
+    if let Some(config) = failing_config {
+        texts.insert("worksheet.json".to_string(), versioned::to_string_pretty(config)?);
+        match build_lilypond_document(config) {
+            Ok(source) => texts.insert("worksheet.ly".to_string(), source),
+            Err(e) => texts.insert("worksheet-error.txt".to_string(), e),
+        };
+    }
+    Ok(texts.into_iter().map(|(path, text)| (path, redact_paths(&text, home).into_bytes())).collect())
+}
+
+/// What a bundle holds, as listed in the confirmation ("a, b and c")
+fn bundle_contents(with_worksheet: bool) -> String {
+    let mut contents = vec!["installation check results", "your settings", "recent LilyPond logs"];
+    if with_worksheet {
+        contents.push("the worksheet that failed with its LilyPond source");
+    }
+    let last = contents.pop().unwrap_or_default();
+    format!("{} and {}", contents.join(", "), last)
+}
+
+/// Zip installation checks, settings, recent LilyPond logs and optionally the failing worksheet for a bug report
+/// The user confirms what goes in before picking where to save it; paths in their home directory are redacted
+/// Returns the path written, or None when either dialog is cancelled
+#[tauri::command]
+pub async fn create_support_bundle(
+    app: AppHandle,
+    policy: State<'_, PolicyState>,
+    settings: State<'_, SettingsState>,
+    failing_config: Option<WorksheetConfig>,
+) -> Result<Option<String>, CommandError> {
+    policy.check(Feature::Export)?;
+
+    let message = format!(
+        "The support bundle will include {}. Paths in your home folder are removed.",
+        bundle_contents(failing_config.is_some())
+    );
+    let confirmed = app
+        .dialog()
+        .message(message)
+        .title("Create Support Bundle")
+        .buttons(MessageDialogButtons::OkCancelCustom("Continue".to_string(), "Cancel".to_string()))
+        .blocking_show();
+    if !confirmed {
+        return Ok(None);
+    }
+
+    let file_path = app
+        .dialog()
+        .file()
+        .add_filter("Zip Archive", &["zip"])
+        .set_file_name("maestro-support.zip")
+        .set_title("Save Support Bundle")
+        .blocking_save_file();
+    let path = match file_path {
+        Some(FilePath::Path(p)) => p,
+        Some(_) => return Err("Invalid file path".into()),
+        None => return Ok(None), // User cancelled
+    };
+
+    let settings = settings.0.lock().map_err(|e| format!("Lock error: {}", e))?.settings().clone();
+    let report = installation_report(&app).await?;
+    let home = app.path().home_dir().ok();
+    let entries =
+        support_bundle_entries(&report, &settings, &recent_lilypond_logs(), failing_config.as_ref(), home.as_deref())?;
+
+    fs::write(&path, write_archive(&entries)?).map_err(|e| format!("Failed to write support bundle: {}", e))?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::encode_wav;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_check_samples() {
+        let wav = encode_wav(&[0.0; 64], 1, 44100);
+        let samples = [("C4", wav.as_slice()), ("Cs4", &b"not audio"[..]), ("D4", wav.as_slice())];
+        let result = check_samples(samples.into_iter());
+        assert_eq!(result.status, CheckStatus::Failed);
+        assert_eq!(result.detail, "1 of 3 samples fail to decode: Cs4");
+
+        assert_eq!(check_samples([("C4", wav.as_slice())].into_iter()).status, CheckStatus::Ok);
+        assert_eq!(check_samples(std::iter::empty()).status, CheckStatus::Failed);
+    }
+
+    #[test]
+    fn test_check_font_and_settings() {
+        let dir = TempDir::new().unwrap();
+        let font = dir.path().join("Bravura.otf");
+        assert_eq!(check_font(std::slice::from_ref(&font)).status, CheckStatus::Failed);
+        fs::write(&font, b"not a font").unwrap();
+        assert_eq!(check_font(&[font]).status, CheckStatus::Failed);
+
+        let settings = dir.path().join(SETTINGS_FILE_NAME);
+        assert_eq!(check_settings(&settings).status, CheckStatus::Ok);
+        fs::write(&settings, "{ \"simplify_spellings\": true }").unwrap();
+        assert_eq!(check_settings(&settings).status, CheckStatus::Ok);
+        fs::write(&settings, "{ truncated").unwrap();
+        assert_eq!(check_settings(&settings).status, CheckStatus::Failed);
+    }
+
+    #[test]
+    fn test_redact_paths() {
+        let home = Path::new("/home/alice");
+        let text = "Reading /home/alice/scores/a.ly; cache in /var/alice/tmp; not alicer/";
+        assert_eq!(redact_paths(text, Some(home)), "Reading ~/scores/a.ly; cache in /var/<user>/tmp; not alicer/");
+        let windows = Path::new(r"C:\Users\bob");
+        assert_eq!(redact_paths(r"C:\Users\bob\x.ly, C:/Users/bob/y.ly", Some(windows)), r"~\x.ly, ~/y.ly");
+        assert_eq!(redact_paths("/home/alice", None), "/home/alice");
+    }
+
+    #[test]
+    fn test_support_bundle_entries() {
+        let report =
+            InstallationReport { version: "1.0.0".to_string(), os: "linux", checks: Vec::new(), healthy: true };
+        let logs = vec!["Failed: exit status: 1\n/home/alice/x.ly:3: error".to_string()];
+        let home = Path::new("/home/alice");
+        let entries = support_bundle_entries(&report, &Settings::default(), &logs, None, Some(home)).unwrap();
+
+        let paths: Vec<&str> = entries.keys().map(String::as_str).collect();
+        assert_eq!(paths, ["installation.json", "logs/lilypond-01.log", "settings.json"]);
+        assert_eq!(entries["logs/lilypond-01.log"], b"Failed: exit status: 1\n~/x.ly:3: error");
+        assert_eq!(bundle_contents(false), "installation check results, your settings and recent LilyPond logs");
+    }
+}
@@ -0,0 +1,52 @@
+// Document lifecycle commands
+// Releases per-document audio, voicing, analysis, preview and song state when a canvas or window closes
+
+use tauri::{AppHandle, Manager, State, Window};
+
+use super::analysis::AnalysisState;
+use super::audio::{AudioState, VoicingSessionState};
+use super::midi::MidiState;
+use super::preview::PreviewState;
+use super::song::SongState;
+use crate::documents;
+
+/// Release the state of one canvas inside the calling window
+#[tauri::command]
+pub fn close_document(
+    window: Window,
+    audio: State<'_, AudioState>,
+    voicings: State<'_, VoicingSessionState>,
+    analysis: State<'_, AnalysisState>,
+    preview: State<'_, PreviewState>,
+    songs: State<'_, SongState>,
+    document_id: String,
+) -> Result<(), String> {
+    let document = documents::document_id(window.label(), Some(&document_id));
+
+    audio.0.lock().map_err(|e| format!("Lock error: {}", e))?.remove(&document);
+    voicings.0.lock().map_err(|e| format!("Lock error: {}", e))?.remove_document(&document);
+    analysis.0.lock().map_err(|e| format!("Lock error: {}", e))?.remove(&document);
+    preview.remove(&document);
+    songs.0.lock().map_err(|e| format!("Lock error: {}", e))?.remove(&document);
+    Ok(())
+}
+
+/// Release the state of every document in a window (called when the window is destroyed)
+pub fn release_window_documents(app: &AppHandle, window_label: &str) {
+    if let Ok(mut engines) = app.state::<AudioState>().0.lock() {
+        engines.remove_window(window_label);
+    }
+    if let Ok(mut sessions) = app.state::<VoicingSessionState>().0.lock() {
+        sessions.remove_window(window_label);
+    }
+    if let Ok(mut analyzers) = app.state::<AnalysisState>().0.lock() {
+        analyzers.remove_window(window_label);
+    }
+    app.state::<PreviewState>().remove_window(window_label);
+    if let Ok(mut songs) = app.state::<SongState>().0.lock() {
+        songs.remove_window(window_label);
+    }
+    if let Ok(mut inputs) = app.state::<MidiState>().0.lock() {
+        inputs.remove(window_label);
+    }
+}
@@ -0,0 +1,338 @@
+// Ear training commands
+// Plays drill questions through the window's audio engine and keeps the answers on the backend
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State, Window};
+
+use super::audio::{play_notes_internal, AudioState};
+use super::quiz::play_quiz_chord;
+use crate::music::intervals::chord_to_notes;
+use crate::music::types::AudioNote;
+use crate::music::voice_leading;
+use crate::training::ear_training::{
+    EarTrainingAnswer, EarTrainingAudio, EarTrainingConfig, EarTrainingKind, EarTrainingQuestion, EarTrainingSession,
+};
+use crate::training::intervals::{
+    IntervalAnswerResult, IntervalDrill, IntervalDrillConfig, IntervalQuestion, IntervalStat, Presentation,
+};
+use crate::training::intonation::{
+    IntonationAnswerResult, IntonationDrill, IntonationDrillConfig, IntonationQuestion, Tuning,
+};
+use crate::training::progressions::{
+    ProgressionAnswerResult, ProgressionDrill, ProgressionDrillConfig, ProgressionQuestion,
+};
+
+/// Gap between the two notes of a melodic interval
+const MELODIC_NOTE_GAP: Duration = Duration::from_millis(800);
+
+/// Time each chord of a progression question sounds before the next
+const PROGRESSION_CHORD_GAP: Duration = Duration::from_millis(1100);
+
+/// Octave progression questions are voiced from
+const PROGRESSION_BASE_OCTAVE: i8 = 3;
+
+/// Managed state wrapper for the running interval drill (None when no drill is active)
+pub struct EarTrainingState(pub Mutex<Option<IntervalDrill>>);
+
+/// Managed state wrapper for the running cadence/progression drill
+pub struct ProgressionDrillState(pub Mutex<Option<ProgressionDrill>>);
+
+/// Managed state wrapper for the running intonation drill
+pub struct IntonationDrillState(pub Mutex<Option<IntonationDrill>>);
+
+/// Managed state wrapper for the running mixed ear training session
+pub struct EarTrainingSessionState(pub Mutex<Option<EarTrainingSession>>);
+
+/// Play groups of notes one after another in a window, the first immediately
+/// Later groups play from a background thread so the command returns at once
+fn play_sequence(app: &AppHandle, window: &Window, audio: &AudioState, groups: Vec<Vec<AudioNote>>, gap: Duration) -> Result<(), String> {
+    let count = groups.len();
+    let mut groups = groups.into_iter();
+    let Some(first) = groups.next() else {
+        return Ok(());
+    };
+    play_notes_internal(audio, window.label(), first, count == 1)?;
+
+    let app = app.clone();
+    let label = window.label().to_string();
+    let rest: Vec<Vec<AudioNote>> = groups.collect();
+    if rest.is_empty() {
+        return Ok(());
+    }
+    thread::spawn(move || {
+        let last = rest.len() - 1;
+        for (i, notes) in rest.into_iter().enumerate() {
+            thread::sleep(gap);
+            if let Err(e) = play_notes_internal(&app.state::<AudioState>(), &label, notes, i == last) {
+                eprintln!("Failed to play ear training sequence: {}", e);
+                return;
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Play interval notes together, or one after the other for melodic questions
+fn play_interval(
+    app: &AppHandle,
+    window: &Window,
+    audio: &AudioState,
+    presentation: Presentation,
+    notes: Vec<AudioNote>,
+) -> Result<(), String> {
+    let groups = match presentation {
+        Presentation::Harmonic => vec![notes],
+        Presentation::Melodic => notes.into_iter().map(|note| vec![note]).collect(),
+    };
+    play_sequence(app, window, audio, groups, MELODIC_NOTE_GAP)
+}
+
+/// Voice and play a progression question
+fn play_progression(app: &AppHandle, window: &Window, audio: &AudioState, chords: &[String]) -> Result<(), String> {
+    let notes = chords
+        .iter()
+        .map(|chord| chord_to_notes(chord).map_err(|e| format!("Failed to parse chord: {}", e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let voiced = voice_leading::voice_progression(&notes, PROGRESSION_BASE_OCTAVE, "lead")
+        .map_err(|e| format!("Voice leading failed: {}", e))?;
+    play_sequence(app, window, audio, voiced, PROGRESSION_CHORD_GAP)
+}
+
+/// Start a mixed ear training session (replaces any running session)
+#[tauri::command]
+pub fn start_ear_training_session(
+    state: State<'_, EarTrainingSessionState>,
+    config: Option<EarTrainingConfig>,
+) -> Result<(), String> {
+    let session = EarTrainingSession::new(config.unwrap_or_default())?;
+    let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    *guard = Some(session);
+    Ok(())
+}
+
+/// Ask and play an interval, chord quality or progression question
+/// Starts a session with the default settings if none is running
+#[tauri::command]
+pub fn generate_ear_training_question(
+    app: AppHandle,
+    window: Window,
+    audio: State<'_, AudioState>,
+    state: State<'_, EarTrainingSessionState>,
+    kind: EarTrainingKind,
+) -> Result<EarTrainingQuestion, String> {
+    let (question, sound) = {
+        let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let session = match guard.as_mut() {
+            Some(session) => session,
+            None => guard.insert(EarTrainingSession::new(EarTrainingConfig::default())?),
+        };
+        session.next_question(kind, &mut rand::thread_rng())?
+    };
+
+    match sound {
+        EarTrainingAudio::Interval(presentation, notes) => play_interval(&app, &window, &audio, presentation, notes)?,
+        EarTrainingAudio::Chord(notes) => play_quiz_chord(&window, &audio, &notes)?,
+        EarTrainingAudio::Progression(chords) => play_progression(&app, &window, &audio, &chords)?,
+    }
+    Ok(question)
+}
+
+/// Grade an answer to the current ear training question; the result carries the session statistics
+#[tauri::command]
+pub fn grade_ear_training_answer(
+    state: State<'_, EarTrainingSessionState>,
+    question_id: String,
+    answer: String,
+) -> Result<EarTrainingAnswer, String> {
+    let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let session = guard.as_mut().ok_or("No ear training session is running")?;
+    session.answer(&question_id, &answer)
+}
+
+/// Start a new interval drill (replaces any running drill)
+#[tauri::command]
+pub fn start_interval_drill(
+    state: State<'_, EarTrainingState>,
+    config: Option<IntervalDrillConfig>,
+) -> Result<(), String> {
+    let drill = IntervalDrill::new(config.unwrap_or_default())?;
+    let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    *guard = Some(drill);
+    Ok(())
+}
+
+/// Pick and play the next interval
+#[tauri::command]
+pub fn next_interval_question(
+    app: AppHandle,
+    window: Window,
+    audio: State<'_, AudioState>,
+    state: State<'_, EarTrainingState>,
+) -> Result<IntervalQuestion, String> {
+    let (question, notes) = {
+        let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let drill = guard.as_mut().ok_or("No interval drill is running")?;
+        drill.next_question(&mut rand::thread_rng())?
+    };
+
+    play_interval(&app, &window, &audio, question.presentation, notes)?;
+    Ok(question)
+}
+
+/// Play the current interval again
+#[tauri::command]
+pub fn replay_interval_question(
+    app: AppHandle,
+    window: Window,
+    audio: State<'_, AudioState>,
+    state: State<'_, EarTrainingState>,
+) -> Result<(), String> {
+    let (presentation, notes) = {
+        let guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let drill = guard.as_ref().ok_or("No interval drill is running")?;
+        drill.current_notes().ok_or("No question is waiting for an answer")?
+    };
+
+    play_interval(&app, &window, &audio, presentation, notes)
+}
+
+/// Submit an answer ("m9", "major tenth") to the current interval
+#[tauri::command]
+pub fn submit_interval_answer(
+    state: State<'_, EarTrainingState>,
+    question_id: String,
+    answer: String,
+) -> Result<IntervalAnswerResult, String> {
+    let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let drill = guard.as_mut().ok_or("No interval drill is running")?;
+    drill.answer(&question_id, &answer)
+}
+
+/// Per-interval statistics of the running drill, split by harmonic/ascending/descending
+#[tauri::command]
+pub fn get_interval_stats(state: State<'_, EarTrainingState>) -> Result<Vec<IntervalStat>, String> {
+    let guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let drill = guard.as_ref().ok_or("No interval drill is running")?;
+    Ok(drill.stats())
+}
+
+/// Start a new cadence or progression drill (replaces any running drill)
+#[tauri::command]
+pub fn start_progression_drill(
+    state: State<'_, ProgressionDrillState>,
+    config: Option<ProgressionDrillConfig>,
+) -> Result<(), String> {
+    let drill = ProgressionDrill::new(config.unwrap_or_default())?;
+    let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    *guard = Some(drill);
+    Ok(())
+}
+
+/// Generate and play the next progression
+#[tauri::command]
+pub fn next_progression_question(
+    app: AppHandle,
+    window: Window,
+    audio: State<'_, AudioState>,
+    state: State<'_, ProgressionDrillState>,
+) -> Result<ProgressionQuestion, String> {
+    let (question, chords) = {
+        let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let drill = guard.as_mut().ok_or("No progression drill is running")?;
+        drill.next_question(&mut rand::thread_rng())?
+    };
+
+    play_progression(&app, &window, &audio, &chords)?;
+    Ok(question)
+}
+
+/// Play the current progression again
+#[tauri::command]
+pub fn replay_progression_question(
+    app: AppHandle,
+    window: Window,
+    audio: State<'_, AudioState>,
+    state: State<'_, ProgressionDrillState>,
+) -> Result<(), String> {
+    let chords = {
+        let guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let drill = guard.as_ref().ok_or("No progression drill is running")?;
+        drill.current_chords().ok_or("No question is waiting for an answer")?
+    };
+
+    play_progression(&app, &window, &audio, &chords)
+}
+
+/// Submit a cadence name or Roman numeral sequence for the current progression
+#[tauri::command]
+pub fn submit_progression_answer(
+    state: State<'_, ProgressionDrillState>,
+    question_id: String,
+    answer: String,
+) -> Result<ProgressionAnswerResult, String> {
+    let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let drill = guard.as_mut().ok_or("No progression drill is running")?;
+    drill.answer(&question_id, &answer)
+}
+
+/// Start a new intonation drill (replaces any running drill)
+#[tauri::command]
+pub fn start_intonation_drill(
+    state: State<'_, IntonationDrillState>,
+    config: Option<IntonationDrillConfig>,
+) -> Result<(), String> {
+    let drill = IntonationDrill::new(config.unwrap_or_default())?;
+    let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    *guard = Some(drill);
+    Ok(())
+}
+
+/// Pick and play the next interval or chord with one note detuned
+#[tauri::command]
+pub fn next_intonation_question(
+    app: AppHandle,
+    window: Window,
+    audio: State<'_, AudioState>,
+    state: State<'_, IntonationDrillState>,
+) -> Result<IntonationQuestion, String> {
+    let (question, notes) = {
+        let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let drill = guard.as_mut().ok_or("No intonation drill is running")?;
+        drill.next_question(&mut rand::thread_rng())?
+    };
+
+    play_interval(&app, &window, &audio, question.presentation, notes)?;
+    Ok(question)
+}
+
+/// Play the current intonation question again
+#[tauri::command]
+pub fn replay_intonation_question(
+    app: AppHandle,
+    window: Window,
+    audio: State<'_, AudioState>,
+    state: State<'_, IntonationDrillState>,
+) -> Result<(), String> {
+    let (presentation, notes) = {
+        let guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let drill = guard.as_ref().ok_or("No intonation drill is running")?;
+        drill.current_notes().ok_or("No question is waiting for an answer")?
+    };
+
+    play_interval(&app, &window, &audio, presentation, notes)
+}
+
+/// Submit which note (lowest first) sounded out of tune and whether it was sharp or flat
+#[tauri::command]
+pub fn submit_intonation_answer(
+    state: State<'_, IntonationDrillState>,
+    question_id: String,
+    note_index: usize,
+    tuning: Tuning,
+) -> Result<IntonationAnswerResult, String> {
+    let mut guard = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let drill = guard.as_mut().ok_or("No intonation drill is running")?;
+    drill.answer(&question_id, note_index, tuning)
+}
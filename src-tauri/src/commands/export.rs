@@ -1,19 +1,211 @@
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tauri::Manager;
+use tauri::{Manager, State};
 use tauri_plugin_dialog::{DialogExt, FilePath};
 use usvg::fontdb;
 
-/// Create a font database with the bundled Bravura music font loaded.
-/// Also loads system fonts as fallbacks.
-fn create_fontdb_with_bravura(app: &tauri::AppHandle) -> Result<fontdb::Database, String> {
-    let mut db = fontdb::Database::new();
-    
-    // Load system fonts as fallback for text elements
-    db.load_system_fonts();
-    
-    // Try multiple paths to find Bravura font:
-    // 1. Production: resource_dir/fonts/Bravura.otf (bundled with app)
-    // 2. Development: src-tauri/resources/fonts/Bravura.otf (source location)
+use super::audio::{sequence_tempo, SequenceChordRequest};
+use super::policy::{CommandError, PolicyState};
+use super::worksheet::parse_hex_color;
+use crate::audio::{
+    encode_wav, render_stems, MetronomeSettings, MixBalance, SequenceChord, Stem, RENDER_CHANNELS, RENDER_SAMPLE_RATE,
+};
+use crate::music::{intervals, voice_leading};
+use crate::notation::braille::worksheet_to_brf;
+use crate::notation::native::{render_progression_strip, StripOptions};
+use crate::settings::Feature;
+use crate::svg::{apply_theme, SvgTheme};
+use crate::types::song::MeasureChange;
+use crate::types::worksheet::WorksheetConfig;
+
+/// A progression to export as a practice track
+#[derive(Debug, Clone, Deserialize)]
+pub struct PracticeTrackRequest {
+    pub chords: Vec<SequenceChordRequest>,
+    pub bpm: f32,
+    #[serde(default)]
+    pub time_signature: Option<String>,
+    #[serde(default)]
+    pub changes: Vec<MeasureChange>,
+    pub voicing_style: String,
+    pub base_octave: i8,
+    /// Click track settings (its tempo follows the progression); no click when absent
+    #[serde(default)]
+    pub metronome: Option<MetronomeSettings>,
+    /// Write each stem to its own file ("name-chords.wav", ...) instead of one mix
+    #[serde(default)]
+    pub stems: bool,
+    /// Stem levels in the mix
+    #[serde(default)]
+    pub balance: MixBalance,
+}
+
+/// A progression to export as audio, e.g. to attach to a worksheet
+#[derive(Debug, Clone, Deserialize)]
+pub struct AudioExportRequest {
+    pub chords: Vec<SequenceChordRequest>,
+    pub bpm: f32,
+    #[serde(default)]
+    pub time_signature: Option<String>,
+    #[serde(default)]
+    pub changes: Vec<MeasureChange>,
+    pub voicing_style: String,
+    pub base_octave: i8,
+}
+
+/// Page size of an export, in points (1/72 inch)
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportPageSize {
+    /// The sheet's own size, from its viewBox
+    #[default]
+    Sheet,
+    Letter,
+    A4,
+    Custom { width: f32, height: f32 },
+}
+
+impl ExportPageSize {
+    fn points(&self) -> Option<(f32, f32)> {
+        match *self {
+            ExportPageSize::Sheet => None,
+            ExportPageSize::Letter => Some((612.0, 792.0)),
+            ExportPageSize::A4 => Some((595.0, 842.0)),
+            ExportPageSize::Custom { width, height } => Some((width, height)),
+        }
+    }
+}
+
+/// Page margins, in points
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportMargins {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+/// How PDF and PNG exports lay out the sheet
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    /// PNG resolution; PDFs are vector and ignore it
+    pub dpi: f32,
+    /// On a fixed page size the sheet is scaled to fit inside the margins
+    pub page_size: ExportPageSize,
+    pub margins: ExportMargins,
+    /// Hex page color ("#ffffff"); None leaves the page transparent
+    pub background: Option<String>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            dpi: 300.0,
+            page_size: ExportPageSize::Sheet,
+            margins: ExportMargins::default(),
+            background: Some("#ffffff".to_string()),
+        }
+    }
+}
+
+/// Largest PNG side, in pixels
+const MAX_PNG_SIDE: f32 = 20_000.0;
+
+/// Where the sheet is drawn on the exported page, in points from its top-left corner
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PageLayout {
+    width: f32,
+    height: f32,
+    x: f32,
+    y: f32,
+    scale: f32,
+}
+
+impl ExportOptions {
+    /// Theme the sheet is printed in: black ink on the page color
+    fn theme(&self) -> SvgTheme {
+        SvgTheme {
+            background: self.background.clone().unwrap_or_else(|| "none".to_string()),
+            ..SvgTheme::print()
+        }
+    }
+
+    /// Page color as RGB fractions; None when transparent
+    fn background_rgb(&self) -> Result<Option<(f32, f32, f32)>, String> {
+        self.background
+            .as_deref()
+            .map(|color| parse_hex_color(color).ok_or_else(|| format!("Invalid background color: {}", color)))
+            .transpose()
+    }
+
+    /// Lay out a sheet of the given size: the page grows by the margins around it, or
+    /// on a fixed page size it is scaled to fit between them, centered across the top
+    fn layout(&self, sheet_width: f32, sheet_height: f32) -> Result<PageLayout, String> {
+        let ExportMargins { top, right, bottom, left } = self.margins;
+        if ![top, right, bottom, left].iter().all(|margin| margin.is_finite() && *margin >= 0.0) {
+            return Err("Margins must be zero or more".to_string());
+        }
+
+        let Some((width, height)) = self.page_size.points() else {
+            return Ok(PageLayout {
+                width: sheet_width + left + right,
+                height: sheet_height + top + bottom,
+                x: left,
+                y: top,
+                scale: 1.0,
+            });
+        };
+        if !(width.is_finite() && height.is_finite() && width > 0.0 && height > 0.0) {
+            return Err(format!("Invalid page size: {} x {} points", width, height));
+        }
+        let (available_width, available_height) = (width - left - right, height - top - bottom);
+        if available_width <= 0.0 || available_height <= 0.0 {
+            return Err("Margins leave no room on the page".to_string());
+        }
+
+        let scale = (available_width / sheet_width).min(available_height / sheet_height);
+        Ok(PageLayout { width, height, x: left + (available_width - sheet_width * scale) / 2.0, y: top, scale })
+    }
+}
+
+/// Voice a progression from a fresh start, keeping each chord's length
+fn voice_sequence(
+    requests: &[SequenceChordRequest],
+    base_octave: i8,
+    voicing_style: &str,
+) -> Result<Vec<SequenceChord>, String> {
+    if let Some(request) = requests.iter().find(|request| !(request.beats > 0.0 && request.beats.is_finite())) {
+        return Err(format!("Invalid length for {}: {} beats", request.chord, request.beats));
+    }
+    let notes = requests
+        .iter()
+        .map(|request| intervals::chord_to_notes(&request.chord))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to parse chord: {}", e))?;
+    let voiced = voice_leading::voice_progression(&notes, base_octave, voicing_style)
+        .map_err(|e| format!("Voice leading failed: {}", e))?;
+    Ok(voiced
+        .into_iter()
+        .zip(requests)
+        .map(|(notes, request)| SequenceChord { notes, beats: request.beats })
+        .collect())
+}
+
+/// File a stem is written to, next to the chosen file ("Track.wav" → "Track-bass.wav")
+fn stem_path(path: &Path, stem: Stem) -> PathBuf {
+    let name = path.file_stem().and_then(|name| name.to_str()).unwrap_or("track");
+    path.with_file_name(format!("{}-{}.wav", name, stem.name()))
+}
+
+/// Places the Bravura music font is looked for, in order:
+/// 1. Production: resource_dir/fonts/Bravura.otf (bundled with app)
+/// 2. Development: src-tauri/resources/fonts/Bravura.otf (source location)
+pub(crate) fn bravura_font_paths(app: &tauri::AppHandle) -> Vec<PathBuf> {
     let mut font_paths = Vec::new();
     
     // Production path (bundled resources)
@@ -22,8 +214,20 @@ fn create_fontdb_with_bravura(app: &tauri::AppHandle) -> Result<fontdb::Database
     }
     
     // Development fallback paths
-    font_paths.push(std::path::PathBuf::from("resources/fonts/Bravura.otf"));
-    font_paths.push(std::path::PathBuf::from("src-tauri/resources/fonts/Bravura.otf"));
+    font_paths.push(PathBuf::from("resources/fonts/Bravura.otf"));
+    font_paths.push(PathBuf::from("src-tauri/resources/fonts/Bravura.otf"));
+    font_paths
+}
+
+/// Create a font database with the bundled Bravura music font loaded.
+/// Also loads system fonts as fallbacks.
+pub(crate) fn create_fontdb_with_bravura(app: &tauri::AppHandle) -> Result<fontdb::Database, String> {
+    let mut db = fontdb::Database::new();
+    
+    // Load system fonts as fallback for text elements
+    db.load_system_fonts();
+    
+    let font_paths = bravura_font_paths(app);
     
     // Try each path until one works
     let mut font_loaded = false;
@@ -51,18 +255,23 @@ fn create_fontdb_with_bravura(app: &tauri::AppHandle) -> Result<fontdb::Database
 
 /// Export the canvas SVG content to a PDF file.
 /// 
-/// The SVG is converted to PDF using svg2pdf. The PDF page size is determined
-/// by the SVG's viewBox dimensions at 72 DPI (1 SVG unit = 1 PDF point).
-/// For 8.5x11 inch output, the SVG should have viewBox="0 0 612 792".
+/// The SVG is converted to PDF using svg2pdf. By default the PDF page size is
+/// determined by the SVG's viewBox dimensions at 72 DPI (1 SVG unit = 1 PDF point).
+/// For 8.5x11 inch output, the SVG should have viewBox="0 0 612 792"; options can
+/// instead place it on a fixed page size with margins and a page color.
 /// 
 /// The Bravura music font is loaded into the font database for proper
 /// rendering of music notation symbols (noteheads, clefs, etc.).
 #[tauri::command]
 pub async fn export_pdf(
     app: tauri::AppHandle,
+    policy: State<'_, PolicyState>,
     svg_content: String,
     default_filename: String,
-) -> Result<bool, String> {
+    options: Option<ExportOptions>,
+) -> Result<bool, CommandError> {
+    policy.check(Feature::Export)?;
+
     // Show native save dialog
     let file_path = app
         .dialog()
@@ -80,45 +289,101 @@ pub async fn export_pdf(
     // Convert FilePath to std::path::PathBuf
     let path = match file_path {
         FilePath::Path(p) => p,
-        _ => return Err("Invalid file path".to_string()),
+        _ => return Err("Invalid file path".into()),
     };
 
     // Create font database with Bravura loaded
     let fontdb = create_fontdb_with_bravura(&app)?;
+    let pdf = svg_pages_to_pdf(&[svg_content], fontdb, &options.unwrap_or_default())?;
 
-    // Parse SVG with usvg using our custom font database
+    // Write PDF to file
+    std::fs::write(&path, pdf)
+        .map_err(|e| format!("Failed to write PDF: {}", e))?;
+
+    Ok(true)
+}
+
+/// Combine SVG pages into one PDF document.
+///
+/// Each SVG becomes one page laid out by the export options, as in `export_pdf`,
+/// and is printed black on the page color whatever colors it was rendered in.
+pub(crate) fn svg_pages_to_pdf(
+    pages: &[String],
+    fontdb: fontdb::Database,
+    export: &ExportOptions,
+) -> Result<Vec<u8>, String> {
     let options = usvg::Options {
         fontdb: Arc::new(fontdb),
         ..Default::default()
     };
-    
-    let tree = usvg::Tree::from_str(&svg_content, &options)
-        .map_err(|e| format!("Failed to parse SVG: {}", e))?;
+    let theme = export.theme();
+    let background = export.background_rgb()?;
+    let svg_name = Name(b"S1");
 
-    // Convert SVG to PDF (page size determined by SVG viewBox at 72 DPI)
-    let pdf = svg2pdf::to_pdf(
-        &tree,
-        svg2pdf::ConversionOptions::default(),
-        svg2pdf::PageOptions::default(),
-    )
-    .map_err(|e| format!("Failed to convert to PDF: {}", e))?;
+    let mut alloc = Ref::new(1);
+    let catalog_id = alloc.bump();
+    let page_tree_id = alloc.bump();
+    let mut pdf = Pdf::new();
+    let mut page_ids = Vec::new();
 
-    // Write PDF to file
-    std::fs::write(&path, pdf)
-        .map_err(|e| format!("Failed to write PDF: {}", e))?;
+    for svg_content in pages {
+        let svg_content = apply_theme(svg_content, &theme)?;
+        let tree = usvg::Tree::from_str(&svg_content, &options)
+            .map_err(|e| format!("Failed to parse SVG: {}", e))?;
+        let (chunk, svg_id) = svg2pdf::to_chunk(&tree, svg2pdf::ConversionOptions::default())
+            .map_err(|e| format!("Failed to convert to PDF: {}", e))?;
 
-    Ok(true)
+        // Number the page's objects after those of the pages before it
+        let mut ids = HashMap::new();
+        let chunk = chunk.renumber(|old| *ids.entry(old).or_insert_with(|| alloc.bump()));
+        let svg_id = ids[&svg_id];
+        let page_id = alloc.bump();
+        let content_id = alloc.bump();
+
+        let (sheet_width, sheet_height) = (tree.size().width(), tree.size().height());
+        let layout = export.layout(sheet_width, sheet_height)?;
+        let mut page = pdf.page(page_id);
+        page.media_box(Rect::new(0.0, 0.0, layout.width, layout.height));
+        page.parent(page_tree_id);
+        page.contents(content_id);
+        page.resources().x_objects().pair(svg_name, svg_id);
+        page.finish();
+
+        let mut content = Content::new();
+        if let Some((r, g, b)) = background {
+            content.set_fill_rgb(r, g, b).rect(0.0, 0.0, layout.width, layout.height).fill_nonzero();
+        }
+        // The converted SVG fills a unit square, scaled up to the sheet's place on the page
+        // (PDF coordinates run up from the bottom-left corner)
+        let (width, height) = (sheet_width * layout.scale, sheet_height * layout.scale);
+        content
+            .transform([width, 0.0, 0.0, height, layout.x, layout.height - layout.y - height])
+            .x_object(svg_name);
+        pdf.stream(content_id, &content.finish());
+        pdf.extend(&chunk);
+        page_ids.push(page_id);
+    }
+
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id).count(page_ids.len() as i32).kids(page_ids);
+    Ok(pdf.finish())
 }
 
 /// Export the canvas SVG content to a PNG file.
 /// 
-/// The SVG is rendered to PNG using resvg at 300 DPI for print quality.
+/// The SVG is rendered to PNG using resvg, at 300 DPI for print quality unless
+/// the options say otherwise. The image is the size of the SVG's viewBox, or of
+/// the page size chosen in the options, plus the margins.
 #[tauri::command]
 pub async fn export_png(
     app: tauri::AppHandle,
+    policy: State<'_, PolicyState>,
     svg_content: String,
     default_filename: String,
-) -> Result<bool, String> {
+    options: Option<ExportOptions>,
+) -> Result<bool, CommandError> {
+    policy.check(Feature::Export)?;
+
     // Show native save dialog
     let file_path = app
         .dialog()
@@ -136,43 +401,410 @@ pub async fn export_png(
     // Convert FilePath to std::path::PathBuf
     let path = match file_path {
         FilePath::Path(p) => p,
-        _ => return Err("Invalid file path".to_string()),
+        _ => return Err("Invalid file path".into()),
     };
 
     // Create font database with Bravura loaded
     let fontdb = create_fontdb_with_bravura(&app)?;
+    let png_data = svg_to_page_png(&svg_content, fontdb, &options.unwrap_or_default())?;
+
+    std::fs::write(&path, png_data)
+        .map_err(|e| format!("Failed to write PNG: {}", e))?;
+
+    Ok(true)
+}
+
+/// Render an SVG to PNG on a page laid out by the export options.
+fn svg_to_page_png(svg_content: &str, fontdb: fontdb::Database, export: &ExportOptions) -> Result<Vec<u8>, String> {
+    if !(export.dpi.is_finite() && export.dpi > 0.0) {
+        return Err(format!("Invalid DPI: {}", export.dpi));
+    }
 
     // Parse SVG with usvg using our custom font database
     let options = usvg::Options {
         fontdb: Arc::new(fontdb),
         ..Default::default()
     };
-    
+
+    // Exports are always black ink, whatever colors the sheet was shown in
+    let svg_content = apply_theme(svg_content, &export.theme())?;
     let tree = usvg::Tree::from_str(&svg_content, &options)
         .map_err(|e| format!("Failed to parse SVG: {}", e))?;
+    let layout = export.layout(tree.size().width(), tree.size().height())?;
 
-    // Render at 300 DPI (4.16x scale from 72 DPI)
-    // 8.5 x 11 inches at 300 DPI = 2550 x 3300 pixels
-    let scale = 300.0 / 72.0;
-    let width = (8.5 * 300.0) as u32;  // 2550 pixels
-    let height = (11.0 * 300.0) as u32; // 3300 pixels
-
-    let mut pixmap = resvg::tiny_skia::Pixmap::new(width, height)
+    // Points to pixels: 300 DPI is 300/72 ≈ 4.17 pixels per point
+    let pixels_per_point = export.dpi / 72.0;
+    let (width, height) = (layout.width * pixels_per_point, layout.height * pixels_per_point);
+    if width.round() > MAX_PNG_SIDE || height.round() > MAX_PNG_SIDE {
+        return Err(format!("Image would be {:.0} x {:.0} pixels; lower the DPI", width, height));
+    }
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(width.round() as u32, height.round() as u32)
         .ok_or("Failed to create pixmap")?;
-    
-    // Fill with white background
-    pixmap.fill(resvg::tiny_skia::Color::WHITE);
+
+    if let Some((r, g, b)) = export.background_rgb()? {
+        let color = resvg::tiny_skia::Color::from_rgba(r, g, b, 1.0).ok_or("Invalid background color")?;
+        pixmap.fill(color);
+    }
 
     // Render SVG to pixmap
-    let transform = resvg::tiny_skia::Transform::from_scale(scale, scale);
+    let scale = pixels_per_point * layout.scale;
+    let transform = resvg::tiny_skia::Transform::from_row(
+        scale,
+        0.0,
+        0.0,
+        scale,
+        layout.x * pixels_per_point,
+        layout.y * pixels_per_point,
+    );
     resvg::render(&tree, transform, &mut pixmap.as_mut());
 
-    // Encode as PNG and write to file
-    let png_data = pixmap.encode_png()
-        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
-    
+    pixmap.encode_png()
+        .map_err(|e| format!("Failed to encode PNG: {}", e))
+}
+
+/// Export the canvas SVG content to a standalone SVG file.
+///
+/// The SVG goes through the same font pass as the PDF and PNG exports: text is
+/// laid out with the Bravura font database and converted to paths, so the file
+/// opens the same in any viewer, whether or not Bravura is installed.
+#[tauri::command]
+pub async fn export_svg(
+    app: tauri::AppHandle,
+    policy: State<'_, PolicyState>,
+    svg_content: String,
+    default_filename: String,
+) -> Result<bool, CommandError> {
+    policy.check(Feature::Export)?;
+
+    let file_path = app
+        .dialog()
+        .file()
+        .add_filter("SVG Image", &["svg"])
+        .set_file_name(&default_filename)
+        .set_title("Export as SVG")
+        .blocking_save_file();
+    let path = match file_path {
+        Some(FilePath::Path(path)) => path,
+        Some(_) => return Err("Invalid file path".into()),
+        None => return Ok(false), // User cancelled
+    };
+
+    let svg = svg_to_standalone(&svg_content, create_fontdb_with_bravura(&app)?)?;
+    std::fs::write(&path, svg)
+        .map_err(|e| format!("Failed to write SVG: {}", e))?;
+
+    Ok(true)
+}
+
+/// Re-write an SVG black on white with all of its text converted to paths.
+fn svg_to_standalone(svg_content: &str, fontdb: fontdb::Database) -> Result<String, String> {
+    let options = usvg::Options {
+        fontdb: Arc::new(fontdb),
+        ..Default::default()
+    };
+
+    // Exports are always black on white, whatever colors the sheet was shown in
+    let svg_content = apply_theme(svg_content, &SvgTheme::print())?;
+    let tree = usvg::Tree::from_str(&svg_content, &options)
+        .map_err(|e| format!("Failed to parse SVG: {}", e))?;
+
+    Ok(tree.to_string(&usvg::WriteOptions::default()))
+}
+
+/// Export a progression as a PNG strip for sharing.
+///
+/// The strip is drawn by the native renderer at the pixel size of the chosen
+/// format, so LilyPond is not needed.
+#[tauri::command]
+pub async fn export_progression_png(
+    app: tauri::AppHandle,
+    policy: State<'_, PolicyState>,
+    chords: Vec<String>,
+    options: Option<StripOptions>,
+    default_filename: String,
+) -> Result<bool, CommandError> {
+    policy.check(Feature::Export)?;
+
+    // Draw first, so a bad chord is reported before asking where to save
+    let svg_content = render_progression_strip(&chords, &options.unwrap_or_default())
+        .map_err(|e| format!("Failed to draw progression: {}", e))?;
+
+    let file_path = app
+        .dialog()
+        .file()
+        .add_filter("PNG Image", &["png"])
+        .set_file_name(&default_filename)
+        .set_title("Export Progression as PNG")
+        .blocking_save_file();
+    let path = match file_path {
+        Some(FilePath::Path(path)) => path,
+        Some(_) => return Err("Invalid file path".into()),
+        None => return Ok(false),
+    };
+
+    let png_data = svg_to_png(&svg_content, create_fontdb_with_bravura(&app)?)?;
     std::fs::write(&path, png_data)
         .map_err(|e| format!("Failed to write PNG: {}", e))?;
 
     Ok(true)
 }
+
+/// Rasterize an SVG at its own size, one pixel per SVG unit.
+fn svg_to_png(svg_content: &str, fontdb: fontdb::Database) -> Result<Vec<u8>, String> {
+    let options = usvg::Options {
+        fontdb: Arc::new(fontdb),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_str(svg_content, &options)
+        .map_err(|e| format!("Failed to parse SVG: {}", e))?;
+
+    let size = tree.size().to_int_size();
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or("Failed to create pixmap")?;
+    resvg::render(&tree, resvg::tiny_skia::Transform::identity(), &mut pixmap.as_mut());
+
+    pixmap.encode_png()
+        .map_err(|e| format!("Failed to encode PNG: {}", e))
+}
+
+/// Export a progression as a WAV practice track, mixed or as separate stems.
+///
+/// The chords are voiced from a fresh start and rendered offline through the playback
+/// signal chain; the optional click follows the progression's tempo and meter changes.
+#[tauri::command]
+pub async fn export_practice_track(
+    app: tauri::AppHandle,
+    policy: State<'_, PolicyState>,
+    request: PracticeTrackRequest,
+    default_filename: String,
+) -> Result<bool, CommandError> {
+    policy.check(Feature::Export)?;
+
+    let tempo = sequence_tempo(&request.chords, request.bpm, request.time_signature.as_deref(), &request.changes)?;
+    let chords = voice_sequence(&request.chords, request.base_octave, &request.voicing_style)?;
+    let click = request.metronome.as_ref().map(MetronomeSettings::resolve).transpose()?;
+
+    // Show native save dialog
+    let file_path = app
+        .dialog()
+        .file()
+        .add_filter("WAV Audio", &["wav"])
+        .set_file_name(&default_filename)
+        .set_title(if request.stems { "Export Stems" } else { "Export Practice Track" })
+        .blocking_save_file();
+
+    let file_path = match file_path {
+        Some(path) => path,
+        None => return Ok(false), // User cancelled
+    };
+
+    // Convert FilePath to std::path::PathBuf
+    let path = match file_path {
+        FilePath::Path(p) => p,
+        _ => return Err("Invalid file path".into()),
+    };
+
+    let stems = render_stems(&chords, tempo, click.as_ref());
+    if request.stems {
+        for stem in Stem::ALL {
+            if let Some(samples) = stems.get(stem) {
+                std::fs::write(stem_path(&path, stem), encode_wav(samples, RENDER_CHANNELS, RENDER_SAMPLE_RATE))
+                    .map_err(|e| format!("Failed to write {} stem: {}", stem.name(), e))?;
+            }
+        }
+    } else {
+        let mix = stems.mix(&request.balance);
+        std::fs::write(&path, encode_wav(&mix, RENDER_CHANNELS, RENDER_SAMPLE_RATE))
+            .map_err(|e| format!("Failed to write WAV: {}", e))?;
+    }
+
+    Ok(true)
+}
+
+/// Export a progression as a WAV file, e.g. to attach to a worksheet.
+///
+/// The chords are voiced from a fresh start and rendered offline through the playback
+/// signal chain, without a click; no audio output device is needed.
+#[tauri::command]
+pub async fn export_audio(
+    app: tauri::AppHandle,
+    policy: State<'_, PolicyState>,
+    request: AudioExportRequest,
+    default_filename: String,
+) -> Result<bool, CommandError> {
+    policy.check(Feature::Export)?;
+
+    let tempo = sequence_tempo(&request.chords, request.bpm, request.time_signature.as_deref(), &request.changes)?;
+    let chords = voice_sequence(&request.chords, request.base_octave, &request.voicing_style)?;
+
+    // Show native save dialog
+    let file_path = app
+        .dialog()
+        .file()
+        .add_filter("WAV Audio", &["wav"])
+        .set_file_name(&default_filename)
+        .set_title("Export Audio")
+        .blocking_save_file();
+
+    let file_path = match file_path {
+        Some(path) => path,
+        None => return Ok(false), // User cancelled
+    };
+
+    // Convert FilePath to std::path::PathBuf
+    let path = match file_path {
+        FilePath::Path(p) => p,
+        _ => return Err("Invalid file path".into()),
+    };
+
+    let mix = render_stems(&chords, tempo, None).mix(&MixBalance::default());
+    std::fs::write(&path, encode_wav(&mix, RENDER_CHANNELS, RENDER_SAMPLE_RATE))
+        .map_err(|e| format!("Failed to write WAV: {}", e))?;
+
+    Ok(true)
+}
+
+/// Export a worksheet as a Braille Ready File (BRF) for embossing or reading on a braille display.
+///
+/// Notes, chords, rests and key and time signatures are transcribed to braille music;
+/// answers are included only when the worksheet shows them.
+#[tauri::command]
+pub async fn export_braille(
+    app: tauri::AppHandle,
+    policy: State<'_, PolicyState>,
+    config: WorksheetConfig,
+    default_filename: String,
+) -> Result<bool, CommandError> {
+    policy.check(Feature::Export)?;
+
+    let brf = worksheet_to_brf(&config).map_err(|e| format!("Failed to transcribe worksheet: {}", e))?;
+
+    // Show native save dialog
+    let file_path = app
+        .dialog()
+        .file()
+        .add_filter("Braille Ready File", &["brf"])
+        .set_file_name(&default_filename)
+        .set_title("Export as Braille")
+        .blocking_save_file();
+
+    let file_path = match file_path {
+        Some(path) => path,
+        None => return Ok(false), // User cancelled
+    };
+
+    // Convert FilePath to std::path::PathBuf
+    let path = match file_path {
+        FilePath::Path(p) => p,
+        _ => return Err("Invalid file path".into()),
+    };
+
+    std::fs::write(&path, brf)
+        .map_err(|e| format!("Failed to write BRF: {}", e))?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_svg_pages_to_pdf() {
+        let page = |width: u32| {
+            format!(
+                r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="792" viewBox="0 0 {w} 792">
+                <rect x="10" y="10" width="100" height="50" fill="#000000"/></svg>"##,
+                w = width
+            )
+        };
+        let pdf = svg_pages_to_pdf(&[page(612), page(595)], fontdb::Database::new(), &ExportOptions::default());
+        let pdf = pdf.unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-"));
+        assert!(text.contains("/Count 2"));
+        // Each page keeps its own size
+        assert!(text.contains("/MediaBox [0 0 612 792]"));
+        assert!(text.contains("/MediaBox [0 0 595 792]"));
+
+        let invalid = svg_pages_to_pdf(&["not svg".to_string()], fontdb::Database::new(), &ExportOptions::default());
+        assert!(invalid.is_err());
+
+        // A fixed page size replaces the sheet's own
+        let a4 = ExportOptions { page_size: ExportPageSize::A4, ..ExportOptions::default() };
+        let pdf = svg_pages_to_pdf(&[page(612)], fontdb::Database::new(), &a4).unwrap();
+        assert!(String::from_utf8_lossy(&pdf).contains("/MediaBox [0 0 595 842]"));
+    }
+
+    #[test]
+    fn test_export_layout() {
+        let margins = ExportMargins { top: 36.0, right: 36.0, bottom: 36.0, left: 36.0 };
+        let grown = ExportOptions { margins, ..ExportOptions::default() }.layout(612.0, 792.0).unwrap();
+        assert_eq!(grown, PageLayout { width: 684.0, height: 864.0, x: 36.0, y: 36.0, scale: 1.0 });
+
+        // A letter sheet on A4 shrinks to the narrower width; a narrow sheet is centered across the page
+        let a4 = ExportOptions { page_size: ExportPageSize::A4, margins, ..ExportOptions::default() };
+        let fitted = a4.layout(612.0, 792.0).unwrap();
+        assert_eq!((fitted.width, fitted.height, fitted.x, fitted.y), (595.0, 842.0, 36.0, 36.0));
+        assert!((fitted.scale - 523.0 / 612.0).abs() < 1e-6);
+        let narrow = a4.layout(300.0, 792.0).unwrap();
+        assert!((narrow.scale - 770.0 / 792.0).abs() < 1e-6);
+        assert!((narrow.x * 2.0 + 300.0 * narrow.scale - 595.0).abs() < 1e-3);
+
+        let cramped = ExportOptions { page_size: ExportPageSize::Custom { width: 60.0, height: 60.0 }, ..a4 };
+        assert!(cramped.layout(612.0, 792.0).is_err());
+        let negative = ExportMargins { left: -1.0, ..ExportMargins::default() };
+        assert!(ExportOptions { margins: negative, ..ExportOptions::default() }.layout(10.0, 10.0).is_err());
+
+        let json = r#"{"dpi": 150, "page_size": {"custom": {"width": 400, "height": 300}}, "background": null}"#;
+        let options: ExportOptions = serde_json::from_str(json).unwrap();
+        assert_eq!(options.page_size, ExportPageSize::Custom { width: 400.0, height: 300.0 });
+        assert_eq!((options.dpi, options.margins, options.background), (150.0, ExportMargins::default(), None));
+    }
+
+    #[test]
+    fn test_png_size_follows_the_sheet() {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="612" height="792" viewBox="0 0 612 792">
+            <rect x="10" y="10" width="100" height="50" fill="#000000"/></svg>"##;
+        // Width and height are the big-endian words after the PNG signature and IHDR header
+        let png_size = |png: &[u8]| {
+            let word = |at: usize| u32::from_be_bytes(png[at..at + 4].try_into().unwrap());
+            (word(16), word(20))
+        };
+
+        let png = svg_to_page_png(svg, fontdb::Database::new(), &ExportOptions::default()).unwrap();
+        assert_eq!(png_size(&png), (2550, 3300));
+        let a4 =
+            ExportOptions { dpi: 72.0, page_size: ExportPageSize::A4, background: None, ..ExportOptions::default() };
+        let png = svg_to_page_png(svg, fontdb::Database::new(), &a4).unwrap();
+        assert_eq!(png_size(&png), (595, 842));
+
+        assert!(svg_to_page_png(svg, fontdb::Database::new(), &ExportOptions { dpi: 0.0, ..a4.clone() }).is_err());
+        assert!(svg_to_page_png(svg, fontdb::Database::new(), &ExportOptions { dpi: 10_000.0, ..a4 }).is_err());
+        let bad_color = ExportOptions { background: Some("#12".to_string()), ..ExportOptions::default() };
+        assert!(svg_to_page_png(svg, fontdb::Database::new(), &bad_color).is_err());
+    }
+
+    #[test]
+    fn test_svg_to_standalone() {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="612" height="792" viewBox="0 0 612 792">
+            <rect x="10" y="10" width="100" height="50" fill="#000000"/>
+            <text x="20" y="40" font-family="Bravura">&#xE050;</text></svg>"##;
+        let standalone = svg_to_standalone(svg, fontdb::Database::new()).unwrap();
+        assert!(standalone.contains(r#"width="612" height="792""#));
+        assert!(standalone.contains("<path"));
+        // Text never reaches the file as text: it is outlined, or dropped when its font is missing
+        assert!(!standalone.contains("<text"));
+
+        assert!(svg_to_standalone("not svg", fontdb::Database::new()).is_err());
+    }
+
+    #[test]
+    fn test_progression_png_size() {
+        let chords = vec!["C".to_string(), "G7".to_string()];
+        let svg = render_progression_strip(&chords, &StripOptions::default()).unwrap();
+        let png = svg_to_png(&svg, fontdb::Database::new()).unwrap();
+        // Width and height are the big-endian words after the PNG signature and IHDR header
+        assert_eq!(&png[16..24], [0, 0, 4, 176, 0, 0, 2, 163]);
+    }
+}
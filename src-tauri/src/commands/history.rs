@@ -0,0 +1,24 @@
+// Render history commands
+// Lists and restores autosaved worksheet renders
+
+use std::sync::Mutex;
+use tauri::State;
+
+use crate::render_history::{RenderHistory, RenderHistoryEntry, RestoredRender};
+
+/// Managed state wrapper for the render history
+pub struct RenderHistoryState(pub Mutex<RenderHistory>);
+
+/// List saved renders, newest first
+#[tauri::command]
+pub fn list_render_history(state: State<'_, RenderHistoryState>) -> Result<Vec<RenderHistoryEntry>, String> {
+    let history = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(history.list())
+}
+
+/// Restore a saved render (SVG and the config that produced it)
+#[tauri::command]
+pub fn restore_render(state: State<'_, RenderHistoryState>, id: String) -> Result<RestoredRender, String> {
+    let history = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    history.restore(&id)
+}
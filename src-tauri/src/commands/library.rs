@@ -0,0 +1,41 @@
+// Example library commands
+// Search the bundled progressions and load one onto the canvas or into a worksheet
+
+use tauri::{State, Window};
+
+use super::analysis::{apply_progression_edit, AnalysisState};
+use crate::library::{self, LibraryEntry, LibraryFilters, LibraryProgression};
+use crate::music::analysis::ProgressionEdit;
+use crate::types::worksheet::WorksheetConfig;
+
+/// Search the example library; an empty query lists every entry that passes the filters
+#[tauri::command]
+pub fn search_library(query: String, filters: Option<LibraryFilters>) -> Vec<LibraryEntry> {
+    library::search_library(&query, &filters.unwrap_or_default()).into_iter().cloned().collect()
+}
+
+/// Load a library progression onto a document's canvas, in the entry's own key when none is given
+/// The canvas analysis is replaced and ANALYSIS_CHANGED_EVENT is emitted as for any other edit
+#[tauri::command]
+pub fn load_library_progression(
+    window: Window,
+    state: State<'_, AnalysisState>,
+    document_id: Option<String>,
+    id: String,
+    key: Option<String>,
+) -> Result<LibraryProgression, String> {
+    let entry = library::find_entry(&id).map_err(|e| format!("Failed to load library entry: {}", e))?;
+    let progression = library::progression_in_key(entry, key.as_deref())
+        .map_err(|e| format!("Failed to transpose progression: {}", e))?;
+
+    let chords = progression.chords.iter().map(|chord| chord.chord.clone()).collect();
+    apply_progression_edit(window, state, document_id, ProgressionEdit::Load { chords, key: progression.key.clone() })?;
+    Ok(progression)
+}
+
+/// Make a chord naming worksheet from a library entry
+#[tauri::command]
+pub fn create_library_worksheet(id: String, key: Option<String>) -> Result<WorksheetConfig, String> {
+    let entry = library::find_entry(&id).map_err(|e| format!("Failed to load library entry: {}", e))?;
+    library::entry_worksheet(entry, key.as_deref()).map_err(|e| format!("Failed to create worksheet: {}", e))
+}
@@ -1,43 +1,67 @@
-use std::fs;
-use std::process::Command;
-use tempfile::TempDir;
+// LilyPond rendering commands
+// Render jobs started from a window report their progress to it as RENDER_PROGRESS_EVENT
+
+use serde::Serialize;
+use tauri::{Emitter, Window};
 use uuid::Uuid;
 
+use crate::lilypond::{cancel_job, run_lilypond, RenderEvent, RenderJob};
+use crate::svg::{postprocess_svg, SvgOptions, SvgTheme};
+
+/// Event emitted to the calling window as a render job progresses
+pub const RENDER_PROGRESS_EVENT: &str = "render-progress";
+
+/// Payload of RENDER_PROGRESS_EVENT
+#[derive(Debug, Clone, Serialize)]
+struct RenderProgress {
+    job_id: String,
+    #[serde(flatten)]
+    event: RenderEvent,
+}
+
+/// Job reporting to a window with RENDER_PROGRESS_EVENT
+pub fn window_render_job(window: &Window, id: Option<String>) -> RenderJob {
+    let target = window.clone();
+    let job_id = id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    let payload_id = job_id.clone();
+    let listener = Box::new(move |event| {
+        let payload = RenderProgress { job_id: payload_id.clone(), event };
+        if let Err(e) = target.emit_to(target.label(), RENDER_PROGRESS_EVENT, &payload) {
+            eprintln!("Failed to emit render event: {}", e);
+        }
+    });
+    RenderJob::start(Some(job_id), Some(listener))
+}
+
+/// Render LilyPond notation to post-processed SVG on a blocking thread, leaving the async runtime free
+pub async fn render_svg(notation: String, theme: Option<SvgTheme>, mut job: RenderJob) -> Result<String, String> {
+    let svg_content = tauri::async_runtime::spawn_blocking(move || run_lilypond(&notation, &[], &mut job))
+        .await
+        .map_err(|e| format!("Render task failed: {}", e))??;
+    postprocess_svg(&svg_content, &SvgOptions { theme, ..SvgOptions::default() })
+}
+
+/// Render LilyPond notation to SVG
+/// Emits RENDER_PROGRESS_EVENT to the calling window under `job_id`, which cancel_render accepts
+#[tauri::command]
+pub async fn render_lilypond(
+    window: Window,
+    notation: String,
+    theme: Option<SvgTheme>,
+    job_id: Option<String>,
+) -> Result<String, String> {
+    render_svg(notation, theme, window_render_job(&window, job_id)).await
+}
+
+/// Stop a running render, killing its LilyPond process; the render fails with "Render cancelled"
+/// Returns false when no render with the id is running
+#[tauri::command]
+pub fn cancel_render(job_id: String) -> bool {
+    cancel_job(&job_id)
+}
+
+/// Drop every cached render, in memory and on disk
 #[tauri::command]
-pub async fn render_lilypond(notation: String) -> Result<String, String> {
-    // Create temporary directory
-    let temp_dir = TempDir::new().map_err(|e| format!("Failed to create temp directory: {}", e))?;
-    let temp_path = temp_dir.path();
-    
-    // Generate unique filename
-    let file_id = Uuid::new_v4().to_string();
-    let input_file = temp_path.join(format!("{}.ly", file_id));
-    let output_dir = temp_path.join("output");
-    
-    // Create output directory
-    fs::create_dir_all(&output_dir).map_err(|e| format!("Failed to create output directory: {}", e))?;
-    
-    // Write LilyPond notation to file
-    fs::write(&input_file, &notation).map_err(|e| format!("Failed to write input file: {}", e))?;
-    
-    // Execute LilyPond command
-    let output = Command::new("lilypond")
-        .arg("--svg")
-        .arg("-o")
-        .arg(&output_dir)
-        .arg(&input_file)
-        .output()
-        .map_err(|e| format!("Failed to execute lilypond: {}. Make sure LilyPond is installed and in PATH.", e))?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("LilyPond execution failed: {}", stderr));
-    }
-    
-    // Read the generated SVG file
-    let svg_file = output_dir.join(format!("{}.svg", file_id));
-    let svg_content = fs::read_to_string(&svg_file)
-        .map_err(|e| format!("Failed to read SVG output: {}", e))?;
-    
-    Ok(svg_content)
-}
\ No newline at end of file
+pub fn clear_render_cache() -> Result<(), String> {
+    crate::lilypond::clear_render_cache()
+}
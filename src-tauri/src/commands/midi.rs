@@ -0,0 +1,55 @@
+// MIDI input commands
+// Opens a keyboard for a window and streams the sounding notes and detected chord as events
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{Emitter, State, Window};
+
+use crate::midi::{self, describe_notes, HeldNotes, MidiInputConnection, MidiInputInfo};
+
+/// Event emitted to the window whenever the sounding notes change (payload: MidiInputEvent)
+pub const MIDI_INPUT_EVENT: &str = "midi-input";
+
+/// Managed state wrapper for the open MIDI input of each window
+pub struct MidiState(pub Mutex<HashMap<String, MidiInputConnection>>);
+
+/// List MIDI sources that can be opened
+#[tauri::command]
+pub fn list_midi_inputs() -> Result<Vec<MidiInputInfo>, String> {
+    midi::list_inputs()
+}
+
+/// Open a MIDI source for this window, replacing any input it already had
+/// Chords are spelled for the given key (default C)
+#[tauri::command]
+pub fn open_midi_input(
+    window: Window,
+    state: State<'_, MidiState>,
+    input_id: String,
+    key: Option<String>,
+) -> Result<(), String> {
+    let key = key.unwrap_or_else(|| "C".to_string());
+    let target = window.clone();
+    let mut held = HeldNotes::default();
+
+    let connection = midi::open_input(&input_id, move |message| {
+        if held.apply(message) {
+            let event = describe_notes(&held.sounding(), &key);
+            if let Err(e) = target.emit_to(target.label(), MIDI_INPUT_EVENT, &event) {
+                eprintln!("Failed to emit MIDI input: {}", e);
+            }
+        }
+    })?;
+
+    let mut inputs = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    inputs.insert(window.label().to_string(), connection);
+    Ok(())
+}
+
+/// Close this window's MIDI input
+#[tauri::command]
+pub fn close_midi_input(window: Window, state: State<'_, MidiState>) -> Result<(), String> {
+    let mut inputs = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    inputs.remove(window.label());
+    Ok(())
+}
@@ -1,5 +1,27 @@
+pub mod analytics;
+pub mod analysis;
 pub mod audio;
+pub mod autosave;
+pub mod battle;
+pub mod curriculum;
+pub mod diagnostics;
+pub mod documents;
+pub mod ear_training;
 pub mod export;
+pub mod history;
+pub mod library;
 pub mod lilypond;
+pub mod midi;
 pub mod music;
-pub mod worksheet;
\ No newline at end of file
+pub mod notation;
+pub mod ocr;
+pub mod policy;
+pub mod practice_stats;
+pub mod preview;
+pub mod project;
+pub mod quiz;
+pub mod recent_files;
+pub mod settings;
+pub mod song;
+pub mod worksheet;
+pub mod tutorial;
@@ -1,4 +1,29 @@
 use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::State;
+
+use crate::music::comparison::{self, ChordComparison};
+use crate::music::completion::{self, ProgressionCandidate};
+use crate::music::composition::{self, ComposedProgression, ProgressionConstraints};
+use crate::music::explanation::{self, ChordExplanation};
+use crate::music::guide_tones::{self, GuideToneLines};
+use crate::music::intervals::CHORD_INTERVAL_SPECS;
+use crate::music::identify::{self, ChordMatch, ChordMatchKind};
+use crate::music::key_detection::{self, KeyCandidate};
+use crate::music::progression_diff::{self, ProgressionChange, ProgressionMerge};
+use crate::music::progression_db::{ImportSummary, ProgressionDatabase};
+use crate::music::progression_stats::{NextMove, ProgressionSource};
+use crate::music::recommendations::{self, DEFAULT_RECOMMENDATIONS};
+use crate::music::playability::{self, PlayabilityOptions, PlayabilityReport};
+use crate::music::scales::{self, ScaleType};
+use crate::music::tiers::{self, TierClassification};
+use crate::music::types::{AudioNote, ChordRecommendation, ChordValidationResult, ParseMode};
+use crate::music::voice_leading_analysis::{self, VoiceLeadingReport};
+use crate::music::voice_motion::{self, VoiceTransition};
+use crate::types::song::SongChord;
+
+/// Managed state holding the progression statistics behind chord recommendations
+pub struct ProgressionDbState(pub Mutex<ProgressionDatabase>);
 
 /// A note with octave for rendering
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -8,12 +33,36 @@ pub struct PitchResult {
 }
 
 /// Request to generate chord pitches
+/// Either root, quality and inversion, or explicit pitches for a chord drawn by hand
 #[derive(Debug, Clone, Deserialize)]
 pub struct ChordRequest {
+    #[serde(default)]
     pub root: String,           // "C", "F#", "Bb"
+    #[serde(default)]
     pub quality: String,        // "maj", "min", "dim", "aug", "maj7", etc.
+    #[serde(default)]
     pub root_octave: u8,        // Octave for the root (bottom) note
     pub inversion: Option<String>, // "root", "first", "second", "third"
+    /// Spelled pitches in any order; when given, root, quality and inversion are ignored
+    /// and the chord is named by the detection engine
+    #[serde(default)]
+    pub pitches: Option<Vec<PitchResult>>,
+}
+
+/// Request to generate scale pitches
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScaleRequest {
+    pub root: String,           // "C", "F#", "Bb"
+    pub scale: String,          // "major", "harmonic_minor", "dorian", "blues", etc.
+    pub root_octave: u8,        // Octave for the tonic (bottom) note
+    pub descending: Option<bool>,
+}
+
+/// Response with generated scale pitches, tonic to tonic
+#[derive(Debug, Clone, Serialize)]
+pub struct ScaleResponse {
+    pub pitches: Vec<PitchResult>,
+    pub display_name: String,   // "F# harmonic minor"
 }
 
 /// Response with generated chord pitches
@@ -21,6 +70,8 @@ pub struct ChordRequest {
 pub struct ChordResponse {
     pub pitches: Vec<PitchResult>,
     pub display_name: String,   // "Cmaj7", "Dm", etc.
+    /// The pitches as notes for play_notes, lowest first
+    pub voicing: Vec<AudioNote>,
 }
 
 /// Map our UI quality names to Canon's suffix format
@@ -122,12 +173,98 @@ fn note_to_semitone(note: &str) -> Option<i32> {
     Some((base + modifier + 12) % 12)
 }
 
+/// Octave of a spelled note lying absolute_semitone above C of root_octave
+fn spelled_octave(note: &str, absolute_semitone: i32, root_octave: u8) -> u8 {
+    // Calculate octave based on absolute semitone
+    let base_octave = root_octave as i32 + (absolute_semitone / 12);
+    
+    // Adjust octave for enharmonic spellings:
+    // Cb is enharmonically B, so Cb/4 = B/3 (Cb needs octave +1 vs B)
+    // B# is enharmonically C, so B#/3 = C/4 (B# needs octave -1 vs C)
+    let note_semitone = note_to_semitone(note).unwrap_or(0);
+    let expected_semitone = absolute_semitone.rem_euclid(12);
+    
+    let octave_adjustment = if note_semitone == 11 && expected_semitone == 11 && note.starts_with('C') {
+        // Cb case: note is spelled as C-flat but sounds like B
+        // Cb/4 sounds like B/3, so we need to bump up the octave
+        1
+    } else if note_semitone == 0 && expected_semitone == 0 && note.starts_with('B') {
+        // B# case: note is spelled as B-sharp but sounds like C
+        // B#/3 sounds like C/4, so we need to reduce the octave
+        -1
+    } else {
+        0
+    };
+    
+    (base_octave + octave_adjustment) as u8
+}
+
+/// Sounding pitch of a spelled note in its written octave, in half steps from C0 (Cb4 sounds a half step below C4)
+fn written_semitone(pitch: &PitchResult) -> Option<i32> {
+    let letter = note_to_semitone(pitch.note.get(..1)?)?;
+    let modifier = note_to_semitone(&pitch.note)? - letter;
+    // note_to_semitone wraps; undo the wrap for Cb and B#
+    let modifier = match modifier {
+        m if m > 6 => m - 12,
+        m if m < -6 => m + 12,
+        m => m,
+    };
+    Some(pitch.octave as i32 * 12 + letter + modifier)
+}
+
+fn voicing_of(pitches: &[PitchResult]) -> Vec<AudioNote> {
+    pitches
+        .iter()
+        .map(|pitch| AudioNote {
+            note: pitch.note.clone(),
+            octave: pitch.octave as i8,
+            is_common_tone: false,
+            cents: 0.0,
+        })
+        .collect()
+}
+
+/// Name explicit pitches with the detection engine, with inversion figures as generate_chord_pitches gives them
+/// Pitches that form no known chord (clusters) are named by their notes
+fn chord_from_pitches(mut pitches: Vec<PitchResult>) -> Result<ChordResponse, String> {
+    if pitches.is_empty() {
+        return Err("Chord has no pitches".to_string());
+    }
+    if let Some(pitch) = pitches.iter().find(|pitch| written_semitone(pitch).is_none()) {
+        return Err(format!("Invalid note: {}", pitch.note));
+    }
+    pitches.sort_by_key(written_semitone);
+
+    let names: Vec<String> = pitches.iter().map(|pitch| pitch.note.clone()).collect();
+    let display_name = match identify::notes_to_chord_candidates(&names).into_iter().next() {
+        Some(found) if found.kind == ChordMatchKind::Slash => found.symbol,
+        Some(found) => {
+            let inversion = ["root", "first", "second", "third"].get(found.inversion as usize).copied();
+            let is_seventh = CHORD_INTERVAL_SPECS
+                .get(found.quality.as_str())
+                .is_some_and(|specs| specs.len() == 4 && specs.iter().any(|&(_, degree)| degree == 7));
+            match get_inversion_figures(inversion, is_seventh) {
+                ("", "") => format!("{}{}", found.root, found.quality),
+                (sup, sub) => format!("{}{}|{}|{}", found.root, found.quality, sup, sub),
+            }
+        }
+        None => names.join(" "),
+    };
+
+    Ok(ChordResponse { voicing: voicing_of(&pitches), pitches, display_name })
+}
+
 // Removed obsolete semitone_to_note function - now using diatonic spelling from music::intervals
 
-/// Generate chord pitches from root, quality, and octave
+/// Generate chord pitches from root, quality, and octave, or name explicitly given pitches
 #[tauri::command]
 pub fn generate_chord_pitches(request: ChordRequest) -> Result<ChordResponse, String> {
     use crate::music::intervals::{parse_chord_with_interval_specs, spell_interval_with_degree};
+    use crate::music::notes::preferred_spelling;
+
+    if let Some(pitches) = request.pitches {
+        return chord_from_pitches(pitches);
+    }
     
     let quality = normalize_quality(&request.quality);
     
@@ -148,33 +285,11 @@ pub fn generate_chord_pitches(request: ChordRequest) -> Result<ChordResponse, St
         
         // Use correct diatonic spelling with explicit scale degree
         let note = spell_interval_with_degree(&request.root, semitones, degree)
+            .map(|note| preferred_spelling(&note))
             .map_err(|e| format!("Spelling error: {}", e))?;
         
-        // Calculate octave based on absolute semitone
-        let base_octave = request.root_octave as i32 + (absolute_semitone / 12);
-        
-        // Adjust octave for enharmonic spellings:
-        // Cb is enharmonically B, so Cb/4 = B/3 (Cb needs octave +1 vs B)
-        // B# is enharmonically C, so B#/3 = C/4 (B# needs octave -1 vs C)
-        let note_semitone = note_to_semitone(&note).unwrap_or(0);
-        let expected_semitone = ((absolute_semitone % 12) + 12) % 12;
-        
-        let octave_adjustment = if note_semitone == 11 && expected_semitone == 11 && note.starts_with('C') {
-            // Cb case: note is spelled as C-flat but sounds like B
-            // Cb/4 sounds like B/3, so we need to bump up the octave
-            1
-        } else if note_semitone == 0 && expected_semitone == 0 && note.starts_with('B') {
-            // B# case: note is spelled as B-sharp but sounds like C
-            // B#/3 sounds like C/4, so we need to reduce the octave
-            -1
-        } else {
-            0
-        };
-        
-        pitches.push(PitchResult {
-            note,
-            octave: (base_octave + octave_adjustment) as u8,
-        });
+        let octave = spelled_octave(&note, absolute_semitone, request.root_octave);
+        pitches.push(PitchResult { note, octave });
     }
     
     let display_name = format_display_name(&request.root, &request.quality, request.inversion.as_deref());
@@ -206,11 +321,63 @@ pub fn generate_chord_pitches(request: ChordRequest) -> Result<ChordResponse, St
     }
     
     Ok(ChordResponse {
+        voicing: voicing_of(&pitches),
         pitches,
         display_name,
     })
 }
 
+/// Generate scale pitches from tonic, scale type, and octave
+/// Ascends one octave and repeats the tonic on top; descending reverses the order
+#[tauri::command]
+pub fn generate_scale_pitches(request: ScaleRequest) -> Result<ScaleResponse, String> {
+    let scale = ScaleType::from_name(&request.scale)
+        .ok_or_else(|| format!("Unknown scale type: {}", request.scale))?;
+    let root_semitone = note_to_semitone(&request.root)
+        .ok_or_else(|| format!("Invalid root note: {}", request.root))?;
+    let notes = scales::scale_to_notes(&request.root, scale)
+        .map_err(|e| format!("Failed to spell scale: {}", e))?;
+
+    let mut pitches: Vec<PitchResult> = notes
+        .into_iter()
+        .zip(scale.interval_specs())
+        .map(|(note, &(semitones, _))| {
+            let octave = spelled_octave(&note, root_semitone + semitones as i32, request.root_octave);
+            PitchResult { note, octave }
+        })
+        .collect();
+    if let Some(tonic) = pitches.first().cloned() {
+        pitches.push(PitchResult { octave: tonic.octave + 1, ..tonic });
+    }
+    if request.descending.unwrap_or(false) {
+        pitches.reverse();
+    }
+
+    Ok(ScaleResponse {
+        pitches,
+        display_name: format!("{} {}", request.root, scale.name()),
+    })
+}
+
+/// Get all available scale types
+#[tauri::command]
+pub fn get_scale_types() -> Vec<ScaleType> {
+    ScaleType::ALL.to_vec()
+}
+
+/// Validate a chord name or Roman numeral typed by the user
+/// Strict mode rejects non-textbook symbols and names the convention that was broken
+#[tauri::command]
+pub fn validate_chord(
+    input: String,
+    key: String,
+    use_flats: bool,
+    mode: Option<ParseMode>,
+) -> Result<ChordValidationResult, String> {
+    crate::music::chords::validate_chord_input(&input, &key, use_flats, mode.unwrap_or_default())
+        .map_err(|e| format!("Failed to validate chord: {}", e))
+}
+
 /// Get all available chord qualities
 #[tauri::command]
 pub fn get_chord_qualities() -> Vec<String> {
@@ -218,6 +385,159 @@ pub fn get_chord_qualities() -> Vec<String> {
     CHORD_INTERVAL_SPECS.keys().map(|s| s.to_string()).collect()
 }
 
+/// Score how playable a progression is on piano for a given voicing style
+/// Tempo defaults to 100 BPM with one chord per 4/4 bar
+#[tauri::command]
+pub fn score_playability(
+    progression: Vec<String>,
+    voicing_style: String,
+    tempo_bpm: Option<f32>,
+    beats_per_chord: Option<f32>,
+) -> Result<PlayabilityReport, String> {
+    let defaults = PlayabilityOptions::default();
+    let options = PlayabilityOptions {
+        tempo_bpm: tempo_bpm.unwrap_or(defaults.tempo_bpm),
+        beats_per_chord: beats_per_chord.unwrap_or(defaults.beats_per_chord),
+    };
+
+    playability::score_playability(&progression, &voicing_style, &options)
+        .map_err(|e| format!("Failed to score playability: {}", e))
+}
+
+/// Extend a partial progression to target_length chords ending on a cadence
+/// Returns several candidates (default 5), most conventional first
+#[tauri::command]
+pub fn complete_progression(
+    partial: Vec<String>,
+    key: String,
+    target_length: usize,
+    max_candidates: Option<usize>,
+) -> Result<Vec<ProgressionCandidate>, String> {
+    completion::complete_progression(&partial, &key, target_length, max_candidates.unwrap_or(5))
+        .map_err(|e| format!("Failed to complete progression: {}", e))
+}
+
+/// Generate progressions for a composition assignment that meet all of its constraints
+/// Returns several (default 5), most conventional first; fails when the constraints can't be met
+#[tauri::command]
+pub fn compose_progressions(
+    constraints: ProgressionConstraints,
+    max_results: Option<usize>,
+) -> Result<Vec<ComposedProgression>, String> {
+    composition::compose_progressions(&constraints, max_results.unwrap_or(5))
+        .map_err(|e| format!("Failed to compose progression: {}", e))
+}
+
+/// Classify a chord's tier (Safe/Colorful/Bold) for coloring chord blocks
+/// History is the chords placed before it, most recent last
+#[tauri::command]
+pub fn classify_tier(chord: String, key: String, history: Option<Vec<String>>) -> Result<TierClassification, String> {
+    tiers::classify_tier(&chord, &key, &history.unwrap_or_default())
+        .map_err(|e| format!("Failed to classify tier: {}", e))
+}
+
+/// Ranked suggestions for the next chord, with Roman numerals and tiers, from common progressions
+/// History is the chords placed so far, most recent last, and needs at least one chord
+#[tauri::command]
+pub fn get_chord_recommendations(
+    db: State<'_, ProgressionDbState>,
+    history: Vec<String>,
+    key: String,
+    use_flats: bool,
+    limit: Option<usize>,
+) -> Result<Vec<ChordRecommendation>, String> {
+    let db = db.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    recommendations::recommend_chords(&*db, &history, &key, use_flats, limit.unwrap_or(DEFAULT_RECOMMENDATIONS))
+        .map_err(|e| format!("Failed to recommend chords: {}", e))
+}
+
+/// Moves seen after an interval key (e.g. "m7_5_7"), most frequent first; empty for an unseen key
+#[tauri::command]
+pub fn query_progression_stats(db: State<'_, ProgressionDbState>, key: String) -> Result<Vec<NextMove>, String> {
+    let db = db.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    db.query(&key).map_err(|e| format!("Failed to query progression statistics: {}", e))
+}
+
+/// Import a JSON progression dataset, adding its counts to earlier imports (or replacing them)
+/// The bundled statistics are always kept; imports are saved with the app data
+#[tauri::command]
+pub fn import_progression_dataset(
+    db: State<'_, ProgressionDbState>,
+    json: String,
+    replace: bool,
+) -> Result<ImportSummary, String> {
+    let mut db = db.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    db.import(&json, replace).map_err(|e| format!("Failed to import progression dataset: {}", e))
+}
+
+/// Explain a chord's role in a key for tooltips, lessons and worksheet annotations
+/// Context is the chords placed before it, most recent last
+#[tauri::command]
+pub fn explain_chord(chord: String, key: String, context: Option<Vec<String>>) -> Result<ChordExplanation, String> {
+    explanation::explain_chord(&chord, &key, &context.unwrap_or_default())
+        .map_err(|e| format!("Failed to explain chord: {}", e))
+}
+
+/// Compare two chords: shared and differing tones, root interval, and subset relationship
+#[tauri::command]
+pub fn compare_chords(a: String, b: String) -> Result<ChordComparison, String> {
+    comparison::compare_chords(&a, &b).map_err(|e| format!("Failed to compare chords: {}", e))
+}
+
+/// Chord-level changes from one version of a progression to another
+#[tauri::command]
+pub fn diff_progressions(a: Vec<SongChord>, b: Vec<SongChord>) -> Vec<ProgressionChange> {
+    progression_diff::diff_progressions(&a, &b)
+}
+
+/// Three-way merge of two edits of the same progression, reporting chords both sides changed differently
+#[tauri::command]
+pub fn merge_progressions(base: Vec<SongChord>, ours: Vec<SongChord>, theirs: Vec<SongChord>) -> ProgressionMerge {
+    progression_diff::merge_progressions(&base, &ours, &theirs)
+}
+
+/// Name a set of notes (lowest first), ranking root position, inversion and slash readings
+/// Empty when the notes form no known chord
+#[tauri::command]
+pub fn identify_chord(notes: Vec<String>) -> Result<Vec<ChordMatch>, String> {
+    if notes.is_empty() {
+        return Err("Failed to identify chord: no notes given".to_string());
+    }
+    Ok(identify::notes_to_chord_candidates(&notes))
+}
+
+/// Rank the major and minor keys a progression is likely in, most likely first
+/// Each candidate says whether to spell the progression with flats
+#[tauri::command]
+pub fn detect_key(chords: Vec<String>) -> Result<Vec<KeyCandidate>, String> {
+    let candidates = key_detection::detect_key(&chords);
+    if candidates.is_empty() {
+        return Err("Failed to detect key: no readable chords".to_string());
+    }
+    Ok(candidates)
+}
+
+/// The 3rd and 7th lines through a progression, with the notes to play at each chord
+#[tauri::command]
+pub fn extract_guide_tones(progression: Vec<String>) -> Result<GuideToneLines, String> {
+    guide_tones::extract_guide_tones(&progression).map_err(|e| format!("Failed to extract guide tones: {}", e))
+}
+
+/// Which voices move, by how many half steps and which way, between consecutive voiced chords
+/// Takes chords as played by the voice-leading engine; note indices refer to each chord as given
+#[tauri::command]
+pub fn get_voice_motions(voicings: Vec<Vec<AudioNote>>) -> Result<Vec<VoiceTransition>, String> {
+    voice_motion::voice_motions(&voicings).map_err(|e| format!("Failed to trace voice motion: {}", e))
+}
+
+/// Parallel fifths and octaves, voice crossings, large leaps and total motion in a voiced progression
+/// Each chord's notes are given bass first, in voice order, as the engine plays them or a student wrote them
+#[tauri::command]
+pub fn analyze_progression_voicing(voicings: Vec<Vec<AudioNote>>) -> Result<VoiceLeadingReport, String> {
+    voice_leading_analysis::analyze_voice_leading(&voicings)
+        .map_err(|e| format!("Failed to analyze voice leading: {}", e))
+}
+
 // ============================================================================
 // Integration Tests
 // ============================================================================
@@ -234,6 +554,7 @@ mod tests {
             quality: "minor7".to_string(),
             root_octave: 3,
             inversion: None,
+            pitches: None,
         };
 
         let response = generate_chord_pitches(request).unwrap();
@@ -251,6 +572,32 @@ mod tests {
         assert_eq!(response.pitches[3].octave, 4);  // Eb
     }
 
+    #[test]
+    fn test_generate_scale_pitches() {
+        let request = ScaleRequest {
+            root: "B".to_string(),
+            scale: "major".to_string(),
+            root_octave: 3,
+            descending: None,
+        };
+
+        let response = generate_scale_pitches(request).unwrap();
+        let notes: Vec<String> = response.pitches.iter().map(|p| format!("{}{}", p.note, p.octave)).collect();
+        assert_eq!(notes, vec!["B3", "C#4", "D#4", "E4", "F#4", "G#4", "A#4", "B4"]);
+        assert_eq!(response.display_name, "B major");
+
+        let request = ScaleRequest {
+            root: "C#".to_string(),
+            scale: "major".to_string(),
+            root_octave: 4,
+            descending: Some(true),
+        };
+        let response = generate_scale_pitches(request).unwrap();
+        // B# sits just below C#5, still in octave 4
+        assert_eq!(response.pitches[1], PitchResult { note: "B#".to_string(), octave: 4 });
+        assert_eq!(response.pitches[7], PitchResult { note: "C#".to_string(), octave: 4 });
+    }
+
     #[test]
     fn test_generate_dbaug_pitches() {
         // User spec: augmented with flat root should naturalize
@@ -259,6 +606,7 @@ mod tests {
             quality: "augmented".to_string(),
             root_octave: 4,
             inversion: None,
+            pitches: None,
         };
 
         let response = generate_chord_pitches(request).unwrap();
@@ -275,6 +623,7 @@ mod tests {
             quality: "minor7".to_string(),
             root_octave: 3,
             inversion: None,
+            pitches: None,
         };
 
         let response = generate_chord_pitches(request).unwrap();
@@ -293,6 +642,7 @@ mod tests {
             quality: "major7".to_string(),
             root_octave: 3,
             inversion: None,
+            pitches: None,
         };
 
         let response = generate_chord_pitches(request).unwrap();
@@ -312,6 +662,7 @@ mod tests {
             quality: "major".to_string(),
             root_octave: 3,
             inversion: None,
+            pitches: None,
         };
 
         let response = generate_chord_pitches(request).unwrap();
@@ -335,6 +686,7 @@ mod tests {
             quality: "diminished".to_string(),
             root_octave: 4,
             inversion: None,
+            pitches: None,
         };
 
         let response = generate_chord_pitches(request).unwrap();
@@ -353,6 +705,7 @@ mod tests {
             quality: "half-diminished7".to_string(),
             root_octave: 3,
             inversion: None,
+            pitches: None,
         };
 
         let response = generate_chord_pitches(request).unwrap();
@@ -380,6 +733,7 @@ mod tests {
             quality: "minor".to_string(),
             root_octave: 3,
             inversion: None,
+            pitches: None,
         };
 
         let request2 = ChordRequest {
@@ -387,6 +741,7 @@ mod tests {
             quality: "min".to_string(),
             root_octave: 3,
             inversion: None,
+            pitches: None,
         };
 
         let response1 = generate_chord_pitches(request1).unwrap();
@@ -406,6 +761,7 @@ mod tests {
             quality: "major".to_string(),
             root_octave: 4,
             inversion: Some("first".to_string()),
+            pitches: None,
         };
 
         let response = generate_chord_pitches(request).unwrap();
@@ -420,6 +776,34 @@ mod tests {
         assert_eq!(response.pitches[2].octave, 4);  // Stays at original octave
     }
 
+    #[test]
+    fn test_chord_from_explicit_pitches() {
+        let pitch = |note: &str, octave| PitchResult { note: note.to_string(), octave };
+        let request = |pitches| ChordRequest {
+            root: String::new(),
+            quality: String::new(),
+            root_octave: 0,
+            inversion: None,
+            pitches: Some(pitches),
+        };
+
+        // Drawn top down: the Cb sounds below C4, so it is the bass of an Ab minor first inversion
+        let response = generate_chord_pitches(request(vec![pitch("Ab", 4), pitch("Eb", 4), pitch("Cb", 4)])).unwrap();
+        let notes: Vec<String> = response.pitches.iter().map(|p| format!("{}{}", p.note, p.octave)).collect();
+        assert_eq!(notes, ["Cb4", "Eb4", "Ab4"]);
+        assert_eq!(response.display_name, "Abm|6|");
+        assert_eq!(response.voicing[0].note, "Cb");
+
+        let dominant = vec![pitch("G", 3), pitch("B", 3), pitch("D", 4), pitch("F", 4)];
+        assert_eq!(generate_chord_pitches(request(dominant)).unwrap().display_name, "G7");
+        let response = generate_chord_pitches(request(vec![pitch("C", 4), pitch("D", 4), pitch("Eb", 4)])).unwrap();
+        assert_eq!(response.display_name, "C D Eb");
+        assert_eq!(response.voicing.len(), 3);
+
+        assert!(generate_chord_pitches(request(vec![])).is_err());
+        assert!(generate_chord_pitches(request(vec![pitch("H", 4)])).is_err());
+    }
+
     #[test]
     fn test_half_diminished_inversion_with_cb() {
         // F half-diminished: F-Ab-Cb-Eb
@@ -429,6 +813,7 @@ mod tests {
             quality: "half-diminished7".to_string(),
             root_octave: 3,
             inversion: Some("first".to_string()),
+            pitches: None,
         };
 
         let response = generate_chord_pitches(request).unwrap();
@@ -0,0 +1,116 @@
+// Notation layout commands
+// Structured layout data for drawing notation outside LilyPond
+
+use serde::Serialize;
+
+use super::lilypond::render_svg;
+use crate::lilypond::RenderJob;
+use crate::music::guide_tones::extract_guide_tones;
+use crate::music::types::AudioNote;
+use crate::notation::beaming::{self, RhythmLayout, RhythmNote};
+use crate::notation::description::describe_chord;
+use crate::notation::key_signature::{self, KeySignatureLayout, StaffClef};
+use crate::notation::keyboard;
+use crate::notation::voicing;
+use crate::svg::{apply_theme, SvgTheme};
+
+/// Pitch rhythm-only LilyPond output is written at (treble middle line)
+const RHYTHM_PITCH: &str = "b'";
+/// Keyboard diagram voicing and starting octave when none is given
+const DIAGRAM_VOICING: &str = "close";
+const DIAGRAM_OCTAVE: i8 = 4;
+
+/// Rhythm layout together with equivalent LilyPond input
+#[derive(Debug, Clone, Serialize)]
+pub struct RhythmRendering {
+    pub layout: RhythmLayout,
+    /// Notes with explicit beams ([ ]) and stem directions
+    pub lilypond: String,
+}
+
+/// Voiced chords on a grand staff, as LilyPond input and rendered SVG
+#[derive(Debug, Clone, Serialize)]
+pub struct VoicingRendering {
+    pub lilypond: String,
+    pub svg: String,
+    /// Screen-reader text for each chord, in order
+    pub descriptions: Vec<String>,
+}
+
+/// Chord drawn on a small keyboard, with the notes the voicing engine chose
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyboardDiagram {
+    pub svg: String,
+    /// Highlighted MIDI notes, lowest first
+    pub notes: Vec<u8>,
+}
+
+/// Draw a chord on a piano keyboard, voiced as the voicing engine plays it
+/// Defaults to a close voicing from middle C's octave; the theme recolors the outlines and black keys
+#[tauri::command]
+pub fn render_keyboard_diagram(
+    chord: String,
+    voicing_style: Option<String>,
+    base_octave: Option<i8>,
+    theme: Option<SvgTheme>,
+) -> Result<KeyboardDiagram, String> {
+    let style = voicing_style.as_deref().unwrap_or(DIAGRAM_VOICING);
+    let mut notes = keyboard::voiced_chord_midi(chord.trim(), style, base_octave.unwrap_or(DIAGRAM_OCTAVE))
+        .map_err(|e| format!("Failed to voice {}: {}", chord, e))?;
+    notes.sort_unstable();
+    let svg = keyboard::keyboard_svg(&notes).ok_or_else(|| format!("Failed to voice {}: no notes", chord))?;
+    let svg = match theme {
+        Some(theme) => apply_theme(&svg, &theme)?,
+        None => svg,
+    };
+    Ok(KeyboardDiagram { svg, notes })
+}
+
+/// Get the accidentals of a key signature with their staff positions on a clef
+#[tauri::command]
+pub fn get_key_signature_layout(key: String, clef: StaffClef) -> Result<KeySignatureLayout, String> {
+    key_signature::get_key_signature_layout(&key, clef).map_err(|e| format!("Failed to lay out key signature: {}", e))
+}
+
+/// Group a rhythm into beams and compute stem directions
+#[tauri::command]
+pub fn get_rhythm_layout(time_signature: String, notes: Vec<RhythmNote>) -> Result<RhythmRendering, String> {
+    let layout =
+        beaming::layout_rhythm(&time_signature, &notes).map_err(|e| format!("Failed to lay out rhythm: {}", e))?;
+    let lilypond = beaming::to_lilypond(&layout, &notes, RHYTHM_PITCH);
+    Ok(RhythmRendering { layout, lilypond })
+}
+
+/// Render voiced chords (as played by the voice-leading engine) onto a grand staff
+/// Labels, usually chord symbols, are printed above the chord at the same index
+#[tauri::command]
+pub async fn render_voicing(
+    chords: Vec<Vec<AudioNote>>,
+    key: Option<String>,
+    labels: Option<Vec<String>>,
+    theme: Option<SvgTheme>,
+) -> Result<VoicingRendering, String> {
+    if chords.is_empty() {
+        return Err("Failed to render voicing: no chords given".to_string());
+    }
+    let lilypond = voicing::voicing_to_lilypond(&chords, key.as_deref(), &labels.unwrap_or_default());
+    let svg = render_svg(lilypond.clone(), theme, RenderJob::background()).await?;
+    let descriptions = chords.iter().map(|chord| describe_chord(chord)).collect();
+    Ok(VoicingRendering { lilypond, svg, descriptions })
+}
+
+/// Render a progression's guide-tone lines as two voices on one staff, chord symbols above
+/// As an exercise only the first chord's guide tones are printed, for students to continue the lines
+#[tauri::command]
+pub async fn render_guide_tones(
+    progression: Vec<String>,
+    key: Option<String>,
+    exercise: Option<bool>,
+    theme: Option<SvgTheme>,
+) -> Result<VoicingRendering, String> {
+    let lines = extract_guide_tones(&progression).map_err(|e| format!("Failed to extract guide tones: {}", e))?;
+    let lilypond = voicing::guide_tones_to_lilypond(&lines, key.as_deref(), exercise.unwrap_or(false));
+    let svg = render_svg(lilypond.clone(), theme, RenderJob::background()).await?;
+    let descriptions = lines.playback.iter().map(|chord| describe_chord(chord)).collect();
+    Ok(VoicingRendering { lilypond, svg, descriptions })
+}
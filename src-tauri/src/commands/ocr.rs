@@ -0,0 +1,139 @@
+// Chord chart import from photographs
+// Runs tesseract over an image and repairs the recognized tokens into a progression
+
+use serde::Serialize;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::music::chord_correction::{self, ChordCorrection};
+
+/// Tokens below this confidence are flagged for the user to review
+const REVIEW_CONFIDENCE: f32 = 0.7;
+
+/// How long tesseract may take over one image before it is stopped
+const OCR_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often a running tesseract process is checked for the timeout
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Result of importing a photographed chord chart
+#[derive(Debug, Clone, Serialize)]
+pub struct ChordChartImport {
+    /// Recognized chords in reading order, ready for editing
+    pub progression: Vec<String>,
+    /// Per-chord corrections with confidence
+    pub chords: Vec<ChordCorrection>,
+    /// Indices into chords that should be double-checked
+    pub needs_review: Vec<usize>,
+    /// Raw OCR text for troubleshooting
+    pub raw_text: String,
+}
+
+/// Build an import result from OCR text
+fn build_import(raw_text: String) -> ChordChartImport {
+    let chords = chord_correction::extract_progression(&raw_text);
+    let progression = chords.iter().map(|c| c.chord.clone()).collect();
+    let needs_review = chords
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.confidence < REVIEW_CONFIDENCE)
+        .map(|(i, _)| i)
+        .collect();
+
+    ChordChartImport {
+        progression,
+        chords,
+        needs_review,
+        raw_text,
+    }
+}
+
+/// Read a child's pipe to the end on its own thread
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let mut text = String::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_string(&mut text);
+        }
+        text
+    })
+}
+
+/// Text tesseract recognizes in an image, killing it when it runs past the timeout
+/// Pass the image as an absolute path, so a name starting with "-" can't be read as an option
+fn run_tesseract(image: &Path, timeout: Duration) -> Result<String, String> {
+    // --psm 6 treats the page as a single block of text, which suits chord charts
+    let mut child = Command::new("tesseract")
+        .arg(image)
+        .arg("stdout")
+        .arg("--psm")
+        .arg("6")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to execute tesseract: {}. Make sure Tesseract OCR is installed and in PATH.", e))?;
+
+    // Both pipes are drained while waiting, so a chatty run can't block on a full pipe
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| format!("Failed to wait for tesseract: {}", e))? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("Tesseract timed out after {} seconds", timeout.as_secs()));
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    if !status.success() {
+        return Err(format!("Tesseract execution failed: {}", stderr.join().unwrap_or_default()));
+    }
+    Ok(stdout.join().unwrap_or_default())
+}
+
+/// Run OCR over a chord chart photo and return an editable progression
+/// Requires tesseract to be installed and in PATH; it runs on a blocking thread, leaving the async runtime free
+#[tauri::command]
+pub async fn import_chord_chart(image_path: String) -> Result<ChordChartImport, String> {
+    let image = fs::canonicalize(&image_path).map_err(|e| format!("Image not found: {}: {}", image_path, e))?;
+    let raw_text = tauri::async_runtime::spawn_blocking(move || run_tesseract(&image, OCR_TIMEOUT))
+        .await
+        .map_err(|e| format!("OCR task failed: {}", e))??;
+    let import = build_import(raw_text);
+
+    if import.progression.is_empty() {
+        return Err("No chord symbols were recognized in the image".to_string());
+    }
+
+    Ok(import)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_import_flags_low_confidence() {
+        let import = build_import("C  Am7  Fmaj9#l1  Gsus3".to_string());
+
+        assert_eq!(import.progression.len(), 4);
+        assert_eq!(import.progression[0], "C");
+        assert!(import.needs_review.contains(&3));
+        assert!(!import.needs_review.contains(&0));
+    }
+
+    #[test]
+    fn test_import_needs_an_existing_image() {
+        let missing = tauri::async_runtime::block_on(import_chord_chart("-no-such-chart.png".to_string()));
+        assert!(missing.unwrap_err().starts_with("Image not found"));
+    }
+}
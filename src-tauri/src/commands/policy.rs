@@ -0,0 +1,51 @@
+// Policy enforcement for commands
+// Commands that write files or change settings check the deployment policy first
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::settings::{Feature, Policy};
+
+/// Managed state wrapper for the policy loaded at startup (fixed for the session)
+pub struct PolicyState(pub Policy);
+
+/// Structured error for commands guarded by the policy
+/// Lets the frontend tell a disabled feature apart from an ordinary failure
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CommandError {
+    FeatureDisabled { feature: Feature, message: String },
+    Failed { message: String },
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Failed { message }
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        CommandError::Failed { message: message.to_string() }
+    }
+}
+
+impl PolicyState {
+    /// Fail with FeatureDisabled if the policy turns the feature off
+    pub fn check(&self, feature: Feature) -> Result<(), CommandError> {
+        if self.0.allows(feature) {
+            Ok(())
+        } else {
+            Err(CommandError::FeatureDisabled {
+                feature,
+                message: format!("{} is disabled on this computer", feature.description()),
+            })
+        }
+    }
+}
+
+/// Get the active policy so the UI can hide disabled features
+#[tauri::command]
+pub fn get_policy(policy: State<'_, PolicyState>) -> Policy {
+    policy.0.clone()
+}
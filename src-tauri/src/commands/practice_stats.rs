@@ -0,0 +1,42 @@
+// Practice statistics commands
+// Exercises report each answered question; dashboards read progress and streaks back
+
+use std::sync::Mutex;
+use tauri::State;
+
+use super::policy::PolicyState;
+use crate::practice_stats::{AttemptRecord, ExerciseProgress, PracticeStats, ProgressSummary, Streaks};
+use crate::settings::Feature;
+
+/// Managed state wrapper for the practice statistics
+pub struct PracticeStatsState(pub Mutex<PracticeStats>);
+
+/// Count an answered question; returns the exercise's progress, with a difficulty suggestion
+#[tauri::command]
+pub fn record_attempt(
+    state: State<'_, PracticeStatsState>,
+    policy: State<'_, PolicyState>,
+    attempt: AttemptRecord,
+) -> Result<ExerciseProgress, String> {
+    let mut stats = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let progress = stats.record(&attempt)?;
+    // Tracking is incidental, so a locked policy keeps it for this session only instead of failing
+    if policy.0.allows(Feature::Settings) {
+        stats.save()?;
+    }
+    Ok(progress)
+}
+
+/// Accuracy and timing of every exercise type practiced
+#[tauri::command]
+pub fn get_progress_summary(state: State<'_, PracticeStatsState>) -> Result<ProgressSummary, String> {
+    let stats = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(stats.summary())
+}
+
+/// Practice-day streaks in the student's time zone and answer streaks per exercise type
+#[tauri::command]
+pub fn get_streaks(state: State<'_, PracticeStatsState>, utc_offset_minutes: Option<i32>) -> Result<Streaks, String> {
+    let stats = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(stats.streaks(utc_offset_minutes.unwrap_or(0)))
+}
@@ -0,0 +1,173 @@
+// Debounced worksheet preview rendering
+// Newest request wins: a preview superseded while waiting or rendering is dropped
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Window};
+
+use super::worksheet::{render_worksheet, WorksheetResponse};
+use crate::documents::{self, DocumentMap};
+use crate::lilypond::RenderJob;
+use crate::svg::SvgTheme;
+use crate::types::worksheet::WorksheetConfig;
+
+/// Quiet period before a preview renders; requests arriving within it replace it
+const PREVIEW_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Result of a preview request
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PreviewResult {
+    Rendered(WorksheetResponse),
+    /// A newer preview for the same document replaced this one
+    Superseded,
+}
+
+/// Managed state: latest preview request per document, and a lock so one preview renders at a time
+#[derive(Default)]
+pub struct PreviewState {
+    latest: Mutex<DocumentMap<u64>>,
+    render_lock: Mutex<()>,
+}
+
+impl PreviewState {
+    /// Register a request for a document, superseding any earlier ones
+    fn begin(&self, document: &str) -> Result<u64, String> {
+        let mut latest = self.latest.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let ticket = latest.entry(document);
+        *ticket += 1;
+        Ok(*ticket)
+    }
+
+    fn is_current(&self, document: &str, ticket: u64) -> bool {
+        self.latest
+            .lock()
+            .is_ok_and(|latest| latest.get(document) == Some(&ticket))
+    }
+
+    /// Wait out the debounce period, then render unless a newer request came in
+    fn run(
+        &self,
+        document: &str,
+        ticket: u64,
+        debounce: Duration,
+        render: impl FnOnce() -> Result<WorksheetResponse, String>,
+    ) -> Result<PreviewResult, String> {
+        thread::sleep(debounce);
+        if !self.is_current(document, ticket) {
+            return Ok(PreviewResult::Superseded);
+        }
+
+        let _rendering = self.render_lock.lock().map_err(|e| format!("Lock error: {}", e))?;
+        // A newer request may have arrived while another preview held the renderer
+        if !self.is_current(document, ticket) {
+            return Ok(PreviewResult::Superseded);
+        }
+        let response = render()?;
+
+        if self.is_current(document, ticket) {
+            Ok(PreviewResult::Rendered(response))
+        } else {
+            Ok(PreviewResult::Superseded)
+        }
+    }
+
+    /// Forget a closed document
+    pub fn remove(&self, document: &str) {
+        if let Ok(mut latest) = self.latest.lock() {
+            latest.remove(document);
+        }
+    }
+
+    /// Forget every document in a closed window
+    pub fn remove_window(&self, window_label: &str) {
+        if let Ok(mut latest) = self.latest.lock() {
+            latest.remove_window(window_label);
+        }
+    }
+}
+
+/// Render a worksheet preview, debounced per document
+/// Only the newest request for a document renders; older ones resolve as superseded
+#[tauri::command]
+pub async fn preview_worksheet(
+    app: AppHandle,
+    window: Window,
+    document_id: Option<String>,
+    config: WorksheetConfig,
+    theme: Option<SvgTheme>,
+) -> Result<PreviewResult, String> {
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    let ticket = app.state::<PreviewState>().begin(&document)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<PreviewState>()
+            .run(&document, ticket, PREVIEW_DEBOUNCE, || {
+                render_worksheet(&config, theme, &mut RenderJob::background())
+            })
+    })
+    .await
+    .map_err(|e| format!("Preview task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn response(svg: &str) -> WorksheetResponse {
+        WorksheetResponse { svg_content: svg.to_string(), interactive_elements: Vec::new(), descriptions: Vec::new() }
+    }
+
+    #[test]
+    fn test_single_request_renders() {
+        let state = PreviewState::default();
+        let ticket = state.begin("main").unwrap();
+        let result = state.run("main", ticket, Duration::ZERO, || Ok(response("<svg/>"))).unwrap();
+        assert!(matches!(result, PreviewResult::Rendered(r) if r.svg_content == "<svg/>"));
+    }
+
+    #[test]
+    fn test_newer_request_supersedes_waiting_one() {
+        let state = Arc::new(PreviewState::default());
+        let first = state.begin("main").unwrap();
+
+        let waiting = {
+            let state = Arc::clone(&state);
+            thread::spawn(move || {
+                state.run("main", first, Duration::from_millis(50), || panic!("superseded preview must not render"))
+            })
+        };
+        let second = state.begin("main").unwrap();
+
+        assert!(matches!(waiting.join().unwrap().unwrap(), PreviewResult::Superseded));
+        let result = state.run("main", second, Duration::ZERO, || Ok(response("new"))).unwrap();
+        assert!(matches!(result, PreviewResult::Rendered(_)));
+    }
+
+    #[test]
+    fn test_request_superseded_during_render_is_dropped() {
+        let state = PreviewState::default();
+        let ticket = state.begin("main").unwrap();
+        let result = state
+            .run("main", ticket, Duration::ZERO, || {
+                state.begin("main").unwrap();
+                Ok(response("stale"))
+            })
+            .unwrap();
+        assert!(matches!(result, PreviewResult::Superseded));
+    }
+
+    #[test]
+    fn test_documents_are_independent() {
+        let state = PreviewState::default();
+        let a = state.begin("main/a").unwrap();
+        state.begin("main/b").unwrap();
+        assert!(state.is_current("main/a", a));
+
+        state.remove_window("main");
+        assert!(!state.is_current("main/a", a));
+    }
+}
@@ -0,0 +1,60 @@
+// Project commands
+// Save the user's working state to a .maestro file and open it again, upgrading files from older versions
+
+use serde::Serialize;
+use std::fs;
+use tauri::{AppHandle, State};
+use tauri_plugin_dialog::{DialogExt, FilePath};
+
+use super::policy::{CommandError, PolicyState};
+use crate::settings::Feature;
+use crate::types::project::{Project, PROJECT_EXTENSION};
+use crate::types::versioned;
+
+/// A project opened from disk
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadedProject {
+    pub project: Project,
+    pub path: String,
+    /// Version the file was upgraded from; the UI should offer to save it so it is upgraded in place
+    pub upgraded_from: Option<u32>,
+}
+
+/// Save a project through a save dialog
+/// Returns the path written, or None when the dialog is cancelled
+#[tauri::command]
+pub async fn save_project(
+    app: AppHandle,
+    policy: State<'_, PolicyState>,
+    project: Project,
+) -> Result<Option<String>, CommandError> {
+    policy.check(Feature::Export)?;
+
+    let json = versioned::to_string_pretty(&project)?;
+    let name = if project.name.trim().is_empty() { "Untitled" } else { project.name.trim() };
+    let file_path = app
+        .dialog()
+        .file()
+        .add_filter("Maestro Project", &[PROJECT_EXTENSION])
+        .add_filter("JSON", &["json"])
+        .set_file_name(format!("{}.{}", name, PROJECT_EXTENSION))
+        .set_title("Save Project")
+        .blocking_save_file();
+
+    let path = match file_path {
+        Some(FilePath::Path(p)) => p,
+        Some(_) => return Err("Invalid file path".into()),
+        None => return Ok(None), // User cancelled
+    };
+
+    fs::write(&path, json).map_err(|e| format!("Failed to write project: {}", e))?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// Open a saved project (.maestro or .json), upgrading older versions
+#[tauri::command]
+pub fn load_project(path: String) -> Result<LoadedProject, String> {
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read project: {}", e))?;
+    let loaded = versioned::from_slice::<Project>(&bytes)?;
+    Ok(LoadedProject { project: loaded.value, path, upgraded_from: loaded.upgraded_from })
+}
@@ -0,0 +1,142 @@
+// Name-that-chord quiz commands
+// Ties the quiz engine to audio playback and the persisted high score table
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::{State, Window};
+
+use super::audio::{play_notes_internal, AudioState};
+use super::policy::PolicyState;
+use super::settings::SettingsState;
+use crate::music::types::VoicingStyle;
+use crate::music::voice_leading;
+use crate::settings::Feature;
+use crate::training::grading::{self, GradeResult, GradingOptions};
+use crate::training::quiz::{
+    record_high_score, HighScore, QuizAnswerResult, QuizConfig, QuizGame, QuizQuestion, QuizSummary,
+};
+
+/// Octave quiz chords are voiced from
+const QUIZ_BASE_OCTAVE: i8 = 3;
+
+/// Managed state wrapper for the running quiz (None when no game is active)
+pub struct QuizState(pub Mutex<Option<QuizGame>>);
+
+/// Result of finishing a game
+#[derive(Debug, Clone, Serialize)]
+pub struct QuizFinish {
+    pub summary: QuizSummary,
+    /// 0-based position in the high score table, if the game made it
+    pub rank: Option<usize>,
+    pub high_scores: Vec<HighScore>,
+}
+
+/// Voice and play a set of chord notes in the quiz window
+pub fn play_quiz_chord(window: &Window, audio: &AudioState, notes: &[String]) -> Result<(), String> {
+    let bass_note = notes.first().cloned().unwrap_or_default();
+    let voiced = voice_leading::voice_chord(notes, &bass_note, QUIZ_BASE_OCTAVE, VoicingStyle::Close)
+        .map_err(|e| format!("Voice leading failed: {}", e))?;
+    play_notes_internal(audio, window.label(), voiced, true)
+}
+
+/// Start a new quiz game (replaces any running game)
+#[tauri::command]
+pub fn start_chord_quiz(quiz: State<'_, QuizState>, config: Option<QuizConfig>) -> Result<(), String> {
+    let game = QuizGame::new(config.unwrap_or_default())?;
+    let mut guard = quiz.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    *guard = Some(game);
+    Ok(())
+}
+
+/// Pick and play the next quiz chord
+#[tauri::command]
+pub fn next_quiz_question(
+    window: Window,
+    audio: State<'_, AudioState>,
+    quiz: State<'_, QuizState>,
+) -> Result<QuizQuestion, String> {
+    let (question, notes) = {
+        let mut guard = quiz.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let game = guard.as_mut().ok_or("No quiz is running")?;
+        game.next_question(&mut rand::thread_rng())?
+    };
+
+    play_quiz_chord(&window, &audio, &notes)?;
+    Ok(question)
+}
+
+/// Play the current quiz chord again
+#[tauri::command]
+pub fn replay_quiz_question(
+    window: Window,
+    audio: State<'_, AudioState>,
+    quiz: State<'_, QuizState>,
+) -> Result<(), String> {
+    let notes = {
+        let guard = quiz.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let game = guard.as_ref().ok_or("No quiz is running")?;
+        game.current_notes().ok_or("No question is waiting for an answer")?
+    };
+
+    play_quiz_chord(&window, &audio, &notes)
+}
+
+/// Submit an answer to the current quiz question
+#[tauri::command]
+pub fn submit_quiz_answer(
+    quiz: State<'_, QuizState>,
+    question_id: String,
+    answer: String,
+) -> Result<QuizAnswerResult, String> {
+    let mut guard = quiz.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let game = guard.as_mut().ok_or("No quiz is running")?;
+    game.answer_at(&question_id, &answer, Instant::now())
+}
+
+/// End the running quiz and record its score in the high score table
+#[tauri::command]
+pub fn finish_chord_quiz(
+    quiz: State<'_, QuizState>,
+    settings: State<'_, SettingsState>,
+    policy: State<'_, PolicyState>,
+) -> Result<QuizFinish, String> {
+    let game = quiz
+        .0
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?
+        .take()
+        .ok_or("No quiz is running")?;
+    let summary = game.summary();
+
+    let achieved_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let mut store = settings.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    // A locked policy keeps the table as is; the summary is still reported
+    if !policy.0.allows(Feature::Settings) {
+        let high_scores = store.settings().quiz_high_scores.clone();
+        return Ok(QuizFinish { summary, rank: None, high_scores });
+    }
+    let (rank, high_scores) = store.update(|s| {
+        let rank = record_high_score(&mut s.quiz_high_scores, &summary, achieved_at);
+        (rank, s.quiz_high_scores.clone())
+    })?;
+
+    Ok(QuizFinish { summary, rank, high_scores })
+}
+
+/// Get the persisted quiz high scores (best first)
+#[tauri::command]
+pub fn get_quiz_high_scores(settings: State<'_, SettingsState>) -> Result<Vec<HighScore>, String> {
+    let store = settings.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(store.settings().quiz_high_scores.clone())
+}
+
+/// Grade a free-text note or chord answer leniently, with feedback on the mistake
+#[tauri::command]
+pub fn grade_answer(answer: String, expected: String, options: Option<GradingOptions>) -> GradeResult {
+    grading::grade_answer(&answer, &expected, &options.unwrap_or_default())
+}
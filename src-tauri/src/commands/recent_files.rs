@@ -0,0 +1,48 @@
+// Recent files commands
+// The list behind File → Open Recent
+
+use std::sync::Mutex;
+use tauri::State;
+
+use super::policy::{CommandError, PolicyState};
+use crate::recent_files::{RecentFilesStore, RecentProject};
+use crate::settings::Feature;
+
+/// Managed state wrapper for the recent files list
+pub struct RecentFilesState(pub Mutex<RecentFilesStore>);
+
+/// Recent projects, pinned first, then most recent first
+#[tauri::command]
+pub fn get_recent_projects(state: State<'_, RecentFilesState>) -> Result<Vec<RecentProject>, String> {
+    let store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(store.projects())
+}
+
+/// Record that a project was opened or saved, moving it to the top of the list
+#[tauri::command]
+pub fn add_recent_project(
+    state: State<'_, RecentFilesState>,
+    policy: State<'_, PolicyState>,
+    path: String,
+    name: Option<String>,
+) -> Result<Vec<RecentProject>, String> {
+    let mut store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    // Tracking is incidental, so a locked policy skips it instead of failing
+    if !policy.0.allows(Feature::Settings) {
+        return Ok(store.projects());
+    }
+    store.add(&path, name.as_deref())
+}
+
+/// Pin or unpin a recent project
+#[tauri::command]
+pub fn pin_recent_project(
+    state: State<'_, RecentFilesState>,
+    policy: State<'_, PolicyState>,
+    path: String,
+    pinned: bool,
+) -> Result<Vec<RecentProject>, CommandError> {
+    policy.check(Feature::Settings)?;
+    let mut store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(store.pin(&path, pinned)?)
+}
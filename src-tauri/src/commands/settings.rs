@@ -0,0 +1,252 @@
+// Settings store commands
+// Recently used chords and favorites for the chord palette
+
+use std::sync::Mutex;
+use tauri::State;
+
+use super::analytics::{record_event, AnalyticsState};
+use crate::analytics::AnalyticsEvent;
+use crate::audio::{self, DEFAULT_VOICE_LIMIT, VOICE_LIMIT_RANGE};
+use crate::lilypond::{self, DEFAULT_RENDER_TIMEOUT_SECS};
+use crate::music::aliases::{self, AliasDictionary};
+use crate::music::interval_encoding::{builtin_alias_dictionary, is_builtin_suffix};
+use crate::music::notes;
+use crate::music::tiers::{self, TierThresholds};
+use super::policy::{CommandError, PolicyState};
+use crate::settings::{ChordVocabulary, Feature, SettingsStore};
+
+/// Managed state wrapper for the persistent settings store
+pub struct SettingsState(pub Mutex<SettingsStore>);
+
+/// Get recent and favorite chords/qualities
+#[tauri::command]
+pub fn get_chord_vocabulary(state: State<'_, SettingsState>) -> Result<ChordVocabulary, String> {
+    let store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(store.settings().vocabulary.clone())
+}
+
+/// Record that a chord was used (adds it and its quality to the recent lists)
+#[tauri::command]
+pub fn record_chord_usage(
+    state: State<'_, SettingsState>,
+    analytics: State<'_, AnalyticsState>,
+    policy: State<'_, PolicyState>,
+    chord: String,
+    key: Option<String>,
+) -> Result<ChordVocabulary, String> {
+    record_event(&analytics, AnalyticsEvent::ChordTried { chord: chord.clone(), key });
+
+    let mut store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    // Usage tracking is incidental, so a locked policy skips it instead of failing
+    if !policy.0.allows(Feature::Settings) {
+        return Ok(store.settings().vocabulary.clone());
+    }
+    store.update(|s| {
+        s.vocabulary.record_chord(&chord);
+        s.vocabulary.clone()
+    })
+}
+
+/// Pin or unpin a chord as a favorite
+#[tauri::command]
+pub fn set_favorite_chord(
+    state: State<'_, SettingsState>,
+    policy: State<'_, PolicyState>,
+    chord: String,
+    favorite: bool,
+) -> Result<ChordVocabulary, CommandError> {
+    policy.check(Feature::Settings)?;
+    let mut store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(store.update(|s| {
+        s.vocabulary.set_favorite_chord(&chord, favorite);
+        s.vocabulary.clone()
+    })?)
+}
+
+/// Pin or unpin a chord quality (suffix) as a favorite
+#[tauri::command]
+pub fn set_favorite_quality(
+    state: State<'_, SettingsState>,
+    policy: State<'_, PolicyState>,
+    quality: String,
+    favorite: bool,
+) -> Result<ChordVocabulary, CommandError> {
+    policy.check(Feature::Settings)?;
+    let mut store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(store.update(|s| {
+        s.vocabulary.set_favorite_quality(&quality, favorite);
+        s.vocabulary.clone()
+    })?)
+}
+
+/// Clear recently used chords and qualities (favorites are kept)
+#[tauri::command]
+pub fn clear_recent_chords(
+    state: State<'_, SettingsState>,
+    policy: State<'_, PolicyState>,
+) -> Result<ChordVocabulary, CommandError> {
+    policy.check(Feature::Settings)?;
+    let mut store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(store.update(|s| {
+        s.vocabulary.clear_recent();
+        s.vocabulary.clone()
+    })?)
+}
+
+/// Get the user alias dictionary currently merged with the built-ins
+#[tauri::command]
+pub fn get_alias_dictionary(state: State<'_, SettingsState>) -> Result<AliasDictionary, String> {
+    let store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(store.settings().aliases.clone())
+}
+
+/// Export the alias dictionary as JSON
+/// With include_builtins, the built-in tables are included (user entries win)
+#[tauri::command]
+pub fn export_alias_dictionary(
+    state: State<'_, SettingsState>,
+    include_builtins: bool,
+) -> Result<String, String> {
+    let store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+
+    let user = store.settings().aliases.clone();
+    let dictionary = if include_builtins {
+        let mut merged = builtin_alias_dictionary();
+        merged.merge(user);
+        merged
+    } else {
+        user
+    };
+
+    dictionary.to_json().map_err(|e| format!("Failed to export alias dictionary: {}", e))
+}
+
+/// Import a JSON alias dictionary, merging with (or replacing) the user's aliases
+/// The result is persisted and applied to chord normalization immediately
+#[tauri::command]
+pub fn import_alias_dictionary(
+    state: State<'_, SettingsState>,
+    policy: State<'_, PolicyState>,
+    json: String,
+    replace: bool,
+) -> Result<AliasDictionary, CommandError> {
+    policy.check(Feature::Settings)?;
+    let imported = AliasDictionary::from_json(&json, is_builtin_suffix)
+        .map_err(|e| format!("Failed to import alias dictionary: {}", e))?;
+
+    let mut store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let dictionary = store.update(|s| {
+        if replace {
+            s.aliases = imported;
+        } else {
+            s.aliases.merge(imported);
+        }
+        s.aliases.clone()
+    })?;
+
+    aliases::set_user_aliases(dictionary.clone());
+    Ok(dictionary)
+}
+
+/// Check whether generated spellings are simplified (strict spelling off)
+#[tauri::command]
+pub fn get_simplify_spellings(state: State<'_, SettingsState>) -> Result<bool, String> {
+    let store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(store.settings().simplify_spellings)
+}
+
+/// Turn enharmonic simplification of generated spellings on or off
+/// Applies to chord notes for playback labels and worksheets immediately
+#[tauri::command]
+pub fn set_simplify_spellings(
+    state: State<'_, SettingsState>,
+    policy: State<'_, PolicyState>,
+    enabled: bool,
+) -> Result<(), CommandError> {
+    policy.check(Feature::Settings)?;
+    let mut store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    store.update(|s| s.simplify_spellings = enabled)?;
+
+    notes::set_simplify_spellings(enabled);
+    Ok(())
+}
+
+/// Get how many seconds a LilyPond render may run before it is stopped
+#[tauri::command]
+pub fn get_render_timeout(state: State<'_, SettingsState>) -> Result<u64, String> {
+    let store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(store.settings().render_timeout_secs.unwrap_or(DEFAULT_RENDER_TIMEOUT_SECS))
+}
+
+/// Set how many seconds a LilyPond render may run before it is stopped (None or 0 = the default)
+/// Applies to renders started afterwards
+#[tauri::command]
+pub fn set_render_timeout(
+    state: State<'_, SettingsState>,
+    policy: State<'_, PolicyState>,
+    seconds: Option<u64>,
+) -> Result<(), CommandError> {
+    policy.check(Feature::Settings)?;
+    let seconds = seconds.filter(|seconds| *seconds > 0);
+    let mut store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    store.update(|s| s.render_timeout_secs = seconds)?;
+
+    lilypond::set_render_timeout(seconds);
+    Ok(())
+}
+
+/// Get how many voices of earlier chords may ring on before the oldest are cut
+#[tauri::command]
+pub fn get_voice_limit(state: State<'_, SettingsState>) -> Result<usize, String> {
+    let store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(store.settings().voice_limit.unwrap_or(DEFAULT_VOICE_LIMIT))
+}
+
+/// Set the polyphony cap of chord playback (None = the default)
+/// Lower limits save CPU during rapid chord changes; applies from the next chord played
+#[tauri::command]
+pub fn set_voice_limit(
+    state: State<'_, SettingsState>,
+    policy: State<'_, PolicyState>,
+    voices: Option<usize>,
+) -> Result<(), CommandError> {
+    policy.check(Feature::Settings)?;
+    if let Some(voices) = voices.filter(|voices| !VOICE_LIMIT_RANGE.contains(voices)) {
+        return Err(format!(
+            "Voice limit must be between {} and {}, got {}",
+            VOICE_LIMIT_RANGE.start(),
+            VOICE_LIMIT_RANGE.end(),
+            voices
+        )
+        .into());
+    }
+    let mut store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    store.update(|s| s.voice_limit = voices)?;
+
+    audio::set_voice_limit(voices);
+    Ok(())
+}
+
+/// Get the probabilities that map chord transitions to Safe/Colorful/Bold tiers
+#[tauri::command]
+pub fn get_tier_thresholds(state: State<'_, SettingsState>) -> Result<TierThresholds, String> {
+    let store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(store.settings().tier_thresholds)
+}
+
+/// Set the tier thresholds; lower values make more chord blocks Safe, higher ones make the palette bolder
+/// Applies to classifications made afterwards
+#[tauri::command]
+pub fn set_tier_thresholds(
+    state: State<'_, SettingsState>,
+    policy: State<'_, PolicyState>,
+    thresholds: TierThresholds,
+) -> Result<(), CommandError> {
+    policy.check(Feature::Settings)?;
+    thresholds.validate().map_err(|e| format!("Invalid tier thresholds: {}", e))?;
+    let mut store = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    store.update(|s| s.tier_thresholds = thresholds)?;
+
+    tiers::set_tier_thresholds(thresholds);
+    Ok(())
+}
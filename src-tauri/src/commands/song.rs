@@ -0,0 +1,123 @@
+// Song arrangement commands
+// The backend keeps each document's song; edits return the updated song
+
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{State, Window};
+
+use super::audio::{start_sequence, AudioState, VoicingSessionState};
+use super::lilypond::render_svg;
+use crate::lilypond::RenderJob;
+use crate::documents::{self, DocumentMap};
+use crate::music::song::{self, SongEdit, SongPosition};
+use crate::music::types::AudioNote;
+use crate::notation::lead_sheet;
+use crate::svg::SvgTheme;
+use crate::types::song::Song;
+use crate::types::versioned;
+
+/// Managed state wrapper for the song arranged in each open document
+pub struct SongState(pub Mutex<DocumentMap<Song>>);
+
+/// A song started on the sequencer
+#[derive(Debug, Clone, Serialize)]
+pub struct SongPlayback {
+    /// Song position of each sequence index reported by sequence events
+    pub positions: Vec<SongPosition>,
+    pub voiced: Vec<Vec<AudioNote>>,
+}
+
+/// A song written out as a lead sheet
+#[derive(Debug, Clone, Serialize)]
+pub struct LeadSheetRendering {
+    pub lilypond: String,
+    pub svg: String,
+}
+
+/// Copy of a document's song (an empty song if none was started)
+fn current_song(state: &SongState, document: &str) -> Result<Song, String> {
+    let songs = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(songs.get(document).cloned().unwrap_or_default())
+}
+
+/// Get a document's song
+#[tauri::command]
+pub fn get_song(window: Window, state: State<'_, SongState>, document_id: Option<String>) -> Result<Song, String> {
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    current_song(&state, &document)
+}
+
+/// Apply an edit to a document's song and return the updated song
+#[tauri::command]
+pub fn apply_song_edit(
+    window: Window,
+    state: State<'_, SongState>,
+    document_id: Option<String>,
+    edit: SongEdit,
+) -> Result<Song, String> {
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    let mut songs = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let song = songs.entry(&document);
+    song::apply_edit(song, edit).map_err(|e| format!("Failed to edit song: {}", e))?;
+    Ok(song.clone())
+}
+
+/// Serialize a document's song in the versioned project format
+#[tauri::command]
+pub fn save_song(window: Window, state: State<'_, SongState>, document_id: Option<String>) -> Result<String, String> {
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    versioned::to_string_pretty(&current_song(&state, &document)?)
+}
+
+/// Load a saved song into a document, upgrading older versions
+#[tauri::command]
+pub fn load_song(
+    window: Window,
+    state: State<'_, SongState>,
+    document_id: Option<String>,
+    json: String,
+) -> Result<Song, String> {
+    let loaded = versioned::from_slice::<Song>(json.as_bytes())?;
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    let mut songs = state.0.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let song = songs.entry(&document);
+    *song = loaded.value;
+    Ok(song.clone())
+}
+
+/// Play a document's song on the sequencer, following each section's tempo and meter changes
+/// Sequence events index into the returned positions
+#[tauri::command]
+pub fn play_song(
+    window: Window,
+    state: State<'_, SongState>,
+    audio: State<'_, AudioState>,
+    sessions: State<'_, VoicingSessionState>,
+    document_id: Option<String>,
+    voicing_style: String,
+    base_octave: i8,
+) -> Result<SongPlayback, String> {
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    let song = current_song(&state, &document)?;
+    let playback = song::playback(&song).map_err(|e| format!("Failed to play song: {}", e))?;
+
+    let chords: Vec<(String, f32)> = playback.chords.into_iter().map(|chord| (chord.chord, chord.beats)).collect();
+    let voiced =
+        start_sequence(&window, &audio, &sessions, document_id, &chords, playback.tempo, &voicing_style, base_octave)?;
+    Ok(SongPlayback { positions: playback.positions, voiced })
+}
+
+/// Render a document's song as a multi-section lead sheet
+#[tauri::command]
+pub async fn render_lead_sheet(
+    window: Window,
+    state: State<'_, SongState>,
+    document_id: Option<String>,
+    theme: Option<SvgTheme>,
+) -> Result<LeadSheetRendering, String> {
+    let document = documents::document_id(window.label(), document_id.as_deref());
+    let song = current_song(&state, &document)?;
+    let lilypond = lead_sheet::song_to_lilypond(&song).map_err(|e| format!("Failed to write lead sheet: {}", e))?;
+    let svg = render_svg(lilypond.clone(), theme, RenderJob::background()).await?;
+    Ok(LeadSheetRendering { lilypond, svg })
+}
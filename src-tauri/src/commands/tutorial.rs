@@ -0,0 +1,16 @@
+// Onboarding tutorial commands
+// The frontend reports what the user built for a step and shows the verdict
+
+use crate::training::tutorial::{self, StepVerdict, TutorialState, TutorialStep, TUTORIAL_STEPS};
+
+/// Tutorial steps in teaching order
+#[tauri::command]
+pub fn list_tutorial_steps() -> Vec<TutorialStep> {
+    TUTORIAL_STEPS.to_vec()
+}
+
+/// Check whether the user's canvas chords or keyboard notes complete a tutorial step
+#[tauri::command]
+pub fn verify_exercise(step_id: String, user_state: TutorialState) -> Result<StepVerdict, String> {
+    tutorial::verify_exercise(&step_id, &user_state).map_err(|e| format!("Failed to verify exercise: {}", e))
+}
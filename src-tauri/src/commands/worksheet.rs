@@ -1,20 +1,71 @@
+use std::collections::BTreeMap;
 use std::fs;
-use std::process::Command;
-use tempfile::TempDir;
+use tauri::{AppHandle, State, Window};
+use tauri_plugin_dialog::{DialogExt, FilePath};
 use uuid::Uuid;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
+use super::analytics::{record_event, AnalyticsState};
+use super::export::{create_fontdb_with_bravura, svg_pages_to_pdf, ExportOptions};
+use super::history::RenderHistoryState;
+use super::lilypond::window_render_job;
+use super::policy::{CommandError, PolicyState};
+use crate::analytics::AnalyticsEvent;
+use crate::i18n::{self, Locale};
+use crate::lilypond::{find_lilypond, run_lilypond, RenderJob};
+use crate::music::analysis::analyze_progression;
+use crate::music::chords::{parse_chord, ChordSymbol, SymbolStyle};
+use crate::music::identify::name_midi_chord;
+use crate::music::intervals::{chord_to_notes, parse_chord_with_interval_specs};
+use crate::music::notes::{get_preferred_note_name, note_index};
+use crate::music::scales::{scale_to_notes, ScaleType};
+use crate::music::types::{MusicError, NumeralMode};
+use crate::notation::description::{
+    describe_answer_space, describe_element, element_chord_symbol, lilypond_pitch, ElementDescription,
+};
+use crate::notation::keyboard::{keyboard_markup, voiced_chord_midi};
+use crate::notation::musicxml::musicxml_to_worksheet;
+use crate::notation::native;
+use crate::notation::staff_range::{fit_to_range, ottava_mark};
+use crate::notation::voicing::{lilypond_absolute_pitch, lilypond_note_name, quoted};
+use crate::svg::{postprocess_svg, safe_element_id, SvgOptions, SvgTheme, ELEMENT_ID_ATTRIBUTE};
+use crate::settings::Feature;
 use crate::types::worksheet::*;
 
+/// Written octave chords are stacked up from on each staff, as on natively drawn sheets
+const TREBLE_CHORD_OCTAVE: i8 = 4;
+const BASS_CHORD_OCTAVE: i8 = 3;
+
+/// Color answers are highlighted in on an answer key, unless the worksheet sets its own
+const ANSWER_KEY_COLOR: &str = "#c0392b";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorksheetRequest {
     pub config: WorksheetConfig,
+    /// Screen colors for the rendered SVG (None = black on white for print)
+    #[serde(default)]
+    pub theme: Option<SvgTheme>,
+    /// Id the render's progress events are sent under and cancel_render accepts (None = a new one)
+    #[serde(default)]
+    pub job_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorksheetResponse {
     pub svg_content: String,
     pub interactive_elements: Vec<InteractiveElement>,
+    /// Screen-reader text for every printed element, keyed by its SVG element id
+    #[serde(default)]
+    pub descriptions: Vec<ElementDescription>,
+}
+
+/// Student copy of a worksheet and its answer key, rendered together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorksheetPair {
+    pub student: WorksheetResponse,
+    pub answer_key: WorksheetResponse,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,20 +85,185 @@ pub struct ElementBounds {
 }
 
 /// Generate a complete worksheet document using LilyPond
+/// Progress is emitted to the calling window as render-progress events under the request's job id
+#[tauri::command]
+pub async fn generate_worksheet(
+    window: Window,
+    analytics: State<'_, AnalyticsState>,
+    history: State<'_, RenderHistoryState>,
+    request: WorksheetRequest,
+) -> Result<WorksheetResponse, String> {
+    if let Ok(serde_json::Value::String(worksheet_type)) = serde_json::to_value(&request.config.worksheet_type) {
+        record_event(&analytics, AnalyticsEvent::WorksheetGenerated { worksheet_type });
+    }
+
+    let mut job = window_render_job(&window, request.job_id.clone());
+    let (config, theme) = (request.config.clone(), request.theme.clone());
+    let response = tauri::async_runtime::spawn_blocking(move || render_worksheet(&config, theme, &mut job))
+        .await
+        .map_err(|e| format!("Render task failed: {}", e))??;
+
+    // Autosave the finished sheet; a failed save must not fail the render
+    if let Ok(history) = history.0.lock() {
+        if let Err(e) = history.record(&request.config, &response.svg_content) {
+            println!("[history] {}", e);
+        }
+    }
+
+    Ok(response)
+}
+
+/// Render the student copy and the matching answer key of a worksheet in one call
+/// Only the student copy is kept in the render history; both renders report under the request's job id
+#[tauri::command]
+pub async fn generate_worksheet_pair(
+    window: Window,
+    analytics: State<'_, AnalyticsState>,
+    history: State<'_, RenderHistoryState>,
+    request: WorksheetRequest,
+) -> Result<WorksheetPair, String> {
+    if let Ok(serde_json::Value::String(worksheet_type)) = serde_json::to_value(&request.config.worksheet_type) {
+        record_event(&analytics, AnalyticsEvent::WorksheetGenerated { worksheet_type });
+    }
+
+    let student_config = student_copy(&request.config);
+    let key_config = answer_key(&request.config);
+    let mut job = window_render_job(&window, request.job_id.clone());
+    let (config, theme) = (student_config.clone(), request.theme.clone());
+    let (student, answer_key) = tauri::async_runtime::spawn_blocking(move || {
+        let student = render_worksheet(&config, theme.clone(), &mut job)?;
+        Ok::<_, String>((student, render_worksheet(&key_config, theme, &mut job)?))
+    })
+    .await
+    .map_err(|e| format!("Render task failed: {}", e))??;
+
+    if let Ok(history) = history.0.lock() {
+        if let Err(e) = history.record(&student_config, &student.svg_content) {
+            println!("[history] {}", e);
+        }
+    }
+
+    Ok(WorksheetPair { student, answer_key })
+}
+
+/// Render several worksheets, such as one per student or a set of randomized variants, into one PDF
+/// Each worksheet is one page, in the order of the configs, printed black on white
+/// Progress is emitted as render-progress events under `job_id`; returns false when the save dialog is cancelled
 #[tauri::command]
-pub async fn generate_worksheet(request: WorksheetRequest) -> Result<WorksheetResponse, String> {
-    let lilypond_source = build_lilypond_document(&request.config)?;
-    let svg_content = render_lilypond_document(lilypond_source)?;
+pub async fn generate_worksheet_batch(
+    app: AppHandle,
+    window: Window,
+    policy: State<'_, PolicyState>,
+    configs: Vec<WorksheetConfig>,
+    default_filename: String,
+    job_id: Option<String>,
+) -> Result<bool, CommandError> {
+    policy.check(Feature::Export)?;
+    if configs.is_empty() {
+        return Err("Failed to generate worksheets: no worksheets given".into());
+    }
+
+    let file_path = app
+        .dialog()
+        .file()
+        .add_filter("PDF Document", &["pdf"])
+        .set_file_name(&default_filename)
+        .set_title("Export Worksheets as PDF")
+        .blocking_save_file();
+    let path = match file_path {
+        Some(FilePath::Path(path)) => path,
+        Some(_) => return Err("Invalid file path".into()),
+        None => return Ok(false),
+    };
+
+    let fontdb = create_fontdb_with_bravura(&app)?;
+    let mut job = window_render_job(&window, job_id);
+    let pdf = tauri::async_runtime::spawn_blocking(move || {
+        let pages = configs
+            .iter()
+            .map(|config| Ok(render_worksheet(config, None, &mut job)?.svg_content))
+            .collect::<Result<Vec<_>, String>>()?;
+        svg_pages_to_pdf(&pages, fontdb, &ExportOptions::default())
+    })
+    .await
+    .map_err(|e| format!("Render task failed: {}", e))??;
+
+    fs::write(&path, pdf).map_err(|e| format!("Failed to write PDF: {}", e))?;
+    Ok(true)
+}
+
+/// Worksheet with every answer hidden
+fn student_copy(config: &WorksheetConfig) -> WorksheetConfig {
+    let mut student = config.clone();
+    student.global_settings.show_answers = false;
+    student
+}
+
+/// Worksheet with every answer shown and highlighted, subtitled as the answer key
+fn answer_key(config: &WorksheetConfig) -> WorksheetConfig {
+    let mut key = config.clone();
+    key.global_settings.show_answers = true;
+    key.global_settings.answer_color.get_or_insert_with(|| ANSWER_KEY_COLOR.to_string());
+    let locale = i18n::locale(config.global_settings.locale.as_deref());
+    key.subtitle = Some(match config.subtitle.as_deref().map(str::trim).filter(|subtitle| !subtitle.is_empty()) {
+        Some(subtitle) => locale.format("answer_key.with_subtitle", &[("subtitle", subtitle)]),
+        None => locale.text("answer_key"),
+    });
+    key
+}
+
+/// Run the full rendering pipeline for a worksheet
+/// Without LilyPond installed, the worksheet types the native renderer supports are drawn by it instead
+/// Blocks until LilyPond finishes, so async callers run it on a blocking thread
+pub fn render_worksheet(
+    config: &WorksheetConfig,
+    theme: Option<SvgTheme>,
+    job: &mut RenderJob,
+) -> Result<WorksheetResponse, String> {
+    let svg = if native::supports(&config.worksheet_type) && !lilypond_available() {
+        native::render_worksheet(config).map_err(|e| format!("Failed to render worksheet: {}", e))?
+    } else {
+        render_lilypond_document(build_lilypond_document(config)?, job)?
+    };
+    let options = SvgOptions { theme, ..SvgOptions::default() };
+    let svg_content = postprocess_svg(&svg, &options)?;
     let interactive_elements = extract_interactive_elements(&svg_content)?;
 
     Ok(WorksheetResponse {
         svg_content,
         interactive_elements,
+        descriptions: describe_worksheet(config),
     })
 }
 
+/// Screen-reader descriptions of every element as printed, in score order within each section
+/// Answers hidden on the student copy are described as blanks
+fn describe_worksheet(config: &WorksheetConfig) -> Vec<ElementDescription> {
+    let show_answers = config.global_settings.show_answers;
+    config
+        .sections
+        .iter()
+        .flat_map(|section| {
+            let mut elements: Vec<&EditableElement> = section.elements.iter().collect();
+            elements.sort_by_key(|element| (element.position.measure, element.position.beat));
+            elements
+        })
+        .map(|element| {
+            let hidden = element.is_answer
+                && !show_answers
+                && matches!(element.element_type, EditableElementType::Chord | EditableElementType::Note);
+            ElementDescription {
+                element_id: safe_element_id(&element.id),
+                text: if hidden { describe_answer_space(element) } else { describe_element(element) },
+            }
+        })
+        .collect()
+}
+
 /// Build a complete LilyPond document from worksheet configuration
-fn build_lilypond_document(config: &WorksheetConfig) -> Result<String, String> {
+/// Every user-supplied string is placed as an escaped LilyPond string; music and signatures are
+/// validated and rewritten, and anything that isn't a chord, pitch, rest or signature is rejected
+pub fn build_lilypond_document(config: &WorksheetConfig) -> Result<String, String> {
     let paper_size = match config.global_settings.paper_size {
         PaperSize::Letter => "letter",
         PaperSize::A4 => "a4",
@@ -58,6 +274,9 @@ fn build_lilypond_document(config: &WorksheetConfig) -> Result<String, String> {
         Orientation::Landscape => "landscape",
     };
 
+    // Right-to-left locales wrap header lines in direction marks so mixed Arabic/Hebrew and Latin text reads correctly
+    let locale = i18n::locale(config.global_settings.locale.as_deref());
+
     let mut document = format!(
         r#"\version "2.24.0"
 
@@ -75,8 +294,8 @@ fn build_lilypond_document(config: &WorksheetConfig) -> Result<String, String> {
 }}
 
 \header {{
-  title = "{}"
-  subtitle = "{}"
+  title = {}
+  subtitle = {}
   tagline = ##f
   composer = ##f
 }}
@@ -84,8 +303,8 @@ fn build_lilypond_document(config: &WorksheetConfig) -> Result<String, String> {
 "#,
         paper_size,
         if orientation == "landscape" { "-landscape" } else { "" },
-        config.title,
-        config.subtitle.as_deref().unwrap_or("")
+        quoted(&locale.directional(&config.title)),
+        quoted(&locale.directional(config.subtitle.as_deref().unwrap_or("")))
     );
 
     // Add each section as a separate score
@@ -97,14 +316,14 @@ fn build_lilypond_document(config: &WorksheetConfig) -> Result<String, String> {
         if !section.title.is_empty() {
             document.push_str(&format!(r#"\markup {{ \column {{
   \vspace #2
-  \fill-line {{ \fontsize #2 \bold {{ "{}" }} }}
+  \fill-line {{ \fontsize #2 \bold {{ {} }} }}
   {}
 }}}}
 
-"#, &section.title, 
+"#, quoted(&locale.directional(&section.title)), 
             if let Some(inst) = &section.instructions {
                 format!(r#"
-  \fill-line {{ \italic {{ "{}" }} }}"#, inst)
+  \fill-line {{ \italic {{ {} }} }}"#, quoted(&locale.directional(inst)))
             } else {
                 String::new()
             }));
@@ -127,27 +346,28 @@ fn build_section_lilypond(
         Clef::Both => "treble", // Handle both clefs with separate staves
     };
 
-    let time_signature = section.layout.time_signature
-        .as_deref()
-        .unwrap_or("4/4");
-
-    let key_signature = section.layout.key_signature
-        .as_deref()
-        .unwrap_or("c");
+    let time_signature = time_signature_content(section.layout.time_signature.as_deref().unwrap_or("4/4"))?;
+    let key_signature = key_signature_content(section.layout.key_signature.as_deref().unwrap_or("c"))?;
 
     // Generate music content and chord symbols from elements
-    let (music_content, chord_symbols) = build_music_and_chords_from_elements(&section.elements, global_settings.show_answers)?;
+    let (music_content, chord_symbols) = build_music_and_chords_from_elements(
+        &section.elements,
+        global_settings.show_answers,
+        global_settings.answer_color.as_deref(),
+        section.layout.answer_blank,
+        if matches!(section.layout.clef, Clef::Bass) { BASS_CHORD_OCTAVE } else { TREBLE_CHORD_OCTAVE },
+    )?;
 
     let score = format!(
         r#"\score {{
   <<
-    \new ChordNames {{
+    \new ChordNames \chordmode {{
       {}
     }}
     \new Staff {{
       \clef "{}"
-      \key "{}" \major
-      \time "{}"
+      \key {} \major
+      \time {}
       {}
     }}
   >>
@@ -171,9 +391,13 @@ fn build_section_lilypond(
 }
 
 /// Build LilyPond music notation and chord symbols from worksheet elements
+/// Shown answers are drawn in `answer_color` when one is given, and hidden ones left as `answer_blank`
 fn build_music_and_chords_from_elements(
     elements: &[EditableElement], 
-    show_answers: bool
+    show_answers: bool,
+    answer_color: Option<&str>,
+    answer_blank: AnswerBlank,
+    chord_octave: i8,
 ) -> Result<(String, String), String> {
     let mut music = String::new();
     let mut chords = String::new();
@@ -204,32 +428,58 @@ fn build_music_and_chords_from_elements(
         }
 
         // Add the element
+        let answer_style;
+        let style = match (answer_color, element.is_answer) {
+            (Some(color), true) => {
+                let enclosure = element.style.as_ref().and_then(|style| style.enclosure);
+                answer_style = ElementStyle { color: Some(color.to_string()), enclosure };
+                Some(&answer_style)
+            }
+            _ => element.style.as_ref(),
+        };
+        let hint = hint_markup(element.hint.as_deref());
+        let diagram = element.keyboard.as_deref().map(keyboard_markup).unwrap_or_default();
         match element.element_type {
             EditableElementType::Chord => {
                 if show_answers || !element.is_answer {
                     // Add chord symbol
-                    chords.push_str(&format!("{}4 ", element.content));
-                    // Add simple chord notes (root position)
-                    let root_note = get_chord_root_note(&element.content);
-                    music.push_str(&format!("<{} {} {}>4 ", root_note, get_chord_third(&element.content), get_chord_fifth(&element.content)));
+                    chords.push_str(&tag_element("ChordName", "interactive-chord", &element.id));
+                    chords.push_str(&style_overrides(style, "ChordName", false)?);
+                    chords.push_str(&format!("{} ", chordmode_chord(&element.content, 4)?));
+                    // Add the chord's notes, stacked from the root (or slash bass)
+                    let pitches = chord_lilypond_pitches(&element.content, chord_octave)?;
+                    music.push_str(&tag_element("NoteHead", "interactive-note", &element.id));
+                    music.push_str(&style_overrides(style, "NoteHead", true)?);
+                    music.push_str(&format!("<{}>4{}{} ", pitches, hint, diagram));
                 } else {
-                    // Show question mark for hidden answers
+                    // Leave a blank for hidden answers; the hint stays with the question
                     chords.push_str("r4 ");
-                    music.push_str("r4 ");
+                    music.push_str(&format!("{}{} ", answer_blank_markup(answer_blank), hint));
                 }
             }
             EditableElementType::Note => {
                 if show_answers || !element.is_answer {
-                    music.push_str(&format!("{}4 ", element.content));
+                    music.push_str(&tag_element("NoteHead", "interactive-note", &element.id));
+                    music.push_str(&style_overrides(style, "NoteHead", false)?);
+                    music.push_str(&format!("{}4{}{} ", note_content(&element.content)?, hint, diagram));
                 } else {
-                    music.push_str("r4 ");
+                    music.push_str(&format!("{}{} ", answer_blank_markup(answer_blank), hint));
                 }
                 chords.push_str("s4 "); // Spacer for non-chord elements
             }
             EditableElementType::Rest => {
-                music.push_str(&format!("{} ", element.content));
+                music.push_str(&format!("{} ", rest_content(&element.content)?));
                 chords.push_str("s4 ");
             }
+            // Signature changes take no time
+            EditableElementType::TimeSignature => {
+                music.push_str(&format!("\\time {} ", time_signature_content(&element.content)?));
+                continue;
+            }
+            EditableElementType::KeySignature => {
+                music.push_str(&format!("\\key {} \\major ", key_signature_content(&element.content)?));
+                continue;
+            }
             _ => {
                 music.push_str("r4 "); // Default to rest
                 chords.push_str("s4 ");
@@ -242,108 +492,200 @@ fn build_music_and_chords_from_elements(
     Ok((music, chords))
 }
 
-/// Extract root note from chord notation
-fn get_chord_root_note(chord: &str) -> String {
-    // Simple extraction - take first character(s) before any chord quality
-    if chord.len() >= 2 && &chord[1..2] == "#" {
-        format!("{}is", chord.chars().next().unwrap())
-    } else if chord.len() >= 2 && &chord[1..2] == "b" {
-        format!("{}es", chord.chars().next().unwrap())
-    } else {
-        chord.chars().next().unwrap().to_string()
-    }
-}
-
-/// Get third of chord (simplified)
-fn get_chord_third(chord: &str) -> String {
-    let root = chord.chars().next().unwrap();
-    if chord.contains("m") || chord.contains("dim") {
-        // Minor third
-        match root {
-            'C' => "ees".to_string(),
-            'D' => "f".to_string(),
-            'E' => "g".to_string(),
-            'F' => "aes".to_string(),
-            'G' => "bes".to_string(),
-            'A' => "c".to_string(),
-            'B' => "d".to_string(),
-            _ => "ees".to_string(),
-        }
-    } else {
-        // Major third
-        match root {
-            'C' => "e".to_string(),
-            'D' => "fis".to_string(),
-            'E' => "gis".to_string(),
-            'F' => "a".to_string(),
-            'G' => "b".to_string(),
-            'A' => "cis".to_string(),
-            'B' => "dis".to_string(),
-            _ => "e".to_string(),
+/// Time signature element content as written after \\time ("3/4")
+fn time_signature_content(content: &str) -> Result<&str, String> {
+    let content = content.trim();
+    let valid = content
+        .split_once('/')
+        .is_some_and(|(beats, unit)| beats.parse::<u32>().is_ok() && unit.parse::<u32>().is_ok());
+    if valid { Ok(content) } else { Err(format!("Invalid time signature: {}", content)) }
+}
+
+/// Key signature element content as written after \\key: the major tonic as a LilyPond name ("ees")
+fn key_signature_content(content: &str) -> Result<&str, String> {
+    let content = content.trim();
+    let valid = matches!(content.chars().next(), Some('a'..='g')) && content.chars().all(|c| c.is_ascii_lowercase());
+    if valid { Ok(content) } else { Err(format!("Invalid key signature: {}", content)) }
+}
+
+/// Note element content as written before its duration: an absolute LilyPond pitch ("fis'") or a chord
+/// of them ("<c' e' g'>")
+fn note_content(content: &str) -> Result<&str, String> {
+    let content = content.trim();
+    let valid = match content.strip_prefix('<').and_then(|inner| inner.strip_suffix('>')) {
+        Some(pitches) => {
+            !pitches.trim().is_empty() && pitches.split_whitespace().all(|pitch| lilypond_pitch(pitch).is_some())
         }
+        None => lilypond_pitch(content).is_some(),
+    };
+    if valid { Ok(content) } else { Err(format!("Invalid note: {}", content)) }
+}
+
+/// Rest element content: a rest or spacer with an optional duration ("r4", "r2.", "s8")
+fn rest_content(content: &str) -> Result<&str, String> {
+    let content = content.trim();
+    let valid = content.strip_prefix(['r', 'R', 's']).is_some_and(|duration| {
+        matches!(duration.trim_end_matches('.'), "" | "1" | "2" | "4" | "8" | "16" | "32" | "64")
+    });
+    if valid { Ok(content) } else { Err(format!("Invalid rest: {}", content)) }
+}
+
+/// Chord element content as a chord-mode chord of the given duration ("Bb7", 4 → "bes4:1.3.5.7")
+/// Steps are written out from the chord engine's intervals, so the name LilyPond prints is built from
+/// the same notes as the staff; content that isn't a chord symbol is rejected
+fn chordmode_chord(content: &str, duration: u32) -> Result<String, String> {
+    let symbol = element_chord_symbol(content.trim()).ok_or_else(|| format!("Invalid chord '{}'", content.trim()))?;
+    let invalid = |e: MusicError| format!("Invalid chord '{}': {}", symbol, e);
+    let chord = parse_chord(&symbol).map_err(invalid)?;
+    let steps = parse_chord_with_interval_specs(&chord.suffix)
+        .map_err(invalid)?
+        .iter()
+        .map(|(semitones, degree)| chordmode_step(*semitones, *degree))
+        .collect::<Option<Vec<String>>>()
+        .ok_or_else(|| format!("Invalid chord '{}'", symbol))?;
+
+    let mut written = format!("{}{}", lilypond_note_name(&chord.root), duration);
+    if steps != ["1", "3", "5"] {
+        written.push_str(&format!(":{}", steps.join(".")));
+    }
+    if let Some(bass) = &chord.bass {
+        note_index(bass).map_err(invalid)?;
+        written.push_str(&format!("/{}", lilypond_note_name(bass)));
+    }
+    Ok(written)
+}
+
+/// Chord-mode step of an interval above the root ("3-" for a minor third, "9" for a major ninth)
+/// Unaltered steps are those of the major scale, except for the minor seventh
+fn chordmode_step(semitones: u8, degree: u8) -> Option<String> {
+    const UNALTERED: [u8; 7] = [0, 2, 4, 5, 7, 9, 10];
+    let octaves = semitones / 12;
+    let unaltered = UNALTERED.get(usize::from(degree).checked_sub(1)?)? + 12 * octaves;
+    let alteration = match semitones as i8 - unaltered as i8 {
+        -1 => "-",
+        0 => "",
+        1 => "+",
+        _ => return None,
+    };
+    Some(format!("{}{}", degree + 7 * octaves, alteration))
+}
+
+/// One-off override tagging the next grob with its worksheet element id
+/// The SVG post-processor turns the tag into a stable id
+fn tag_element(grob: &str, class: &str, element_id: &str) -> String {
+    format!(
+        "\\once \\override {}.output-attributes = #'((class . \"{}\") ({} . \"{}\")) ",
+        grob, class, ELEMENT_ID_ATTRIBUTE, safe_element_id(element_id)
+    )
+}
+
+/// One-off overrides drawing the next grob in the element's color and enclosure
+/// Chords are enclosed by their name only (`color_only`), not every note head
+fn style_overrides(style: Option<&ElementStyle>, grob: &str, color_only: bool) -> Result<String, String> {
+    let Some(style) = style else {
+        return Ok(String::new());
+    };
+
+    let mut overrides = String::new();
+    if let Some(color) = &style.color {
+        let (r, g, b) = parse_hex_color(color).ok_or_else(|| format!("Invalid color: {}", color))?;
+        overrides.push_str(&format!("\\once \\override {}.color = #(rgb-color {:.3} {:.3} {:.3}) ", grob, r, g, b));
+    }
+    if let (Some(enclosure), false) = (style.enclosure, color_only) {
+        let stencil = match enclosure {
+            Enclosure::Circle => "circle-stencil",
+            Enclosure::Box => "box-stencil",
+        };
+        let print = if grob == "NoteHead" { "ly:note-head::print" } else { "ly:text-interface::print" };
+        overrides.push_str(&format!(
+            "\\once \\override {}.stencil = #(lambda (grob) ({} ({} grob) 0.1 0.3)) ",
+            grob, stencil, print
+        ));
+    }
+    Ok(overrides)
+}
+
+/// "#c0392b" or "#c33" as RGB fractions
+pub(crate) fn parse_hex_color(color: &str) -> Option<(f32, f32, f32)> {
+    let hex = color.strip_prefix('#')?;
+    let digits: Vec<u8> = hex.chars().map(|c| c.to_digit(16).map(|d| d as u8)).collect::<Option<_>>()?;
+    let channels: Vec<u8> = match digits.len() {
+        3 => digits.iter().map(|d| d * 17).collect(),
+        6 => digits.chunks(2).map(|pair| pair[0] * 16 + pair[1]).collect(),
+        _ => return None,
+    };
+    Some((channels[0] as f32 / 255.0, channels[1] as f32 / 255.0, channels[2] as f32 / 255.0))
+}
+
+/// Hint printed in small type beneath the element it is attached to
+fn hint_markup(hint: Option<&str>) -> String {
+    match hint.map(str::trim).filter(|hint| !hint.is_empty()) {
+        Some(hint) => format!("_\\markup {{ \\small {} }}", quoted(hint)),
+        None => String::new(),
     }
 }
 
-/// Get fifth of chord (simplified - always perfect fifth except diminished)
-fn get_chord_fifth(chord: &str) -> String {
-    let root = chord.chars().next().unwrap();
-    if chord.contains("dim") {
-        // Diminished fifth
-        match root {
-            'C' => "ges".to_string(),
-            'D' => "aes".to_string(),
-            'E' => "bes".to_string(),
-            'F' => "ces".to_string(),
-            'G' => "des".to_string(),
-            'A' => "ees".to_string(),
-            'B' => "f".to_string(),
-            _ => "ges".to_string(),
+/// A quarter-beat blank where a hidden answer goes
+/// Lines and boxes hang below a hidden rest; shading and "?" replace the rest on the staff
+fn answer_blank_markup(blank: AnswerBlank) -> String {
+    let on_staff = |markup: &str| {
+        format!(
+            "\\once \\override Rest.stencil = #ly:text-interface::print \
+             \\once \\override Rest.text = \\markup {{ {} }} r4",
+            markup
+        )
+    };
+    match blank {
+        AnswerBlank::Rest => "r4".to_string(),
+        AnswerBlank::Line => "\\once \\hide Rest r4_\\markup { \\draw-line #'(4 . 0) }".to_string(),
+        AnswerBlank::Box => {
+            "\\once \\hide Rest r4_\\markup { \\box \\pad-to-box #'(0 . 4) #'(0 . 2.5) \\null }".to_string()
         }
-    } else {
-        // Perfect fifth
-        match root {
-            'C' => "g".to_string(),
-            'D' => "a".to_string(),
-            'E' => "b".to_string(),
-            'F' => "c".to_string(),
-            'G' => "d".to_string(),
-            'A' => "e".to_string(),
-            'B' => "fis".to_string(),
-            _ => "g".to_string(),
+        AnswerBlank::Shaded => format!(
+            "\\once \\override Rest.layer = #-1 {}",
+            on_staff("\\with-color #(rgb-color 0.85 0.85 0.85) \\filled-box #'(-0.5 . 2.5) #'(-2 . 2) #0")
+        ),
+        AnswerBlank::Question => on_staff("\\vcenter \\fontsize #2 \\bold \"?\""),
+    }
+}
+
+/// Chord tones as the body of an absolute LilyPond chord ("d' f' a' c''"), spelled by the chord engine
+/// and stacked upward from the root, or the slash bass, in the given written octave
+/// Older worksheets stored the root as a LilyPond name ("bes7"); it is read as the chord symbol it stands for
+fn chord_lilypond_pitches(chord: &str, octave: i8) -> Result<String, String> {
+    let symbol = element_chord_symbol(chord.trim()).ok_or_else(|| format!("Invalid chord '{}'", chord.trim()))?;
+    let chord = symbol.as_str();
+    let invalid = |e: MusicError| format!("Invalid chord '{}': {}", chord, e);
+    let mut notes = chord_to_notes(chord.split('/').next().unwrap_or(chord)).map_err(invalid)?;
+    if let Some(bass) = parse_chord(chord).map_err(invalid)?.bass {
+        notes.retain(|note| *note != bass);
+        notes.insert(0, bass);
+    }
+
+    // Each note is written on the first line or space above the one below it
+    let mut below: Option<i32> = None;
+    let mut pitches = Vec::new();
+    for note in &notes {
+        let letter = note.chars().next().and_then(|letter| "CDEFGAB".find(letter.to_ascii_uppercase()));
+        let mut step = octave as i32 * 7 + letter.ok_or_else(|| format!("Invalid chord '{}'", chord))? as i32;
+        while below.is_some_and(|below| step <= below) {
+            step += 7;
         }
+        below = Some(step);
+        pitches.push(lilypond_absolute_pitch(note, step.div_euclid(7) as i8));
     }
+    Ok(pitches.join(" "))
+}
+
+/// Whether a lilypond executable is on the PATH
+fn lilypond_available() -> bool {
+    find_lilypond().is_some()
 }
 
-/// Render LilyPond document to SVG
-fn render_lilypond_document(lilypond_source: String) -> Result<String, String> {
-    let temp_dir = TempDir::new().map_err(|e| format!("Failed to create temp directory: {}", e))?;
-    let temp_path = temp_dir.path();
-    
-    let file_id = Uuid::new_v4().to_string();
-    let input_file = temp_path.join(format!("{}.ly", file_id));
-    let output_dir = temp_path.join("output");
-    
-    fs::create_dir_all(&output_dir).map_err(|e| format!("Failed to create output directory: {}", e))?;
-    
-    fs::write(&input_file, lilypond_source).map_err(|e| format!("Failed to write input file: {}", e))?;
-    
-    let output = Command::new("lilypond")
-        .arg("--svg")
-        .arg("-dno-point-and-click") // Disable for now, we'll add our own interactivity
-        .arg("-o")
-        .arg(&output_dir)
-        .arg(&input_file)
-        .output()
-        .map_err(|e| format!("Failed to execute lilypond: {}. Make sure LilyPond is installed and in PATH.", e))?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("LilyPond execution failed: {}", stderr));
-    }
-    
-    let svg_file = output_dir.join(format!("{}.svg", file_id));
-    fs::read_to_string(&svg_file)
-        .map_err(|e| format!("Failed to read SVG output: {}", e))
+/// Render LilyPond document to SVG, reusing the cached output when the document is unchanged
+fn render_lilypond_document(lilypond_source: String, job: &mut RenderJob) -> Result<String, String> {
+    // Disable point-and-click links, we add our own interactivity
+    run_lilypond(&lilypond_source, &["-dno-point-and-click"], job)
 }
 
 /// Extract interactive elements from SVG (placeholder for now)
@@ -353,9 +695,20 @@ fn extract_interactive_elements(_svg_content: &str) -> Result<Vec<InteractiveEle
     Ok(vec![])
 }
 
+/// Locales generated worksheet text is available in, for the worksheet locale picker
+#[tauri::command]
+pub fn get_worksheet_locales() -> Vec<&'static str> {
+    i18n::available_locales()
+}
+
 /// Generate chord naming worksheet template
 #[tauri::command]
 pub async fn generate_chord_naming_template(params: ChordNamingParams) -> Result<WorksheetConfig, String> {
+    Ok(build_chord_naming_worksheet(params, "c"))
+}
+
+fn build_chord_naming_worksheet(params: ChordNamingParams, key_signature: &str) -> WorksheetConfig {
+    let locale = i18n::locale(params.locale.as_deref());
     let mut elements = Vec::new();
 
     for (index, chord) in params.chords.iter().enumerate() {
@@ -366,27 +719,31 @@ pub async fn generate_chord_naming_template(params: ChordNamingParams) -> Result
             content: format_chord_notation(&chord.root, &chord.quality),
             is_answer: chord.show_answer,
             is_interactive: true,
+            style: None,
+            hint: None,
+            keyboard: None,
         };
         elements.push(element);
     }
 
     let section = WorksheetSection {
         id: "chord-naming-section".to_string(),
-        title: "Chord Identification".to_string(),
-        instructions: Some(params.instructions.unwrap_or_else(|| "Identify the following chords".to_string())),
+        title: locale.text("chord_naming.section"),
+        instructions: Some(params.instructions.unwrap_or_else(|| locale.text("instructions.identify_chords"))),
         elements,
         layout: WorksheetSectionLayout {
             measures_per_system: params.layout.chords_per_line,
             systems_per_page: 4,
             clef: Clef::Treble,
             time_signature: Some("4/4".to_string()),
-            key_signature: Some("c".to_string()),
+            key_signature: Some(key_signature.to_string()),
+            answer_blank: AnswerBlank::default(),
         },
     };
 
-    let config = WorksheetConfig {
+    WorksheetConfig {
         id: Uuid::new_v4().to_string(),
-        title: "Chord Naming Worksheet".to_string(),
+        title: locale.text("chord_naming.title"),
         subtitle: None,
         worksheet_type: WorksheetType::ChordNaming,
         sections: vec![section],
@@ -394,30 +751,212 @@ pub async fn generate_chord_naming_template(params: ChordNamingParams) -> Result
             paper_size: PaperSize::Letter,
             orientation: Orientation::Portrait,
             show_answers: false,
+            answer_color: None,
             font_size: 14,
+            locale: params.locale,
         },
+    }
+}
+
+/// Reference chart keys, around the circle of fifths
+const CHART_KEYS: [&str; 12] = ["C", "G", "D", "A", "E", "B", "F#", "Db", "Ab", "Eb", "Bb", "F"];
+/// Voicing and starting octave of keyboard diagrams; the octave below middle C keeps every chord in range
+const KEYBOARD_VOICING: &str = "close";
+const KEYBOARD_OCTAVE: i8 = 3;
+
+/// Diatonic triad and seventh chord qualities on each degree of the major and natural minor scales
+const MAJOR_KEY_QUALITIES: [(ChordQuality, ChordQuality); 7] = [
+    (ChordQuality::Major, ChordQuality::Major7),
+    (ChordQuality::Minor, ChordQuality::Minor7),
+    (ChordQuality::Minor, ChordQuality::Minor7),
+    (ChordQuality::Major, ChordQuality::Major7),
+    (ChordQuality::Major, ChordQuality::Dominant7),
+    (ChordQuality::Minor, ChordQuality::Minor7),
+    (ChordQuality::Diminished, ChordQuality::HalfDiminished7),
+];
+const MINOR_KEY_QUALITIES: [(ChordQuality, ChordQuality); 7] = [
+    (ChordQuality::Minor, ChordQuality::Minor7),
+    (ChordQuality::Diminished, ChordQuality::HalfDiminished7),
+    (ChordQuality::Major, ChordQuality::Major7),
+    (ChordQuality::Minor, ChordQuality::Minor7),
+    (ChordQuality::Minor, ChordQuality::Minor7),
+    (ChordQuality::Major, ChordQuality::Major7),
+    (ChordQuality::Major, ChordQuality::Dominant7),
+];
+
+/// Generate a chord naming worksheet covering every diatonic chord of a key
+/// Chords are shuffled and placed one per measure
+#[tauri::command]
+pub async fn generate_whole_key_template(params: WholeKeyChordParams) -> Result<WorksheetConfig, String> {
+    build_whole_key_worksheet(params, &mut rand::thread_rng())
+}
+
+fn build_whole_key_worksheet(params: WholeKeyChordParams, rng: &mut impl Rng) -> Result<WorksheetConfig, String> {
+    let (tonic, mode) = NumeralMode::from_key(&params.key);
+    let (scale, qualities) = match mode {
+        NumeralMode::Major => (ScaleType::Major, &MAJOR_KEY_QUALITIES),
+        _ => (ScaleType::NaturalMinor, &MINOR_KEY_QUALITIES),
     };
+    let roots = note_index(tonic)
+        .and_then(|_| scale_to_notes(tonic, scale))
+        .map_err(|e| format!("Invalid key {}: {}", params.key, e))?;
 
+    let mut chords: Vec<(String, ChordQuality)> = roots
+        .iter()
+        .zip(qualities.iter())
+        .map(|(root, (triad, _))| (root.clone(), *triad))
+        .collect();
+    if params.include_sevenths {
+        chords.extend(roots.iter().zip(qualities.iter()).map(|(root, (_, seventh))| (root.clone(), *seventh)));
+    }
+    chords.shuffle(rng);
+
+    // The staff only writes major key signatures; a minor key shares its relative major's
+    let signature_tonic = if scale == ScaleType::Major { tonic } else { &roots[2] };
+    let key_signature = lilypond_note_name(signature_tonic);
+
+    let chords = chords
+        .into_iter()
+        .enumerate()
+        .map(|(index, (root, quality))| ChordDefinition {
+            root,
+            quality,
+            position: ElementPosition { measure: index as u32 + 1, beat: 1, voice: None },
+            show_answer: params.show_answers,
+        })
+        .collect();
+    let locale = i18n::locale(params.locale.as_deref());
+    let key_name = key_display_name(tonic, mode, locale);
+    let instructions =
+        params.instructions.or_else(|| Some(locale.format("whole_key.instructions", &[("key", &key_name)])));
+
+    let mut config = build_chord_naming_worksheet(
+        ChordNamingParams { chords, instructions, layout: params.layout, locale: params.locale },
+        &key_signature,
+    );
+    config.title = locale.format("whole_key.title", &[("key", &key_name)]);
     Ok(config)
 }
 
-/// Format chord notation for LilyPond
-fn format_chord_notation(root: &str, quality: &ChordQuality) -> String {
-    let lilypond_root = match root {
-        "C#" => "cis",
-        "Db" => "des",
-        "D#" => "dis",
-        "Eb" => "ees",
-        "F#" => "fis",
-        "Gb" => "ges",
-        "G#" => "gis",
-        "Ab" => "aes",
-        "A#" => "ais",
-        "Bb" => "bes",
-        _ => root,
+/// Generate a printable chart of the diatonic triads and seventh chords in a key, or in every major key
+/// Each key is a page: triads on the first system and sevenths on the second, with their Roman numerals
+#[tauri::command]
+pub async fn generate_reference_chart(params: ReferenceChartParams) -> Result<WorksheetConfig, String> {
+    build_reference_chart(&params)
+}
+
+fn build_reference_chart(params: &ReferenceChartParams) -> Result<WorksheetConfig, String> {
+    let locale = i18n::locale(params.locale.as_deref());
+    let keys = match params.key.as_deref() {
+        Some(key) => vec![key],
+        None => CHART_KEYS.to_vec(),
+    };
+    let sections = keys
+        .into_iter()
+        .enumerate()
+        .map(|(index, key)| reference_chart_section(key, index, params.keyboard_diagrams, locale))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let title = match params.key.as_deref() {
+        Some(key) => {
+            let (tonic, mode) = NumeralMode::from_key(key);
+            locale.format("whole_key.title", &[("key", &key_display_name(tonic, mode, locale))])
+        }
+        None => locale.text("reference_chart.all_keys_title"),
+    };
+    Ok(WorksheetConfig {
+        id: Uuid::new_v4().to_string(),
+        title,
+        subtitle: Some(locale.text("reference_chart.subtitle")),
+        worksheet_type: WorksheetType::ChordNaming,
+        sections,
+        global_settings: WorksheetGlobalSettings {
+            paper_size: PaperSize::Letter,
+            orientation: Orientation::Portrait,
+            show_answers: true,
+            answer_color: None,
+            font_size: 14,
+            locale: params.locale.clone(),
+        },
+    })
+}
+
+/// One key of the reference chart: every chord shown, one per measure, with its numeral as the hint
+fn reference_chart_section(
+    key: &str,
+    index: usize,
+    keyboard_diagrams: bool,
+    locale: &Locale,
+) -> Result<WorksheetSection, String> {
+    let (tonic, mode) = NumeralMode::from_key(key);
+    let (scale, qualities) = match mode {
+        NumeralMode::Major => (ScaleType::Major, &MAJOR_KEY_QUALITIES),
+        _ => (ScaleType::NaturalMinor, &MINOR_KEY_QUALITIES),
     };
+    let roots = note_index(tonic)
+        .and_then(|_| scale_to_notes(tonic, scale))
+        .map_err(|e| format!("Invalid key {}: {}", key, e))?;
+
+    let symbol = |root: &String, quality| format!("{}{}", root, quality_suffix(quality));
+    let triads = roots.iter().zip(qualities.iter()).map(|(root, (triad, _))| symbol(root, triad));
+    let sevenths = roots.iter().zip(qualities.iter()).map(|(root, (_, seventh))| symbol(root, seventh));
+    let symbols: Vec<String> = triads.chain(sevenths).collect();
+    let analysis = analyze_progression(&symbols, key);
+
+    let mut elements = Vec::new();
+    for (position, (symbol, chord)) in symbols.iter().zip(&analysis).enumerate() {
+        let keyboard = match keyboard_diagrams {
+            true => Some(
+                voiced_chord_midi(symbol, KEYBOARD_VOICING, KEYBOARD_OCTAVE)
+                    .map_err(|e| format!("Failed to voice {}: {}", symbol, e))?,
+            ),
+            false => None,
+        };
+        elements.push(EditableElement {
+            id: format!("chart-{}-{}", index, position),
+            element_type: EditableElementType::Chord,
+            position: ElementPosition { measure: position as u32 + 1, beat: 1, voice: None },
+            content: symbol.clone(),
+            is_answer: false,
+            is_interactive: false,
+            style: None,
+            hint: chord.numeral.clone(),
+            keyboard,
+        });
+    }
+
+    // The staff only writes major key signatures; a minor key shares its relative major's
+    let signature_tonic = if scale == ScaleType::Major { tonic } else { &roots[2] };
+    Ok(WorksheetSection {
+        id: format!("chart-{}", index),
+        title: key_display_name(tonic, mode, locale),
+        instructions: None,
+        elements,
+        layout: WorksheetSectionLayout {
+            measures_per_system: roots.len() as u32,
+            systems_per_page: 2,
+            clef: Clef::Treble,
+            time_signature: Some("4/4".to_string()),
+            key_signature: Some(lilypond_note_name(signature_tonic)),
+            answer_blank: AnswerBlank::default(),
+        },
+    })
+}
+
+/// "Eb major", "F# minor", in the locale's words
+fn key_display_name(tonic: &str, mode: NumeralMode, locale: &Locale) -> String {
+    let key = if mode == NumeralMode::Major { "key.major" } else { "key.minor" };
+    locale.format(key, &[("tonic", tonic)])
+}
+
+/// Chord symbol of a root and quality as chord elements store it ("Bb7")
+fn format_chord_notation(root: &str, quality: &ChordQuality) -> String {
+    ChordSymbol::new(root, quality_suffix(quality)).to_string(SymbolStyle::default())
+}
 
-    let chord_extension = match quality {
+/// Chord symbol suffix of a quality ("m7b5")
+fn quality_suffix(quality: &ChordQuality) -> &'static str {
+    match quality {
         ChordQuality::Major => "",
         ChordQuality::Minor => "m",
         ChordQuality::Diminished => "dim",
@@ -425,7 +964,673 @@ fn format_chord_notation(root: &str, quality: &ChordQuality) -> String {
         ChordQuality::Dominant7 => "7",
         ChordQuality::Major7 => "maj7",
         ChordQuality::Minor7 => "m7",
+        ChordQuality::HalfDiminished7 => "m7b5",
+    }
+}
+/// Generate worksheet content from a performance captured at the keyboard
+/// Onsets are quantized to the selected beat grid; simultaneous notes become chords
+#[tauri::command]
+pub async fn generate_performance_template(params: PerformanceParams) -> Result<WorksheetConfig, String> {
+    build_performance_worksheet(&params)
+}
+
+/// Convert an exercise written in notation software, saved as uncompressed MusicXML, into an editable worksheet
+#[tauri::command]
+pub async fn import_musicxml(path: String) -> Result<WorksheetConfig, String> {
+    if path.to_lowercase().ends_with(".mxl") {
+        return Err("Compressed MusicXML (.mxl) isn't supported; export the score as uncompressed MusicXML".to_string());
+    }
+    let xml = fs::read_to_string(&path).map_err(|e| format!("Failed to read MusicXML file: {}", e))?;
+    musicxml_to_worksheet(&xml).map_err(|e| format!("Failed to import MusicXML: {}", e))
+}
+
+fn build_performance_worksheet(params: &PerformanceParams) -> Result<WorksheetConfig, String> {
+    if params.notes.is_empty() {
+        return Err("No notes were captured".to_string());
+    }
+    if !(params.tempo_bpm.is_finite() && params.tempo_bpm > 0.0) {
+        return Err(format!("Invalid tempo: {}", params.tempo_bpm));
+    }
+
+    let time_signature = params.time_signature.clone().unwrap_or_else(|| "4/4".to_string());
+    let beats_per_measure: u32 = time_signature
+        .split('/')
+        .next()
+        .and_then(|n| n.trim().parse().ok())
+        .filter(|n| *n > 0)
+        .ok_or_else(|| format!("Invalid time signature: {}", time_signature))?;
+    let grid_beats = params.grid_beats.unwrap_or(1).max(1);
+    let key = params.key.clone().unwrap_or_else(|| "C".to_string());
+
+    // Group notes by quantized beat, measured from the first onset
+    let beat_ms = 60_000.0 / params.tempo_bpm;
+    let origin = params.notes.iter().map(|n| n.start_ms).fold(f64::INFINITY, f64::min);
+    let mut slots: BTreeMap<u32, Vec<u8>> = BTreeMap::new();
+    for note in &params.notes {
+        let beats = ((note.start_ms - origin) / beat_ms).max(0.0);
+        let slot = (beats / grid_beats as f64).round() as u32 * grid_beats;
+        slots.entry(slot).or_default().push(note.midi);
+    }
+
+    let mut placed = Vec::new();
+    let mut has_chords = false;
+    let mut midi_total = 0u32;
+    let mut midi_count = 0u32;
+
+    for (slot, mut midis) in slots {
+        midis.sort_unstable();
+        midis.dedup();
+        midi_total += midis.iter().map(|m| *m as u32).sum::<u32>();
+        midi_count += midis.len() as u32;
+
+        let position = ElementPosition {
+            measure: slot / beats_per_measure + 1,
+            beat: slot % beats_per_measure + 1,
+            voice: None,
+        };
+
+        // Groups that don't form a recognizable chord keep their top (melody) note
+        let chord = name_midi_chord(&midis, &key);
+        has_chords |= chord.is_some();
+        placed.push((position, chord, *midis.last().unwrap()));
+    }
+
+    let clef = if midi_total >= 60 * midi_count { Clef::Treble } else { Clef::Bass };
+
+    // Melody notes move by octaves together to sit on the staff; any still off it are marked with an ottava
+    let melody: Vec<u8> = placed.iter().filter(|(_, chord, _)| chord.is_none()).map(|(_, _, midi)| *midi).collect();
+    let mut fitted = fit_to_range(&melody, &clef).notes.into_iter();
+    let elements: Vec<EditableElement> = placed
+        .into_iter()
+        .enumerate()
+        .map(|(index, (position, chord, _))| {
+            let (element_type, content, hint) = match chord {
+                Some(chord) => (EditableElementType::Chord, chord, None),
+                None => {
+                    let note = fitted.next().expect("every melody note is fitted");
+                    let hint = ottava_mark(note.ottava).map(str::to_string);
+                    (EditableElementType::Note, midi_to_lilypond_pitch(note.midi, &key), hint)
+                }
+            };
+            EditableElement {
+                id: format!("performance-{}", index),
+                element_type,
+                position,
+                content,
+                is_answer: params.as_answers,
+                is_interactive: true,
+                style: None,
+                hint,
+                keyboard: None,
+            }
+        })
+        .collect();
+    let locale = i18n::locale(params.locale.as_deref());
+    let (worksheet_type, default_instructions) = if has_chords {
+        (WorksheetType::ChordNaming, "instructions.identify_chords")
+    } else {
+        (WorksheetType::NoteIdentification, "instructions.identify_notes")
+    };
+
+    let section = WorksheetSection {
+        id: "performance-section".to_string(),
+        title: locale.text("performance.section"),
+        instructions: Some(params.instructions.clone().unwrap_or_else(|| locale.text(default_instructions))),
+        elements,
+        layout: WorksheetSectionLayout {
+            measures_per_system: 4,
+            systems_per_page: 4,
+            clef,
+            time_signature: Some(time_signature),
+            key_signature: Some(lilypond_note_name(&key)),
+            answer_blank: AnswerBlank::default(),
+        },
+    };
+
+    Ok(WorksheetConfig {
+        id: Uuid::new_v4().to_string(),
+        title: params.title.clone().unwrap_or_else(|| locale.text("performance.title")),
+        subtitle: None,
+        worksheet_type,
+        sections: vec![section],
+        global_settings: WorksheetGlobalSettings {
+            paper_size: PaperSize::Letter,
+            orientation: Orientation::Portrait,
+            show_answers: false,
+            answer_color: None,
+            font_size: 14,
+            locale: params.locale.clone(),
+        },
+    })
+}
+
+/// Absolute LilyPond pitch for a MIDI note (C3 = "c", middle C = "c'")
+fn midi_to_lilypond_pitch(midi: u8, key: &str) -> String {
+    let name = lilypond_note_name(get_preferred_note_name(midi % 12, key, false));
+    let octave = midi as i32 / 12 - 1;
+    let marks = match octave - 3 {
+        n if n > 0 => "'".repeat(n as usize),
+        n => ",".repeat((-n) as usize),
     };
+    format!("{}{}", name, marks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::assert_golden;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn note(midi: u8, start_ms: f64) -> PerformedNote {
+        PerformedNote { midi, start_ms }
+    }
+
+    fn params(notes: Vec<PerformedNote>) -> PerformanceParams {
+        PerformanceParams {
+            notes,
+            tempo_bpm: 120.0,
+            time_signature: None,
+            grid_beats: None,
+            key: None,
+            title: None,
+            instructions: None,
+            as_answers: false,
+            locale: None,
+        }
+    }
+
+
+    #[test]
+    fn test_elements_are_tagged_for_svg_ids() {
+        let element = EditableElement {
+            id: "chord-0\"".to_string(),
+            element_type: EditableElementType::Chord,
+            position: ElementPosition { measure: 1, beat: 1, voice: None },
+            content: "C".to_string(),
+            is_answer: false,
+            is_interactive: true,
+            style: None,
+            hint: None,
+            keyboard: None,
+        };
+        let (music, chords) = build_music_and_chords_from_elements(
+            &[element],
+            false,
+            None,
+            AnswerBlank::Rest,
+            TREBLE_CHORD_OCTAVE,
+        )
+        .unwrap();
+        assert!(music.contains(r#"\once \override NoteHead.output-attributes = #'((class . "interactive-note") (data-element-id . "chord-0"))"#));
+        assert!(chords.contains(r#"ChordName.output-attributes = #'((class . "interactive-chord") (data-element-id . "chord-0"))"#));
+    }
+
+    #[test]
+    fn test_chords_spelled_by_chord_engine() {
+        assert_eq!(chord_lilypond_pitches("Dm7", 4).unwrap(), "d' f' a' c''");
+        assert_eq!(chord_lilypond_pitches("Gsus4", 4).unwrap(), "g' c'' d''");
+        assert_eq!(chord_lilypond_pitches("Fdim7", 4).unwrap(), "f' aes' ces'' eeses''");
+        assert_eq!(chord_lilypond_pitches("Cmaj9", 3).unwrap(), "c e g b d'");
+        // The slash bass is written lowest, with the chord stacked above it as on native sheets
+        assert_eq!(chord_lilypond_pitches("C/E", 4).unwrap(), "e' c'' g''");
+        assert!(chord_lilypond_pitches("H7", 4).is_err());
+        // An unknown quality is an error, not a major triad
+        assert!(chord_lilypond_pitches("Cxyz", 4).is_err());
+    }
+
+    #[test]
+    fn test_flat_root_chords_engraved_with_their_own_notes() {
+        let config = whole_key("Eb", true).unwrap();
+        let pitches = |symbol: &str| {
+            let element = config.sections[0].elements.iter().find(|e| e.content == symbol).unwrap();
+            chord_lilypond_pitches(&element.content, TREBLE_CHORD_OCTAVE).unwrap()
+        };
+        assert_eq!(pitches("Bb7"), "bes' d'' f'' aes''");
+        assert_eq!(pitches("Eb"), "ees' g' bes'");
+        assert_eq!(pitches("Abmaj7"), "aes' c'' ees'' g''");
+
+        // Chords saved with LilyPond roots engrave the same notes
+        assert_eq!(chord_lilypond_pitches("bes7", 4).unwrap(), "bes' d'' f'' aes''");
+        assert_eq!(chord_lilypond_pitches("fism7", 4).unwrap(), "fis' a' cis'' e''");
+        assert_eq!(chord_lilypond_pitches("aesaug", 4).unwrap(), "aes' c'' e''");
+    }
+
+    #[test]
+    fn test_element_style_and_hint() {
+        let element = EditableElement {
+            id: "note-0".to_string(),
+            element_type: EditableElementType::Note,
+            position: ElementPosition { measure: 1, beat: 1, voice: None },
+            content: "e'".to_string(),
+            is_answer: true,
+            is_interactive: true,
+            style: Some(ElementStyle { color: Some("#f00".to_string()), enclosure: Some(Enclosure::Circle) }),
+            hint: Some("Count up from \"C\"".to_string()),
+            keyboard: None,
+        };
+
+        let elements = std::slice::from_ref(&element);
+        let (music, _) =
+            build_music_and_chords_from_elements(elements, true, None, AnswerBlank::Rest, TREBLE_CHORD_OCTAVE).unwrap();
+        assert!(music.contains(r"\once \override NoteHead.color = #(rgb-color 1.000 0.000 0.000)"));
+        assert!(music.contains("(circle-stencil (ly:note-head::print grob) 0.1 0.3)"));
+        assert!(music.contains(r#"e'4_\markup { \small "Count up from \"C\"" }"#));
 
-    format!("{}{}", lilypond_root, chord_extension)
-}
\ No newline at end of file
+        // The student copy hides the answer but keeps the hint
+        let (music, _) = build_music_and_chords_from_elements(
+            elements,
+            false,
+            None,
+            AnswerBlank::Rest,
+            TREBLE_CHORD_OCTAVE,
+        )
+        .unwrap();
+        assert!(music.starts_with(r#"r4_\markup { \small "Count up from \"C\"" }"#));
+        assert!(!music.contains("color"));
+
+        let style = Some(ElementStyle { color: Some("red".to_string()), enclosure: None });
+        let bad = EditableElement { style, ..element };
+        assert!(
+            build_music_and_chords_from_elements(&[bad], true, None, AnswerBlank::Rest, TREBLE_CHORD_OCTAVE).is_err()
+        );
+    }
+
+    #[test]
+    fn test_answer_blanks() {
+        let element = EditableElement {
+            id: "chord-0".to_string(),
+            element_type: EditableElementType::Chord,
+            position: ElementPosition { measure: 1, beat: 1, voice: None },
+            content: "Dm7".to_string(),
+            is_answer: true,
+            is_interactive: true,
+            style: None,
+            hint: Some("ii".to_string()),
+            keyboard: None,
+        };
+        let elements = std::slice::from_ref(&element);
+        let blank = |answer_blank| {
+            build_music_and_chords_from_elements(elements, false, None, answer_blank, TREBLE_CHORD_OCTAVE).unwrap()
+        };
+
+        assert_eq!(blank(AnswerBlank::Rest).0, r#"r4_\markup { \small "ii" } "#);
+        // Lines and boxes hang below the staff, above the hint
+        let (music, chords) = blank(AnswerBlank::Line);
+        assert!(music.starts_with(r"\once \hide Rest r4_\markup { \draw-line #'(4 . 0) }_\markup { \small"));
+        assert_eq!(chords, "r4 ");
+        assert!(blank(AnswerBlank::Box).0.contains(r"\box \pad-to-box"));
+        let shaded = blank(AnswerBlank::Shaded).0;
+        assert!(shaded.contains(r"Rest.layer = #-1") && shaded.contains(r"\filled-box"));
+        let question = blank(AnswerBlank::Question).0;
+        assert!(question.contains(r#"Rest.text = \markup { \vcenter \fontsize #2 \bold "?" } r4"#));
+
+        // Shown answers are drawn whatever the blank style
+        let (music, _) = build_music_and_chords_from_elements(
+            elements,
+            true,
+            None,
+            AnswerBlank::Shaded,
+            TREBLE_CHORD_OCTAVE,
+        )
+        .unwrap();
+        assert!(!music.contains("Rest"));
+    }
+
+    #[test]
+    fn test_answer_key_highlights_answers() {
+        let element = |id: &str, beat, is_answer| EditableElement {
+            id: id.to_string(),
+            element_type: EditableElementType::Note,
+            position: ElementPosition { measure: 1, beat, voice: None },
+            content: "e'".to_string(),
+            is_answer,
+            is_interactive: true,
+            style: Some(ElementStyle { color: Some("#00f".to_string()), enclosure: Some(Enclosure::Box) }),
+            hint: None,
+            keyboard: None,
+        };
+        let elements = [element("given-0", 1, false), element("answer-1", 2, true)];
+        let color = Some("#f00");
+        let (music, _) = build_music_and_chords_from_elements(
+            &elements,
+            true,
+            color,
+            AnswerBlank::Rest,
+            TREBLE_CHORD_OCTAVE,
+        )
+        .unwrap();
+        let (given, answer) = music.split_once("e'4 ").unwrap();
+        assert!(given.contains("rgb-color 0.000 0.000 1.000"));
+        assert!(answer.contains("rgb-color 1.000 0.000 0.000") && answer.contains("box-stencil"));
+
+        let mut config = build_performance_worksheet(&params(vec![note(60, 0.0)])).unwrap();
+        config.subtitle = None;
+        assert!(!student_copy(&config).global_settings.show_answers);
+        let key = answer_key(&config);
+        assert!(key.global_settings.show_answers);
+        assert_eq!(key.global_settings.answer_color.as_deref(), Some(ANSWER_KEY_COLOR));
+        assert_eq!(key.subtitle.as_deref(), Some("Answer Key"));
+    }
+
+    #[test]
+    fn test_worksheet_text_escaped() {
+        let mut config = build_performance_worksheet(&params(vec![note(60, 0.0)])).unwrap();
+        config.title = r#"Quiz" } #(system "rm -rf ~") \header { title = ""#.to_string();
+        config.subtitle = Some("Back\\slash".to_string());
+        config.sections[0].title = "Section \"1\"\n".to_string();
+        config.sections[0].instructions = Some(r#"Play \markup #(ly:gulp-file "/etc/passwd")"#.to_string());
+
+        let document = build_lilypond_document(&config).unwrap();
+        assert!(document.contains(r#"title = "Quiz\" } #(system \"rm -rf ~\") \\header { title = \"""#));
+        assert!(document.contains(r#"subtitle = "Back\\slash""#));
+        assert!(document.contains(r#"\bold { "Section \"1\" " }"#));
+        assert!(document.contains(r#"\italic { "Play \\markup #(ly:gulp-file \"/etc/passwd\")" }"#));
+        assert!(document.contains("\\key c \\major\n      \\time 4/4\n"));
+        // Every quote outside the escaped strings pairs up, so none of the text leaks out of its string
+        let unescaped = document.replace("\\\\", "").replace("\\\"", "");
+        assert_eq!(unescaped.matches('"').count() % 2, 0);
+
+        // Signatures are written as LilyPond music, so anything but a signature is rejected
+        config.sections[0].layout.time_signature = Some(r#"4/4" #(exit) ""#.to_string());
+        assert!(build_lilypond_document(&config).is_err());
+        config.sections[0].layout.time_signature = None;
+        config.sections[0].layout.key_signature = Some("ees \\major #(exit)".to_string());
+        assert!(build_lilypond_document(&config).is_err());
+    }
+
+    #[test]
+    fn test_element_content_validated() {
+        let mut config = chord_naming();
+        let document = build_lilypond_document(&answer_key(&config)).unwrap();
+        assert!(document.contains("\\new ChordNames \\chordmode {"));
+        assert!(document.contains(" bes4:1.3.5.7 "));
+        assert!(document.contains("(data-element-id . \"chord-3\")) d4:1.3-.5-.7 "));
+        assert!(document.contains("(data-element-id . \"chord-4\")) aes4:1.3.5+ "));
+        assert_eq!(chordmode_chord("C", 4).unwrap(), "c4");
+        assert_eq!(chordmode_chord("Cdim7/Eb", 2).unwrap(), "c2:1.3-.5-.7-/ees");
+        assert_eq!(chordmode_chord("G7b9", 4).unwrap(), "g4:1.3.5.7.9-");
+        assert_eq!(chordmode_chord("fism7", 4).unwrap(), "fis4:1.3-.5.7");
+
+        let hostile = [
+            (EditableElementType::Chord, "C4 #(ly:gulp-file \"/etc/passwd\")"),
+            (EditableElementType::Chord, "C/E #(exit)"),
+            (EditableElementType::Note, "c'4 #(ly:gulp-file \"/etc/passwd\")"),
+            (EditableElementType::Note, "<c' e'> \\bar"),
+            (EditableElementType::Rest, "r4 #(exit)"),
+        ];
+        for (element_type, content) in hostile {
+            let element = &mut config.sections[0].elements[0];
+            element.element_type = element_type;
+            element.content = content.to_string();
+            element.is_answer = false;
+            assert!(build_lilypond_document(&config).is_err(), "{}", content);
+        }
+
+        assert_eq!(note_content(" <c' ees' g'> ").unwrap(), "<c' ees' g'>");
+        assert_eq!(rest_content("r2.").unwrap(), "r2.");
+        assert!(rest_content("r3").is_err());
+    }
+
+    #[test]
+    fn test_reference_chart() {
+        let params = ReferenceChartParams { key: Some("Am".to_string()), keyboard_diagrams: true, locale: None };
+        let chart = build_reference_chart(&params).unwrap();
+        assert_eq!(chart.title, "Chords in A minor");
+        let section = &chart.sections[0];
+        assert_eq!(section.layout.key_signature.as_deref(), Some("c"));
+        let chords: Vec<&str> = section.elements.iter().map(|element| element.content.as_str()).collect();
+        assert_eq!(chords[..7], ["Am", "Bdim", "C", "Dm", "Em", "F", "G"]);
+        assert_eq!(chords[13], "G7");
+        assert_eq!(section.elements[1].hint.as_deref(), Some("ii°"));
+        assert_eq!(section.elements[8].keyboard.as_deref(), Some(&[59, 62, 65, 69][..]));
+
+        let elements = &section.elements[..1];
+        let (music, _) =
+            build_music_and_chords_from_elements(elements, true, None, AnswerBlank::Rest, TREBLE_CHORD_OCTAVE).unwrap();
+        assert!(music.contains("\\overlay"));
+
+        let every_key =
+            build_reference_chart(&ReferenceChartParams { key: None, keyboard_diagrams: false, locale: None }).unwrap();
+        assert_eq!(every_key.sections.len(), 12);
+        assert_eq!(every_key.sections[8].title, "Ab major");
+        let mut elements = every_key.sections.iter().flat_map(|section| &section.elements);
+        assert!(elements.all(|element| element.keyboard.is_none()));
+    }
+
+    #[test]
+    fn test_signature_changes_take_no_time() {
+        let element = |id: &str, element_type, measure, content: &str| EditableElement {
+            id: id.to_string(),
+            element_type,
+            position: ElementPosition { measure, beat: 1, voice: None },
+            content: content.to_string(),
+            is_answer: false,
+            is_interactive: true,
+            style: None,
+            hint: None,
+            keyboard: None,
+        };
+        let elements = [
+            element("note-0", EditableElementType::Note, 1, "c'"),
+            element("time-1", EditableElementType::TimeSignature, 2, "3/4"),
+            element("key-2", EditableElementType::KeySignature, 2, "ees"),
+            element("note-3", EditableElementType::Note, 2, "ees'"),
+        ];
+        let (music, chords) = build_music_and_chords_from_elements(
+            &elements,
+            true,
+            None,
+            AnswerBlank::Rest,
+            TREBLE_CHORD_OCTAVE,
+        )
+        .unwrap();
+        assert!(music.contains(" | \\time 3/4 \\key ees \\major "));
+        assert!(music.ends_with("ees'4 "));
+        assert_eq!(chords, "s4  | s4 ");
+
+        let bad = [element("time-0", EditableElementType::TimeSignature, 1, "3/4 \\bar")];
+        assert!(
+            build_music_and_chords_from_elements(&bad, true, None, AnswerBlank::Rest, TREBLE_CHORD_OCTAVE).is_err()
+        );
+    }
+
+    #[test]
+    fn test_midi_to_lilypond_pitch() {
+        assert_eq!(midi_to_lilypond_pitch(60, "C"), "c'");
+        assert_eq!(midi_to_lilypond_pitch(48, "C"), "c");
+        assert_eq!(midi_to_lilypond_pitch(70, "F"), "bes'");
+        assert_eq!(midi_to_lilypond_pitch(30, "G"), "fis,,");
+    }
+
+    #[test]
+    fn test_performance_quantized_to_beats() {
+        // 120 bpm: one beat every 500ms, played slightly off the grid
+        let notes = vec![
+            note(60, 1000.0),
+            note(64, 1010.0),
+            note(67, 995.0),
+            note(72, 1530.0),
+            note(65, 2980.0),
+            note(69, 3000.0),
+            note(72, 3020.0),
+        ];
+        let config = build_performance_worksheet(&params(notes)).unwrap();
+        let elements = &config.sections[0].elements;
+
+        assert_eq!(elements.len(), 3);
+        assert!(matches!(elements[0].element_type, EditableElementType::Chord));
+        assert_eq!(elements[0].content, "C");
+        assert!(matches!(elements[1].element_type, EditableElementType::Note));
+        assert_eq!(elements[1].content, "c''");
+        assert_eq!((elements[1].position.measure, elements[1].position.beat), (1, 2));
+        assert_eq!(elements[2].content, "F");
+        assert_eq!((elements[2].position.measure, elements[2].position.beat), (2, 1));
+        assert!(matches!(config.worksheet_type, WorksheetType::ChordNaming));
+    }
+
+    #[test]
+    fn test_performance_fitted_to_staff() {
+        // Played high above the staff: the melody comes down an octave onto the treble staff, keeping its shape
+        let config = build_performance_worksheet(&params(vec![note(96, 0.0), note(100, 500.0), note(91, 1000.0)]));
+        let elements = &config.unwrap().sections[0].elements;
+        let contents: Vec<&str> = elements.iter().map(|element| element.content.as_str()).collect();
+        assert_eq!(contents, ["c'''", "e'''", "g''"]);
+        assert!(elements.iter().all(|element| element.hint.is_none()));
+
+        // A note far above the rest keeps its place under an ottava instead of on ledger lines
+        let config = build_performance_worksheet(&params(vec![note(60, 0.0), note(64, 500.0), note(108, 1000.0)]));
+        let elements = &config.unwrap().sections[0].elements;
+        assert_eq!(elements[0].content, "c'");
+        assert_eq!((elements[2].content.as_str(), elements[2].hint.as_deref()), ("c'''", Some("15ma")));
+    }
+
+    #[test]
+    fn test_performance_coarser_grid() {
+        let mut request = params(vec![note(60, 0.0), note(62, 400.0), note(64, 1100.0)]);
+        request.grid_beats = Some(2);
+        request.time_signature = Some("3/4".to_string());
+        let config = build_performance_worksheet(&request).unwrap();
+        let positions: Vec<(u32, u32)> = config.sections[0]
+            .elements
+            .iter()
+            .map(|e| (e.position.measure, e.position.beat))
+            .collect();
+
+        // Beats 0 and 0.8 share the first slot, 2.2 snaps to beat 2 (measure 1, beat 3)
+        assert_eq!(positions, vec![(1, 1), (1, 3)]);
+        assert!(matches!(config.worksheet_type, WorksheetType::NoteIdentification));
+    }
+
+    fn whole_key(key: &str, include_sevenths: bool) -> Result<WorksheetConfig, String> {
+        let params = WholeKeyChordParams {
+            key: key.to_string(),
+            include_sevenths,
+            show_answers: false,
+            instructions: None,
+            layout: ChordLayout { chords_per_line: 4, show_staff_lines: true },
+            locale: None,
+        };
+        build_whole_key_worksheet(params, &mut StdRng::seed_from_u64(3))
+    }
+
+    #[test]
+    fn test_whole_key_covers_every_diatonic_chord() {
+        let config = whole_key("Eb", true).unwrap();
+        let section = &config.sections[0];
+        let mut contents: Vec<&str> = section.elements.iter().map(|e| e.content.as_str()).collect();
+        let measures: Vec<u32> = section.elements.iter().map(|e| e.position.measure).collect();
+        assert_eq!(measures, (1..=14).collect::<Vec<_>>());
+        assert_eq!(section.layout.key_signature.as_deref(), Some("ees"));
+
+        contents.sort_unstable();
+        let mut expected = vec![
+            "Eb", "Fm", "Gm", "Ab", "Bb", "Cm", "Ddim",
+            "Ebmaj7", "Fm7", "Gm7", "Abmaj7", "Bb7", "Cm7", "Dm7b5",
+        ];
+        expected.sort_unstable();
+        assert_eq!(contents, expected);
+    }
+
+    #[test]
+    fn test_whole_key_minor_uses_relative_major_signature() {
+        let config = whole_key("Bm", false).unwrap();
+        let section = &config.sections[0];
+        assert_eq!(section.elements.len(), 7);
+        assert_eq!(section.layout.key_signature.as_deref(), Some("d"));
+        assert!(section.elements.iter().any(|e| e.content == "C#dim"));
+        assert_eq!(config.title, "Chords in B minor");
+        assert!(whole_key("H", false).is_err());
+    }
+
+    #[test]
+    fn test_performance_rejects_empty_capture() {
+        assert!(build_performance_worksheet(&params(Vec::new())).is_err());
+    }
+
+    fn chord_naming() -> WorksheetConfig {
+        chord_naming_in(None)
+    }
+
+    fn chord_naming_in(locale: Option<&str>) -> WorksheetConfig {
+        let chords = [
+            ("C", ChordQuality::Major, true),
+            ("F#", ChordQuality::Minor7, false),
+            ("Bb", ChordQuality::Dominant7, true),
+            ("D", ChordQuality::HalfDiminished7, false),
+            ("Ab", ChordQuality::Augmented, false),
+        ];
+        let chords = chords
+            .into_iter()
+            .enumerate()
+            .map(|(index, (root, quality, show_answer))| ChordDefinition {
+                root: root.to_string(),
+                quality,
+                position: ElementPosition { measure: index as u32 + 1, beat: 1, voice: None },
+                show_answer,
+            })
+            .collect();
+        let params = ChordNamingParams {
+            chords,
+            instructions: Some("Name each \"chord\"".to_string()),
+            layout: ChordLayout { chords_per_line: 4, show_staff_lines: true },
+            locale: locale.map(str::to_string),
+        };
+        build_chord_naming_worksheet(params, "d")
+    }
+
+    /// Snapshots of the LilyPond source for representative worksheets; see test_support to update them
+    #[test]
+    fn test_lilypond_source_golden_files() {
+        let naming = chord_naming();
+        let mut bass_landscape = chord_naming();
+        bass_landscape.sections[0].layout.clef = Clef::Bass;
+        bass_landscape.global_settings.paper_size = PaperSize::A4;
+        bass_landscape.global_settings.orientation = Orientation::Landscape;
+        let notes = vec![note(60, 0.0), note(64, 0.0), note(67, 500.0), note(108, 1000.0)];
+        let performance = build_performance_worksheet(&params(notes));
+        let chart_params = ReferenceChartParams { key: Some("Am".to_string()), keyboard_diagrams: true, locale: None };
+        let chart = build_reference_chart(&chart_params);
+
+        let worksheets = [
+            ("chord_naming.ly", naming.clone()),
+            ("chord_naming_answer_key.ly", answer_key(&naming)),
+            ("chord_naming_bass_a4_landscape.ly", bass_landscape),
+            ("whole_key_eb.ly", whole_key("Eb", true).unwrap()),
+            ("performance.ly", performance.unwrap()),
+            ("reference_chart_am.ly", chart.unwrap()),
+        ];
+        for (name, config) in worksheets {
+            assert_golden(&format!("lilypond/{}", name), &build_lilypond_document(&config).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_localized_worksheet_text() {
+        let mut naming = chord_naming_in(Some("es"));
+        assert_eq!(naming.title, "Hoja de nombres de acordes");
+        assert_eq!(naming.sections[0].title, "Identificación de acordes");
+        naming.subtitle = Some("Unidad 2".to_string());
+        assert_eq!(answer_key(&naming).subtitle.as_deref(), Some("Unidad 2 (Clave de respuestas)"));
+
+        let params = WholeKeyChordParams {
+            key: "Eb".to_string(),
+            include_sevenths: false,
+            show_answers: false,
+            instructions: None,
+            layout: ChordLayout { chords_per_line: 4, show_staff_lines: true },
+            locale: Some("de-AT".to_string()),
+        };
+        let whole_key = build_whole_key_worksheet(params, &mut StdRng::seed_from_u64(3)).unwrap();
+        assert_eq!(whole_key.title, "Akkorde in Eb-Dur");
+        assert_eq!(whole_key.global_settings.locale.as_deref(), Some("de-AT"));
+
+        // Right-to-left headers are wrapped in direction marks; the music itself is untouched
+        let arabic = answer_key(&chord_naming_in(Some("ar")));
+        let source = build_lilypond_document(&arabic).unwrap();
+        assert!(source.contains("title = \"\u{200F}ورقة عمل تسمية الأكوردات\u{200F}\""));
+        assert!(source.contains("subtitle = \"\u{200F}مفتاح الإجابات\u{200F}\""));
+        assert!(source.contains("\\italic { \"\u{200F}Name each \\\"chord\\\"\u{200F}\" }"));
+        assert!(!build_lilypond_document(&chord_naming()).unwrap().contains('\u{200F}'));
+    }
+}
@@ -0,0 +1,202 @@
+// Minimal zip archive support
+// Writes deflated entries and reads stored or deflated entries, enough for .maestropack bundles
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+const END_OF_DIRECTORY_LEN: usize = 22;
+const LOCAL_HEADER_LEN: usize = 30;
+const CENTRAL_HEADER_LEN: usize = 46;
+
+/// Zip 2.0, the lowest version that supports deflate
+const ZIP_VERSION: u16 = 20;
+/// General purpose flag: names are UTF-8
+const UTF8_FLAG: u16 = 0x0800;
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATE: u16 = 8;
+/// 1980-01-01 00:00 in DOS date format; entries carry no meaningful timestamps
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// Largest total uncompressed size accepted when reading
+pub const MAX_UNCOMPRESSED_SIZE: u64 = 512 * 1024 * 1024;
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Result<u16, String> {
+    bytes
+        .get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| "Archive is truncated".to_string())
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Result<u32, String> {
+    bytes
+        .get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| "Archive is truncated".to_string())
+}
+
+/// Build a zip archive from (path, contents) pairs
+pub fn write_archive(entries: &BTreeMap<String, Vec<u8>>) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+
+    for (name, data) in entries {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).map_err(|e| format!("Failed to compress {}: {}", name, e))?;
+        let compressed = encoder.finish().map_err(|e| format!("Failed to compress {}: {}", name, e))?;
+
+        let offset = u32::try_from(out.len()).map_err(|_| "Archive is too large".to_string())?;
+        let name_len = u16::try_from(name.len()).map_err(|_| format!("File name is too long: {}", name))?;
+        let sizes = (
+            u32::try_from(compressed.len()).map_err(|_| format!("File is too large: {}", name))?,
+            u32::try_from(data.len()).map_err(|_| format!("File is too large: {}", name))?,
+        );
+        let crc = crc32(data);
+
+        // Fields shared by the local and central headers, from "version needed" to "extra length"
+        let mut common = Vec::with_capacity(26);
+        common.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+        common.extend_from_slice(&UTF8_FLAG.to_le_bytes());
+        common.extend_from_slice(&METHOD_DEFLATE.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&DOS_DATE.to_le_bytes());
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&sizes.0.to_le_bytes());
+        common.extend_from_slice(&sizes.1.to_le_bytes());
+        common.extend_from_slice(&name_len.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+
+        out.extend_from_slice(&LOCAL_HEADER_SIGNATURE.to_le_bytes());
+        out.extend_from_slice(&common);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&compressed);
+
+        central.extend_from_slice(&CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+        central.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+        central.extend_from_slice(&common);
+        central.extend_from_slice(&[0; 10]); // comment length, disk, internal and external attributes
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+    }
+
+    let count = u16::try_from(entries.len()).map_err(|_| "Too many files in archive".to_string())?;
+    let central_offset = u32::try_from(out.len()).map_err(|_| "Archive is too large".to_string())?;
+    let central_len = u32::try_from(central.len()).map_err(|_| "Archive is too large".to_string())?;
+    out.extend_from_slice(&central);
+
+    out.extend_from_slice(&END_OF_DIRECTORY_SIGNATURE.to_le_bytes());
+    out.extend_from_slice(&[0; 4]); // disk numbers
+    out.extend_from_slice(&count.to_le_bytes());
+    out.extend_from_slice(&count.to_le_bytes());
+    out.extend_from_slice(&central_len.to_le_bytes());
+    out.extend_from_slice(&central_offset.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+
+    Ok(out)
+}
+
+/// Read every file of a zip archive, checking CRCs
+/// Directory entries are skipped
+pub fn read_archive(bytes: &[u8]) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let end = (0..=bytes.len().saturating_sub(END_OF_DIRECTORY_LEN))
+        .rev()
+        .find(|at| read_u32(bytes, *at).is_ok_and(|sig| sig == END_OF_DIRECTORY_SIGNATURE))
+        .ok_or("Not a zip archive")?;
+
+    let count = read_u16(bytes, end + 10)? as usize;
+    let mut at = read_u32(bytes, end + 16)? as usize;
+    let mut entries = BTreeMap::new();
+    let mut total: u64 = 0;
+
+    for _ in 0..count {
+        if read_u32(bytes, at)? != CENTRAL_HEADER_SIGNATURE {
+            return Err("Corrupt zip central directory".to_string());
+        }
+        let method = read_u16(bytes, at + 10)?;
+        let crc = read_u32(bytes, at + 16)?;
+        let compressed_len = read_u32(bytes, at + 20)? as usize;
+        let size = read_u32(bytes, at + 24)? as u64;
+        let name_len = read_u16(bytes, at + 28)? as usize;
+        let extra_len = read_u16(bytes, at + 30)? as usize;
+        let comment_len = read_u16(bytes, at + 32)? as usize;
+        let local = read_u32(bytes, at + 42)? as usize;
+        let name = bytes
+            .get(at + CENTRAL_HEADER_LEN..at + CENTRAL_HEADER_LEN + name_len)
+            .ok_or("Archive is truncated")?;
+        let name = String::from_utf8(name.to_vec()).map_err(|_| "Invalid file name in archive".to_string())?;
+        at += CENTRAL_HEADER_LEN + name_len + extra_len + comment_len;
+
+        if name.ends_with('/') {
+            continue;
+        }
+
+        total += size;
+        if total > MAX_UNCOMPRESSED_SIZE {
+            return Err("Archive contents are too large".to_string());
+        }
+
+        if read_u32(bytes, local)? != LOCAL_HEADER_SIGNATURE {
+            return Err(format!("Corrupt zip entry: {}", name));
+        }
+        let data_start = local + LOCAL_HEADER_LEN + read_u16(bytes, local + 26)? as usize + read_u16(bytes, local + 28)? as usize;
+        let raw = bytes.get(data_start..data_start + compressed_len).ok_or("Archive is truncated")?;
+
+        let data = match method {
+            METHOD_STORED => raw.to_vec(),
+            METHOD_DEFLATE => {
+                let mut data = Vec::new();
+                DeflateDecoder::new(raw)
+                    .take(size + 1)
+                    .read_to_end(&mut data)
+                    .map_err(|e| format!("Failed to decompress {}: {}", name, e))?;
+                data
+            }
+            other => return Err(format!("Unsupported compression method {} for {}", other, name)),
+        };
+        if data.len() as u64 != size || crc32(&data) != crc {
+            return Err(format!("Checksum mismatch for {}", name));
+        }
+
+        entries.insert(name, data);
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut entries = BTreeMap::new();
+        entries.insert("manifest.json".to_string(), b"{}".to_vec());
+        entries.insert("audio/tone.wav".to_string(), vec![7u8; 4096]);
+        entries.insert("empty.txt".to_string(), Vec::new());
+
+        let bytes = write_archive(&entries).unwrap();
+        assert_eq!(read_archive(&bytes).unwrap(), entries);
+    }
+
+    #[test]
+    fn test_rejects_corruption() {
+        let mut entries = BTreeMap::new();
+        entries.insert("a.txt".to_string(), b"hello hello hello".to_vec());
+        let mut bytes = write_archive(&entries).unwrap();
+
+        assert!(read_archive(b"not a zip").is_err());
+        bytes[LOCAL_HEADER_LEN + 5] ^= 0xff;
+        assert!(read_archive(&bytes).is_err());
+    }
+}
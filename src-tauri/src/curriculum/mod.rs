@@ -0,0 +1,5 @@
+mod archive;
+mod pack;
+
+pub use archive::write_archive;
+pub use pack::{CurriculumPack, LessonContent, PackProgression, PACK_EXTENSION};
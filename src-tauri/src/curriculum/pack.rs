@@ -0,0 +1,336 @@
+// .maestropack curriculum bundles
+// A zip archive with a manifest listing ordered lessons, their worksheet templates, progressions and audio
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use super::archive::{read_archive, write_archive};
+use crate::types::versioned;
+use crate::types::worksheet::WorksheetConfig;
+
+/// File extension of curriculum packs
+pub const PACK_EXTENSION: &str = "maestropack";
+
+/// Current manifest format version
+pub const PACK_FORMAT_VERSION: u32 = 1;
+
+/// Path of the manifest inside the archive
+const MANIFEST_PATH: &str = "manifest.json";
+
+/// Audio formats accepted as lesson assets
+const AUDIO_EXTENSIONS: [&str; 4] = ["wav", "mp3", "ogg", "flac"];
+
+/// A chord progression attached to a lesson
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackProgression {
+    pub name: String,
+    pub key: String,
+    pub chords: Vec<String>,
+}
+
+/// One lesson in the manifest; file fields are paths inside the archive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackLesson {
+    pub title: String,
+    /// Lesson text shown to students
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub worksheets: Vec<String>,
+    #[serde(default)]
+    pub progressions: Vec<PackProgression>,
+    #[serde(default)]
+    pub audio: Vec<String>,
+}
+
+/// Integrity record for one file in the archive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackFile {
+    pub path: String,
+    /// Lowercase hex SHA-256 of the contents
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// Manifest describing a pack; lessons are in teaching order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackManifest {
+    pub version: u32,
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub lessons: Vec<PackLesson>,
+    pub files: Vec<PackFile>,
+}
+
+/// Lesson content before it is packed
+#[derive(Debug, Clone)]
+pub struct LessonContent {
+    pub title: String,
+    pub text: String,
+    pub worksheets: Vec<WorksheetConfig>,
+    pub progressions: Vec<PackProgression>,
+    /// (file name, contents) pairs
+    pub audio: Vec<(String, Vec<u8>)>,
+}
+
+/// A curriculum pack held in memory
+#[derive(Debug, Clone)]
+pub struct CurriculumPack {
+    pub manifest: PackManifest,
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Archive paths must be relative and stay inside the pack
+fn check_path(path: &str) -> Result<(), String> {
+    let unsafe_path = path.is_empty()
+        || path.starts_with('/')
+        || path.contains('\\')
+        || path.contains(':')
+        || path.split('/').any(|part| part.is_empty() || part == "." || part == "..");
+    if unsafe_path {
+        Err(format!("Invalid path in pack: {}", path))
+    } else {
+        Ok(())
+    }
+}
+
+fn is_audio_path(path: &str) -> bool {
+    path.rsplit_once('.')
+        .is_some_and(|(_, ext)| AUDIO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Keep only characters that are safe in an archive file name
+fn sanitize_file_name(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let cleaned: String = base
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    cleaned.trim_start_matches('.').to_string()
+}
+
+impl CurriculumPack {
+    pub fn new(title: &str, author: Option<String>, description: Option<String>) -> Self {
+        Self {
+            manifest: PackManifest {
+                version: PACK_FORMAT_VERSION,
+                id: Uuid::new_v4().to_string(),
+                title: title.to_string(),
+                author,
+                description,
+                lessons: Vec::new(),
+                files: Vec::new(),
+            },
+            files: BTreeMap::new(),
+        }
+    }
+
+    fn add_file(&mut self, path: String, data: Vec<u8>) -> Result<String, String> {
+        check_path(&path)?;
+        if self.files.contains_key(&path) {
+            return Err(format!("Duplicate file in pack: {}", path));
+        }
+        self.manifest.files.push(PackFile { path: path.clone(), sha256: sha256_hex(&data), size: data.len() as u64 });
+        self.files.insert(path.clone(), data);
+        Ok(path)
+    }
+
+    /// Append a lesson, storing its worksheets and audio as files
+    pub fn add_lesson(&mut self, lesson: LessonContent) -> Result<(), String> {
+        let dir = format!("lessons/{:02}", self.manifest.lessons.len() + 1);
+
+        let mut worksheets = Vec::new();
+        for (i, config) in lesson.worksheets.iter().enumerate() {
+            let json = versioned::to_string_pretty(config)?;
+            worksheets.push(self.add_file(format!("{}/worksheet-{}.json", dir, i + 1), json.into_bytes())?);
+        }
+
+        let mut audio = Vec::new();
+        for (name, data) in lesson.audio {
+            let file_name = sanitize_file_name(&name);
+            if !is_audio_path(&file_name) {
+                return Err(format!("Unsupported audio file: {}", name));
+            }
+            audio.push(self.add_file(format!("{}/audio/{}", dir, file_name), data)?);
+        }
+
+        self.manifest.lessons.push(PackLesson {
+            title: lesson.title,
+            text: lesson.text,
+            worksheets,
+            progressions: lesson.progressions,
+            audio,
+        });
+        Ok(())
+    }
+
+    /// Contents of a file in the pack
+    pub fn file(&self, path: &str) -> Option<&[u8]> {
+        self.files.get(path).map(Vec::as_slice)
+    }
+
+    /// Parse a worksheet template stored in the pack
+    pub fn worksheet(&self, path: &str) -> Result<WorksheetConfig, String> {
+        let data = self.file(path).ok_or_else(|| format!("Missing worksheet in pack: {}", path))?;
+        versioned::from_slice(data)
+            .map(|loaded| loaded.value)
+            .map_err(|e| format!("Invalid worksheet {}: {}", path, e))
+    }
+
+    /// Check the manifest against the files it describes
+    /// Every listed file must be present with a matching hash, every lesson reference must
+    /// point at a listed file of the right kind, and no unlisted files may be bundled
+    pub fn validate(&self) -> Result<(), String> {
+        if self.manifest.version > PACK_FORMAT_VERSION {
+            return Err(format!(
+                "Pack format version {} is newer than supported version {}",
+                self.manifest.version, PACK_FORMAT_VERSION
+            ));
+        }
+        if self.manifest.title.trim().is_empty() {
+            return Err("Pack has no title".to_string());
+        }
+
+        for entry in &self.manifest.files {
+            check_path(&entry.path)?;
+            let data = self.file(&entry.path).ok_or_else(|| format!("Missing file in pack: {}", entry.path))?;
+            if data.len() as u64 != entry.size || sha256_hex(data) != entry.sha256 {
+                return Err(format!("Integrity check failed for {}", entry.path));
+            }
+        }
+        if let Some(extra) = self.files.keys().find(|path| !self.manifest.files.iter().any(|f| &f.path == *path)) {
+            return Err(format!("Unlisted file in pack: {}", extra));
+        }
+
+        for lesson in &self.manifest.lessons {
+            for path in &lesson.worksheets {
+                self.worksheet(path)?;
+            }
+            for path in &lesson.audio {
+                if self.file(path).is_none() || !is_audio_path(path) {
+                    return Err(format!("Invalid audio asset in lesson '{}': {}", lesson.title, path));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Serialize to .maestropack archive bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        self.validate()?;
+        let mut entries = self.files.clone();
+        let manifest =
+            serde_json::to_vec_pretty(&self.manifest).map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+        entries.insert(MANIFEST_PATH.to_string(), manifest);
+        write_archive(&entries)
+    }
+
+    /// Read and validate a .maestropack archive
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut files = read_archive(bytes).map_err(|e| format!("Invalid pack: {}", e))?;
+        let manifest = files.remove(MANIFEST_PATH).ok_or("Pack has no manifest")?;
+        let manifest: PackManifest =
+            serde_json::from_slice(&manifest).map_err(|e| format!("Invalid pack manifest: {}", e))?;
+
+        let pack = Self { manifest, files };
+        pack.validate()?;
+        Ok(pack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::worksheet::*;
+
+    fn worksheet() -> WorksheetConfig {
+        WorksheetConfig {
+            id: "ws-1".to_string(),
+            title: "Triads".to_string(),
+            subtitle: None,
+            worksheet_type: WorksheetType::ChordNaming,
+            sections: Vec::new(),
+            global_settings: WorksheetGlobalSettings {
+                paper_size: PaperSize::Letter,
+                orientation: Orientation::Portrait,
+                show_answers: false,
+                answer_color: None,
+                font_size: 14,
+                locale: None,
+            },
+        }
+    }
+
+    fn sample_pack() -> CurriculumPack {
+        let mut pack = CurriculumPack::new("Unit 1", Some("Ms. Rivera".to_string()), None);
+        pack.add_lesson(LessonContent {
+            title: "Major triads".to_string(),
+            text: "Stack two thirds.".to_string(),
+            worksheets: vec![worksheet()],
+            progressions: vec![PackProgression {
+                name: "Cadence".to_string(),
+                key: "C".to_string(),
+                chords: vec!["C".to_string(), "F".to_string(), "G".to_string(), "C".to_string()],
+            }],
+            audio: vec![("../../Example Tone.wav".to_string(), vec![1, 2, 3])],
+        })
+        .unwrap();
+        pack
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let pack = sample_pack();
+        let restored = CurriculumPack::from_bytes(&pack.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(restored.manifest, pack.manifest);
+        let lesson = &restored.manifest.lessons[0];
+        assert_eq!(lesson.audio, vec!["lessons/01/audio/Example_Tone.wav"]);
+        assert_eq!(restored.file(&lesson.audio[0]), Some(&[1u8, 2, 3][..]));
+        assert_eq!(restored.worksheet(&lesson.worksheets[0]).unwrap().title, "Triads");
+    }
+
+    #[test]
+    fn test_integrity_failures() {
+        let mut tampered = sample_pack();
+        tampered.files.insert("lessons/01/audio/Example_Tone.wav".to_string(), vec![9, 9, 9]);
+        assert!(tampered.validate().unwrap_err().contains("Integrity"));
+
+        let mut unlisted = sample_pack();
+        unlisted.files.insert("extra.bin".to_string(), Vec::new());
+        assert!(unlisted.validate().is_err());
+
+        let mut escaping = sample_pack();
+        escaping.manifest.lessons[0].audio.push("../secret.wav".to_string());
+        assert!(escaping.validate().is_err());
+    }
+
+    #[test]
+    fn test_rejects_unsupported_audio_and_paths() {
+        let mut pack = CurriculumPack::new("Unit", None, None);
+        let lesson = LessonContent {
+            title: "Lesson".to_string(),
+            text: String::new(),
+            worksheets: Vec::new(),
+            progressions: Vec::new(),
+            audio: vec![("notes.exe".to_string(), Vec::new())],
+        };
+        assert!(pack.add_lesson(lesson).is_err());
+
+        assert!(check_path("lessons/01/a.wav").is_ok());
+        for bad in ["/etc/passwd", "a/../b", "C:/x", "a//b", ""] {
+            assert!(check_path(bad).is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// Per-document backend state
+// Each window, or canvas within a window, gets its own audio session, voicing memory and analysis
+
+use std::collections::HashMap;
+
+/// Separator between a window label and a canvas id in a document id
+const CANVAS_SEPARATOR: char = '/';
+
+/// Document id for a command call: the window label, narrowed to a canvas when one is given
+pub fn document_id(window_label: &str, canvas: Option<&str>) -> String {
+    match canvas {
+        Some(canvas) if !canvas.is_empty() => format!("{}{}{}", window_label, CANVAS_SEPARATOR, canvas),
+        _ => window_label.to_string(),
+    }
+}
+
+/// Check whether a document lives in the given window
+pub fn belongs_to_window(document: &str, window_label: &str) -> bool {
+    document
+        .strip_prefix(window_label)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(CANVAS_SEPARATOR))
+}
+
+/// State kept separately for each open document
+pub struct DocumentMap<T> {
+    entries: HashMap<String, T>,
+}
+
+impl<T> Default for DocumentMap<T> {
+    fn default() -> Self {
+        Self { entries: HashMap::new() }
+    }
+}
+
+impl<T> DocumentMap<T> {
+    pub fn get(&self, document: &str) -> Option<&T> {
+        self.entries.get(document)
+    }
+
+    /// State of a document, created on first use
+    pub fn get_or_try_insert_with(
+        &mut self,
+        document: &str,
+        create: impl FnOnce() -> Result<T, String>,
+    ) -> Result<&mut T, String> {
+        if !self.entries.contains_key(document) {
+            self.entries.insert(document.to_string(), create()?);
+        }
+        Ok(self.entries.get_mut(document).expect("entry was just inserted"))
+    }
+
+    pub fn get_mut(&mut self, document: &str) -> Option<&mut T> {
+        self.entries.get_mut(document)
+    }
+
+    pub fn remove(&mut self, document: &str) -> Option<T> {
+        self.entries.remove(document)
+    }
+
+    /// Drop the state of every document in a window
+    pub fn remove_window(&mut self, window_label: &str) {
+        self.entries.retain(|document, _| !belongs_to_window(document, window_label));
+    }
+}
+
+impl<T: Default> DocumentMap<T> {
+    /// State of a document, created with defaults on first use
+    pub fn entry(&mut self, document: &str) -> &mut T {
+        self.entries.entry(document.to_string()).or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_ids() {
+        assert_eq!(document_id("main", None), "main");
+        assert_eq!(document_id("main", Some("canvas-2")), "main/canvas-2");
+        assert!(belongs_to_window("main/canvas-2", "main"));
+        assert!(belongs_to_window("main", "main"));
+        assert!(!belongs_to_window("main-2", "main"));
+    }
+
+    #[test]
+    fn test_documents_are_isolated() {
+        let mut map: DocumentMap<Vec<u32>> = DocumentMap::default();
+        map.entry("main").push(1);
+        map.entry("main/b").push(2);
+        map.entry("worksheet").push(3);
+
+        assert_eq!(map.get("main"), Some(&vec![1]));
+        map.remove_window("main");
+        assert!(map.get("main").is_none());
+        assert!(map.get("main/b").is_none());
+        assert_eq!(map.get("worksheet"), Some(&vec![3]));
+    }
+}
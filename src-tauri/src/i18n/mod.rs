@@ -0,0 +1,144 @@
+// Localized text for generated documents
+// Section titles, instructions and answer-key labels come from the locale files next to this module.
+// Strings missing from a locale fall back to English, so a partial translation still renders
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Locale used when none is given or the requested one isn't available
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Locale files embedded at build time: (code, JSON)
+const LOCALE_FILES: &[(&str, &str)] = &[
+    ("en", include_str!("en.json")),
+    ("es", include_str!("es.json")),
+    ("fr", include_str!("fr.json")),
+    ("de", include_str!("de.json")),
+    ("ar", include_str!("ar.json")),
+    ("he", include_str!("he.json")),
+];
+
+/// Right-to-left mark; keeps punctuation and Latin chord names at the right end of an RTL line
+pub const RLM: char = '\u{200F}';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Direction {
+    Ltr,
+    Rtl,
+}
+
+#[derive(Debug, Deserialize)]
+struct LocaleFile {
+    direction: Direction,
+    strings: HashMap<String, String>,
+}
+
+/// A loaded locale
+#[derive(Debug)]
+pub struct Locale {
+    code: &'static str,
+    direction: Direction,
+    strings: HashMap<String, String>,
+}
+
+fn locales() -> &'static [Locale] {
+    static LOCALES: OnceLock<Vec<Locale>> = OnceLock::new();
+    LOCALES.get_or_init(|| {
+        LOCALE_FILES
+            .iter()
+            .map(|(code, json)| {
+                let file: LocaleFile =
+                    serde_json::from_str(json).unwrap_or_else(|e| panic!("Invalid locale file {}: {}", code, e));
+                Locale { code, direction: file.direction, strings: file.strings }
+            })
+            .collect()
+    })
+}
+
+/// Codes of the available locales
+pub fn available_locales() -> Vec<&'static str> {
+    locales().iter().map(|locale| locale.code).collect()
+}
+
+/// Find a locale by code ("es", "pt-BR", "ar_EG"), trying the language alone before falling back to English
+pub fn locale(code: Option<&str>) -> &'static Locale {
+    let all = locales();
+    let find = |code: &str| all.iter().find(|locale| locale.code.eq_ignore_ascii_case(code));
+    code.map(str::trim)
+        .and_then(|code| find(code).or_else(|| code.split(['-', '_']).next().and_then(find)))
+        .or_else(|| find(DEFAULT_LOCALE))
+        .expect("English locale is embedded")
+}
+
+impl Locale {
+    pub fn is_rtl(&self) -> bool {
+        self.direction == Direction::Rtl
+    }
+
+    /// The string for a key, from English if this locale lacks it; the key itself if no locale has it
+    pub fn text(&self, key: &str) -> String {
+        self.strings
+            .get(key)
+            .or_else(|| locale(None).strings.get(key))
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// The string for a key with its `{name}` placeholders filled in
+    pub fn format(&self, key: &str, args: &[(&str, &str)]) -> String {
+        args.iter()
+            .fold(self.text(key), |text, (name, value)| text.replace(&format!("{{{}}}", name), value))
+    }
+
+    /// Wrap a line in right-to-left marks for RTL locales so it reads and aligns right to left
+    /// even when it starts or ends with Latin text such as a chord or key name
+    pub fn directional(&self, text: &str) -> String {
+        if self.is_rtl() && !text.is_empty() {
+            format!("{}{}{}", RLM, text, RLM)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(text: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = text.split('{').skip(1).filter_map(|part| part.split('}').next()).collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn test_locales_translate_every_english_string() {
+        let english = locale(None);
+        for code in available_locales() {
+            let locale = locale(Some(code));
+            for (key, text) in &english.strings {
+                let translated = locale.strings.get(key).unwrap_or_else(|| panic!("{} is missing {}", code, key));
+                assert_eq!(placeholders(translated), placeholders(text), "{} {}", code, key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_locale_lookup_and_fallback() {
+        assert_eq!(locale(Some("es")).code, "es");
+        assert_eq!(locale(Some("fr-CA")).code, "fr");
+        assert_eq!(locale(Some("AR_eg")).code, "ar");
+        assert_eq!(locale(Some("xx")).code, "en");
+        assert_eq!(locale(None).code, "en");
+
+        let arabic = locale(Some("ar"));
+        assert!(arabic.is_rtl());
+        assert!(!locale(Some("de")).is_rtl());
+        assert_eq!(arabic.text("unknown.key"), "unknown.key");
+        assert_eq!(locale(Some("es")).format("key.major", &[("tonic", "Re")]), "Re mayor");
+        assert_eq!(arabic.directional("Am"), "\u{200F}Am\u{200F}");
+        assert_eq!(locale(None).directional("Am"), "Am");
+    }
+}
@@ -0,0 +1,47 @@
+// Maestro Blocks
+// Music theory, training and worksheet generation, with playback, LilyPond engraving and the
+// Tauri desktop app behind cargo features:
+// - `theory-only` (with --no-default-features): theory, training, notation and document modules, and
+//   a guarantee of it: the build fails if anything turns on a subsystem below
+// - `audio`: sample playback, offline rendering and MIDI keyboard input, into a null output
+// - `playback`: `audio` through the default output device
+// - `lilypond`: engraving through an installed LilyPond
+// - `storage`: imported progression statistics in an embedded SQLite database
+// - `gui` (default): the desktop app, with every subsystem
+
+#[cfg(all(
+    feature = "theory-only",
+    any(feature = "audio", feature = "lilypond", feature = "storage", feature = "gui")
+))]
+compile_error!("`theory-only` excludes `audio`, `lilypond`, `storage` and `gui`; build it with --no-default-features");
+
+pub mod analytics;
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod autosave;
+pub mod curriculum;
+pub mod documents;
+pub mod i18n;
+pub mod library;
+#[cfg(feature = "lilypond")]
+pub mod lilypond;
+pub mod midi;
+pub mod music;
+pub mod notation;
+pub mod practice_stats;
+pub mod recent_files;
+pub mod render_history;
+pub mod settings;
+pub mod svg;
+pub mod training;
+pub mod types;
+
+#[cfg(feature = "gui")]
+mod commands;
+#[cfg(feature = "gui")]
+mod app;
+#[cfg(all(test, feature = "gui"))]
+mod test_support;
+
+#[cfg(feature = "gui")]
+pub use app::run;
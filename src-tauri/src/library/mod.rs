@@ -0,0 +1,324 @@
+// Example library
+// Curated progressions and exercises compiled into the app, searchable by text, style, difficulty and concept,
+// and ready to load onto the canvas or turn into a worksheet in any key
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::music::chords::transpose_chord;
+use crate::music::types::{Accidental, MusicError, MusicResult};
+use crate::notation::key_signature::{get_key_signature_layout, key_for_fifths, StaffClef};
+use crate::notation::voicing::lilypond_note_name;
+use crate::types::song::SongChord;
+use crate::types::worksheet::*;
+
+/// Beats per measure when a library progression is laid out on a worksheet
+const WORKSHEET_BEATS_PER_MEASURE: f32 = 4.0;
+
+/// Search weight of a term found in the title, a concept, or anywhere else in an entry
+const TITLE_WEIGHT: u32 = 3;
+const CONCEPT_WEIGHT: u32 = 2;
+const OTHER_WEIGHT: u32 = 1;
+
+static LIBRARY: Lazy<Vec<LibraryEntry>> = Lazy::new(|| {
+    serde_json::from_str(include_str!("progressions.json")).expect("bundled library is valid JSON")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Beginner,
+    Intermediate,
+    Advanced,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    /// A progression to play and study
+    Example,
+    /// A progression to name, with instructions for students
+    Exercise,
+}
+
+/// A progression in the library, written in its home key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    pub id: String,
+    pub title: String,
+    pub kind: EntryKind,
+    /// "pop", "jazz", "blues", "classical", ...
+    pub style: String,
+    pub difficulty: Difficulty,
+    /// Theory topics the progression shows ("ii-V-I", "deceptive cadence")
+    pub concepts: Vec<String>,
+    /// Key name, with an "m" suffix for minor keys ("Bb", "Cm")
+    pub key: String,
+    pub description: String,
+    /// Instructions printed on worksheets made from an exercise
+    #[serde(default)]
+    pub instructions: Option<String>,
+    pub chords: Vec<SongChord>,
+}
+
+/// Narrows a library search; unset fields match everything
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LibraryFilters {
+    pub kind: Option<EntryKind>,
+    pub style: Option<String>,
+    pub difficulty: Option<Difficulty>,
+    /// Entries must show every one of these concepts
+    pub concepts: Vec<String>,
+}
+
+/// A library progression written out in the key it was requested in
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LibraryProgression {
+    pub id: String,
+    pub key: String,
+    pub chords: Vec<SongChord>,
+}
+
+pub fn find_entry(id: &str) -> MusicResult<&'static LibraryEntry> {
+    LIBRARY
+        .iter()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| MusicError::DataLookupFailed(format!("library entry {}", id)))
+}
+
+fn matches_filters(entry: &LibraryEntry, filters: &LibraryFilters) -> bool {
+    filters.kind.is_none_or(|kind| entry.kind == kind)
+        && filters.style.as_deref().is_none_or(|style| entry.style.eq_ignore_ascii_case(style.trim()))
+        && filters.difficulty.is_none_or(|difficulty| entry.difficulty == difficulty)
+        && filters
+            .concepts
+            .iter()
+            .all(|wanted| entry.concepts.iter().any(|concept| concept.eq_ignore_ascii_case(wanted.trim())))
+}
+
+/// How well an entry matches every search term; None when any term is missing from it
+fn relevance(entry: &LibraryEntry, terms: &[String]) -> Option<u32> {
+    terms.iter().try_fold(0, |total, term| {
+        let contains = |text: &str| text.to_lowercase().contains(term.as_str());
+        let weight = if contains(&entry.title) {
+            TITLE_WEIGHT
+        } else if entry.concepts.iter().any(|concept| contains(concept)) {
+            CONCEPT_WEIGHT
+        } else if contains(&entry.style)
+            || contains(&entry.description)
+            || entry.chords.iter().any(|chord| chord.chord.eq_ignore_ascii_case(term))
+        {
+            OTHER_WEIGHT
+        } else {
+            return None;
+        };
+        Some(total + weight)
+    })
+}
+
+/// Entries matching the filters and every word of the query, best matches first
+/// An empty query lists every entry that passes the filters, easiest first
+pub fn search_library(query: &str, filters: &LibraryFilters) -> Vec<&'static LibraryEntry> {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    let mut found: Vec<(u32, &LibraryEntry)> = LIBRARY
+        .iter()
+        .filter(|entry| matches_filters(entry, filters))
+        .filter_map(|entry| relevance(entry, &terms).map(|score| (score, entry)))
+        .collect();
+    found.sort_by(|(a_score, a), (b_score, b)| {
+        b_score.cmp(a_score).then(a.difficulty.cmp(&b.difficulty)).then_with(|| a.title.cmp(&b.title))
+    });
+    found.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Tonic and mode of a key name ("F#m" → ("F#", true))
+fn split_key(key: &str) -> (&str, bool) {
+    match key.strip_suffix('m') {
+        Some(tonic) => (tonic, true),
+        None => (key, false),
+    }
+}
+
+/// Major key whose signature a key is written with, and whether it is spelled with flats
+fn signature(key: &str) -> MusicResult<(&'static str, bool)> {
+    let layout = get_key_signature_layout(key, StaffClef::Treble)?;
+    let flats = layout.accidentals.first().is_some_and(|accidental| accidental.accidental == Accidental::Flat);
+    let count = layout.accidentals.len() as i32;
+    let major = key_for_fifths(if flats { -count } else { count }, false);
+    Ok((major.ok_or_else(|| MusicError::InvalidKey(key.to_string()))?, flats))
+}
+
+/// An entry's chords in another key, keeping its mode: "D" or "Dm" both move a minor progression to D minor
+/// None keeps the entry's own key
+pub fn progression_in_key(entry: &LibraryEntry, key: Option<&str>) -> MusicResult<LibraryProgression> {
+    let (from, minor) = split_key(&entry.key);
+    let Some(to) = key.map(str::trim).filter(|key| !key.is_empty()) else {
+        return Ok(LibraryProgression { id: entry.id.clone(), key: entry.key.clone(), chords: entry.chords.clone() });
+    };
+    let (to, _) = split_key(to);
+    let key = if minor { format!("{}m", to) } else { to.to_string() };
+    let (_, use_flats) = signature(&key)?;
+
+    let chords = entry
+        .chords
+        .iter()
+        .map(|chord| {
+            let symbol = transpose_chord(&chord.chord, from, to, use_flats)?;
+            Ok(SongChord { chord: symbol, beats: chord.beats })
+        })
+        .collect::<MusicResult<Vec<_>>>()?;
+    Ok(LibraryProgression { id: entry.id.clone(), key, chords })
+}
+
+/// A chord naming worksheet of an entry in the given key, one chord element where each chord starts
+/// Exercise chords are answers, hidden on the student copy
+pub fn entry_worksheet(entry: &LibraryEntry, key: Option<&str>) -> MusicResult<WorksheetConfig> {
+    let progression = progression_in_key(entry, key)?;
+    let (signature_key, _) = signature(&progression.key)?;
+
+    let mut start = 0.0;
+    let mut elements = Vec::new();
+    for (index, chord) in progression.chords.iter().enumerate() {
+        elements.push(EditableElement {
+            id: format!("library-{}", index),
+            element_type: EditableElementType::Chord,
+            position: ElementPosition {
+                measure: (start / WORKSHEET_BEATS_PER_MEASURE) as u32 + 1,
+                beat: (start % WORKSHEET_BEATS_PER_MEASURE) as u32 + 1,
+                voice: None,
+            },
+            content: chord.chord.clone(),
+            is_answer: entry.kind == EntryKind::Exercise,
+            is_interactive: true,
+            style: None,
+            hint: None,
+            keyboard: None,
+        });
+        start += chord.beats;
+    }
+
+    let section = WorksheetSection {
+        id: format!("library-{}", entry.id),
+        title: entry.title.clone(),
+        instructions: Some(entry.instructions.clone().unwrap_or_else(|| entry.description.clone())),
+        elements,
+        layout: WorksheetSectionLayout {
+            measures_per_system: 4,
+            systems_per_page: 4,
+            clef: Clef::Treble,
+            time_signature: Some("4/4".to_string()),
+            key_signature: Some(lilypond_note_name(signature_key)),
+            answer_blank: AnswerBlank::default(),
+        },
+    };
+
+    Ok(WorksheetConfig {
+        id: Uuid::new_v4().to_string(),
+        title: entry.title.clone(),
+        subtitle: Some(format!("Key of {}", progression.key)),
+        worksheet_type: WorksheetType::ChordNaming,
+        sections: vec![section],
+        global_settings: WorksheetGlobalSettings {
+            paper_size: PaperSize::Letter,
+            orientation: Orientation::Portrait,
+            show_answers: false,
+            answer_color: None,
+            font_size: 14,
+            locale: None,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::intervals::chord_to_notes;
+    use std::collections::HashSet;
+
+    fn ids(entries: &[&LibraryEntry]) -> Vec<String> {
+        entries.iter().map(|entry| entry.id.clone()).collect()
+    }
+
+    #[test]
+    fn test_bundled_library_is_valid() {
+        let mut seen = HashSet::new();
+        for entry in LIBRARY.iter() {
+            assert!(seen.insert(&entry.id), "duplicate id {}", entry.id);
+            assert!(!entry.chords.is_empty() && !entry.concepts.is_empty(), "{} is incomplete", entry.id);
+            assert!(signature(&entry.key).is_ok(), "{} has an unknown key", entry.id);
+            assert_eq!(entry.kind == EntryKind::Exercise, entry.instructions.is_some(), "{}", entry.id);
+            for chord in &entry.chords {
+                assert!(chord_to_notes(&chord.chord).is_ok(), "{} has an invalid chord {}", entry.id, chord.chord);
+                assert!(chord.beats > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_search_library() {
+        // Title matches rank above concept matches
+        let found = search_library("cadence", &LibraryFilters::default());
+        assert!(found.len() > 3);
+        assert!(found[0].title.to_lowercase().contains("cadence"));
+        assert!(found.iter().any(|entry| entry.id == "secondary-dominants"));
+        // A word only found in the description ranks last
+        assert_eq!(found.last().unwrap().id, "major-two-five-one");
+
+        let filters = LibraryFilters { style: Some("Jazz".to_string()), ..LibraryFilters::default() };
+        let jazz = search_library("ii-v-i", &filters);
+        assert_eq!(ids(&jazz), vec!["major-two-five-one", "minor-two-five-one"]);
+
+        let filters = LibraryFilters {
+            kind: Some(EntryKind::Exercise),
+            difficulty: Some(Difficulty::Beginner),
+            ..LibraryFilters::default()
+        };
+        assert_eq!(ids(&search_library("", &filters)), vec!["name-diatonic-triads"]);
+
+        let filters = LibraryFilters { concepts: vec!["Minor key".to_string()], ..LibraryFilters::default() };
+        assert!(search_library("", &filters).iter().all(|entry| entry.key.ends_with('m')));
+        let blues = search_library("A7 blues", &LibraryFilters::default());
+        assert_eq!(ids(&blues), vec!["twelve-bar-blues"]);
+        assert!(search_library("polka", &LibraryFilters::default()).is_empty());
+    }
+
+    #[test]
+    fn test_progression_in_key() {
+        let entry = find_entry("minor-two-five-one").unwrap();
+        let progression = progression_in_key(entry, Some("A")).unwrap();
+        assert_eq!(progression.key, "Am");
+        let chords: Vec<&str> = progression.chords.iter().map(|chord| chord.chord.as_str()).collect();
+        assert_eq!(chords, vec!["Bm7b5", "E7", "Am6", "Am6"]);
+
+        let entry = find_entry("pop-axis").unwrap();
+        let progression = progression_in_key(entry, Some("Eb")).unwrap();
+        assert_eq!(progression.chords[1].chord, "Bb");
+        assert_eq!(progression_in_key(entry, None).unwrap().chords, entry.chords);
+        assert!(progression_in_key(entry, Some("H")).is_err());
+        assert!(find_entry("missing").is_err());
+    }
+
+    #[test]
+    fn test_entry_worksheet() {
+        let entry = find_entry("jazz-turnaround").unwrap();
+        let worksheet = entry_worksheet(entry, Some("Bb")).unwrap();
+        let section = &worksheet.sections[0];
+        assert_eq!(section.layout.key_signature.as_deref(), Some("bes"));
+        let placed: Vec<(u32, u32, &str)> = section
+            .elements
+            .iter()
+            .map(|element| (element.position.measure, element.position.beat, element.content.as_str()))
+            .collect();
+        assert_eq!(placed, vec![(1, 1, "Bbmaj7"), (1, 3, "Gm7"), (2, 1, "Cm7"), (2, 3, "F7")]);
+        assert!(section.elements.iter().all(|element| !element.is_answer));
+
+        // Exercises hide their chords on the student copy; minor keys use the relative major's signature
+        let exercise = find_entry("name-diatonic-triads").unwrap();
+        let worksheet = entry_worksheet(exercise, None).unwrap();
+        assert!(worksheet.sections[0].elements.iter().all(|element| element.is_answer));
+        let minor = entry_worksheet(find_entry("minor-blues").unwrap(), None).unwrap();
+        assert_eq!(minor.sections[0].layout.key_signature.as_deref(), Some("ees"));
+    }
+}
@@ -0,0 +1,534 @@
+// LilyPond engraving
+// Runs an installed LilyPond on generated notation in a sandbox, with cancellation, timeouts,
+// progress by stage and a render cache; the commands report the progress to their window
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, LazyLock, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tempfile::TempDir;
+use uuid::Uuid;
+
+use crate::svg::canonicalize_svg;
+
+/// Seconds a LilyPond run may take when no timeout is configured
+pub const DEFAULT_RENDER_TIMEOUT_SECS: u64 = 60;
+
+/// How often a running LilyPond process is checked for cancellation and timeout
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Start of the LilyPond log line announcing each stage, in order, with the stage name reported
+const STAGES: &[(&str, &str)] = &[
+    ("Parsing", "parsing"),
+    ("Interpreting music", "interpreting"),
+    ("Preprocessing graphical objects", "layout"),
+    ("Finding the ideal number of pages", "pagination"),
+    ("Drawing systems", "drawing"),
+    ("Layout output to", "output"),
+];
+
+/// Options every LilyPond run gets. `-dsafe` is deprecated and only partly restricts Scheme and \include, so it is a
+/// last line only: the scrubbed environment and the throwaway working directory contain a run, and keeping user
+/// text out of the source in the first place is the job of the input validation in the document builders
+const SANDBOX_ARGS: &[&str] = &["-dsafe"];
+
+/// Environment variables LilyPond keeps; the rest (GUILE_LOAD_PATH and the like) could change what it loads
+const PASSTHROUGH_ENV: &[&str] = &["PATH", "HOME", "LANG", "LC_ALL", "XDG_CACHE_HOME", "SYSTEMROOT"];
+
+static RENDER_TIMEOUT_SECS: AtomicU64 = AtomicU64::new(DEFAULT_RENDER_TIMEOUT_SECS);
+
+/// Cancellation flags of running render jobs, by job id
+static RENDER_JOBS: LazyLock<Mutex<HashMap<String, Arc<AtomicBool>>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// LilyPond runs whose logs are kept for support bundles
+const MAX_RECENT_LOGS: usize = 10;
+
+/// Logs of the latest LilyPond runs, oldest first
+static RECENT_LOGS: LazyLock<Mutex<VecDeque<String>>> = LazyLock::new(|| Mutex::new(VecDeque::new()));
+
+fn remember_log(status: &str, log: &[String]) {
+    if let Ok(mut logs) = RECENT_LOGS.lock() {
+        logs.push_back(format!("{}\n{}\n", status, log.join("\n")));
+        while logs.len() > MAX_RECENT_LOGS {
+            logs.pop_front();
+        }
+    }
+}
+
+/// Logs of the latest LilyPond runs, oldest first, each starting with how the run ended
+pub fn recent_lilypond_logs() -> Vec<String> {
+    RECENT_LOGS.lock().map(|logs| logs.iter().cloned().collect()).unwrap_or_default()
+}
+
+/// Set how long a LilyPond run may take before it is stopped (None = the default)
+pub fn set_render_timeout(seconds: Option<u64>) {
+    let seconds = seconds.filter(|seconds| *seconds > 0).unwrap_or(DEFAULT_RENDER_TIMEOUT_SECS);
+    RENDER_TIMEOUT_SECS.store(seconds, Ordering::Relaxed);
+}
+
+/// Progress of a render job
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RenderEvent {
+    /// LilyPond reached a stage; `progress` is the share of stages reached, 0-1
+    Stage { stage: &'static str, progress: f32 },
+    /// The SVG is ready; `cached` when it came from the render cache without running LilyPond
+    Finished { cached: bool },
+    Cancelled,
+    TimedOut { seconds: u64 },
+}
+
+/// Receives a render job's events on the rendering thread
+pub type RenderListener = Box<dyn FnMut(RenderEvent) + Send>;
+
+/// A LilyPond render that reports progress and can be cancelled by id until it is dropped
+pub struct RenderJob {
+    pub id: String,
+    cancelled: Arc<AtomicBool>,
+    listener: Option<RenderListener>,
+}
+
+impl RenderJob {
+    /// Register a job under the given id (a new one when None)
+    pub fn start(id: Option<String>, listener: Option<RenderListener>) -> Self {
+        let id = id.unwrap_or_else(|| Uuid::new_v4().to_string());
+        let cancelled = Arc::new(AtomicBool::new(false));
+        if let Ok(mut jobs) = RENDER_JOBS.lock() {
+            jobs.insert(id.clone(), cancelled.clone());
+        }
+        Self { id, cancelled, listener }
+    }
+
+    /// Job nobody follows, e.g. a render made on the way to another result
+    pub fn background() -> Self {
+        Self::start(None, None)
+    }
+
+    fn emit(&mut self, event: RenderEvent) {
+        if let Some(listener) = self.listener.as_mut() {
+            listener(event);
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for RenderJob {
+    fn drop(&mut self) {
+        if let Ok(mut jobs) = RENDER_JOBS.lock() {
+            // A newer job may have reused the id
+            if jobs.get(&self.id).is_some_and(|flag| Arc::ptr_eq(flag, &self.cancelled)) {
+                jobs.remove(&self.id);
+            }
+        }
+    }
+}
+
+/// Ask a running job to stop; false when no job has the id
+pub fn cancel_job(job_id: &str) -> bool {
+    let jobs = RENDER_JOBS.lock();
+    let flag = jobs.ok().and_then(|jobs| jobs.get(job_id).cloned());
+    flag.map(|flag| flag.store(true, Ordering::Relaxed)).is_some()
+}
+
+/// The lilypond executable found first on the PATH
+pub fn find_lilypond() -> Option<PathBuf> {
+    let names: &[&str] = if cfg!(windows) { &["lilypond.exe", "lilypond.bat"] } else { &["lilypond"] };
+    env::split_paths(&env::var_os("PATH")?)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
+/// The lilypond on the PATH as the file it resolves to and that file's modification time, so renders cached
+/// from another LilyPond install or version are not reused; empty when none is found
+fn lilypond_identity() -> String {
+    let Some(path) = find_lilypond() else {
+        return String::new();
+    };
+    let path = fs::canonicalize(&path).unwrap_or(path);
+    let modified = fs::metadata(&path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_nanos())
+        .unwrap_or_default();
+    format!("{}@{}", path.display(), modified)
+}
+
+/// A lilypond process with a scrubbed environment, working in `dir` and keeping its temporary files there
+fn lilypond_command(dir: &Path) -> Command {
+    let mut command = Command::new("lilypond");
+    command.env_clear().current_dir(dir);
+    for name in PASSTHROUGH_ENV {
+        if let Some(value) = env::var_os(name) {
+            command.env(name, value);
+        }
+    }
+    for name in ["TMPDIR", "TEMP", "TMP"] {
+        command.env(name, dir);
+    }
+    command
+}
+
+/// First line of `lilypond --version` ("GNU LilyPond 2.24.3 (running Guile 2.2)")
+pub fn lilypond_version() -> Result<String, String> {
+    let output = lilypond_command(&env::temp_dir())
+        .arg("--version")
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to execute lilypond: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("lilypond --version exited with {}", output.status));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.lines().next().unwrap_or_default().trim().to_string())
+}
+
+/// Stage announced by a LilyPond log line, with the share of stages reached
+fn stage_of(line: &str) -> Option<RenderEvent> {
+    let line = line.trim_start();
+    let index = STAGES.iter().position(|(prefix, _)| line.starts_with(prefix))?;
+    Some(RenderEvent::Stage { stage: STAGES[index].1, progress: (index + 1) as f32 / STAGES.len() as f32 })
+}
+
+/// Directory name of the render cache inside the app data directory
+pub const RENDER_CACHE_DIR: &str = "render-cache";
+
+/// Renders kept in memory, and on disk, before the least recently stored are dropped
+const MEMORY_CACHE_ENTRIES: usize = 64;
+const DISK_CACHE_ENTRIES: usize = 500;
+
+/// LilyPond SVG output keyed by a hash of the LilyPond binary, the input and the arguments, in memory and on disk
+/// Output is canonicalized, so the same input caches the same bytes whichever LilyPond run produced it,
+/// and cached before post-processing, so a theme change still hits the cache
+/// The cache only decides what to keep; files are read, written and removed with its lock released
+struct RenderCache {
+    memory: HashMap<String, String>,
+    /// Memory keys, oldest first
+    order: VecDeque<String>,
+    /// None until the app data directory is known
+    dir: Option<PathBuf>,
+    /// Disk keys, oldest first
+    disk: VecDeque<String>,
+}
+
+static RENDER_CACHE: LazyLock<Mutex<RenderCache>> = LazyLock::new(|| Mutex::new(RenderCache::new(None)));
+
+/// Keep the on-disk cache in the standard directory inside the app data directory
+pub fn set_render_cache_dir(data_dir: &Path) {
+    let dir = data_dir.join(RENDER_CACHE_DIR);
+    let disk = saved_renders(&dir);
+    if let Ok(mut cache) = RENDER_CACHE.lock() {
+        cache.dir = Some(dir);
+        cache.disk = disk;
+    }
+}
+
+/// Keys of the renders saved in a cache directory, oldest first
+fn saved_renders(dir: &Path) -> VecDeque<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return VecDeque::new();
+    };
+    let mut files: Vec<(SystemTime, String)> = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "svg"))
+        .filter_map(|entry| {
+            let key = entry.path().file_stem()?.to_str()?.to_string();
+            Some((entry.metadata().ok()?.modified().ok()?, key))
+        })
+        .collect();
+    files.sort();
+    files.into_iter().map(|(_, key)| key).collect()
+}
+
+fn cache_key(lilypond: &str, source: &str, args: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(lilypond.as_bytes());
+    hasher.update([0]);
+    for arg in args {
+        hasher.update(arg.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.update(source.as_bytes());
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl RenderCache {
+    fn new(dir: Option<PathBuf>) -> Self {
+        let disk = dir.as_deref().map(saved_renders).unwrap_or_default();
+        Self { memory: HashMap::new(), order: VecDeque::new(), dir, disk }
+    }
+
+    fn disk_path(&self, key: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{}.svg", key)))
+    }
+
+    fn remember(&mut self, key: &str, svg: &str) {
+        if self.memory.insert(key.to_string(), svg.to_string()).is_none() {
+            self.order.push_back(key.to_string());
+        }
+        while self.order.len() > MEMORY_CACHE_ENTRIES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.memory.remove(&oldest);
+            }
+        }
+    }
+
+    /// Output held in memory
+    fn get(&self, key: &str) -> Option<String> {
+        self.memory.get(key).cloned()
+    }
+
+    /// Hold output in memory and count it on disk, returning the file to save it to and the older files that
+    /// no longer fit
+    fn insert(&mut self, key: &str, svg: &str) -> Option<(PathBuf, Vec<PathBuf>)> {
+        self.remember(key, svg);
+        let path = self.disk_path(key)?;
+        self.disk.retain(|saved| saved != key);
+        self.disk.push_back(key.to_string());
+        let excess = self.disk.len().saturating_sub(DISK_CACHE_ENTRIES);
+        let evicted: Vec<String> = self.disk.drain(..excess).collect();
+        Some((path, evicted.iter().filter_map(|key| self.disk_path(key)).collect()))
+    }
+
+    fn clear(&mut self) -> Result<(), String> {
+        self.memory.clear();
+        self.order.clear();
+        self.disk.clear();
+        match &self.dir {
+            Some(dir) if dir.exists() => {
+                fs::remove_dir_all(dir).map_err(|e| format!("Failed to clear render cache: {}", e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Cached output, from memory or else from disk
+fn cached_render(cache: &Mutex<RenderCache>, key: &str) -> Option<String> {
+    let path = {
+        let cache = cache.lock().ok()?;
+        if let Some(svg) = cache.get(key) {
+            return Some(svg);
+        }
+        cache.disk_path(key)?
+    };
+    let svg = fs::read_to_string(path).ok()?;
+    cache.lock().ok()?.remember(key, &svg);
+    Some(svg)
+}
+
+/// Cache output, then save it and drop the renders it pushed out of the disk cache; a failed write only loses
+/// the disk copy
+fn cache_render(cache: &Mutex<RenderCache>, key: &str, svg: &str) {
+    let Some((path, evicted)) = cache.lock().ok().and_then(|mut cache| cache.insert(key, svg)) else {
+        return;
+    };
+    let written = path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::write(&path, svg));
+    if let Err(e) = written {
+        println!("[render cache] Failed to save render: {}", e);
+    }
+    for path in evicted {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Run LilyPond on a document and return its SVG output, from the cache when the same input was rendered before
+/// `args` are extra command-line options and, with the LilyPond binary, are part of the cache key
+/// The process is killed when the job is cancelled or runs past the configured timeout
+/// LilyPond runs with a scrubbed environment, confined to a fresh directory holding only the input
+pub fn run_lilypond(source: &str, args: &[&str], job: &mut RenderJob) -> Result<String, String> {
+    let key = cache_key(&lilypond_identity(), source, args);
+    if let Some(svg) = cached_render(&RENDER_CACHE, &key) {
+        job.emit(RenderEvent::Finished { cached: true });
+        return Ok(svg);
+    }
+
+    // Create temporary directory
+    let temp_dir = TempDir::new().map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let temp_path = temp_dir.path();
+
+    // Generate unique filename
+    let file_id = Uuid::new_v4().to_string();
+    let input_file = format!("{}.ly", file_id);
+    let output_dir = temp_path.join("output");
+
+    // Create output directory
+    fs::create_dir_all(&output_dir).map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    // Write LilyPond notation to file
+    fs::write(temp_path.join(&input_file), source).map_err(|e| format!("Failed to write input file: {}", e))?;
+
+    // Execute LilyPond command with paths relative to the temporary directory, so relative includes resolve
+    // there; its log on stderr announces each stage
+    let mut child = lilypond_command(temp_path)
+        .arg("--svg")
+        .args(SANDBOX_ARGS)
+        .args(args)
+        .arg("-o")
+        .arg("output")
+        .arg(&input_file)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to execute lilypond: {}. Make sure LilyPond is installed and in PATH.", e))?;
+
+    let (sender, lines) = mpsc::channel();
+    if let Some(stderr) = child.stderr.take() {
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    let timeout = RENDER_TIMEOUT_SECS.load(Ordering::Relaxed);
+    let started = Instant::now();
+    let mut log = Vec::new();
+    let status = loop {
+        for line in lines.try_iter() {
+            if let Some(stage) = stage_of(&line) {
+                job.emit(stage);
+            }
+            log.push(line);
+        }
+        if let Some(status) = child.try_wait().map_err(|e| format!("Failed to wait for lilypond: {}", e))? {
+            break status;
+        }
+        if job.is_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            job.emit(RenderEvent::Cancelled);
+            return Err("Render cancelled".to_string());
+        }
+        if started.elapsed() >= Duration::from_secs(timeout) {
+            let _ = child.kill();
+            let _ = child.wait();
+            job.emit(RenderEvent::TimedOut { seconds: timeout });
+            remember_log(&format!("Timed out after {} seconds", timeout), &log);
+            return Err(format!("LilyPond timed out after {} seconds", timeout));
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    if !status.success() {
+        // The reader finishes once the process closes stderr
+        log.extend(lines.iter());
+        remember_log(&format!("Failed: {}", status), &log);
+        return Err(format!("LilyPond execution failed: {}", log.join("\n")));
+    }
+
+    log.extend(lines.try_iter());
+    remember_log("Succeeded", &log);
+
+    // Read the generated SVG file
+    let svg_file = output_dir.join(format!("{}.svg", file_id));
+    let svg_content = fs::read_to_string(&svg_file)
+        .map_err(|e| format!("Failed to read SVG output: {}", e))?;
+    let svg_content = canonicalize_svg(&svg_content)?;
+
+    cache_render(&RENDER_CACHE, &key, &svg_content);
+    job.emit(RenderEvent::Finished { cached: false });
+    Ok(svg_content)
+}
+
+/// Drop every cached render, in memory and on disk
+pub fn clear_render_cache() -> Result<(), String> {
+    let mut cache = RENDER_CACHE.lock().map_err(|e| format!("Lock error: {}", e))?;
+    cache.clear()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_jobs() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let listener: RenderListener = Box::new(move |event| sink.lock().unwrap().push(event));
+        let mut job = RenderJob::start(Some("job-1".to_string()), Some(listener));
+
+        assert!(!job.is_cancelled());
+        assert!(cancel_job("job-1"));
+        assert!(job.is_cancelled());
+        assert!(!cancel_job("job-2"));
+
+        job.emit(RenderEvent::Cancelled);
+        assert_eq!(*events.lock().unwrap(), [RenderEvent::Cancelled]);
+        drop(job);
+        assert!(!cancel_job("job-1"));
+    }
+
+    #[test]
+    fn test_stages_from_log() {
+        assert_eq!(stage_of("Parsing..."), Some(RenderEvent::Stage { stage: "parsing", progress: 1.0 / 6.0 }));
+        assert_eq!(stage_of("Drawing systems..."), Some(RenderEvent::Stage { stage: "drawing", progress: 5.0 / 6.0 }));
+        assert_eq!(stage_of("warning: no \\version statement found"), None);
+    }
+
+    #[test]
+    fn test_lilypond_command_environment() {
+        let dir = TempDir::new().unwrap();
+        let command = lilypond_command(dir.path());
+        assert_eq!(command.get_current_dir(), Some(dir.path()));
+        for (name, value) in command.get_envs() {
+            let name = name.to_str().unwrap();
+            assert!(PASSTHROUGH_ENV.contains(&name) || value == Some(dir.path().as_os_str()), "{} passed", name);
+        }
+    }
+
+    #[test]
+    fn test_render_cache() {
+        let dir = TempDir::new().unwrap();
+        let key = cache_key("/usr/bin/lilypond@1", "{ c'4 }", &["-dno-point-and-click"]);
+        assert_ne!(key, cache_key("/usr/bin/lilypond@1", "{ c'4 }", &[]));
+        assert_ne!(key, cache_key("/usr/bin/lilypond@2", "{ c'4 }", &["-dno-point-and-click"]));
+
+        let cache = Mutex::new(RenderCache::new(Some(dir.path().join(RENDER_CACHE_DIR))));
+        assert_eq!(cached_render(&cache, &key), None);
+        cache_render(&cache, &key, "<svg/>");
+
+        // A fresh cache over the same directory finds the render on disk
+        let reopened = Mutex::new(RenderCache::new(Some(dir.path().join(RENDER_CACHE_DIR))));
+        assert_eq!(reopened.lock().unwrap().disk.iter().collect::<Vec<_>>(), [&key]);
+        assert_eq!(cached_render(&reopened, &key).as_deref(), Some("<svg/>"));
+        for index in 0..MEMORY_CACHE_ENTRIES {
+            reopened.lock().unwrap().remember(&index.to_string(), "");
+        }
+        assert!(!reopened.lock().unwrap().memory.contains_key(&key));
+        assert_eq!(cached_render(&reopened, &key).as_deref(), Some("<svg/>"));
+
+        reopened.lock().unwrap().clear().unwrap();
+        assert_eq!(cached_render(&reopened, &key), None);
+        assert_eq!(cached_render(&Mutex::new(RenderCache::new(Some(dir.path().join(RENDER_CACHE_DIR)))), &key), None);
+    }
+
+    #[test]
+    fn test_render_cache_evicts_oldest_from_disk() {
+        let dir = TempDir::new().unwrap();
+        let mut cache = RenderCache::new(Some(dir.path().to_path_buf()));
+        for index in 0..DISK_CACHE_ENTRIES {
+            assert_eq!(cache.insert(&index.to_string(), ""), Some((dir.path().join(format!("{}.svg", index)), vec![])));
+        }
+        // Storing a render again makes it the newest
+        cache.insert("0", "");
+        let (_, evicted) = cache.insert("new", "").unwrap();
+        assert_eq!(evicted, [dir.path().join("1.svg")]);
+        assert_eq!(cache.disk.len(), DISK_CACHE_ENTRIES);
+
+        assert_eq!(RenderCache::new(None).insert("new", ""), None);
+    }
+}
@@ -0,0 +1,64 @@
+// MIDI input devices
+// Keyboards are read through midir, which uses ALSA on Linux, CoreMIDI on macOS and WinMM on Windows;
+// messages arrive on the backend's own callback thread
+
+use midir::{MidiInput, MidiInputConnection as Connection};
+use serde::Serialize;
+
+use super::input::MidiMessage;
+
+/// Name the app registers under with the MIDI backend
+const CLIENT_NAME: &str = "Maestro Blocks";
+
+/// A MIDI source that can be opened for input
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MidiInputInfo {
+    /// Identifier passed back to open_input
+    pub id: String,
+    pub name: String,
+}
+
+/// An open MIDI input; input stops when it is dropped
+pub struct MidiInputConnection {
+    _connection: Connection<()>,
+}
+
+fn open_client() -> Result<MidiInput, String> {
+    MidiInput::new(CLIENT_NAME).map_err(|e| format!("Failed to open MIDI input: {}", e))
+}
+
+/// MIDI sources available for input
+pub fn list_inputs() -> Result<Vec<MidiInputInfo>, String> {
+    let client = open_client()?;
+    Ok(client
+        .ports()
+        .iter()
+        .filter_map(|port| {
+            // A port that disappears while listing is skipped
+            let name = client.port_name(port).ok()?;
+            Some(MidiInputInfo { id: port.id(), name })
+        })
+        .collect())
+}
+
+/// Start reading a MIDI source, calling on_message from the backend's thread for every message
+pub fn open_input(
+    id: &str,
+    mut on_message: impl FnMut(MidiMessage) + Send + 'static,
+) -> Result<MidiInputConnection, String> {
+    let client = open_client()?;
+    let port = client.find_port_by_id(id.to_string()).ok_or_else(|| format!("Invalid MIDI input: {}", id))?;
+    let connection = client
+        .connect(
+            &port,
+            CLIENT_NAME,
+            move |_, bytes, _| {
+                if let Some(message) = MidiMessage::from_bytes(bytes) {
+                    on_message(message);
+                }
+            },
+            (),
+        )
+        .map_err(|e| format!("Failed to connect to MIDI input {}: {}", id, e))?;
+    Ok(MidiInputConnection { _connection: connection })
+}
@@ -0,0 +1,154 @@
+// Held-note tracking for live MIDI input
+// Folds note and sustain pedal messages into the set of sounding notes
+
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+use crate::music::identify::name_midi_chord;
+use crate::music::notes::get_preferred_note_name;
+
+/// Pedal values at or above this count as pressed
+const SUSTAIN_THRESHOLD: i32 = 64;
+
+/// Sustain pedal controller number
+const SUSTAIN_CONTROLLER: u8 = 64;
+
+/// Channel message relevant to chord entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiMessage {
+    NoteOn { note: u8, velocity: u8 },
+    NoteOff { note: u8 },
+    /// Sustain pedal (controller 64) pressed or released
+    Sustain(bool),
+}
+
+impl MidiMessage {
+    /// Sustain pedal message from a controller value
+    pub fn sustain(value: i32) -> Self {
+        MidiMessage::Sustain(value >= SUSTAIN_THRESHOLD)
+    }
+
+    /// Read a raw MIDI message on any channel; None for everything but notes and the sustain pedal
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (status, data) = bytes.split_first()?;
+        // The high nibble is the message type, the low one the channel
+        match (status & 0xF0, data) {
+            (0x90, &[note, velocity, ..]) => Some(MidiMessage::NoteOn { note, velocity }),
+            (0x80, &[note, _, ..]) => Some(MidiMessage::NoteOff { note }),
+            (0xB0, &[SUSTAIN_CONTROLLER, value, ..]) => Some(MidiMessage::sustain(value as i32)),
+            _ => None,
+        }
+    }
+}
+
+/// Notes currently sounding on the keyboard
+#[derive(Debug, Default)]
+pub struct HeldNotes {
+    pressed: BTreeSet<u8>,
+    /// Released while the pedal was down, still ringing
+    sustained: BTreeSet<u8>,
+    pedal: bool,
+}
+
+impl HeldNotes {
+    /// Apply a message; returns true when the set of sounding notes changed
+    pub fn apply(&mut self, message: MidiMessage) -> bool {
+        let before = self.sounding();
+        match message {
+            // Note on with velocity 0 is a note off by convention
+            MidiMessage::NoteOn { note, velocity: 0 } | MidiMessage::NoteOff { note } => {
+                if self.pressed.remove(&note) && self.pedal {
+                    self.sustained.insert(note);
+                }
+            }
+            MidiMessage::NoteOn { note, .. } => {
+                self.pressed.insert(note);
+                self.sustained.remove(&note);
+            }
+            MidiMessage::Sustain(down) => {
+                self.pedal = down;
+                if !down {
+                    self.sustained.clear();
+                }
+            }
+        }
+        self.sounding() != before
+    }
+
+    /// Sounding MIDI note numbers, lowest first
+    pub fn sounding(&self) -> Vec<u8> {
+        self.pressed.union(&self.sustained).copied().collect()
+    }
+}
+
+/// One sounding note
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MidiNote {
+    pub midi: u8,
+    pub note: String,
+    pub octave: i8,
+}
+
+/// Payload sent to the frontend whenever the sounding notes change
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MidiInputEvent {
+    pub notes: Vec<MidiNote>,
+    /// Chord symbol for the sounding notes, slash chord when inverted
+    pub chord: Option<String>,
+}
+
+/// Spell sounding notes in a key and name the chord they form
+pub fn describe_notes(midis: &[u8], key: &str) -> MidiInputEvent {
+    let notes = midis
+        .iter()
+        .map(|midi| MidiNote {
+            midi: *midi,
+            note: get_preferred_note_name(midi % 12, key, false).to_string(),
+            octave: (*midi / 12) as i8 - 1,
+        })
+        .collect();
+    MidiInputEvent { notes, chord: name_midi_chord(midis, key) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_held_notes_with_sustain() {
+        let mut held = HeldNotes::default();
+        assert!(held.apply(MidiMessage::NoteOn { note: 60, velocity: 90 }));
+        assert!(held.apply(MidiMessage::NoteOn { note: 64, velocity: 90 }));
+        assert!(!held.apply(MidiMessage::NoteOn { note: 67, velocity: 0 }));
+
+        held.apply(MidiMessage::sustain(127));
+        assert!(!held.apply(MidiMessage::NoteOff { note: 60 }));
+        assert_eq!(held.sounding(), vec![60, 64]);
+
+        assert!(held.apply(MidiMessage::sustain(0)));
+        assert_eq!(held.sounding(), vec![64]);
+    }
+
+    #[test]
+    fn test_messages_from_bytes() {
+        assert_eq!(MidiMessage::from_bytes(&[0x90, 60, 90]), Some(MidiMessage::NoteOn { note: 60, velocity: 90 }));
+        assert_eq!(MidiMessage::from_bytes(&[0x83, 60, 40]), Some(MidiMessage::NoteOff { note: 60 }));
+        assert_eq!(MidiMessage::from_bytes(&[0xB0, 64, 127]), Some(MidiMessage::Sustain(true)));
+        assert_eq!(MidiMessage::from_bytes(&[0xB5, 64, 10]), Some(MidiMessage::Sustain(false)));
+        // Other controllers, clock and truncated messages are ignored
+        assert_eq!(MidiMessage::from_bytes(&[0xB0, 7, 100]), None);
+        assert_eq!(MidiMessage::from_bytes(&[0xF8]), None);
+        assert_eq!(MidiMessage::from_bytes(&[0x90, 60]), None);
+    }
+
+    #[test]
+    fn test_describe_notes() {
+        let event = describe_notes(&[52, 55, 60], "C");
+        assert_eq!(event.chord.as_deref(), Some("C/E"));
+        assert_eq!(event.notes[0], MidiNote { midi: 52, note: "E".to_string(), octave: 3 });
+
+        let event = describe_notes(&[58, 62, 65], "F");
+        assert_eq!(event.chord.as_deref(), Some("Bb"));
+        assert_eq!(describe_notes(&[60], "C").chord, None);
+    }
+}
@@ -0,0 +1,10 @@
+// MIDI keyboard input
+// Tracks the notes held on a connected keyboard and names the chord they form
+
+#[cfg(feature = "audio")]
+pub mod device;
+pub mod input;
+
+#[cfg(feature = "audio")]
+pub use device::{list_inputs, open_input, MidiInputConnection, MidiInputInfo};
+pub use input::{describe_notes, HeldNotes};
@@ -0,0 +1,141 @@
+// User-extensible chord suffix aliases
+// Institutions can add accepted symbols on top of the built-in normalization tables
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{LazyLock, RwLock};
+
+use super::types::{MusicError, MusicResult};
+
+/// Current alias dictionary file format version
+pub const ALIAS_DICTIONARY_VERSION: u32 = 1;
+
+/// Alias dictionary as imported/exported in JSON
+/// Alias keys are matched case-insensitively; values are canonical suffixes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AliasDictionary {
+    pub version: u32,
+    pub aliases: BTreeMap<String, String>,
+    /// Extra suffixes accepted as canonical in addition to the built-ins
+    pub valid_suffixes: Vec<String>,
+}
+
+/// User aliases merged into suffix normalization at runtime
+static USER_ALIASES: LazyLock<RwLock<AliasDictionary>> = LazyLock::new(|| RwLock::new(AliasDictionary::default()));
+
+impl AliasDictionary {
+    /// Parse and validate a JSON alias dictionary
+    /// Every alias must point at a built-in suffix or one listed in valid_suffixes
+    pub fn from_json(json: &str, is_builtin_suffix: impl Fn(&str) -> bool) -> MusicResult<Self> {
+        let mut dictionary: AliasDictionary = serde_json::from_str(json)
+            .map_err(|e| MusicError::ParseError(format!("Invalid alias dictionary: {}", e)))?;
+
+        if dictionary.version > ALIAS_DICTIONARY_VERSION {
+            return Err(MusicError::ParseError(format!(
+                "Alias dictionary version {} is newer than supported version {}",
+                dictionary.version, ALIAS_DICTIONARY_VERSION
+            )));
+        }
+        dictionary.version = ALIAS_DICTIONARY_VERSION;
+
+        dictionary.aliases = dictionary
+            .aliases
+            .into_iter()
+            .map(|(alias, target)| (alias.trim().to_lowercase(), target.trim().to_string()))
+            .collect();
+
+        for (alias, target) in &dictionary.aliases {
+            if alias.is_empty() {
+                return Err(MusicError::ParseError("Alias names cannot be empty".to_string()));
+            }
+            if !is_builtin_suffix(target) && !dictionary.valid_suffixes.contains(target) {
+                return Err(MusicError::UnknownQuality(format!("{} (alias '{}')", target, alias)));
+            }
+        }
+
+        Ok(dictionary)
+    }
+
+    pub fn to_json(&self) -> MusicResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| MusicError::ParseError(format!("Failed to serialize alias dictionary: {}", e)))
+    }
+
+    /// Merge another dictionary into this one (entries in `other` win)
+    pub fn merge(&mut self, other: AliasDictionary) {
+        self.aliases.extend(other.aliases);
+        for suffix in other.valid_suffixes {
+            if !self.valid_suffixes.contains(&suffix) {
+                self.valid_suffixes.push(suffix);
+            }
+        }
+        self.version = ALIAS_DICTIONARY_VERSION;
+    }
+}
+
+/// Replace the runtime user aliases
+pub fn set_user_aliases(dictionary: AliasDictionary) {
+    if let Ok(mut aliases) = USER_ALIASES.write() {
+        *aliases = dictionary;
+    }
+}
+
+/// Look up a user alias (key must already be lowercase)
+#[allow(dead_code)]
+pub fn resolve_user_alias(lower: &str) -> Option<String> {
+    USER_ALIASES.read().ok()?.aliases.get(lower).cloned()
+}
+
+/// Check whether a suffix was added as valid by the user
+#[allow(dead_code)]
+pub fn is_user_suffix(suffix: &str) -> bool {
+    USER_ALIASES
+        .read()
+        .map(|a| a.valid_suffixes.iter().any(|s| s == suffix))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builtin(suffix: &str) -> bool {
+        ["", "m", "maj7", "dim"].contains(&suffix)
+    }
+
+    #[test]
+    fn test_from_json_normalizes_keys() {
+        let dict = AliasDictionary::from_json(r#"{"aliases": {" MA7 ": "maj7", "Δ": "maj7"}}"#, builtin).unwrap();
+        assert_eq!(dict.aliases.get("ma7").map(String::as_str), Some("maj7"));
+        assert_eq!(dict.version, ALIAS_DICTIONARY_VERSION);
+    }
+
+    #[test]
+    fn test_from_json_rejects_unknown_targets() {
+        assert!(AliasDictionary::from_json(r#"{"aliases": {"x": "nonsense"}}"#, builtin).is_err());
+
+        // Allowed once the target is declared valid
+        let json = r#"{"aliases": {"x": "nonsense"}, "valid_suffixes": ["nonsense"]}"#;
+        assert!(AliasDictionary::from_json(json, builtin).is_ok());
+    }
+
+    #[test]
+    fn test_from_json_rejects_newer_version() {
+        assert!(AliasDictionary::from_json(r#"{"version": 99}"#, builtin).is_err());
+    }
+
+    #[test]
+    fn test_merge_prefers_incoming() {
+        let mut base = AliasDictionary::default();
+        base.aliases.insert("mi".to_string(), "m".to_string());
+
+        let mut incoming = AliasDictionary::default();
+        incoming.aliases.insert("mi".to_string(), "dim".to_string());
+        incoming.valid_suffixes.push("69".to_string());
+
+        base.merge(incoming);
+        assert_eq!(base.aliases["mi"], "dim");
+        assert_eq!(base.valid_suffixes, vec!["69"]);
+    }
+}
@@ -0,0 +1,313 @@
+// Harmonic analysis of chord progressions
+// Numerals, harmonic functions, and cadences, plus an incremental analyzer for live editing
+
+use serde::{Deserialize, Serialize};
+
+use super::roman::{get_chord_numeral, get_display_numeral, parse_roman_numeral};
+use super::types::{MusicError, MusicResult, NumeralDepth, NumeralMode};
+
+/// Harmonic function of a chord within the key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HarmonicFunction {
+    Tonic,
+    Predominant,
+    Dominant,
+    /// Borrowed or altered chords (numerals with an accidental)
+    Chromatic,
+}
+
+/// Cadence type, attached to the chord where the cadence arrives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Cadence {
+    Authentic,
+    Plagal,
+    Deceptive,
+    Half,
+}
+
+/// Analysis of one chord in context
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChordAnalysis {
+    pub chord: String,
+    /// None when the chord can't be analyzed in this key (e.g. incomplete input)
+    pub numeral: Option<String>,
+    /// Applied-chord reading of a secondary dominant or leading-tone chord ("V7/V")
+    #[serde(default)]
+    pub applied: Option<String>,
+    pub function: Option<HarmonicFunction>,
+    pub cadence: Option<Cadence>,
+}
+
+/// Scale degree (1-7) and whether the numeral carries an accidental
+fn numeral_degree(numeral: &str) -> Option<(u8, bool)> {
+    parse_roman_numeral(numeral)
+        .ok()
+        .map(|parts| (parts.degree, parts.accidental.is_some()))
+}
+
+fn function_for_numeral(numeral: &str) -> Option<HarmonicFunction> {
+    let (degree, altered) = numeral_degree(numeral)?;
+    if altered {
+        return Some(HarmonicFunction::Chromatic);
+    }
+
+    Some(match degree {
+        1 | 3 | 6 => HarmonicFunction::Tonic,
+        2 | 4 => HarmonicFunction::Predominant,
+        _ => HarmonicFunction::Dominant,
+    })
+}
+
+/// Detect a cadence arriving at `current`, given the preceding numeral
+fn detect_cadence(previous: Option<&str>, current: &str, is_last: bool) -> Option<Cadence> {
+    let (degree, altered) = numeral_degree(current)?;
+    if altered {
+        return None;
+    }
+
+    let previous = previous.and_then(numeral_degree).filter(|(_, altered)| !altered);
+    match (previous.map(|(d, _)| d), degree) {
+        (Some(5) | Some(7), 1) => Some(Cadence::Authentic),
+        (Some(4), 1) => Some(Cadence::Plagal),
+        (Some(5), 6) => Some(Cadence::Deceptive),
+        (Some(prev), 5) if is_last && prev != 5 => Some(Cadence::Half),
+        _ => None,
+    }
+}
+
+/// Analyze every chord of a progression in the given key
+/// Minor keys ("Am") are analyzed against the natural minor, so E7 is V7 and G is VII
+pub fn analyze_progression(chords: &[String], key: &str) -> Vec<ChordAnalysis> {
+    let (tonic, mode) = NumeralMode::from_key(key);
+    let numerals: Vec<Option<String>> = chords
+        .iter()
+        .map(|chord| get_display_numeral(chord, tonic, mode).ok())
+        .collect();
+
+    chords
+        .iter()
+        .enumerate()
+        .map(|(i, chord)| {
+            let numeral = numerals[i].clone();
+            let previous = i.checked_sub(1).and_then(|p| numerals[p].as_deref());
+            let is_last = i + 1 == chords.len();
+
+            let applied = get_chord_numeral(chord, tonic, mode, NumeralDepth::Applied)
+                .ok()
+                .filter(|applied| applied.contains('/') && !chord.contains('/'));
+
+            ChordAnalysis {
+                chord: chord.clone(),
+                applied,
+                function: numeral.as_deref().and_then(function_for_numeral),
+                cadence: numeral.as_deref().and_then(|n| detect_cadence(previous, n, is_last)),
+                numeral,
+            }
+        })
+        .collect()
+}
+
+/// An edit applied to the progression being analyzed
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProgressionEdit {
+    Insert { index: usize, chord: String },
+    Remove { index: usize },
+    Replace { index: usize, chord: String },
+    Move { from: usize, to: usize },
+    SetKey { key: String },
+    Load { chords: Vec<String>, key: String },
+}
+
+/// Analysis result at a position in the progression
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexedAnalysis {
+    pub index: usize,
+    pub analysis: ChordAnalysis,
+}
+
+/// Only the analysis entries that changed after an edit
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisDelta {
+    pub key: String,
+    /// New progression length; entries at or beyond it were removed
+    pub length: usize,
+    pub changed: Vec<IndexedAnalysis>,
+}
+
+impl AnalysisDelta {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty()
+    }
+}
+
+/// Holds the progression being edited and its latest analysis
+#[derive(Debug, Clone)]
+pub struct ProgressionAnalyzer {
+    key: String,
+    chords: Vec<String>,
+    analysis: Vec<ChordAnalysis>,
+}
+
+impl Default for ProgressionAnalyzer {
+    fn default() -> Self {
+        Self::new("C")
+    }
+}
+
+impl ProgressionAnalyzer {
+    pub fn new(key: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            chords: Vec::new(),
+            analysis: Vec::new(),
+        }
+    }
+
+    /// Current full analysis
+    pub fn analysis(&self) -> &[ChordAnalysis] {
+        &self.analysis
+    }
+
+    fn check_index(&self, index: usize, allow_end: bool) -> MusicResult<()> {
+        let limit = if allow_end { self.chords.len() } else { self.chords.len().saturating_sub(1) };
+        if index > limit || (!allow_end && self.chords.is_empty()) {
+            return Err(MusicError::ParseError(format!(
+                "Index {} out of range for progression of length {}",
+                index,
+                self.chords.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Apply an edit and return the analysis entries that changed
+    pub fn apply(&mut self, edit: ProgressionEdit) -> MusicResult<AnalysisDelta> {
+        match edit {
+            ProgressionEdit::Insert { index, chord } => {
+                self.check_index(index, true)?;
+                self.chords.insert(index, chord);
+            }
+            ProgressionEdit::Remove { index } => {
+                self.check_index(index, false)?;
+                self.chords.remove(index);
+            }
+            ProgressionEdit::Replace { index, chord } => {
+                self.check_index(index, false)?;
+                self.chords[index] = chord;
+            }
+            ProgressionEdit::Move { from, to } => {
+                self.check_index(from, false)?;
+                self.check_index(to, false)?;
+                let chord = self.chords.remove(from);
+                self.chords.insert(to, chord);
+            }
+            ProgressionEdit::SetKey { key } => {
+                self.key = key;
+            }
+            ProgressionEdit::Load { chords, key } => {
+                self.chords = chords;
+                self.key = key;
+            }
+        }
+
+        let updated = analyze_progression(&self.chords, &self.key);
+        let changed = updated
+            .iter()
+            .enumerate()
+            .filter(|(i, entry)| self.analysis.get(*i) != Some(entry))
+            .map(|(index, entry)| IndexedAnalysis { index, analysis: entry.clone() })
+            .collect();
+        self.analysis = updated;
+
+        Ok(AnalysisDelta {
+            key: self.key.clone(),
+            length: self.chords.len(),
+            changed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chords(names: &[&str]) -> Vec<String> {
+        names.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_functions_and_authentic_cadence() {
+        let result = analyze_progression(&chords(&["C", "F", "G", "C"]), "C");
+
+        assert_eq!(result[0].function, Some(HarmonicFunction::Tonic));
+        assert_eq!(result[1].function, Some(HarmonicFunction::Predominant));
+        assert_eq!(result[2].function, Some(HarmonicFunction::Dominant));
+        assert_eq!(result[3].cadence, Some(Cadence::Authentic));
+    }
+
+    #[test]
+    fn test_deceptive_and_half_cadences() {
+        let deceptive = analyze_progression(&chords(&["G", "Am"]), "C");
+        assert_eq!(deceptive[1].cadence, Some(Cadence::Deceptive));
+
+        let half = analyze_progression(&chords(&["C", "F", "G"]), "C");
+        assert_eq!(half[2].cadence, Some(Cadence::Half));
+    }
+
+    #[test]
+    fn test_minor_key_numerals() {
+        let result = analyze_progression(&chords(&["Am", "G", "E7", "Am"]), "Am");
+
+        let numerals: Vec<_> = result.iter().map(|a| a.numeral.as_deref()).collect();
+        assert_eq!(numerals, vec![Some("i"), Some("VII"), Some("V7"), Some("i")]);
+        assert_eq!(result[3].cadence, Some(Cadence::Authentic));
+    }
+
+    #[test]
+    fn test_applied_chords_reported_alongside_numerals() {
+        let result = analyze_progression(&chords(&["C", "D7", "G", "C"]), "C");
+
+        assert_eq!(result[1].numeral.as_deref(), Some("II7"));
+        assert_eq!(result[1].applied.as_deref(), Some("V7/V"));
+        assert_eq!(result[2].applied, None);
+    }
+
+    #[test]
+    fn test_replace_only_reports_affected_chords() {
+        let mut analyzer = ProgressionAnalyzer::new("C");
+        analyzer
+            .apply(ProgressionEdit::Load { chords: chords(&["C", "Am", "F", "C"]), key: "C".to_string() })
+            .unwrap();
+
+        // F → G changes the third chord and turns the last one into an authentic cadence
+        let delta = analyzer
+            .apply(ProgressionEdit::Replace { index: 2, chord: "G".to_string() })
+            .unwrap();
+        let indices: Vec<usize> = delta.changed.iter().map(|c| c.index).collect();
+        assert_eq!(indices, vec![2, 3]);
+        assert_eq!(delta.length, 4);
+    }
+
+    #[test]
+    fn test_remove_shrinks_length() {
+        let mut analyzer = ProgressionAnalyzer::default();
+        analyzer
+            .apply(ProgressionEdit::Load { chords: chords(&["C", "G", "C"]), key: "C".to_string() })
+            .unwrap();
+
+        let delta = analyzer.apply(ProgressionEdit::Remove { index: 2 }).unwrap();
+        assert_eq!(delta.length, 2);
+        assert_eq!(analyzer.analysis().len(), 2);
+        assert!(analyzer.apply(ProgressionEdit::Remove { index: 5 }).is_err());
+    }
+
+    #[test]
+    fn test_unknown_chord_has_no_numeral() {
+        let result = analyze_progression(&chords(&["C", "xyz"]), "C");
+        assert!(result[1].numeral.is_none());
+        assert!(result[1].function.is_none());
+    }
+}
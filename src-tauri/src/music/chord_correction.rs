@@ -0,0 +1,209 @@
+// Fuzzy chord symbol correction
+// Repairs noisy chord tokens (OCR output, quick typing) into valid chord names
+
+use serde::{Deserialize, Serialize};
+
+use super::chords::{parse_chord, ChordSymbol, SymbolStyle};
+use super::intervals::CHORD_INTERVAL_SPECS;
+
+/// Confidence for tokens that were already valid chords
+const EXACT_CONFIDENCE: f32 = 1.0;
+/// Confidence after character-level substitutions only
+const SUBSTITUTION_CONFIDENCE: f32 = 0.85;
+/// Confidence lost per edit when snapping a suffix to a known quality
+const EDIT_PENALTY: f32 = 0.2;
+
+/// Characters commonly misread as a chord root letter
+const ROOT_CONFUSIONS: &[(char, char)] = &[
+    ('8', 'B'), ('6', 'G'), ('0', 'D'), ('O', 'D'), ('Q', 'G'), ('€', 'E'), ('£', 'E'),
+];
+
+/// Multi-character misreads inside a suffix, applied in order
+const SUFFIX_CONFUSIONS: &[(&str, &str)] = &[
+    ("rn", "m"), ("nn", "m"), ("ma7", "maj7"), ("mai", "maj"), ("rnaj", "maj"),
+    ("Δ", "maj7"), ("ø", "m7b5"), ("°", "dim"), ("l", "1"), ("I", "1"), ("S", "5"), ("O", "o"),
+];
+
+/// A corrected chord token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChordCorrection {
+    /// Token as it appeared in the input
+    pub original: String,
+    /// Valid chord name
+    pub chord: String,
+    /// 0.0-1.0, 1.0 when no correction was needed
+    pub confidence: f32,
+}
+
+/// Levenshtein edit distance between two short strings
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.iter().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+
+    prev[b.len()]
+}
+
+/// Fix the root letter and accidental, returning (root, rest, was_changed)
+fn correct_root(token: &str) -> Option<(String, String, bool)> {
+    let mut chars = token.chars();
+    let first = chars.next()?;
+    let rest: String = chars.collect();
+
+    let (letter, mut changed) = match first {
+        'A'..='G' => (first, false),
+        'a'..='g' => (first.to_ascii_uppercase(), true),
+        _ => (ROOT_CONFUSIONS.iter().find(|(from, _)| *from == first)?.1, true),
+    };
+
+    let mut root = letter.to_string();
+    let mut rest_chars = rest.chars().peekable();
+    match rest_chars.peek() {
+        Some('#') | Some('b') => root.push(rest_chars.next()?),
+        Some('♯') => {
+            rest_chars.next();
+            root.push('#');
+            changed = true;
+        }
+        Some('♭') => {
+            rest_chars.next();
+            root.push('b');
+            changed = true;
+        }
+        _ => {}
+    }
+
+    Some((root, rest_chars.collect(), changed))
+}
+
+/// Snap a suffix to a known chord quality, returning (suffix, edits)
+/// Edit-distance snapping is skipped when allow_edits is false
+fn correct_suffix(suffix: &str, allow_edits: bool) -> Option<(String, usize)> {
+    if CHORD_INTERVAL_SPECS.contains_key(suffix) {
+        return Some((suffix.to_string(), 0));
+    }
+
+    let substituted = SUFFIX_CONFUSIONS
+        .iter()
+        .fold(suffix.to_string(), |acc, (from, to)| acc.replace(from, to));
+    if CHORD_INTERVAL_SPECS.contains_key(substituted.as_str()) {
+        return Some((substituted, 1));
+    }
+    if !allow_edits {
+        return None;
+    }
+
+    // Allow one edit for short suffixes, two for longer ones
+    let max_edits = if substituted.chars().count() >= 4 { 2 } else { 1 };
+    CHORD_INTERVAL_SPECS
+        .keys()
+        .map(|known| (*known, edit_distance(&substituted, known)))
+        .filter(|(_, distance)| *distance <= max_edits)
+        .min_by(|(a, da), (b, db)| da.cmp(db).then(a.len().cmp(&b.len())).then(a.cmp(b)))
+        .map(|(known, distance)| (known.to_string(), distance + 1))
+}
+
+/// Correct a single token into a chord, or None if it doesn't look like one
+pub fn correct_chord_token(token: &str) -> Option<ChordCorrection> {
+    let cleaned = token.trim_matches(|c: char| c.is_whitespace() || "|.,;:()[]{}".contains(c));
+    if cleaned.is_empty() {
+        return None;
+    }
+
+    let (main, bass) = match cleaned.split_once('/') {
+        Some((main, bass)) => (main, Some(bass)),
+        None => (cleaned, None),
+    };
+
+    let (root, suffix, root_changed) = correct_root(main)?;
+    // A guessed root plus a guessed suffix is usually a word, not a chord
+    let (suffix, suffix_edits) = correct_suffix(&suffix, !root_changed)?;
+
+    let mut symbol = ChordSymbol::new(root, suffix);
+    let mut bass_changed = false;
+    if let Some(bass) = bass {
+        let (bass_root, bass_rest, changed) = correct_root(bass)?;
+        if !bass_rest.is_empty() {
+            return None;
+        }
+        symbol = symbol.over(bass_root);
+        bass_changed = changed;
+    }
+    let chord = symbol.to_string(SymbolStyle::default());
+
+    // Final sanity check against the real parser
+    parse_chord(&chord).ok()?;
+
+    let confidence = if suffix_edits > 1 {
+        (SUBSTITUTION_CONFIDENCE - EDIT_PENALTY * (suffix_edits - 1) as f32).max(0.0)
+    } else if root_changed || bass_changed || suffix_edits == 1 {
+        SUBSTITUTION_CONFIDENCE
+    } else {
+        EXACT_CONFIDENCE
+    };
+
+    Some(ChordCorrection {
+        original: token.to_string(),
+        chord,
+        confidence,
+    })
+}
+
+/// Extract a progression from free text (e.g. OCR output), skipping tokens that aren't chords
+pub fn extract_progression(text: &str) -> Vec<ChordCorrection> {
+    text.split(|c: char| c.is_whitespace() || c == '|')
+        .filter_map(correct_chord_token)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_chord_unchanged() {
+        let result = correct_chord_token("Dm7").unwrap();
+        assert_eq!(result.chord, "Dm7");
+        assert_eq!(result.confidence, EXACT_CONFIDENCE);
+    }
+
+    #[test]
+    fn test_common_ocr_misreads() {
+        assert_eq!(correct_chord_token("8b").unwrap().chord, "Bb");
+        assert_eq!(correct_chord_token("6rn7").unwrap().chord, "Gm7");
+        assert_eq!(correct_chord_token("Cma7").unwrap().chord, "Cmaj7");
+        assert_eq!(correct_chord_token("f#m").unwrap().chord, "F#m");
+    }
+
+    #[test]
+    fn test_slash_chord_correction() {
+        let result = correct_chord_token("C/e").unwrap();
+        assert_eq!(result.chord, "C/E");
+        assert!(result.confidence < EXACT_CONFIDENCE);
+    }
+
+    #[test]
+    fn test_edit_distance_snaps_suffix() {
+        let result = correct_chord_token("Csus3").unwrap();
+        assert!(result.chord.starts_with("Csus"));
+        assert!(result.confidence < SUBSTITUTION_CONFIDENCE);
+    }
+
+    #[test]
+    fn test_extract_progression_skips_words() {
+        let chords: Vec<String> = extract_progression("| C | Am | F  G7 |\nVerse one to be sung")
+            .into_iter()
+            .map(|c| c.chord)
+            .collect();
+        assert_eq!(chords, vec!["C", "Am", "F", "G7"]);
+    }
+}
@@ -1,7 +1,12 @@
 // Chord parsing, transposition, and validation
 // This module contains the core chord manipulation logic
 
-use super::types::{Chord, ChordNotation, ChordValidationResult, MusicError, MusicResult};
+use super::types::{
+    Chord, ChordNotation, ChordValidationResult, ConventionViolation, MusicError, MusicResult, NumeralMode,
+    ParseMode, SymbolConvention,
+};
+use super::interval_encoding::{is_valid_suffix, normalize_suffix};
+use super::intervals::CHORD_INTERVAL_SPECS;
 use super::notes::{note_index, get_preferred_note_name, get_key_signature_type, KeyType};
 use super::roman;
 
@@ -27,8 +32,65 @@ const DIATONIC_MINOR_IN_C: &[(&str, u8)] = &[
     ("Bb", 10),  // VII
 ];
 
+/// Split a note name off the front of a string, normalizing case and accidentals
+/// Accepts lowercase letters and Unicode accidentals: "b♭" → "Bb", "F𝄪" → "F##"
+/// Returns the note and the rest, or None when the string doesn't start with a note letter
+fn split_root(input: &str) -> Option<(String, &str)> {
+    let mut chars = input.char_indices();
+    let (_, first) = chars.next()?;
+    let letter = first.to_ascii_uppercase();
+    if !('A'..='G').contains(&letter) {
+        return None;
+    }
+
+    let mut root = letter.to_string();
+    let mut rest = &input[first.len_utf8()..];
+    if let Some(accidental) = rest.chars().next() {
+        let normalized = match accidental {
+            '#' | '♯' => Some("#"),
+            'b' | '♭' => Some("b"),
+            '𝄪' => Some("##"),
+            '𝄫' => Some("bb"),
+            _ => None,
+        };
+        if let Some(normalized) = normalized {
+            root.push_str(normalized);
+            rest = &rest[accidental.len_utf8()..];
+        }
+    }
+
+    Some((root, rest))
+}
+
+/// Replace Unicode chord symbols in a suffix with their ASCII spelling
+/// "Δ" → "maj7", "Δ9" → "maj9", "ø" → "m7b5", "°7" → "dim7", "♭9" → "b9"
+fn normalize_suffix_symbols(suffix: &str) -> String {
+    let mut out = String::with_capacity(suffix.len());
+    let mut chars = suffix.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next_is_digit = chars.peek().is_some_and(|n| n.is_ascii_digit());
+        match c {
+            'Δ' | '∆' if next_is_digit => out.push_str("maj"),
+            'Δ' | '∆' => out.push_str("maj7"),
+            'ø' | 'Ø' => {
+                if chars.peek() == Some(&'7') {
+                    chars.next();
+                }
+                out.push_str("m7b5");
+            }
+            '°' | 'º' => out.push_str("dim"),
+            '♯' => out.push('#'),
+            '♭' => out.push('b'),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
 /// Parse a chord string into root, suffix, and isMinor flag
 /// Examples: "C" → (C, "", false), "Dm7" → (D, "m7", true), "C/E" → (C, "", false) with bass
+/// Pasted input is normalized: lowercase roots ("c#m7"), Unicode accidentals ("B♭maj7", "F𝄪")
+/// and chord symbols ("CΔ7", "Bø", "D°7")
 pub fn parse_chord(chord: &str) -> MusicResult<Chord> {
 
     if chord.is_empty() {
@@ -42,38 +104,92 @@ pub fn parse_chord(chord: &str) -> MusicResult<Chord> {
     // Handle slash chords (e.g., "C/E")
     let (main_chord, bass) = if let Some(slash_pos) = chord.find('/') {
         let main = &chord[..slash_pos];
-        let bass_note = &chord[slash_pos + 1..].trim();
-        (main, Some(bass_note.to_string()))
+        let bass_note = chord[slash_pos + 1..].trim();
+        let bass_note = match split_root(bass_note) {
+            Some((note, "")) => note,
+            _ => bass_note.to_string(),
+        };
+        (main, Some(bass_note))
     } else {
         (chord, None)
     };
 
-    // Parse root note (first character)
-    let mut chars = main_chord.chars();
-    let first = chars.next().ok_or_else(|| MusicError::InvalidChord(chord.to_string()))?;
+    // Parse root note (letter plus optional accidental)
+    let (root, remainder) = split_root(main_chord)
+        .ok_or_else(|| MusicError::InvalidChord(format!("Root must be a note letter A-G: {}", chord)))?;
+    let suffix = normalize_suffix_symbols(remainder);
+
+    Ok(Chord { root, suffix, bass })
+}
 
-    if !first.is_ascii_uppercase() {
-        return Err(MusicError::InvalidChord(format!("Root must be uppercase: {}", chord)));
+/// How a chord symbol is written out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SymbolStyle {
+    /// Spell the suffix in its normalized form ("mi" → "m", "Δ7" → "maj7")
+    pub normalize_suffix: bool,
+    /// Leave out a bass note that is the root ("C/C" → "C")
+    pub omit_root_bass: bool,
+}
+
+/// A chord symbol built from its parts
+/// Chord strings are only ever assembled here, so every module writes them the same way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChordSymbol {
+    pub root: String,
+    pub suffix: String,
+    pub bass: Option<String>,
+}
+
+impl ChordSymbol {
+    pub fn new(root: impl Into<String>, suffix: impl Into<String>) -> Self {
+        Self { root: root.into(), suffix: suffix.into(), bass: None }
     }
 
-    let mut root = first.to_string();
-    let remainder: String = chars.collect();
+    /// The same chord over a bass note (a note name, or chord-tone notation such as "b7")
+    pub fn over(mut self, bass: impl Into<String>) -> Self {
+        self.bass = Some(bass.into());
+        self
+    }
 
-    // Check for accidental (# or b) immediately after root
-    let suffix = if let Some(first_char) = remainder.chars().next() {
-        if first_char == '#' || first_char == 'b' {
-            root.push(first_char);
-            remainder.chars().skip(1).collect()
-        } else {
-            remainder
+    /// Suffix without a slash part of its own when a bass is written after it
+    /// ("m7/G" over "B" is "m7"), so a symbol never carries two bass notes; "6/9" is a quality and stays
+    fn main_suffix(&self) -> &str {
+        match self.suffix.split_once('/') {
+            Some((main, _)) if self.bass.is_some() && !CHORD_INTERVAL_SPECS.contains_key(self.suffix.as_str()) => main,
+            _ => &self.suffix,
         }
-    } else {
-        String::new()
-    };
+    }
 
+    fn bass_is_root(&self, bass: &str) -> bool {
+        match (note_index(&self.root), note_index(bass)) {
+            (Ok(root), Ok(bass)) => root == bass,
+            _ => self.root == bass,
+        }
+    }
 
-    
-    Ok(Chord { root, suffix, bass })
+    /// Write the symbol: root, suffix and "/bass"
+    pub fn to_string(&self, style: SymbolStyle) -> String {
+        let suffix = self.main_suffix();
+        let mut symbol = self.root.clone();
+        if style.normalize_suffix {
+            symbol.push_str(&normalize_suffix(suffix));
+        } else {
+            symbol.push_str(suffix);
+        }
+        if let Some(bass) = self.bass.as_deref().filter(|bass| !bass.is_empty()) {
+            if !(style.omit_root_bass && self.bass_is_root(bass)) {
+                symbol.push('/');
+                symbol.push_str(bass);
+            }
+        }
+        symbol
+    }
+}
+
+impl From<Chord> for ChordSymbol {
+    fn from(chord: Chord) -> Self {
+        Self { root: chord.root, suffix: chord.suffix, bass: chord.bass }
+    }
 }
 
 /// Transpose a chord from one key to another with proper enharmonic spelling
@@ -101,17 +217,16 @@ pub fn transpose_chord(
     let new_root_idx = (root_idx + interval) % 12;
     let new_root = get_preferred_note_name(new_root_idx, to_key, use_flats);
 
+    let mut symbol = ChordSymbol::new(new_root, parsed.suffix);
+
     // Transpose bass note if present (slash chord)
-    let result = if let Some(bass_note) = parsed.bass {
+    if let Some(bass_note) = parsed.bass {
         let bass_idx = note_index(&bass_note)?;
         let new_bass_idx = (bass_idx + interval) % 12;
-        let new_bass = get_preferred_note_name(new_bass_idx, to_key, use_flats);
-        format!("{}{}/{}", new_root, parsed.suffix, new_bass)
-    } else {
-        format!("{}{}", new_root, parsed.suffix)
-    };
+        symbol = symbol.over(get_preferred_note_name(new_bass_idx, to_key, use_flats));
+    }
 
-    Ok(result)
+    Ok(symbol.to_string(SymbolStyle::default()))
 }
 
 /// Normalize chord notation to match the key signature
@@ -168,11 +283,65 @@ pub fn get_minor_diatonic_chords(
         .collect()
 }
 
+/// Check a chord symbol against textbook formatting, returning the first convention it breaks
+/// Strict (teaching) mode rejects anything the permissive parser would silently normalize
+pub fn textbook_violation(input: &str) -> Option<ConventionViolation> {
+    let violation = |convention, message: String| Some(ConventionViolation { convention, message });
+
+    if input.chars().any(char::is_whitespace) {
+        return violation(SymbolConvention::NoSpaces, "Chord symbols are written without spaces".to_string());
+    }
+    if input.chars().any(|c| matches!(c, '♯' | '♭' | '𝄪' | '𝄫')) {
+        return violation(SymbolConvention::AsciiAccidentals, "Write accidentals as # and b".to_string());
+    }
+    if input.chars().any(|c| matches!(c, 'Δ' | '∆' | 'ø' | 'Ø' | '°' | 'º')) {
+        return violation(
+            SymbolConvention::SpelledQuality,
+            "Write maj7, m7b5 and dim instead of Δ, ø and °".to_string(),
+        );
+    }
+
+    let (main, bass) = match input.split_once('/') {
+        Some((main, bass)) => (main, Some(bass)),
+        None => (input, None),
+    };
+    for part in std::iter::once(main).chain(bass) {
+        if let Some(first) = part.chars().next().filter(|c| ('a'..='g').contains(c)) {
+            return violation(
+                SymbolConvention::UppercaseRoot,
+                format!("Write note letters as capitals: {}, not {}", first.to_ascii_uppercase(), first),
+            );
+        }
+    }
+
+    let parsed = parse_chord(input).ok()?;
+    let standard = normalize_suffix(&parsed.suffix);
+    if standard != parsed.suffix {
+        return violation(
+            SymbolConvention::StandardSuffix,
+            format!("Write \"{}\" rather than \"{}\"", standard, parsed.suffix),
+        );
+    }
+    if !is_valid_suffix(&parsed.suffix) && !CHORD_INTERVAL_SPECS.contains_key(parsed.suffix.as_str()) {
+        return violation(SymbolConvention::KnownQuality, format!("Unknown chord quality: {}", parsed.suffix));
+    }
+
+    None
+}
+
+/// Rebuild a parsed chord in its normalized spelling ("c#mi" → "C#m", "B♭Δ" → "Bbmaj7")
+pub(crate) fn normalized_symbol(chord: &Chord) -> String {
+    ChordSymbol::from(chord.clone()).to_string(SymbolStyle { normalize_suffix: true, ..SymbolStyle::default() })
+}
+
 /// Validate chord input (accepts chord names or Roman numerals)
+/// Permissive mode normalizes lowercase roots, Unicode symbols and alias suffixes;
+/// strict mode rejects them and reports the convention that was broken
 pub fn validate_chord_input(
     input: &str,
     key: &str,
     use_flats: bool,
+    mode: ParseMode,
 ) -> MusicResult<ChordValidationResult> {
     let trimmed = input.trim();
 
@@ -183,37 +352,76 @@ pub fn validate_chord_input(
             normalized_chord: None,
             message: None,
             input_type: None,
+            violation: None,
         });
     }
 
+    let violation = match mode {
+        ParseMode::Strict => textbook_violation(input),
+        ParseMode::Permissive => None,
+    };
+    let invalid = |violation: ConventionViolation| ChordValidationResult {
+        valid: false,
+        chord: None,
+        normalized_chord: None,
+        message: Some(violation.message),
+        input_type: Some("chord".to_string()),
+        violation: Some(violation.convention),
+    };
+
     // First, try to parse as a chord name (starts with A-G)
     if let Some(first_char) = trimmed.chars().next() {
         if first_char.is_ascii_uppercase() && "ABCDEFG".contains(first_char) {
             if let Ok(parsed) = parse_chord(trimmed) {
                 if !parsed.root.is_empty() {
+                    if let Some(violation) = violation {
+                        return Ok(invalid(violation));
+                    }
                     return Ok(ChordValidationResult {
                         valid: true,
                         chord: Some(trimmed.to_string()),
-                        normalized_chord: Some(trimmed.to_string()),
+                        normalized_chord: Some(normalized_symbol(&parsed)),
                         message: None,
                         input_type: Some("chord".to_string()),
+                        violation: None,
                     });
                 }
             }
         }
     }
 
-    // Try to parse as a Roman numeral
-    if let Ok(chord) = roman::roman_numeral_to_chord(trimmed, key, use_flats) {
+    // Try to parse as a Roman numeral, read in the key's mode ("Am" → A natural minor)
+    let (tonic, numeral_mode) = NumeralMode::from_key(key);
+    if let Ok(chord) = roman::roman_numeral_to_chord(trimmed, tonic, numeral_mode, use_flats) {
         return Ok(ChordValidationResult {
             valid: true,
             chord: Some(chord.clone()),
             normalized_chord: Some(chord),
             message: None,
             input_type: Some("numeral".to_string()),
+            violation: None,
         });
     }
 
+    // Lowercase chord names ("c#m7"), read only once they can't be a numeral and the quality is known
+    if let Ok(parsed) = parse_chord(trimmed) {
+        let normalized = normalize_suffix(&parsed.suffix);
+        let known = is_valid_suffix(&normalized) || CHORD_INTERVAL_SPECS.contains_key(normalized.as_str());
+        if known {
+            if let Some(violation) = violation {
+                return Ok(invalid(violation));
+            }
+            return Ok(ChordValidationResult {
+                valid: true,
+                chord: Some(trimmed.to_string()),
+                normalized_chord: Some(normalized_symbol(&parsed)),
+                message: None,
+                input_type: Some("chord".to_string()),
+                violation: None,
+            });
+        }
+    }
+
     // Neither valid chord nor valid Roman numeral
     Ok(ChordValidationResult {
         valid: false,
@@ -221,12 +429,33 @@ pub fn validate_chord_input(
         normalized_chord: None,
         message: Some(format!("Invalid chord or numeral: {}", trimmed)),
         input_type: None,
+        violation: None,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::music::intervals::chord_to_notes;
+
+    #[test]
+    fn test_chord_symbol_styles() {
+        let plain = SymbolStyle::default();
+        assert_eq!(ChordSymbol::new("F", "").over("A").to_string(plain), "F/A");
+        assert_eq!(ChordSymbol::new("C", "mi7").to_string(plain), "Cmi7");
+
+        let normalized = SymbolStyle { normalize_suffix: true, ..plain };
+        assert_eq!(ChordSymbol::new("C", "min7").over("G").to_string(normalized), "Cm7/G");
+
+        let root_position = SymbolStyle { omit_root_bass: true, ..plain };
+        assert_eq!(ChordSymbol::new("C", "").over("C").to_string(root_position), "C");
+        assert_eq!(ChordSymbol::new("C", "").over("C").to_string(plain), "C/C");
+
+        // A bass already written into the suffix is replaced, never doubled; 6/9 is a quality
+        assert_eq!(ChordSymbol::new("C", "m7/G").over("Bb").to_string(plain), "Cm7/Bb");
+        assert_eq!(ChordSymbol::new("C", "6/9").over("E").to_string(plain), "C6/9/E");
+        assert_eq!(ChordSymbol::new("C", "6/9").to_string(plain), "C6/9");
+    }
 
     #[test]
     fn test_parse_chord_simple() {
@@ -255,6 +484,38 @@ mod tests {
         assert_eq!(chord2.suffix, "maj7");
     }
 
+    #[test]
+    fn test_parse_chord_lowercase_and_unicode() {
+        let chord = parse_chord("c#m7").unwrap();
+        assert_eq!((chord.root.as_str(), chord.suffix.as_str()), ("C#", "m7"));
+
+        let chord = parse_chord("B♭maj7").unwrap();
+        assert_eq!((chord.root.as_str(), chord.suffix.as_str()), ("Bb", "maj7"));
+
+        assert_eq!(parse_chord("F♯").unwrap().root, "F#");
+        assert_eq!(parse_chord("bb").unwrap().root, "Bb");
+        assert_eq!(parse_chord("F𝄪").unwrap().root, "F##");
+        assert_eq!(parse_chord("E𝄫").unwrap().root, "Ebb");
+        assert_eq!(parse_chord("Emi").unwrap().suffix, "mi");
+        assert_eq!(chord_to_notes("Emi").unwrap(), ["E", "G", "B"]);
+        assert_eq!(chord_to_notes("emi7").unwrap(), ["E", "G", "B", "D"]);
+        assert_eq!(chord_to_notes("E-").unwrap(), ["E", "G", "B"]);
+        assert!(chord_to_notes("Exyz").is_err());
+        assert_eq!(parse_chord("d/f♯").unwrap().bass, Some("F#".to_string()));
+        assert!(parse_chord("H7").is_err());
+    }
+
+    #[test]
+    fn test_parse_chord_symbols() {
+        assert_eq!(parse_chord("CΔ").unwrap().suffix, "maj7");
+        assert_eq!(parse_chord("CΔ7").unwrap().suffix, "maj7");
+        assert_eq!(parse_chord("CΔ9").unwrap().suffix, "maj9");
+        assert_eq!(parse_chord("Bø").unwrap().suffix, "m7b5");
+        assert_eq!(parse_chord("Bø7").unwrap().suffix, "m7b5");
+        assert_eq!(parse_chord("D°7").unwrap().suffix, "dim7");
+        assert_eq!(parse_chord("G7♭9").unwrap().suffix, "7b9");
+    }
+
     #[test]
     fn test_parse_slash_chord() {
         let chord = parse_chord("C/E").unwrap();
@@ -323,13 +584,46 @@ mod tests {
 
     #[test]
     fn test_validate_chord() {
-        let result = validate_chord_input("C", "C", true).unwrap();
+        let result = validate_chord_input("C", "C", true, ParseMode::Permissive).unwrap();
         assert!(result.valid);
 
-        let result2 = validate_chord_input("Dm7", "C", true).unwrap();
+        let result2 = validate_chord_input("Dm7", "C", true, ParseMode::Permissive).unwrap();
         assert!(result2.valid);
     }
 
+    #[test]
+    fn test_validate_permissive_normalizes() {
+        let result = validate_chord_input("c#m7", "C", true, ParseMode::Permissive).unwrap();
+        assert!(result.valid);
+        assert_eq!(result.normalized_chord.as_deref(), Some("C#m7"));
+
+        let result = validate_chord_input("B♭Δ", "C", true, ParseMode::Permissive).unwrap();
+        assert_eq!(result.normalized_chord.as_deref(), Some("Bbmaj7"));
+        assert_eq!(validate_chord_input("Emi", "C", true, ParseMode::Permissive).unwrap().normalized_chord.as_deref(), Some("Em"));
+
+        // Lowercase numerals still read as numerals
+        let result = validate_chord_input("bVII", "C", true, ParseMode::Permissive).unwrap();
+        assert_eq!(result.input_type.as_deref(), Some("numeral"));
+    }
+
+    #[test]
+    fn test_validate_strict_reports_convention() {
+        let strict = |input| validate_chord_input(input, "C", true, ParseMode::Strict).unwrap();
+
+        assert!(strict("C#m7").valid);
+        assert!(strict("G7/B").valid);
+        assert!(strict("vi").valid);
+        assert_eq!(strict("c#m7").violation, Some(SymbolConvention::UppercaseRoot));
+        assert_eq!(strict("B♭maj7").violation, Some(SymbolConvention::AsciiAccidentals));
+        assert_eq!(strict("CΔ7").violation, Some(SymbolConvention::SpelledQuality));
+        assert_eq!(strict("C/e").violation, Some(SymbolConvention::UppercaseRoot));
+
+        let result = strict("Emi");
+        assert!(!result.valid);
+        assert_eq!(result.violation, Some(SymbolConvention::StandardSuffix));
+        assert_eq!(result.message.as_deref(), Some("Write \"m\" rather than \"mi\""));
+    }
+
     #[test]
     fn test_get_initial_chords() {
         let chords = get_initial_chords("C", true).unwrap();
@@ -349,7 +643,7 @@ pub fn get_initial_chords(key: &str, use_flats: bool) -> MusicResult<Vec<String>
     ];
     
     for numeral in common_non_diatonic {
-        if let Ok(chord) = roman::roman_numeral_to_chord(numeral, key, use_flats) {
+        if let Ok(chord) = roman::roman_numeral_to_chord(numeral, key, NumeralMode::Major, use_flats) {
             initial.push(chord);
         }
     }
@@ -364,7 +658,8 @@ pub fn get_initial_chords(key: &str, use_flats: bool) -> MusicResult<Vec<String>
 /// The caller (recommendations, user input) has already chosen the correct enharmonic spelling
 pub fn prepare_chord_display(chord: &str, key: &str) -> MusicResult<ChordNotation> {
     // Compute roman numeral for display - preserve chord as-is
-    let numeral = roman::get_display_numeral(chord, key)?;
+    let (tonic, mode) = NumeralMode::from_key(key);
+    let numeral = roman::get_display_numeral(chord, tonic, mode)?;
 
     Ok(ChordNotation {
         chord: chord.to_string(),
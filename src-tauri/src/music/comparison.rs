@@ -0,0 +1,165 @@
+// Chord comparison
+// Shared and differing tones between two chords, for teaching chord relationships
+
+use serde::Serialize;
+
+use super::chords::parse_chord;
+use super::intervals::chord_to_notes;
+use super::notes::note_index;
+use super::types::{MusicError, MusicResult};
+
+/// Names of the interval classes by letter steps (0 = unison ... 6 = seventh)
+const INTERVAL_NAMES: [&str; 7] = ["unison", "second", "third", "fourth", "fifth", "sixth", "seventh"];
+/// Semitones of the major/perfect form of each letter step
+const NATURAL_SEMITONES: [i32; 7] = [0, 2, 4, 5, 7, 9, 11];
+
+/// How the note sets of two chords relate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SetRelation {
+    /// Same pitch classes (e.g. C6 and Am7)
+    Equivalent,
+    /// Every note of the first chord is in the second
+    Subset,
+    /// The first chord contains every note of the second
+    Superset,
+    /// Some notes in common
+    Overlapping,
+    Disjoint,
+}
+
+/// Interval from the first chord's root up to the second's
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RootInterval {
+    /// "major sixth", "perfect fifth"
+    pub name: String,
+    pub semitones: u8,
+}
+
+/// Comparison of two chords
+#[derive(Debug, Clone, Serialize)]
+pub struct ChordComparison {
+    pub a: String,
+    pub b: String,
+    /// Tones in both chords, spelled as in the first
+    pub shared_tones: Vec<String>,
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+    pub root_interval: RootInterval,
+    pub relation: SetRelation,
+}
+
+/// Letter step (0-6) of a note name
+fn letter_step(note: &str) -> MusicResult<i32> {
+    let letter = note.chars().next().map(|c| c.to_ascii_uppercase());
+    ["C", "D", "E", "F", "G", "A", "B"]
+        .iter()
+        .position(|l| Some(l.chars().next().unwrap()) == letter)
+        .map(|p| p as i32)
+        .ok_or_else(|| MusicError::InvalidChord(note.to_string()))
+}
+
+/// Spelled interval name between two roots, measured upwards
+fn interval_name(from: &str, to: &str) -> MusicResult<RootInterval> {
+    let steps = (letter_step(to)? - letter_step(from)?).rem_euclid(7) as usize;
+    let semitones = (note_index(to)? as i32 - note_index(from)? as i32).rem_euclid(12);
+
+    // Deviation from the major/perfect size, wrapped so a B to C "unison" reads as a semitone
+    let deviation = (semitones - NATURAL_SEMITONES[steps] + 6).rem_euclid(12) - 6;
+    let perfect = matches!(steps, 0 | 3 | 4);
+    let quality = match (perfect, deviation) {
+        (true, 0) => "perfect",
+        (false, 0) => "major",
+        (false, -1) => "minor",
+        (true, -1) | (false, -2) => "diminished",
+        (_, 1) => "augmented",
+        (_, d) if d < 0 => "doubly diminished",
+        _ => "doubly augmented",
+    };
+
+    Ok(RootInterval { name: format!("{} {}", quality, INTERVAL_NAMES[steps]), semitones: semitones as u8 })
+}
+
+/// Notes of a chord with their pitch classes
+fn chord_tones(chord: &str) -> MusicResult<Vec<(String, u8)>> {
+    chord_to_notes(chord)?
+        .into_iter()
+        .map(|note| note_index(&note).map(|pc| (note, pc)))
+        .collect()
+}
+
+/// Compare two chords' tones and roots
+pub fn compare_chords(a: &str, b: &str) -> MusicResult<ChordComparison> {
+    let tones_a = chord_tones(a)?;
+    let tones_b = chord_tones(b)?;
+    let in_b = |pc: u8| tones_b.iter().any(|(_, other)| *other == pc);
+    let in_a = |pc: u8| tones_a.iter().any(|(_, other)| *other == pc);
+
+    let shared_tones: Vec<String> = tones_a.iter().filter(|(_, pc)| in_b(*pc)).map(|(n, _)| n.clone()).collect();
+    let only_in_a: Vec<String> = tones_a.iter().filter(|(_, pc)| !in_b(*pc)).map(|(n, _)| n.clone()).collect();
+    let only_in_b: Vec<String> = tones_b.iter().filter(|(_, pc)| !in_a(*pc)).map(|(n, _)| n.clone()).collect();
+
+    let relation = match (only_in_a.is_empty(), only_in_b.is_empty()) {
+        (true, true) => SetRelation::Equivalent,
+        (true, false) => SetRelation::Subset,
+        (false, true) => SetRelation::Superset,
+        _ if shared_tones.is_empty() => SetRelation::Disjoint,
+        _ => SetRelation::Overlapping,
+    };
+
+    let root_interval = interval_name(&parse_chord(a)?.root, &parse_chord(b)?.root)?;
+
+    Ok(ChordComparison {
+        a: a.to_string(),
+        b: b.to_string(),
+        shared_tones,
+        only_in_a,
+        only_in_b,
+        root_interval,
+        relation,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equivalent_chords() {
+        let comparison = compare_chords("C6", "Am7").unwrap();
+        assert_eq!(comparison.relation, SetRelation::Equivalent);
+        assert_eq!(comparison.shared_tones.len(), 4);
+        assert_eq!(comparison.root_interval.name, "major sixth");
+        assert_eq!(comparison.root_interval.semitones, 9);
+    }
+
+    #[test]
+    fn test_subset_and_superset() {
+        let comparison = compare_chords("C", "Cmaj7").unwrap();
+        assert_eq!(comparison.relation, SetRelation::Subset);
+        assert_eq!(comparison.only_in_b, vec!["B"]);
+        assert_eq!(comparison.root_interval.name, "perfect unison");
+
+        assert_eq!(compare_chords("G7", "Bdim").unwrap().relation, SetRelation::Superset);
+    }
+
+    #[test]
+    fn test_overlapping_and_disjoint() {
+        let comparison = compare_chords("C", "Am").unwrap();
+        assert_eq!(comparison.relation, SetRelation::Overlapping);
+        assert_eq!(comparison.shared_tones, vec!["C", "E"]);
+        assert_eq!(comparison.only_in_a, vec!["G"]);
+        assert_eq!(comparison.only_in_b, vec!["A"]);
+
+        assert_eq!(compare_chords("C", "Db").unwrap().relation, SetRelation::Disjoint);
+    }
+
+    #[test]
+    fn test_spelled_root_intervals() {
+        assert_eq!(interval_name("C", "G").unwrap().name, "perfect fifth");
+        assert_eq!(interval_name("C", "Eb").unwrap().name, "minor third");
+        assert_eq!(interval_name("C", "F#").unwrap().name, "augmented fourth");
+        assert_eq!(interval_name("C", "Gb").unwrap().name, "diminished fifth");
+        assert_eq!(interval_name("E", "C").unwrap().name, "minor sixth");
+    }
+}
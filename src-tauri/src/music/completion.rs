@@ -0,0 +1,208 @@
+// Progression autocomplete
+// Extends a partial progression to a full phrase that ends on a cadence
+
+use serde::Serialize;
+
+use super::analysis::Cadence;
+use super::chords::{get_diatonic_chords, parse_chord};
+use super::notes::note_index;
+use super::types::{MusicError, MusicResult};
+
+/// Partial sequences kept at each search step
+const BEAM_WIDTH: usize = 64;
+
+/// Functional-harmony transition weights between scale degrees (1-7)
+/// Rows are the current degree, entries are (next degree, weight)
+const TRANSITIONS: [&[(u8, f32)]; 7] = [
+    &[(4, 0.30), (5, 0.25), (6, 0.20), (2, 0.15), (3, 0.10)], // I
+    &[(5, 0.60), (4, 0.15), (7, 0.15), (1, 0.10)],             // ii
+    &[(6, 0.50), (4, 0.30), (2, 0.20)],                        // iii
+    &[(5, 0.45), (1, 0.25), (2, 0.15), (7, 0.15)],             // IV
+    &[(1, 0.60), (6, 0.25), (4, 0.15)],                        // V
+    &[(2, 0.35), (4, 0.35), (5, 0.15), (3, 0.15)],             // vi
+    &[(1, 0.70), (6, 0.15), (5, 0.15)],                        // vii
+];
+
+/// One suggested completion
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressionCandidate {
+    /// Full progression: the partial input followed by the suggested chords
+    pub chords: Vec<String>,
+    pub cadence: Cadence,
+    /// Relative likelihood (higher is more conventional)
+    pub score: f32,
+}
+
+/// Preference for each cadence when ranking otherwise similar phrases
+fn cadence_weight(cadence: Cadence) -> f32 {
+    match cadence {
+        Cadence::Authentic => 1.0,
+        Cadence::Plagal => 0.7,
+        Cadence::Half => 0.6,
+        Cadence::Deceptive => 0.5,
+    }
+}
+
+/// Cadence formed by the final two degrees of a phrase
+pub fn final_cadence(previous: u8, last: u8) -> Option<Cadence> {
+    match (previous, last) {
+        (5 | 7, 1) => Some(Cadence::Authentic),
+        (4, 1) => Some(Cadence::Plagal),
+        (5, 6) => Some(Cadence::Deceptive),
+        (p, 5) if p != 5 => Some(Cadence::Half),
+        _ => None,
+    }
+}
+
+/// Likelihood of moving from one scale degree to another (1-7)
+pub fn transition_weight(from: u8, to: u8) -> f32 {
+    TRANSITIONS[(from - 1) as usize]
+        .iter()
+        .find(|(degree, _)| *degree == to)
+        .map(|(_, weight)| *weight)
+        .unwrap_or(0.0)
+}
+
+/// Most likely next scale degrees after the given degree (1-7), most likely first
+pub fn common_successors(from: u8) -> Vec<u8> {
+    TRANSITIONS[(from - 1) as usize].iter().map(|(degree, _)| *degree).collect()
+}
+
+/// Scale degree (1-7) of a chord's root within the key's diatonic chords
+fn chord_degree(chord: &str, diatonic: &[String]) -> Option<u8> {
+    let root = note_index(&parse_chord(chord).ok()?.root).ok()?;
+    diatonic
+        .iter()
+        .position(|d| {
+            parse_chord(d)
+                .ok()
+                .and_then(|p| note_index(&p.root).ok())
+                .is_some_and(|idx| idx == root)
+        })
+        .map(|i| i as u8 + 1)
+}
+
+/// Extend a partial progression to target_length chords, ending on a cadence
+/// Returns up to max_candidates distinct completions, best first
+pub fn complete_progression(
+    partial: &[String],
+    key: &str,
+    target_length: usize,
+    max_candidates: usize,
+) -> MusicResult<Vec<ProgressionCandidate>> {
+    if target_length <= partial.len() {
+        return Err(MusicError::ParseError(format!(
+            "Target length {} must be longer than the partial progression ({} chords)",
+            target_length,
+            partial.len()
+        )));
+    }
+
+    let diatonic = get_diatonic_chords(key, true)?;
+
+    // Seed from the last entered chord; chromatic chords are treated like a fresh start on I
+    let (seed_degrees, remaining) = match partial.last() {
+        Some(last) => {
+            let previous = partial.len().checked_sub(2).and_then(|i| chord_degree(&partial[i], &diatonic));
+            let last = chord_degree(last, &diatonic).unwrap_or(1);
+            (previous.into_iter().chain(std::iter::once(last)).collect::<Vec<u8>>(), target_length - partial.len())
+        }
+        None => (vec![1], target_length - 1),
+    };
+
+    // Beam search over degree sequences
+    let mut beam: Vec<(Vec<u8>, f32)> = vec![(Vec::new(), 1.0)];
+    for _ in 0..remaining {
+        let mut next: Vec<(Vec<u8>, f32)> = Vec::new();
+        for (path, score) in &beam {
+            let from = *path.last().or(seed_degrees.last()).unwrap_or(&1);
+            for to in 1..=7u8 {
+                let weight = transition_weight(from, to);
+                if weight > 0.0 {
+                    let mut extended = path.clone();
+                    extended.push(to);
+                    next.push((extended, score * weight));
+                }
+            }
+        }
+        next.sort_by(|a, b| b.1.total_cmp(&a.1));
+        next.truncate(BEAM_WIDTH);
+        beam = next;
+    }
+
+    let mut candidates: Vec<(Vec<u8>, Cadence, f32)> = beam
+        .into_iter()
+        .filter_map(|(path, score)| {
+            let full: Vec<u8> = seed_degrees.iter().chain(path.iter()).copied().collect();
+            let (previous, last) = match full.as_slice() {
+                [.., p, l] => (*p, *l),
+                _ => return None,
+            };
+            final_cadence(previous, last).map(|cadence| (path, cadence, score * cadence_weight(cadence)))
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    let prefix: Vec<String> = if partial.is_empty() { vec![diatonic[0].clone()] } else { partial.to_vec() };
+
+    Ok(candidates
+        .into_iter()
+        .take(max_candidates)
+        .map(|(path, cadence, score)| {
+            let mut chords = prefix.clone();
+            chords.extend(path.iter().map(|degree| diatonic[(*degree - 1) as usize].clone()));
+            ProgressionCandidate { chords, cadence, score }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chords(names: &[&str]) -> Vec<String> {
+        names.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_completes_to_target_length_with_cadence() {
+        let results = complete_progression(&chords(&["C", "Am"]), "C", 4, 3).unwrap();
+
+        assert!(!results.is_empty());
+        for candidate in &results {
+            assert_eq!(candidate.chords.len(), 4);
+            assert_eq!(&candidate.chords[..2], &["C", "Am"]);
+        }
+        // vi → ii → V (half cadence) is the strongest continuation, vi → V → I is also offered
+        assert_eq!(results[0].chords, vec!["C", "Am", "Dm", "G"]);
+        assert_eq!(results[0].cadence, Cadence::Half);
+        assert!(results.iter().any(|c| c.cadence == Cadence::Authentic && c.chords[3] == "C"));
+    }
+
+    #[test]
+    fn test_candidates_are_ranked_and_distinct() {
+        let results = complete_progression(&chords(&["G"]), "G", 5, 5).unwrap();
+        assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+        for (i, a) in results.iter().enumerate() {
+            assert!(results[i + 1..].iter().all(|b| b.chords != a.chords));
+        }
+    }
+
+    #[test]
+    fn test_single_chord_completion_forms_cadence() {
+        let results = complete_progression(&chords(&["C", "F", "G"]), "C", 4, 3).unwrap();
+        assert_eq!(results[0].chords, vec!["C", "F", "G", "C"]);
+    }
+
+    #[test]
+    fn test_empty_partial_starts_on_tonic() {
+        let results = complete_progression(&[], "F", 4, 1).unwrap();
+        assert_eq!(results[0].chords[0], "F");
+        assert_eq!(results[0].chords.len(), 4);
+    }
+
+    #[test]
+    fn test_target_must_exceed_partial() {
+        assert!(complete_progression(&chords(&["C", "G"]), "C", 2, 3).is_err());
+    }
+}
@@ -0,0 +1,306 @@
+// Progression generator for composition exercises
+// Searches the functional-harmony transition model for progressions that meet an assignment's
+// constraints (length, first chord, final cadence, secondary dominants, repeats), so every
+// assignment handed out is known to have solutions
+
+use serde::{Deserialize, Serialize};
+
+use super::analysis::Cadence;
+use super::completion::{final_cadence, transition_weight};
+use super::roman::roman_numeral_to_chord;
+use super::types::{MusicError, MusicResult, NumeralMode};
+
+/// Longest progression the search is asked for
+pub const MAX_COMPOSITION_LENGTH: usize = 16;
+
+/// Partial progressions tried before the search settles for the solutions found so far
+const MAX_SEARCH_NODES: usize = 200_000;
+
+/// Solutions collected before ranking; plenty for any sensible number of results
+const MAX_SOLUTIONS: usize = 2_000;
+
+/// Weight of tonicizing a chord through its secondary dominant, relative to moving to it directly
+const SECONDARY_WEIGHT: f32 = 0.4;
+
+/// Diatonic triads searched in each mode, by degree
+/// Minor keys use the harmonic-minor V so that authentic cadences have a leading tone
+const MAJOR_NUMERALS: [&str; 7] = ["I", "ii", "iii", "IV", "V", "vi", "vii°"];
+const MINOR_NUMERALS: [&str; 7] = ["i", "ii°", "III", "iv", "V", "VI", "VII"];
+
+/// What an assignment asks of the progression
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ProgressionConstraints {
+    /// Key name ("Eb", "F#m"); minor keys use the natural minor with a major V
+    pub key: String,
+    pub length: usize,
+    /// Numeral of the first chord ("I", "vi", "V7/V"); None leaves it open
+    pub start: Option<String>,
+    /// Cadence of the final two chords; an authentic cadence is perfect, V to I in root position
+    pub end_cadence: Option<Cadence>,
+    /// Exact number of secondary dominants (V7/x, each followed by its target); None allows any
+    pub secondary_dominants: Option<usize>,
+    /// No chord immediately repeated
+    pub no_repeats: bool,
+    pub use_flats: bool,
+}
+
+impl Default for ProgressionConstraints {
+    fn default() -> Self {
+        Self {
+            key: "C".to_string(),
+            length: 8,
+            start: None,
+            end_cadence: None,
+            secondary_dominants: None,
+            no_repeats: false,
+            use_flats: false,
+        }
+    }
+}
+
+/// One progression meeting the constraints
+#[derive(Debug, Clone, Serialize)]
+pub struct ComposedProgression {
+    pub chords: Vec<String>,
+    pub numerals: Vec<String>,
+    /// Cadence of the final two chords, if they form one
+    pub cadence: Option<Cadence>,
+    /// Relative likelihood (higher is more conventional)
+    pub score: f32,
+}
+
+/// A chord the search can place
+#[derive(Debug, Clone)]
+struct Step {
+    numeral: String,
+    /// Scale degree (1-7) of the chord, or of its target for a secondary dominant
+    degree: u8,
+    secondary: bool,
+}
+
+/// The key's diatonic triads followed by a secondary dominant for each major or minor triad but the tonic
+fn vocabulary(mode: NumeralMode) -> Vec<Step> {
+    let numerals = if mode == NumeralMode::Major { &MAJOR_NUMERALS } else { &MINOR_NUMERALS };
+    let diatonic = numerals
+        .iter()
+        .zip(1u8..)
+        .map(|(numeral, degree)| Step { numeral: numeral.to_string(), degree, secondary: false });
+    let secondary = numerals
+        .iter()
+        .zip(1u8..)
+        .skip(1)
+        .filter(|(numeral, _)| !numeral.ends_with('°'))
+        .map(|(numeral, degree)| Step { numeral: format!("V7/{}", numeral), degree, secondary: true });
+    diatonic.chain(secondary).collect()
+}
+
+/// Weight of moving from one step to the next; a secondary dominant must resolve to its target
+fn step_weight(from: &Step, to: &Step) -> f32 {
+    match (from.secondary, to.secondary) {
+        (true, _) => f32::from(!to.secondary && to.degree == from.degree),
+        (false, false) => transition_weight(from.degree, to.degree),
+        (false, true) => transition_weight(from.degree, to.degree) * SECONDARY_WEIGHT,
+    }
+}
+
+/// Cadence formed by the final two steps; secondary dominants form none
+fn ending_cadence(previous: &Step, last: &Step) -> Option<Cadence> {
+    if previous.secondary || last.secondary {
+        return None;
+    }
+    final_cadence(previous.degree, last.degree)
+}
+
+struct Search<'a> {
+    constraints: &'a ProgressionConstraints,
+    steps: Vec<Step>,
+    nodes: usize,
+    solutions: Vec<(Vec<usize>, f32)>,
+}
+
+impl Search<'_> {
+    fn secondaries(&self, path: &[usize]) -> usize {
+        path.iter().filter(|index| self.steps[**index].secondary).count()
+    }
+
+    fn accepts(&self, path: &[usize]) -> bool {
+        let [.., previous, last] = path else {
+            return false;
+        };
+        let (previous, last) = (&self.steps[*previous], &self.steps[*last]);
+        let cadence = ending_cadence(previous, last);
+        let cadence_met = match self.constraints.end_cadence {
+            Some(Cadence::Authentic) => cadence == Some(Cadence::Authentic) && previous.degree == 5,
+            Some(expected) => cadence == Some(expected),
+            None => true,
+        };
+        cadence_met
+            && !last.secondary
+            && self.constraints.secondary_dominants.is_none_or(|count| self.secondaries(path) == count)
+    }
+
+    fn extend(&mut self, path: &mut Vec<usize>, score: f32) {
+        self.nodes += 1;
+        if self.nodes > MAX_SEARCH_NODES || self.solutions.len() >= MAX_SOLUTIONS {
+            return;
+        }
+        if path.len() == self.constraints.length {
+            if self.accepts(path) {
+                self.solutions.push((path.clone(), score));
+            }
+            return;
+        }
+
+        // Each secondary dominant still needed takes a chord and is followed by another
+        let remaining = self.constraints.length - path.len();
+        let used = self.secondaries(path);
+        if let Some(count) = self.constraints.secondary_dominants {
+            if used > count || count - used > remaining / 2 {
+                return;
+            }
+        }
+
+        let last = *path.last().expect("paths start with a first chord");
+        let mut next: Vec<(usize, f32)> = (0..self.steps.len())
+            .filter(|index| !(self.constraints.no_repeats && *index == last))
+            .map(|index| (index, step_weight(&self.steps[last], &self.steps[index])))
+            .filter(|(_, weight)| *weight > 0.0)
+            .collect();
+        next.sort_by(|a, b| b.1.total_cmp(&a.1));
+        for (index, weight) in next {
+            path.push(index);
+            self.extend(path, score * weight);
+            path.pop();
+        }
+    }
+}
+
+/// Progressions meeting the constraints, most conventional first
+/// Fails when no progression can meet them, so an assignment is never handed out unsolvable
+pub fn compose_progressions(
+    constraints: &ProgressionConstraints,
+    max_results: usize,
+) -> MusicResult<Vec<ComposedProgression>> {
+    let min_length = if constraints.end_cadence.is_some() { 2 } else { 1 };
+    if !(min_length..=MAX_COMPOSITION_LENGTH).contains(&constraints.length) {
+        return Err(MusicError::ParseError(format!(
+            "Length must be between {} and {} chords",
+            min_length, MAX_COMPOSITION_LENGTH
+        )));
+    }
+
+    let (tonic, mode) = NumeralMode::from_key(constraints.key.trim());
+    let steps = vocabulary(mode);
+    let first: Vec<usize> = match constraints.start.as_deref().map(str::trim) {
+        Some(start) => {
+            let index = steps.iter().position(|step| step.numeral == start).ok_or_else(|| {
+                MusicError::ParseError(format!(
+                    "{} is not a diatonic triad or secondary dominant of {}",
+                    start, constraints.key
+                ))
+            })?;
+            vec![index]
+        }
+        None => (0..steps.len()).collect(),
+    };
+    let chords = steps
+        .iter()
+        .map(|step| roman_numeral_to_chord(&step.numeral, tonic, mode, constraints.use_flats))
+        .collect::<MusicResult<Vec<String>>>()?;
+
+    let mut search = Search { constraints, steps, nodes: 0, solutions: Vec::new() };
+    for index in first {
+        search.extend(&mut vec![index], 1.0);
+    }
+    if search.solutions.is_empty() {
+        return Err(MusicError::ParseError("No progression meets these constraints".to_string()));
+    }
+
+    let Search { steps, mut solutions, .. } = search;
+    solutions.sort_by(|a, b| b.1.total_cmp(&a.1));
+    Ok(solutions
+        .into_iter()
+        .take(max_results)
+        .map(|(path, score)| {
+            let cadence = match path.as_slice() {
+                [.., previous, last] => ending_cadence(&steps[*previous], &steps[*last]),
+                _ => None,
+            };
+            ComposedProgression {
+                chords: path.iter().map(|index| chords[*index].clone()).collect(),
+                numerals: path.iter().map(|index| steps[*index].numeral.clone()).collect(),
+                cadence,
+                score,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::analysis::analyze_progression;
+
+    fn constraints(length: usize) -> ProgressionConstraints {
+        ProgressionConstraints { length, ..ProgressionConstraints::default() }
+    }
+
+    #[test]
+    fn test_meets_assignment_constraints() {
+        // Start on I, end with a PAC, exactly one secondary dominant, 8 chords, no repeats
+        let assignment = ProgressionConstraints {
+            start: Some("I".to_string()),
+            end_cadence: Some(Cadence::Authentic),
+            secondary_dominants: Some(1),
+            no_repeats: true,
+            ..constraints(8)
+        };
+        let results = compose_progressions(&assignment, 20).unwrap();
+        assert_eq!(results.len(), 20);
+        assert!(results.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        for result in &results {
+            assert_eq!(result.chords.len(), 8);
+            assert_eq!(result.chords[0], "C");
+            assert_eq!(&result.numerals[6..], ["V", "I"]);
+            assert_eq!(result.cadence, Some(Cadence::Authentic));
+            assert!(result.chords.windows(2).all(|pair| pair[0] != pair[1]));
+
+            // The analyzer reads exactly one applied dominant, resolving to its target
+            let analysis = analyze_progression(&result.chords, "C");
+            let applied: Vec<usize> = (0..8).filter(|i| analysis[*i].applied.is_some()).collect();
+            assert_eq!(applied.len(), 1, "{:?}", result.chords);
+            let target = result.numerals[applied[0]].strip_prefix("V7/").unwrap();
+            assert_eq!(result.numerals[applied[0] + 1], target);
+        }
+    }
+
+    #[test]
+    fn test_minor_keys_and_other_cadences() {
+        let minor = ProgressionConstraints {
+            key: "Am".to_string(),
+            start: Some("i".to_string()),
+            end_cadence: Some(Cadence::Authentic),
+            secondary_dominants: Some(0),
+            ..constraints(4)
+        };
+        let results = compose_progressions(&minor, 5).unwrap();
+        assert_eq!(results[0].chords[0], "Am");
+        assert!(results.iter().all(|result| result.chords[2..] == ["E", "Am"]));
+
+        let half = ProgressionConstraints { end_cadence: Some(Cadence::Half), ..constraints(4) };
+        assert!(compose_progressions(&half, 5).unwrap().iter().all(|result| result.numerals[3] == "V"));
+    }
+
+    #[test]
+    fn test_unsolvable_constraints_are_rejected() {
+        // Three secondary dominants need six chords
+        let crowded = ProgressionConstraints { secondary_dominants: Some(3), ..constraints(5) };
+        assert!(compose_progressions(&crowded, 5).is_err());
+        let unknown = ProgressionConstraints { start: Some("V/vii°".to_string()), ..constraints(4) };
+        assert!(compose_progressions(&unknown, 5).is_err());
+        assert!(compose_progressions(&constraints(MAX_COMPOSITION_LENGTH + 1), 5).is_err());
+        let cadence = ProgressionConstraints { end_cadence: Some(Cadence::Plagal), ..constraints(1) };
+        assert!(compose_progressions(&cadence, 5).is_err());
+    }
+}
@@ -0,0 +1,239 @@
+// Chord explanations
+// Builds short human-readable descriptions of a chord's role in a key for tooltips, lessons and worksheet notes
+
+use serde::Serialize;
+
+use super::analysis::{analyze_progression, Cadence, HarmonicFunction};
+use super::chords::{get_diatonic_chords, get_minor_diatonic_chords, parse_chord};
+use super::completion::common_successors;
+use super::notes::note_index;
+use super::roman::get_display_numeral;
+use super::tiers::{classify_tier, parse_key, relative_pitch_classes, root_degree, KeyRelation, MAJOR_SCALE, MINOR_SCALE};
+use super::types::{MusicResult, NumeralMode};
+
+/// Modes a major key commonly borrows from, closest to major first
+const MAJOR_KEY_SOURCES: [(&str, [u8; 7]); 5] = [
+    ("mixolydian", [0, 2, 4, 5, 7, 9, 10]),
+    ("lydian", [0, 2, 4, 6, 7, 9, 11]),
+    ("dorian", [0, 2, 3, 5, 7, 9, 10]),
+    ("minor", MINOR_SCALE),
+    ("phrygian", [0, 1, 3, 5, 7, 8, 10]),
+];
+
+/// Scales a minor key commonly borrows from, closest to natural minor first
+const MINOR_KEY_SOURCES: [(&str, [u8; 7]); 5] = [
+    ("harmonic minor", [0, 2, 3, 5, 7, 8, 11]),
+    ("melodic minor", [0, 2, 3, 5, 7, 9, 11]),
+    ("dorian", [0, 2, 3, 5, 7, 9, 10]),
+    ("phrygian", [0, 1, 3, 5, 7, 8, 10]),
+    ("major", MAJOR_SCALE),
+];
+
+/// Usual continuations of common borrowed chords in a major key
+/// Entries are (root semitones above the tonic, minor chord, resolutions)
+const BORROWED_RESOLUTIONS: [(u8, bool, &[&str]); 5] = [
+    (10, false, &["IV", "I"]),
+    (8, false, &["bVII", "V"]),
+    (3, false, &["IV", "bVI"]),
+    (1, false, &["V", "I"]),
+    (5, true, &["I", "V"]),
+];
+
+/// Number of likely resolutions mentioned in the text
+const MAX_RESOLUTIONS: usize = 2;
+
+/// Explanation of a chord's role in a key
+#[derive(Debug, Clone, Serialize)]
+pub struct ChordExplanation {
+    pub chord: String,
+    pub numeral: Option<String>,
+    pub function: Option<HarmonicFunction>,
+    pub relation: KeyRelation,
+    /// Mode or scale the chord is borrowed from ("C mixolydian")
+    pub borrowed_from: Option<String>,
+    /// Numeral of the chord this one acts as a secondary dominant of ("ii" for V/ii)
+    pub tonicizes: Option<String>,
+    /// Cadence completed by moving here from the previous chord
+    pub cadence: Option<Cadence>,
+    /// Numerals this chord commonly moves to, most likely first
+    pub resolutions: Vec<String>,
+    /// Full sentence for display
+    pub text: String,
+}
+
+fn function_phrase(function: HarmonicFunction) -> Option<&'static str> {
+    match function {
+        HarmonicFunction::Tonic => Some("a tonic chord"),
+        HarmonicFunction::Predominant => Some("a predominant chord"),
+        HarmonicFunction::Dominant => Some("a dominant chord"),
+        HarmonicFunction::Chromatic => None,
+    }
+}
+
+fn cadence_name(cadence: Cadence) -> &'static str {
+    match cadence {
+        Cadence::Authentic => "an authentic cadence",
+        Cadence::Plagal => "a plagal cadence",
+        Cadence::Deceptive => "a deceptive cadence",
+        Cadence::Half => "a half cadence",
+    }
+}
+
+/// "IV", "IV or I", "ii, IV or vi"
+fn join_alternatives(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [only] => only.clone(),
+        [init @ .., last] => format!("{} or {}", init.join(", "), last),
+    }
+}
+
+/// Major triad or dominant seventh, the shapes that act as secondary dominants
+fn is_dominant_shape(chord: &str) -> bool {
+    let Some(root) = parse_chord(chord).ok().and_then(|p| note_index(&p.root).ok()) else {
+        return false;
+    };
+    let Ok(mut intervals) = relative_pitch_classes(chord, root) else {
+        return false;
+    };
+    intervals.sort_unstable();
+    intervals == [0, 4, 7] || intervals == [0, 4, 7, 10]
+}
+
+/// Explain a chord in a key
+/// Context is the chords placed before it, most recent last, used to spot cadences
+pub fn explain_chord(chord: &str, key: &str, context: &[String]) -> MusicResult<ChordExplanation> {
+    let (tonic_pc, minor) = parse_key(key)?;
+    let tonic = key.strip_suffix('m').unwrap_or(key);
+    let key_name = format!("{} {}", tonic, if minor { "minor" } else { "major" });
+    let scale = if minor { &MINOR_SCALE } else { &MAJOR_SCALE };
+
+    let relation = classify_tier(chord, key, context)?.relation;
+    let pitch_classes = relative_pitch_classes(chord, tonic_pc)?;
+    let root = (note_index(&parse_chord(chord)?.root)? + 12 - tonic_pc) % 12;
+
+    let diatonic = if minor { get_minor_diatonic_chords(tonic, true)? } else { get_diatonic_chords(tonic, true)? };
+    let mode = if minor { NumeralMode::NaturalMinor } else { NumeralMode::Major };
+    let degree_numeral = |degree: u8| get_display_numeral(&diatonic[(degree - 1) as usize], tonic, mode).ok();
+
+    let progression: Vec<String> = context.iter().chain(std::iter::once(&chord.to_string())).cloned().collect();
+    let analysis = analyze_progression(&progression, key).pop();
+    let numeral = analysis.as_ref().and_then(|a| a.numeral.clone());
+    let function = analysis.as_ref().and_then(|a| a.function);
+    // Only arrivals count here; a half cadence needs the phrase to end, which the context can't tell
+    let cadence = analysis.and_then(|a| a.cadence).filter(|c| *c != Cadence::Half);
+
+    // Secondary dominant of a non-tonic, non-diminished diatonic chord
+    let tonicized_degree = if relation == KeyRelation::Diatonic || !is_dominant_shape(chord) {
+        None
+    } else {
+        let target = (root + 5) % 12;
+        scale
+            .iter()
+            .position(|s| *s == target)
+            .map(|i| i as u8 + 1)
+            .filter(|degree| *degree != 1 && !diatonic[(*degree - 1) as usize].contains("dim"))
+    };
+    let tonicizes = tonicized_degree.and_then(degree_numeral);
+
+    let borrowed_from = match (relation, &tonicizes) {
+        (KeyRelation::Diatonic, _) | (_, Some(_)) => None,
+        _ => {
+            let sources = if minor { &MINOR_KEY_SOURCES } else { &MAJOR_KEY_SOURCES };
+            sources
+                .iter()
+                .find(|(_, mode)| pitch_classes.iter().all(|pc| mode.contains(pc)))
+                .map(|(name, _)| format!("{} {}", tonic, name))
+        }
+    };
+
+    let chord_is_minor = parse_chord(chord).is_ok_and(|p| p.suffix.starts_with('m') && !p.suffix.starts_with("maj"));
+    let borrowed_table = BORROWED_RESOLUTIONS
+        .iter()
+        .find(|(semitones, is_minor, _)| !minor && borrowed_from.is_some() && *semitones == root && *is_minor == chord_is_minor)
+        .map(|(_, _, resolutions)| resolutions.iter().map(|r| r.to_string()).collect::<Vec<_>>());
+
+    let resolutions: Vec<String> = match (&tonicizes, borrowed_table) {
+        (Some(target), _) => vec![target.clone()],
+        (None, Some(table)) => table,
+        (None, None) => root_degree(chord, tonic_pc, scale)
+            .filter(|_| relation != KeyRelation::Chromatic)
+            .map(|degree| common_successors(degree).into_iter().filter_map(degree_numeral).collect())
+            .unwrap_or_default(),
+    };
+    let resolutions: Vec<String> = resolutions.into_iter().take(MAX_RESOLUTIONS).collect();
+
+    let numeral_text = numeral.clone().unwrap_or_else(|| "?".to_string());
+    let mut text = match (&tonicizes, &borrowed_from) {
+        (Some(target), _) => format!("{} is V/{} in {}, the dominant of {}", chord, target, key_name, target),
+        (None, Some(source)) => format!("{} is the {}, borrowed from {}", chord, numeral_text, source),
+        (None, None) if relation == KeyRelation::Chromatic => {
+            format!("{} is a chromatic chord ({}) in {}", chord, numeral_text, key_name)
+        }
+        (None, None) => match function.and_then(function_phrase) {
+            Some(phrase) => format!("{} is the {} in {}, {}", chord, numeral_text, key_name, phrase),
+            None => format!("{} is the {} in {}", chord, numeral_text, key_name),
+        },
+    };
+    if let (Some(cadence), Some(previous)) = (cadence, context.last()) {
+        text.push_str(&format!("; after {} it completes {}", previous, cadence_name(cadence)));
+    }
+    if !resolutions.is_empty() {
+        text.push_str(&format!("; it commonly resolves to {}", join_alternatives(&resolutions)));
+    }
+
+    Ok(ChordExplanation {
+        chord: chord.to_string(),
+        numeral,
+        function,
+        relation,
+        borrowed_from,
+        tonicizes,
+        cadence,
+        resolutions,
+        text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn explain(chord: &str, key: &str, context: &[&str]) -> ChordExplanation {
+        let context: Vec<String> = context.iter().map(|c| c.to_string()).collect();
+        explain_chord(chord, key, &context).unwrap()
+    }
+
+    #[test]
+    fn test_borrowed_chord_names_its_source() {
+        let explanation = explain("Bb", "C", &[]);
+        assert_eq!(explanation.borrowed_from.as_deref(), Some("C mixolydian"));
+        assert_eq!(explanation.text, "Bb is the bVII, borrowed from C mixolydian; it commonly resolves to IV or I");
+
+        assert_eq!(explain("Fm", "C", &[]).borrowed_from.as_deref(), Some("C minor"));
+        assert_eq!(explain("E", "Am", &[]).borrowed_from.as_deref(), Some("A harmonic minor"));
+    }
+
+    #[test]
+    fn test_diatonic_chord_describes_function() {
+        let explanation = explain("G7", "C", &["Dm"]);
+        assert_eq!(explanation.function, Some(HarmonicFunction::Dominant));
+        assert_eq!(explanation.text, "G7 is the V7 in C major, a dominant chord; it commonly resolves to I or vi");
+    }
+
+    #[test]
+    fn test_cadence_from_context() {
+        let explanation = explain("C", "C", &["F", "G"]);
+        assert_eq!(explanation.cadence, Some(Cadence::Authentic));
+        assert!(explanation.text.contains("after G it completes an authentic cadence"));
+    }
+
+    #[test]
+    fn test_secondary_dominant() {
+        let explanation = explain("A7", "C", &[]);
+        assert_eq!(explanation.tonicizes.as_deref(), Some("ii"));
+        assert_eq!(explanation.borrowed_from, None);
+        assert_eq!(explanation.resolutions, vec!["ii"]);
+        assert!(explanation.text.starts_with("A7 is V/ii in C major"));
+    }
+}
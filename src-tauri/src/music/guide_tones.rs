@@ -0,0 +1,166 @@
+// Guide tones
+// The 3rd and 7th of each chord, connected into two smooth melodic lines through a progression
+
+use serde::Serialize;
+
+use super::chords::parse_chord;
+use super::intervals::{parse_chord_with_interval_specs, spell_interval_with_degree};
+use super::notes::{note_index, preferred_spelling};
+use super::types::{AudioNote, MusicError, MusicResult};
+use super::voice_leading::{midi_to_note, VoiceRange};
+
+/// Chord degrees tried in order for each guide tone: sus chords use their suspension for the 3rd,
+/// and chords without a 7th use their 6th, or the root
+const THIRD_DEGREES: &[u8] = &[3, 4, 2, 5];
+const SEVENTH_DEGREES: &[u8] = &[7, 6, 1];
+/// The first chord's 3rd is placed nearest G3, with its 7th above
+const START_MIDI: u8 = 55;
+
+/// One guide tone of a line
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GuideTone {
+    pub chord: String,
+    /// Spelled note name in the chord ("Bb", "F#")
+    pub note: String,
+    /// Written octave of the spelled note (Cb4 sounds as B3)
+    pub octave: i8,
+    pub midi: u8,
+    /// Chord degree the note is (3, 7, or a stand-in such as 4 in a sus chord)
+    pub degree: u8,
+}
+
+/// Two guide-tone lines through a progression, one note per chord in each
+#[derive(Debug, Clone, Serialize)]
+pub struct GuideToneLines {
+    pub upper: Vec<GuideTone>,
+    pub lower: Vec<GuideTone>,
+    /// Both lines' notes at each chord, lower first, ready for the audio engine
+    /// Notes held from the previous chord are marked as common tones
+    pub playback: Vec<Vec<AudioNote>>,
+}
+
+/// A chord's guide tone as (pitch class, spelled name, degree)
+type Tone = (u8, String, u8);
+
+/// The 3rd-line and 7th-line tones of a chord, ignoring any slash bass
+fn chord_guide_tones(chord: &str) -> MusicResult<(Tone, Tone)> {
+    let parsed = parse_chord(chord.trim())?;
+    let specs = parse_chord_with_interval_specs(&parsed.suffix)?;
+    let root = note_index(&parsed.root)?;
+
+    let tone = |degrees: &[u8]| -> MusicResult<Tone> {
+        let (semitones, degree) = degrees
+            .iter()
+            .find_map(|degree| specs.iter().find(|(_, spec_degree)| spec_degree == degree))
+            .copied()
+            .ok_or_else(|| MusicError::InvalidChord(chord.to_string()))?;
+        let note = preferred_spelling(&spell_interval_with_degree(&parsed.root, semitones, degree)?);
+        Ok(((root + semitones) % 12, note, degree))
+    };
+    Ok((tone(THIRD_DEGREES)?, tone(SEVENTH_DEGREES)?))
+}
+
+/// The MIDI note with this pitch class nearest to `from`, moving down on a tritone, kept in range
+fn nearest(from: u8, pitch_class: u8, range: &VoiceRange) -> u8 {
+    let up = (pitch_class + 12 - from % 12) % 12;
+    let midi = if up < 6 { from + up } else { from + up - 12 };
+    match midi {
+        midi if midi > range.max_midi => midi - 12,
+        midi if midi < range.min_midi => midi + 12,
+        midi => midi,
+    }
+}
+
+fn guide_tone(chord: &str, (_, note, degree): &Tone, midi: u8) -> GuideTone {
+    let alter: i16 = note.chars().skip(1).map(|c| if c == '#' { 1 } else { -1 }).sum();
+    let octave = ((midi as i16 - alter).div_euclid(12) - 1) as i8;
+    GuideTone { chord: chord.trim().to_string(), note: note.clone(), octave, midi, degree: *degree }
+}
+
+/// Sounding note for playback, named with sharps as the samples are
+fn audio_note(midi: u8, is_common_tone: bool) -> MusicResult<AudioNote> {
+    let name = midi_to_note(midi)?;
+    let split = name.find(|c: char| c.is_ascii_digit() || c == '-').unwrap_or(name.len());
+    Ok(AudioNote { note: name[..split].to_string(), octave: (midi / 12) as i8 - 1, is_common_tone, cents: 0.0 })
+}
+
+/// Extract the 3rd and 7th lines through a progression
+/// Each line moves to the nearer of the next chord's guide tones, so the 3rd of one chord typically
+/// becomes the 7th of the next; lines only cross when every choice would cross them
+pub fn extract_guide_tones(progression: &[String]) -> MusicResult<GuideToneLines> {
+    let range = VoiceRange::default();
+    let (mut upper, mut lower) = (Vec::new(), Vec::new());
+    let mut playback: Vec<Vec<AudioNote>> = Vec::new();
+
+    for chord in progression {
+        let (third, seventh) = chord_guide_tones(chord)?;
+        let (low, high) = match (lower.last(), upper.last()) {
+            (Some(&GuideTone { midi: low, .. }), Some(&GuideTone { midi: high, .. })) => {
+                // Try both assignments of the tones to the lines; prefer not crossing, then least motion
+                let candidates = [(&third, &seventh), (&seventh, &third)].map(|(to_low, to_high)| {
+                    let (next_low, next_high) = (nearest(low, to_low.0, &range), nearest(high, to_high.0, &range));
+                    let motion = next_low.abs_diff(low) + next_high.abs_diff(high);
+                    ((next_low >= next_high, motion), (to_low, next_low), (to_high, next_high))
+                });
+                let [keep, swap] = candidates;
+                let (_, low, high) = if swap.0 < keep.0 { swap } else { keep };
+                (low, high)
+            }
+            _ => {
+                let low = nearest(START_MIDI, third.0, &range);
+                let above = (seventh.0 + 12 - low % 12) % 12;
+                ((&third, low), (&seventh, low + above))
+            }
+        };
+
+        let held = |line: &[GuideTone], midi: u8| line.last().is_some_and(|tone| tone.midi == midi);
+        playback.push(vec![audio_note(low.1, held(&lower, low.1))?, audio_note(high.1, held(&upper, high.1))?]);
+        lower.push(guide_tone(chord, low.0, low.1));
+        upper.push(guide_tone(chord, high.0, high.1));
+    }
+
+    if playback.is_empty() {
+        return Err(MusicError::ParseError("Empty progression".to_string()));
+    }
+    Ok(GuideToneLines { upper, lower, playback })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progression(chords: &[&str]) -> Vec<String> {
+        chords.iter().map(|chord| chord.to_string()).collect()
+    }
+
+    fn notes(line: &[GuideTone]) -> Vec<String> {
+        line.iter().map(|tone| format!("{}{}", tone.note, tone.octave)).collect()
+    }
+
+    #[test]
+    fn test_two_five_one_lines() {
+        let lines = extract_guide_tones(&progression(&["Dm7", "G7", "Cmaj7", "A7"])).unwrap();
+        // Each 7th falls a half step to the next 3rd while the other line holds or steps
+        assert_eq!(notes(&lines.lower), ["F3", "F3", "E3", "G3"]);
+        assert_eq!(notes(&lines.upper), ["C4", "B3", "B3", "C#4"]);
+        assert_eq!(lines.lower.iter().map(|tone| tone.degree).collect::<Vec<_>>(), [3, 7, 3, 7]);
+
+        assert_eq!(lines.playback.len(), 4);
+        assert_eq!((lines.playback[1][0].note.as_str(), lines.playback[1][0].octave), ("F", 3));
+        assert!(lines.playback[1][0].is_common_tone && !lines.playback[1][1].is_common_tone);
+    }
+
+    #[test]
+    fn test_stand_in_tones_and_spelling() {
+        let lines = extract_guide_tones(&progression(&["Csus4", "C", "Abm7/Cb"])).unwrap();
+        assert_eq!(lines.lower[0].degree, 4);
+        // A triad's root stands in for its 7th
+        assert!(lines.lower[1].degree == 1 || lines.upper[1].degree == 1);
+        // Cb is written in the octave above the B it sounds as
+        let cb = lines.lower.iter().chain(&lines.upper).find(|tone| tone.note == "Cb").unwrap();
+        assert_eq!(cb.octave as i16, (cb.midi / 12) as i16);
+
+        assert!(extract_guide_tones(&[]).is_err());
+        assert!(extract_guide_tones(&progression(&["C", "H7"])).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+// Writing habits
+// Which chords, keys and progressions a user reaches for across their saved songs, to encourage exploring others
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::analysis::analyze_progression;
+use super::chords::{normalized_symbol, parse_chord};
+use crate::types::song::Song;
+
+/// Chords in a progression pattern, written as Roman numerals so the same progression in any key counts once
+const PATTERN_LENGTH: usize = 4;
+/// Entries kept in each ranking
+const TOP_COUNT: usize = 10;
+/// A habit is reported once it shows up in this share of projects...
+const HABIT_SHARE: f32 = 0.5;
+/// ...and there are enough projects for the share to mean something
+const MIN_PROJECTS: usize = 3;
+
+/// How often something was used
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Usage {
+    pub name: String,
+    /// Times it appears across all projects (chords and patterns) or sections written in it (keys)
+    pub count: usize,
+    /// Projects using it
+    pub projects: usize,
+    /// Share of all projects using it, 0-1
+    pub project_share: f32,
+}
+
+/// Aggregated usage across a set of projects
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WritingHabits {
+    pub project_count: usize,
+    pub chords: Vec<Usage>,
+    pub keys: Vec<Usage>,
+    /// Numeral patterns of consecutive chords ("vi–IV–I–V")
+    pub progressions: Vec<Usage>,
+    /// Plain-language observations about the strongest habits
+    pub insights: Vec<String>,
+}
+
+/// Running count of one kind of item: total uses, and the projects each item was used in
+#[derive(Default)]
+struct Tally(BTreeMap<String, (usize, BTreeSet<usize>)>);
+
+impl Tally {
+    fn add(&mut self, name: String, project: usize) {
+        let (count, projects) = self.0.entry(name).or_default();
+        *count += 1;
+        projects.insert(project);
+    }
+
+    /// Most-used first, by projects and then total uses
+    fn ranking(self, project_count: usize) -> Vec<Usage> {
+        let mut usage: Vec<Usage> = self
+            .0
+            .into_iter()
+            .map(|(name, (count, projects))| Usage {
+                name,
+                count,
+                projects: projects.len(),
+                project_share: projects.len() as f32 / project_count.max(1) as f32,
+            })
+            .collect();
+        usage.sort_by(|a, b| b.projects.cmp(&a.projects).then(b.count.cmp(&a.count)).then_with(|| a.name.cmp(&b.name)));
+        usage.truncate(TOP_COUNT);
+        usage
+    }
+}
+
+/// Chord symbol in its normalized spelling, so "CΔ" and "Cmaj7" are counted together
+fn chord_name(chord: &str) -> String {
+    parse_chord(chord.trim()).map(|parsed| normalized_symbol(&parsed)).unwrap_or_else(|_| chord.trim().to_string())
+}
+
+fn percent(share: f32) -> u32 {
+    (share * 100.0).round() as u32
+}
+
+fn insights(habits: &WritingHabits) -> Vec<String> {
+    if habits.project_count < MIN_PROJECTS {
+        return Vec::new();
+    }
+    let mut insights = Vec::new();
+    if let Some(pattern) = habits.progressions.first().filter(|usage| usage.project_share >= HABIT_SHARE) {
+        insights.push(format!("You've used {} in {}% of projects", pattern.name, percent(pattern.project_share)));
+    }
+    if let Some(key) = habits.keys.first().filter(|usage| usage.project_share >= HABIT_SHARE) {
+        insights.push(format!("{}% of your projects have a section in {}", percent(key.project_share), key.name));
+    }
+    if let Some(chord) = habits.chords.first().filter(|usage| usage.project_share >= HABIT_SHARE) {
+        insights.push(format!("{} appears in {}% of projects", chord.name, percent(chord.project_share)));
+    }
+    insights
+}
+
+/// Count chords, keys and numeral patterns across projects
+/// Patterns are counted within each section, wherever every chord of the window has a numeral in the section's key
+pub fn writing_habits(projects: &[Song]) -> WritingHabits {
+    let (mut chords, mut keys, mut progressions) = (Tally::default(), Tally::default(), Tally::default());
+    for (project, song) in projects.iter().enumerate() {
+        for section in &song.sections {
+            keys.add(section.key.trim().to_string(), project);
+            let names: Vec<String> = section.chords.iter().map(|chord| chord_name(&chord.chord)).collect();
+            for name in &names {
+                chords.add(name.clone(), project);
+            }
+
+            let numerals: Vec<Option<String>> =
+                analyze_progression(&names, &section.key).into_iter().map(|analysis| analysis.numeral).collect();
+            for window in numerals.windows(PATTERN_LENGTH) {
+                if let Some(pattern) = window.iter().cloned().collect::<Option<Vec<String>>>() {
+                    progressions.add(pattern.join("–"), project);
+                }
+            }
+        }
+    }
+
+    let project_count = projects.len();
+    let mut habits = WritingHabits {
+        project_count,
+        chords: chords.ranking(project_count),
+        keys: keys.ranking(project_count),
+        progressions: progressions.ranking(project_count),
+        insights: Vec::new(),
+    };
+    habits.insights = insights(&habits);
+    habits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::song::{SectionKind, SongChord, SongSection};
+
+    fn song(sections: &[(&str, &[&str])]) -> Song {
+        let sections = sections
+            .iter()
+            .map(|(key, chords)| SongSection {
+                id: String::new(),
+                kind: SectionKind::Verse,
+                label: None,
+                key: key.to_string(),
+                tempo: 100.0,
+                rehearsal_mark: None,
+                chords: chords.iter().map(|chord| SongChord { chord: chord.to_string(), beats: 4.0 }).collect(),
+                markers: Vec::new(),
+                changes: Vec::new(),
+                show_analysis: false,
+            })
+            .collect();
+        Song { title: String::new(), time_signature: "4/4".to_string(), sections }
+    }
+
+    #[test]
+    fn test_writing_habits() {
+        let projects = vec![
+            song(&[("C", &["Am", "F", "C", "G"])]),
+            song(&[("G", &["Em", "C", "G", "D", "Em"]), ("G", &["C", "D"])]),
+            song(&[("D", &["Bm", "G", "D", "A"])]),
+            song(&[("C", &["C", "Dm7", "G7", "CΔ"])]),
+        ];
+        let habits = writing_habits(&projects);
+        assert_eq!(habits.project_count, 4);
+
+        // The same numerals in three keys are one habit; a longer section counts every window
+        let top = &habits.progressions[0];
+        assert_eq!((top.name.as_str(), top.projects, top.count), ("vi–IV–I–V", 3, 3));
+        assert!(habits.progressions.iter().any(|usage| usage.name == "I–ii7–V7–Imaj7"));
+        assert_eq!(habits.insights[0], "You've used vi–IV–I–V in 75% of projects");
+
+        let c = habits.chords.iter().find(|usage| usage.name == "C").unwrap();
+        assert_eq!((c.count, c.projects), (4, 3));
+        assert!(habits.chords.iter().any(|usage| usage.name == "Cmaj7"));
+        assert_eq!(habits.keys[0].name, "C");
+        assert_eq!(habits.keys.iter().find(|usage| usage.name == "G").unwrap().count, 2);
+    }
+
+    #[test]
+    fn test_no_insights_from_few_projects() {
+        let habits = writing_habits(&[song(&[("C", &["C", "G", "Am", "F"])])]);
+        assert_eq!(habits.progressions[0].project_share, 1.0);
+        assert!(habits.insights.is_empty());
+        assert!(writing_habits(&[]).chords.is_empty());
+    }
+}
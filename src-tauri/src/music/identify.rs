@@ -0,0 +1,189 @@
+// Chord identification
+// Names chords from the notes that sound, for MIDI input, performance capture and "name this chord" exercises
+
+use serde::Serialize;
+
+use super::chords::{ChordSymbol, SymbolStyle};
+use super::intervals::CHORD_INTERVAL_SPECS;
+use super::notes::{get_preferred_note_name, note_index};
+
+/// Distinct pitch classes needed before a group of simultaneous notes is named as a chord
+const MIN_CHORD_PITCH_CLASSES: usize = 3;
+
+/// Suffixes never offered when naming notes: each reads better as an inversion ("Em#5" is C/E)
+const UNNAMED_SUFFIXES: [&str; 2] = ["m#5", "m7#5"];
+
+/// How the bass note relates to a candidate chord
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChordMatchKind {
+    /// The bass is the root
+    RootPosition,
+    /// The bass is another chord tone
+    Inversion,
+    /// The bass is not a chord tone; the notes above it form the chord
+    Slash,
+}
+
+/// One way of naming a set of notes, best first in notes_to_chord_candidates
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChordMatch {
+    /// Full symbol, slashed when the bass is not the root ("C/E", "C/D")
+    pub symbol: String,
+    pub root: String,
+    /// Quality suffix as in CHORD_INTERVAL_SPECS ("", "m7", "7b9")
+    pub quality: String,
+    pub bass: String,
+    pub kind: ChordMatchKind,
+    /// Chord tone in the bass: 0 root, 1 third, 2 fifth, 3 seventh, ...
+    pub inversion: u8,
+}
+
+/// Preference among suffix spellings: plain-letter suffixes ("m" over "-"), then the shortest
+fn suffix_rank(suffix: &str) -> (bool, usize, &str) {
+    (!suffix.chars().all(|c| c.is_ascii_alphanumeric()), suffix.len(), suffix)
+}
+
+/// Pitch-class spelling of a note as given, ignoring any octave number ("Eb4" → "Eb")
+fn spelled_pitch(note: &str) -> Option<(String, u8)> {
+    let name = note.trim().trim_end_matches(|c: char| c.is_ascii_digit() || c == '-');
+    let mut chars = name.chars();
+    let letter = chars.next()?.to_ascii_uppercase();
+    let name = format!("{}{}", letter, chars.as_str());
+    let pitch_class = note_index(&name).ok()?;
+    Some((name, pitch_class))
+}
+
+/// All chord names for a set of notes, best first
+/// The first note is the bass; each root appears once, with its simplest quality spelling
+pub fn notes_to_chord_candidates(notes: &[String]) -> Vec<ChordMatch> {
+    let Some(spelled) = notes.iter().map(|n| spelled_pitch(n)).collect::<Option<Vec<_>>>() else {
+        return Vec::new();
+    };
+    let Some((bass_name, bass)) = spelled.first().cloned() else {
+        return Vec::new();
+    };
+
+    // Pitch classes in ascending order, each spelled as it first appears
+    let mut pitch_classes: Vec<(u8, String)> = Vec::new();
+    for (name, pitch_class) in &spelled {
+        if !pitch_classes.iter().any(|(pc, _)| pc == pitch_class) {
+            pitch_classes.push((*pitch_class, name.clone()));
+        }
+    }
+    pitch_classes.sort_by_key(|(pc, _)| *pc);
+    let above_bass: Vec<(u8, String)> =
+        pitch_classes.iter().filter(|(pc, _)| *pc != bass).cloned().collect();
+
+    let mut candidates: Vec<ChordMatch> = Vec::new();
+    for (tones, slash) in [(&pitch_classes, false), (&above_bass, true)] {
+        for (root, root_name) in tones.iter() {
+            let mut relative: Vec<u8> = tones.iter().map(|(pc, _)| (pc + 12 - root) % 12).collect();
+            relative.sort_unstable();
+
+            // Every suffix matching one root spells the same chord; keep the preferred one
+            let mut best: Option<(&str, u8)> = None;
+            let named = CHORD_INTERVAL_SPECS.iter().filter(|(suffix, _)| !UNNAMED_SUFFIXES.contains(suffix));
+            for (suffix, specs) in named {
+                let mut template: Vec<u8> = specs.iter().map(|(semitones, _)| semitones % 12).collect();
+                template.sort_unstable();
+                template.dedup();
+                if template != relative {
+                    continue;
+                }
+                let bass_interval = (bass + 12 - root) % 12;
+                let inversion = specs
+                    .iter()
+                    .position(|(semitones, _)| semitones % 12 == bass_interval)
+                    .unwrap_or(0) as u8;
+                if best.is_none_or(|(best_suffix, _)| suffix_rank(suffix) < suffix_rank(best_suffix)) {
+                    best = Some((suffix, inversion));
+                }
+            }
+
+            if let Some((suffix, inversion)) = best {
+                let kind = match (slash, *root == bass) {
+                    (true, _) => ChordMatchKind::Slash,
+                    (false, true) => ChordMatchKind::RootPosition,
+                    (false, false) => ChordMatchKind::Inversion,
+                };
+                // Root position chords are named without their bass
+                let symbol = ChordSymbol::new(root_name.clone(), suffix).over(bass_name.clone());
+                candidates.push(ChordMatch {
+                    symbol: symbol.to_string(SymbolStyle { omit_root_bass: true, ..SymbolStyle::default() }),
+                    root: root_name.clone(),
+                    quality: suffix.to_string(),
+                    bass: bass_name.clone(),
+                    kind,
+                    inversion: if slash { 0 } else { inversion },
+                });
+            }
+        }
+    }
+
+    // Root position before inversions before slash chords; the stable sort keeps
+    // roots in ascending pitch order within equal ranks
+    candidates.sort_by(|a, b| (a.kind, suffix_rank(&a.quality)).cmp(&(b.kind, suffix_rank(&b.quality))));
+    candidates
+}
+
+/// Name a group of simultaneous MIDI notes as a chord symbol (slash chord when inverted)
+/// Notes are spelled for the key and the first note is taken as the bass
+pub fn name_midi_chord(midis: &[u8], key: &str) -> Option<String> {
+    let mut pitch_classes: Vec<u8> = midis.iter().map(|m| m % 12).collect();
+    pitch_classes.sort_unstable();
+    pitch_classes.dedup();
+    if pitch_classes.len() < MIN_CHORD_PITCH_CLASSES {
+        return None;
+    }
+
+    let notes: Vec<String> =
+        midis.iter().map(|m| get_preferred_note_name(m % 12, key, false).to_string()).collect();
+    notes_to_chord_candidates(&notes).into_iter().next().map(|m| m.symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notes(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_name_midi_chord() {
+        assert_eq!(name_midi_chord(&[60, 64, 67], "C").as_deref(), Some("C"));
+        assert_eq!(name_midi_chord(&[57, 60, 64], "C").as_deref(), Some("Am"));
+        assert_eq!(name_midi_chord(&[64, 67, 72], "C").as_deref(), Some("C/E"));
+        assert_eq!(name_midi_chord(&[58, 62, 65, 68], "F").as_deref(), Some("Bb7"));
+        assert_eq!(name_midi_chord(&[60, 64], "C"), None);
+    }
+
+    #[test]
+    fn test_candidates_rank_inversions() {
+        let candidates = notes_to_chord_candidates(&notes(&["C", "E", "G", "A"]));
+        let symbols: Vec<&str> = candidates.iter().map(|m| m.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["C6", "Am7/C"]);
+        assert_eq!(candidates[1].kind, ChordMatchKind::Inversion);
+        assert_eq!(candidates[1].inversion, 1);
+
+        let first = &notes_to_chord_candidates(&notes(&["G4", "B4", "D5", "F5"]))[0];
+        assert_eq!((first.symbol.as_str(), first.kind), ("G7", ChordMatchKind::RootPosition));
+        let third = &notes_to_chord_candidates(&notes(&["F", "G", "B", "D"]))[0];
+        assert_eq!((third.symbol.as_str(), third.inversion), ("G7/F", 3));
+    }
+
+    #[test]
+    fn test_candidates_keep_spelling_and_slash_bass() {
+        let first = &notes_to_chord_candidates(&notes(&["c#", "E#", "G#"]))[0];
+        assert_eq!(first.symbol, "C#");
+
+        let candidates = notes_to_chord_candidates(&notes(&["D", "C", "E", "G"]));
+        assert_eq!(candidates[0].symbol, "Cadd2/D");
+        let slash = candidates.iter().find(|m| m.kind == ChordMatchKind::Slash).unwrap();
+        assert_eq!(slash.symbol, "C/D");
+
+        assert!(notes_to_chord_candidates(&notes(&["C", "D"])).is_empty());
+        assert!(notes_to_chord_candidates(&notes(&["C", "H", "G"])).is_empty());
+    }
+}
@@ -18,8 +18,9 @@ use std::collections::HashMap;
 use std::sync::LazyLock;
 
 use super::notes::{note_index, get_preferred_note_name};
-use super::chords::parse_chord;
+use super::chords::{parse_chord, ChordSymbol, SymbolStyle};
 use super::types::{MusicError, MusicResult};
+use super::aliases::{self, AliasDictionary, ALIAS_DICTIONARY_VERSION};
 
 /// Static lookup table for suffix normalization (case-insensitive keys)
 static SUFFIX_MAPPINGS: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
@@ -45,6 +46,15 @@ static SUFFIX_MAPPINGS: LazyLock<HashMap<&'static str, &'static str>> = LazyLock
         ("om", "dim"),
         // Spanish notation
         ("mi", "m"),
+        ("mi6", "m6"),
+        ("mi7", "m7"),
+        ("mi9", "m9"),
+        ("mi7b5", "m7b5"),
+        // Dash as minor
+        ("-", "m"),
+        ("-6", "m6"),
+        ("-7", "m7"),
+        ("-7b5", "m7b5"),
         // Reversed sus notation
         ("sus7", "7sus4"),
         ("sus9", "9sus4"),
@@ -97,21 +107,41 @@ static VALID_SUFFIXES: &[&str] = &[
     "7no5",
 ];
 
-/// Check if suffix is valid
-fn is_valid_suffix(suffix: &str) -> bool {
+/// Check if suffix is one of the built-in valid suffixes
+pub fn is_builtin_suffix(suffix: &str) -> bool {
     VALID_SUFFIXES.contains(&suffix)
 }
 
+/// Check if suffix is valid (built-in or added by a user alias dictionary)
+pub fn is_valid_suffix(suffix: &str) -> bool {
+    is_builtin_suffix(suffix) || aliases::is_user_suffix(suffix)
+}
+
+/// Built-in normalization tables as an alias dictionary (for export)
+pub fn builtin_alias_dictionary() -> AliasDictionary {
+    AliasDictionary {
+        version: ALIAS_DICTIONARY_VERSION,
+        aliases: SUFFIX_MAPPINGS
+            .iter()
+            .map(|(alias, target)| (alias.to_string(), target.to_string()))
+            .collect(),
+        valid_suffixes: VALID_SUFFIXES.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
 /// Normalize chord suffix to standard form (matches TypeScript normalizeSuffix)
-fn normalize_suffix(suffix: &str) -> String {
+pub fn normalize_suffix(suffix: &str) -> String {
     // Step 1: Strip parentheses
     let s = suffix.replace(['(', ')'], "");
     
     // Step 2: Apply hyphen-flat notation replacements
     let s = s.replace("-5", "b5").replace("-9", "b9");
     
-    // Step 3: Lookup in mapping table (case-insensitive)
+    // Step 3: Lookup in user aliases, then the built-in mapping table (case-insensitive)
     let lower = s.to_lowercase();
+    if let Some(normalized) = aliases::resolve_user_alias(&lower) {
+        return normalized;
+    }
     if let Some(&normalized) = SUFFIX_MAPPINGS.get(lower.as_str()) {
         return normalized.to_string();
     }
@@ -251,11 +281,7 @@ pub fn semitone_to_note_in_key(semitone: u8, key: &str, use_flats: bool) -> Stri
 /// root="F", suffix="", bass_note="A" -> "F/A"
 /// root="C", suffix="m7", bass_note="G" -> "Cm7/G"
 pub fn build_slash_chord(root: &str, suffix: &str, bass_note: &str) -> String {
-    if suffix.is_empty() {
-        format!("{}/{}", root, bass_note)
-    } else {
-        format!("{}{}/{}", root, suffix, bass_note)
-    }
+    ChordSymbol::new(root, suffix).over(bass_note).to_string(SymbolStyle::default())
 }
 
 /// Convert interval recommendation back to absolute chord
@@ -290,19 +316,16 @@ pub fn interval_to_chord(interval: u8, quality: &str, from_root: u8, use_flats:
     let suffix = if main_quality == "M" { String::new() } else { main_quality };
 
     // Step 5: Handle bass note if present
+    let mut symbol = ChordSymbol::new(root_note, suffix);
     if let Some(bass_int) = bass_interval {
         // Calculate bass note semitone relative to the NEW chord root
         let bass_semitone = calculate_bass_semitone(new_root_semitone, bass_int);
 
         // Get the exact bass note name with proper spelling
-        let bass_note = semitone_to_note_in_key(bass_semitone, key, use_flats);
-
-        // Build the slash chord
-        Ok(build_slash_chord(&root_note, &suffix, &bass_note))
-    } else {
-        // No bass note - just root + suffix
-        Ok(format!("{}{}", root_note, suffix))
+        symbol = symbol.over(semitone_to_note_in_key(bass_semitone, key, use_flats));
     }
+
+    Ok(symbol.to_string(SymbolStyle::default()))
 }
 
 /// Get a display-friendly representation of an interval recommendation
@@ -679,4 +702,25 @@ mod tests {
             assert_eq!(qualities.len(), prog.len(), "Should have {} qualities for {:?}", prog.len(), prog);
         }
     }
+
+    #[test]
+    fn test_user_aliases_extend_normalization() {
+        let mut dictionary = AliasDictionary::default();
+        dictionary.aliases.insert("zzmaj".to_string(), "maj7".to_string());
+        dictionary.aliases.insert("zzsix".to_string(), "69zz".to_string());
+        dictionary.valid_suffixes.push("69zz".to_string());
+        aliases::set_user_aliases(dictionary);
+
+        assert_eq!(parse_chord_for_interval("CZZmaj").unwrap(), (0, "maj7".to_string()));
+        assert_eq!(parse_chord_for_interval("Czzsix").unwrap(), (0, "69zz".to_string()));
+
+        aliases::set_user_aliases(AliasDictionary::default());
+    }
+
+    #[test]
+    fn test_builtin_alias_dictionary_exports_tables() {
+        let dictionary = builtin_alias_dictionary();
+        assert_eq!(dictionary.aliases.get("min7").map(String::as_str), Some("m7"));
+        assert!(dictionary.valid_suffixes.contains(&"maj7".to_string()));
+    }
 }
@@ -1,6 +1,7 @@
 // Chord quality to interval mappings and chord decomposition
 // This module handles converting chord suffixes to note intervals
 
+use super::interval_encoding::normalize_suffix;
 use super::types::{MusicError, MusicResult};
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
@@ -36,6 +37,11 @@ pub static CHORD_INTERVAL_SPECS: Lazy<HashMap<&'static str, Vec<IntervalSpec>>>
     // Augmented - 1, M3, A5
     m.insert("aug", vec![(0,1), (4,3), (8,5)]);
     m.insert("+", vec![(0,1), (4,3), (8,5)]);
+    m.insert("#5", vec![(0,1), (4,3), (8,5)]);
+    // Minor augmented - 1, m3, A5
+    m.insert("m#5", vec![(0,1), (3,3), (8,5)]);
+    // Flat five - 1, M3, d5
+    m.insert("b5", vec![(0,1), (4,3), (6,5)]);
 
     // Suspended - 1, M2/P4, P5
     m.insert("sus2", vec![(0,1), (2,2), (7,5)]);
@@ -74,6 +80,8 @@ pub static CHORD_INTERVAL_SPECS: Lazy<HashMap<&'static str, Vec<IntervalSpec>>>
     m.insert("7b5", vec![(0,1), (4,3), (6,5), (10,7)]);
     // 7#5 - 1, M3, A5, m7
     m.insert("7#5", vec![(0,1), (4,3), (8,5), (10,7)]);
+    // m7#5 - 1, m3, A5, m7
+    m.insert("m7#5", vec![(0,1), (3,3), (8,5), (10,7)]);
     // maj7b5 - 1, M3, d5, M7
     m.insert("maj7b5", vec![(0,1), (4,3), (6,5), (11,7)]);
     // maj7#5 - 1, M3, A5, M7
@@ -92,6 +100,8 @@ pub static CHORD_INTERVAL_SPECS: Lazy<HashMap<&'static str, Vec<IntervalSpec>>>
     // Extended chords with altered 11ths/13ths
     // maj7#11 - 1, M3, P5, M7, #11 (degree 4)
     m.insert("maj7#11", vec![(0,1), (4,3), (7,5), (11,7), (18,4)]);
+    // 7#11 - 1, M3, P5, m7, #11 (degree 4)
+    m.insert("7#11", vec![(0,1), (4,3), (7,5), (10,7), (18,4)]);
     // 7b13 - 1, M3, P5, m7, b13 (degree 6)
     m.insert("7b13", vec![(0,1), (4,3), (7,5), (10,7), (20,6)]);
 
@@ -212,7 +222,16 @@ pub const DEFAULT_CHORD_INTERVALS: &[u8] = &[0, 4, 7];
 pub const DEFAULT_CHORD_INTERVAL_SPECS: &[IntervalSpec] = &[(0,1), (4,3), (7,5)];
 
 /// Parse chord suffix to get interval specifications with explicit scale degrees
+/// Alternative spellings ("mi7", "-", "m7-5") are read through the suffix normalization;
+/// a suffix that is still unrecognized is an error rather than a major triad
 pub fn parse_chord_with_interval_specs(suffix: &str) -> MusicResult<Vec<IntervalSpec>> {
+    if let Some(specs) = CHORD_INTERVAL_SPECS.get(suffix) {
+        return Ok(specs.clone());
+    }
+    if let Some(specs) = CHORD_INTERVAL_SPECS.get(normalize_suffix(suffix).as_str()) {
+        return Ok(specs.clone());
+    }
+
     // Remove common separators
     let cleaned = suffix.replace(['-', '_', ' '], "");
     
@@ -236,8 +255,7 @@ pub fn parse_chord_with_interval_specs(suffix: &str) -> MusicResult<Vec<Interval
         return Ok(specs.clone());
     }
     
-    // Return default major triad if not found
-    Ok(DEFAULT_CHORD_INTERVAL_SPECS.to_vec())
+    Err(MusicError::InvalidChord(format!("Unknown chord quality: {}", suffix)))
 }
 
 /// Legacy function - Parse chord with intervals to get interval pattern (semitones only)
@@ -379,6 +397,7 @@ pub fn intervals_to_notes(root: &str, intervals: &[u8]) -> MusicResult<Vec<Strin
 /// Example: "C/E" → ["E", "C", "E", "G"] (bass note prepended)
 pub fn chord_to_notes(chord: &str) -> MusicResult<Vec<String>> {
     use super::chords::parse_chord;
+    use super::notes::preferred_spelling;
 
     let parsed = parse_chord(chord)?;
     if parsed.root.is_empty() {
@@ -388,8 +407,11 @@ pub fn chord_to_notes(chord: &str) -> MusicResult<Vec<String>> {
     // Get interval specifications with explicit degrees
     let specs = parse_chord_with_interval_specs(&parsed.suffix)?;
 
-    // Convert interval specs to note names with correct spelling
-    let mut notes = interval_specs_to_notes(&parsed.root, &specs)?;
+    // Convert interval specs to note names with correct spelling, simplified if the user prefers
+    let mut notes: Vec<String> = interval_specs_to_notes(&parsed.root, &specs)?
+        .iter()
+        .map(|note| preferred_spelling(note))
+        .collect();
 
     // Prepend bass note for slash chords (e.g., "C/E" puts E first)
     if let Some(bass) = parsed.bass {
@@ -658,7 +680,12 @@ mod tests {
         assert_eq!(parse_chord_with_intervals("major").unwrap(), vec![0, 4, 7]);
         assert_eq!(parse_chord_with_intervals("minor").unwrap(), vec![0, 3, 7]);
         
-        // Test unknown chord falls back to major triad
-        assert_eq!(parse_chord_with_intervals("unknown").unwrap(), vec![0, 4, 7]);
+        assert_eq!(parse_chord_with_intervals("mi7").unwrap(), vec![0, 3, 7, 10]);
+        assert_eq!(parse_chord_with_intervals("-").unwrap(), vec![0, 3, 7]);
+        assert_eq!(parse_chord_with_intervals("m7-5").unwrap(), vec![0, 3, 6, 10]);
+
+        // Unknown qualities are rejected rather than played as a major triad
+        assert!(parse_chord_with_intervals("unknown").is_err());
+        assert!(parse_chord_with_intervals("es7").is_err());
     }
 }
@@ -0,0 +1,193 @@
+// Key detection
+// Ranks the 24 major and minor keys for a pasted progression so the app can suggest a key
+// and choose sharp or flat spellings for display
+
+use serde::Serialize;
+
+use super::chords::parse_chord;
+use super::intervals::chord_to_notes;
+use super::notes::note_index;
+
+/// Krumhansl-Kessler probe-tone profiles, tonic first
+const MAJOR_PROFILE: [f32; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR_PROFILE: [f32; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+/// Tonic names by pitch class, using the spelling with the fewest accidentals
+/// Enharmonic ties (F#/Gb major, D#/Eb minor) follow the progression's own spelling
+const MAJOR_TONICS: [&str; 12] = ["C", "Db", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B"];
+const MINOR_TONICS: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "Bb", "B"];
+
+/// Flat-signature tonics among the table spellings above
+const FLAT_MAJOR_TONICS: [&str; 6] = ["F", "Bb", "Eb", "Ab", "Db", "Gb"];
+const FLAT_MINOR_TONICS: [&str; 6] = ["D", "G", "C", "F", "Bb", "Eb"];
+
+/// Extra weight of a chord's root over its other tones
+const ROOT_WEIGHT: f32 = 1.0;
+/// Score bonus when the progression ends, or starts, on the key's tonic chord
+const FINAL_TONIC_BONUS: f32 = 0.15;
+const OPENING_TONIC_BONUS: f32 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyMode {
+    Major,
+    Minor,
+}
+
+/// One possible key for a progression, best first in detect_key
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeyCandidate {
+    /// Key name as used across the app ("Eb", "F#m")
+    pub key: String,
+    pub tonic: String,
+    pub mode: KeyMode,
+    /// Profile correlation plus tonic bonuses; higher is more likely
+    pub score: f32,
+    /// Whether notes in this key should be spelled with flats
+    pub use_flats: bool,
+}
+
+/// Pearson correlation of a pitch-class histogram with a profile rotated to a tonic
+fn correlation(histogram: &[f32; 12], profile: &[f32; 12], tonic: usize) -> f32 {
+    let rotated: Vec<f32> = (0..12).map(|pc| profile[(pc + 12 - tonic) % 12]).collect();
+    let mean_h = histogram.iter().sum::<f32>() / 12.0;
+    let mean_p = rotated.iter().sum::<f32>() / 12.0;
+
+    let (mut covariance, mut var_h, mut var_p) = (0.0, 0.0, 0.0);
+    for (h, p) in histogram.iter().zip(&rotated) {
+        covariance += (h - mean_h) * (p - mean_p);
+        var_h += (h - mean_h).powi(2);
+        var_p += (p - mean_p).powi(2);
+    }
+    if var_h == 0.0 {
+        return 0.0;
+    }
+    covariance / (var_h * var_p).sqrt()
+}
+
+/// Whether a chord is the tonic chord of a key: same root and same third
+fn is_tonic_chord(root: u8, minor: bool, tonic: usize, mode: KeyMode) -> bool {
+    root as usize == tonic && minor == (mode == KeyMode::Minor)
+}
+
+/// Rank all 24 major and minor keys for a progression, most likely first
+/// Chords that cannot be parsed are skipped; an empty or unreadable progression has no candidates
+pub fn detect_key(chords: &[String]) -> Vec<KeyCandidate> {
+    let mut histogram = [0.0f32; 12];
+    // (root pitch class, minor third) of each readable chord
+    let mut tonalities = Vec::new();
+    let mut flats = 0;
+    let mut sharps = 0;
+
+    for chord in chords {
+        let (Ok(parsed), Ok(notes)) = (parse_chord(chord.trim()), chord_to_notes(chord.trim())) else {
+            continue;
+        };
+        let Ok(root) = note_index(&parsed.root) else {
+            continue;
+        };
+        for pc in notes.iter().filter_map(|note| note_index(note).ok()) {
+            histogram[pc as usize] += 1.0;
+        }
+        histogram[root as usize] += ROOT_WEIGHT;
+
+        flats += parsed.root.matches('b').count();
+        sharps += parsed.root.matches('#').count();
+        let minor = parsed.suffix.starts_with('m') && !parsed.suffix.starts_with("maj");
+        tonalities.push((root, minor));
+    }
+    if tonalities.is_empty() {
+        return Vec::new();
+    }
+    let prefers_flats = flats > sharps;
+
+    let mut candidates = Vec::with_capacity(24);
+    for mode in [KeyMode::Major, KeyMode::Minor] {
+        let (profile, tonics, flat_tonics) = match mode {
+            KeyMode::Major => (&MAJOR_PROFILE, &MAJOR_TONICS, &FLAT_MAJOR_TONICS[..]),
+            KeyMode::Minor => (&MINOR_PROFILE, &MINOR_TONICS, &FLAT_MINOR_TONICS[..]),
+        };
+        for (tonic, table_name) in tonics.iter().enumerate() {
+            let tonic_name = match (*table_name, prefers_flats) {
+                ("F#", true) if mode == KeyMode::Major => "Gb",
+                ("D#", true) => "Eb",
+                (name, _) => name,
+            };
+
+            // C major and A minor have no signature, so they keep the progression's own preference
+            let neutral = tonic_name == if mode == KeyMode::Major { "C" } else { "A" };
+            let use_flats = flat_tonics.contains(&tonic_name) || (neutral && prefers_flats);
+
+            let mut score = correlation(&histogram, profile, tonic);
+            let &(last_root, last_minor) = tonalities.last().expect("progression has chords");
+            if is_tonic_chord(last_root, last_minor, tonic, mode) {
+                score += FINAL_TONIC_BONUS;
+            }
+            let (first_root, first_minor) = tonalities[0];
+            if is_tonic_chord(first_root, first_minor, tonic, mode) {
+                score += OPENING_TONIC_BONUS;
+            }
+
+            candidates.push(KeyCandidate {
+                key: match mode {
+                    KeyMode::Major => tonic_name.to_string(),
+                    KeyMode::Minor => format!("{}m", tonic_name),
+                },
+                tonic: tonic_name.to_string(),
+                mode,
+                score,
+                use_flats,
+            });
+        }
+    }
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progression(chords: &[&str]) -> Vec<String> {
+        chords.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_detects_major_and_minor_keys() {
+        let keys = detect_key(&progression(&["C", "F", "G7", "C"]));
+        assert_eq!(keys.len(), 24);
+        assert_eq!(keys[0].key, "C");
+
+        let keys = detect_key(&progression(&["Am", "Dm", "E7", "Am"]));
+        assert_eq!(keys[0].key, "Am");
+        assert_eq!(keys[0].mode, KeyMode::Minor);
+
+        let keys = detect_key(&progression(&["Bb", "Eb", "F7", "Bb"]));
+        assert_eq!(keys[0].key, "Bb");
+        assert!(keys[0].use_flats);
+
+        let keys = detect_key(&progression(&["E", "A", "B7", "E"]));
+        assert_eq!(keys[0].key, "E");
+        assert!(!keys[0].use_flats);
+    }
+
+    #[test]
+    fn test_relative_keys_follow_the_cadence() {
+        // Same chords, different resolution
+        assert_eq!(detect_key(&progression(&["C", "Am", "Dm", "G", "C"]))[0].key, "C");
+        assert_eq!(detect_key(&progression(&["Am", "C", "Dm", "E", "Am"]))[0].key, "Am");
+    }
+
+    #[test]
+    fn test_enharmonic_tonics_follow_input_spelling() {
+        assert_eq!(detect_key(&progression(&["Gb", "Cb", "Db7", "Gb"]))[0].key, "Gb");
+        assert_eq!(detect_key(&progression(&["F#", "B", "C#7", "F#"]))[0].key, "F#");
+    }
+
+    #[test]
+    fn test_unreadable_progression() {
+        assert!(detect_key(&[]).is_empty());
+        assert!(detect_key(&progression(&["H7", ""])).is_empty());
+    }
+}
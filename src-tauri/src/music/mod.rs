@@ -5,6 +5,29 @@ pub mod intervals;
 pub mod interval_encoding;
 pub mod roman;
 pub mod voice_leading;
+pub mod playability;
+pub mod chord_correction;
+pub mod analysis;
+pub mod aliases;
+pub mod completion;
+pub mod tiers;
+pub mod explanation;
+pub mod comparison;
+pub mod scales;
+pub mod identify;
+pub mod key_detection;
+pub mod song;
+pub mod tempo;
+pub mod progression_diff;
+pub mod habits;
+pub mod guide_tones;
+pub mod voice_motion;
+pub mod voice_leading_analysis;
+pub mod progression_stats;
+#[cfg(feature = "storage")]
+pub mod progression_db;
+pub mod recommendations;
+pub mod composition;
 
 // Re-export commonly used items
 pub use types::*;
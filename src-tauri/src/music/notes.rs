@@ -12,8 +12,12 @@ pub enum KeyType {
     Neutral,
 }
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use once_cell::sync::Lazy;
 
+/// Whether generated spellings are simplified (strict pedagogical spelling is off)
+static SIMPLIFY_SPELLINGS: AtomicBool = AtomicBool::new(false);
+
 /// Chromatic scale with sharps
 pub const CHROMATIC: [&str; 12] = [
     "C", "C#", "D", "D#", "E", "F",
@@ -127,10 +131,81 @@ pub fn get_preferred_note_name(
     }
 }
 
+/// Turn enharmonic simplification of generated spellings on or off
+pub fn set_simplify_spellings(enabled: bool) {
+    SIMPLIFY_SPELLINGS.store(enabled, Ordering::Relaxed);
+}
+
+/// Check whether generated spellings should be simplified
+pub fn simplifies_spellings() -> bool {
+    SIMPLIFY_SPELLINGS.load(Ordering::Relaxed)
+}
+
+/// Simplest enharmonic equivalent of an impractical spelling (B#, E#, Cb, Fb, double or
+/// triple accidentals), keeping the direction of the original accidental
+/// Returns the note and the octave shift its letter needs (B#3 sounds as C4 → +1)
+/// Practical spellings and unrecognized input are returned unchanged
+pub fn simplify_spelling(note: &str) -> (String, i8) {
+    let mut chars = note.chars();
+    let natural: i32 = match chars.next() {
+        Some('C') => 0,
+        Some('D') => 2,
+        Some('E') => 4,
+        Some('F') => 5,
+        Some('G') => 7,
+        Some('A') => 9,
+        Some('B') => 11,
+        _ => return (note.to_string(), 0),
+    };
+    let mut accidental: i32 = 0;
+    for c in chars {
+        accidental += match c {
+            '#' => 1,
+            'x' => 2,
+            'b' => -1,
+            _ => return (note.to_string(), 0),
+        };
+    }
+
+    let impractical = accidental.abs() > 1 || matches!(note, "B#" | "E#" | "Cb" | "Fb");
+    if !impractical {
+        return (note.to_string(), 0);
+    }
+
+    let sounding = natural + accidental;
+    let pitch_class = sounding.rem_euclid(12) as usize;
+    let name = if accidental < 0 { CHROMATIC_FLAT[pitch_class] } else { CHROMATIC[pitch_class] };
+    (name.to_string(), sounding.div_euclid(12) as i8)
+}
+
+/// Apply the user's spelling preference to a generated note name
+pub fn preferred_spelling(note: &str) -> String {
+    if simplifies_spellings() {
+        simplify_spelling(note).0
+    } else {
+        note.to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_simplify_spelling() {
+        assert_eq!(simplify_spelling("B#"), ("C".to_string(), 1));
+        assert_eq!(simplify_spelling("Cb"), ("B".to_string(), -1));
+        assert_eq!(simplify_spelling("Fbb"), ("Eb".to_string(), 0));
+        assert_eq!(simplify_spelling("C##"), ("D".to_string(), 0));
+        assert_eq!(simplify_spelling("Fx"), ("G".to_string(), 0));
+        assert_eq!(simplify_spelling("Ebbb"), ("Db".to_string(), 0));
+        assert_eq!(simplify_spelling("Bbb"), ("A".to_string(), 0));
+        assert_eq!(simplify_spelling("E#"), ("F".to_string(), 0));
+        // Practical spellings stay as written
+        assert_eq!(simplify_spelling("F#"), ("F#".to_string(), 0));
+        assert_eq!(simplify_spelling("Bb"), ("Bb".to_string(), 0));
+    }
+
     #[test]
     fn test_note_index() {
         assert_eq!(note_index("C").unwrap(), 0);
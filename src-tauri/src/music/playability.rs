@@ -0,0 +1,268 @@
+// Piano playability scoring for chord progressions
+// Estimates hand spans, leaps, black-key density, and tempo feasibility of a voiced progression
+
+use serde::{Deserialize, Serialize};
+
+use super::intervals::chord_to_notes;
+use super::types::{AudioNote, MusicError, MusicResult};
+use super::voice_leading::{note_to_midi, voice_progression};
+
+/// Largest right-hand span (in semitones) considered comfortable - an octave
+const COMFORTABLE_SPAN: u8 = 12;
+/// Largest right-hand span most students can reach at all - a major ninth
+const MAX_REACHABLE_SPAN: u8 = 14;
+/// Hand shifts larger than this count as leaps
+const LEAP_THRESHOLD: u8 = 7;
+/// Minimum time (seconds) to re-place a hand, plus a cost per semitone moved
+const BASE_SHIFT_TIME: f32 = 0.12;
+const SHIFT_TIME_PER_SEMITONE: f32 = 0.02;
+/// Base octave used when voicing the progression for analysis
+const ANALYSIS_BASE_OCTAVE: i8 = 3;
+
+/// Semitones (mod 12) that fall on black keys
+const BLACK_KEYS: [u8; 5] = [1, 3, 6, 8, 10];
+
+/// Tempo settings used for the feasibility check
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PlayabilityOptions {
+    pub tempo_bpm: f32,
+    pub beats_per_chord: f32,
+}
+
+impl Default for PlayabilityOptions {
+    /// Moderate tempo with one chord per 4/4 bar
+    fn default() -> Self {
+        Self { tempo_bpm: 100.0, beats_per_chord: 4.0 }
+    }
+}
+
+/// Overall difficulty bucket derived from the playability score
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Beginner,
+    Intermediate,
+    Advanced,
+}
+
+/// Playability report for a voiced progression
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayabilityReport {
+    /// 0-100, higher is easier to play
+    pub score: f32,
+    pub difficulty: Difficulty,
+    /// Widest right-hand span in semitones
+    pub max_hand_span: u8,
+    /// Largest single hand shift between consecutive chords, in semitones
+    pub max_leap: u8,
+    /// Mean hand shift between consecutive chords, in semitones
+    pub average_leap: f32,
+    /// Fraction of played notes that are black keys (0.0-1.0)
+    pub black_key_density: f32,
+    pub tempo_feasible: bool,
+    /// Fastest tempo at which the largest hand shift is still comfortable
+    pub max_comfortable_tempo: f32,
+    /// Human-readable problems found, one per chord or transition
+    pub issues: Vec<String>,
+}
+
+/// Left-hand (bass) and right-hand (upper voices) MIDI notes for one chord
+struct HandPositions {
+    left: u8,
+    right: Vec<u8>,
+}
+
+fn to_hand_positions(voicing: &[AudioNote]) -> MusicResult<HandPositions> {
+    let mut midi = voicing
+        .iter()
+        .map(|n| note_to_midi(&n.note, n.octave))
+        .collect::<MusicResult<Vec<u8>>>()?;
+    midi.sort_unstable();
+
+    let (&left, right) = midi
+        .split_first()
+        .ok_or_else(|| MusicError::VoiceLeadingError("Empty voicing".to_string()))?;
+
+    Ok(HandPositions { left, right: right.to_vec() })
+}
+
+fn hand_span(notes: &[u8]) -> u8 {
+    match (notes.iter().min(), notes.iter().max()) {
+        (Some(lo), Some(hi)) => hi - lo,
+        _ => 0,
+    }
+}
+
+/// How far the hands move from one chord to the next (left, right)
+fn hand_shifts(from: &HandPositions, to: &HandPositions) -> (u8, u8) {
+    let left = from.left.abs_diff(to.left);
+    let right = match (from.right.first(), to.right.first(), from.right.last(), to.right.last()) {
+        (Some(a_lo), Some(b_lo), Some(a_hi), Some(b_hi)) => a_lo.abs_diff(*b_lo).max(a_hi.abs_diff(*b_hi)),
+        _ => 0,
+    };
+    (left, right)
+}
+
+fn is_black_key(midi: u8) -> bool {
+    BLACK_KEYS.contains(&(midi % 12))
+}
+
+fn difficulty_for_score(score: f32) -> Difficulty {
+    if score >= 75.0 {
+        Difficulty::Beginner
+    } else if score >= 50.0 {
+        Difficulty::Intermediate
+    } else {
+        Difficulty::Advanced
+    }
+}
+
+/// Score how playable a progression is on piano with the given voicing style
+/// voicing_style uses the same names as playback ("close", "wide", "common-tone", "lead")
+pub fn score_playability(
+    progression: &[String],
+    voicing_style: &str,
+    options: &PlayabilityOptions,
+) -> MusicResult<PlayabilityReport> {
+    if progression.is_empty() {
+        return Err(MusicError::InvalidChord("Progression is empty".to_string()));
+    }
+    if options.tempo_bpm <= 0.0 || options.beats_per_chord <= 0.0 {
+        return Err(MusicError::ParseError("Tempo and beats per chord must be positive".to_string()));
+    }
+
+    let chord_notes = progression
+        .iter()
+        .map(|chord| chord_to_notes(chord))
+        .collect::<MusicResult<Vec<_>>>()?;
+    let voicings = voice_progression(&chord_notes, ANALYSIS_BASE_OCTAVE, voicing_style)?;
+    let hands = voicings
+        .iter()
+        .map(|v| to_hand_positions(v))
+        .collect::<MusicResult<Vec<_>>>()?;
+
+    let mut score = 100.0_f32;
+    let mut issues = Vec::new();
+
+    // Hand spans
+    let mut max_hand_span = 0;
+    for (chord, hand) in progression.iter().zip(&hands) {
+        let span = hand_span(&hand.right);
+        max_hand_span = max_hand_span.max(span);
+
+        if span > MAX_REACHABLE_SPAN {
+            score -= (span - COMFORTABLE_SPAN) as f32 * 4.0;
+            issues.push(format!("{}: right hand spans {} semitones, beyond a ninth", chord, span));
+        } else if span > COMFORTABLE_SPAN {
+            score -= (span - COMFORTABLE_SPAN) as f32 * 2.0;
+            issues.push(format!("{}: right hand stretches past an octave", chord));
+        }
+    }
+
+    // Leaps between consecutive chords
+    let mut max_leap = 0;
+    let mut total_leap = 0u32;
+    let mut leap_count = 0u32;
+    for (i, pair) in hands.windows(2).enumerate() {
+        let (left, right) = hand_shifts(&pair[0], &pair[1]);
+        for shift in [left, right] {
+            total_leap += shift as u32;
+            leap_count += 1;
+            max_leap = max_leap.max(shift);
+        }
+
+        let largest = left.max(right);
+        if largest > LEAP_THRESHOLD {
+            score -= (largest - LEAP_THRESHOLD) as f32 * 1.5;
+            issues.push(format!(
+                "{} → {}: hand leaps {} semitones",
+                progression[i], progression[i + 1], largest
+            ));
+        }
+    }
+    let average_leap = if leap_count > 0 { total_leap as f32 / leap_count as f32 } else { 0.0 };
+    score -= average_leap;
+
+    // Black-key density
+    let all_notes: Vec<u8> = hands
+        .iter()
+        .flat_map(|h| std::iter::once(h.left).chain(h.right.iter().copied()))
+        .collect();
+    let black_count = all_notes.iter().filter(|&&m| is_black_key(m)).count();
+    let black_key_density = black_count as f32 / all_notes.len() as f32;
+    score -= black_key_density * 20.0;
+
+    // Tempo feasibility: the largest shift must fit inside one chord's duration
+    let required_time = BASE_SHIFT_TIME + max_leap as f32 * SHIFT_TIME_PER_SEMITONE;
+    let max_comfortable_tempo = options.beats_per_chord * 60.0 / required_time;
+    let tempo_feasible = options.tempo_bpm <= max_comfortable_tempo;
+    if !tempo_feasible {
+        score -= 20.0;
+        issues.push(format!(
+            "Tempo {} BPM is too fast for the largest leap (comfortable up to {:.0} BPM)",
+            options.tempo_bpm, max_comfortable_tempo
+        ));
+    }
+
+    let score = score.clamp(0.0, 100.0);
+
+    Ok(PlayabilityReport {
+        score,
+        difficulty: difficulty_for_score(score),
+        max_hand_span,
+        max_leap,
+        average_leap,
+        black_key_density,
+        tempo_feasible,
+        max_comfortable_tempo,
+        issues,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progression(chords: &[&str]) -> Vec<String> {
+        chords.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_simple_progression_is_beginner() {
+        let report = score_playability(
+            &progression(&["C", "F", "G", "C"]),
+            "lead",
+            &PlayabilityOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(report.difficulty, Difficulty::Beginner);
+        assert!(report.max_hand_span <= COMFORTABLE_SPAN);
+        assert_eq!(report.black_key_density, 0.0);
+        assert!(report.tempo_feasible);
+    }
+
+    #[test]
+    fn test_black_keys_lower_score() {
+        let options = PlayabilityOptions::default();
+        let white = score_playability(&progression(&["C", "F", "G"]), "close", &options).unwrap();
+        let black = score_playability(&progression(&["Db", "Gb", "Ab"]), "close", &options).unwrap();
+
+        assert!(black.black_key_density > white.black_key_density);
+        assert!(black.score < white.score);
+    }
+
+    #[test]
+    fn test_fast_tempo_with_leaps_is_infeasible() {
+        let options = PlayabilityOptions { tempo_bpm: 400.0, beats_per_chord: 1.0 };
+        let report = score_playability(&progression(&["C", "F#", "C", "F#"]), "wide", &options).unwrap();
+
+        assert!(!report.tempo_feasible);
+        assert!(report.issues.iter().any(|i| i.contains("BPM")));
+    }
+
+    #[test]
+    fn test_empty_progression_errors() {
+        assert!(score_playability(&[], "lead", &PlayabilityOptions::default()).is_err());
+    }
+}
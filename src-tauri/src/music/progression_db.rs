@@ -0,0 +1,203 @@
+// Progression statistics database
+// The bundled progression statistics (see progression_stats) plus any the user imports, held in an
+// embedded SQLite database whose imports are kept in the app data directory
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::progression_stats::{parse_dataset, ProgressionSource, ProgressionStats, BUNDLED_STATS};
+use super::types::{MusicError, MusicResult};
+
+/// File in the app data directory holding imported statistics
+pub const PROGRESSION_DB_FILE: &str = "progression_stats.sqlite3";
+
+/// Imported counts, kept in the database file; totals are summed in 64 bits so repeated imports can't overflow
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS moves (
+        context TEXT NOT NULL,
+        next TEXT NOT NULL,
+        count INTEGER NOT NULL,
+        PRIMARY KEY (context, next)
+    );
+    CREATE TEMP TABLE bundled_moves (
+        context TEXT NOT NULL,
+        next TEXT NOT NULL,
+        count INTEGER NOT NULL,
+        PRIMARY KEY (context, next)
+    );
+";
+
+/// Bundled and imported counts of the moves after a context, added together; moves never seen are left out
+const MOVES_QUERY: &str = "
+    SELECT next, SUM(count) FROM (
+        SELECT next, count FROM moves WHERE context = ?1
+        UNION ALL
+        SELECT next, count FROM bundled_moves WHERE context = ?1
+    )
+    GROUP BY next
+    HAVING SUM(count) > 0
+";
+
+/// Result of importing a dataset
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportSummary {
+    /// Contexts and moves in the imported dataset
+    pub contexts: usize,
+    pub moves: usize,
+    /// Contexts known after the import, bundled and imported together
+    pub total_contexts: usize,
+}
+
+fn sql_error(e: rusqlite::Error) -> String {
+    format!("Progression database error: {}", e)
+}
+
+/// Add a dataset's counts to a table, in one statement per move
+fn add_counts(connection: &Connection, table: &str, stats: &ProgressionStats) -> rusqlite::Result<()> {
+    let mut insert = connection.prepare(&format!(
+        "INSERT INTO {} (context, next, count) VALUES (?1, ?2, ?3)
+         ON CONFLICT (context, next) DO UPDATE SET count = count + excluded.count",
+        table
+    ))?;
+    for (context, moves) in stats {
+        for (next, count) in moves {
+            insert.execute(params![context, next, count])?;
+        }
+    }
+    Ok(())
+}
+
+/// Bundled statistics with the user's imports on top
+pub struct ProgressionDatabase {
+    connection: Connection,
+}
+
+impl Default for ProgressionDatabase {
+    /// The bundled statistics alone, with imports kept in memory
+    fn default() -> Self {
+        let connection = Connection::open_in_memory().expect("in-memory SQLite database opens");
+        Self::with_connection(connection).expect("bundled progression statistics load")
+    }
+}
+
+impl ProgressionDatabase {
+    /// Create the schema and load the bundled statistics
+    fn with_connection(connection: Connection) -> Result<Self, String> {
+        connection.execute_batch(SCHEMA).map_err(sql_error)?;
+        add_counts(&connection, "bundled_moves", &BUNDLED_STATS).map_err(sql_error)?;
+        Ok(Self { connection })
+    }
+
+    /// The bundled statistics with the imports saved in the app data directory
+    /// A database that can't be opened is reported and left out, so recommendations still work
+    pub fn in_dir(data_dir: &Path) -> Self {
+        let path = data_dir.join(PROGRESSION_DB_FILE);
+        let opened = fs::create_dir_all(data_dir)
+            .map_err(|e| format!("Failed to create data directory: {}", e))
+            .and_then(|_| Connection::open(&path).map_err(sql_error))
+            .and_then(Self::with_connection);
+        opened.unwrap_or_else(|e| {
+            println!("[progression db] Ignoring unreadable imported statistics {:?}: {}", path, e);
+            Self::default()
+        })
+    }
+
+    fn context_count(&self) -> rusqlite::Result<usize> {
+        self.connection.query_row(
+            "SELECT COUNT(*) FROM (SELECT context FROM moves UNION SELECT context FROM bundled_moves)",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    /// Add a dataset's counts to the imports (or replace earlier imports with it), saved in one transaction
+    pub fn import(&mut self, json: &str, replace: bool) -> Result<ImportSummary, String> {
+        let stats = parse_dataset(json).map_err(|e| e.to_string())?;
+        let contexts = stats.len();
+        let moves = stats.values().map(|moves| moves.len()).sum();
+
+        let transaction = self.connection.transaction().map_err(sql_error)?;
+        if replace {
+            transaction.execute("DELETE FROM moves", []).map_err(sql_error)?;
+        }
+        add_counts(&transaction, "moves", &stats).map_err(sql_error)?;
+        transaction.commit().map_err(|e| format!("Failed to save progression statistics: {}", e))?;
+
+        Ok(ImportSummary { contexts, moves, total_contexts: self.context_count().map_err(sql_error)? })
+    }
+}
+
+impl ProgressionSource for ProgressionDatabase {
+    /// Bundled and imported counts added together
+    fn moves(&self, context: &str) -> MusicResult<HashMap<String, u64>> {
+        let lookup_failed = |e: rusqlite::Error| MusicError::DataLookupFailed(format!("{}: {}", context, e));
+        let mut statement = self.connection.prepare_cached(MOVES_QUERY).map_err(lookup_failed)?;
+        let rows = statement
+            .query_map([context], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+            .map_err(lookup_failed)?;
+        rows.map(|row| row.map(|(next, count)| (next, count as u64)).map_err(lookup_failed)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_without_imports_matches_the_bundled_dataset() {
+        let db = ProgressionDatabase::default();
+        for context in BUNDLED_STATS.keys() {
+            assert_eq!(db.query(context).unwrap(), BUNDLED_STATS.query(context).unwrap());
+        }
+        assert!(db.query("M_6_M_6_M").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_import_adds_counts_and_persists() {
+        let dir = TempDir::new().unwrap();
+        let mut db = ProgressionDatabase::in_dir(dir.path());
+        let before = db.moves("m7_5_7").unwrap()["5_maj7"];
+
+        let summary = db.import(r#"{ "m7_5_7": { "5_maj7": 100 }, "M_6_M": { "6_M": 2 } }"#, false).unwrap();
+        assert_eq!((summary.contexts, summary.moves), (2, 2));
+        assert_eq!(db.moves("m7_5_7").unwrap()["5_maj7"], before + 100);
+        db.import(r#"{ "M_6_M": { "6_M": 1 } }"#, false).unwrap();
+
+        // Reopened from disk, imports are still there; replacing drops the earlier ones
+        let mut db = ProgressionDatabase::in_dir(dir.path());
+        assert_eq!(db.query("M_6_M").unwrap()[0].count, 3);
+        db.import(r#"{ "M_6_M": { "6_M": 1 } }"#, true).unwrap();
+        assert_eq!(db.moves("m7_5_7").unwrap()["5_maj7"], before);
+        assert_eq!(db.query("M_6_M").unwrap()[0].count, 1);
+    }
+
+    #[test]
+    fn test_counts_add_up_past_u32_and_skip_zero() {
+        let mut db = ProgressionDatabase::default();
+        let max = format!(r#"{{ "M_6_M": {{ "6_M": {}, "5_M": 0 }} }}"#, u32::MAX);
+        db.import(&max, false).unwrap();
+        db.import(&max, false).unwrap();
+        assert_eq!(db.moves("M_6_M").unwrap()["6_M"], 2 * u32::MAX as u64);
+
+        // A move seen zero times is left out, and a context with nothing but those has no moves
+        assert_eq!(db.query("M_6_M").unwrap().len(), 1);
+        assert_eq!(db.query("M_6_M").unwrap()[0].probability, 1.0);
+        db.import(r#"{ "m_6_m": { "7_M": 0 } }"#, false).unwrap();
+        assert!(db.query("m_6_m").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_import_rejects_invalid_datasets() {
+        let mut db = ProgressionDatabase::default();
+        assert!(db.import("[1, 2]", false).is_err());
+        assert!(db.import(r#"{ "M_9_m": { "5_M": 1 } }"#, false).is_err());
+        assert!(db.import(r#"{ "M_5": { "5_M": 1 } }"#, false).is_err());
+        assert!(db.import(r#"{ "M": { "12_M": 1 } }"#, false).is_err());
+        assert!(db.import(r#"{ "M": { "5_xyz": 1 } }"#, false).is_err());
+        assert!(db.import(r#"{ "M/4": { "7_m/3": 1 } }"#, false).is_ok());
+    }
+}
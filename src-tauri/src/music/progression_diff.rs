@@ -0,0 +1,351 @@
+// Progression diff and merge
+// Chord-level changes between two versions of a progression, and a three-way merge of concurrent edits
+
+use serde::Serialize;
+
+use super::chords::{normalized_symbol, parse_chord};
+use crate::types::song::SongChord;
+
+/// One change from the original progression to the edited one
+/// Indices and beats are positions in the original
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProgressionChange {
+    /// Chords added before the original chord at `index` (at the end when `index` is its length)
+    Insert { index: usize, beat: f32, chords: Vec<SongChord> },
+    /// Chords removed, starting with the one at `index`
+    Delete { index: usize, beat: f32, chords: Vec<SongChord> },
+    /// A chord swapped for a different one in its place
+    Replace { index: usize, beat: f32, from: SongChord, to: SongChord },
+    /// The same chord held for a different number of beats
+    Resize { index: usize, beat: f32, from: f32, to: f32 },
+}
+
+/// Edits on one side of a merge that touch the same chords as edits on the other
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MergeConflict {
+    /// Where the conflicting chords start in the base progression
+    pub base_index: usize,
+    pub base: Vec<SongChord>,
+    pub ours: Vec<SongChord>,
+    pub theirs: Vec<SongChord>,
+    /// Where the conflicting chords start in the merged progression, which holds our version of them
+    pub merged_index: usize,
+}
+
+/// Result of a three-way merge
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProgressionMerge {
+    pub chords: Vec<SongChord>,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// A chord's identity: its normalized symbol, so "CΔ" and "Cmaj7" are the same chord
+fn identity(chord: &SongChord) -> String {
+    match parse_chord(chord.chord.trim()) {
+        Ok(parsed) => normalized_symbol(&parsed),
+        Err(_) => chord.chord.trim().to_string(),
+    }
+}
+
+/// Pairs of original and edited indices in order: both for a chord kept, one side only for a chord
+/// removed or added. Kept chords are a longest common subsequence by identity
+fn alignment(a: &[SongChord], b: &[SongChord]) -> Vec<(Option<usize>, Option<usize>)> {
+    let a_ids: Vec<String> = a.iter().map(identity).collect();
+    let b_ids: Vec<String> = b.iter().map(identity).collect();
+
+    // lengths[i][j] = longest common subsequence of a[i..] and b[j..]
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a_ids[i] == b_ids[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut pairs = Vec::new();
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a_ids[i] == b_ids[j] {
+            pairs.push((Some(i), Some(j)));
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lengths[i + 1][j] >= lengths[i][j + 1]) {
+            pairs.push((Some(i), None));
+            i += 1;
+        } else {
+            pairs.push((None, Some(j)));
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Beat each chord starts on, plus the total length
+fn start_beats(chords: &[SongChord]) -> Vec<f32> {
+    let mut beats = vec![0.0];
+    for chord in chords {
+        beats.push(beats.last().copied().unwrap_or_default() + chord.beats);
+    }
+    beats
+}
+
+/// Changes that turn progression `a` into `b`
+/// Chords replaced one for one in a run of edits are reported as replacements, the rest of the run
+/// as insertions or deletions
+pub fn diff_progressions(a: &[SongChord], b: &[SongChord]) -> Vec<ProgressionChange> {
+    let beats = start_beats(a);
+    let mut changes = Vec::new();
+    let (mut removed, mut added): (Vec<usize>, Vec<usize>) = (Vec::new(), Vec::new());
+    let mut next = 0;
+
+    // A run of removed and added chords between two kept ones
+    let run = |removed: &[usize], added: &[usize], next: usize| {
+        let mut changes = Vec::new();
+        let start = removed.first().copied().unwrap_or(next);
+        let paired = removed.len().min(added.len());
+        for (offset, (i, j)) in removed.iter().zip(added).enumerate() {
+            let (index, from, to) = (start + offset, a[*i].clone(), b[*j].clone());
+            changes.push(ProgressionChange::Replace { index, beat: beats[index], from, to });
+        }
+        if removed.len() > paired {
+            let index = start + paired;
+            let chords = removed[paired..].iter().map(|i| a[*i].clone()).collect();
+            changes.push(ProgressionChange::Delete { index, beat: beats[index], chords });
+        }
+        if added.len() > paired {
+            let index = start + paired;
+            let chords = added[paired..].iter().map(|j| b[*j].clone()).collect();
+            changes.push(ProgressionChange::Insert { index, beat: beats[index], chords });
+        }
+        changes
+    };
+
+    for pair in alignment(a, b) {
+        match pair {
+            (Some(i), Some(j)) => {
+                changes.extend(run(&std::mem::take(&mut removed), &std::mem::take(&mut added), next));
+                if a[i].beats != b[j].beats {
+                    let (from, to) = (a[i].beats, b[j].beats);
+                    changes.push(ProgressionChange::Resize { index: i, beat: beats[i], from, to });
+                }
+                next = i + 1;
+            }
+            (Some(i), None) => {
+                removed.push(i);
+                next = i + 1;
+            }
+            (None, Some(j)) => added.push(j),
+            (None, None) => {}
+        }
+    }
+    changes.extend(run(&removed, &added, next));
+    changes
+}
+
+/// Base chords `start..end` replaced by `chords`
+#[derive(Debug, Clone, PartialEq)]
+struct Hunk {
+    start: usize,
+    end: usize,
+    chords: Vec<SongChord>,
+}
+
+/// Edits from `base` to `edited` as hunks of the base, with touching hunks joined so each one is
+/// surrounded by unchanged chords
+fn hunks(base: &[SongChord], edited: &[SongChord]) -> Vec<Hunk> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut next = 0;
+    for pair in alignment(base, edited) {
+        let hunk = match pair {
+            (Some(i), Some(j)) if base[i].beats != edited[j].beats => {
+                Hunk { start: i, end: i + 1, chords: vec![edited[j].clone()] }
+            }
+            (Some(i), Some(_)) => {
+                next = i + 1;
+                continue;
+            }
+            (Some(i), None) => Hunk { start: i, end: i + 1, chords: Vec::new() },
+            (None, Some(j)) => Hunk { start: next, end: next, chords: vec![edited[j].clone()] },
+            (None, None) => continue,
+        };
+        next = hunk.end;
+        match hunks.last_mut() {
+            Some(last) if last.end == hunk.start => {
+                last.end = hunk.end;
+                last.chords.extend(hunk.chords);
+            }
+            _ => hunks.push(hunk),
+        }
+    }
+    hunks
+}
+
+/// One side's version of base chords `start..end`, given its hunks within them
+fn side_version(base: &[SongChord], hunks: &[&Hunk], start: usize, end: usize) -> Vec<SongChord> {
+    let mut chords = Vec::new();
+    let mut cursor = start;
+    for hunk in hunks {
+        chords.extend_from_slice(&base[cursor..hunk.start]);
+        chords.extend_from_slice(&hunk.chords);
+        cursor = hunk.end;
+    }
+    chords.extend_from_slice(&base[cursor..end]);
+    chords
+}
+
+/// Whether a hunk touches the same chords as a group spanning base chords `start..end`
+/// Insertions only clash with each other at the same place, or with edits around the point they insert at
+fn overlaps(hunk: &Hunk, start: usize, end: usize) -> bool {
+    let both_insertions = hunk.start == hunk.end && start == end;
+    (hunk.start < end && start < hunk.end) || (both_insertions && hunk.start == start)
+}
+
+/// Three-way merge of two edited versions of a base progression
+/// Edits to different chords are combined; where both sides changed the same chords differently, the merge
+/// keeps our version and reports a conflict for the user to resolve
+pub fn merge_progressions(base: &[SongChord], ours: &[SongChord], theirs: &[SongChord]) -> ProgressionMerge {
+    let ours = hunks(base, ours);
+    let theirs = hunks(base, theirs);
+    let (mut o, mut t) = (0, 0);
+    let mut chords = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut cursor = 0;
+
+    // Hunks in base order, insertions before edits starting at the same chord
+    let order = |hunk: &Hunk| (hunk.start, hunk.end > hunk.start);
+    loop {
+        let first = match (ours.get(o), theirs.get(t)) {
+            (Some(a), Some(b)) => {
+                if order(a) <= order(b) {
+                    a
+                } else {
+                    b
+                }
+            }
+            (Some(a), None) => a,
+            (None, Some(b)) => b,
+            (None, None) => break,
+        };
+
+        // Group every hunk on either side that overlaps the group as it grows
+        let (start, mut end) = (first.start, first.end);
+        let (mut our_group, mut their_group): (Vec<&Hunk>, Vec<&Hunk>) = (Vec::new(), Vec::new());
+        let joins = |hunk: &Hunk, end: usize| std::ptr::eq(hunk, first) || overlaps(hunk, start, end);
+        loop {
+            if let Some(hunk) = ours.get(o).filter(|hunk| joins(hunk, end)) {
+                end = end.max(hunk.end);
+                our_group.push(hunk);
+                o += 1;
+            } else if let Some(hunk) = theirs.get(t).filter(|hunk| joins(hunk, end)) {
+                end = end.max(hunk.end);
+                their_group.push(hunk);
+                t += 1;
+            } else {
+                break;
+            }
+        }
+
+        chords.extend_from_slice(&base[cursor..start]);
+        let our_version = side_version(base, &our_group, start, end);
+        let their_version = side_version(base, &their_group, start, end);
+        if their_group.is_empty() || our_version == their_version {
+            chords.extend(our_version);
+        } else if our_group.is_empty() {
+            chords.extend(their_version);
+        } else {
+            conflicts.push(MergeConflict {
+                base_index: start,
+                base: base[start..end].to_vec(),
+                ours: our_version.clone(),
+                theirs: their_version,
+                merged_index: chords.len(),
+            });
+            chords.extend(our_version);
+        }
+        cursor = end;
+    }
+    chords.extend_from_slice(&base[cursor..]);
+
+    ProgressionMerge { chords, conflicts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progression(chords: &[(&str, f32)]) -> Vec<SongChord> {
+        chords.iter().map(|(chord, beats)| SongChord { chord: chord.to_string(), beats: *beats }).collect()
+    }
+
+    fn names(chords: &[SongChord]) -> Vec<&str> {
+        chords.iter().map(|chord| chord.chord.as_str()).collect()
+    }
+
+    #[test]
+    fn test_diff_progressions() {
+        let a = progression(&[("C", 4.0), ("Am", 4.0), ("F", 4.0), ("G7", 4.0)]);
+        let b = progression(&[("C", 2.0), ("Em", 2.0), ("Am", 4.0), ("Dm7", 4.0), ("G7", 4.0), ("C", 4.0)]);
+        let changes = diff_progressions(&a, &b);
+        assert_eq!(
+            changes,
+            vec![
+                ProgressionChange::Resize { index: 0, beat: 0.0, from: 4.0, to: 2.0 },
+                ProgressionChange::Insert { index: 1, beat: 4.0, chords: progression(&[("Em", 2.0)]) },
+                ProgressionChange::Replace { index: 2, beat: 8.0, from: a[2].clone(), to: b[3].clone() },
+                ProgressionChange::Insert { index: 4, beat: 16.0, chords: progression(&[("C", 4.0)]) },
+            ]
+        );
+
+        // Respelled symbols are the same chord; removals are reported from where they start
+        let a = progression(&[("CΔ", 4.0), ("Dm", 4.0), ("Em", 4.0)]);
+        let b = progression(&[("Cmaj7", 4.0)]);
+        assert_eq!(
+            diff_progressions(&a, &b),
+            vec![ProgressionChange::Delete { index: 1, beat: 4.0, chords: a[1..].to_vec() }]
+        );
+        assert!(diff_progressions(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn test_merge_combines_separate_edits() {
+        let base = progression(&[("C", 4.0), ("Am", 4.0), ("F", 4.0), ("G", 4.0)]);
+        let ours = progression(&[("C", 4.0), ("Am7", 4.0), ("F", 4.0), ("G", 4.0)]);
+        let theirs = progression(&[("C", 4.0), ("Am", 4.0), ("F", 4.0), ("G", 2.0), ("G7", 2.0), ("C", 4.0)]);
+        let merge = merge_progressions(&base, &ours, &theirs);
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(names(&merge.chords), vec!["C", "Am7", "F", "G", "G7", "C"]);
+        assert_eq!(merge.chords[3].beats, 2.0);
+
+        // The same edit made on both sides is applied once
+        let merge = merge_progressions(&base, &ours, &ours);
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(merge.chords, ours);
+    }
+
+    #[test]
+    fn test_merge_conflicts_keep_ours() {
+        let base = progression(&[("C", 4.0), ("F", 4.0), ("G", 4.0)]);
+        let ours = progression(&[("C", 4.0), ("Dm", 4.0), ("G", 4.0)]);
+        let theirs = progression(&[("C", 4.0), ("F", 2.0), ("G", 4.0), ("E7", 4.0)]);
+        let merge = merge_progressions(&base, &ours, &theirs);
+        assert_eq!(names(&merge.chords), vec!["C", "Dm", "G", "E7"]);
+        assert_eq!(merge.conflicts.len(), 1);
+        let conflict = &merge.conflicts[0];
+        assert_eq!((conflict.base_index, conflict.merged_index), (1, 1));
+        assert_eq!(names(&conflict.base), vec!["F"]);
+        assert_eq!(names(&conflict.ours), vec!["Dm"]);
+        assert_eq!(conflict.theirs, progression(&[("F", 2.0)]));
+
+        // Different chords inserted at the same place conflict
+        let ours = progression(&[("C", 4.0), ("Am", 4.0), ("F", 4.0), ("G", 4.0)]);
+        let theirs = progression(&[("C", 4.0), ("Em", 4.0), ("F", 4.0), ("G", 4.0)]);
+        let merge = merge_progressions(&base, &ours, &theirs);
+        assert_eq!(names(&merge.chords), vec!["C", "Am", "F", "G"]);
+        assert_eq!(names(&merge.conflicts[0].theirs), vec!["Em"]);
+        assert!(merge.conflicts[0].base.is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+// Progression statistics
+// Counts of the moves that followed each run of chords, keyed by interval encoding (see interval_encoding),
+// and the dataset bundled with the app; builds with the storage feature add the user's imports on top
+// (see progression_db)
+//
+// A dataset is JSON mapping each context to its moves and counts, e.g. { "m7_5_7": { "5_maj7": 9 } }.
+// Contexts are interval keys (intervals normalized 0-6); moves are "interval_quality" with the interval
+// counted up from the last chord's root (0-11, so a move up a fourth and up a fifth stay apart).
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::interval_encoding::{is_valid_suffix, parse_interval_key, parse_quality_with_bass};
+use super::types::{MusicError, MusicResult};
+
+/// Moves seen after each context, with the number of times each was seen
+pub type ProgressionStats = HashMap<String, HashMap<String, u32>>;
+
+/// The dataset bundled with the app
+pub static BUNDLED_STATS: Lazy<ProgressionStats> = Lazy::new(|| {
+    parse_dataset(include_str!("progression_stats.json")).expect("bundled progression statistics are valid")
+});
+
+/// One move seen after a context
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NextMove {
+    /// "interval_quality", e.g. "5_maj7"
+    pub next: String,
+    pub count: u64,
+    /// Share of the context's moves
+    pub probability: f32,
+}
+
+/// A chord quality as encoded, with an optional bass interval ("m7", "M/4")
+fn check_quality(quality: &str) -> MusicResult<()> {
+    let (main, bass) = parse_quality_with_bass(quality)?;
+    if main != "M" && !is_valid_suffix(&main) {
+        return Err(MusicError::UnknownQuality(main));
+    }
+    match bass {
+        Some(bass) if bass > 11 => Err(MusicError::ParseError(format!("Invalid bass interval: {}", bass))),
+        _ => Ok(()),
+    }
+}
+
+/// An interval key: qualities separated by normalized intervals
+fn check_context(context: &str) -> MusicResult<()> {
+    let parts: Vec<&str> = context.split('_').collect();
+    if parts.len().is_multiple_of(2) {
+        return Err(MusicError::ParseError(format!("Invalid interval key: {}", context)));
+    }
+    for (index, part) in parts.iter().enumerate() {
+        if index.is_multiple_of(2) {
+            check_quality(part)?;
+        } else if !part.parse::<u8>().is_ok_and(|interval| interval <= 6) {
+            return Err(MusicError::ParseError(format!("Invalid interval {} in {}", part, context)));
+        }
+    }
+    Ok(())
+}
+
+fn check_move(next: &str) -> MusicResult<()> {
+    let (interval, quality) = parse_interval_key(next)?;
+    if interval > 11 {
+        return Err(MusicError::ParseError(format!("Invalid interval in move: {}", next)));
+    }
+    check_quality(&quality)
+}
+
+/// Parse and check a dataset
+pub fn parse_dataset(json: &str) -> MusicResult<ProgressionStats> {
+    let stats: ProgressionStats = serde_json::from_str(json)
+        .map_err(|e| MusicError::ParseError(format!("Invalid progression dataset: {}", e)))?;
+    for (context, moves) in &stats {
+        check_context(context)?;
+        for next in moves.keys() {
+            check_move(next)?;
+        }
+    }
+    Ok(stats)
+}
+
+/// Statistics that recommendations can look moves up in
+pub trait ProgressionSource {
+    /// Moves seen after a context, with their counts; moves never seen are left out
+    fn moves(&self, context: &str) -> MusicResult<HashMap<String, u64>>;
+
+    /// Moves seen after a context, most frequent first
+    fn query(&self, context: &str) -> MusicResult<Vec<NextMove>> {
+        let moves = self.moves(context)?;
+        // Moves are only returned with a count, so a context with any moves has a total above zero
+        let total: u64 = moves.values().sum();
+        let mut moves: Vec<NextMove> = moves
+            .into_iter()
+            .map(|(next, count)| NextMove { next, count, probability: (count as f64 / total as f64) as f32 })
+            .collect();
+        moves.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.next.cmp(&b.next)));
+        Ok(moves)
+    }
+}
+
+/// A parsed dataset on its own, e.g. the bundled one in builds without a database
+impl ProgressionSource for ProgressionStats {
+    fn moves(&self, context: &str) -> MusicResult<HashMap<String, u64>> {
+        let moves = self.get(context).into_iter().flatten();
+        Ok(moves.filter(|(_, count)| **count > 0).map(|(next, count)| (next.clone(), *count as u64)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_dataset_is_valid() {
+        let moves = BUNDLED_STATS.query("m7_5_7").unwrap();
+        assert_eq!(moves[0].next, "5_maj7");
+        assert!(moves.windows(2).all(|pair| pair[0].count >= pair[1].count));
+        let total: f32 = moves.iter().map(|next| next.probability).sum();
+        assert!((total - 1.0).abs() < 1e-6);
+        assert!(BUNDLED_STATS.query("M_6_M_6_M").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_datasets() {
+        assert!(parse_dataset("[1, 2]").is_err());
+        assert!(parse_dataset(r#"{ "M_9_m": { "5_M": 1 } }"#).is_err());
+        assert!(parse_dataset(r#"{ "M_5": { "5_M": 1 } }"#).is_err());
+        assert!(parse_dataset(r#"{ "M": { "12_M": 1 } }"#).is_err());
+        assert!(parse_dataset(r#"{ "M": { "5_xyz": 1 } }"#).is_err());
+
+        // Moves seen zero times are accepted but never looked up
+        let stats = parse_dataset(r#"{ "M/4": { "7_m/3": 1, "5_M": 0 } }"#).unwrap();
+        assert_eq!(stats.query("M/4").unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,108 @@
+// Chord recommendations
+// Suggests the next chord from statistics of common progressions, looked up by interval encoding
+
+use std::collections::HashMap;
+
+use super::chords::prepare_chord_display;
+use super::interval_encoding::{
+    history_to_interval_key, interval_to_chord, parse_chord_for_interval, parse_interval_key,
+};
+use super::progression_stats::ProgressionSource;
+use super::tiers::classify_tier;
+use super::types::{ChordRecommendation, MusicError, MusicResult};
+
+/// Most recent chords used as context; shorter contexts are tried when a longer one was never seen
+const MAX_CONTEXT: usize = 3;
+
+/// Suggestions returned when the caller does not ask for a count
+pub const DEFAULT_RECOMMENDATIONS: usize = 8;
+
+/// Moves seen after the longest recent stretch of the history the statistics know
+fn next_moves(db: &impl ProgressionSource, history: &[String]) -> MusicResult<HashMap<String, u64>> {
+    for length in (1..=history.len().min(MAX_CONTEXT)).rev() {
+        let moves = db.moves(&history_to_interval_key(&history[history.len() - length..])?)?;
+        if !moves.is_empty() {
+            return Ok(moves);
+        }
+    }
+    Ok(HashMap::new())
+}
+
+/// Likely next chords after the history (most recent last), most likely first
+/// Each is spelled for the key, with its Roman numeral and its tier after the history
+pub fn recommend_chords(
+    db: &impl ProgressionSource,
+    history: &[String],
+    key: &str,
+    use_flats: bool,
+    limit: usize,
+) -> MusicResult<Vec<ChordRecommendation>> {
+    let last = history.last().ok_or_else(|| MusicError::ParseError("Empty history".to_string()))?;
+    let (last_root, _) = parse_chord_for_interval(last)?;
+    let moves = next_moves(db, history)?;
+    let total: u64 = moves.values().sum();
+
+    let mut recommendations: Vec<ChordRecommendation> = Vec::new();
+    for (next, count) in moves {
+        let (interval, quality) = parse_interval_key(&next)?;
+        let chord = interval_to_chord(interval, &quality, last_root, use_flats, key)?;
+        // Moves the numeral or tier engines can't place in the key are left out
+        let notation = prepare_chord_display(&chord, key);
+        let (Ok(notation), Ok(classification)) = (notation, classify_tier(&chord, key, history)) else {
+            continue;
+        };
+        recommendations.push(ChordRecommendation {
+            chord,
+            probability: (count as f64 / total as f64) as f32,
+            numeral: notation.numeral,
+            tier: classification.tier,
+        });
+    }
+
+    recommendations.sort_by(|a, b| b.probability.total_cmp(&a.probability).then_with(|| a.chord.cmp(&b.chord)));
+    recommendations.truncate(limit);
+    Ok(recommendations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::progression_stats::BUNDLED_STATS;
+    use crate::music::types::Tier;
+
+    fn history(chords: &[&str]) -> Vec<String> {
+        chords.iter().map(|chord| chord.to_string()).collect()
+    }
+
+    fn chords(recommendations: &[ChordRecommendation]) -> Vec<&str> {
+        recommendations.iter().map(|recommendation| recommendation.chord.as_str()).collect()
+    }
+
+    #[test]
+    fn test_ii_v_resolves_to_the_tonic() {
+        let db = &*BUNDLED_STATS;
+        let recommendations =
+            recommend_chords(db, &history(&["Dm7", "G7"]), "C", false, DEFAULT_RECOMMENDATIONS).unwrap();
+        let first = &recommendations[0];
+        assert_eq!((first.chord.as_str(), first.numeral.as_str(), first.tier), ("Cmaj7", "Imaj7", Tier::Safe));
+        let total: f32 = recommendations.iter().map(|recommendation| recommendation.probability).sum();
+        assert!(total <= 1.0 + 1e-6);
+        assert!(recommendations.windows(2).all(|pair| pair[0].probability >= pair[1].probability));
+    }
+
+    #[test]
+    fn test_recommendations_follow_the_key() {
+        let db = &*BUNDLED_STATS;
+        // The same motion a whole step lower, spelled in Bb
+        let recommendations = recommend_chords(db, &history(&["Cm7", "F7"]), "Bb", true, 1).unwrap();
+        assert_eq!(chords(&recommendations), ["Bbmaj7"]);
+
+        // An unseen context backs off to the last chord alone
+        let recommendations = recommend_chords(db, &history(&["C", "F#", "G"]), "C", false, 3).unwrap();
+        assert_eq!(recommendations.len(), 3);
+        assert!(chords(&recommendations).contains(&"C"));
+
+        assert!(recommend_chords(db, &[], "C", false, 3).is_err());
+        assert!(recommend_chords(db, &history(&["H"]), "C", false, 3).is_err());
+    }
+}
@@ -1,9 +1,10 @@
 // Roman numeral conversion system
 // Handles conversion between chord names and Roman numeral notation
 
-use super::types::{RomanNumeralParts, Accidental, MusicError, MusicResult};
+use super::types::{RomanNumeralParts, Accidental, MusicError, MusicResult, NumeralDepth, NumeralMode};
 use super::notes::{note_index, get_preferred_note_name};
-use super::chords::transpose_chord;
+use super::chords::{transpose_chord, ChordSymbol, SymbolStyle};
+use super::scales::{scale_to_notes, ScaleType};
 use std::collections::HashMap;
 
 // Map roman numeral to degree (1-7)
@@ -17,11 +18,49 @@ static ROMAN_TO_DEGREE: &[(&str, u8)] = &[
     ("vii", 7), ("VII", 7),
 ];
 
-// Degree (1-7) to semitone interval in major scale
-static DEGREE_SEMITONES: &[u8] = &[0, 0, 2, 4, 5, 7, 9, 11]; // Index 0 unused, 1-7 are degrees
+impl NumeralMode {
+    fn scale(self) -> ScaleType {
+        match self {
+            NumeralMode::Major => ScaleType::Major,
+            NumeralMode::NaturalMinor => ScaleType::NaturalMinor,
+            NumeralMode::HarmonicMinor => ScaleType::HarmonicMinor,
+        }
+    }
+
+    /// Semitones from the tonic to a scale degree (1-7)
+    fn degree_semitones(self, degree: u8) -> u8 {
+        self.scale().interval_specs()[(degree - 1) as usize].0
+    }
+
+    /// Lookup numeral for each semitone above the tonic, accidentals relative to the mode's scale
+    fn lookup_numerals(self) -> &'static [&'static str; 12] {
+        match self {
+            NumeralMode::Major => &LOOKUP_MAJOR,
+            NumeralMode::NaturalMinor => &LOOKUP_NATURAL_MINOR,
+            NumeralMode::HarmonicMinor => &LOOKUP_HARMONIC_MINOR,
+        }
+    }
+}
+
+/// Semitone-based lookup numerals (match TypeScript's MAJOR_NUMERALS); minor qualities use the lowercase
+const LOOKUP_MAJOR: [&str; 12] = ["I", "bII", "II", "bIII", "III", "IV", "#IV", "V", "bVI", "VI", "bVII", "VII"];
+/// In minor the third, sixth and seventh are diatonic, so C in A minor is III and C# is #III
+const LOOKUP_NATURAL_MINOR: [&str; 12] =
+    ["I", "bII", "II", "III", "#III", "IV", "#IV", "V", "VI", "#VI", "VII", "#VII"];
+/// The harmonic minor's raised seventh is VII, so G in A minor is bVII
+const LOOKUP_HARMONIC_MINOR: [&str; 12] =
+    ["I", "bII", "II", "III", "#III", "IV", "#IV", "V", "VI", "#VI", "bVII", "VII"];
+
+/// Split an applied chord into its numeral and target: "V7/V" → ("V7", "V")
+/// Slashes followed by a chord tone or note name ("III/4", "V/G#") are inversions, not applied chords
+fn split_applied(numeral: &str) -> Option<(&str, &str)> {
+    let (chord, target) = numeral.split_once('/')?;
+    let degree = target.trim_start_matches(['b', '#']);
+    degree.starts_with(['I', 'V', 'i', 'v']).then_some((chord, target))
+}
 
 /// Parse a roman numeral string into its components
-/// Handles: I, ii, bVII, #iv, V7, vii°7, III/4, Imaj7, etc.
+/// Handles: I, ii, bVII, #iv, V7, vii°7, III/4, Imaj7, V7/V, etc.
 pub fn parse_roman_numeral(numeral: &str) -> MusicResult<RomanNumeralParts> {
     if numeral.is_empty() {
         return Err(MusicError::ParseError("Empty roman numeral".to_string()));
@@ -32,6 +71,13 @@ pub fn parse_roman_numeral(numeral: &str) -> MusicResult<RomanNumeralParts> {
         return Err(MusicError::ParseError("Empty roman numeral".to_string()));
     }
 
+    // Applied chords keep their target aside; the numeral is parsed on its own
+    if let Some((chord, target)) = split_applied(trimmed) {
+        parse_roman_numeral(target)?;
+        let parts = parse_roman_numeral(chord)?;
+        return Ok(RomanNumeralParts { applied_to: Some(target.to_string()), ..parts });
+    }
+
     // Handle slash chords - strip off bass notation for parsing
     let (main_part, bass_notation) = if let Some(slash_pos) = trimmed.find('/') {
         let main = &trimmed[..slash_pos];
@@ -119,12 +165,22 @@ pub fn parse_roman_numeral(numeral: &str) -> MusicResult<RomanNumeralParts> {
         is_minor,
         suffix: final_suffix,
         bass: bass_notation,
+        applied_to: None,
     })
 }
 
 /// Get note name for a scale degree in a given key
-/// Builds major scale and picks appropriate degree
-fn get_scale_degree_note(degree: u8, key: &str, use_flats: bool) -> MusicResult<String> {
+/// Builds the mode's scale and picks appropriate degree
+/// Minor scales are spelled from their tonic letter, so D minor's VI is Bb whatever `use_flats` says
+fn get_scale_degree_note(degree: u8, key: &str, mode: NumeralMode, use_flats: bool) -> MusicResult<String> {
+    if !(1..=7).contains(&degree) {
+        return Err(MusicError::ParseError(format!("Invalid scale degree: {}", degree)));
+    }
+    if mode != NumeralMode::Major {
+        note_index(key)?;
+        return Ok(scale_to_notes(key, mode.scale())?.swap_remove((degree - 1) as usize));
+    }
+
     // C major scale notes (with letters): C, D, E, F, G, A, B
     let c_major_scale_notes = ["C", "D", "E", "F", "G", "A", "B"];
 
@@ -137,11 +193,7 @@ fn get_scale_degree_note(degree: u8, key: &str, use_flats: bool) -> MusicResult<
     let major_scale = major_scale?;
 
     // Return note for requested degree (1-indexed)
-    if degree >= 1 && degree <= 7 {
-        Ok(major_scale[(degree - 1) as usize].clone())
-    } else {
-        Err(MusicError::ParseError(format!("Invalid scale degree: {}", degree)))
-    }
+    Ok(major_scale[(degree - 1) as usize].clone())
 }
 
 /// Apply an accidental to a note, preserving letter name
@@ -218,7 +270,16 @@ fn apply_accidental_to_note(note: &str, accidental: Option<Accidental>) -> Music
 
 /// Convert a roman numeral to a chord name in a given key
 /// Handles: I, ii, bVII, V7, vii°7, III/4, etc.
-pub fn roman_numeral_to_chord(numeral: &str, key: &str, use_flats: bool) -> MusicResult<String> {
+/// Degrees are read from the mode's scale: VII in A natural minor is G, in A major G#
+/// Applied chords are built in the key of their target: V7/V in C → D7, vii°7/vi in C → G#dim7
+pub fn roman_numeral_to_chord(numeral: &str, key: &str, mode: NumeralMode, use_flats: bool) -> MusicResult<String> {
+    if let Some((chord, target)) = split_applied(numeral.trim()) {
+        let target_chord = roman_numeral_to_chord(target, key, mode, use_flats)?;
+        let target_root = super::chords::parse_chord(&target_chord)?.root;
+        let target_mode = applied_mode(parse_roman_numeral(target)?.is_minor);
+        return roman_numeral_to_chord(chord, &target_root, target_mode, use_flats);
+    }
+
     let parts = parse_roman_numeral(numeral)?;
 
     let RomanNumeralParts {
@@ -227,10 +288,11 @@ pub fn roman_numeral_to_chord(numeral: &str, key: &str, use_flats: bool) -> Musi
         is_minor,
         suffix,
         bass: _,
+        applied_to: _,
     } = parts;
 
     // Get scale degree note (e.g., III in C = "E", IV in Eb = "Ab")
-    let scale_degree_note = get_scale_degree_note(degree, key, use_flats)?;
+    let scale_degree_note = get_scale_degree_note(degree, key, mode, use_flats)?;
 
     // Apply Roman numeral's accidental (b or #) to that note
     let root = apply_accidental_to_note(&scale_degree_note, accidental)?;
@@ -275,7 +337,7 @@ pub fn roman_numeral_to_chord(numeral: &str, key: &str, use_flats: bool) -> Musi
         }
     }
 
-    let mut chord = format!("{}{}", root, chord_suffix);
+    let mut chord = ChordSymbol::new(root, chord_suffix);
 
     // Convert bass notation (chord tone number) back to actual note
     if let Some(bass_notation) = bass_notation {
@@ -299,24 +361,97 @@ pub fn roman_numeral_to_chord(numeral: &str, key: &str, use_flats: bool) -> Musi
 
         if let Some(&bass_interval) = tone_to_interval.get(bass_notation) {
             // Calculate root note's semitone index for bass note calculation
-            let root_idx = note_index(&root)?;
+            let root_idx = note_index(&chord.root)?;
             let bass_note_idx = (root_idx + bass_interval) % 12;
-            let bass_note = get_preferred_note_name(bass_note_idx, key, use_flats);
-            chord = format!("{}/{}", chord, bass_note);
+            chord = chord.over(get_preferred_note_name(bass_note_idx, key, use_flats));
         } else {
             // If we can't parse bass notation, just append it as-is
-            chord = format!("{}/{}", chord, bass_notation);
+            chord = chord.over(bass_notation);
         }
     }
 
-    Ok(chord)
+    Ok(chord.to_string(SymbolStyle::default()))
+}
+
+/// Scale the chords applied to a target are built from: the target's major key, or the
+/// harmonic minor for minor targets so that leading tones are raised (vii°7/vi in C → G#dim7)
+fn applied_mode(target_is_minor: bool) -> NumeralMode {
+    if target_is_minor {
+        NumeralMode::HarmonicMinor
+    } else {
+        NumeralMode::Major
+    }
+}
+
+/// Whether the triad on a scale degree (1-7) is minor and whether it is diminished
+fn diatonic_triad_quality(mode: NumeralMode, degree: u8) -> (bool, bool) {
+    let step = |offset: u8| mode.degree_semitones((degree - 1 + offset) % 7 + 1);
+    let third = (step(2) + 12 - step(0)) % 12;
+    let fifth = (step(4) + 12 - step(0)) % 12;
+    (third == 3, fifth == 6)
+}
+
+/// Read a chromatic dominant or leading-tone chord as applied to the degree it resolves to
+/// D7 in C → V7/V, G#dim7 in C → vii°7/vi; None when the chord is diatonic, is not a
+/// dominant or leading-tone chord, or would resolve to the tonic or a diminished triad
+fn applied_chord_numeral(chord: &str, key: &str, mode: NumeralMode) -> MusicResult<Option<String>> {
+    use super::chords::parse_chord;
+    use super::intervals::{chord_to_notes, spell_interval_with_degree};
+
+    if chord.contains('/') {
+        return Ok(None);
+    }
+    let parsed = parse_chord(chord)?;
+    let key_semitone = note_index(key)?;
+    let scale: Vec<u8> = (1..=7).map(|degree| (key_semitone + mode.degree_semitones(degree)) % 12).collect();
+    let notes = chord_to_notes(chord)?;
+    let mut diatonic = true;
+    for note in &notes {
+        diatonic &= scale.contains(&note_index(note)?);
+    }
+    if diatonic {
+        return Ok(None);
+    }
+
+    // Dominants resolve a fourth up, leading-tone chords a semitone up
+    let (semitones, letters) = match parsed.suffix.as_str() {
+        "" | "7" => (5, 4),
+        "dim" | "dim7" | "m7b5" => (1, 2),
+        _ => return Ok(None),
+    };
+    let target_root = spell_interval_with_degree(&parsed.root, semitones, letters)?;
+    let target = parse_roman_numeral(&scale_numeral(&target_root, key, mode)?)?;
+    let (target_minor, target_diminished) = diatonic_triad_quality(mode, target.degree);
+    if target.accidental.is_some() || target.degree == 1 || target_diminished {
+        return Ok(None);
+    }
+
+    let target_numeral = if target_minor { MINOR_BASES } else { MAJOR_BASES }[(target.degree - 1) as usize];
+    let numeral = scale_numeral(chord, &target_root, applied_mode(target_minor))?;
+    Ok(Some(format!("{}/{}", numeral, target_numeral)))
 }
 
 /// Get the Roman numeral for a chord in a given key
 /// Uses letter-based scale degrees to preserve chord spelling:
 /// F#m in C → #iv (F is 4th degree, sharp applied)
 /// Gbm in C → bv (G is 5th degree, flat applied)
-pub fn get_chord_numeral(chord: &str, key: &str) -> MusicResult<String> {
+/// Accidentals are relative to the mode's scale: G in A natural minor → VII, not bVII
+/// With `NumeralDepth::Applied`, secondary dominants are named by their target: D7 in C → V7/V
+pub fn get_chord_numeral(chord: &str, key: &str, mode: NumeralMode, depth: NumeralDepth) -> MusicResult<String> {
+    if depth == NumeralDepth::Applied {
+        if let Some(numeral) = applied_chord_numeral(chord, key, mode)? {
+            return Ok(numeral);
+        }
+    }
+    scale_numeral(chord, key, mode)
+}
+
+// Roman numeral bases (0-indexed: I, II, III, IV, V, VI, VII)
+const MAJOR_BASES: &[&str] = &["I", "II", "III", "IV", "V", "VI", "VII"];
+const MINOR_BASES: &[&str] = &["i", "ii", "iii", "iv", "v", "vi", "vii"];
+
+/// Roman numeral measured against the key's scale, without applied-chord readings
+fn scale_numeral(chord: &str, key: &str, mode: NumeralMode) -> MusicResult<String> {
     use super::chords::parse_chord;
 
     if chord.is_empty() {
@@ -380,8 +515,7 @@ pub fn get_chord_numeral(chord: &str, key: &str) -> MusicResult<String> {
     let relative_degree = ((chord_degree as i32 - key_degree as i32).rem_euclid(7)) as usize;
 
     // Calculate expected semitone for this scale degree to determine display accidental
-    // DEGREE_SEMITONES is 1-indexed, relative_degree is 0-indexed
-    let degree_semitone_offset = DEGREE_SEMITONES[relative_degree + 1];
+    let degree_semitone_offset = mode.degree_semitones(relative_degree as u8 + 1);
     let chord_semitone = note_index(&parsed_chord.root)?;
     let key_semitone = note_index(key)?;
     let expected_semitone = (key_semitone + degree_semitone_offset) % 12;
@@ -398,10 +532,6 @@ pub fn get_chord_numeral(chord: &str, key: &str) -> MusicResult<String> {
         }
     };
 
-    // Roman numeral bases (0-indexed: I, II, III, IV, V, VI, VII)
-    const MAJOR_BASES: &[&str] = &["I", "II", "III", "IV", "V", "VI", "VII"];
-    const MINOR_BASES: &[&str] = &["i", "ii", "iii", "iv", "v", "vi", "vii"];
-
     // Determine if chord is minor (m, min, but not maj)
     let is_minor = parsed_chord.suffix.starts_with('m') && !parsed_chord.suffix.starts_with("maj");
 
@@ -423,14 +553,19 @@ pub fn get_chord_numeral(chord: &str, key: &str) -> MusicResult<String> {
     }
     
     // Add quality indicators for extended chords
-    if suffix.contains("7") && !suffix.contains("maj7") {
+    if suffix.contains("dim") {
+        // Convert to lowercase and add degree symbol (vii°, vii°7)
+        numeral = numeral.to_lowercase();
+        numeral.push('°');
+        if suffix.contains('7') {
+            numeral.push('7');
+        }
+    } else if suffix.starts_with("m7b5") {
+        numeral.push_str("ø7");
+    } else if suffix.contains("7") && !suffix.contains("maj7") {
         numeral.push('7');
     } else if suffix.contains("maj7") {
         numeral.push_str("maj7");
-    } else if suffix.contains("dim") {
-        // Convert to lowercase and add degree symbol
-        numeral = numeral.to_lowercase();
-        numeral.push('°');
     } else if suffix.contains("aug") {
         numeral.push('+');
     } else if suffix.contains("sus") {
@@ -449,23 +584,16 @@ pub fn get_chord_numeral(chord: &str, key: &str) -> MusicResult<String> {
 }
 
 /// Semitone-based Roman numeral for database lookups
-/// Matches TypeScript's chordToRoman() in convert-to-roman.ts
+/// Matches TypeScript's chordToRoman() in convert-to-roman.ts for major keys
 /// Both F#m and Gbm → #iv (semitone 6 maps to #IV)
 ///
 /// This is necessary because the database was generated using semitone-based
 /// Roman numerals, so lookups must use the same logic.
+/// Minor-key datasets are keyed against the minor scale: C in A minor is III, not bIII
 #[allow(dead_code)]
-pub fn get_chord_numeral_for_lookup(chord: &str, key: &str) -> MusicResult<String> {
+pub fn get_chord_numeral_for_lookup(chord: &str, key: &str, mode: NumeralMode) -> MusicResult<String> {
     use super::chords::parse_chord;
 
-    // Semitone-based lookup tables (match TypeScript's MAJOR_NUMERALS/MINOR_NUMERALS)
-    const LOOKUP_MAJOR: &[&str] = &[
-        "I", "bII", "II", "bIII", "III", "IV", "#IV", "V", "bVI", "VI", "bVII", "VII"
-    ];
-    const LOOKUP_MINOR: &[&str] = &[
-        "i", "bii", "ii", "biii", "iii", "iv", "#iv", "v", "bvi", "vi", "bvii", "vii"
-    ];
-
     if chord.is_empty() {
         return Err(MusicError::ParseError("Chord cannot be empty".to_string()));
     }
@@ -494,19 +622,9 @@ pub fn get_chord_numeral_for_lookup(chord: &str, key: &str) -> MusicResult<Strin
     let is_aug = suffix.to_lowercase().contains("aug") || suffix.contains('+');
 
     // Get base numeral based on quality (matches TypeScript logic)
-    let base = if is_dim {
-        // Diminished uses lowercase
-        LOOKUP_MINOR[interval]
-    } else if is_aug {
-        // Augmented uses uppercase
-        LOOKUP_MAJOR[interval]
-    } else if is_minor {
-        LOOKUP_MINOR[interval]
-    } else {
-        LOOKUP_MAJOR[interval]
-    };
-
-    let mut numeral = base.to_string();
+    // Diminished and minor use lowercase, augmented uppercase
+    let base = mode.lookup_numerals()[interval];
+    let mut numeral = if is_dim || (is_minor && !is_aug) { base.to_lowercase() } else { base.to_string() };
 
     // Format suffix to match TypeScript's formatSuffixForRoman()
     let formatted_suffix = format_suffix_for_lookup(suffix, is_dim, is_aug, is_minor);
@@ -610,9 +728,9 @@ fn get_bass_chord_tone_for_lookup(root: &str, bass: &str, _suffix: &str, is_mino
 
 /// Get display-formatted Roman numeral for UI
 /// Preserves the chord spelling - F#m → #iv, Gbm → bv
-pub fn get_display_numeral(chord: &str, key: &str) -> MusicResult<String> {
+pub fn get_display_numeral(chord: &str, key: &str, mode: NumeralMode) -> MusicResult<String> {
     // Use chord as-is - the numeral accidental is derived from the chord's spelling
-    let raw_numeral = get_chord_numeral(chord, key)?;
+    let raw_numeral = get_chord_numeral(chord, key, mode, NumeralDepth::Scale)?;
 
     // Apply display formatting (handles slash chords)
     roman_numeral_for_display(&raw_numeral, chord)
@@ -711,25 +829,25 @@ mod tests {
 
     #[test]
     fn test_roman_numeral_to_chord_simple() {
-        let result = roman_numeral_to_chord("I", "C", true).unwrap();
+        let result = roman_numeral_to_chord("I", "C", NumeralMode::Major, true).unwrap();
         assert_eq!(result, "C");
     }
 
     #[test]
     fn test_roman_numeral_to_chord_minor() {
-        let result = roman_numeral_to_chord("ii", "C", true).unwrap();
+        let result = roman_numeral_to_chord("ii", "C", NumeralMode::Major, true).unwrap();
         assert_eq!(result, "Dm");
     }
 
     #[test]
     fn test_roman_numeral_to_chord_with_accidental() {
-        let result = roman_numeral_to_chord("bVII", "C", true).unwrap();
+        let result = roman_numeral_to_chord("bVII", "C", NumeralMode::Major, true).unwrap();
         assert_eq!(result, "Bb");
     }
 
     #[test]
     fn test_roman_numeral_to_chord_with_suffix() {
-        let result = roman_numeral_to_chord("V7", "C", true).unwrap();
+        let result = roman_numeral_to_chord("V7", "C", NumeralMode::Major, true).unwrap();
         assert_eq!(result, "G7");
     }
 
@@ -743,65 +861,132 @@ mod tests {
 
     #[test]
     fn test_get_scale_degree_note() {
-        assert_eq!(get_scale_degree_note(1, "C", true).unwrap(), "C");
-        assert_eq!(get_scale_degree_note(3, "C", true).unwrap(), "E");
-        assert_eq!(get_scale_degree_note(5, "C", true).unwrap(), "G");
-        assert_eq!(get_scale_degree_note(1, "G", true).unwrap(), "G");
-        assert_eq!(get_scale_degree_note(4, "G", true).unwrap(), "C");
+        assert_eq!(get_scale_degree_note(1, "C", NumeralMode::Major, true).unwrap(), "C");
+        assert_eq!(get_scale_degree_note(3, "C", NumeralMode::Major, true).unwrap(), "E");
+        assert_eq!(get_scale_degree_note(5, "C", NumeralMode::Major, true).unwrap(), "G");
+        assert_eq!(get_scale_degree_note(1, "G", NumeralMode::Major, true).unwrap(), "G");
+        assert_eq!(get_scale_degree_note(4, "G", NumeralMode::Major, true).unwrap(), "C");
+        assert_eq!(get_scale_degree_note(6, "D", NumeralMode::NaturalMinor, false).unwrap(), "Bb");
+        assert_eq!(get_scale_degree_note(7, "A", NumeralMode::HarmonicMinor, true).unwrap(), "G#");
+    }
+
+    #[test]
+    fn test_roman_numeral_to_chord_in_minor() {
+        assert_eq!(roman_numeral_to_chord("V7", "A", NumeralMode::NaturalMinor, false).unwrap(), "E7");
+        assert_eq!(roman_numeral_to_chord("VII", "A", NumeralMode::NaturalMinor, false).unwrap(), "G");
+        assert_eq!(roman_numeral_to_chord("III", "C", NumeralMode::NaturalMinor, true).unwrap(), "Eb");
+        assert_eq!(roman_numeral_to_chord("vii°7", "A", NumeralMode::HarmonicMinor, false).unwrap(), "G#dim7");
     }
 }
 #[cfg(test)]
 mod numeral_tests {
     use super::*;
 
+    fn numeral(chord: &str, key: &str) -> String {
+        get_chord_numeral(chord, key, NumeralMode::Major, NumeralDepth::Scale).unwrap()
+    }
+
     #[test]
     fn test_sharp_fourth_vs_flat_fifth() {
         // F#m in C should be #iv (F is 4th degree, with sharp)
-        let result = get_chord_numeral("F#m", "C").unwrap();
+        let result = numeral("F#m", "C");
         assert_eq!(result, "#iv", "F#m in C should be #iv");
 
         // Gbm in C should be bv (G is 5th degree, with flat)  
-        let result = get_chord_numeral("Gbm", "C").unwrap();
+        let result = numeral("Gbm", "C");
         assert_eq!(result, "bv", "Gbm in C should be bv");
     }
 
     #[test]
     fn test_natural_degrees() {
         // C in C should be I
-        assert_eq!(get_chord_numeral("C", "C").unwrap(), "I");
+        assert_eq!(numeral("C", "C"), "I");
         // Am in C should be vi
-        assert_eq!(get_chord_numeral("Am", "C").unwrap(), "vi");
+        assert_eq!(numeral("Am", "C"), "vi");
         // G in C should be V
-        assert_eq!(get_chord_numeral("G", "C").unwrap(), "V");
+        assert_eq!(numeral("G", "C"), "V");
     }
 
     #[test]
     fn test_flat_key_degrees() {
         // In Gb major, the tonic Gb should be "I" (not "bI")
-        assert_eq!(get_chord_numeral("Gb", "Gb").unwrap(), "I", "Gb in key Gb should be I");
+        assert_eq!(numeral("Gb", "Gb"), "I", "Gb in key Gb should be I");
 
         // Ab is the natural II in Gb major
-        assert_eq!(get_chord_numeral("Ab", "Gb").unwrap(), "II", "Ab in key Gb should be II");
+        assert_eq!(numeral("Ab", "Gb"), "II", "Ab in key Gb should be II");
 
         // Bb is the natural III in Gb major
-        assert_eq!(get_chord_numeral("Bbm", "Gb").unwrap(), "iii", "Bbm in key Gb should be iii");
+        assert_eq!(numeral("Bbm", "Gb"), "iii", "Bbm in key Gb should be iii");
 
         // F is the natural VII in Gb major (no flat)
-        assert_eq!(get_chord_numeral("F", "Gb").unwrap(), "VII", "F in key Gb should be VII");
+        assert_eq!(numeral("F", "Gb"), "VII", "F in key Gb should be VII");
 
         // Db is the natural V in Gb major
-        assert_eq!(get_chord_numeral("Db", "Gb").unwrap(), "V", "Db in key Gb should be V");
+        assert_eq!(numeral("Db", "Gb"), "V", "Db in key Gb should be V");
 
         // G (natural) in key Gb should be #I (raised tonic)
-        assert_eq!(get_chord_numeral("G", "Gb").unwrap(), "#I", "G in key Gb should be #I");
+        assert_eq!(numeral("G", "Gb"), "#I", "G in key Gb should be #I");
+    }
+
+    #[test]
+    fn test_minor_key_degrees() {
+        // Against the natural minor, E7 is the dominant and G the subtonic of A minor, not bVII of C
+        assert_eq!(get_chord_numeral("E7", "A", NumeralMode::NaturalMinor, NumeralDepth::Scale).unwrap(), "V7");
+        assert_eq!(get_chord_numeral("G", "A", NumeralMode::NaturalMinor, NumeralDepth::Scale).unwrap(), "VII");
+        assert_eq!(get_chord_numeral("F", "A", NumeralMode::NaturalMinor, NumeralDepth::Scale).unwrap(), "VI");
+        assert_eq!(get_chord_numeral("G#dim", "A", NumeralMode::NaturalMinor, NumeralDepth::Scale).unwrap(), "#vii°");
+        assert_eq!(get_chord_numeral("Bb", "D", NumeralMode::NaturalMinor, NumeralDepth::Scale).unwrap(), "VI");
+
+        // The harmonic minor raises the seventh degree instead
+        assert_eq!(get_chord_numeral("G#dim", "A", NumeralMode::HarmonicMinor, NumeralDepth::Scale).unwrap(), "vii°");
+        assert_eq!(get_chord_numeral("G", "A", NumeralMode::HarmonicMinor, NumeralDepth::Scale).unwrap(), "bVII");
+
+        assert_eq!(get_display_numeral("E/G#", "A", NumeralMode::HarmonicMinor).unwrap(), "V/G#");
+        assert_eq!(NumeralMode::from_key("F#m"), ("F#", NumeralMode::NaturalMinor));
+        assert_eq!(NumeralMode::from_key("Eb"), ("Eb", NumeralMode::Major));
+    }
+
+    #[test]
+    fn test_applied_chords() {
+        let applied = |chord: &str, key: &str| {
+            get_chord_numeral(chord, key, NumeralMode::Major, NumeralDepth::Applied).unwrap()
+        };
+        assert_eq!(applied("D7", "C"), "V7/V");
+        assert_eq!(applied("A", "C"), "V/ii");
+        assert_eq!(applied("G#dim7", "C"), "vii°7/vi");
+        assert_eq!(applied("F#m7b5", "C"), "viiø7/V");
+        assert_eq!(applied("C7", "C"), "V7/IV");
+        assert_eq!(applied("F7", "Eb"), "V7/V");
+
+        // Diatonic chords, chords aimed at the tonic and other chromatic chords keep their plain numeral
+        assert_eq!(applied("G7", "C"), "V7");
+        assert_eq!(applied("Bdim", "C"), "vii°");
+        assert_eq!(applied("Bb", "C"), "bVII");
+        assert_eq!(applied("E7", "A"), "V7");
+        assert_eq!(applied("A7", "Eb"), "#IV7");
+        assert_eq!(numeral("D7", "C"), "II7");
+    }
+
+    #[test]
+    fn test_applied_numerals_to_chords() {
+        let chord = |numeral: &str| roman_numeral_to_chord(numeral, "C", NumeralMode::Major, true).unwrap();
+        assert_eq!(chord("V7/V"), "D7");
+        assert_eq!(chord("V/ii"), "A");
+        assert_eq!(chord("vii°7/vi"), "G#dim7");
+        assert_eq!(chord("III/4"), "E/A");
+        assert_eq!(roman_numeral_to_chord("V7/iv", "A", NumeralMode::NaturalMinor, false).unwrap(), "A7");
+
+        let parts = parse_roman_numeral("V7/V").unwrap();
+        assert_eq!((parts.degree, parts.suffix.as_str(), parts.applied_to.as_deref()), (5, "7", Some("V")));
+        assert!(parse_roman_numeral("V7/x").unwrap().applied_to.is_none());
     }
 
     #[test]
     fn test_lookup_enharmonic_equivalents() {
         // For database lookups, F#m and Gbm should BOTH map to #iv
         // (because database was generated with semitone-based logic)
-        let f_sharp = get_chord_numeral_for_lookup("F#m", "C").unwrap();
-        let g_flat = get_chord_numeral_for_lookup("Gbm", "C").unwrap();
+        let f_sharp = get_chord_numeral_for_lookup("F#m", "C", NumeralMode::Major).unwrap();
+        let g_flat = get_chord_numeral_for_lookup("Gbm", "C", NumeralMode::Major).unwrap();
 
         assert_eq!(f_sharp, "#iv", "F#m lookup should be #iv");
         assert_eq!(g_flat, "#iv", "Gbm lookup should also be #iv (same semitone)");
@@ -811,27 +996,46 @@ mod numeral_tests {
     #[test]
     fn test_lookup_basic_numerals() {
         // Basic diatonic chords
-        assert_eq!(get_chord_numeral_for_lookup("C", "C").unwrap(), "I");
-        assert_eq!(get_chord_numeral_for_lookup("Dm", "C").unwrap(), "ii");
-        assert_eq!(get_chord_numeral_for_lookup("Em", "C").unwrap(), "iii");
-        assert_eq!(get_chord_numeral_for_lookup("F", "C").unwrap(), "IV");
-        assert_eq!(get_chord_numeral_for_lookup("G", "C").unwrap(), "V");
-        assert_eq!(get_chord_numeral_for_lookup("Am", "C").unwrap(), "vi");
+        assert_eq!(get_chord_numeral_for_lookup("C", "C", NumeralMode::Major).unwrap(), "I");
+        assert_eq!(get_chord_numeral_for_lookup("Dm", "C", NumeralMode::Major).unwrap(), "ii");
+        assert_eq!(get_chord_numeral_for_lookup("Em", "C", NumeralMode::Major).unwrap(), "iii");
+        assert_eq!(get_chord_numeral_for_lookup("F", "C", NumeralMode::Major).unwrap(), "IV");
+        assert_eq!(get_chord_numeral_for_lookup("G", "C", NumeralMode::Major).unwrap(), "V");
+        assert_eq!(get_chord_numeral_for_lookup("Am", "C", NumeralMode::Major).unwrap(), "vi");
 
         // Non-diatonic (chromatic) chords
-        assert_eq!(get_chord_numeral_for_lookup("Bb", "C").unwrap(), "bVII");
-        assert_eq!(get_chord_numeral_for_lookup("Eb", "C").unwrap(), "bIII");
-        assert_eq!(get_chord_numeral_for_lookup("Ab", "C").unwrap(), "bVI");
+        assert_eq!(get_chord_numeral_for_lookup("Bb", "C", NumeralMode::Major).unwrap(), "bVII");
+        assert_eq!(get_chord_numeral_for_lookup("Eb", "C", NumeralMode::Major).unwrap(), "bIII");
+        assert_eq!(get_chord_numeral_for_lookup("Ab", "C", NumeralMode::Major).unwrap(), "bVI");
     }
 
     #[test]
     fn test_lookup_with_quality() {
         // 7th chords
-        assert_eq!(get_chord_numeral_for_lookup("Dm7", "C").unwrap(), "ii7");
-        assert_eq!(get_chord_numeral_for_lookup("G7", "C").unwrap(), "V7");
+        assert_eq!(get_chord_numeral_for_lookup("Dm7", "C", NumeralMode::Major).unwrap(), "ii7");
+        assert_eq!(get_chord_numeral_for_lookup("G7", "C", NumeralMode::Major).unwrap(), "V7");
 
         // Major 7th
-        assert_eq!(get_chord_numeral_for_lookup("Cmaj7", "C").unwrap(), "IM7");
-        assert_eq!(get_chord_numeral_for_lookup("Fmaj7", "C").unwrap(), "IVM7");
+        assert_eq!(get_chord_numeral_for_lookup("Cmaj7", "C", NumeralMode::Major).unwrap(), "IM7");
+        assert_eq!(get_chord_numeral_for_lookup("Fmaj7", "C", NumeralMode::Major).unwrap(), "IVM7");
+    }
+
+    #[test]
+    fn test_lookup_in_minor_keys() {
+        let lookup = |chord, mode| get_chord_numeral_for_lookup(chord, "A", mode).unwrap();
+        assert_eq!(lookup("Am", NumeralMode::NaturalMinor), "i");
+        assert_eq!(lookup("Bdim", NumeralMode::NaturalMinor), "ii°");
+        assert_eq!(lookup("C", NumeralMode::NaturalMinor), "III");
+        assert_eq!(lookup("Dm7", NumeralMode::NaturalMinor), "iv7");
+        assert_eq!(lookup("F", NumeralMode::NaturalMinor), "VI");
+        assert_eq!(lookup("G", NumeralMode::NaturalMinor), "VII");
+
+        // Chromatic chords are measured against the minor scale, not the parallel major
+        assert_eq!(lookup("C#m", NumeralMode::NaturalMinor), "#iii");
+        assert_eq!(lookup("E7", NumeralMode::NaturalMinor), "V7");
+        assert_eq!(lookup("G#dim7", NumeralMode::NaturalMinor), "#vii°7");
+        assert_eq!(lookup("G#dim7", NumeralMode::HarmonicMinor), "vii°7");
+        assert_eq!(lookup("G", NumeralMode::HarmonicMinor), "bVII");
+        assert_eq!(lookup("C", NumeralMode::Major), "bIII");
     }
 }
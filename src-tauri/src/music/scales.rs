@@ -0,0 +1,155 @@
+// Scale generation
+// Spells major, minor, modal, pentatonic and blues scales with the letter names each key calls for
+
+use serde::{Deserialize, Serialize};
+
+use super::intervals::spell_interval_with_degree;
+use super::notes::preferred_spelling;
+use super::types::MusicResult;
+
+/// Scale types supported for scale-building exercises
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleType {
+    Major,
+    NaturalMinor,
+    HarmonicMinor,
+    MelodicMinor,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Locrian,
+    MajorPentatonic,
+    MinorPentatonic,
+    Blues,
+}
+
+impl ScaleType {
+    pub const ALL: [ScaleType; 12] = [
+        ScaleType::Major,
+        ScaleType::NaturalMinor,
+        ScaleType::HarmonicMinor,
+        ScaleType::MelodicMinor,
+        ScaleType::Dorian,
+        ScaleType::Phrygian,
+        ScaleType::Lydian,
+        ScaleType::Mixolydian,
+        ScaleType::Locrian,
+        ScaleType::MajorPentatonic,
+        ScaleType::MinorPentatonic,
+        ScaleType::Blues,
+    ];
+
+    /// Parse a scale name, accepting common synonyms ("ionian", "aeolian", "minor")
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalized = name.trim().to_lowercase().replace([' ', '-'], "_");
+        let scale = match normalized.as_str() {
+            "major" | "ionian" => ScaleType::Major,
+            "minor" | "natural_minor" | "aeolian" => ScaleType::NaturalMinor,
+            "harmonic_minor" => ScaleType::HarmonicMinor,
+            "melodic_minor" => ScaleType::MelodicMinor,
+            "dorian" => ScaleType::Dorian,
+            "phrygian" => ScaleType::Phrygian,
+            "lydian" => ScaleType::Lydian,
+            "mixolydian" => ScaleType::Mixolydian,
+            "locrian" => ScaleType::Locrian,
+            "major_pentatonic" | "pentatonic" => ScaleType::MajorPentatonic,
+            "minor_pentatonic" => ScaleType::MinorPentatonic,
+            "blues" | "minor_blues" => ScaleType::Blues,
+            _ => return None,
+        };
+        Some(scale)
+    }
+
+    /// Display name ("harmonic minor")
+    pub fn name(self) -> &'static str {
+        match self {
+            ScaleType::Major => "major",
+            ScaleType::NaturalMinor => "natural minor",
+            ScaleType::HarmonicMinor => "harmonic minor",
+            ScaleType::MelodicMinor => "melodic minor",
+            ScaleType::Dorian => "dorian",
+            ScaleType::Phrygian => "phrygian",
+            ScaleType::Lydian => "lydian",
+            ScaleType::Mixolydian => "mixolydian",
+            ScaleType::Locrian => "locrian",
+            ScaleType::MajorPentatonic => "major pentatonic",
+            ScaleType::MinorPentatonic => "minor pentatonic",
+            ScaleType::Blues => "blues",
+        }
+    }
+
+    /// Scale steps as (semitones above the tonic, scale degree) pairs, ascending
+    /// Degrees pick the letter name, so F# major spells E# rather than F
+    /// Melodic minor is the ascending (jazz) form
+    pub fn interval_specs(self) -> &'static [(u8, u8)] {
+        match self {
+            ScaleType::Major => &[(0, 1), (2, 2), (4, 3), (5, 4), (7, 5), (9, 6), (11, 7)],
+            ScaleType::NaturalMinor => &[(0, 1), (2, 2), (3, 3), (5, 4), (7, 5), (8, 6), (10, 7)],
+            ScaleType::HarmonicMinor => &[(0, 1), (2, 2), (3, 3), (5, 4), (7, 5), (8, 6), (11, 7)],
+            ScaleType::MelodicMinor => &[(0, 1), (2, 2), (3, 3), (5, 4), (7, 5), (9, 6), (11, 7)],
+            ScaleType::Dorian => &[(0, 1), (2, 2), (3, 3), (5, 4), (7, 5), (9, 6), (10, 7)],
+            ScaleType::Phrygian => &[(0, 1), (1, 2), (3, 3), (5, 4), (7, 5), (8, 6), (10, 7)],
+            ScaleType::Lydian => &[(0, 1), (2, 2), (4, 3), (6, 4), (7, 5), (9, 6), (11, 7)],
+            ScaleType::Mixolydian => &[(0, 1), (2, 2), (4, 3), (5, 4), (7, 5), (9, 6), (10, 7)],
+            ScaleType::Locrian => &[(0, 1), (1, 2), (3, 3), (5, 4), (6, 5), (8, 6), (10, 7)],
+            ScaleType::MajorPentatonic => &[(0, 1), (2, 2), (4, 3), (7, 5), (9, 6)],
+            ScaleType::MinorPentatonic => &[(0, 1), (3, 3), (5, 4), (7, 5), (10, 7)],
+            // The blue note is spelled as a flat fifth (C blues: C Eb F Gb G Bb)
+            ScaleType::Blues => &[(0, 1), (3, 3), (5, 4), (6, 5), (7, 5), (10, 7)],
+        }
+    }
+}
+
+/// Spell a scale ascending from its tonic, one octave without the repeated tonic
+/// Example: scale_to_notes("F#", ScaleType::Major) → F# G# A# B C# D# E#
+pub fn scale_to_notes(tonic: &str, scale: ScaleType) -> MusicResult<Vec<String>> {
+    scale
+        .interval_specs()
+        .iter()
+        .map(|&(semitones, degree)| spell_interval_with_degree(tonic, semitones, degree).map(|note| preferred_spelling(&note)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notes(tonic: &str, scale: ScaleType) -> Vec<String> {
+        scale_to_notes(tonic, scale).unwrap()
+    }
+
+    #[test]
+    fn test_major_scales_spell_every_letter_once() {
+        assert_eq!(notes("C", ScaleType::Major), vec!["C", "D", "E", "F", "G", "A", "B"]);
+        assert_eq!(notes("F#", ScaleType::Major), vec!["F#", "G#", "A#", "B", "C#", "D#", "E#"]);
+        assert_eq!(notes("Gb", ScaleType::Major), vec!["Gb", "Ab", "Bb", "Cb", "Db", "Eb", "F"]);
+    }
+
+    #[test]
+    fn test_minor_forms_and_modes() {
+        assert_eq!(notes("A", ScaleType::NaturalMinor), vec!["A", "B", "C", "D", "E", "F", "G"]);
+        assert_eq!(notes("G#", ScaleType::HarmonicMinor), vec!["G#", "A#", "B", "C#", "D#", "E", "F##"]);
+        assert_eq!(notes("C", ScaleType::MelodicMinor), vec!["C", "D", "Eb", "F", "G", "A", "B"]);
+        assert_eq!(notes("D", ScaleType::Dorian), vec!["D", "E", "F", "G", "A", "B", "C"]);
+        assert_eq!(notes("F", ScaleType::Lydian), vec!["F", "G", "A", "B", "C", "D", "E"]);
+        assert_eq!(notes("B", ScaleType::Locrian), vec!["B", "C", "D", "E", "F", "G", "A"]);
+    }
+
+    #[test]
+    fn test_pentatonic_and_blues() {
+        assert_eq!(notes("G", ScaleType::MajorPentatonic), vec!["G", "A", "B", "D", "E"]);
+        assert_eq!(notes("E", ScaleType::MinorPentatonic), vec!["E", "G", "A", "B", "D"]);
+        assert_eq!(notes("C", ScaleType::Blues), vec!["C", "Eb", "F", "Gb", "G", "Bb"]);
+    }
+
+    #[test]
+    fn test_scale_names() {
+        assert_eq!(ScaleType::from_name("Harmonic Minor"), Some(ScaleType::HarmonicMinor));
+        assert_eq!(ScaleType::from_name("aeolian"), Some(ScaleType::NaturalMinor));
+        assert_eq!(ScaleType::from_name("melodic-minor"), Some(ScaleType::MelodicMinor));
+        assert_eq!(ScaleType::from_name("bebop"), None);
+        assert!(ScaleType::ALL.iter().all(|s| ScaleType::from_name(s.name()) == Some(*s)));
+    }
+}
@@ -0,0 +1,266 @@
+// Song editing and playback
+// Edits to a song's sections, and the flattened chord list the sequencer plays
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::tempo::TempoMap;
+use super::types::{MusicError, MusicResult};
+use crate::notation::beaming::parse_time_signature;
+use crate::types::song::{MeasureChange, SectionKind, Song, SongChord, SongMarker, SongSection};
+
+/// Section contents sent by the frontend; ids are assigned by the backend
+#[derive(Debug, Clone, Deserialize)]
+pub struct SectionInput {
+    pub kind: SectionKind,
+    #[serde(default)]
+    pub label: Option<String>,
+    pub key: String,
+    pub tempo: f32,
+    #[serde(default)]
+    pub rehearsal_mark: Option<String>,
+    #[serde(default)]
+    pub chords: Vec<SongChord>,
+    #[serde(default)]
+    pub markers: Vec<SongMarker>,
+    #[serde(default)]
+    pub changes: Vec<MeasureChange>,
+    #[serde(default)]
+    pub show_analysis: bool,
+}
+
+/// An edit applied to a song
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SongEdit {
+    SetTitle { title: String },
+    SetTimeSignature { time_signature: String },
+    /// Insert a section, at the end when no index is given
+    AddSection { index: Option<usize>, section: SectionInput },
+    /// Replace a section's contents, keeping its id
+    UpdateSection { id: String, section: SectionInput },
+    RemoveSection { id: String },
+    MoveSection { id: String, to: usize },
+    /// Insert a copy of a section right after it (e.g. a repeated chorus)
+    DuplicateSection { id: String },
+}
+
+/// Where a played chord comes from in the song
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SongPosition {
+    pub section_id: String,
+    pub chord_index: usize,
+}
+
+/// Chords of a song in playing order, with the tempo and meter they are played at
+#[derive(Debug, Clone, PartialEq)]
+pub struct SongPlayback {
+    pub tempo: TempoMap,
+    pub chords: Vec<SongChord>,
+    pub positions: Vec<SongPosition>,
+}
+
+fn check_section(input: &SectionInput) -> MusicResult<()> {
+    if !(input.tempo > 0.0 && input.tempo.is_finite()) {
+        return Err(MusicError::ParseError(format!("Invalid tempo: {}", input.tempo)));
+    }
+    if let Some(chord) = input.chords.iter().find(|c| !(c.beats > 0.0 && c.beats.is_finite())) {
+        return Err(MusicError::ParseError(format!("Invalid length for {}: {} beats", chord.chord, chord.beats)));
+    }
+    if let Some(marker) = input.markers.iter().find(|m| m.chord_index >= input.chords.len()) {
+        return Err(MusicError::ParseError(format!(
+            "Marker \"{}\" is on chord {} of a section with {} chords",
+            marker.label,
+            marker.chord_index,
+            input.chords.len()
+        )));
+    }
+    for change in &input.changes {
+        if change.measure == 0 {
+            return Err(MusicError::ParseError("Measures are counted from 1".to_string()));
+        }
+        if let Some(tempo) = change.tempo.filter(|tempo| !(*tempo > 0.0 && tempo.is_finite())) {
+            return Err(MusicError::ParseError(format!("Invalid tempo: {}", tempo)));
+        }
+        if let Some(time_signature) = &change.time_signature {
+            parse_time_signature(time_signature)?;
+        }
+    }
+    Ok(())
+}
+
+fn build_section(id: String, input: SectionInput) -> SongSection {
+    SongSection {
+        id,
+        kind: input.kind,
+        label: input.label,
+        key: input.key,
+        tempo: input.tempo,
+        rehearsal_mark: input.rehearsal_mark,
+        chords: input.chords,
+        markers: input.markers,
+        changes: input.changes,
+        show_analysis: input.show_analysis,
+    }
+}
+
+fn section_index(song: &Song, id: &str) -> MusicResult<usize> {
+    song.sections
+        .iter()
+        .position(|section| section.id == id)
+        .ok_or_else(|| MusicError::DataLookupFailed(format!("section {}", id)))
+}
+
+/// Apply an edit to a song
+pub fn apply_edit(song: &mut Song, edit: SongEdit) -> MusicResult<()> {
+    match edit {
+        SongEdit::SetTitle { title } => song.title = title,
+        SongEdit::SetTimeSignature { time_signature } => {
+            parse_time_signature(&time_signature)?;
+            song.time_signature = time_signature;
+        }
+        SongEdit::AddSection { index, section } => {
+            check_section(&section)?;
+            let index = index.unwrap_or(song.sections.len());
+            if index > song.sections.len() {
+                return Err(MusicError::ParseError(format!(
+                    "Index {} out of range for song with {} sections",
+                    index,
+                    song.sections.len()
+                )));
+            }
+            song.sections.insert(index, build_section(Uuid::new_v4().to_string(), section));
+        }
+        SongEdit::UpdateSection { id, section } => {
+            check_section(&section)?;
+            let index = section_index(song, &id)?;
+            song.sections[index] = build_section(id, section);
+        }
+        SongEdit::RemoveSection { id } => {
+            let index = section_index(song, &id)?;
+            song.sections.remove(index);
+        }
+        SongEdit::MoveSection { id, to } => {
+            let index = section_index(song, &id)?;
+            let section = song.sections.remove(index);
+            song.sections.insert(to.min(song.sections.len()), section);
+        }
+        SongEdit::DuplicateSection { id } => {
+            let index = section_index(song, &id)?;
+            let copy = SongSection { id: Uuid::new_v4().to_string(), ..song.sections[index].clone() };
+            song.sections.insert(index + 1, copy);
+        }
+    }
+    Ok(())
+}
+
+fn section_beats(section: &SongSection) -> f64 {
+    section.chords.iter().map(|chord| chord.beats as f64).sum()
+}
+
+/// Tempo and meter over a whole song, in beats from its start
+/// Each section starts at its own tempo and then applies its measure changes; meter changes hold
+/// into later sections
+pub fn tempo_map(song: &Song) -> MusicResult<TempoMap> {
+    let first = song.sections.first().ok_or_else(|| MusicError::ParseError("Song has no sections".to_string()))?;
+    let mut tempo = TempoMap::new(first.tempo, &song.time_signature)?;
+    let mut beat = 0.0;
+    for section in &song.sections {
+        let end = beat + section_beats(section);
+        tempo.change_at(beat, Some(section.tempo), None)?;
+        tempo.add_measure_changes(beat, &section.changes, end).map_err(|e| {
+            MusicError::ParseError(format!("{} in section \"{}\"", e, section.display_name()))
+        })?;
+        beat = end;
+    }
+    Ok(tempo)
+}
+
+/// Flatten a song for the sequencer
+pub fn playback(song: &Song) -> MusicResult<SongPlayback> {
+    if song.sections.iter().all(|section| section.chords.is_empty()) {
+        return Err(MusicError::ParseError("Song has no chords".to_string()));
+    }
+
+    let mut chords = Vec::new();
+    let mut positions = Vec::new();
+    for section in &song.sections {
+        for (chord_index, chord) in section.chords.iter().enumerate() {
+            chords.push(chord.clone());
+            positions.push(SongPosition { section_id: section.id.clone(), chord_index });
+        }
+    }
+    Ok(SongPlayback { tempo: tempo_map(song)?, chords, positions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(kind: SectionKind, tempo: f32, chords: &[&str]) -> SectionInput {
+        SectionInput {
+            kind,
+            label: None,
+            key: "C".to_string(),
+            tempo,
+            rehearsal_mark: None,
+            chords: chords.iter().map(|c| SongChord { chord: c.to_string(), beats: 4.0 }).collect(),
+            markers: Vec::new(),
+            changes: Vec::new(),
+            show_analysis: false,
+        }
+    }
+
+    fn add_section(song: &mut Song, section: SectionInput) {
+        apply_edit(song, SongEdit::AddSection { index: None, section }).unwrap();
+    }
+
+    #[test]
+    fn test_section_edits() {
+        let mut song = Song::default();
+        add_section(&mut song, section(SectionKind::Verse, 100.0, &["C", "G"]));
+        add_section(&mut song, section(SectionKind::Chorus, 100.0, &["F"]));
+        let verse = song.sections[0].id.clone();
+        let chorus = song.sections[1].id.clone();
+
+        apply_edit(&mut song, SongEdit::DuplicateSection { id: chorus.clone() }).unwrap();
+        apply_edit(&mut song, SongEdit::MoveSection { id: verse.clone(), to: 2 }).unwrap();
+        let kinds: Vec<SectionKind> = song.sections.iter().map(|s| s.kind).collect();
+        assert_eq!(kinds, vec![SectionKind::Chorus, SectionKind::Chorus, SectionKind::Verse]);
+        assert_ne!(song.sections[0].id, song.sections[1].id);
+
+        apply_edit(&mut song, SongEdit::RemoveSection { id: chorus }).unwrap();
+        assert_eq!(song.sections.len(), 2);
+        assert!(apply_edit(&mut song, SongEdit::RemoveSection { id: "missing".to_string() }).is_err());
+
+        let mut bad = section(SectionKind::Bridge, 100.0, &["C"]);
+        bad.markers.push(SongMarker { chord_index: 3, label: "Coda".to_string() });
+        assert!(apply_edit(&mut song, SongEdit::UpdateSection { id: verse, section: bad }).is_err());
+        assert!(apply_edit(&mut song, SongEdit::SetTimeSignature { time_signature: "5/x".to_string() }).is_err());
+    }
+
+    #[test]
+    fn test_playback_follows_section_tempos_and_changes() {
+        let mut song = Song::default();
+        let mut verse = section(SectionKind::Verse, 120.0, &["C", "F", "G"]);
+        verse.changes.push(MeasureChange { measure: 3, tempo: None, time_signature: Some("3/4".to_string()) });
+        add_section(&mut song, verse);
+        add_section(&mut song, section(SectionKind::Outro, 60.0, &["G", "C"]));
+
+        let playback = playback(&song).unwrap();
+        let beats: Vec<f32> = playback.chords.iter().map(|c| c.beats).collect();
+        assert_eq!(beats, vec![4.0; 5]);
+        assert_eq!(playback.positions[4], SongPosition { section_id: song.sections[1].id.clone(), chord_index: 1 });
+
+        // The 3/4 bar carries into the outro, which slows down
+        let segments: Vec<(f64, f32, u32)> =
+            playback.tempo.segments().iter().map(|s| (s.beat, s.bpm, s.beats_per_bar)).collect();
+        assert_eq!(segments, vec![(0.0, 120.0, 4), (8.0, 120.0, 3), (12.0, 60.0, 3)]);
+        assert!(super::playback(&Song::default()).is_err());
+
+        let mut late = section(SectionKind::Bridge, 100.0, &["C"]);
+        late.changes.push(MeasureChange { measure: 3, tempo: Some(90.0), time_signature: None });
+        add_section(&mut song, late);
+        assert!(tempo_map(&song).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+// Tempo maps
+// Tempo and meter over a progression, counted in beats, for timing playback and click tracks
+// and for placing tempo and time signature marks
+
+use std::time::Duration;
+
+use super::types::{MusicError, MusicResult};
+use crate::notation::beaming::parse_time_signature;
+use crate::types::song::MeasureChange;
+
+/// Tempo and meter from one beat of a progression until the next segment
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TempoSegment {
+    /// Beat the segment starts on, counted from the start of the progression
+    pub beat: f64,
+    /// Beats per minute, counted in the meter's beat unit
+    pub bpm: f32,
+    pub beats_per_bar: u32,
+    pub beat_unit: u32,
+}
+
+impl TempoSegment {
+    fn seconds_per_beat(&self) -> f64 {
+        60.0 / self.bpm as f64
+    }
+}
+
+/// Tempo and meter segments in beat order; the first starts at beat 0
+#[derive(Debug, Clone, PartialEq)]
+pub struct TempoMap {
+    segments: Vec<TempoSegment>,
+}
+
+fn check_bpm(bpm: f32) -> MusicResult<f32> {
+    if bpm > 0.0 && bpm.is_finite() {
+        Ok(bpm)
+    } else {
+        Err(MusicError::ParseError(format!("Invalid tempo: {}", bpm)))
+    }
+}
+
+impl TempoMap {
+    /// A single tempo and meter throughout
+    pub fn new(bpm: f32, time_signature: &str) -> MusicResult<Self> {
+        let (beats_per_bar, beat_unit) = parse_time_signature(time_signature)?;
+        Ok(Self { segments: vec![TempoSegment { beat: 0.0, bpm: check_bpm(bpm)?, beats_per_bar, beat_unit }] })
+    }
+
+    pub fn segments(&self) -> &[TempoSegment] {
+        &self.segments
+    }
+
+    fn last(&self) -> &TempoSegment {
+        self.segments.last().expect("tempo map has a first segment")
+    }
+
+    /// Change tempo, meter or both from a beat onwards
+    /// Changes are added in order; one on the beat of the last segment updates that segment
+    pub fn change_at(&mut self, beat: f64, bpm: Option<f32>, time_signature: Option<&str>) -> MusicResult<()> {
+        let last = *self.last();
+        if beat < last.beat {
+            let message = format!("Tempo change at beat {} comes before beat {}", beat, last.beat);
+            return Err(MusicError::ParseError(message));
+        }
+        let (beats_per_bar, beat_unit) = match time_signature {
+            Some(time_signature) => parse_time_signature(time_signature)?,
+            None => (last.beats_per_bar, last.beat_unit),
+        };
+        let segment = TempoSegment {
+            beat,
+            bpm: bpm.map(check_bpm).transpose()?.unwrap_or(last.bpm),
+            beats_per_bar,
+            beat_unit,
+        };
+
+        if beat == last.beat {
+            *self.segments.last_mut().expect("tempo map has a first segment") = segment;
+        } else if segment.bpm != last.bpm || (beats_per_bar, beat_unit) != (last.beats_per_bar, last.beat_unit) {
+            self.segments.push(segment);
+        }
+        Ok(())
+    }
+
+    /// Add changes at measures counted from 1 at `from_beat`, in the meter in effect there
+    /// Every change must fall before `end_beat`
+    pub fn add_measure_changes(&mut self, from_beat: f64, changes: &[MeasureChange], end_beat: f64) -> MusicResult<()> {
+        let mut changes: Vec<&MeasureChange> = changes.iter().collect();
+        changes.sort_by_key(|change| change.measure);
+
+        let (mut measure, mut beat) = (1, from_beat);
+        for change in changes {
+            if change.measure == 0 {
+                return Err(MusicError::ParseError("Measures are counted from 1".to_string()));
+            }
+            beat += (change.measure - measure) as f64 * self.last().beats_per_bar as f64;
+            measure = change.measure;
+            if beat >= end_beat {
+                return Err(MusicError::ParseError(format!("Measure {} is past the end of the progression", measure)));
+            }
+            self.change_at(beat, change.tempo, change.time_signature.as_deref())?;
+        }
+        Ok(())
+    }
+
+    /// Time from the start of the progression to a beat
+    pub fn time_at(&self, beat: f64) -> Duration {
+        let mut seconds = 0.0;
+        for (index, segment) in self.segments.iter().enumerate() {
+            let end = self.segments.get(index + 1).map_or(f64::INFINITY, |next| next.beat).min(beat);
+            if end <= segment.beat {
+                break;
+            }
+            seconds += (end - segment.beat) * segment.seconds_per_beat();
+        }
+        Duration::from_secs_f64(seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(measure: u32, tempo: Option<f32>, time_signature: Option<&str>) -> MeasureChange {
+        MeasureChange { measure, tempo, time_signature: time_signature.map(str::to_string) }
+    }
+
+    #[test]
+    fn test_measure_changes_follow_the_meter() {
+        let mut tempo = TempoMap::new(120.0, "4/4").unwrap();
+        let changes = [change(3, None, Some("3/4")), change(5, Some(60.0), None)];
+        tempo.add_measure_changes(0.0, &changes, 32.0).unwrap();
+
+        // Two bars of 4/4, then two of 3/4
+        let beats: Vec<f64> = tempo.segments().iter().map(|segment| segment.beat).collect();
+        assert_eq!(beats, vec![0.0, 8.0, 14.0]);
+        assert_eq!(tempo.segments()[1].beats_per_bar, 3);
+        assert_eq!(tempo.segments()[2].bpm, 60.0);
+
+        assert!(tempo.add_measure_changes(14.0, &[change(0, Some(90.0), None)], 32.0).is_err());
+        assert!(tempo.add_measure_changes(14.0, &[change(20, Some(90.0), None)], 32.0).is_err());
+        assert!(TempoMap::new(0.0, "4/4").is_err());
+    }
+
+    #[test]
+    fn test_time_across_tempo_changes() {
+        let mut tempo = TempoMap::new(120.0, "4/4").unwrap();
+        tempo.change_at(4.0, Some(60.0), None).unwrap();
+
+        assert_eq!(tempo.time_at(2.0), Duration::from_secs(1));
+        assert_eq!(tempo.time_at(6.0), Duration::from_secs(4));
+
+        // A change that repeats the current tempo adds no segment
+        tempo.change_at(8.0, Some(60.0), Some("4/4")).unwrap();
+        assert_eq!(tempo.segments().len(), 2);
+        assert!(tempo.change_at(2.0, Some(90.0), None).is_err());
+    }
+}
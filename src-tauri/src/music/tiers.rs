@@ -0,0 +1,220 @@
+// Chord tier classification
+// Assigns Safe/Colorful/Bold tiers used to color chord blocks on the canvas
+
+use serde::{Deserialize, Serialize};
+use std::sync::{LazyLock, RwLock};
+
+use super::chords::parse_chord;
+use super::completion::transition_weight;
+use super::intervals::chord_to_notes;
+use super::notes::note_index;
+use super::types::{MusicError, MusicResult, Tier};
+
+/// Major and natural minor scales as semitones above the tonic
+pub const MAJOR_SCALE: [u8; 7] = [0, 2, 4, 5, 7, 9, 11];
+pub const MINOR_SCALE: [u8; 7] = [0, 2, 3, 5, 7, 8, 10];
+
+/// Probabilities that decide the tier of a diatonic move from the previous chord
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TierThresholds {
+    /// Moves at least this likely are Safe
+    pub safe: f32,
+    /// Less likely moves are Colorful down to this probability, and Bold below it
+    pub colorful: f32,
+}
+
+impl Default for TierThresholds {
+    /// Unlikely diatonic moves are Colorful, never Bold
+    fn default() -> Self {
+        Self { safe: 0.15, colorful: 0.0 }
+    }
+}
+
+impl TierThresholds {
+    /// Check both are probabilities, with the Colorful threshold not above the Safe one
+    pub fn validate(&self) -> MusicResult<()> {
+        let in_range = |p: f32| (0.0..=1.0).contains(&p);
+        if in_range(self.safe) && in_range(self.colorful) && self.colorful <= self.safe {
+            Ok(())
+        } else {
+            Err(MusicError::ParseError(format!(
+                "Tier thresholds must satisfy 0 <= colorful ({}) <= safe ({}) <= 1",
+                self.colorful, self.safe
+            )))
+        }
+    }
+}
+
+static TIER_THRESHOLDS: LazyLock<RwLock<TierThresholds>> = LazyLock::new(|| RwLock::new(TierThresholds::default()));
+
+/// Replace the thresholds used by classify_tier
+pub fn set_tier_thresholds(thresholds: TierThresholds) {
+    if let Ok(mut current) = TIER_THRESHOLDS.write() {
+        *current = thresholds;
+    }
+}
+
+/// Thresholds currently used by classify_tier
+pub fn tier_thresholds() -> TierThresholds {
+    TIER_THRESHOLDS.read().map(|thresholds| *thresholds).unwrap_or_default()
+}
+
+/// How a chord relates to the key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyRelation {
+    /// Every note belongs to the key
+    Diatonic,
+    /// Every note belongs to the parallel major/minor (modal mixture)
+    Borrowed,
+    Chromatic,
+}
+
+/// Tier assigned to a chord with the facts that decided it
+#[derive(Debug, Clone, Serialize)]
+pub struct TierClassification {
+    pub tier: Tier,
+    pub relation: KeyRelation,
+    /// Likelihood of moving here from the previous chord (diatonic moves only)
+    pub probability: Option<f32>,
+}
+
+/// Tonic pitch class and whether the key is minor ("Am", "F#m")
+pub fn parse_key(key: &str) -> MusicResult<(u8, bool)> {
+    let (tonic, minor) = match key.strip_suffix('m') {
+        Some(tonic) => (tonic, true),
+        None => (key, false),
+    };
+    Ok((note_index(tonic).map_err(|_| MusicError::InvalidKey(key.to_string()))?, minor))
+}
+
+/// Pitch classes of a chord relative to the tonic
+pub fn relative_pitch_classes(chord: &str, tonic: u8) -> MusicResult<Vec<u8>> {
+    chord_to_notes(chord)?
+        .iter()
+        .map(|note| note_index(note).map(|pc| (pc + 12 - tonic) % 12))
+        .collect()
+}
+
+/// Scale degree (1-7) of a chord's root, if the root is in the scale
+pub fn root_degree(chord: &str, tonic: u8, scale: &[u8; 7]) -> Option<u8> {
+    let root = note_index(&parse_chord(chord).ok()?.root).ok()?;
+    let relative = (root + 12 - tonic) % 12;
+    scale.iter().position(|s| *s == relative).map(|i| i as u8 + 1)
+}
+
+/// Classify a chord into a tier for the given key and preceding chords, with the user's thresholds
+///
+/// - Diatonic chords are Safe, unless the move from the previous diatonic chord is
+///   unlikely in functional harmony, which makes them Colorful (or Bold, below the Colorful threshold)
+/// - Chords borrowed from the parallel mode are Colorful
+/// - Anything else is Bold
+pub fn classify_tier(chord: &str, key: &str, history: &[String]) -> MusicResult<TierClassification> {
+    classify_tier_with(chord, key, history, &tier_thresholds())
+}
+
+/// Classify a chord into a tier with the given thresholds
+pub fn classify_tier_with(
+    chord: &str,
+    key: &str,
+    history: &[String],
+    thresholds: &TierThresholds,
+) -> MusicResult<TierClassification> {
+    let (tonic, minor) = parse_key(key)?;
+    let (scale, parallel) = if minor { (&MINOR_SCALE, &MAJOR_SCALE) } else { (&MAJOR_SCALE, &MINOR_SCALE) };
+
+    let pitch_classes = relative_pitch_classes(chord, tonic)?;
+    let relation = if pitch_classes.iter().all(|pc| scale.contains(pc)) {
+        KeyRelation::Diatonic
+    } else if pitch_classes.iter().all(|pc| parallel.contains(pc)) {
+        KeyRelation::Borrowed
+    } else {
+        KeyRelation::Chromatic
+    };
+
+    let previous = history.last().filter(|previous| {
+        relative_pitch_classes(previous, tonic).is_ok_and(|pcs| pcs.iter().all(|pc| scale.contains(pc)))
+    });
+    let probability = match (relation, previous) {
+        (KeyRelation::Diatonic, Some(previous)) => {
+            match (root_degree(previous, tonic, scale), root_degree(chord, tonic, scale)) {
+                (Some(from), Some(to)) => Some(transition_weight(from, to)),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    let tier = match relation {
+        KeyRelation::Diatonic if probability.is_none_or(|p| p >= thresholds.safe) => Tier::Safe,
+        KeyRelation::Diatonic if probability.is_some_and(|p| p < thresholds.colorful) => Tier::Bold,
+        KeyRelation::Diatonic | KeyRelation::Borrowed => Tier::Colorful,
+        KeyRelation::Chromatic => Tier::Bold,
+    };
+
+    Ok(TierClassification { tier, relation, probability })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tier(chord: &str, key: &str, history: &[&str]) -> Tier {
+        let history: Vec<String> = history.iter().map(|c| c.to_string()).collect();
+        classify_tier(chord, key, &history).unwrap().tier
+    }
+
+    #[test]
+    fn test_diatonic_chords_are_safe() {
+        assert_eq!(tier("F", "C", &[]), Tier::Safe);
+        assert_eq!(tier("G7", "C", &["Dm"]), Tier::Safe);
+        assert_eq!(tier("Bdim", "C", &[]), Tier::Safe);
+        assert_eq!(tier("Dm", "Bb", &[]), Tier::Safe);
+    }
+
+    #[test]
+    fn test_unlikely_diatonic_move_is_colorful() {
+        // V → ii is rare in functional harmony
+        let result = classify_tier("Dm", "C", &["G".to_string()]).unwrap();
+        assert_eq!(result.tier, Tier::Colorful);
+        assert_eq!(result.relation, KeyRelation::Diatonic);
+        assert_eq!(result.probability, Some(0.0));
+    }
+
+    #[test]
+    fn test_borrowed_and_chromatic() {
+        assert_eq!(tier("Bb", "C", &[]), Tier::Colorful);
+        assert_eq!(tier("Fm", "C", &["C"]), Tier::Colorful);
+        assert_eq!(tier("F#", "C", &[]), Tier::Bold);
+        assert_eq!(tier("E", "C", &[]), Tier::Bold);
+    }
+
+    #[test]
+    fn test_minor_keys() {
+        assert_eq!(tier("C", "Am", &[]), Tier::Safe);
+        // Picardy-style major tonic is borrowed from A major
+        assert_eq!(tier("D", "Am", &[]), Tier::Colorful);
+    }
+
+    #[test]
+    fn test_custom_thresholds() {
+        let history = ["G".to_string()];
+        // I → IV and V → ii under thresholds that call only the likeliest moves Safe
+        let strict = TierThresholds { safe: 0.5, colorful: 0.05 };
+        assert_eq!(classify_tier_with("F", "C", &["C".to_string()], &strict).unwrap().tier, Tier::Colorful);
+        assert_eq!(classify_tier_with("Dm", "C", &history, &strict).unwrap().tier, Tier::Bold);
+        // Thresholds at zero make every diatonic move Safe
+        let lenient = TierThresholds { safe: 0.0, colorful: 0.0 };
+        assert_eq!(classify_tier_with("Dm", "C", &history, &lenient).unwrap().tier, Tier::Safe);
+
+        assert!(TierThresholds::default().validate().is_ok());
+        assert!(TierThresholds { safe: 0.1, colorful: 0.2 }.validate().is_err());
+        assert!(TierThresholds { safe: 1.5, colorful: 0.0 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_invalid_key() {
+        assert!(classify_tier("C", "X", &[]).is_err());
+    }
+}
@@ -17,12 +17,13 @@ pub struct ChordNotation {
     pub numeral: String,
 }
 
-/// Chord recommendation result with probability and numeral representation
+/// Chord recommendation result with probability, numeral representation and tier
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChordRecommendation {
     pub chord: String,
     pub probability: f32,
     pub numeral: String,
+    pub tier: Tier,
 }
 
 /// Audio note with octave for voice leading
@@ -30,6 +31,12 @@ pub struct ChordRecommendation {
 pub struct AudioNote {
     pub note: String,
     pub octave: i8,
+    /// True when this note was held at the same pitch from the previous chord
+    #[serde(default)]
+    pub is_common_tone: bool,
+    /// Detune in cents, played back by resampling (intonation training); 0 when in tune
+    #[serde(default)]
+    pub cents: f32,
 }
 
 /// Voicing style for chord arrangement
@@ -41,7 +48,7 @@ pub enum VoicingStyle {
 }
 
 /// Chord recommendation tier classification
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Tier {
     Safe,
@@ -52,7 +59,8 @@ pub enum Tier {
 // KeyType moved to notes.rs since it's specific to note logic
 
 /// Accidental type (sharp or flat)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 #[allow(dead_code)]
 pub enum Accidental {
     Flat,
@@ -70,6 +78,39 @@ pub struct RomanNumeralParts {
     pub is_minor: bool,
     pub suffix: String,
     pub bass: Option<String>,    // For slash chords
+    pub applied_to: Option<String>, // Target of an applied chord ("V" in V7/V)
+}
+
+/// Scale a key's Roman numerals are measured against
+/// In A natural minor G is VII; against the harmonic minor it is bVII and G# is vii
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumeralMode {
+    #[default]
+    Major,
+    NaturalMinor,
+    HarmonicMinor,
+}
+
+impl NumeralMode {
+    /// Split a key name into its tonic and mode: "Am" is A natural minor, "Eb" is Eb major
+    pub fn from_key(key: &str) -> (&str, NumeralMode) {
+        match key.strip_suffix('m') {
+            Some(tonic) => (tonic, NumeralMode::NaturalMinor),
+            None => (key, NumeralMode::Major),
+        }
+    }
+}
+
+/// How far Roman numeral analysis looks past the key's own scale
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumeralDepth {
+    /// Every chord is measured against the key: D7 in C is II7
+    #[default]
+    Scale,
+    /// Chromatic dominants and leading-tone chords are read as applied chords: D7 in C is V7/V
+    Applied,
 }
 
 /// Custom error type for music theory operations
@@ -101,6 +142,42 @@ pub enum MusicError {
 /// Result type alias for music operations
 pub type MusicResult<T> = Result<T, MusicError>;
 
+/// How strictly chord symbols are read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParseMode {
+    /// Apply every normalization silently ("c#m7", "B♭Δ", "Emi")
+    #[default]
+    Permissive,
+    /// Teaching mode: only textbook-formatted symbols are accepted
+    Strict,
+}
+
+/// Textbook convention a chord symbol can break in strict mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolConvention {
+    /// No spaces inside or around the symbol
+    NoSpaces,
+    /// Root and bass letters are capitals
+    UppercaseRoot,
+    /// Accidentals are written # and b
+    AsciiAccidentals,
+    /// Qualities are spelled out (maj7, m7b5, dim) rather than Δ, ø, °
+    SpelledQuality,
+    /// The quality uses its standard abbreviation ("m", not "mi")
+    StandardSuffix,
+    /// The quality is one the app knows
+    KnownQuality,
+}
+
+/// A broken convention with an explanation for the student
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConventionViolation {
+    pub convention: SymbolConvention,
+    pub message: String,
+}
+
 /// Chord validation result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -112,4 +189,6 @@ pub struct ChordValidationResult {
     #[serde(rename = "error")]
     pub message: Option<String>,
     pub input_type: Option<String>,
+    /// Convention broken by the input in strict mode
+    pub violation: Option<SymbolConvention>,
 }
@@ -2,6 +2,7 @@
 // Converts chords to MIDI notes with minimal movement between voicings
 
 use super::types::{AudioNote, VoicingStyle, MusicError, MusicResult};
+use super::notes::note_index;
 
 // Note to semitone mapping (same as in notes.rs)
 static NOTE_TO_SEMITONE: &[(&str, u8)] = &[
@@ -82,7 +83,7 @@ fn apply_close_voicing(notes: &[String], base_octave: i8) -> MusicResult<Vec<Aud
     for (i, note) in notes.iter().enumerate() {
         let prev = if i > 0 { result.get(i - 1) } else { None };
         let octave = calc_close_voicing_octave(note, base_octave, prev)?;
-        result.push(AudioNote { note: note.clone(), octave });
+        result.push(AudioNote { note: note.clone(), octave, is_common_tone: false, cents: 0.0 });
     }
 
     Ok(result)
@@ -96,27 +97,24 @@ fn apply_wide_voicing(notes: &[String], base_octave: i8) -> Vec<AudioNote> {
         .map(|(i, note)| AudioNote {
             note: note.clone(),
             octave: base_octave + (i / 2) as i8,
+            is_common_tone: false,
+            cents: 0.0,
         })
         .collect()
 }
 
-/// Clamp audio note to valid MIDI range (A1-C5)
-fn clamp_audio_note(audio_note: &mut AudioNote, min_midi: u8, max_midi: u8) -> MusicResult<()> {
-    let midi = note_to_midi(&audio_note.note, audio_note.octave)?;
-    let clamped = midi.max(min_midi).min(max_midi);
-
-    if clamped != midi {
-        let note_str = midi_to_note(clamped)?;
-        let note_len = note_str.len().saturating_sub(1);
-        if note_len > 0 {
-            audio_note.note = note_str[..note_len].to_string();
-            audio_note.octave = note_str[note_len..]
-                .parse()
-                .map_err(|_| MusicError::ParseError("Invalid octave".to_string()))?;
+/// Move audio notes into a range by whole octaves, keeping their spelling
+/// A note whose pitch class the range can't hold is left out
+fn fit_audio_notes(audio_notes: Vec<AudioNote>, range: &VoiceRange) -> MusicResult<Vec<AudioNote>> {
+    let mut fitted = Vec::with_capacity(audio_notes.len());
+    for mut audio_note in audio_notes {
+        let midi = note_to_midi(&audio_note.note, audio_note.octave)?;
+        if let Some(moved) = range.fit(midi) {
+            audio_note.octave += (moved as i8 - midi as i8) / 12;
+            fitted.push(audio_note);
         }
     }
-
-    Ok(())
+    Ok(fitted)
 }
 
 /// Apply voicing strategy to a set of note names
@@ -127,17 +125,13 @@ pub fn voice_chord(
     base_octave: i8,
     style: VoicingStyle,
 ) -> MusicResult<Vec<AudioNote>> {
-    let mut audio_notes = match style {
+    let audio_notes = match style {
         VoicingStyle::Close => apply_close_voicing(notes, base_octave)?,
         VoicingStyle::Wide => apply_wide_voicing(notes, base_octave),
     };
 
-    // Clamp all notes to available range (A1 = MIDI 21 to C5 = MIDI 72)
-    for audio_note in &mut audio_notes {
-        clamp_audio_note(audio_note, 21, 72)?;
-    }
-
-    Ok(audio_notes)
+    // Keep all notes in the available range (A1 = MIDI 21 to C5 = MIDI 72)
+    fit_audio_notes(audio_notes, &VoiceRange::default())
 }
 
 // Voice leading constants
@@ -145,118 +139,305 @@ const BASS_OCTAVE: i8 = 2;
 const MIN_MIDI: u8 = 21;  // A1
 const MAX_MIDI: u8 = 72;  // C5
 
-thread_local! {
-    static PREVIOUS_VOICING: std::cell::RefCell<Option<Vec<AudioNote>>> = std::cell::RefCell::new(None);
+/// Permitted MIDI range for voiced notes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VoiceRange {
+    pub min_midi: u8,
+    pub max_midi: u8,
+}
+
+impl Default for VoiceRange {
+    /// Sample range of the embedded piano (A1-C5)
+    fn default() -> Self {
+        Self { min_midi: MIN_MIDI, max_midi: MAX_MIDI }
+    }
+}
+
+/// Voicing rules for an instrument or ensemble: range, bass register, voice count and doubling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VoicingConfig {
+    pub range: VoiceRange,
+    /// Octave the bass is placed in, raised an octave at a time while it lies below the range
+    pub bass_octave: i8,
+    /// Most notes sounded at once, bass included (None = every chord tone)
+    pub max_voices: Option<usize>,
+    pub doubling: Doubling,
 }
 
-/// Reset voice leading state (called when starting new progression or changing key)
-pub fn reset_voicing() {
-    PREVIOUS_VOICING.with(|v| *v.borrow_mut() = None);
+/// Whether the bass note is repeated among the upper voices
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Doubling {
+    /// Upper voices skip the bass note
+    None,
+    /// The bass note is doubled above when there is a voice to spare, as for triads in four parts
+    Bass,
 }
 
-/// Convert MIDI number to AudioNote, clamping to valid range
+impl Default for VoicingConfig {
+    /// The embedded piano, bass in octave 2 and every chord tone sounded
+    fn default() -> Self {
+        Self { range: VoiceRange::default(), bass_octave: BASS_OCTAVE, max_voices: None, doubling: Doubling::None }
+    }
+}
+
+impl VoicingConfig {
+    /// Preset by name: "piano", "guitar-friendly", "satb" or "kids-keyboard"
+    pub fn preset(name: &str) -> Option<Self> {
+        let piano = Self::default();
+        match name {
+            "piano" => Some(piano),
+            // Four-note shapes from the low E string up
+            "guitar-friendly" => Some(Self {
+                range: VoiceRange { min_midi: 40, max_midi: MAX_MIDI },
+                max_voices: Some(4),
+                ..piano
+            }),
+            // Four-part writing from the bass's low E, the bass doubled in triads
+            "satb" => Some(Self {
+                range: VoiceRange { min_midi: 40, max_midi: MAX_MIDI },
+                max_voices: Some(4),
+                doubling: Doubling::Bass,
+                ..piano
+            }),
+            // Two octaves from C3, three notes for small hands
+            "kids-keyboard" => Some(Self {
+                range: VoiceRange { min_midi: 48, max_midi: MAX_MIDI },
+                bass_octave: 3,
+                max_voices: Some(3),
+                ..piano
+            }),
+            _ => None,
+        }
+    }
+
+    /// Octave for the bass note: the configured one, raised until the note is inside the range
+    fn bass_octave_for(&self, bass_note: &str) -> MusicResult<i8> {
+        let mut octave = self.bass_octave;
+        while note_to_midi(bass_note, octave)? < self.range.min_midi {
+            octave += 1;
+        }
+        Ok(octave)
+    }
+
+    /// Notes for the upper voices: chord tones other than the bass, thinned to the voice count by dropping the
+    /// fifth above the bass and then the highest extensions, and the bass doubled when a voice is left over
+    fn upper_notes<'a>(&self, notes: &'a [String], bass_note: &str) -> Vec<&'a String> {
+        let mut upper: Vec<&String> = notes.iter().filter(|note| note.as_str() != bass_note).collect();
+        let max_upper = self.max_voices.map_or(usize::MAX, |voices| voices.saturating_sub(1));
+
+        let fifth = note_index(bass_note).ok().map(|bass| (bass + 7) % 12);
+        while upper.len() > max_upper {
+            match upper.iter().position(|note| note_index(note).ok().is_some_and(|index| Some(index) == fifth)) {
+                Some(position) => {
+                    upper.remove(position);
+                }
+                None => {
+                    upper.pop();
+                }
+            }
+        }
+
+        if self.doubling == Doubling::Bass && upper.len() < max_upper && !upper.is_empty() {
+            if let Some(bass) = notes.iter().find(|note| note.as_str() == bass_note) {
+                upper.push(bass);
+            }
+        }
+        upper
+    }
+}
+
+impl VoiceRange {
+    /// Check whether a MIDI note falls inside the range
+    pub fn contains(&self, midi: u8) -> bool {
+        (self.min_midi..=self.max_midi).contains(&midi)
+    }
+
+    /// Move a MIDI note into the range by whole octaves, so it keeps its pitch class
+    /// None when no note of that pitch class lies inside the range
+    pub fn fit(&self, midi: u8) -> Option<u8> {
+        let mut midi = midi;
+        while midi < self.min_midi {
+            midi += 12;
+        }
+        while midi > self.max_midi {
+            midi = midi.checked_sub(12)?;
+        }
+        self.contains(midi).then_some(midi)
+    }
+
+    /// Every octave number that has at least one note inside the range
+    pub fn octaves(&self) -> std::ops::RangeInclusive<i8> {
+        let lowest = (self.min_midi / 12) as i8 - 1;
+        let highest = (self.max_midi / 12) as i8 - 1;
+        lowest.max(0)..=highest
+    }
+}
+
+/// Voice leading memory of one progression: the chord voiced last, which the next one leads from
+/// Held by its owner (a document, a frontend voicing session or a single analysis pass) and passed to each call
+#[derive(Debug, Clone, Default)]
+pub struct VoicingSession {
+    previous: Option<Vec<AudioNote>>,
+}
+
+impl VoicingSession {
+    /// Forget the previous chord, so the next one is voiced afresh
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    /// Previous upper voices (excluding bass)
+    fn previous_upper_voices(&self) -> Option<Vec<AudioNote>> {
+        self.previous.as_ref().filter(|prev| prev.len() > 1).map(|prev| prev[1..].to_vec())
+    }
+
+    /// Previous bass voice
+    fn previous_bass(&self) -> Option<&AudioNote> {
+        self.previous.as_ref().and_then(|prev| prev.first())
+    }
+}
+
+/// Convert MIDI number to AudioNote
 fn midi_to_audio_note(midi: u8) -> MusicResult<AudioNote> {
-    let clamped = midi.max(MIN_MIDI).min(MAX_MIDI);
-    let note_str = midi_to_note(clamped)?;
+    let note_str = midi_to_note(midi)?;
     let note_len = note_str.len().saturating_sub(1);
     
     Ok(AudioNote {
         note: note_str[..note_len].to_string(),
         octave: note_str[note_len..].parse()
             .map_err(|_| MusicError::ParseError("Invalid octave".to_string()))?,
+        is_common_tone: false,
+        cents: 0.0,
     })
 }
 
-/// Get previous upper voices (excluding bass) from thread-local state
-fn get_previous_upper_voices() -> Option<Vec<AudioNote>> {
-    PREVIOUS_VOICING.with(|v| {
-        v.borrow()
-            .as_ref()
-            .filter(|prev| prev.len() > 1)
-            .map(|prev| prev[1..].to_vec())
-    })
+/// Check whether two note names share a pitch class (enharmonics included)
+fn same_pitch_class(a: &str, b: &str) -> bool {
+    match (note_index(a), note_index(b)) {
+        (Ok(a_idx), Ok(b_idx)) => a_idx == b_idx,
+        _ => false,
+    }
+}
+
+/// Find a previous upper voice that can be held as a common tone
+/// Returns its MIDI number so the note stays at exactly the same pitch
+fn find_common_tone(note: &str, bass_midi: u8, previous_upper: &[AudioNote]) -> MusicResult<Option<u8>> {
+    for prev_note in previous_upper {
+        if !same_pitch_class(note, &prev_note.note) {
+            continue;
+        }
+        let prev_midi = note_to_midi(&prev_note.note, prev_note.octave)?;
+        if prev_midi > bass_midi {
+            return Ok(Some(prev_midi));
+        }
+    }
+    Ok(None)
 }
 
 /// Build initial voicing for first chord (no previous chord to reference)
+/// A note with no room above the bass inside the range is left out
 fn build_initial_voicing(
     upper_notes: &[&String],
-    bass_note: &str,
+    bass_midi: u8,
+    bass_octave: i8,
+    range: &VoiceRange,
 ) -> MusicResult<Vec<AudioNote>> {
     let mut result = Vec::new();
-    let mut current_octave: i8 = 2;
-    let bass_midi = note_to_midi(bass_note, BASS_OCTAVE)?;
+    let mut current_octave = bass_octave;
 
     for note in upper_notes {
-        let octave = find_octave_above_bass(note, current_octave, bass_midi)?;
-        let midi = note_to_midi(note, octave)?;
-        result.push(midi_to_audio_note(midi)?);
+        let Some(octave) = find_octave_above_bass(note, current_octave, bass_midi, range) else {
+            continue;
+        };
+        result.push(midi_to_audio_note(note_to_midi(note, octave)?)?);
         current_octave = octave;
     }
 
     Ok(result)
 }
 
-/// Find the lowest octave where note is above bass
-fn find_octave_above_bass(note: &str, start_octave: i8, bass_midi: u8) -> MusicResult<i8> {
-    let mut octave = start_octave;
-    let mut midi = note_to_midi(note, octave)?;
-    
-    while midi <= bass_midi && octave < 5 {
-        octave += 1;
-        midi = note_to_midi(note, octave)?;
+/// Find the lowest octave, from start_octave up, where note is above bass and inside the range
+/// None when the range has no room for the note above the bass
+fn find_octave_above_bass(note: &str, start_octave: i8, bass_midi: u8, range: &VoiceRange) -> Option<i8> {
+    for octave in start_octave..=*range.octaves().end() {
+        // Octaves above MIDI 127 can't be represented, so nothing higher fits either
+        let Ok(midi) = note_to_midi(note, octave) else {
+            break;
+        };
+        if midi > bass_midi && range.contains(midi) {
+            return Some(octave);
+        }
     }
-    
-    Ok(octave)
+    None
 }
 
 /// Build voicing using voice leading from previous chord
+/// With retain_common_tones, notes shared with the previous chord stay at the same pitch while it is inside the range
+/// A note with no room above the bass inside the range is left out
 fn build_voice_led_voicing(
     upper_notes: &[&String],
-    bass_note: &str,
+    bass_midi: u8,
     base_octave: i8,
     previous_upper: &[AudioNote],
+    retain_common_tones: bool,
+    range: &VoiceRange,
 ) -> MusicResult<Vec<AudioNote>> {
     let mut result = Vec::new();
-    let bass_midi = note_to_midi(bass_note, BASS_OCTAVE)?;
 
     for note in upper_notes {
-        let best_octave = find_closest_octave(note, bass_midi, base_octave, previous_upper)?;
-        let midi = note_to_midi(note, best_octave)?;
-        result.push(midi_to_audio_note(midi)?);
+        if retain_common_tones {
+            let held_midi = find_common_tone(note, bass_midi, previous_upper)?.filter(|midi| range.contains(*midi));
+            if let Some(held_midi) = held_midi {
+                let mut held = midi_to_audio_note(held_midi)?;
+                held.is_common_tone = true;
+                result.push(held);
+                continue;
+            }
+        }
+
+        let Some(best_octave) = find_closest_octave(note, bass_midi, base_octave, previous_upper, range)? else {
+            continue;
+        };
+        result.push(midi_to_audio_note(note_to_midi(note, best_octave)?)?);
     }
 
     Ok(result)
 }
 
 /// Find octave that minimizes distance to previous upper voices
-fn find_closest_octave(
+/// Searches every octave inside the voice range (above the bass); ties prefer
+/// the octave closest to default_octave. None when no octave of the note fits
+pub fn find_closest_octave(
     note: &str,
     bass_midi: u8,
     default_octave: i8,
     previous_upper: &[AudioNote],
-) -> MusicResult<i8> {
-    let mut best_octave = default_octave;
-    let mut min_distance = u8::MAX;
+    range: &VoiceRange,
+) -> MusicResult<Option<i8>> {
+    let mut best: Option<((u8, u8), i8)> = None;
 
-    for octave in 2..=3 {
-        let midi = note_to_midi(note, octave)?;
-        
-        if midi < MIN_MIDI || midi > MAX_MIDI || midi <= bass_midi {
+    for octave in range.octaves() {
+        // Octaves above MIDI 127 can't be represented - skip them rather than fail
+        let Ok(midi) = note_to_midi(note, octave) else {
+            continue;
+        };
+
+        if !range.contains(midi) || midi <= bass_midi {
             continue;
         }
 
+        // With no previous voices to lead from, the octave closest to default_octave wins
+        let mut distance = if previous_upper.is_empty() { 0 } else { u8::MAX };
         for prev_note in previous_upper {
-            let prev_midi = note_to_midi(&prev_note.note, prev_note.octave)?;
-            let distance = midi.abs_diff(prev_midi);
-            
-            if distance < min_distance {
-                min_distance = distance;
-                best_octave = octave;
-            }
+            distance = distance.min(midi.abs_diff(note_to_midi(&prev_note.note, prev_note.octave)?));
+        }
+        let score = (distance, octave.abs_diff(default_octave));
+        if best.is_none_or(|(best_score, _)| score < best_score) {
+            best = Some((score, octave));
         }
     }
 
-    Ok(best_octave)
+    Ok(best.map(|(_, octave)| octave))
 }
 
 /// Sort upper voices by ascending pitch
@@ -270,33 +451,102 @@ fn sort_upper_voices_by_pitch(mut voices: Vec<AudioNote>) -> Vec<AudioNote> {
 }
 
 /// Voice a chord using voice leading principles
-/// Bass note stays at octave 2, upper voices move to closest positions from previous chord
+/// Bass note stays in the config's bass octave, upper voices move to closest positions from previous chord
 pub fn voice_chord_with_leading(
+    session: &mut VoicingSession,
+    notes: &[String],
+    bass_note: &str,
+    base_octave: i8,
+    config: &VoicingConfig,
+) -> MusicResult<Vec<AudioNote>> {
+    voice_with_leading(session, notes, bass_note, base_octave, false, config)
+}
+
+/// Voice a chord using voice leading, holding common tones from the previous chord
+/// Held notes keep their exact pitch and are flagged with is_common_tone
+pub fn voice_chord_with_common_tones(
+    session: &mut VoicingSession,
+    notes: &[String],
+    bass_note: &str,
+    base_octave: i8,
+    config: &VoicingConfig,
+) -> MusicResult<Vec<AudioNote>> {
+    voice_with_leading(session, notes, bass_note, base_octave, true, config)
+}
+
+/// Voice a chord by frontend style name ("close", "wide", "common-tone", "lead")
+/// Unknown names fall back to voice leading
+/// Close and wide voicings keep their shape and are only moved into the config's range, an octave at a time
+pub fn voice_chord_by_style(
+    session: &mut VoicingSession,
     notes: &[String],
     bass_note: &str,
     base_octave: i8,
+    voicing_style: &str,
+    config: &VoicingConfig,
 ) -> MusicResult<Vec<AudioNote>> {
+    let style = match voicing_style {
+        "close" => VoicingStyle::Close,
+        "wide" => VoicingStyle::Wide,
+        "common-tone" => return voice_chord_with_common_tones(session, notes, bass_note, base_octave, config),
+        _ => return voice_chord_with_leading(session, notes, bass_note, base_octave, config),
+    };
+    fit_audio_notes(voice_chord(notes, bass_note, base_octave, style)?, &config.range)
+}
+
+/// Voice a whole progression of chord note lists from a fresh start
+/// Uses its own session, so this is safe to call for analysis while a document plays
+pub fn voice_progression(
+    chords: &[Vec<String>],
+    base_octave: i8,
+    voicing_style: &str,
+) -> MusicResult<Vec<Vec<AudioNote>>> {
+    let mut session = VoicingSession::default();
+    chords
+        .iter()
+        .map(|notes| {
+            let bass_note = notes.first().cloned().unwrap_or_default();
+            voice_chord_by_style(&mut session, notes, &bass_note, base_octave, voicing_style, &VoicingConfig::default())
+        })
+        .collect()
+}
+
+fn voice_with_leading(
+    session: &mut VoicingSession,
+    notes: &[String],
+    bass_note: &str,
+    base_octave: i8,
+    retain_common_tones: bool,
+    config: &VoicingConfig,
+) -> MusicResult<Vec<AudioNote>> {
+    let range = &config.range;
+
     // 1. Bass voice - always at low octave
+    let bass_is_held = retain_common_tones
+        && session.previous_bass().is_some_and(|prev| same_pitch_class(&prev.note, bass_note));
+    let bass_octave = config.bass_octave_for(bass_note)?;
+    let bass_midi = note_to_midi(bass_note, bass_octave)?;
     let bass = AudioNote {
         note: bass_note.to_string(),
-        octave: BASS_OCTAVE,
+        octave: bass_octave,
+        is_common_tone: bass_is_held,
+        cents: 0.0,
     };
 
-    // 2. Upper voices - exclude bass note
-    let upper_notes: Vec<&String> = notes
-        .iter()
-        .filter(|note| note.as_str() != bass_note)
-        .collect();
+    // 2. Upper voices - exclude bass note unless it is doubled, up to the voice count
+    let upper_notes = config.upper_notes(notes, bass_note);
 
     if upper_notes.is_empty() {
         return Ok(vec![bass]);
     }
 
     // 3. Build upper voices based on whether we have previous voicing
-    let previous_upper = get_previous_upper_voices();
+    let previous_upper = session.previous_upper_voices();
     let upper_voices = match previous_upper {
-        None => build_initial_voicing(&upper_notes, bass_note)?,
-        Some(ref prev) => build_voice_led_voicing(&upper_notes, bass_note, base_octave, prev)?,
+        None => build_initial_voicing(&upper_notes, bass_midi, bass_octave, range)?,
+        Some(ref prev) => {
+            build_voice_led_voicing(&upper_notes, bass_midi, base_octave, prev, retain_common_tones, range)?
+        }
     };
 
     // 4. Combine bass with sorted upper voices
@@ -304,7 +554,7 @@ pub fn voice_chord_with_leading(
     result.extend(sort_upper_voices_by_pitch(upper_voices));
 
     // 5. Store for next chord
-    PREVIOUS_VOICING.with(|v| *v.borrow_mut() = Some(result.clone()));
+    session.previous = Some(result.clone());
 
     Ok(result)
 }
@@ -312,6 +562,7 @@ pub fn voice_chord_with_leading(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::documents::DocumentMap;
 
     #[test]
     fn test_note_to_midi() {
@@ -352,9 +603,9 @@ mod tests {
 
     #[test]
     fn test_voice_chord_with_leading_first() {
-        reset_voicing(); // Ensure clean state
+        let mut session = VoicingSession::default();
         let notes = vec!["C".to_string(), "E".to_string(), "G".to_string()];
-        let result = voice_chord_with_leading(&notes, "C", 3).unwrap();
+        let result = voice_chord_with_leading(&mut session, &notes, "C", 3, &VoicingConfig::default()).unwrap();
         
         assert_eq!(result.len(), 3);
         assert_eq!(result[0].note, "C");
@@ -363,10 +614,10 @@ mod tests {
 
     #[test]
     fn test_voice_chord_with_leading_bass_excluded_from_upper() {
-        reset_voicing();
+        let mut session = VoicingSession::default();
         // C major: C E G with C as bass
         let notes = vec!["C".to_string(), "E".to_string(), "G".to_string()];
-        let result = voice_chord_with_leading(&notes, "C", 3).unwrap();
+        let result = voice_chord_with_leading(&mut session, &notes, "C", 3, &VoicingConfig::default()).unwrap();
         
         // Bass is C, upper voices should be E and G only (C excluded)
         assert_eq!(result.len(), 3);
@@ -379,16 +630,16 @@ mod tests {
 
     #[test]
     fn test_voice_chord_with_leading_sequence() {
-        reset_voicing();
+        let mut session = VoicingSession::default();
         
         // First chord: C major
         let c_major = vec!["C".to_string(), "E".to_string(), "G".to_string()];
-        let result1 = voice_chord_with_leading(&c_major, "C", 3).unwrap();
+        let result1 = voice_chord_with_leading(&mut session, &c_major, "C", 3, &VoicingConfig::default()).unwrap();
         assert_eq!(result1.len(), 3);
         
         // Second chord: G major - should use voice leading from previous
         let g_major = vec!["G".to_string(), "B".to_string(), "D".to_string()];
-        let result2 = voice_chord_with_leading(&g_major, "G", 3).unwrap();
+        let result2 = voice_chord_with_leading(&mut session, &g_major, "G", 3, &VoicingConfig::default()).unwrap();
         assert_eq!(result2.len(), 3);
         assert_eq!(result2[0].note, "G"); // Bass
         assert_eq!(result2[0].octave, 2); // Bass always at octave 2
@@ -400,11 +651,11 @@ mod tests {
 
     #[test]
     fn test_voice_chord_with_leading_upper_voices_sorted() {
-        reset_voicing();
+        let mut session = VoicingSession::default();
         
         // Notes in non-ascending order
         let notes = vec!["C".to_string(), "G".to_string(), "E".to_string()];
-        let result = voice_chord_with_leading(&notes, "C", 3).unwrap();
+        let result = voice_chord_with_leading(&mut session, &notes, "C", 3, &VoicingConfig::default()).unwrap();
         
         // Upper voices (excluding bass) should be sorted by pitch
         if result.len() > 2 {
@@ -416,10 +667,10 @@ mod tests {
 
     #[test]
     fn test_voice_chord_with_leading_range_limits() {
-        reset_voicing();
+        let mut session = VoicingSession::default();
         
         let notes = vec!["C".to_string(), "E".to_string(), "G".to_string()];
-        let result = voice_chord_with_leading(&notes, "C", 3).unwrap();
+        let result = voice_chord_with_leading(&mut session, &notes, "C", 3, &VoicingConfig::default()).unwrap();
         
         // All notes should be within MIDI 21 (A1) to 72 (C5)
         for audio_note in &result {
@@ -431,11 +682,11 @@ mod tests {
 
     #[test]
     fn test_voice_chord_with_leading_single_note() {
-        reset_voicing();
+        let mut session = VoicingSession::default();
         
         // Edge case: chord with only root note
         let notes = vec!["C".to_string()];
-        let result = voice_chord_with_leading(&notes, "C", 3).unwrap();
+        let result = voice_chord_with_leading(&mut session, &notes, "C", 3, &VoicingConfig::default()).unwrap();
         
         // Should just have bass note
         assert_eq!(result.len(), 1);
@@ -446,15 +697,16 @@ mod tests {
     #[test]
     fn test_reset_voicing_clears_state() {
         // First chord sets state
+        let mut session = VoicingSession::default();
         let notes = vec!["C".to_string(), "E".to_string(), "G".to_string()];
-        let _ = voice_chord_with_leading(&notes, "C", 3).unwrap();
+        let _ = voice_chord_with_leading(&mut session, &notes, "C", 3, &VoicingConfig::default()).unwrap();
         
         // Reset should clear state
-        reset_voicing();
+        session.reset();
         
         // Next chord should behave like first chord (no voice leading)
         let g_major = vec!["G".to_string(), "B".to_string(), "D".to_string()];
-        let result = voice_chord_with_leading(&g_major, "G", 3).unwrap();
+        let result = voice_chord_with_leading(&mut session, &g_major, "G", 3, &VoicingConfig::default()).unwrap();
         
         // Should use initial voicing logic, not voice leading
         assert_eq!(result[0].note, "G");
@@ -463,13 +715,226 @@ mod tests {
 
     #[test]
     fn test_voice_chord_range_clamping() {
-        // Test very low notes get clamped to A1 (MIDI 21)
+        // Test very low notes get raised by octaves to A1 (MIDI 21) or above
         let notes = vec!["C".to_string(), "E".to_string()];
         let result = voice_chord(&notes, "C", 0, VoicingStyle::Close).unwrap();
         
-        // Should clamp to minimum range
+        // Should stay inside the minimum range with the same notes
         assert!(result.iter().all(|note| {
             note_to_midi(&note.note, note.octave).unwrap_or(0) >= 21
         }));
+        let voiced: Vec<(&str, i8)> = result.iter().map(|note| (note.note.as_str(), note.octave)).collect();
+        assert_eq!(voiced, [("C", 1), ("E", 1)]);
+    }
+
+    #[test]
+    fn test_common_tones_held_at_same_pitch() {
+        let mut session = VoicingSession::default();
+
+        // C major then E minor: E and G are shared
+        let c_major = vec!["C".to_string(), "E".to_string(), "G".to_string()];
+        let first = voice_chord_with_common_tones(&mut session, &c_major, "C", 3, &VoicingConfig::default()).unwrap();
+        assert!(first.iter().all(|n| !n.is_common_tone), "First chord has nothing to hold");
+
+        let e_minor = vec!["E".to_string(), "G".to_string(), "B".to_string()];
+        let second = voice_chord_with_common_tones(&mut session, &e_minor, "E", 3, &VoicingConfig::default()).unwrap();
+
+        // G sits above the new bass, so it is held at exactly the same pitch
+        let first_g = first.iter().find(|n| n.note == "G").unwrap();
+        let second_g = second.iter().find(|n| n.note == "G").unwrap();
+        assert!(second_g.is_common_tone);
+        assert_eq!(second_g.octave, first_g.octave, "Common tone should stay at the same pitch");
+
+        let second_b = second.iter().find(|n| n.note == "B").unwrap();
+        assert!(!second_b.is_common_tone);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_common_tone_bass_flagged() {
+        let mut session = VoicingSession::default();
+
+        let c_major = vec!["C".to_string(), "E".to_string(), "G".to_string()];
+        let _ = voice_chord_with_common_tones(&mut session, &c_major, "C", 3, &VoicingConfig::default()).unwrap();
+
+        // C/F-style pedal: same bass carries over
+        let f_over_c = vec!["C".to_string(), "F".to_string(), "A".to_string()];
+        let result = voice_chord_with_common_tones(&mut session, &f_over_c, "C", 3, &VoicingConfig::default()).unwrap();
+        assert_eq!(result[0].note, "C");
+        assert!(result[0].is_common_tone);
+    }
+
+    #[test]
+    fn test_plain_leading_does_not_flag_common_tones() {
+        let mut session = VoicingSession::default();
+
+        let c_major = vec!["C".to_string(), "E".to_string(), "G".to_string()];
+        let _ = voice_chord_with_leading(&mut session, &c_major, "C", 3, &VoicingConfig::default()).unwrap();
+
+        let a_minor = vec!["A".to_string(), "C".to_string(), "E".to_string()];
+        let result = voice_chord_with_leading(&mut session, &a_minor, "A", 3, &VoicingConfig::default()).unwrap();
+        assert!(result.iter().all(|n| !n.is_common_tone));
+    }
+
+    #[test]
+    fn test_find_closest_octave_searches_full_range() {
+        let range = VoiceRange { min_midi: 21, max_midi: 84 };
+        let previous = vec![
+            AudioNote { note: "E".to_string(), octave: 5, is_common_tone: false, cents: 0.0 },
+            AudioNote { note: "G".to_string(), octave: 5, is_common_tone: false, cents: 0.0 },
+        ];
+        let bass_midi = note_to_midi("C", 2).unwrap();
+
+        // Previously only octaves 2-3 were considered, collapsing F down to F3
+        let octave = find_closest_octave("F", bass_midi, 4, &previous, &range).unwrap();
+        assert_eq!(octave, Some(5));
+    }
+
+    #[test]
+    fn test_find_closest_octave_respects_range() {
+        let range = VoiceRange::default();
+        let previous = vec![AudioNote { note: "B".to_string(), octave: 4, is_common_tone: false, cents: 0.0 }];
+        let bass_midi = note_to_midi("C", 2).unwrap();
+
+        // D5 would be closest to B4 but lies above C5, so D4 is chosen
+        let octave = find_closest_octave("D", bass_midi, 3, &previous, &range).unwrap();
+        assert_eq!(octave, Some(4));
+    }
+
+    #[test]
+    fn test_notes_without_room_above_a_high_bass_are_left_out() {
+        let config = VoicingConfig::preset("kids-keyboard").unwrap();
+        let previous = vec![AudioNote { note: "C".to_string(), octave: 5, is_common_tone: false, cents: 0.0 }];
+
+        // Above A4 the range ends at C5, so there is no D, but B and C still fit
+        let bass_midi = note_to_midi("A", 4).unwrap();
+        assert_eq!(find_closest_octave("D", bass_midi, 4, &previous, &config.range).unwrap(), None);
+        assert_eq!(find_closest_octave("B", bass_midi, 4, &previous, &config.range).unwrap(), Some(4));
+        assert_eq!(find_closest_octave("C", bass_midi, 4, &[], &config.range).unwrap(), Some(5));
+        assert_eq!(find_octave_above_bass("D", 3, bass_midi, &config.range), None);
+
+        // Notes are only moved into the range by octaves, never onto another pitch class
+        assert_eq!(config.range.fit(note_to_midi("D", 5).unwrap()), Some(62));
+        assert_eq!(config.range.fit(note_to_midi("A", 1).unwrap()), Some(57));
+        assert_eq!(VoiceRange { min_midi: 60, max_midi: 64 }.fit(note_to_midi("G", 4).unwrap()), None);
+
+        // Every voice of a progression over the highest bass keeps its pitch class, inside the range
+        let mut session = VoicingSession::default();
+        for (chord, style) in [(["B", "D#", "F#"], "lead"), (["B", "D", "G"], "lead"), (["B", "E", "G#"], "wide")] {
+            let notes: Vec<String> = chord.iter().map(|note| note.to_string()).collect();
+            let voiced = voice_chord_by_style(&mut session, &notes, "B", 4, style, &config).unwrap();
+            assert_eq!(voiced.len(), 3);
+            for voice in &voiced {
+                assert!(config.range.contains(note_to_midi(&voice.note, voice.octave).unwrap()));
+                assert!(notes.iter().any(|note| same_pitch_class(note, &voice.note)), "{:?} moved", voice);
+            }
+        }
+    }
+
+    #[test]
+    fn test_voice_range_octaves() {
+        assert_eq!(VoiceRange::default().octaves(), 0..=5);
+        assert_eq!(VoiceRange { min_midi: 48, max_midi: 84 }.octaves(), 3..=6);
+    }
+
+    #[test]
+    fn test_voicing_presets() {
+        let notes = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        let midis = |voiced: &[AudioNote]| -> Vec<u8> {
+            voiced.iter().map(|note| note_to_midi(&note.note, note.octave).unwrap()).collect()
+        };
+        assert_eq!(VoicingConfig::preset("piano"), Some(VoicingConfig::default()));
+        assert_eq!(VoicingConfig::preset("harpsichord"), None);
+
+        // SATB doubles the bass of a triad, and drops the fifth of a ninth chord to keep four voices
+        let satb = VoicingConfig::preset("satb").unwrap();
+        let mut session = VoicingSession::default();
+        let voiced = voice_chord_with_leading(&mut session, &notes(&["C", "E", "G"]), "C", 3, &satb).unwrap();
+        assert_eq!(voiced.iter().map(|note| note.note.as_str()).collect::<Vec<_>>(), ["C", "E", "G", "C"]);
+        session.reset();
+        let ninth = notes(&["C", "E", "G", "Bb", "D"]);
+        let voiced = voice_chord_with_leading(&mut session, &ninth, "C", 3, &satb).unwrap();
+        assert_eq!(midis(&voiced), [48, 50, 52, 58]);
+
+        // The kids' keyboard starts at C3: the bass moves up an octave and three notes sound
+        let kids = VoicingConfig::preset("kids-keyboard").unwrap();
+        session.reset();
+        let voiced = voice_chord_with_leading(&mut session, &notes(&["A", "C", "E", "G"]), "A", 3, &kids).unwrap();
+        assert_eq!(midis(&voiced), [57, 60, 67]);
+        let voiced = voice_chord_by_style(&mut session, &notes(&["C", "E", "G"]), "C", 2, "close", &kids).unwrap();
+        assert!(midis(&voiced).iter().all(|&midi| kids.range.contains(midi)));
+
+        // Guitar keeps the bass on or above the low E string
+        let guitar = VoicingConfig::preset("guitar-friendly").unwrap();
+        session.reset();
+        let voiced = voice_chord_with_leading(&mut session, &notes(&["D", "F#", "A"]), "D", 3, &guitar).unwrap();
+        assert_eq!(midis(&voiced)[0], 50);
+    }
+
+    #[test]
+    fn test_voice_progression_is_stateless() {
+        let pitches = |voiced: &[Vec<AudioNote>]| -> Vec<Vec<u8>> {
+            voiced
+                .iter()
+                .map(|chord| chord.iter().map(|note| note_to_midi(&note.note, note.octave).unwrap()).collect())
+                .collect()
+        };
+        let chords = vec![
+            vec!["A".to_string(), "C".to_string(), "E".to_string()],
+            vec!["F".to_string(), "A".to_string(), "C".to_string()],
+        ];
+        let voiced = voice_progression(&chords, 3, "lead").unwrap();
+        assert_eq!(voiced.len(), 2);
+        assert_eq!(voiced[0][0].note, "A");
+
+        // The first chord is voiced afresh, and voicing elsewhere in between changes nothing
+        let mut fresh = VoicingSession::default();
+        let first = voice_chord_with_leading(&mut fresh, &chords[0], "A", 3, &VoicingConfig::default()).unwrap();
+        assert_eq!(pitches(&[first]), pitches(&voiced[..1]));
+        let c_major = vec!["C".to_string(), "E".to_string(), "G".to_string()];
+        voice_chord_with_leading(&mut fresh, &c_major, "C", 3, &VoicingConfig::default()).unwrap();
+        assert_eq!(pitches(&voice_progression(&chords, 3, "lead").unwrap()), pitches(&voiced));
+    }
+
+    #[test]
+    fn test_voicing_sessions_are_isolated() {
+        let c = vec!["C".to_string(), "E".to_string(), "G".to_string()];
+        let e_minor = vec!["E".to_string(), "G".to_string(), "B".to_string()];
+        let piano = VoicingConfig::default();
+        let (mut first, mut second) = (VoicingSession::default(), VoicingSession::default());
+
+        voice_chord_with_common_tones(&mut first, &c, "C", 3, &piano).unwrap();
+        // The second session has no previous chord
+        let fresh = voice_chord_with_common_tones(&mut second, &e_minor, "E", 3, &piano).unwrap();
+        assert!(fresh.iter().all(|n| !n.is_common_tone));
+
+        let led = voice_chord_with_common_tones(&mut first, &e_minor, "E", 3, &piano).unwrap();
+        assert!(led.iter().any(|n| n.is_common_tone));
+
+        first.reset();
+        let after_reset = voice_chord_with_common_tones(&mut first, &e_minor, "E", 3, &piano).unwrap();
+        assert!(after_reset.iter().all(|n| !n.is_common_tone));
+    }
+
+    #[test]
+    fn test_document_voicings_are_isolated() {
+        let c = vec!["C".to_string(), "E".to_string(), "G".to_string()];
+        let e_minor = vec!["E".to_string(), "G".to_string(), "B".to_string()];
+        let piano = VoicingConfig::default();
+        let mut voicings: DocumentMap<VoicingSession> = DocumentMap::default();
+
+        let first = voice_chord_by_style(voicings.entry("main/a"), &c, "C", 3, "common-tone", &piano).unwrap();
+        // main/b starts fresh even though main/a just voiced a chord
+        let fresh = voice_chord_by_style(voicings.entry("main/b"), &c, "C", 3, "common-tone", &piano).unwrap();
+        assert!(first.iter().zip(&fresh).all(|(a, b)| a.note == b.note && a.octave == b.octave));
+
+        // main/a remembers its own previous chord, so G is held as a common tone
+        let led = voice_chord_by_style(voicings.entry("main/a"), &e_minor, "E", 3, "common-tone", &piano).unwrap();
+        assert!(led.iter().any(|n| n.is_common_tone));
+
+        // Closing the window releases its documents' memory
+        voicings.remove_window("main");
+        let after_close =
+            voice_chord_by_style(voicings.entry("main/a"), &e_minor, "E", 3, "common-tone", &piano).unwrap();
+        assert!(after_close.iter().all(|n| !n.is_common_tone));
+    }
+}
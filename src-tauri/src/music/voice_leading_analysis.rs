@@ -0,0 +1,209 @@
+// Voice leading analysis
+// Flags part-writing faults in a voiced progression, for grading theory exercises and checking the engine's voicings
+
+use serde::Serialize;
+
+use super::types::{AudioNote, MusicResult};
+use super::voice_leading::note_to_midi;
+use super::voice_motion::{voice_motions, MotionDirection, VoiceMotion, VoiceTransition};
+
+/// Largest move in half steps an upper voice makes before it counts as a large leap (a perfect fifth)
+const MAX_UPPER_LEAP: u8 = 7;
+/// Largest move in half steps the bass makes before it counts as a large leap (an octave)
+const MAX_BASS_LEAP: u8 = 12;
+
+/// Kind of voice-leading fault
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoiceLeadingIssueKind {
+    /// Two voices a fifth (or compound fifth) apart move the same way to another fifth
+    ParallelFifths,
+    /// Two voices an octave or unison apart move the same way to another octave or unison
+    ParallelOctaves,
+    /// A voice sounds below the voice given before it in the same chord
+    VoiceCrossing,
+    /// A voice moves further than a fifth, or the bass further than an octave
+    LargeLeap,
+}
+
+/// One fault found in the progression
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VoiceLeadingIssue {
+    pub kind: VoiceLeadingIssueKind,
+    /// Index of the chord the fault is in; for motion faults, the chord the voices move from
+    pub chord: usize,
+    /// Indices of the notes involved in that chord as given
+    pub notes: Vec<usize>,
+    pub description: String,
+}
+
+/// Voice-leading faults and the amount of motion in a voiced progression
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VoiceLeadingReport {
+    /// Faults in chord order
+    pub issues: Vec<VoiceLeadingIssue>,
+    /// Half steps moved by all voices from each chord to the next
+    pub movement: Vec<u32>,
+    pub total_semitones: u32,
+}
+
+fn name(note: &AudioNote) -> String {
+    format!("{}{}", note.note, note.octave)
+}
+
+/// Notes given in voice order, lowest voice first, that sound below the voice given before them
+fn crossings(chord: usize, notes: &[AudioNote]) -> MusicResult<Vec<VoiceLeadingIssue>> {
+    let midis = notes.iter().map(|note| note_to_midi(&note.note, note.octave)).collect::<MusicResult<Vec<_>>>()?;
+    Ok((1..notes.len())
+        .filter(|&index| midis[index] < midis[index - 1])
+        .map(|index| VoiceLeadingIssue {
+            kind: VoiceLeadingIssueKind::VoiceCrossing,
+            chord,
+            notes: vec![index - 1, index],
+            description: format!("{} sounds below {}", name(&notes[index]), name(&notes[index - 1])),
+        })
+        .collect())
+}
+
+/// Parallel fifths and octaves, then large leaps, in one transition
+fn motion_faults(transition: &VoiceTransition, from: &[AudioNote], to: &[AudioNote]) -> Vec<VoiceLeadingIssue> {
+    let chord = transition.from_chord;
+    // Only voices that move can move in parallel or leap; held, entering and leaving voices are skipped
+    let moving: Vec<(usize, &VoiceMotion, usize, usize)> = transition
+        .motions
+        .iter()
+        .enumerate()
+        .filter(|(_, motion)| matches!(motion.direction, MotionDirection::Up | MotionDirection::Down))
+        .filter_map(|(voice, motion)| Some((voice, motion, motion.from_index?, motion.to_index?)))
+        .collect();
+    let interval_class = |a: Option<u8>, b: Option<u8>| Some(a?.abs_diff(b?) % 12);
+
+    let mut issues = Vec::new();
+    for (position, &(_, lower, lower_from, lower_to)) in moving.iter().enumerate() {
+        for &(_, upper, upper_from, upper_to) in &moving[position + 1..] {
+            if lower.direction != upper.direction {
+                continue;
+            }
+            let before = interval_class(lower.from_midi, upper.from_midi);
+            let after = interval_class(lower.to_midi, upper.to_midi);
+            let (kind, interval) = match (before, after) {
+                (Some(7), Some(7)) => (VoiceLeadingIssueKind::ParallelFifths, "fifths"),
+                (Some(0), Some(0)) => (VoiceLeadingIssueKind::ParallelOctaves, "octaves"),
+                _ => continue,
+            };
+            issues.push(VoiceLeadingIssue {
+                kind,
+                chord,
+                notes: vec![lower_from, upper_from],
+                description: format!(
+                    "{} and {} move to {} and {} in parallel {}",
+                    name(&from[lower_from]),
+                    name(&from[upper_from]),
+                    name(&to[lower_to]),
+                    name(&to[upper_to]),
+                    interval
+                ),
+            });
+        }
+    }
+
+    for &(voice, motion, from_index, to_index) in &moving {
+        // The bass is always the first motion
+        let limit = if voice == 0 { MAX_BASS_LEAP } else { MAX_UPPER_LEAP };
+        if motion.semitones.unsigned_abs() > limit {
+            issues.push(VoiceLeadingIssue {
+                kind: VoiceLeadingIssueKind::LargeLeap,
+                chord,
+                notes: vec![from_index],
+                description: format!(
+                    "{} leaps {} half steps to {}",
+                    name(&from[from_index]),
+                    motion.semitones.unsigned_abs(),
+                    name(&to[to_index])
+                ),
+            });
+        }
+    }
+    issues
+}
+
+/// Check a voiced progression for parallel fifths and octaves, voice crossings and large leaps
+/// Each chord's notes are in voice order, bass first; voices are followed between chords as voice_motions
+/// pairs them, so chords may have different numbers of notes
+pub fn analyze_voice_leading(voicings: &[Vec<AudioNote>]) -> MusicResult<VoiceLeadingReport> {
+    let transitions = voice_motions(voicings)?;
+
+    let mut issues = Vec::new();
+    for (index, chord) in voicings.iter().enumerate() {
+        issues.extend(crossings(index, chord)?);
+        if let Some(transition) = transitions.get(index) {
+            issues.extend(motion_faults(transition, chord, &voicings[index + 1]));
+        }
+    }
+
+    let movement: Vec<u32> = transitions.iter().map(|transition| transition.total_semitones).collect();
+    Ok(VoiceLeadingReport { issues, total_semitones: movement.iter().sum(), movement })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(notes: &[(&str, i8)]) -> Vec<AudioNote> {
+        notes
+            .iter()
+            .map(|&(note, octave)| AudioNote { note: note.to_string(), octave, is_common_tone: false, cents: 0.0 })
+            .collect()
+    }
+
+    fn kinds(report: &VoiceLeadingReport) -> Vec<(usize, VoiceLeadingIssueKind)> {
+        report.issues.iter().map(|issue| (issue.chord, issue.kind)).collect()
+    }
+
+    #[test]
+    fn test_clean_progression() {
+        // I - IV - V - I in textbook four-part writing
+        let voicings = [
+            chord(&[("C", 3), ("E", 4), ("G", 4), ("C", 5)]),
+            chord(&[("F", 3), ("F", 4), ("A", 4), ("C", 5)]),
+            chord(&[("G", 3), ("D", 4), ("G", 4), ("B", 4)]),
+            chord(&[("C", 3), ("E", 4), ("G", 4), ("C", 5)]),
+        ];
+        let report = analyze_voice_leading(&voicings).unwrap();
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+        assert_eq!(report.movement, [8, 8, 10]);
+        assert_eq!(report.total_semitones, 26);
+    }
+
+    #[test]
+    fn test_parallels_and_leaps() {
+        use VoiceLeadingIssueKind::*;
+        // Root-position triads a step apart moved in block: fifths and octaves against the bass
+        let voicings = [
+            chord(&[("C", 3), ("G", 3), ("C", 4), ("E", 4)]),
+            chord(&[("D", 3), ("A", 3), ("D", 4), ("F", 4)]),
+        ];
+        let report = analyze_voice_leading(&voicings).unwrap();
+        assert_eq!(kinds(&report), [(0, ParallelFifths), (0, ParallelOctaves)]);
+        assert_eq!(report.issues[0].notes, [0, 1]);
+        assert_eq!(report.issues[0].description, "C3 and G3 move to D3 and A3 in parallel fifths");
+
+        // Contrary fifths are not parallel; the soprano's leap of a sixth is large
+        let voicings = [chord(&[("C", 3), ("G", 3), ("E", 4)]), chord(&[("G", 2), ("D", 4), ("C", 5)])];
+        let report = analyze_voice_leading(&voicings).unwrap();
+        assert_eq!(kinds(&report), [(0, LargeLeap)]);
+        assert_eq!(report.issues[0].description, "E4 leaps 8 half steps to C5");
+    }
+
+    #[test]
+    fn test_voice_crossing() {
+        let voicings = [chord(&[("C", 3), ("E", 4), ("C", 4)])];
+        let report = analyze_voice_leading(&voicings).unwrap();
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].kind, VoiceLeadingIssueKind::VoiceCrossing);
+        assert_eq!(report.issues[0].notes, [1, 2]);
+        assert!(report.movement.is_empty());
+
+        assert!(analyze_voice_leading(&[chord(&[("H", 3)])]).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+// Voice motion
+// How each voice moves between consecutive voiced chords, for drawing motion arrows between chord blocks
+
+use serde::Serialize;
+
+use super::types::{AudioNote, MusicError, MusicResult};
+use super::voice_leading::note_to_midi;
+
+/// Which way a voice goes from one chord to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MotionDirection {
+    Up,
+    Down,
+    Held,
+    /// A voice the next chord adds, with no voice to come from
+    Enters,
+    /// A voice of the previous chord with nowhere to go
+    Leaves,
+}
+
+/// One voice's motion across a transition
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VoiceMotion {
+    /// Index of the note in the previous chord as given (None when the voice enters)
+    pub from_index: Option<usize>,
+    /// Index of the note in the next chord as given (None when the voice leaves)
+    pub to_index: Option<usize>,
+    pub from_midi: Option<u8>,
+    pub to_midi: Option<u8>,
+    /// Signed half steps moved, 0 for held, entering and leaving voices
+    pub semitones: i8,
+    pub direction: MotionDirection,
+}
+
+/// Every voice's motion from one chord to the next, lowest voice first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VoiceTransition {
+    /// Index of the previous chord; the next chord is the one after it
+    pub from_chord: usize,
+    pub motions: Vec<VoiceMotion>,
+    /// Half steps moved by all voices together
+    pub total_semitones: u32,
+}
+
+/// A note of a chord as (index as given, MIDI)
+type Voice = (usize, u8);
+
+/// A chord's notes, lowest first
+fn sounding(chord: &[AudioNote]) -> MusicResult<Vec<Voice>> {
+    let mut notes = chord
+        .iter()
+        .enumerate()
+        .map(|(index, note)| Ok((index, note_to_midi(&note.note, note.octave)?)))
+        .collect::<MusicResult<Vec<_>>>()?;
+    notes.sort_by_key(|&(index, midi)| (midi, index));
+    Ok(notes)
+}
+
+fn motion(from: Option<Voice>, to: Option<Voice>) -> VoiceMotion {
+    let (semitones, direction) = match (from, to) {
+        (Some((_, a)), Some((_, b))) => {
+            let semitones = b as i8 - a as i8;
+            let direction = match semitones {
+                0 => MotionDirection::Held,
+                s if s > 0 => MotionDirection::Up,
+                _ => MotionDirection::Down,
+            };
+            (semitones, direction)
+        }
+        (None, _) => (0, MotionDirection::Enters),
+        (_, None) => (0, MotionDirection::Leaves),
+    };
+    VoiceMotion {
+        from_index: from.map(|(index, _)| index),
+        to_index: to.map(|(index, _)| index),
+        from_midi: from.map(|(_, midi)| midi),
+        to_midi: to.map(|(_, midi)| midi),
+        semitones,
+        direction,
+    }
+}
+
+/// Pair the voices of two chords without crossing, so that every voice of the smaller chord moves to the
+/// voice of the larger one that gives the least total motion; the rest enter or leave
+fn pair_voices(from: &[Voice], to: &[Voice]) -> Vec<(Option<Voice>, Option<Voice>)> {
+    let flipped = from.len() > to.len();
+    let (short, long) = if flipped { (to, from) } else { (from, to) };
+
+    // cost[i][j]: least motion pairing the first i short voices within the first j long voices
+    let (n, m) = (short.len(), long.len());
+    let mut cost = vec![vec![u32::MAX; m + 1]; n + 1];
+    cost[0] = vec![0; m + 1];
+    for i in 1..=n {
+        for j in i..=m {
+            let paired = cost[i - 1][j - 1].saturating_add(short[i - 1].1.abs_diff(long[j - 1].1) as u32);
+            cost[i][j] = paired.min(cost[i][j - 1]);
+        }
+    }
+
+    // Walk back from the end, lowest voice last
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while j > 0 {
+        if i > 0 && cost[i][j] != cost[i][j - 1] {
+            pairs.push((Some(short[i - 1]), Some(long[j - 1])));
+            i -= 1;
+        } else {
+            pairs.push((None, Some(long[j - 1])));
+        }
+        j -= 1;
+    }
+    pairs.reverse();
+    if flipped {
+        pairs.into_iter().map(|(a, b)| (b, a)).collect()
+    } else {
+        pairs
+    }
+}
+
+/// How each voice moves between consecutive chords of a voiced progression
+/// The bass always moves to the bass; upper voices are paired without crossing, by least motion
+pub fn voice_motions(voicings: &[Vec<AudioNote>]) -> MusicResult<Vec<VoiceTransition>> {
+    let chords = voicings.iter().map(|chord| sounding(chord)).collect::<MusicResult<Vec<_>>>()?;
+    if chords.iter().any(|chord| chord.is_empty()) {
+        return Err(MusicError::VoiceLeadingError("Empty voicing".to_string()));
+    }
+
+    Ok(chords
+        .windows(2)
+        .enumerate()
+        .map(|(from_chord, pair)| {
+            let ((from_bass, from_upper), (to_bass, to_upper)) = (pair[0].split_at(1), pair[1].split_at(1));
+            let mut motions = vec![motion(Some(from_bass[0]), Some(to_bass[0]))];
+            motions.extend(pair_voices(from_upper, to_upper).into_iter().map(|(from, to)| motion(from, to)));
+            let total_semitones = motions.iter().map(|motion| motion.semitones.unsigned_abs() as u32).sum();
+            VoiceTransition { from_chord, motions, total_semitones }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(notes: &[(&str, i8)]) -> Vec<AudioNote> {
+        notes
+            .iter()
+            .map(|&(note, octave)| AudioNote { note: note.to_string(), octave, is_common_tone: false, cents: 0.0 })
+            .collect()
+    }
+
+    fn moves(transition: &VoiceTransition) -> Vec<(i8, MotionDirection)> {
+        transition.motions.iter().map(|motion| (motion.semitones, motion.direction)).collect()
+    }
+
+    #[test]
+    fn test_triad_motions() {
+        // C to F in the usual voice leading: G up to A, E up to F, C held
+        let voicings = [
+            chord(&[("C", 3), ("G", 3), ("E", 4), ("C", 5)]),
+            chord(&[("F", 2), ("A", 3), ("F", 4), ("C", 5)]),
+        ];
+        let transitions = voice_motions(&voicings).unwrap();
+        assert_eq!(transitions.len(), 1);
+        use MotionDirection::*;
+        assert_eq!(moves(&transitions[0]), [(-7, Down), (2, Up), (1, Up), (0, Held)]);
+        assert_eq!(transitions[0].total_semitones, 10);
+        assert_eq!(transitions[0].motions[1].from_index, Some(1));
+    }
+
+    #[test]
+    fn test_changing_voice_count() {
+        use MotionDirection::*;
+        let c = chord(&[("C", 3), ("E", 4), ("G", 4)]);
+        // Notes out of order: indices follow the input, motions go lowest voice first
+        let g7 = chord(&[("G", 2), ("F", 4), ("B", 3), ("D", 4)]);
+
+        let transitions = voice_motions(&[c.clone(), g7, c]).unwrap();
+        // E falls to D, G falls to F, and the B below them enters
+        assert_eq!(moves(&transitions[0]), [(-5, Down), (0, Enters), (-2, Down), (-2, Down)]);
+        assert_eq!(transitions[0].motions[3].to_index, Some(1));
+        // Back to C the B leaves, as the D and F resolve to E and G
+        assert_eq!(moves(&transitions[1]), [(5, Up), (0, Leaves), (2, Up), (2, Up)]);
+        assert_eq!(transitions[1].from_chord, 1);
+
+        assert!(voice_motions(&[]).unwrap().is_empty());
+        assert!(voice_motions(&[chord(&[])]).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+// Analysis line
+// Roman numerals with inversion figures, function letters and cadence brackets printed beneath the staff,
+// laid out as in theory textbooks
+
+use super::voicing::quoted;
+use crate::music::analysis::{Cadence, ChordAnalysis, HarmonicFunction};
+use crate::music::chords::parse_chord;
+use crate::music::intervals::chord_to_notes;
+use crate::music::notes::note_index;
+
+/// Figures after a triad's numeral, by inversion
+const TRIAD_FIGURES: [&[&str]; 3] = [&[], &["6"], &["6", "4"]];
+/// Figures after a seventh chord's numeral, by inversion; root position is V7
+const SEVENTH_FIGURES: [&[&str]; 4] = [&["7"], &["6", "5"], &["4", "3"], &["4", "2"]];
+
+/// Engraver the cadence brackets need, added to the Voice context of scores with an analysis line
+pub const BRACKET_ENGRAVER: &str = r#"\context { \Voice \consists "Horizontal_bracket_engraver" }"#;
+
+/// Chord tone in the bass: 0 in root position, 1 in first inversion, ...
+/// None when the bass isn't a chord tone
+fn inversion(chord: &str) -> Option<usize> {
+    let Some(bass) = parse_chord(chord).ok()?.bass else {
+        return Some(0);
+    };
+    let main = chord.split('/').next().unwrap_or(chord);
+    let bass = note_index(&bass).ok()?;
+    chord_to_notes(main).ok()?.iter().position(|tone| note_index(tone).ok() == Some(bass))
+}
+
+/// Numeral without its bass, and the figures showing the inversion: "V7/B" on G7/B → ("V", ["6", "5"])
+/// A bass outside the chord keeps the numeral as displayed ("I/D")
+fn numeral_with_figures(numeral: &str, chord: &str) -> (String, &'static [&'static str]) {
+    let base = numeral.split('/').next().unwrap_or(numeral);
+    let Some(inversion) = inversion(chord) else {
+        return (numeral.to_string(), &[]);
+    };
+    let figures = match base.strip_suffix('7') {
+        Some(stem) => SEVENTH_FIGURES.get(inversion).map(|figures| (stem.replace("maj", "M"), *figures)),
+        None => TRIAD_FIGURES.get(inversion).map(|figures| (base.to_string(), *figures)),
+    };
+    figures.unwrap_or_else(|| (numeral.to_string(), &[]))
+}
+
+fn function_letter(function: HarmonicFunction) -> Option<&'static str> {
+    match function {
+        HarmonicFunction::Tonic => Some("T"),
+        HarmonicFunction::Predominant => Some("S"),
+        HarmonicFunction::Dominant => Some("D"),
+        HarmonicFunction::Chromatic => None,
+    }
+}
+
+fn cadence_label(cadence: Cadence) -> &'static str {
+    match cadence {
+        Cadence::Authentic => "AC",
+        Cadence::Plagal => "PC",
+        Cadence::Deceptive => "DC",
+        Cadence::Half => "HC",
+    }
+}
+
+/// Numeral markup with its figures raised after it, one figure beside the numeral or two stacked
+fn numeral_markup(numeral: &str, figures: &[&str]) -> String {
+    let figures = figures.iter().map(|figure| quoted(figure)).collect::<Vec<_>>();
+    match figures.as_slice() {
+        [] => quoted(numeral),
+        [figure] => format!("\\concat {{ {} \\super {} }}", quoted(numeral), figure),
+        stacked => format!(
+            "\\concat {{ {} \\raise #1 \\fontsize #-3 \\override #'(baseline-skip . 1.6) \\column {{ {} }} }}",
+            quoted(numeral),
+            stacked.join(" ")
+        ),
+    }
+}
+
+/// Markup under one chord: its numeral (or applied-chord reading) over its function letter
+/// None for chords that can't be analyzed in the key
+fn chord_markup(analysis: &ChordAnalysis) -> Option<String> {
+    let numeral = match &analysis.applied {
+        Some(applied) => numeral_markup(applied, &[]),
+        None => {
+            let (numeral, figures) = numeral_with_figures(analysis.numeral.as_deref()?, &analysis.chord);
+            numeral_markup(&numeral, figures)
+        }
+    };
+    let mut lines = vec![numeral];
+    if let Some(letter) = analysis.function.and_then(function_letter) {
+        lines.push(format!("\\small {}", quoted(letter)));
+    }
+    Some(format!("_\\markup {{ \\center-column {{ {} }} }}", lines.join(" ")))
+}
+
+/// Post-events to attach to the first note of each analyzed chord: its analysis markup, and cadence
+/// brackets that run from the chord before each cadence to the chord it arrives on
+pub fn analysis_line(analysis: &[ChordAnalysis]) -> Vec<String> {
+    let mut events: Vec<String> = analysis.iter().map(|chord| chord_markup(chord).unwrap_or_default()).collect();
+    for (index, chord) in analysis.iter().enumerate().skip(1) {
+        if let Some(cadence) = chord.cadence {
+            let start = format!("-\\tweak HorizontalBracketText.text {} \\startGroup", quoted(cadence_label(cadence)));
+            events[index - 1].push_str(&start);
+            events[index].push_str("\\stopGroup");
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::analysis::analyze_progression;
+
+    fn line(chords: &[&str], key: &str) -> Vec<String> {
+        let chords: Vec<String> = chords.iter().map(|chord| chord.to_string()).collect();
+        analysis_line(&analyze_progression(&chords, key))
+    }
+
+    #[test]
+    fn test_inversion_figures() {
+        assert_eq!(numeral_with_figures("V7/B", "G7/B"), ("V".to_string(), &["6", "5"][..]));
+        assert_eq!(numeral_with_figures("I/G", "C/G"), ("I".to_string(), &["6", "4"][..]));
+        assert_eq!(numeral_with_figures("V7", "G7"), ("V".to_string(), &["7"][..]));
+        assert_eq!(numeral_with_figures("Imaj7/B", "Cmaj7/B"), ("IM".to_string(), &["4", "2"][..]));
+        assert_eq!(numeral_with_figures("I/D", "C/D"), ("I/D".to_string(), &[][..]));
+    }
+
+    #[test]
+    fn test_numerals_functions_and_cadences() {
+        let events = line(&["C", "F/A", "G7/B", "C"], "C");
+        assert_eq!(events[0], "_\\markup { \\center-column { \"I\" \\small \"T\" } }");
+        assert_eq!(events[1], "_\\markup { \\center-column { \\concat { \"IV\" \\super \"6\" } \\small \"S\" } }");
+        assert!(events[2].ends_with("-\\tweak HorizontalBracketText.text \"AC\" \\startGroup"));
+        assert!(events[2].contains("\\column { \"6\" \"5\" }"));
+        assert!(events[3].ends_with("\\stopGroup"));
+
+        // Applied chords show their applied reading (V/V leads to V, so it is predominant);
+        // chromatic chords have no function letter
+        let events = line(&["C", "D7", "G", "Bb"], "C");
+        assert_eq!(events[1], "_\\markup { \\center-column { \"V7/V\" \\small \"S\" } }");
+        assert_eq!(events[3], "_\\markup { \\center-column { \"bVII\" } }");
+    }
+}
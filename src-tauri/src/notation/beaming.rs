@@ -0,0 +1,363 @@
+// Beaming and stem directions
+// Groups note durations into beams by time signature and picks stem directions for rhythm rendering
+
+use serde::{Deserialize, Serialize};
+
+use crate::music::types::{MusicError, MusicResult};
+
+/// Ticks per whole note (quarter = 480)
+pub const TICKS_PER_WHOLE: u32 = 1920;
+
+/// Staff position of the middle line (steps above the bottom line)
+const MIDDLE_LINE: i8 = 4;
+
+/// Written note value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteValue {
+    Whole,
+    Half,
+    Quarter,
+    Eighth,
+    Sixteenth,
+    #[serde(rename = "thirtysecond")]
+    ThirtySecond,
+}
+
+impl NoteValue {
+    fn ticks(self) -> u32 {
+        match self {
+            NoteValue::Whole => TICKS_PER_WHOLE,
+            NoteValue::Half => TICKS_PER_WHOLE / 2,
+            NoteValue::Quarter => TICKS_PER_WHOLE / 4,
+            NoteValue::Eighth => TICKS_PER_WHOLE / 8,
+            NoteValue::Sixteenth => TICKS_PER_WHOLE / 16,
+            NoteValue::ThirtySecond => TICKS_PER_WHOLE / 32,
+        }
+    }
+
+    /// Number of beams (or flags) the value is drawn with
+    fn beam_count(self) -> u8 {
+        match self {
+            NoteValue::Eighth => 1,
+            NoteValue::Sixteenth => 2,
+            NoteValue::ThirtySecond => 3,
+            _ => 0,
+        }
+    }
+
+    fn has_stem(self) -> bool {
+        self != NoteValue::Whole
+    }
+
+    fn lilypond_duration(self) -> &'static str {
+        match self {
+            NoteValue::Whole => "1",
+            NoteValue::Half => "2",
+            NoteValue::Quarter => "4",
+            NoteValue::Eighth => "8",
+            NoteValue::Sixteenth => "16",
+            NoteValue::ThirtySecond => "32",
+        }
+    }
+}
+
+/// One note or rest of a rhythm
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RhythmNote {
+    pub value: NoteValue,
+    #[serde(default)]
+    pub dots: u8,
+    #[serde(default)]
+    pub rest: bool,
+    /// Steps above the bottom staff line, used for stem direction (defaults to the middle line)
+    #[serde(default)]
+    pub staff_position: Option<i8>,
+}
+
+impl RhythmNote {
+    /// Duration in ticks including dots
+    pub fn ticks(&self) -> u32 {
+        let base = self.value.ticks();
+        (1..=self.dots as u32).fold(base, |total, dot| total + (base >> dot))
+    }
+
+    /// LilyPond duration string ("8.", "4")
+    pub fn lilypond_duration(&self) -> String {
+        format!("{}{}", self.value.lilypond_duration(), ".".repeat(self.dots as usize))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StemDirection {
+    Up,
+    Down,
+}
+
+/// Layout of one input note
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NoteLayout {
+    /// 1-based measure number
+    pub measure: u32,
+    /// Onset in ticks from the start of the measure
+    pub offset: u32,
+    /// None for rests and whole notes
+    pub stem: Option<StemDirection>,
+    /// Index into RhythmLayout::beams when the note is beamed
+    pub beam: Option<usize>,
+    /// Flags drawn on an unbeamed note
+    pub flags: u8,
+}
+
+/// A run of notes joined by beams
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BeamGroup {
+    /// Indices of the beamed notes
+    pub notes: Vec<usize>,
+    /// Shared stem direction for the group
+    pub stem: StemDirection,
+    /// Beam count of the shortest note in the group
+    pub levels: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RhythmLayout {
+    pub measure_ticks: u32,
+    /// Beam group boundaries within a measure, in ticks
+    pub beat_groups: Vec<u32>,
+    pub notes: Vec<NoteLayout>,
+    pub beams: Vec<BeamGroup>,
+}
+
+/// Parse "6/8" into (beats, beat unit)
+pub fn parse_time_signature(time_signature: &str) -> MusicResult<(u32, u32)> {
+    let invalid = || MusicError::ParseError(format!("Invalid time signature: {}", time_signature));
+    let (beats, unit) = time_signature.split_once('/').ok_or_else(invalid)?;
+    let beats: u32 = beats.trim().parse().map_err(|_| invalid())?;
+    let unit: u32 = unit.trim().parse().map_err(|_| invalid())?;
+    if beats == 0 || !unit.is_power_of_two() || unit > 32 {
+        return Err(invalid());
+    }
+    Ok((beats, unit))
+}
+
+/// Lengths (ticks) of the beam groups in a measure
+/// Compound meters group by dotted beats, 4/4 by half bars, odd eighth meters as 2+2+...+3
+fn beat_group_lengths(beats: u32, unit: u32) -> Vec<u32> {
+    let unit_ticks = TICKS_PER_WHOLE / unit;
+    match (beats, unit) {
+        (4, 4) => vec![unit_ticks * 2; 2],
+        (b, 8) if b % 3 == 0 && b > 3 => vec![unit_ticks * 3; (b / 3) as usize],
+        (b, 8) if b % 2 == 1 && b > 3 => {
+            let mut groups = vec![unit_ticks * 2; ((b - 3) / 2) as usize];
+            groups.push(unit_ticks * 3);
+            groups
+        }
+        (b, _) => vec![unit_ticks; b as usize],
+    }
+}
+
+/// Stem direction for notes at the given staff positions
+/// The note furthest from the middle line decides; the middle line itself takes a down stem
+fn stem_for(positions: impl Iterator<Item = i8>) -> StemDirection {
+    let furthest = positions.max_by_key(|p| ((p - MIDDLE_LINE).abs(), -*p)).unwrap_or(MIDDLE_LINE);
+    if furthest >= MIDDLE_LINE {
+        StemDirection::Down
+    } else {
+        StemDirection::Up
+    }
+}
+
+/// Compute beams and stem directions for a rhythm in the given time signature
+pub fn layout_rhythm(time_signature: &str, notes: &[RhythmNote]) -> MusicResult<RhythmLayout> {
+    let (beats, unit) = parse_time_signature(time_signature)?;
+    let group_lengths = beat_group_lengths(beats, unit);
+    let measure_ticks = TICKS_PER_WHOLE * beats / unit;
+
+    // Group boundaries as offsets within the measure
+    let beat_groups: Vec<u32> = group_lengths
+        .iter()
+        .scan(0, |start, len| {
+            let boundary = *start;
+            *start += len;
+            Some(boundary)
+        })
+        .collect();
+    let group_of = |offset: u32| beat_groups.iter().rposition(|start| *start <= offset).unwrap_or(0);
+
+    let position = |note: &RhythmNote| note.staff_position.unwrap_or(MIDDLE_LINE);
+
+    let mut layouts = Vec::with_capacity(notes.len());
+    let mut beams: Vec<BeamGroup> = Vec::new();
+    let mut current: Vec<usize> = Vec::new();
+    let mut current_key: Option<(u32, usize)> = None;
+    let mut onset = 0u32;
+
+    let close = |current: &mut Vec<usize>, beams: &mut Vec<BeamGroup>| {
+        if current.len() >= 2 {
+            beams.push(BeamGroup {
+                notes: current.clone(),
+                stem: stem_for(current.iter().map(|i| position(&notes[*i]))),
+                levels: current.iter().map(|i| notes[*i].value.beam_count()).max().unwrap_or(1),
+            });
+        }
+        current.clear();
+    };
+
+    for (index, note) in notes.iter().enumerate() {
+        let measure = onset / measure_ticks;
+        let offset = onset % measure_ticks;
+        let key = (measure, group_of(offset));
+
+        let beamable = !note.rest && note.value.beam_count() > 0;
+        if !beamable || current_key != Some(key) {
+            close(&mut current, &mut beams);
+        }
+        if beamable {
+            current.push(index);
+            current_key = Some(key);
+        } else {
+            current_key = None;
+        }
+
+        layouts.push(NoteLayout {
+            measure: measure + 1,
+            offset,
+            stem: (!note.rest && note.value.has_stem()).then(|| stem_for(std::iter::once(position(note)))),
+            beam: None,
+            flags: 0,
+        });
+        onset += note.ticks();
+    }
+    close(&mut current, &mut beams);
+
+    // Beamed notes share the group's stem; lone short notes keep flags
+    for (group_index, group) in beams.iter().enumerate() {
+        for index in &group.notes {
+            layouts[*index].beam = Some(group_index);
+            layouts[*index].stem = Some(group.stem);
+        }
+    }
+    for (layout, note) in layouts.iter_mut().zip(notes) {
+        if layout.beam.is_none() && !note.rest {
+            layout.flags = note.value.beam_count();
+        }
+    }
+
+    Ok(RhythmLayout { measure_ticks, beat_groups, notes: layouts, beams })
+}
+
+/// LilyPond rhythm with explicit beams and stem directions
+/// Every note is written at `pitch`, so only the rhythm is shown
+pub fn to_lilypond(layout: &RhythmLayout, notes: &[RhythmNote], pitch: &str) -> String {
+    let mut output = Vec::with_capacity(notes.len());
+    let mut stem: Option<StemDirection> = None;
+
+    for (index, (note, note_layout)) in notes.iter().zip(&layout.notes).enumerate() {
+        let mut token = String::new();
+        if note_layout.stem.is_some() && note_layout.stem != stem {
+            stem = note_layout.stem;
+            token.push_str(match stem {
+                Some(StemDirection::Up) => "\\stemUp ",
+                _ => "\\stemDown ",
+            });
+        }
+
+        token.push_str(if note.rest { "r" } else { pitch });
+        token.push_str(&note.lilypond_duration());
+
+        if let Some(group) = note_layout.beam.map(|b| &layout.beams[b]) {
+            if group.notes.first() == Some(&index) {
+                token.push('[');
+            } else if group.notes.last() == Some(&index) {
+                token.push(']');
+            }
+        }
+        output.push(token);
+    }
+
+    output.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(value: NoteValue, staff_position: i8) -> RhythmNote {
+        RhythmNote { value, dots: 0, rest: false, staff_position: Some(staff_position) }
+    }
+
+    fn rest(value: NoteValue) -> RhythmNote {
+        RhythmNote { value, dots: 0, rest: true, staff_position: None }
+    }
+
+    #[test]
+    fn test_eighths_in_four_four_beam_by_half_bar() {
+        let notes = vec![note(NoteValue::Eighth, 2); 8];
+        let layout = layout_rhythm("4/4", &notes).unwrap();
+
+        assert_eq!(layout.beams.len(), 2);
+        assert_eq!(layout.beams[0].notes, vec![0, 1, 2, 3]);
+        assert_eq!(layout.beams[1].notes, vec![4, 5, 6, 7]);
+        assert_eq!(layout.beams[0].stem, StemDirection::Up);
+    }
+
+    #[test]
+    fn test_compound_meter_groups_by_dotted_quarter() {
+        let notes = vec![note(NoteValue::Eighth, 6); 6];
+        let layout = layout_rhythm("6/8", &notes).unwrap();
+
+        assert_eq!(layout.beat_groups, vec![0, 720]);
+        assert_eq!(layout.beams[0].notes, vec![0, 1, 2]);
+        assert_eq!(layout.beams[1].notes, vec![3, 4, 5]);
+        assert_eq!(layout.beams[1].stem, StemDirection::Down);
+    }
+
+    #[test]
+    fn test_rests_break_beams_and_single_notes_keep_flags() {
+        let notes = vec![
+            note(NoteValue::Eighth, 2),
+            rest(NoteValue::Eighth),
+            note(NoteValue::Sixteenth, 2),
+            note(NoteValue::Sixteenth, 2),
+            note(NoteValue::Quarter, 7),
+            note(NoteValue::Half, 1),
+        ];
+        let layout = layout_rhythm("3/4", &notes).unwrap();
+
+        assert_eq!(layout.beams.len(), 1);
+        assert_eq!(layout.beams[0].notes, vec![2, 3]);
+        assert_eq!(layout.beams[0].levels, 2);
+        assert_eq!(layout.notes[0].flags, 1);
+        assert_eq!(layout.notes[1].stem, None);
+        assert_eq!(layout.notes[4].stem, Some(StemDirection::Down));
+        assert_eq!((layout.notes[5].measure, layout.notes[5].offset), (1, 1200));
+    }
+
+    #[test]
+    fn test_group_stem_follows_furthest_note() {
+        let notes = vec![note(NoteValue::Eighth, 3), note(NoteValue::Eighth, 9)];
+        let layout = layout_rhythm("2/4", &notes).unwrap();
+        assert_eq!(layout.beams[0].stem, StemDirection::Down);
+        assert!(layout.notes.iter().all(|n| n.stem == Some(StemDirection::Down)));
+    }
+
+    #[test]
+    fn test_dotted_durations_and_lilypond_output() {
+        let mut dotted = note(NoteValue::Eighth, 2);
+        dotted.dots = 1;
+        let notes = vec![dotted, note(NoteValue::Sixteenth, 2), note(NoteValue::Quarter, 2)];
+        let layout = layout_rhythm("2/4", &notes).unwrap();
+
+        assert_eq!(notes[0].ticks(), 360);
+        assert_eq!(layout.notes[2].offset, 480);
+        assert_eq!(to_lilypond(&layout, &notes, "c'"), "\\stemUp c'8.[ c'16] c'4");
+    }
+
+    #[test]
+    fn test_invalid_time_signature() {
+        assert!(layout_rhythm("4", &[]).is_err());
+        assert!(layout_rhythm("3/5", &[]).is_err());
+    }
+}
@@ -0,0 +1,526 @@
+// Braille music
+// Worksheet content transcribed to Braille Ready Format (BRF), the North American ASCII braille read by
+// embossers and braille displays, following the single-line format of the Braille Music Code
+
+use std::collections::HashMap;
+
+use super::description::{element_chord_symbol, lilypond_pitch};
+use super::key_signature::{get_key_signature_layout, StaffClef};
+use crate::music::intervals::chord_to_notes;
+use crate::music::types::{Accidental, MusicError, MusicResult};
+use crate::types::worksheet::{Clef, EditableElement, EditableElementType, WorksheetConfig, WorksheetSection};
+
+/// Cells per line and lines per page of a standard embossed page
+const LINE_CELLS: usize = 40;
+const PAGE_LINES: usize = 25;
+
+const LETTERS: [char; 7] = ['C', 'D', 'E', 'F', 'G', 'A', 'B'];
+
+/// Note cells for C to B, by value: eighth, quarter, half, whole
+const NOTE_CELLS: [[char; 7]; 4] = [
+    ['d', 'e', 'f', 'g', 'h', 'i', 'j'],
+    ['?', ':', '$', ']', '\\', '[', 'w'],
+    ['n', 'o', 'p', 'q', 'r', 's', 't'],
+    ['y', 'z', '&', '=', '(', '!', ')'],
+];
+
+/// Rest cells by value: eighth, quarter, half, whole (the whole rest also fills an empty measure)
+const REST_CELLS: [char; 4] = ['x', 'v', 'u', 'm'];
+
+/// Octave marks for octaves 1 to 7; octave 4 starts at middle C
+const OCTAVE_MARKS: [&str; 7] = ["@", "^", "_", "\"", ".", ";", ","];
+
+/// Interval signs by diatonic steps, second to seventh; index 0 is the octave
+const INTERVAL_SIGNS: [char; 7] = ['-', '/', '+', '#', '9', '0', '3'];
+
+/// Dot added to a note or rest value
+const DOT: char = '\'';
+const FINAL_BAR: &str = "<k";
+const NUMBER_SIGN: char = '#';
+const CAPITAL_SIGN: char = ',';
+/// Marks a letter a-j after a number as a letter rather than a digit
+const LETTER_SIGN: char = ';';
+
+/// Written value of a note or rest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Value {
+    Eighth,
+    Quarter,
+    Half,
+    Whole,
+}
+
+impl Value {
+    fn from_lilypond(duration: &str) -> Option<Self> {
+        match duration {
+            "8" => Some(Value::Eighth),
+            "4" => Some(Value::Quarter),
+            "2" => Some(Value::Half),
+            "1" => Some(Value::Whole),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pitch {
+    /// Index into LETTERS
+    letter: usize,
+    /// Semitones of sharps (positive) or flats (negative)
+    alter: i8,
+    octave: i8,
+}
+
+impl Pitch {
+    fn parse(name: &str, octave: i8) -> Option<Self> {
+        let mut chars = name.chars();
+        let first = chars.next()?;
+        let letter = LETTERS.iter().position(|letter| *letter == first)?;
+        let alter = chars
+            .map(|accidental| match accidental {
+                '#' => Some(1),
+                'b' => Some(-1),
+                _ => None,
+            })
+            .sum::<Option<i8>>()?;
+        Some(Self { letter, alter, octave })
+    }
+
+    /// Diatonic steps from C0, counting letter names only
+    fn step(&self) -> i32 {
+        self.octave as i32 * 7 + self.letter as i32
+    }
+}
+
+/// What sounds on one beat of a worksheet staff
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    Note(Pitch),
+    Chord(Vec<Pitch>),
+    Rest(Value, usize),
+}
+
+fn octave_mark(octave: i8) -> &'static str {
+    match octave {
+        i8::MIN..=0 => "@@",
+        1..=7 => OCTAVE_MARKS[octave as usize - 1],
+        _ => ",,",
+    }
+}
+
+/// A melody note needs its octave marked after a sixth or more, or after a fourth or fifth into another octave
+fn needs_octave_mark(previous: Option<Pitch>, pitch: Pitch) -> bool {
+    let Some(previous) = previous else {
+        return true;
+    };
+    let steps = (pitch.step() - previous.step()).abs();
+    steps >= 5 || (steps >= 3 && previous.octave != pitch.octave)
+}
+
+fn accidental_sign(alter: i8) -> String {
+    match alter {
+        0 => "*".to_string(),
+        sharps if sharps > 0 => "%".repeat(sharps as usize),
+        flats => "<".repeat(flats.unsigned_abs() as usize),
+    }
+}
+
+/// Upper-cell digits after a number sign: "12" → "#ab"
+fn upper_number(digits: &str) -> String {
+    let cells = digits.chars().filter_map(|digit| match digit {
+        '0' => Some('j'),
+        '1'..='9' => Some((b'a' + (digit as u8 - b'1')) as char),
+        _ => None,
+    });
+    std::iter::once(NUMBER_SIGN).chain(cells).collect()
+}
+
+/// Uncontracted (grade 1) braille for titles and instructions
+/// Characters without a braille equivalent here are left out
+pub fn braille_text(text: &str) -> String {
+    let mut braille = String::new();
+    let mut in_number = false;
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            if !in_number {
+                braille.push(NUMBER_SIGN);
+                in_number = true;
+            }
+            braille.push_str(&upper_number(&c.to_string())[1..]);
+            continue;
+        }
+        if c.is_ascii_alphabetic() {
+            if c.is_ascii_uppercase() {
+                braille.push(CAPITAL_SIGN);
+            } else if in_number && ('a'..='j').contains(&c) {
+                braille.push(LETTER_SIGN);
+            }
+            braille.push(c.to_ascii_lowercase());
+        } else if let Some(cell) = match c {
+            ' ' => Some(' '),
+            '.' => Some('4'),
+            ',' => Some('1'),
+            '?' => Some('8'),
+            '!' => Some('6'),
+            ':' => Some('3'),
+            ';' => Some('2'),
+            '-' => Some('-'),
+            '\'' => Some('\''),
+            _ => None,
+        } {
+            braille.push(cell);
+        }
+        in_number = false;
+    }
+    braille
+}
+
+/// Words wrapped to the line width, optionally centered
+fn text_lines(text: &str, centered: bool) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > LINE_CELLS {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    if centered {
+        for line in &mut lines {
+            let padding = LINE_CELLS.saturating_sub(line.len()) / 2;
+            line.insert_str(0, &" ".repeat(padding));
+        }
+    }
+    lines
+}
+
+/// Key and time signature sign: "%%#d4" for D major in 4/4
+/// More than three sharps or flats are counted with a number
+fn signature_sign(key: Option<&str>, time_signature: Option<&str>) -> MusicResult<String> {
+    let mut sign = String::new();
+    if let Some(key) = key.and_then(element_chord_symbol) {
+        let layout = get_key_signature_layout(&key, StaffClef::Treble)?;
+        let count = layout.accidentals.len();
+        let cell = match layout.accidentals.first().map(|accidental| accidental.accidental) {
+            Some(Accidental::Sharp) => "%",
+            _ => "<",
+        };
+        match count {
+            0 => {}
+            1..=3 => sign.push_str(&cell.repeat(count)),
+            _ => sign.push_str(&format!("{}{}", upper_number(&count.to_string()), cell)),
+        }
+    }
+    if let Some((upper, lower)) = time_signature.and_then(|time| time.split_once('/')) {
+        sign.push_str(&upper_number(upper.trim()));
+        sign.push_str(lower.trim());
+    }
+    Ok(sign)
+}
+
+/// Chord tones stacked upwards in close position from the root, around middle C for the clef
+fn chord_pitches(content: &str, clef: &Clef) -> MusicResult<Vec<Pitch>> {
+    let invalid = || MusicError::InvalidChord(content.to_string());
+    let symbol = element_chord_symbol(content).ok_or_else(invalid)?;
+    let mut octave = if matches!(clef, Clef::Bass) { 3 } else { 4 };
+
+    let mut pitches: Vec<Pitch> = Vec::new();
+    for name in chord_to_notes(&symbol)? {
+        let mut pitch = Pitch::parse(&name, octave).ok_or_else(invalid)?;
+        if pitches.last().is_some_and(|below| pitch.step() <= below.step()) {
+            octave += 1;
+            pitch.octave = octave;
+        }
+        pitches.push(pitch);
+    }
+    Ok(pitches)
+}
+
+/// Rest written as LilyPond ("r4", "r2."); anything else fills the beat with a quarter rest
+fn rest_event(content: &str) -> Event {
+    let duration = content.trim().trim_start_matches(['r', 'R', 's']);
+    let dots = duration.chars().filter(|c| *c == '.').count();
+    let value = Value::from_lilypond(duration.trim_end_matches('.')).unwrap_or(Value::Quarter);
+    Event::Rest(value, dots)
+}
+
+fn element_event(element: &EditableElement, clef: &Clef, show_answers: bool) -> MusicResult<Event> {
+    let hidden = element.is_answer && !show_answers;
+    let content = element.content.trim();
+    Ok(match element.element_type {
+        // Hidden answers are left as rests, as on the printed student copy
+        EditableElementType::Chord | EditableElementType::Note if hidden => Event::Rest(Value::Quarter, 0),
+        EditableElementType::Chord => Event::Chord(chord_pitches(content, clef)?),
+        EditableElementType::Note => {
+            let pitch = lilypond_pitch(content).and_then(|note| Pitch::parse(&note.note, note.octave));
+            Event::Note(pitch.ok_or_else(|| MusicError::ParseError(format!("Invalid note: {}", content)))?)
+        }
+        EditableElementType::Rest => rest_event(content),
+        _ => Event::Rest(Value::Quarter, 0),
+    })
+}
+
+/// Section elements as measures of events, with a quarter rest on every skipped beat
+fn section_measures(section: &WorksheetSection, show_answers: bool) -> MusicResult<Vec<Vec<Event>>> {
+    let mut elements: Vec<&EditableElement> = section.elements.iter().collect();
+    elements.sort_by_key(|element| (element.position.measure, element.position.beat));
+
+    let mut measures: Vec<Vec<Event>> = vec![Vec::new()];
+    let (mut measure, mut beat) = (1, 1);
+    for element in elements {
+        // Signature changes take no time
+        if matches!(element.element_type, EditableElementType::TimeSignature | EditableElementType::KeySignature) {
+            continue;
+        }
+        while measure < element.position.measure {
+            measures.push(Vec::new());
+            measure += 1;
+            beat = 1;
+        }
+        let events = measures.last_mut().expect("at least one measure");
+        while beat < element.position.beat {
+            events.push(Event::Rest(Value::Quarter, 0));
+            beat += 1;
+        }
+        events.push(element_event(element, &section.layout.clef, show_answers)?);
+        beat += 1;
+    }
+    Ok(measures)
+}
+
+/// Writes measures of one staff, tracking the octave and accidental context braille leaves implicit
+struct Transcriber {
+    /// Alteration of each letter in the key signature
+    key: HashMap<usize, i8>,
+    /// Chords are written from their top note in treble and from their bottom note in bass
+    from_top: bool,
+    previous: Option<Pitch>,
+    /// Accidentals written so far in the measure, by letter and octave
+    measure: HashMap<(usize, i8), i8>,
+}
+
+impl Transcriber {
+    fn new(key: Option<&str>, clef: &Clef) -> MusicResult<Self> {
+        let mut alterations = HashMap::new();
+        if let Some(key) = key.and_then(element_chord_symbol) {
+            for accidental in get_key_signature_layout(&key, StaffClef::Treble)?.accidentals {
+                if let Some(pitch) = Pitch::parse(&accidental.note, 4) {
+                    alterations.insert(pitch.letter, pitch.alter);
+                }
+            }
+        }
+        Ok(Self { key: alterations, from_top: !matches!(clef, Clef::Bass), previous: None, measure: HashMap::new() })
+    }
+
+    /// Accidental needed for a pitch against the key signature and earlier accidentals in the measure
+    fn accidental(&mut self, pitch: Pitch) -> String {
+        let current = self
+            .measure
+            .get(&(pitch.letter, pitch.octave))
+            .or_else(|| self.key.get(&pitch.letter))
+            .copied()
+            .unwrap_or(0);
+        if pitch.alter == current {
+            return String::new();
+        }
+        self.measure.insert((pitch.letter, pitch.octave), pitch.alter);
+        accidental_sign(pitch.alter)
+    }
+
+    fn note(&mut self, pitch: Pitch, value: Value) -> String {
+        let mut cells = self.accidental(pitch);
+        if needs_octave_mark(self.previous, pitch) {
+            cells.push_str(octave_mark(pitch.octave));
+        }
+        cells.push(NOTE_CELLS[value as usize][pitch.letter]);
+        self.previous = Some(pitch);
+        cells
+    }
+
+    /// Written note followed by an interval sign for each other tone, each with its accidental
+    fn chord(&mut self, pitches: &[Pitch]) -> String {
+        let mut ordered = pitches.to_vec();
+        ordered.sort_by_key(Pitch::step);
+        if self.from_top {
+            ordered.reverse();
+        }
+        let Some((&written, others)) = ordered.split_first() else {
+            return REST_CELLS[Value::Quarter as usize].to_string();
+        };
+
+        let mut cells = self.note(written, Value::Quarter);
+        for &tone in others {
+            cells.push_str(&self.accidental(tone));
+            let steps = (tone.step() - written.step()).unsigned_abs() as usize;
+            // Intervals wider than an octave carry the octave of their note
+            if steps > 7 {
+                cells.push_str(octave_mark(tone.octave));
+            }
+            cells.push(INTERVAL_SIGNS[steps % 7]);
+        }
+        cells
+    }
+
+    fn measure(&mut self, events: &[Event]) -> String {
+        self.measure.clear();
+        if events.is_empty() {
+            return REST_CELLS[Value::Whole as usize].to_string();
+        }
+        events
+            .iter()
+            .map(|event| match event {
+                Event::Note(pitch) => self.note(*pitch, Value::Quarter),
+                Event::Chord(pitches) => self.chord(pitches),
+                Event::Rest(value, dots) => {
+                    format!("{}{}", REST_CELLS[*value as usize], DOT.to_string().repeat(*dots))
+                }
+            })
+            .collect()
+    }
+
+    /// Measures separated by spaces and wrapped at measure boundaries, ending with a final bar line
+    /// The first note of every line restates its octave
+    fn lines(&mut self, measures: &[Vec<Event>]) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        for (index, events) in measures.iter().enumerate() {
+            let ending = if index + 1 == measures.len() { FINAL_BAR } else { "" };
+            let mut cells = self.measure(events) + ending;
+            if !line.is_empty() && line.len() + 1 + cells.len() > LINE_CELLS {
+                lines.push(std::mem::take(&mut line));
+                self.previous = None;
+                cells = self.measure(events) + ending;
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&cells);
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+        lines
+    }
+}
+
+fn section_lines(section: &WorksheetSection, show_answers: bool) -> MusicResult<Vec<String>> {
+    let mut lines = text_lines(&braille_text(&section.title), true);
+    if let Some(instructions) = &section.instructions {
+        lines.extend(text_lines(&braille_text(instructions), false));
+    }
+
+    let key = section.layout.key_signature.as_deref();
+    let signature = signature_sign(key, section.layout.time_signature.as_deref())?;
+    lines.extend(text_lines(&signature, true));
+
+    let mut transcriber = Transcriber::new(key, &section.layout.clef)?;
+    lines.extend(transcriber.lines(&section_measures(section, show_answers)?));
+    Ok(lines)
+}
+
+/// Transcribe a worksheet's notes, chords and signatures to a BRF file, paginated for embossing
+/// Answers are written only when the worksheet shows them
+pub fn worksheet_to_brf(config: &WorksheetConfig) -> MusicResult<String> {
+    let mut lines = text_lines(&braille_text(&config.title), true);
+    if let Some(subtitle) = &config.subtitle {
+        lines.extend(text_lines(&braille_text(subtitle), true));
+    }
+    for section in &config.sections {
+        lines.push(String::new());
+        lines.extend(section_lines(section, config.global_settings.show_answers)?);
+    }
+
+    let pages: Vec<String> = lines.chunks(PAGE_LINES).map(|page| page.join("\r\n") + "\r\n").collect();
+    Ok(pages.join("\x0c"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::worksheet::{AnswerBlank, ElementPosition, WorksheetSectionLayout};
+
+    fn element(element_type: EditableElementType, content: &str, measure: u32, beat: u32) -> EditableElement {
+        EditableElement {
+            id: format!("e-{}-{}", measure, beat),
+            element_type,
+            position: ElementPosition { measure, beat, voice: None },
+            content: content.to_string(),
+            is_answer: false,
+            is_interactive: true,
+            style: None,
+            hint: None,
+            keyboard: None,
+        }
+    }
+
+    fn section(elements: Vec<EditableElement>, clef: Clef, key: &str) -> WorksheetSection {
+        WorksheetSection {
+            id: "s".to_string(),
+            title: String::new(),
+            instructions: None,
+            elements,
+            layout: WorksheetSectionLayout {
+                measures_per_system: 4,
+                systems_per_page: 4,
+                clef,
+                time_signature: Some("4/4".to_string()),
+                key_signature: Some(key.to_string()),
+                answer_blank: AnswerBlank::default(),
+            },
+        }
+    }
+
+    /// Section lines without the centering
+    fn lines(section: &WorksheetSection, show_answers: bool) -> Vec<String> {
+        section_lines(section, show_answers).unwrap().iter().map(|line| line.trim().to_string()).collect()
+    }
+
+    #[test]
+    fn test_melody_octaves_and_accidentals() {
+        let notes = ["g'", "fis'", "f'", "d''"]
+            .iter()
+            .enumerate()
+            .map(|(beat, pitch)| element(EditableElementType::Note, pitch, 1, beat as u32 + 1))
+            .collect();
+        // G4, F# from the key, F natural, then a sixth up to D5 needs its octave
+        assert_eq!(lines(&section(notes, Clef::Treble, "g"), true), ["%#d4", "\"\\]*].:<k"]);
+    }
+
+    #[test]
+    fn test_chords_follow_the_clef() {
+        let chord = |clef| section(vec![element(EditableElementType::Chord, "C", 1, 1)], clef, "c");
+        // Treble: G4 with a third and fifth below; bass: C3 with a third and fifth above
+        assert_eq!(lines(&chord(Clef::Treble), true)[1], "\"\\+9<k");
+        assert_eq!(lines(&chord(Clef::Bass), true)[1], "_?+9<k");
+
+        // Bb major in Eb needs no accidentals; hidden answers are rests
+        let mut elements = vec![element(EditableElementType::Chord, "bes", 1, 1)];
+        elements.push(element(EditableElementType::Chord, "ees", 1, 3));
+        elements[1].is_answer = true;
+        assert_eq!(lines(&section(elements, Clef::Treble, "ees"), false), ["<<<#d4", ".]+9vv<k"]);
+    }
+
+    #[test]
+    fn test_lines_restate_the_octave() {
+        let notes = (1..=12).flat_map(|measure| {
+            (1..=4).map(move |beat| element(EditableElementType::Note, "c'", measure, beat))
+        });
+        let written = lines(&section(notes.collect(), Clef::Treble, "c"), true);
+        assert_eq!(written[0], "#d4");
+        assert_eq!(written[1], format!("\"????{}", " ????".repeat(7)));
+        assert!(written[2].starts_with("\"????"));
+        assert!(written[2].ends_with("????<k"));
+    }
+
+    #[test]
+    fn test_braille_text() {
+        assert_eq!(braille_text("Week 12a: Chords!"), ",week #ab;a3 ,chords6");
+        assert_eq!(signature_sign(Some("e"), Some("6/8")).unwrap(), "#d%#f8");
+    }
+}
@@ -0,0 +1,296 @@
+// Verbal descriptions
+// Screen-reader text for rendered notation, exported alongside the SVG for visually impaired students
+
+use serde::{Deserialize, Serialize};
+
+use crate::music::chords::parse_chord;
+use crate::music::identify::{notes_to_chord_candidates, ChordMatchKind};
+use crate::music::intervals::chord_to_notes;
+use crate::music::notes::note_index;
+use crate::music::types::AudioNote;
+use crate::types::worksheet::{EditableElement, EditableElementType, Enclosure};
+
+/// Spoken names of chord qualities by suffix; other suffixes are read as written
+const QUALITY_NAMES: &[(&str, &str)] = &[
+    ("", "major"),
+    ("m", "minor"),
+    ("dim", "diminished"),
+    ("aug", "augmented"),
+    ("sus2", "suspended second"),
+    ("sus4", "suspended fourth"),
+    ("sus", "suspended fourth"),
+    ("5", "power chord"),
+    ("6", "major sixth"),
+    ("m6", "minor sixth"),
+    ("7", "dominant seventh"),
+    ("maj7", "major seventh"),
+    ("M7", "major seventh"),
+    ("m7", "minor seventh"),
+    ("mM7", "minor major seventh"),
+    ("dim7", "diminished seventh"),
+    ("m7b5", "half-diminished seventh"),
+    ("aug7", "augmented seventh"),
+    ("7sus4", "dominant seventh suspended fourth"),
+    ("9", "dominant ninth"),
+    ("maj9", "major ninth"),
+    ("M9", "major ninth"),
+    ("m9", "minor ninth"),
+    ("add9", "added ninth"),
+];
+
+/// Position names by the chord tone in the bass (0 root, 1 third, ...)
+const INVERSIONS: [&str; 4] = ["root position", "first inversion", "second inversion", "third inversion"];
+
+/// Octave LilyPond writes unmarked pitches in ("c" = C3)
+const LILYPOND_BASE_OCTAVE: i32 = 3;
+
+/// Description of one rendered element, keyed by the id it carries in the SVG
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElementDescription {
+    pub element_id: String,
+    pub text: String,
+}
+
+/// Note name with its accidentals spoken: "Ab" → "A-flat", "F##" → "F-double-sharp"
+pub fn spoken_note(name: &str) -> String {
+    let mut chars = name.chars();
+    let Some(letter) = chars.next() else {
+        return String::new();
+    };
+    let accidentals = chars.as_str();
+    let word = match accidentals {
+        "" => return letter.to_string(),
+        "#" => "sharp",
+        "b" => "flat",
+        "##" => "double-sharp",
+        "bb" => "double-flat",
+        other => return format!("{}{}", letter, other),
+    };
+    format!("{}-{}", letter, word)
+}
+
+/// Pitch with its octave: "C4", or "A-flat 3" where a spoken accidental needs the octave set apart
+pub fn spoken_pitch(note: &AudioNote) -> String {
+    let name = spoken_note(&note.note);
+    if name.contains('-') {
+        format!("{} {}", name, note.octave)
+    } else {
+        format!("{}{}", name, note.octave)
+    }
+}
+
+fn quality_name(suffix: &str) -> String {
+    QUALITY_NAMES
+        .iter()
+        .find(|(known, _)| *known == suffix)
+        .map_or_else(|| suffix.to_string(), |(_, name)| name.to_string())
+}
+
+fn position_name(inversion: usize) -> String {
+    INVERSIONS.get(inversion).map_or_else(|| format!("inversion {}", inversion), |name| name.to_string())
+}
+
+/// Describe voiced pitches, lowest first: "F minor seventh, first inversion: A-flat 3, C4, E-flat 4, F4"
+/// Seventh chords are preferred to sixth chords on the same notes (Fm7/Ab rather than Ab6)
+/// Notes that don't form a known chord are only listed
+pub fn describe_chord(pitches: &[AudioNote]) -> String {
+    let mut sorted: Vec<&AudioNote> = pitches.iter().collect();
+    sorted.sort_by_key(|note| note.octave as i32 * 12 + note_index(&note.note).map_or(0, i32::from));
+    let listed = sorted.iter().map(|note| spoken_pitch(note)).collect::<Vec<_>>().join(", ");
+
+    match sorted.len() {
+        0 => "Rest".to_string(),
+        1 => listed,
+        _ => {
+            let names: Vec<String> = sorted.iter().map(|note| note.note.clone()).collect();
+            let candidates = notes_to_chord_candidates(&names);
+            let best = candidates
+                .into_iter()
+                .min_by_key(|chord| (chord.kind == ChordMatchKind::Slash, chord.quality.contains('6')));
+            match best {
+                Some(chord) => {
+                    let position = match chord.kind {
+                        ChordMatchKind::Slash => format!("over {}", spoken_note(&chord.bass)),
+                        _ => position_name(chord.inversion as usize),
+                    };
+                    let name = format!("{} {}", spoken_note(&chord.root), quality_name(&chord.quality));
+                    format!("{}, {}: {}", name, position, listed)
+                }
+                None => format!("Notes {}", listed),
+            }
+        }
+    }
+}
+
+/// Describe a chord symbol: "Fm7/Ab" → "F minor seventh, first inversion"
+pub fn describe_chord_symbol(symbol: &str) -> Option<String> {
+    let parsed = parse_chord(symbol).ok()?;
+    let name = format!("{} {}", spoken_note(&parsed.root), quality_name(&parsed.suffix));
+    let Some(bass) = parsed.bass else {
+        return Some(name);
+    };
+
+    let main = symbol.split('/').next().unwrap_or(symbol);
+    let bass_pitch = note_index(&bass).ok()?;
+    let tones = chord_to_notes(main).ok()?;
+    let position = match tones.iter().position(|tone| note_index(tone).ok() == Some(bass_pitch)) {
+        Some(inversion) => position_name(inversion),
+        None => format!("over {}", spoken_note(&bass)),
+    };
+    Some(format!("{}, {}", name, position))
+}
+
+/// Split a LilyPond note name off the front of a string: "fis''" → ("F#", "''")
+fn split_lilypond_note(text: &str) -> Option<(String, &str)> {
+    let letter = text.chars().next().filter(|c| ('a'..='g').contains(c))?;
+    let mut name = letter.to_ascii_uppercase().to_string();
+    let mut rest = &text[1..];
+    // "as" and "es" are the short forms of "aes" and "ees"
+    if matches!(letter, 'a' | 'e') && rest.starts_with('s') && !rest.starts_with("is") {
+        name.push('b');
+        rest = &rest[1..];
+    }
+    loop {
+        if let Some(after) = rest.strip_prefix("is") {
+            name.push('#');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("es") {
+            name.push('b');
+            rest = after;
+        } else {
+            return Some((name, rest));
+        }
+    }
+}
+
+/// Absolute LilyPond pitch as a note and octave: "ees'" → Eb4
+pub(crate) fn lilypond_pitch(pitch: &str) -> Option<AudioNote> {
+    let (note, marks) = split_lilypond_note(pitch)?;
+    let mut octave = LILYPOND_BASE_OCTAVE;
+    for mark in marks.chars() {
+        octave += match mark {
+            '\'' => 1,
+            ',' => -1,
+            _ => return None,
+        };
+    }
+    Some(AudioNote { note, octave: octave as i8, is_common_tone: false, cents: 0.0 })
+}
+
+/// Spoken absolute LilyPond pitch: "ees'" → "E-flat 4"
+fn describe_lilypond_pitch(pitch: &str) -> Option<String> {
+    lilypond_pitch(pitch).map(|note| spoken_pitch(&note))
+}
+
+/// Chord element content as a chord symbol; generated templates write the root as a LilyPond name ("eesmaj7")
+pub(crate) fn element_chord_symbol(content: &str) -> Option<String> {
+    if content.starts_with(|c: char| c.is_ascii_uppercase()) {
+        return Some(content.to_string());
+    }
+    split_lilypond_note(content).map(|(root, suffix)| format!("{}{}", root, suffix))
+}
+
+fn element_place(element: &EditableElement) -> String {
+    format!("Measure {}, beat {}", element.position.measure, element.position.beat)
+}
+
+/// Describe a worksheet element as it is printed, with its place in the score and any hint
+/// "Measure 2, beat 1: chord, F minor seventh, circled. Hint: count the half steps"
+pub fn describe_element(element: &EditableElement) -> String {
+    let content = element.content.trim();
+    let what = match element.element_type {
+        EditableElementType::Chord => {
+            let name = element_chord_symbol(content).and_then(|symbol| describe_chord_symbol(&symbol));
+            format!("chord, {}", name.as_deref().unwrap_or(content))
+        }
+        EditableElementType::Note => {
+            format!("note, {}", describe_lilypond_pitch(content).as_deref().unwrap_or(content))
+        }
+        EditableElementType::Rest => "rest".to_string(),
+        EditableElementType::Text => format!("text, {}", content),
+        EditableElementType::TimeSignature => format!("time signature {}", content),
+        EditableElementType::KeySignature => format!("key signature {}", content),
+    };
+
+    let mut text = format!("{}: {}", element_place(element), what);
+    match element.style.as_ref().and_then(|style| style.enclosure) {
+        Some(Enclosure::Circle) => text.push_str(", circled"),
+        Some(Enclosure::Box) => text.push_str(", boxed"),
+        None => {}
+    }
+    push_hint(&mut text, element);
+    text
+}
+
+/// Describe the blank left for an answer hidden on the student copy
+pub fn describe_answer_space(element: &EditableElement) -> String {
+    let kind = match element.element_type {
+        EditableElementType::Chord => "chord",
+        EditableElementType::Note => "note",
+        _ => "answer",
+    };
+    let mut text = format!("{}: blank for the {}", element_place(element), kind);
+    push_hint(&mut text, element);
+    text
+}
+
+fn push_hint(text: &mut String, element: &EditableElement) {
+    if let Some(hint) = element.hint.as_deref().map(str::trim).filter(|hint| !hint.is_empty()) {
+        text.push_str(&format!(". Hint: {}", hint));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::worksheet::{ElementPosition, ElementStyle};
+
+    fn note(name: &str, octave: i8) -> AudioNote {
+        AudioNote { note: name.to_string(), octave, is_common_tone: false, cents: 0.0 }
+    }
+
+    fn element(element_type: EditableElementType, content: &str) -> EditableElement {
+        EditableElement {
+            id: "e-0".to_string(),
+            element_type,
+            position: ElementPosition { measure: 2, beat: 1, voice: None },
+            content: content.to_string(),
+            is_answer: false,
+            is_interactive: true,
+            style: None,
+            hint: None,
+            keyboard: None,
+        }
+    }
+
+    #[test]
+    fn test_describe_chord() {
+        let pitches = [note("F", 4), note("Ab", 3), note("Eb", 4), note("C", 4)];
+        assert_eq!(describe_chord(&pitches), "F minor seventh, first inversion: A-flat 3, C4, E-flat 4, F4");
+        assert_eq!(describe_chord(&[note("C", 4), note("E", 4), note("G", 4)]), "C major, root position: C4, E4, G4");
+        assert_eq!(describe_chord(&[note("F#", 5)]), "F-sharp 5");
+        assert_eq!(describe_chord(&[]), "Rest");
+    }
+
+    #[test]
+    fn test_describe_chord_symbol() {
+        assert_eq!(describe_chord_symbol("Fm7/Ab").as_deref(), Some("F minor seventh, first inversion"));
+        assert_eq!(describe_chord_symbol("C/D").as_deref(), Some("C major, over D"));
+        assert_eq!(describe_chord_symbol("Bbdim7").as_deref(), Some("B-flat diminished seventh"));
+    }
+
+    #[test]
+    fn test_describe_element() {
+        let mut chord = element(EditableElementType::Chord, "eesmaj7");
+        chord.style = Some(ElementStyle { color: None, enclosure: Some(Enclosure::Circle) });
+        chord.hint = Some("Start from the root".to_string());
+        assert_eq!(
+            describe_element(&chord),
+            "Measure 2, beat 1: chord, E-flat major seventh, circled. Hint: Start from the root"
+        );
+        assert_eq!(describe_answer_space(&chord), "Measure 2, beat 1: blank for the chord. Hint: Start from the root");
+
+        assert_eq!(describe_element(&element(EditableElementType::Note, "fis,")), "Measure 2, beat 1: note, F-sharp 2");
+        assert_eq!(describe_element(&element(EditableElementType::Note, "as''")), "Measure 2, beat 1: note, A-flat 5");
+    }
+}
@@ -0,0 +1,184 @@
+// Key signature layout
+// Ordered accidentals with their staff positions, for drawing key signatures on any clef
+
+use serde::{Deserialize, Serialize};
+
+use crate::music::types::{Accidental, MusicError, MusicResult};
+
+/// Order sharps are written in a key signature (flats are the reverse)
+const SHARP_ORDER: [&str; 7] = ["F", "C", "G", "D", "A", "E", "B"];
+
+/// Major keys by accidental count (index = number of sharps / flats)
+const SHARP_MAJOR_KEYS: [&str; 8] = ["C", "G", "D", "A", "E", "B", "F#", "C#"];
+const FLAT_MAJOR_KEYS: [&str; 8] = ["C", "F", "Bb", "Eb", "Ab", "Db", "Gb", "Cb"];
+const SHARP_MINOR_KEYS: [&str; 8] = ["A", "E", "B", "F#", "C#", "G#", "D#", "A#"];
+const FLAT_MINOR_KEYS: [&str; 8] = ["A", "D", "G", "C", "F", "Bb", "Eb", "Ab"];
+
+/// Staff positions in steps above the bottom line (0 = bottom line, 1 = first space, 8 = top line)
+/// Listed in key signature order
+const TREBLE_SHARPS: [i8; 7] = [8, 5, 9, 6, 3, 7, 4];
+const TREBLE_FLATS: [i8; 7] = [4, 7, 3, 6, 2, 5, 1];
+/// Tenor clef sharps avoid the ledger lines the treble pattern would need
+const TENOR_SHARPS: [i8; 7] = [1, 5, 2, 6, 3, 7, 4];
+
+/// Staff clef for layout purposes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StaffClef {
+    Treble,
+    Bass,
+    Alto,
+    Tenor,
+}
+
+/// One accidental in a key signature
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeySignatureAccidental {
+    /// Altered note ("F#", "Bb", ...)
+    pub note: String,
+    pub accidental: Accidental,
+    /// Steps above the bottom staff line (negative values sit below the staff)
+    pub staff_position: i8,
+}
+
+/// Key signature ready for drawing, accidentals in written order
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeySignatureLayout {
+    pub key: String,
+    pub clef: StaffClef,
+    pub accidentals: Vec<KeySignatureAccidental>,
+}
+
+/// Accidental type and count for a key ("D" = 2 sharps, "Gm" = 2 flats)
+fn key_accidentals(key: &str) -> MusicResult<(Accidental, usize)> {
+    let (tonic, minor) = match key.strip_suffix('m') {
+        Some(tonic) => (tonic, true),
+        None => (key, false),
+    };
+    let (sharp_keys, flat_keys) = if minor {
+        (SHARP_MINOR_KEYS, FLAT_MINOR_KEYS)
+    } else {
+        (SHARP_MAJOR_KEYS, FLAT_MAJOR_KEYS)
+    };
+
+    if let Some(count) = sharp_keys.iter().position(|k| *k == tonic) {
+        return Ok((Accidental::Sharp, count));
+    }
+    flat_keys
+        .iter()
+        .position(|k| *k == tonic)
+        .map(|count| (Accidental::Flat, count))
+        .ok_or_else(|| MusicError::InvalidKey(key.to_string()))
+}
+
+/// Tonic of a key from its signature as a count of fifths (2 = "D" / "Bm", -3 = "Eb" / "Cm")
+/// None beyond seven sharps or flats
+pub fn key_for_fifths(fifths: i32, minor: bool) -> Option<&'static str> {
+    let (sharp_keys, flat_keys) = if minor {
+        (SHARP_MINOR_KEYS, FLAT_MINOR_KEYS)
+    } else {
+        (SHARP_MAJOR_KEYS, FLAT_MAJOR_KEYS)
+    };
+    let keys = if fifths < 0 { flat_keys } else { sharp_keys };
+    keys.get(fifths.unsigned_abs() as usize).copied()
+}
+
+/// Staff positions for each accidental of a signature, in written order
+fn staff_positions(clef: StaffClef, accidental: Accidental) -> [i8; 7] {
+    let sharp = accidental == Accidental::Sharp;
+    let base = if sharp { TREBLE_SHARPS } else { TREBLE_FLATS };
+    // Other clefs reuse the treble shape moved up or down the staff
+    let offset = match clef {
+        StaffClef::Treble => 0,
+        StaffClef::Bass => -2,
+        StaffClef::Alto => -1,
+        StaffClef::Tenor if sharp => return TENOR_SHARPS,
+        StaffClef::Tenor => 1,
+    };
+    base.map(|position| position + offset)
+}
+
+/// Get the ordered accidentals of a key signature with their positions on the given clef
+/// Keys are tonic names, with an "m" suffix for minor keys ("Eb", "F#m")
+pub fn get_key_signature_layout(key: &str, clef: StaffClef) -> MusicResult<KeySignatureLayout> {
+    let (accidental, count) = key_accidentals(key.trim())?;
+    let positions = staff_positions(clef, accidental);
+
+    let accidentals = (0..count)
+        .map(|i| {
+            let (letter, symbol) = match accidental {
+                Accidental::Sharp => (SHARP_ORDER[i], "#"),
+                _ => (SHARP_ORDER[6 - i], "b"),
+            };
+            KeySignatureAccidental {
+                note: format!("{}{}", letter, symbol),
+                accidental,
+                staff_position: positions[i],
+            }
+        })
+        .collect();
+
+    Ok(KeySignatureLayout { key: key.trim().to_string(), clef, accidentals })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notes(layout: &KeySignatureLayout) -> Vec<&str> {
+        layout.accidentals.iter().map(|a| a.note.as_str()).collect()
+    }
+
+    fn positions(layout: &KeySignatureLayout) -> Vec<i8> {
+        layout.accidentals.iter().map(|a| a.staff_position).collect()
+    }
+
+    #[test]
+    fn test_sharp_keys_treble() {
+        let layout = get_key_signature_layout("E", StaffClef::Treble).unwrap();
+        assert_eq!(notes(&layout), vec!["F#", "C#", "G#", "D#"]);
+        assert_eq!(positions(&layout), vec![8, 5, 9, 6]);
+        assert!(layout.accidentals.iter().all(|a| a.accidental == Accidental::Sharp));
+    }
+
+    #[test]
+    fn test_flat_keys_bass() {
+        let layout = get_key_signature_layout("Ab", StaffClef::Bass).unwrap();
+        assert_eq!(notes(&layout), vec!["Bb", "Eb", "Ab", "Db"]);
+        assert_eq!(positions(&layout), vec![2, 5, 1, 4]);
+    }
+
+    #[test]
+    fn test_minor_keys_use_relative_major() {
+        assert_eq!(
+            get_key_signature_layout("Gm", StaffClef::Treble).unwrap().accidentals,
+            get_key_signature_layout("Bb", StaffClef::Treble).unwrap().accidentals
+        );
+        assert!(get_key_signature_layout("Am", StaffClef::Alto).unwrap().accidentals.is_empty());
+    }
+
+    #[test]
+    fn test_c_clefs() {
+        let alto = get_key_signature_layout("D", StaffClef::Alto).unwrap();
+        assert_eq!(positions(&alto), vec![7, 4]);
+
+        let tenor = get_key_signature_layout("C#", StaffClef::Tenor).unwrap();
+        assert_eq!(positions(&tenor), vec![1, 5, 2, 6, 3, 7, 4]);
+        let tenor_flats = get_key_signature_layout("F", StaffClef::Tenor).unwrap();
+        assert_eq!(positions(&tenor_flats), vec![5]);
+    }
+
+    #[test]
+    fn test_key_for_fifths() {
+        assert_eq!(key_for_fifths(0, false), Some("C"));
+        assert_eq!(key_for_fifths(2, false), Some("D"));
+        assert_eq!(key_for_fifths(-3, false), Some("Eb"));
+        assert_eq!(key_for_fifths(-3, true), Some("C"));
+        assert_eq!(key_for_fifths(8, false), None);
+    }
+
+    #[test]
+    fn test_unknown_key() {
+        assert!(get_key_signature_layout("H", StaffClef::Treble).is_err());
+    }
+}
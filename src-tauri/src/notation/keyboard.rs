@@ -0,0 +1,198 @@
+// Keyboard diagrams
+// A small piano keyboard with a chord's notes highlighted, drawn as standalone SVG or as LilyPond markup
+// beneath a worksheet element; both share one key layout
+
+use crate::music::chords::parse_chord;
+use crate::music::intervals::chord_to_notes;
+use crate::music::types::MusicResult;
+use crate::music::voice_leading::{note_to_midi, voice_progression};
+
+/// Key sizes in white key widths
+const WHITE_HEIGHT: f32 = 3.75;
+const BLACK_WIDTH: f32 = 0.625;
+const BLACK_HEIGHT: f32 = 2.375;
+/// Outline left around each white key in markup
+const BORDER: f32 = 0.075;
+/// Staff spaces per white key width in markup, and pixels in SVG
+const MARKUP_SCALE: f32 = 0.8;
+const SVG_SCALE: f32 = 24.0;
+/// Highlighted keys are drawn in this color
+const HIGHLIGHT: [u8; 3] = [0xc0, 0x39, 0x2b];
+/// Outlines and black keys in SVG, recolored by themes
+const INK: &str = "#000000";
+
+/// White keys to the left of each pitch class within its octave, and whether the key is black
+const KEY_POSITIONS: [(u8, bool); 12] = [
+    (0, false),
+    (1, true),
+    (1, false),
+    (2, true),
+    (2, false),
+    (3, false),
+    (3, true),
+    (4, false),
+    (4, true),
+    (5, false),
+    (5, true),
+    (6, false),
+];
+
+/// One key of a diagram
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Key {
+    midi: u8,
+    /// Left edge in white key widths from the diagram's left edge
+    x: f32,
+    black: bool,
+    highlighted: bool,
+}
+
+impl Key {
+    fn size(&self) -> (f32, f32) {
+        if self.black { (BLACK_WIDTH, BLACK_HEIGHT) } else { (1.0, WHITE_HEIGHT) }
+    }
+}
+
+/// Keys spanning whole octaves from the C at or below the lowest note to the B at or above the highest,
+/// white keys first so black keys are drawn over them, and the width in white keys
+fn layout(notes: &[u8]) -> Option<(Vec<Key>, f32)> {
+    let (&low, &high) = (notes.iter().min()?, notes.iter().max()?);
+    let first = low - low % 12;
+    let last = (high - high % 12).saturating_add(11).min(127);
+
+    let key = |midi: u8| {
+        let (whites, black) = KEY_POSITIONS[(midi % 12) as usize];
+        let left = (midi / 12 - first / 12) as f32 * 7.0 + whites as f32;
+        let x = if black { left - BLACK_WIDTH / 2.0 } else { left };
+        Key { midi, x, black, highlighted: notes.contains(&midi) }
+    };
+    let mut keys: Vec<Key> = (first..=last).map(key).collect();
+    keys.sort_by_key(|key| key.black);
+    let width = keys.iter().filter(|key| !key.black).count() as f32;
+    Some((keys, width))
+}
+
+/// MIDI notes of a chord as the voicing engine voices it on its own, from `base_octave`
+/// `voicing_style` is a frontend style name ("close", "wide", "common-tone", "lead")
+pub fn voiced_chord_midi(chord: &str, voicing_style: &str, base_octave: i8) -> MusicResult<Vec<u8>> {
+    let main = chord.split('/').next().unwrap_or(chord);
+    let mut notes = chord_to_notes(main)?;
+    // The bass is voiced first, so a slash chord's bass leads the notes
+    if let Some(bass) = parse_chord(chord)?.bass {
+        notes.retain(|note| *note != bass);
+        notes.insert(0, bass);
+    }
+    let voiced = voice_progression(&[notes], base_octave, voicing_style)?;
+    voiced.concat().iter().map(|note| note_to_midi(&note.note, note.octave)).collect()
+}
+
+fn filled_box(x: f32, width: f32, bottom: f32, height: f32, color: Option<[u8; 3]>) -> String {
+    let shape = format!(
+        "\\translate #'({:.2} . {:.2}) \\filled-box #'(0 . {:.2}) #'(0 . {:.2}) #0",
+        x * MARKUP_SCALE,
+        bottom * MARKUP_SCALE,
+        width * MARKUP_SCALE,
+        height * MARKUP_SCALE
+    );
+    match color {
+        Some([r, g, b]) => {
+            let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+            format!("\\with-color #(rgb-color {:.3} {:.3} {:.3}) {}", r, g, b, shape)
+        }
+        None => shape,
+    }
+}
+
+/// Markup for a keyboard with the given MIDI notes highlighted; empty when there are no notes
+pub fn keyboard_markup(notes: &[u8]) -> String {
+    let Some((keys, _)) = layout(notes) else {
+        return String::new();
+    };
+
+    // White keys are outlined by drawing each over a black box
+    let mut shapes = Vec::new();
+    for key in keys {
+        let color = key.highlighted.then_some(HIGHLIGHT);
+        if key.black {
+            shapes.push(filled_box(key.x, BLACK_WIDTH, WHITE_HEIGHT - BLACK_HEIGHT, BLACK_HEIGHT, color));
+        } else {
+            let (width, height) = (1.0 - 2.0 * BORDER, WHITE_HEIGHT - 2.0 * BORDER);
+            shapes.push(filled_box(key.x, 1.0, 0.0, WHITE_HEIGHT, None));
+            shapes.push(filled_box(key.x + BORDER, width, BORDER, height, Some(color.unwrap_or([0xff; 3]))));
+        }
+    }
+    format!("_\\markup {{ \\overlay {{ {} }} }}", shapes.join(" "))
+}
+
+/// Standalone SVG of a keyboard with the given MIDI notes highlighted; None when there are no notes
+/// Each key carries its MIDI note in a data-midi attribute, and ink is black so themes can recolor it
+pub fn keyboard_svg(notes: &[u8]) -> Option<String> {
+    let (keys, width) = layout(notes)?;
+    let (width, height) = (width * SVG_SCALE + 1.0, WHITE_HEIGHT * SVG_SCALE + 1.0);
+    let highlight = format!("#{:02x}{:02x}{:02x}", HIGHLIGHT[0], HIGHLIGHT[1], HIGHLIGHT[2]);
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    );
+    for key in keys {
+        let (key_width, key_height) = key.size();
+        let fill = match (key.highlighted, key.black) {
+            (true, _) => highlight.as_str(),
+            (false, true) => INK,
+            (false, false) => "#ffffff",
+        };
+        svg.push_str(&format!(
+            r##"<rect class="key" data-midi="{}" x="{}" y="0.5" width="{}" height="{}" fill="{}" stroke="{}"/>"##,
+            key.midi,
+            key.x * SVG_SCALE + 0.5,
+            key_width * SVG_SCALE,
+            key_height * SVG_SCALE,
+            fill,
+            INK
+        ));
+    }
+    svg.push_str("</svg>");
+    Some(svg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyboard_markup() {
+        assert_eq!(keyboard_markup(&[]), "");
+
+        // C major in the middle octave: one octave, three highlighted white keys
+        let markup = keyboard_markup(&[60, 64, 67]);
+        assert_eq!(markup.matches("\\filled-box").count(), 7 * 2 + 5);
+        assert_eq!(markup.matches("rgb-color 0.753").count(), 3);
+        assert!(markup.contains("\\translate #'(2.46 . 0.06)"));
+
+        // Reaching into the next octave adds one; black keys sit across the line between white keys
+        let markup = keyboard_markup(&[62, 66, 69, 72]);
+        assert_eq!(markup.matches("\\filled-box").count(), 14 * 2 + 10);
+        assert!(markup.contains("#(rgb-color 0.753 0.224 0.169) \\translate #'(2.15 . 1.10)"));
+    }
+
+    #[test]
+    fn test_keyboard_svg() {
+        assert_eq!(keyboard_svg(&[]), None);
+        let svg = keyboard_svg(&[62, 66, 69]).unwrap();
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="169" height="91""#));
+        assert_eq!(svg.matches("class=\"key\"").count(), 12);
+        assert!(svg.contains(r##"data-midi="66" x="65" y="0.5" width="15" height="57" fill="#c0392b""##));
+        assert!(svg.contains(r##"data-midi="61" x="17" y="0.5" width="15" height="57" fill="#000000""##));
+    }
+
+    #[test]
+    fn test_voiced_chord_midi() {
+        assert_eq!(voiced_chord_midi("Cmaj7", "close", 4).unwrap(), vec![60, 64, 67, 71]);
+        // A slash chord's bass is voiced at the bottom
+        let voiced = voiced_chord_midi("C/E", "close", 4).unwrap();
+        assert_eq!(voiced.iter().min().map(|midi| midi % 12), Some(4));
+        assert!(voiced_chord_midi("H7", "close", 4).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+// Lead sheets
+// Writes a song as LilyPond: slash rhythm with chord symbols, one block per section
+// with its rehearsal mark, name, key and tempo, and tempo and meter changes where they fall
+// Sections can print their harmonic analysis beneath the staff
+
+use super::analysis_line::{analysis_line, BRACKET_ENGRAVER};
+use super::beaming::parse_time_signature;
+use super::voicing::{key_command, quoted};
+use crate::music::analysis::analyze_progression;
+use crate::music::song::tempo_map;
+use crate::music::tempo::TempoSegment;
+use crate::music::types::MusicResult;
+use crate::types::song::Song;
+
+/// Staff position the rhythm slashes are written at (treble middle line)
+const SLASH_PITCH: &str = "b'";
+
+/// Slashes for a chord lasting `beats`, one per beat plus a half-beat slash for any remainder
+/// Lengths are rounded to the nearest half beat
+fn slashes(beats: f32, unit: u32) -> Vec<String> {
+    let halves = (beats * 2.0).round().max(1.0) as u32;
+    let mut slashes = vec![format!("{}{}", SLASH_PITCH, unit); (halves / 2) as usize];
+    if halves % 2 == 1 {
+        slashes.push(format!("{}{}", SLASH_PITCH, unit * 2));
+    }
+    slashes
+}
+
+/// Allowance when comparing beat positions summed from chord lengths
+const BEAT_EPSILON: f64 = 1e-6;
+
+/// \\time and \\tempo marks for a segment, for whatever differs from the meter and tempo in effect
+fn segment_marks(segment: &TempoSegment, meter: &mut (u32, u32), tempo: &mut Option<(u32, f32)>) -> Vec<String> {
+    let mut marks = Vec::new();
+    if (segment.beats_per_bar, segment.beat_unit) != *meter {
+        *meter = (segment.beats_per_bar, segment.beat_unit);
+        marks.push(format!("\\time {}/{}", meter.0, meter.1));
+    }
+    if *tempo != Some((segment.beat_unit, segment.bpm)) {
+        *tempo = Some((segment.beat_unit, segment.bpm));
+        marks.push(format!("\\tempo {} = {}", segment.beat_unit, segment.bpm.round()));
+    }
+    marks
+}
+
+/// LilyPond lead sheet for a song
+pub fn song_to_lilypond(song: &Song) -> MusicResult<String> {
+    let (beats, unit) = parse_time_signature(&song.time_signature)?;
+    let segments = if song.sections.is_empty() { Vec::new() } else { tempo_map(song)?.segments().to_vec() };
+    let mut segments = segments.iter().peekable();
+    let mut meter = (beats, unit);
+    let mut tempo: Option<(u32, f32)> = None;
+    let mut beat = 0.0;
+    let mut music = Vec::new();
+
+    for (index, section) in song.sections.iter().enumerate() {
+        if index > 0 {
+            music.push("\\bar \"||\"".to_string());
+        }
+        if let Some(mark) = section.rehearsal_mark.as_deref().filter(|mark| !mark.is_empty()) {
+            music.push(format!("\\mark \\markup {{ \\box {} }}", quoted(mark)));
+        }
+        music.push(format!("\\sectionLabel {}", quoted(section.display_name())));
+        music.push(key_command(&section.key));
+        let analysis = if section.show_analysis {
+            let chords: Vec<String> = section.chords.iter().map(|chord| chord.chord.clone()).collect();
+            analysis_line(&analyze_progression(&chords, &section.key))
+        } else {
+            Vec::new()
+        };
+
+        for (chord_index, chord) in section.chords.iter().enumerate() {
+            // A chord crossing a tempo or meter change is written in two parts around the marks
+            let mut remaining = chord.beats as f64;
+            let mut first_part = true;
+            while remaining > BEAT_EPSILON {
+                while let Some(segment) = segments.next_if(|segment| segment.beat <= beat + BEAT_EPSILON) {
+                    music.extend(segment_marks(segment, &mut meter, &mut tempo));
+                }
+                let part = remaining.min(segments.peek().map_or(f64::INFINITY, |next| next.beat - beat));
+                let mut part_slashes = slashes(part as f32, meter.1);
+                if first_part {
+                    part_slashes[0].push_str(&format!("^\\markup {{ {} }}", quoted(&chord.chord)));
+                    if let Some(events) = analysis.get(chord_index) {
+                        part_slashes[0].push_str(events);
+                    }
+                    for marker in section.markers.iter().filter(|marker| marker.chord_index == chord_index) {
+                        part_slashes[0].push_str(&format!("_\\markup {{ \\italic {} }}", quoted(&marker.label)));
+                    }
+                    first_part = false;
+                }
+                music.extend(part_slashes);
+                beat += part;
+                remaining -= part;
+            }
+        }
+    }
+
+    Ok(format!(
+        r#"\version "2.24.0"
+
+\paper {{
+  indent = 0\mm
+}}
+
+\header {{
+  title = {}
+  tagline = ##f
+}}
+
+\score {{
+  \new Staff {{
+    \improvisationOn
+    \time {}/{}
+    {} \bar "|."
+  }}
+  \layout {{ {} }}
+}}
+"#,
+        quoted(&song.title),
+        beats,
+        unit,
+        music.join(" "),
+        if song.sections.iter().any(|section| section.show_analysis) { BRACKET_ENGRAVER } else { "" }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::song::{MeasureChange, SectionKind, SongChord, SongMarker, SongSection};
+
+    fn section(kind: SectionKind, mark: Option<&str>, tempo: f32, chords: &[(&str, f32)]) -> SongSection {
+        SongSection {
+            id: format!("{:?}", kind),
+            kind,
+            label: None,
+            key: "G".to_string(),
+            tempo,
+            rehearsal_mark: mark.map(str::to_string),
+            chords: chords.iter().map(|(c, b)| SongChord { chord: c.to_string(), beats: *b }).collect(),
+            markers: Vec::new(),
+            changes: Vec::new(),
+            show_analysis: false,
+        }
+    }
+
+    #[test]
+    fn test_slashes_per_beat() {
+        assert_eq!(slashes(2.0, 4), vec!["b'4", "b'4"]);
+        assert_eq!(slashes(1.5, 4), vec!["b'4", "b'8"]);
+        assert_eq!(slashes(3.0, 8).len(), 3);
+    }
+
+    #[test]
+    fn test_sections_marks_and_tempo_changes() {
+        let mut verse = section(SectionKind::Verse, Some("A"), 96.0, &[("G", 4.0), ("Em", 4.0)]);
+        verse.markers.push(SongMarker { chord_index: 1, label: "Break".to_string() });
+        let mut chorus = section(SectionKind::Chorus, Some("B"), 96.0, &[("C", 2.0), ("D", 2.0)]);
+        chorus.label = Some("Last Chorus".to_string());
+        let outro = section(SectionKind::Outro, None, 80.0, &[("G", 4.0)]);
+        let song = Song {
+            title: "Tune".to_string(),
+            time_signature: "4/4".to_string(),
+            sections: vec![verse, chorus, outro],
+        };
+
+        let document = song_to_lilypond(&song).unwrap();
+        assert!(document
+            .contains("\\mark \\markup { \\box \"A\" } \\sectionLabel \"Verse\" \\key g \\major \\tempo 4 = 96"));
+        assert!(document.contains("b'4^\\markup { \"Em\" }_\\markup { \\italic \"Break\" }"));
+        assert!(document.contains(
+            "\\bar \"||\" \\mark \\markup { \\box \"B\" } \\sectionLabel \"Last Chorus\" \\key g \\major b'4"
+        ));
+        assert!(document.contains("\\sectionLabel \"Outro\" \\key g \\major \\tempo 4 = 80"));
+        assert_eq!(document.matches("\\tempo").count(), 2);
+    }
+
+    #[test]
+    fn test_measure_changes_split_chords() {
+        let mut verse = section(SectionKind::Verse, None, 120.0, &[("C", 4.0), ("F", 6.0), ("G", 3.0)]);
+        verse.changes.push(MeasureChange { measure: 3, tempo: Some(90.0), time_signature: Some("3/4".to_string()) });
+        let song = Song { title: "Waltz".to_string(), time_signature: "4/4".to_string(), sections: vec![verse] };
+
+        // F starts in bar 2 and carries over the change into the first 3/4 bar
+        let document = song_to_lilypond(&song).unwrap();
+        assert!(document.contains("b'4^\\markup { \"F\" } b'4 b'4 b'4 \\time 3/4 \\tempo 4 = 90 b'4 b'4"));
+        assert!(document.contains("\\tempo 4 = 90 b'4 b'4 b'4^\\markup { \"G\" }"));
+    }
+
+    #[test]
+    fn test_analysis_line_per_section() {
+        let mut verse = section(SectionKind::Verse, None, 96.0, &[("D7", 2.0), ("G", 2.0)]);
+        verse.show_analysis = true;
+        let chorus = section(SectionKind::Chorus, None, 96.0, &[("C", 4.0)]);
+        let song = Song { title: "Tune".to_string(), time_signature: "4/4".to_string(), sections: vec![verse, chorus] };
+
+        let document = song_to_lilypond(&song).unwrap();
+        assert!(document.contains(
+            "b'4^\\markup { \"D7\" }_\\markup { \\center-column { \\concat { \"V\" \\super \"7\" } \\small \"D\" } }"
+        ));
+        assert!(document.contains("\\tweak HorizontalBracketText.text \"AC\" \\startGroup b'4"));
+        let tonic = "_\\markup { \\center-column { \"I\" \\small \"T\" } }";
+        assert!(document.contains(&format!("b'4^\\markup {{ \"G\" }}{}\\stopGroup", tonic)));
+        // Sections without the analysis line print chord symbols only
+        assert!(document.contains("b'4^\\markup { \"C\" } b'4"));
+        assert!(document.contains(BRACKET_ENGRAVER));
+    }
+}
@@ -0,0 +1,11 @@
+pub mod analysis_line;
+pub mod beaming;
+pub mod braille;
+pub mod description;
+pub mod key_signature;
+pub mod keyboard;
+pub mod lead_sheet;
+pub mod musicxml;
+pub mod native;
+pub mod staff_range;
+pub mod voicing;
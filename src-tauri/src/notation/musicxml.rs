@@ -0,0 +1,601 @@
+// MusicXML import
+// Reads an uncompressed partwise MusicXML score into an editable worksheet, one section per part
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use super::key_signature::key_for_fifths;
+use super::staff_range::{fit_to_range, ottava_mark};
+use super::voicing::{lilypond_note_name, lilypond_pitch};
+use crate::music::chords::{ChordSymbol, SymbolStyle};
+use crate::music::identify::name_midi_chord;
+use crate::music::types::{AudioNote, MusicError, MusicResult};
+use crate::types::worksheet::*;
+
+/// Letter names with their pitch classes
+const STEPS: [(&str, i32); 7] = [("C", 0), ("D", 2), ("E", 4), ("F", 5), ("G", 7), ("A", 9), ("B", 11)];
+
+/// MusicXML harmony kinds and the chord suffixes written for them
+const HARMONY_KINDS: [(&str, &str); 25] = [
+    ("major", ""),
+    ("minor", "m"),
+    ("augmented", "aug"),
+    ("diminished", "dim"),
+    ("dominant", "7"),
+    ("major-seventh", "maj7"),
+    ("minor-seventh", "m7"),
+    ("diminished-seventh", "dim7"),
+    ("augmented-seventh", "aug7"),
+    ("half-diminished", "m7b5"),
+    ("major-minor", "mM7"),
+    ("major-sixth", "6"),
+    ("minor-sixth", "m6"),
+    ("dominant-ninth", "9"),
+    ("major-ninth", "maj9"),
+    ("minor-ninth", "m9"),
+    ("dominant-11th", "11"),
+    ("major-11th", "maj11"),
+    ("minor-11th", "m11"),
+    ("dominant-13th", "13"),
+    ("major-13th", "maj13"),
+    ("minor-13th", "m13"),
+    ("suspended-second", "sus2"),
+    ("suspended-fourth", "sus4"),
+    ("power", "5"),
+];
+
+/// Element of the parsed document
+/// MusicXML keeps its data in element text, so nothing else besides attributes is needed
+#[derive(Debug, Default)]
+struct Node {
+    name: String,
+    attributes: Vec<(String, String)>,
+    text: String,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn child(&self, name: &str) -> Option<&Node> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Node> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.child(name).is_some()
+    }
+
+    /// Trimmed text of a child element
+    fn text_of(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|child| child.text.trim()).filter(|text| !text.is_empty())
+    }
+
+    fn number_of(&self, name: &str) -> Option<f64> {
+        self.text_of(name).and_then(|text| text.parse().ok())
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+fn xml_error(error: impl std::fmt::Display) -> MusicError {
+    MusicError::ParseError(format!("Invalid MusicXML: {}", error))
+}
+
+fn start_node(start: &BytesStart) -> MusicResult<Node> {
+    let mut attributes = Vec::new();
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(xml_error)?;
+        let key = String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned();
+        attributes.push((key, attribute.unescape_value().map_err(xml_error)?.into_owned()));
+    }
+    let name = String::from_utf8_lossy(start.local_name().as_ref()).into_owned();
+    Ok(Node { name, attributes, ..Node::default() })
+}
+
+/// Parse a document into its root element
+fn parse_document(xml: &str) -> MusicResult<Node> {
+    let mut reader = Reader::from_str(xml);
+    let mut stack = vec![Node::default()];
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(start) => stack.push(start_node(&start)?),
+            Event::Empty(start) => {
+                let node = start_node(&start)?;
+                stack.last_mut().expect("document node").children.push(node);
+            }
+            Event::End(_) => {
+                let node = stack.pop().filter(|_| !stack.is_empty()).ok_or_else(|| xml_error("unbalanced tags"))?;
+                stack.last_mut().expect("document node").children.push(node);
+            }
+            Event::Text(text) => {
+                let text = text.unescape().map_err(xml_error)?;
+                stack.last_mut().expect("document node").text.push_str(&text);
+            }
+            Event::CData(data) => {
+                stack.last_mut().expect("document node").text.push_str(&String::from_utf8_lossy(&data));
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    let document = stack.pop().filter(|_| stack.is_empty()).ok_or_else(|| xml_error("unclosed tags"))?;
+    document.children.into_iter().next().ok_or_else(|| xml_error("empty document"))
+}
+
+/// Accidental signs for an alteration in semitones
+fn accidental(alter: i32) -> String {
+    if alter < 0 {
+        "b".repeat(alter.unsigned_abs() as usize)
+    } else {
+        "#".repeat(alter as usize)
+    }
+}
+
+/// Note name and pitch class for a step and alteration ("B", -1 → "Bb", 10)
+fn spelled_note(step: &str, alter: f64) -> Option<(String, i32)> {
+    let (letter, pitch_class) = STEPS.iter().find(|(letter, _)| letter.eq_ignore_ascii_case(step))?;
+    // Microtonal alterations are rounded to the nearest semitone
+    let alter = alter.round() as i32;
+    Some((format!("{}{}", letter, accidental(alter)), pitch_class + alter))
+}
+
+/// Written pitch of a note element with its MIDI number
+fn note_pitch(pitch: &Node) -> Option<(AudioNote, u8)> {
+    let (note, pitch_class) = spelled_note(pitch.text_of("step")?, pitch.number_of("alter").unwrap_or(0.0))?;
+    let octave: i8 = pitch.text_of("octave")?.parse().ok()?;
+    let midi = u8::try_from((octave as i32 + 1) * 12 + pitch_class).ok().filter(|midi| *midi <= 127)?;
+    Some((AudioNote { note, octave, is_common_tone: false, cents: 0.0 }, midi))
+}
+
+/// Chord symbol of a harmony element ("Bb", "minor-seventh", bass "F" → "Bbm7/F")
+/// None for "no chord" and for harmonies written as functions instead of roots
+fn harmony_symbol(harmony: &Node) -> Option<String> {
+    let root = harmony.child("root")?;
+    let (root, _) = spelled_note(root.text_of("root-step")?, root.number_of("root-alter").unwrap_or(0.0))?;
+    let kind = harmony.text_of("kind").unwrap_or("major");
+    let (_, suffix) = HARMONY_KINDS.iter().find(|(name, _)| *name == kind)?;
+    let mut symbol = ChordSymbol::new(root, *suffix);
+    if let Some(bass) = harmony.child("bass") {
+        let (bass, _) = spelled_note(bass.text_of("bass-step")?, bass.number_of("bass-alter").unwrap_or(0.0))?;
+        symbol = symbol.over(bass);
+    }
+    Some(symbol.to_string(SymbolStyle::default()))
+}
+
+/// Key of a key element: the major tonic its signature belongs to ("Eb" for three flats, in C minor too)
+fn key_tonic(key: &Node) -> Option<&'static str> {
+    key_for_fifths(key.number_of("fifths")? as i32, false)
+}
+
+fn time_signature(time: &Node) -> Option<String> {
+    Some(format!("{}/{}", time.text_of("beats")?, time.text_of("beat-type")?))
+}
+
+fn clef(attributes: &Node) -> Option<Clef> {
+    if attributes.number_of("staves").is_some_and(|staves| staves > 1.0) {
+        return Some(Clef::Both);
+    }
+    let clef = attributes.children("clef").find(|clef| clef.attribute("number").is_none_or(|number| number == "1"))?;
+    Some(if clef.text_of("sign") == Some("F") { Clef::Bass } else { Clef::Treble })
+}
+
+/// What an imported element holds until the part has been read
+enum Imported {
+    /// Notes struck together, spelled for the key they sound in
+    Notes(Vec<(AudioNote, u8)>, &'static str),
+    Rest,
+    Harmony(String),
+    Key(String),
+    Time(String),
+}
+
+/// Reading state of one part, measure by measure
+struct PartReader {
+    /// Duration units per quarter note
+    divisions: f64,
+    key: Option<&'static str>,
+    time: Option<String>,
+    /// Key, time and clef the part starts with, printed at the start of the section
+    start_key: Option<&'static str>,
+    start_time: Option<String>,
+    start_clef: Option<Clef>,
+    /// The staff holds one line of music, so only the part's first voice is read
+    voice: Option<String>,
+    measure: u32,
+    /// Position in the measure, in duration units
+    offset: f64,
+    /// Onset of the last note, for the notes stacked on it as a chord
+    onset: f64,
+    /// Element the next stacked note joins; None when that note's beat was already taken
+    last_notes: Option<usize>,
+    taken: HashSet<(u32, u32)>,
+    imported: Vec<(ElementPosition, Imported)>,
+}
+
+impl PartReader {
+    fn new() -> Self {
+        Self {
+            divisions: 1.0,
+            key: None,
+            time: None,
+            start_key: None,
+            start_time: None,
+            start_clef: None,
+            voice: None,
+            measure: 0,
+            offset: 0.0,
+            onset: 0.0,
+            last_notes: None,
+            taken: HashSet::new(),
+            imported: Vec::new(),
+        }
+    }
+
+    fn position(&self, offset: f64) -> ElementPosition {
+        let beat = (offset.max(0.0) / self.divisions).floor() as u32 + 1;
+        ElementPosition { measure: self.measure, beat, voice: None }
+    }
+
+    /// Add an element on the beat its onset falls in
+    /// The worksheet holds one element per beat, so later events on a taken beat are dropped
+    fn place(&mut self, offset: f64, imported: Imported) -> Option<usize> {
+        let position = self.position(offset);
+        if !self.taken.insert((position.measure, position.beat)) {
+            return None;
+        }
+        self.imported.push((position, imported));
+        Some(self.imported.len() - 1)
+    }
+
+    fn read_measure(&mut self, measure: &Node) {
+        self.measure += 1;
+        self.offset = 0.0;
+        self.last_notes = None;
+        for child in &measure.children {
+            match child.name.as_str() {
+                "attributes" => self.read_attributes(child),
+                "harmony" => {
+                    if let Some(symbol) = harmony_symbol(child) {
+                        let offset = self.offset + child.number_of("offset").unwrap_or(0.0);
+                        self.place(offset, Imported::Harmony(symbol));
+                    }
+                }
+                "note" => self.read_note(child),
+                "backup" => self.offset = (self.offset - child.number_of("duration").unwrap_or(0.0)).max(0.0),
+                "forward" => self.offset += child.number_of("duration").unwrap_or(0.0),
+                _ => {}
+            }
+        }
+    }
+
+    /// The first key, time and clef go into the section layout; later key and time changes become elements
+    fn read_attributes(&mut self, attributes: &Node) {
+        if let Some(divisions) = attributes.number_of("divisions").filter(|divisions| *divisions > 0.0) {
+            self.divisions = divisions;
+        }
+        if let Some(key) = attributes.child("key").and_then(key_tonic) {
+            if self.key.is_some_and(|current| current != key) {
+                let position = self.position(self.offset);
+                self.imported.push((position, Imported::Key(lilypond_note_name(key))));
+            }
+            self.key = Some(key);
+            self.start_key.get_or_insert(key);
+        }
+        if let Some(time) = attributes.child("time").and_then(time_signature) {
+            if self.time.as_ref().is_some_and(|current| *current != time) {
+                let position = self.position(self.offset);
+                self.imported.push((position, Imported::Time(time.clone())));
+            }
+            self.start_time.get_or_insert_with(|| time.clone());
+            self.time = Some(time);
+        }
+        if self.start_clef.is_none() {
+            self.start_clef = clef(attributes);
+        }
+    }
+
+    fn read_note(&mut self, note: &Node) {
+        if note.has("grace") || note.has("cue") {
+            return;
+        }
+        let stacked = note.has("chord");
+        let voice = note.text_of("voice").unwrap_or("1");
+        let in_voice = self.voice.get_or_insert_with(|| voice.to_string()).as_str() == voice;
+        if !stacked {
+            self.onset = self.offset;
+            self.offset += note.number_of("duration").unwrap_or(0.0);
+        }
+        if !in_voice {
+            return;
+        }
+
+        if note.has("rest") {
+            self.place(self.onset, Imported::Rest);
+            self.last_notes = None;
+            return;
+        }
+        let Some(pitch) = note.child("pitch").and_then(note_pitch) else {
+            return;
+        };
+        if stacked {
+            if let Some(Imported::Notes(pitches, _)) =
+                self.last_notes.and_then(|index| self.imported.get_mut(index)).map(|(_, imported)| imported)
+            {
+                pitches.push(pitch);
+            }
+        } else {
+            let key = self.key.unwrap_or("C");
+            self.last_notes = self.place(self.onset, Imported::Notes(vec![pitch], key));
+        }
+    }
+}
+
+/// Notes struck together become a chord when they name one; otherwise the top (melody) note is kept
+fn strike(mut pitches: Vec<(AudioNote, u8)>, key: &'static str) -> Imported {
+    pitches.sort_by_key(|(_, midi)| *midi);
+    let midis: Vec<u8> = pitches.iter().map(|(_, midi)| *midi).collect();
+    if midis.len() > 1 {
+        if let Some(chord) = name_midi_chord(&midis, key) {
+            return Imported::Harmony(chord);
+        }
+    }
+    let top = pitches.pop().expect("notes hold at least one pitch");
+    Imported::Notes(vec![top], key)
+}
+
+fn read_part(part: &Node, index: usize, title: &str) -> WorksheetSection {
+    let mut reader = PartReader::new();
+    for measure in part.children("measure") {
+        reader.read_measure(measure);
+    }
+
+    // Name stacked notes first, so the melody notes left can be moved onto the part's staff together
+    let imported: Vec<(ElementPosition, Imported)> = reader
+        .imported
+        .into_iter()
+        .map(|(position, imported)| match imported {
+            Imported::Notes(pitches, key) => (position, strike(pitches, key)),
+            other => (position, other),
+        })
+        .collect();
+    let clef = reader.start_clef.unwrap_or(Clef::Treble);
+    let melody: Vec<u8> = imported
+        .iter()
+        .filter_map(|(_, imported)| match imported {
+            Imported::Notes(pitches, _) => pitches.first().map(|(_, midi)| *midi),
+            _ => None,
+        })
+        .collect();
+    let mut fitted = fit_to_range(&melody, &clef).notes.into_iter();
+
+    let section_id = format!("import-part-{}", index + 1);
+    let elements = imported
+        .into_iter()
+        .enumerate()
+        .map(|(n, (position, imported))| {
+            let mut hint = None;
+            let (element_type, content) = match imported {
+                Imported::Notes(mut pitches, _) => {
+                    let fit = fitted.next().expect("every melody note is fitted");
+                    let (mut note, _) = pitches.remove(0);
+                    note.octave += fit.octaves;
+                    hint = ottava_mark(fit.ottava).map(str::to_string);
+                    (EditableElementType::Note, lilypond_pitch(&note))
+                }
+                Imported::Rest => (EditableElementType::Rest, "r4".to_string()),
+                Imported::Harmony(symbol) => (EditableElementType::Chord, symbol),
+                Imported::Key(key) => (EditableElementType::KeySignature, key),
+                Imported::Time(time) => (EditableElementType::TimeSignature, time),
+            };
+            EditableElement {
+                id: format!("{}-{}", section_id, n),
+                element_type,
+                position,
+                content,
+                is_answer: false,
+                is_interactive: true,
+                style: None,
+                hint,
+                keyboard: None,
+            }
+        })
+        .collect();
+
+    WorksheetSection {
+        id: section_id,
+        title: title.to_string(),
+        instructions: None,
+        elements,
+        layout: WorksheetSectionLayout {
+            measures_per_system: 4,
+            systems_per_page: 4,
+            clef,
+            time_signature: reader.start_time,
+            key_signature: reader.start_key.map(lilypond_note_name),
+            answer_blank: AnswerBlank::default(),
+        },
+    }
+}
+
+/// Convert a partwise MusicXML score into a worksheet, one section per part
+/// Notes, chords (stacked notes and chord symbols), rests, key and time signatures become editable elements
+/// on the beat they start on
+pub fn musicxml_to_worksheet(xml: &str) -> MusicResult<WorksheetConfig> {
+    let score = parse_document(xml)?;
+    match score.name.as_str() {
+        "score-partwise" => {}
+        "score-timewise" => {
+            return Err(MusicError::ParseError(
+                "Timewise MusicXML isn't supported; export the score as partwise MusicXML".to_string(),
+            ))
+        }
+        other => return Err(MusicError::ParseError(format!("Not a MusicXML score: <{}>", other))),
+    }
+
+    let part_names: Vec<(&str, &str)> = score
+        .child("part-list")
+        .into_iter()
+        .flat_map(|list| list.children("score-part"))
+        .filter_map(|part| Some((part.attribute("id")?, part.text_of("part-name")?)))
+        .collect();
+    let mut sections: Vec<WorksheetSection> = score
+        .children("part")
+        .enumerate()
+        .map(|(index, part)| {
+            let name = part_names.iter().find(|(id, _)| Some(*id) == part.attribute("id")).map(|(_, name)| *name);
+            read_part(part, index, name.unwrap_or(&format!("Part {}", index + 1)))
+        })
+        .collect();
+    sections.retain(|section| !section.elements.is_empty());
+    if sections.is_empty() {
+        return Err(MusicError::ParseError("The MusicXML score has no notes".to_string()));
+    }
+
+    let has_chords = sections
+        .iter()
+        .flat_map(|section| &section.elements)
+        .any(|element| matches!(element.element_type, EditableElementType::Chord));
+    let (worksheet_type, instructions) = if has_chords {
+        (WorksheetType::ChordNaming, "Identify the following chords")
+    } else {
+        (WorksheetType::NoteIdentification, "Identify the following notes")
+    };
+    for section in &mut sections {
+        section.instructions = Some(instructions.to_string());
+    }
+
+    let title = score
+        .child("work")
+        .and_then(|work| work.text_of("work-title"))
+        .or_else(|| score.text_of("movement-title"))
+        .unwrap_or("Imported Worksheet");
+    let composer = score
+        .child("identification")
+        .into_iter()
+        .flat_map(|identification| identification.children("creator"))
+        .find(|creator| creator.attribute("type") == Some("composer"))
+        .map(|creator| creator.text.trim().to_string())
+        .filter(|composer| !composer.is_empty());
+
+    Ok(WorksheetConfig {
+        id: Uuid::new_v4().to_string(),
+        title: title.to_string(),
+        subtitle: composer,
+        worksheet_type,
+        sections,
+        global_settings: WorksheetGlobalSettings {
+            paper_size: PaperSize::Letter,
+            orientation: Orientation::Portrait,
+            show_answers: false,
+            answer_color: None,
+            font_size: 14,
+            locale: None,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCORE: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<!DOCTYPE score-partwise PUBLIC "-//Recordare//DTD MusicXML 4.0 Partwise//EN"
+  "http://www.musicxml.org/dtds/partwise.dtd">
+<score-partwise version="4.0">
+  <work><work-title>Chorale &amp; Tune</work-title></work>
+  <identification><creator type="composer">J. S. Bach</creator></identification>
+  <part-list><score-part id="P1"><part-name>Piano</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes>
+        <divisions>2</divisions>
+        <key><fifths>-3</fifths><mode>major</mode></key>
+        <time><beats>3</beats><beat-type>4</beat-type></time>
+        <clef><sign>G</sign><line>2</line></clef>
+      </attributes>
+      <harmony><root><root-step>E</root-step><root-alter>-1</root-alter></root><kind>major</kind></harmony>
+      <note><pitch><step>E</step><alter>-1</alter><octave>4</octave></pitch><duration>2</duration></note>
+      <note><pitch><step>G</step><octave>4</octave></pitch><duration>1</duration></note>
+      <note><pitch><step>F</step><octave>4</octave></pitch><duration>1</duration></note>
+      <note><rest/><duration>2</duration></note>
+      <backup><duration>6</duration></backup>
+      <note><pitch><step>C</step><octave>3</octave></pitch><duration>6</duration><voice>2</voice></note>
+    </measure>
+    <measure number="2">
+      <attributes><time><beats>4</beats><beat-type>4</beat-type></time></attributes>
+      <note><pitch><step>A</step><alter>-1</alter><octave>3</octave></pitch><duration>4</duration></note>
+      <note><chord/><pitch><step>C</step><octave>4</octave></pitch><duration>4</duration></note>
+      <note><chord/><pitch><step>E</step><alter>-1</alter><octave>4</octave></pitch><duration>4</duration></note>
+      <harmony>
+        <root><root-step>B</root-step><root-alter>-1</root-alter></root><kind>dominant</kind>
+        <bass><bass-step>D</bass-step></bass>
+      </harmony>
+      <note><grace/><pitch><step>C</step><octave>5</octave></pitch></note>
+      <note><pitch><step>D</step><octave>5</octave></pitch><duration>4</duration></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+    fn contents(section: &WorksheetSection) -> Vec<(u32, u32, &str)> {
+        section
+            .elements
+            .iter()
+            .map(|element| (element.position.measure, element.position.beat, element.content.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_import_notes_chords_and_signatures() {
+        let worksheet = musicxml_to_worksheet(SCORE).unwrap();
+        assert_eq!(worksheet.title, "Chorale & Tune");
+        assert_eq!(worksheet.subtitle.as_deref(), Some("J. S. Bach"));
+        assert!(matches!(worksheet.worksheet_type, WorksheetType::ChordNaming));
+
+        let section = &worksheet.sections[0];
+        assert_eq!(section.title, "Piano");
+        assert_eq!(section.layout.key_signature.as_deref(), Some("ees"));
+        assert_eq!(section.layout.time_signature.as_deref(), Some("3/4"));
+        assert!(matches!(section.layout.clef, Clef::Treble));
+        // The chord symbol takes beat 1 from the melody; the eighth on beat 2.5 shares a beat and is dropped;
+        // the second voice is left out
+        assert_eq!(
+            contents(section),
+            vec![
+                (1, 1, "Eb"),
+                (1, 2, "g'"),
+                (1, 3, "r4"),
+                (2, 1, "4/4"),
+                (2, 1, "Ab"),
+                (2, 3, "Bb7/D"),
+            ]
+        );
+        let types: Vec<_> = section.elements.iter().map(|element| element.element_type.clone()).collect();
+        assert!(matches!(types[3], EditableElementType::TimeSignature));
+        assert!(matches!(types[4], EditableElementType::Chord));
+    }
+
+    #[test]
+    fn test_single_notes_and_errors() {
+        let melody = r#"<score-partwise><part-list><score-part id="P1"/></part-list><part id="P1"><measure>
+            <attributes><clef><sign>F</sign><line>4</line></clef></attributes>
+            <note><pitch><step>F</step><alter>1</alter><octave>2</octave></pitch><duration>1</duration></note>
+        </measure></part></score-partwise>"#;
+        let worksheet = musicxml_to_worksheet(melody).unwrap();
+        assert!(matches!(worksheet.worksheet_type, WorksheetType::NoteIdentification));
+        let section = &worksheet.sections[0];
+        assert_eq!(section.title, "Part 1");
+        assert!(matches!(section.layout.clef, Clef::Bass));
+        assert_eq!(contents(section), vec![(1, 1, "fis,")]);
+
+        assert!(musicxml_to_worksheet("<score-timewise/>").is_err());
+        assert!(musicxml_to_worksheet("<svg></svg>").is_err());
+        assert!(musicxml_to_worksheet("<score-partwise><part></score-partwise>").is_err());
+        assert!(musicxml_to_worksheet("<score-partwise><part id=\"P1\"/></score-partwise>").is_err());
+    }
+}
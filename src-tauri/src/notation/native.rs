@@ -0,0 +1,915 @@
+// Native notation rendering
+// Draws worksheets to SVG without LilyPond: staves, clefs, key and time signatures, note heads with
+// accidentals and ledger lines, and chord symbols, with SMuFL glyphs from the bundled Bravura font
+// Covers the worksheet types made of single notes and chords; the rest still need LilyPond
+// Also draws progressions as compact strips of chord symbols for sharing
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::key_signature::{get_key_signature_layout, StaffClef};
+use crate::i18n;
+use crate::music::analysis::analyze_progression;
+use crate::music::chords::parse_chord;
+use crate::music::intervals::chord_to_notes;
+use crate::music::types::{Accidental, MusicError, MusicResult};
+use crate::svg::{safe_element_id, ELEMENT_ID_ATTRIBUTE};
+use crate::types::worksheet::*;
+
+/// One staff space in pixels; Bravura is drawn at four staff spaces to the em
+const SPACE: f32 = 8.0;
+const MUSIC_FONT: &str = "Bravura";
+const TEXT_FONT: &str = "serif";
+const MARGIN: f32 = 56.0;
+/// Staff lines, ledger lines, bar lines and stems
+const LINE_WIDTH: f32 = 1.0;
+/// Room above each staff for chord symbols, and below it for ledger lines and hints
+const ABOVE_STAFF: f32 = 5.0 * SPACE;
+const BELOW_STAFF: f32 = 7.0 * SPACE;
+const NOTEHEAD_WIDTH: f32 = 1.18 * SPACE;
+const STEM_LENGTH: f32 = 3.5 * SPACE;
+/// Width of an answer blank, and where lines and boxes sit below the staff (above any hint)
+const BLANK_WIDTH: f32 = 4.0 * SPACE;
+const BLANK_BELOW: f32 = 3.5 * SPACE;
+/// Page widths in pixels at 96 dpi
+const LETTER_SIZE: (f32, f32) = (816.0, 1056.0);
+const A4_SIZE: (f32, f32) = (794.0, 1123.0);
+
+/// Strip chords are given this much room before scaling, and drawn at most this much larger
+const STRIP_COLUMN: f32 = 10.0 * SPACE;
+const STRIP_MAX_SCALE: f32 = 2.5;
+const STRIP_MARGIN: f32 = 3.0 * SPACE;
+const WATERMARK: &str = "Maestro Blocks";
+
+/// SMuFL code points
+const G_CLEF: char = '\u{E050}';
+const F_CLEF: char = '\u{E062}';
+const NOTEHEAD_BLACK: char = '\u{E0A4}';
+const REST_QUARTER: char = '\u{E4E5}';
+const TIME_SIGNATURE_ZERO: u32 = 0xE080;
+const CSYM_FLAT: char = '\u{ED60}';
+const CSYM_SHARP: char = '\u{ED62}';
+const CSYM_DIMINISHED: char = '\u{E870}';
+const CSYM_HALF_DIMINISHED: char = '\u{E871}';
+const CSYM_AUGMENTED: char = '\u{E872}';
+
+/// Size of a chord symbol's raised extensions and alterations, relative to the symbol
+const SUPERSCRIPT_SCALE: f32 = 0.7;
+
+const LETTERS: &str = "CDEFGAB";
+
+/// Written pitch: diatonic steps above C0 and alteration in semitones
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pitch {
+    step: i32,
+    alter: i8,
+}
+
+/// Staff a section is drawn on
+struct StaffKind {
+    glyph: char,
+    clef: StaffClef,
+    /// Step of the bottom line, and the staff position the clef glyph sits on
+    bottom_step: i32,
+    glyph_position: i32,
+    /// Octave chords are stacked up from
+    chord_octave: i32,
+}
+
+const TREBLE: StaffKind =
+    StaffKind { glyph: G_CLEF, clef: StaffClef::Treble, bottom_step: 30, glyph_position: 2, chord_octave: 4 };
+const BASS: StaffKind =
+    StaffKind { glyph: F_CLEF, clef: StaffClef::Bass, bottom_step: 18, glyph_position: 6, chord_octave: 3 };
+
+/// Worksheet types drawn without LilyPond
+pub fn supports(worksheet_type: &WorksheetType) -> bool {
+    matches!(worksheet_type, WorksheetType::ChordNaming | WorksheetType::NoteIdentification)
+}
+
+fn accidental_glyph(alter: i8) -> char {
+    match alter {
+        -2 => '\u{E264}',
+        -1 => '\u{E260}',
+        1 => '\u{E262}',
+        2 => '\u{E263}',
+        _ => '\u{E261}',
+    }
+}
+
+fn letter_index(letter: char) -> Option<i32> {
+    LETTERS.find(letter.to_ascii_uppercase()).map(|index| index as i32)
+}
+
+/// Pitch of a note name ("Eb", "F##") in an octave
+fn named_pitch(note: &str, octave: i32) -> Option<Pitch> {
+    let mut chars = note.chars();
+    let step = octave * 7 + letter_index(chars.next()?)?;
+    let alter = chars
+        .map(|c| match c {
+            '#' => Some(1),
+            'b' => Some(-1),
+            _ => None,
+        })
+        .sum::<Option<i8>>()?;
+    Some(Pitch { step, alter })
+}
+
+/// Letter and alteration of a LilyPond note name ("bes", "fisis", "as"), and what follows it
+fn lilypond_name(text: &str) -> Option<(i32, i8, &str)> {
+    let letter = letter_index(text.chars().next().filter(char::is_ascii_lowercase)?)?;
+    let (mut rest, mut alter) = (&text[1..], 0);
+    loop {
+        if let Some(after) = rest.strip_prefix("is") {
+            alter += 1;
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("es") {
+            alter -= 1;
+            rest = after;
+        } else if let (Some(after), 0, 2 | 5) = (rest.strip_prefix('s'), alter, letter) {
+            // "es" and "as" are E-flat and A-flat
+            alter -= 1;
+            rest = after;
+        } else {
+            return Some((letter, alter, rest));
+        }
+    }
+}
+
+/// Pitch of an absolute LilyPond pitch ("ees''", "fis", "c,"); "c" is the octave below middle C
+/// A duration after the octave marks is ignored
+fn lilypond_pitch(text: &str) -> Option<Pitch> {
+    let (letter, alter, marks) = lilypond_name(text.trim())?;
+    let mut octave = 3;
+    for mark in marks.chars() {
+        match mark {
+            '\'' => octave += 1,
+            ',' => octave -= 1,
+            _ => break,
+        }
+    }
+    Some(Pitch { step: octave * 7 + letter, alter })
+}
+
+/// Pitches of a note element: one LilyPond pitch, or a chord of them in angle brackets
+fn note_pitches(content: &str) -> MusicResult<Vec<Pitch>> {
+    let content = content.trim();
+    let inner = content.strip_prefix('<').and_then(|inner| inner.split('>').next()).unwrap_or(content);
+    inner
+        .split_whitespace()
+        .map(|pitch| lilypond_pitch(pitch).ok_or_else(|| MusicError::ParseError(format!("Invalid pitch: {}", pitch))))
+        .collect()
+}
+
+/// Note name of a letter (0 = C) and alteration ("Bb", "F##")
+fn note_name(letter: i32, alter: i8) -> String {
+    let accidentals = if alter < 0 { "b" } else { "#" }.repeat(alter.unsigned_abs() as usize);
+    format!("{}{}", &LETTERS[letter as usize..letter as usize + 1], accidentals)
+}
+
+/// Chord symbol of a chord element: as written, or with a lowercase LilyPond root ("bes", "fis") spelled
+/// as a note
+fn chord_symbol(content: &str) -> String {
+    let content = content.trim();
+    match lilypond_name(content) {
+        Some((letter, alter, rest)) => format!("{}{}", note_name(letter, alter), rest),
+        None => content.to_string(),
+    }
+}
+
+/// Part of a chord symbol set in one style
+#[derive(Debug, Clone, PartialEq)]
+struct SymbolRun {
+    text: String,
+    /// Drawn with Bravura's chord symbol glyphs rather than the text font
+    glyph: bool,
+    raised: bool,
+}
+
+fn push_run(runs: &mut Vec<SymbolRun>, c: char, glyph: bool, raised: bool) {
+    match runs.last_mut() {
+        Some(run) if run.glyph == glyph && run.raised == raised => run.text.push(c),
+        _ => runs.push(SymbolRun { text: c.to_string(), glyph, raised }),
+    }
+}
+
+fn accidental_symbol(c: char) -> Option<char> {
+    match c {
+        'b' => Some(CSYM_FLAT),
+        '#' => Some(CSYM_SHARP),
+        _ => None,
+    }
+}
+
+/// A note name on the baseline with its accidentals as glyphs
+fn push_note_runs(runs: &mut Vec<SymbolRun>, chars: &mut std::iter::Peekable<std::str::Chars>) {
+    if let Some(letter) = chars.next() {
+        push_run(runs, letter, false, false);
+    }
+    while let Some(glyph) = chars.peek().copied().and_then(accidental_symbol) {
+        push_run(runs, glyph, true, false);
+        chars.next();
+    }
+}
+
+/// Lay out a chord symbol the way charts engrave it: root, quality and bass on the baseline, the
+/// extensions and alterations from the first number on raised, and accidentals, diminished,
+/// half-diminished and augmented signs as glyphs ("Bbm7b5/F#" → B♭m, raised 7♭5, /F♯)
+fn chord_symbol_runs(symbol: &str) -> Vec<SymbolRun> {
+    // A slash starts the bass only before a note name, so 6/9 chords stay whole
+    let (main, bass) = match symbol.rsplit_once('/') {
+        Some((main, bass)) if bass.starts_with(|c: char| ('A'..='G').contains(&c)) => (main, Some(bass)),
+        _ => (symbol, None),
+    };
+
+    let mut runs = Vec::new();
+    let mut chars = main.chars().peekable();
+    push_note_runs(&mut runs, &mut chars);
+    let quality: Vec<char> = chars.collect();
+    let mut raised = false;
+    let mut index = 0;
+    while index < quality.len() {
+        let c = quality[index];
+        let before_number = quality.get(index + 1).is_some_and(char::is_ascii_digit);
+        raised |= c.is_ascii_digit() || c == '(' || (accidental_symbol(c).is_some() && before_number);
+        let rest: String = quality[index..].iter().collect();
+        let (glyph, length) = match c {
+            _ if rest.starts_with("dim") => (Some(CSYM_DIMINISHED), 3),
+            _ if rest.starts_with("aug") => (Some(CSYM_AUGMENTED), 3),
+            '°' => (Some(CSYM_DIMINISHED), 1),
+            'ø' => (Some(CSYM_HALF_DIMINISHED), 1),
+            '+' if !before_number => (Some(CSYM_AUGMENTED), 1),
+            _ if before_number => (accidental_symbol(c), 1),
+            _ => (None, 1),
+        };
+        match glyph {
+            Some(glyph) => push_run(&mut runs, glyph, true, raised),
+            None => push_run(&mut runs, c, false, raised),
+        }
+        index += length;
+    }
+
+    if let Some(bass) = bass {
+        push_run(&mut runs, '/', false, false);
+        let mut chars = bass.chars().peekable();
+        push_note_runs(&mut runs, &mut chars);
+        for c in chars {
+            push_run(&mut runs, c, false, false);
+        }
+    }
+    runs
+}
+
+/// Chord tones stacked upward from the root, or the slash bass, in an octave
+fn chord_pitches(symbol: &str, octave: i32) -> MusicResult<Vec<Pitch>> {
+    let main = symbol.split('/').next().unwrap_or(symbol);
+    let mut notes = chord_to_notes(main)?;
+    if let Some(bass) = parse_chord(symbol)?.bass {
+        notes.retain(|note| *note != bass);
+        notes.insert(0, bass);
+    }
+
+    let mut pitches: Vec<Pitch> = Vec::new();
+    for note in &notes {
+        let mut pitch = named_pitch(note, octave).ok_or_else(|| MusicError::InvalidChord(symbol.to_string()))?;
+        while pitches.last().is_some_and(|last| pitch.step <= last.step) {
+            pitch.step += 7;
+        }
+        pitches.push(pitch);
+    }
+    Ok(pitches)
+}
+
+/// Key signature of a section
+struct KeySignature {
+    /// Alteration of each altered letter (0 = C)
+    alterations: HashMap<i32, i8>,
+    /// Accidentals as written, with their staff positions
+    written: Vec<(Accidental, i8)>,
+}
+
+impl KeySignature {
+    /// Signature written as a LilyPond major tonic ("ees")
+    fn new(signature: &str, staff: &StaffKind) -> MusicResult<Self> {
+        let invalid = || MusicError::InvalidKey(signature.to_string());
+        let (letter, alter, _) = lilypond_name(signature.trim()).ok_or_else(invalid)?;
+        let layout = get_key_signature_layout(&note_name(letter, alter), staff.clef)?;
+
+        let alterations = layout
+            .accidentals
+            .iter()
+            .filter_map(|accidental| named_pitch(&accidental.note, 0))
+            .map(|pitch| (pitch.step, pitch.alter))
+            .collect();
+        let written =
+            layout.accidentals.iter().map(|accidental| (accidental.accidental, accidental.staff_position)).collect();
+        Ok(Self { alterations, written })
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn is_hex_color(color: &str) -> bool {
+    color.strip_prefix('#').is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// SVG being drawn top to bottom
+struct Sheet {
+    body: String,
+    width: f32,
+    y: f32,
+}
+
+impl Sheet {
+    fn glyph(&mut self, x: f32, y: f32, glyph: char) {
+        self.body.push_str(&format!(
+            r#"<text x="{:.1}" y="{:.1}" font-family="{}" font-size="{}">{}</text>"#,
+            x,
+            y,
+            MUSIC_FONT,
+            4.0 * SPACE,
+            glyph
+        ));
+    }
+
+    /// Text centered on x, from markup that is already escaped
+    fn text_markup(&mut self, x: f32, y: f32, size: f32, style: &str, markup: &str) {
+        self.body.push_str(&format!(
+            r#"<text x="{:.1}" y="{:.1}" font-family="{}" font-size="{}" text-anchor="middle"{}>{}</text>"#,
+            x, y, TEXT_FONT, size, style, markup
+        ));
+    }
+
+    fn text(&mut self, x: f32, y: f32, size: f32, style: &str, text: &str) {
+        self.text_markup(x, y, size, style, &escape(text));
+    }
+
+    /// Chord symbol centered on x, laid out by chord_symbol_runs
+    fn chord_symbol(&mut self, x: f32, y: f32, size: f32, style: &str, symbol: &str) {
+        let mut markup = String::new();
+        for run in chord_symbol_runs(symbol) {
+            let mut attributes = String::new();
+            if run.glyph {
+                attributes.push_str(&format!(r#" font-family="{}""#, MUSIC_FONT));
+            }
+            if run.raised {
+                attributes.push_str(&format!(r#" font-size="{:.1}" baseline-shift="super""#, size * SUPERSCRIPT_SCALE));
+            }
+            if attributes.is_empty() {
+                markup.push_str(&escape(&run.text));
+            } else {
+                markup.push_str(&format!("<tspan{}>{}</tspan>", attributes, escape(&run.text)));
+            }
+        }
+        self.text_markup(x, y, size, style, &markup);
+    }
+
+    fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32) {
+        self.body.push_str(&format!(
+            r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="black" stroke-width="{}"/>"#,
+            x1, y1, x2, y2, LINE_WIDTH
+        ));
+    }
+
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, paint: &str) {
+        self.body.push_str(&format!(
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" {}/>"#,
+            x, y, width, height, paint
+        ));
+    }
+
+    fn open_element(&mut self, class: &str, element_id: &str, color: Option<&str>) {
+        let fill = color.map(|color| format!(r#" fill="{}""#, color)).unwrap_or_default();
+        self.body.push_str(&format!(
+            r#"<g class="{}" {}="{}"{}>"#,
+            class,
+            ELEMENT_ID_ATTRIBUTE,
+            safe_element_id(element_id),
+            fill
+        ));
+    }
+
+    fn close_element(&mut self) {
+        self.body.push_str("</g>");
+    }
+}
+
+/// One staff being drawn: where it sits and the accidentals in force
+struct Staff<'a> {
+    kind: &'a StaffKind,
+    bottom: f32,
+    key: &'a HashMap<i32, i8>,
+    /// Accidentals written earlier in the current measure, by step
+    measure_accidentals: HashMap<i32, i8>,
+}
+
+impl Staff<'_> {
+    fn y(&self, position: i32) -> f32 {
+        self.bottom - position as f32 * SPACE / 2.0
+    }
+
+    /// Note heads with their accidentals, ledger lines and a shared stem, centered on x
+    fn draw_pitches(&mut self, sheet: &mut Sheet, x: f32, pitches: &[Pitch]) {
+        let left = x - NOTEHEAD_WIDTH / 2.0;
+        for pitch in pitches {
+            let position = pitch.step - self.kind.bottom_step;
+            let ledgers = match position {
+                p if p <= -2 => (p..=-2).filter(|p| p % 2 == 0).collect(),
+                p if p >= 10 => (10..=p).filter(|p| p % 2 == 0).collect(),
+                _ => Vec::new(),
+            };
+            for ledger in ledgers {
+                let y = self.y(ledger);
+                sheet.line(left - 0.4 * SPACE, y, left + NOTEHEAD_WIDTH + 0.4 * SPACE, y);
+            }
+
+            let in_force = self
+                .measure_accidentals
+                .get(&pitch.step)
+                .or_else(|| self.key.get(&pitch.step.rem_euclid(7)))
+                .copied()
+                .unwrap_or(0);
+            if pitch.alter != in_force {
+                sheet.glyph(left - 1.5 * SPACE, self.y(position), accidental_glyph(pitch.alter));
+                self.measure_accidentals.insert(pitch.step, pitch.alter);
+            }
+            sheet.glyph(left, self.y(position), NOTEHEAD_BLACK);
+        }
+
+        let positions = pitches.iter().map(|pitch| pitch.step - self.kind.bottom_step);
+        let (Some(low), Some(high)) = (positions.clone().min(), positions.max()) else {
+            return;
+        };
+        if low + high < 8 {
+            let stem_x = left + NOTEHEAD_WIDTH - LINE_WIDTH / 2.0;
+            sheet.line(stem_x, self.y(low), stem_x, self.y(high) - STEM_LENGTH);
+        } else {
+            let stem_x = left + LINE_WIDTH / 2.0;
+            sheet.line(stem_x, self.y(high), stem_x, self.y(low) + STEM_LENGTH);
+        }
+    }
+}
+
+fn beats_per_measure(time_signature: &str) -> u32 {
+    time_signature.split('/').next().and_then(|beats| beats.trim().parse().ok()).filter(|beats| *beats > 0).unwrap_or(4)
+}
+
+/// Direction attribute of titles and instructions: set for right-to-left locales
+fn heading_direction(settings: &WorksheetGlobalSettings) -> &'static str {
+    if i18n::locale(settings.locale.as_deref()).is_rtl() {
+        r#" direction="rtl""#
+    } else {
+        ""
+    }
+}
+
+/// Draw one section's title, instructions and systems of measures
+fn draw_section(sheet: &mut Sheet, section: &WorksheetSection, settings: &WorksheetGlobalSettings) -> MusicResult<()> {
+    let center = sheet.width / 2.0;
+    let direction = heading_direction(settings);
+    if !section.title.is_empty() {
+        sheet.y += 3.0 * SPACE;
+        sheet.text(center, sheet.y, 18.0, &format!(r#" font-weight="bold"{}"#, direction), &section.title);
+    }
+    if let Some(instructions) = section.instructions.as_deref().filter(|text| !text.is_empty()) {
+        sheet.y += 2.5 * SPACE;
+        sheet.text(center, sheet.y, 14.0, &format!(r#" font-style="italic"{}"#, direction), instructions);
+    }
+
+    let kind = if matches!(section.layout.clef, Clef::Bass) { &BASS } else { &TREBLE };
+    let key = KeySignature::new(section.layout.key_signature.as_deref().unwrap_or("c"), kind)?;
+    let time_signature = section.layout.time_signature.as_deref().unwrap_or("4/4");
+    let beats = beats_per_measure(time_signature);
+
+    let mut elements: Vec<&EditableElement> = section
+        .elements
+        .iter()
+        .filter(|element| {
+            !matches!(element.element_type, EditableElementType::TimeSignature | EditableElementType::KeySignature)
+        })
+        .collect();
+    elements.sort_by_key(|element| (element.position.measure, element.position.beat));
+    let measures = elements.iter().map(|element| element.position.measure).max().unwrap_or(1).max(1);
+    let per_system = section.layout.measures_per_system.max(1);
+
+    for first_measure in (1..=measures).step_by(per_system as usize) {
+        let top = sheet.y + ABOVE_STAFF;
+        let mut staff =
+            Staff { kind, bottom: top + 4.0 * SPACE, key: &key.alterations, measure_accidentals: HashMap::new() };
+        let right = sheet.width - MARGIN;
+        for line in 0..5 {
+            let y = staff.y(line * 2);
+            sheet.line(MARGIN, y, right, y);
+        }
+
+        let mut x = MARGIN + 0.5 * SPACE;
+        sheet.glyph(x, staff.y(kind.glyph_position), kind.glyph);
+        x += 3.5 * SPACE;
+        for (accidental, position) in &key.written {
+            let alter = if *accidental == Accidental::Sharp { 1 } else { -1 };
+            sheet.glyph(x, staff.y(*position as i32), accidental_glyph(alter));
+            x += 1.1 * SPACE;
+        }
+        x += 0.5 * SPACE;
+        if first_measure == 1 {
+            let digits: Vec<&str> = time_signature.split('/').map(str::trim).collect();
+            for (text, position) in digits.iter().zip([6, 2]) {
+                for (index, digit) in text.chars().filter_map(|c| c.to_digit(10)).enumerate() {
+                    let glyph = char::from_u32(TIME_SIGNATURE_ZERO + digit).unwrap_or(REST_QUARTER);
+                    sheet.glyph(x + index as f32 * 1.8 * SPACE, staff.y(position), glyph);
+                }
+            }
+            x += 2.5 * SPACE;
+        }
+
+        let last_measure = (first_measure + per_system - 1).min(measures);
+        let measure_width = (right - x) / (last_measure - first_measure + 1) as f32;
+        for measure in first_measure..=last_measure {
+            let measure_x = x + (measure - first_measure) as f32 * measure_width;
+            sheet.line(measure_x + measure_width, staff.y(8), measure_x + measure_width, staff.y(0));
+            staff.measure_accidentals.clear();
+
+            for element in elements.iter().filter(|element| element.position.measure == measure) {
+                let beat = element.position.beat.clamp(1, beats) as f32;
+                let center = measure_x + (beat - 0.5) * measure_width / beats as f32;
+                draw_element(sheet, &mut staff, element, center, settings, section.layout.answer_blank)?;
+            }
+        }
+        sheet.y = staff.bottom + BELOW_STAFF;
+    }
+    Ok(())
+}
+
+/// Blank left for a hidden answer centered on x, as on the LilyPond sheet
+fn draw_blank(sheet: &mut Sheet, staff: &Staff, x: f32, blank: AnswerBlank) {
+    let (left, below) = (x - BLANK_WIDTH / 2.0, staff.bottom + BLANK_BELOW);
+    match blank {
+        AnswerBlank::Rest => sheet.glyph(x - 0.5 * SPACE, staff.y(4), REST_QUARTER),
+        AnswerBlank::Line => sheet.line(left, below, left + BLANK_WIDTH, below),
+        AnswerBlank::Box => {
+            let paint = format!(r#"fill="none" stroke="black" stroke-width="{}""#, LINE_WIDTH);
+            sheet.rect(left, below - 2.5 * SPACE, BLANK_WIDTH, 2.5 * SPACE, &paint);
+        }
+        // Translucent so the staff lines show through
+        AnswerBlank::Shaded => {
+            sheet.rect(left, staff.y(8), BLANK_WIDTH, 4.0 * SPACE, r#"fill="black" fill-opacity="0.15""#);
+        }
+        AnswerBlank::Question => sheet.text(x, staff.y(3), 3.0 * SPACE, r#" font-weight="bold""#, "?"),
+    }
+}
+
+/// Draw an element centered on x: chord symbols above the staff, notes on it, hints below
+/// Hidden answers are left as the section's blank
+fn draw_element(
+    sheet: &mut Sheet,
+    staff: &mut Staff,
+    element: &EditableElement,
+    x: f32,
+    settings: &WorksheetGlobalSettings,
+    blank: AnswerBlank,
+) -> MusicResult<()> {
+    let shown = settings.show_answers || !element.is_answer;
+    let color = match (&settings.answer_color, element.is_answer) {
+        (Some(color), true) => Some(color.as_str()),
+        _ => element.style.as_ref().and_then(|style| style.color.as_deref()),
+    };
+    if let Some(color) = color.filter(|color| !is_hex_color(color)) {
+        return Err(MusicError::ParseError(format!("Invalid color: {}", color)));
+    }
+
+    match (&element.element_type, shown) {
+        (EditableElementType::Chord, true) => {
+            let symbol = chord_symbol(&element.content);
+            let pitches = chord_pitches(&symbol, staff.kind.chord_octave)?;
+            sheet.open_element("interactive-note", &element.id, color);
+            staff.draw_pitches(sheet, x, &pitches);
+            sheet.close_element();
+            sheet.open_element("interactive-chord", &element.id, color);
+            sheet.chord_symbol(x, staff.y(8) - 2.5 * SPACE, 16.0, "", &symbol);
+            sheet.close_element();
+        }
+        (EditableElementType::Note, true) => {
+            let pitches = note_pitches(&element.content)?;
+            sheet.open_element("interactive-note", &element.id, color);
+            staff.draw_pitches(sheet, x, &pitches);
+            sheet.close_element();
+        }
+        (EditableElementType::Chord | EditableElementType::Note, false) => draw_blank(sheet, staff, x, blank),
+        (EditableElementType::Rest, _) => sheet.glyph(x - 0.5 * SPACE, staff.y(4), REST_QUARTER),
+        _ => {}
+    }
+
+    if let Some(hint) = element.hint.as_deref().map(str::trim).filter(|hint| !hint.is_empty()) {
+        sheet.text(x, staff.bottom + 5.0 * SPACE, 11.0, "", hint);
+    }
+    Ok(())
+}
+
+/// Render a whole worksheet as one SVG, sections one after another down the page
+pub fn render_worksheet(config: &WorksheetConfig) -> MusicResult<String> {
+    let settings = &config.global_settings;
+    let (width, height) = match settings.paper_size {
+        PaperSize::Letter => LETTER_SIZE,
+        PaperSize::A4 => A4_SIZE,
+    };
+    let width = if matches!(settings.orientation, Orientation::Landscape) { height } else { width };
+
+    let direction = heading_direction(settings);
+    let mut sheet = Sheet { body: String::new(), width, y: MARGIN };
+    sheet.text(width / 2.0, sheet.y, 24.0, &format!(r#" font-weight="bold"{}"#, direction), &config.title);
+    if let Some(subtitle) = config.subtitle.as_deref().filter(|text| !text.is_empty()) {
+        sheet.y += 3.0 * SPACE;
+        sheet.text(width / 2.0, sheet.y, 16.0, direction, subtitle);
+    }
+    for section in &config.sections {
+        sheet.y += 2.0 * SPACE;
+        draw_section(&mut sheet, section, settings)?;
+    }
+
+    Ok(format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{:.0}">{}</svg>"#,
+        width,
+        sheet.y + MARGIN,
+        sheet.body
+    ))
+}
+
+/// Social media sizes a progression strip is drawn at
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StripFormat {
+    /// 1200×675, the 16:9 size feeds and link previews show uncropped
+    #[default]
+    Landscape,
+    /// 1500×500 profile header
+    Banner,
+}
+
+impl StripFormat {
+    /// Width and height in pixels
+    pub fn size(self) -> (f32, f32) {
+        match self {
+            StripFormat::Landscape => (1200.0, 675.0),
+            StripFormat::Banner => (1500.0, 500.0),
+        }
+    }
+}
+
+/// What a progression strip shows besides the chord symbols
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StripOptions {
+    pub format: StripFormat,
+    /// Key to print Roman numerals in, under the symbols (None = no numerals)
+    pub key: Option<String>,
+    /// Draw each chord on a small treble staff
+    pub staff: bool,
+    pub watermark: bool,
+}
+
+/// Draw a progression as one row of chord symbols on a white background, sized for social media
+/// Chords shrink to fit long progressions; on the staff every accidental is written out
+pub fn render_progression_strip(chords: &[String], options: &StripOptions) -> MusicResult<String> {
+    if chords.is_empty() {
+        return Err(MusicError::ParseError("Empty progression".to_string()));
+    }
+    let (width, height) = options.format.size();
+    let clef_width = if options.staff { 4.0 * SPACE } else { 0.0 };
+    let natural_width = 2.0 * STRIP_MARGIN + clef_width + chords.len() as f32 * STRIP_COLUMN;
+    let scale = (width / natural_width).min(STRIP_MAX_SCALE);
+    let (inner_width, inner_height) = (width / scale, height / scale);
+
+    let numerals: Option<Vec<Option<String>>> = options
+        .key
+        .as_deref()
+        .map(|key| analyze_progression(chords, key).into_iter().map(|analysis| analysis.numeral).collect());
+    // Symbols, numerals, and a staff with room for ledger lines above and below
+    let content_height = 3.0 * SPACE
+        + if numerals.is_some() { 2.5 * SPACE } else { 0.0 }
+        + if options.staff { 11.0 * SPACE } else { 0.0 };
+    let mut sheet = Sheet { body: String::new(), width: inner_width, y: (inner_height - content_height) / 2.0 };
+
+    let left = STRIP_MARGIN + clef_width;
+    let column = (inner_width - STRIP_MARGIN - left) / chords.len() as f32;
+    let centers: Vec<f32> = (0..chords.len()).map(|index| left + (index as f32 + 0.5) * column).collect();
+
+    sheet.y += 3.0 * SPACE;
+    for (symbol, &x) in chords.iter().zip(&centers) {
+        sheet.chord_symbol(x, sheet.y, 20.0, r#" font-weight="bold""#, symbol.trim());
+    }
+    if let Some(numerals) = &numerals {
+        sheet.y += 2.5 * SPACE;
+        for (numeral, &x) in numerals.iter().zip(&centers) {
+            sheet.text(x, sheet.y, 14.0, r#" font-style="italic""#, numeral.as_deref().unwrap_or("?"));
+        }
+    }
+
+    if options.staff {
+        let no_key = HashMap::new();
+        let top = sheet.y + 4.0 * SPACE;
+        let mut staff =
+            Staff { kind: &TREBLE, bottom: top + 4.0 * SPACE, key: &no_key, measure_accidentals: HashMap::new() };
+        let right = inner_width - STRIP_MARGIN;
+        for line in 0..5 {
+            let y = staff.y(line * 2);
+            sheet.line(STRIP_MARGIN, y, right, y);
+        }
+        sheet.glyph(STRIP_MARGIN + 0.5 * SPACE, staff.y(TREBLE.glyph_position), TREBLE.glyph);
+        for (symbol, &x) in chords.iter().zip(&centers) {
+            staff.measure_accidentals.clear();
+            staff.draw_pitches(&mut sheet, x, &chord_pitches(symbol.trim(), TREBLE.chord_octave)?);
+            let bar = x + column / 2.0;
+            sheet.line(bar, staff.y(8), bar, staff.y(0));
+        }
+    }
+
+    if options.watermark {
+        let x = inner_width - STRIP_MARGIN - 5.0 * SPACE;
+        sheet.text(x, inner_height - SPACE, 10.0, r##" fill="#888888""##, WATERMARK);
+    }
+
+    let background = format!(r#"<rect width="{}" height="{}" fill="white"/>"#, width, height);
+    let content = format!(r#"<g transform="scale({:.3})">{}</g>"#, scale, sheet.body);
+    Ok(format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">{}{}</svg>"#,
+        background,
+        content,
+        w = width,
+        h = height
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(
+        id: &str,
+        element_type: EditableElementType,
+        beat: u32,
+        content: &str,
+        is_answer: bool,
+    ) -> EditableElement {
+        EditableElement {
+            id: id.to_string(),
+            element_type,
+            position: ElementPosition { measure: 1, beat, voice: None },
+            content: content.to_string(),
+            is_answer,
+            is_interactive: true,
+            style: None,
+            hint: None,
+            keyboard: None,
+        }
+    }
+
+    fn worksheet(elements: Vec<EditableElement>, key_signature: &str) -> WorksheetConfig {
+        WorksheetConfig {
+            id: "sheet".to_string(),
+            title: "Chords & Notes".to_string(),
+            subtitle: None,
+            worksheet_type: WorksheetType::ChordNaming,
+            sections: vec![WorksheetSection {
+                id: "section".to_string(),
+                title: String::new(),
+                instructions: None,
+                elements,
+                layout: WorksheetSectionLayout {
+                    measures_per_system: 4,
+                    systems_per_page: 4,
+                    clef: Clef::Treble,
+                    time_signature: Some("4/4".to_string()),
+                    key_signature: Some(key_signature.to_string()),
+                    answer_blank: AnswerBlank::default(),
+                },
+            }],
+            global_settings: WorksheetGlobalSettings {
+                paper_size: PaperSize::Letter,
+                orientation: Orientation::Portrait,
+                show_answers: false,
+                answer_color: None,
+                font_size: 14,
+                locale: None,
+            },
+        }
+    }
+
+    fn count(svg: &str, glyph: char) -> usize {
+        svg.matches(glyph).count()
+    }
+
+    #[test]
+    fn test_pitch_parsing() {
+        assert_eq!(lilypond_pitch("ees''"), Some(Pitch { step: 5 * 7 + 2, alter: -1 }));
+        assert_eq!(lilypond_pitch("as,4"), Some(Pitch { step: 2 * 7 + 5, alter: -1 }));
+        assert_eq!(lilypond_pitch("fisis"), Some(Pitch { step: 3 * 7 + 3, alter: 2 }));
+        assert_eq!(lilypond_pitch("C"), None);
+        assert_eq!(chord_symbol("besm7b5"), "Bbm7b5");
+        assert_eq!(chord_symbol("Dm7"), "Dm7");
+        // The slash bass is written lowest, with the chord stacked above it
+        let pitches = chord_pitches("C/E", 4).unwrap();
+        assert_eq!(pitches.iter().map(|pitch| pitch.step).collect::<Vec<_>>(), vec![30, 35, 39]);
+    }
+
+    #[test]
+    fn test_chord_symbol_runs() {
+        let run = |text: &str, glyph, raised| SymbolRun { text: text.to_string(), glyph, raised };
+        let flat = CSYM_FLAT.to_string();
+        let sharp = CSYM_SHARP.to_string();
+        assert_eq!(
+            chord_symbol_runs("Bbm7b5/F#"),
+            vec![
+                run("B", false, false),
+                run(&flat, true, false),
+                run("m", false, false),
+                run("7", false, true),
+                run(&flat, true, true),
+                run("5", false, true),
+                run("/F", false, false),
+                run(&sharp, true, false),
+            ]
+        );
+        let diminished = CSYM_DIMINISHED.to_string();
+        let expected = vec![run("C", false, false), run(&diminished, true, false), run("7", false, true)];
+        assert_eq!(chord_symbol_runs("Cdim7"), expected);
+        assert_eq!(chord_symbol_runs("C6/9"), vec![run("C", false, false), run("6/9", false, true)]);
+        assert_eq!(chord_symbol_runs("Dsus4"), vec![run("Dsus", false, false), run("4", false, true)]);
+        assert_eq!(chord_symbol_runs("Eb"), vec![run("E", false, false), run(&flat, true, false)]);
+    }
+
+    #[test]
+    fn test_render_worksheet() {
+        let elements = vec![
+            element("chord-0", EditableElementType::Chord, 1, "Dm7", false),
+            element("chord-1", EditableElementType::Chord, 2, "ees", true),
+            element("note-2", EditableElementType::Note, 3, "fis'", false),
+            element("note-3", EditableElementType::Note, 4, "f'", false),
+        ];
+        let svg = render_worksheet(&worksheet(elements.clone(), "c")).unwrap();
+        assert!(svg.contains(r#"<g class="interactive-note" data-element-id="chord-0">"#));
+        assert!(svg.contains(r#">Dm<tspan font-size="11.2" baseline-shift="super">7</tspan></text>"#));
+        assert!(svg.contains(">Chords &amp; Notes</text>"));
+        // Four heads of Dm7 and the two notes; the hidden answer is a rest
+        assert_eq!(count(&svg, NOTEHEAD_BLACK), 6);
+        assert_eq!(count(&svg, REST_QUARTER), 1);
+        assert!(!svg.contains("chord-1"));
+        // F# needs a sharp and the F after it in the same measure a natural
+        assert_eq!((count(&svg, accidental_glyph(1)), count(&svg, accidental_glyph(0))), (1, 1));
+
+        // Shown answers take the answer color
+        let mut config = worksheet(elements.clone(), "d");
+        config.global_settings.show_answers = true;
+        config.global_settings.answer_color = Some("#c0392b".to_string());
+        let svg = render_worksheet(&config).unwrap();
+        // Dm7 needs naturals on F and C, and the F# after its F a sharp again
+        assert_eq!((count(&svg, accidental_glyph(1)), count(&svg, accidental_glyph(0))), (2 + 1, 3));
+        assert_eq!(count(&svg, accidental_glyph(-1)), 2);
+        assert!(svg.contains(r##"data-element-id="chord-1" fill="#c0392b""##));
+        assert!(svg.contains(&format!(r#">E<tspan font-family="Bravura">{}</tspan></text>"#, CSYM_FLAT)));
+
+        // Other blank styles replace the rest
+        let mut config = worksheet(elements, "c");
+        config.sections[0].layout.answer_blank = AnswerBlank::Box;
+        let svg = render_worksheet(&config).unwrap();
+        assert_eq!(count(&svg, REST_QUARTER), 0);
+        assert!(svg.contains(r#"fill="none" stroke="black""#));
+        config.sections[0].layout.answer_blank = AnswerBlank::Question;
+        assert!(render_worksheet(&config).unwrap().contains(">?</text>"));
+
+        // Right-to-left locales set the direction of the headings only
+        assert!(!svg.contains("direction="));
+        config.global_settings.locale = Some("he".to_string());
+        let svg = render_worksheet(&config).unwrap();
+        assert_eq!(svg.matches(r#" direction="rtl""#).count(), 1);
+        assert!(svg.contains(r#"font-weight="bold" direction="rtl">Chords &amp; Notes</text>"#));
+
+        assert!(supports(&WorksheetType::NoteIdentification) && !supports(&WorksheetType::RhythmExercise));
+    }
+
+    #[test]
+    fn test_progression_strip() {
+        let chords: Vec<String> = ["C", "Am", "Dm", "G7"].iter().map(|chord| chord.to_string()).collect();
+        let svg = render_progression_strip(&chords, &StripOptions::default()).unwrap();
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="1200" height="675""#));
+        assert!(svg.contains(r#">G<tspan font-size="14.0" baseline-shift="super">7</tspan></text>"#));
+        assert!(svg.contains(r#"transform="scale(2.500)""#));
+        assert_eq!(count(&svg, NOTEHEAD_BLACK), 0);
+        assert!(!svg.contains(WATERMARK));
+
+        let key = Some("C".to_string());
+        let options = StripOptions { format: StripFormat::Banner, key, staff: true, watermark: true };
+        let svg = render_progression_strip(&chords, &options).unwrap();
+        assert!(svg.contains(r#"width="1500" height="500""#));
+        assert!(svg.contains(">vi</text>") && svg.contains(">V7</text>") && svg.contains(WATERMARK));
+        assert_eq!(count(&svg, NOTEHEAD_BLACK), 3 + 3 + 3 + 4);
+        assert_eq!(count(&svg, G_CLEF), 1);
+
+        // Long progressions shrink to fit
+        let long: Vec<String> = chords.iter().cycle().take(24).cloned().collect();
+        assert!(!render_progression_strip(&long, &StripOptions::default()).unwrap().contains("scale(2.500)"));
+
+        assert!(render_progression_strip(&[], &StripOptions::default()).is_err());
+        assert!(render_progression_strip(&["H7".to_string()], &options).is_err());
+    }
+}
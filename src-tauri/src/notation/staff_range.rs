@@ -0,0 +1,118 @@
+// Staff range
+// Moves worksheet notes by octaves so they sit on their staff, marking any that still don't fit with an ottava
+
+use crate::types::worksheet::Clef;
+
+/// Octaves content may be moved in either direction
+const MAX_SHIFT: i32 = 4;
+
+/// Lowest and highest MIDI notes written on a clef's staff with up to three ledger lines
+/// (treble F3-E6, bass A1-G4; the grand staff spans both)
+pub fn staff_range(clef: &Clef) -> (u8, u8) {
+    match clef {
+        Clef::Treble => (53, 88),
+        Clef::Bass => (33, 67),
+        Clef::Both => (33, 88),
+    }
+}
+
+/// Where one note is written after fitting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FittedNote {
+    /// Written MIDI note
+    pub midi: u8,
+    /// Octaves the note is written away from the pitch given (the content's shift plus any ottava)
+    pub octaves: i8,
+    /// Octaves the note sounds above where it is written (below when negative), 0 when it fits the staff
+    pub ottava: i8,
+}
+
+/// Notes fitted to a staff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeFit {
+    /// Octaves all the content was moved (up when positive)
+    pub octaves: i8,
+    /// Every note in the order given; notes with an ottava still didn't fit after the move
+    pub notes: Vec<FittedNote>,
+}
+
+/// Move notes by whole octaves, all together, so the most of them sit on the clef's staff with the least
+/// movement; notes still off it are written inside the range under an ottava instead of on extra ledger lines
+pub fn fit_to_range(notes: &[u8], clef: &Clef) -> RangeFit {
+    let (low, high) = staff_range(clef);
+    let (low, high) = (low as i32, high as i32);
+    let outside =
+        |shift: i32| notes.iter().filter(|&&midi| !(low..=high).contains(&(midi as i32 + 12 * shift))).count();
+    let shift = (-MAX_SHIFT..=MAX_SHIFT).min_by_key(|&shift| (outside(shift), shift.abs())).unwrap_or(0);
+
+    let notes = notes
+        .iter()
+        .map(|&midi| {
+            let moved = midi as i32 + 12 * shift;
+            let ottava = if moved > high {
+                (moved - high + 11) / 12
+            } else if moved < low {
+                -((low - moved + 11) / 12)
+            } else {
+                0
+            };
+            let written = (moved - 12 * ottava).clamp(0, 127);
+            FittedNote { midi: written as u8, octaves: ((written - midi as i32) / 12) as i8, ottava: ottava as i8 }
+        })
+        .collect();
+    RangeFit { octaves: shift as i8, notes }
+}
+
+/// Mark written with a note under an ottava ("8va" sounds an octave higher, "15mb" two octaves lower)
+pub fn ottava_mark(ottava: i8) -> Option<&'static str> {
+    match ottava {
+        0 => None,
+        1 => Some("8va"),
+        2 => Some("15ma"),
+        n if n > 2 => Some("22ma"),
+        -1 => Some("8vb"),
+        -2 => Some("15mb"),
+        _ => Some("22mb"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_staff_range() {
+        assert_eq!(staff_range(&Clef::Treble), (53, 88));
+        assert_eq!(staff_range(&Clef::Bass), (33, 67));
+    }
+
+    #[test]
+    fn test_fit_moves_content_by_octaves() {
+        // A melody above the treble staff's ledger lines comes down as a whole, by the fewest octaves
+        let fit = fit_to_range(&[96, 98, 100, 91], &Clef::Treble);
+        assert_eq!(fit.octaves, -1);
+        assert_eq!(fit.notes.iter().map(|note| note.midi).collect::<Vec<_>>(), [84, 86, 88, 79]);
+        assert!(fit.notes.iter().all(|note| note.ottava == 0));
+
+        // Content that fits stays put
+        let fit = fit_to_range(&[60, 67, 72], &Clef::Treble);
+        assert_eq!(fit.octaves, 0);
+        assert_eq!(fit.notes[0], FittedNote { midi: 60, octaves: 0, ottava: 0 });
+
+        // Bass clef notes in the treble register come down
+        assert_eq!(fit_to_range(&[72, 76], &Clef::Bass).octaves, -1);
+    }
+
+    #[test]
+    fn test_notes_that_still_dont_fit_get_an_ottava() {
+        // Four octaves apart: the low note fits, the high one is written two octaves down
+        let fit = fit_to_range(&[55, 112], &Clef::Treble);
+        assert_eq!(fit.octaves, 0);
+        assert_eq!(fit.notes[0], FittedNote { midi: 55, octaves: 0, ottava: 0 });
+        assert_eq!(fit.notes[1], FittedNote { midi: 88, octaves: -2, ottava: 2 });
+        assert_eq!(ottava_mark(fit.notes[1].ottava), Some("15ma"));
+        assert_eq!(ottava_mark(-1), Some("8vb"));
+        assert_eq!(ottava_mark(0), None);
+        assert_eq!(fit_to_range(&[], &Clef::Bass).notes, []);
+    }
+}
@@ -0,0 +1,255 @@
+// Voicing notation
+// Writes voiced chords (the exact notes the voice-leading engine plays) onto a grand staff as LilyPond
+
+use crate::music::guide_tones::{GuideTone, GuideToneLines};
+use crate::music::types::AudioNote;
+
+/// Lowest written octave placed on the treble staff (middle C and up)
+const TREBLE_LOWEST_OCTAVE: i8 = 4;
+/// Guide-tone lines averaging below middle C are written in the bass clef
+const TREBLE_LOWEST_MIDI: u32 = 60;
+
+/// LilyPond note name for a pitch class name ("C#" -> "cis", "Bb" -> "bes", "F##" -> "fisis")
+pub fn lilypond_note_name(note: &str) -> String {
+    let mut chars = note.chars();
+    let letter = chars.next().map(|c| c.to_ascii_lowercase().to_string()).unwrap_or_default();
+    let accidentals: String = chars
+        .map(|c| match c {
+            '#' => "is",
+            'b' => "es",
+            _ => "",
+        })
+        .collect();
+    format!("{}{}", letter, accidentals)
+}
+
+/// Absolute LilyPond pitch for a written note (C3 = "c", middle C = "c'")
+pub fn lilypond_pitch(note: &AudioNote) -> String {
+    lilypond_absolute_pitch(&note.note, note.octave)
+}
+
+/// Absolute LilyPond pitch for a spelled note name in a written octave ("Ebb", 4 -> "eeses'")
+pub fn lilypond_absolute_pitch(note: &str, octave: i8) -> String {
+    let marks = match octave as i32 - 3 {
+        n if n > 0 => "'".repeat(n as usize),
+        n => ",".repeat((-n) as usize),
+    };
+    format!("{}{}", lilypond_note_name(note), marks)
+}
+
+/// Text as a quoted LilyPond string
+/// Quotes and backslashes are escaped and control characters become spaces, so no text can end the string
+/// early and run as markup or Scheme
+pub fn quoted(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_control() => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// LilyPond \key command for a key name ("Eb" -> major, "F#m" -> minor)
+pub fn key_command(key: &str) -> String {
+    match key.strip_suffix('m') {
+        Some(tonic) if !tonic.is_empty() => format!("\\key {} \\minor", lilypond_note_name(tonic)),
+        _ => format!("\\key {} \\major", lilypond_note_name(key)),
+    }
+}
+
+fn same_written_pitch(a: &AudioNote, b: &AudioNote) -> bool {
+    a.note == b.note && a.octave == b.octave
+}
+
+/// One staff's part of a chord: a chord, a single note, or a rest when the staff has no notes
+fn staff_chord(notes: &[&AudioNote], next: Option<&[AudioNote]>) -> String {
+    let pitches: Vec<String> = notes
+        .iter()
+        .map(|note| {
+            // Tie into the next chord when the voice-leading engine holds this note
+            let held = next.is_some_and(|next| next.iter().any(|n| n.is_common_tone && same_written_pitch(n, note)));
+            format!("{}{}", lilypond_pitch(note), if held { "~" } else { "" })
+        })
+        .collect();
+    match pitches.len() {
+        0 => "r1".to_string(),
+        1 => format!("{}1", pitches[0]),
+        _ => format!("<{}>1", pitches.join(" ")),
+    }
+}
+
+/// LilyPond document with voiced chords on a grand staff, one whole note per chord
+/// Notes from middle C up go on the treble staff; labels (chord symbols) are printed above each chord
+pub fn voicing_to_lilypond(chords: &[Vec<AudioNote>], key: Option<&str>, labels: &[String]) -> String {
+    let key = key.map(key_command).unwrap_or_default();
+    let mut upper = Vec::with_capacity(chords.len());
+    let mut lower = Vec::with_capacity(chords.len());
+
+    for (index, chord) in chords.iter().enumerate() {
+        let next = chords.get(index + 1).map(Vec::as_slice);
+        let (treble, bass): (Vec<&AudioNote>, Vec<&AudioNote>) =
+            chord.iter().partition(|note| note.octave >= TREBLE_LOWEST_OCTAVE);
+
+        let mut top = staff_chord(&treble, next);
+        if let Some(label) = labels.get(index).filter(|label| !label.is_empty()) {
+            top.push_str(&format!("^\\markup {{ {} }}", quoted(label)));
+        }
+        upper.push(top);
+        lower.push(staff_chord(&bass, next));
+    }
+
+    format!(
+        r#"\version "2.24.0"
+
+\paper {{
+  indent = 0\mm
+}}
+
+\header {{
+  tagline = ##f
+}}
+
+\score {{
+  \new PianoStaff <<
+    \new Staff = "upper" {{
+      \clef treble {}
+      \omit Staff.TimeSignature
+      {} \bar "|."
+    }}
+    \new Staff = "lower" {{
+      \clef bass {}
+      \omit Staff.TimeSignature
+      {} \bar "|."
+    }}
+  >>
+  \layout {{ }}
+}}
+"#,
+        key,
+        upper.join(" "),
+        key,
+        lower.join(" ")
+    )
+}
+
+/// One guide-tone voice, a whole note per chord tied across held notes
+/// Chord symbols are attached when given; beyond `shown` chords the notes are left as hidden rests to fill in
+fn guide_tone_voice(line: &[GuideTone], shown: usize, labels: bool) -> String {
+    let notes: Vec<String> = line
+        .iter()
+        .enumerate()
+        .map(|(index, tone)| {
+            let mut note = if index < shown {
+                let written =
+                    AudioNote { note: tone.note.clone(), octave: tone.octave, is_common_tone: false, cents: 0.0 };
+                let held = index + 1 < shown && line.get(index + 1).is_some_and(|next| next.midi == tone.midi);
+                format!("{}1{}", lilypond_pitch(&written), if held { "~" } else { "" })
+            } else {
+                "\\once \\hide Rest r1".to_string()
+            };
+            if labels {
+                note.push_str(&format!("^\\markup {{ {} }}", quoted(&tone.chord)));
+            }
+            note
+        })
+        .collect();
+    notes.join(" ")
+}
+
+/// LilyPond document with two guide-tone lines as voices on one staff, chord symbols above
+/// As an exercise only the first chord's guide tones are given
+pub fn guide_tones_to_lilypond(lines: &GuideToneLines, key: Option<&str>, exercise: bool) -> String {
+    let key = key.map(key_command).unwrap_or_default();
+    let shown = if exercise { 1 } else { lines.upper.len() };
+    let tones = lines.upper.iter().chain(&lines.lower);
+    let average = tones.clone().map(|tone| tone.midi as u32).sum::<u32>() / tones.count().max(1) as u32;
+    let clef = if average < TREBLE_LOWEST_MIDI { "bass" } else { "treble" };
+
+    format!(
+        r#"\version "2.24.0"
+
+\paper {{
+  indent = 0\mm
+}}
+
+\header {{
+  tagline = ##f
+}}
+
+\score {{
+  \new Staff <<
+    \clef {} {}
+    \omit Staff.TimeSignature
+    \new Voice = "upper" {{ \voiceOne {} \bar "|." }}
+    \new Voice = "lower" {{ \voiceTwo {} }}
+  >>
+  \layout {{ }}
+}}
+"#,
+        clef,
+        key,
+        guide_tone_voice(&lines.upper, shown, true),
+        guide_tone_voice(&lines.lower, shown, false)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::guide_tones::extract_guide_tones;
+
+    fn note(name: &str, octave: i8, is_common_tone: bool) -> AudioNote {
+        AudioNote { note: name.to_string(), octave, is_common_tone, cents: 0.0 }
+    }
+
+    #[test]
+    fn test_quoted() {
+        assert_eq!(quoted("Count up from C#"), r#""Count up from C#""#);
+        assert_eq!(quoted(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+        assert_eq!(quoted("one\ntwo\t\u{0}"), r#""one two  ""#);
+    }
+
+    #[test]
+    fn test_lilypond_pitch() {
+        assert_eq!(lilypond_pitch(&note("C", 4, false)), "c'");
+        assert_eq!(lilypond_pitch(&note("Bb", 2, false)), "bes,");
+        assert_eq!(lilypond_pitch(&note("F##", 5, false)), "fisis''");
+        assert_eq!(key_command("F#m"), "\\key fis \\minor");
+        assert_eq!(key_command("Eb"), "\\key ees \\major");
+    }
+
+    #[test]
+    fn test_voicing_splits_staves_and_ties_held_notes() {
+        let chords = vec![
+            vec![note("C", 3, false), note("E", 4, false), note("G", 4, false)],
+            vec![note("B", 2, false), note("D", 4, false), note("G", 4, true)],
+            vec![note("C", 3, false)],
+        ];
+        let document = voicing_to_lilypond(&chords, Some("C"), &["C".to_string(), "G/B".to_string()]);
+        assert!(document.contains("<e' g'~>1^\\markup { \"C\" } <d' g'>1^\\markup { \"G/B\" } r1"));
+        assert!(document.contains("c1 b,1 c1"));
+        assert!(document.contains("\\key c \\major"));
+    }
+
+    #[test]
+    fn test_guide_tones_as_two_voices() {
+        let progression: Vec<String> = ["Dm7", "G7", "Cmaj7"].iter().map(|chord| chord.to_string()).collect();
+        let lines = extract_guide_tones(&progression).unwrap();
+        let document = guide_tones_to_lilypond(&lines, Some("C"), false);
+        assert!(document.contains("\\clef bass \\key c \\major"));
+        assert!(document.contains(r#"\voiceOne c'1^\markup { "Dm7" } b1~^\markup { "G7" } b1^\markup { "Cmaj7" }"#));
+        assert!(document.contains(r#"\voiceTwo f1~ f1 e1 }"#));
+
+        // The exercise gives the first chord's notes and leaves the rest blank
+        let exercise = guide_tones_to_lilypond(&lines, None, true);
+        assert!(exercise.contains(r#"\voiceTwo f1 \once \hide Rest r1 \once \hide Rest r1 }"#));
+    }
+}
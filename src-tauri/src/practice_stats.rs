@@ -0,0 +1,347 @@
+// Practice statistics
+// Accuracy, timing and streaks per exercise type for progress dashboards and difficulty adaptation;
+// stored as JSON in the app data directory
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name of the statistics inside the app data directory
+pub const PRACTICE_STATS_NAME: &str = "practice_stats.json";
+
+/// Latest attempts kept per exercise type for recent accuracy; older ones only count in the totals
+pub const RECENT_ATTEMPTS: usize = 20;
+
+/// Recent attempts needed before a difficulty change is suggested
+const MIN_ATTEMPTS_FOR_SUGGESTION: usize = 10;
+
+/// Recent accuracy at or above which the exercise can get harder
+const HARDER_ACCURACY: f32 = 0.9;
+
+/// Recent accuracy below which the exercise should get easier
+const EASIER_ACCURACY: f32 = 0.6;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// One answered question, as reported by an exercise
+#[derive(Debug, Clone, Deserialize)]
+pub struct AttemptRecord {
+    /// Exercise type ("interval", "chord_quality", "progression", "quiz")
+    pub exercise: String,
+    pub correct: bool,
+    /// Time taken to answer, if the exercise measures it
+    pub response_ms: Option<u64>,
+    /// Minutes the student's local time is ahead of UTC, so practice days follow their calendar
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+}
+
+/// What the app can do with an exercise's difficulty given recent results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DifficultySuggestion {
+    Easier,
+    Keep,
+    Harder,
+}
+
+/// Progress in one exercise type
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExerciseProgress {
+    pub exercise: String,
+    pub attempts: u32,
+    pub correct: u32,
+    /// 0.0-1.0 over all attempts
+    pub accuracy: f32,
+    /// 0.0-1.0 over the last RECENT_ATTEMPTS attempts
+    pub recent_accuracy: f32,
+    /// Mean time of the timed attempts
+    pub average_response_ms: Option<u64>,
+    pub suggestion: DifficultySuggestion,
+}
+
+/// Progress across all exercise types
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProgressSummary {
+    pub exercises: Vec<ExerciseProgress>,
+    pub attempts: u32,
+    pub correct: u32,
+    pub accuracy: f32,
+    /// Days with at least one attempt
+    pub practice_days: u32,
+}
+
+/// Consecutive correct answers in one exercise type
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExerciseStreak {
+    pub exercise: String,
+    pub current: u32,
+    pub best: u32,
+}
+
+/// Practice-day and answer streaks
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Streaks {
+    /// Consecutive days practiced up to today, or up to yesterday if today has no practice yet
+    pub current_days: u32,
+    pub longest_days: u32,
+    pub practiced_today: bool,
+    pub exercises: Vec<ExerciseStreak>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ExerciseStats {
+    attempts: u32,
+    correct: u32,
+    timed: u32,
+    total_response_ms: u64,
+    streak: u32,
+    best_streak: u32,
+    /// Correctness of the latest attempts, oldest first
+    recent: VecDeque<bool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct StoredStats {
+    exercises: BTreeMap<String, ExerciseStats>,
+    /// Local days with practice, counted from the Unix epoch
+    practice_days: BTreeSet<i64>,
+}
+
+/// Statistics loaded from disk; save writes them back
+pub struct PracticeStats {
+    path: PathBuf,
+    stats: StoredStats,
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Local day of a timestamp, counted from the Unix epoch
+fn local_day(at_ms: i64, utc_offset_minutes: i32) -> i64 {
+    (at_ms + i64::from(utc_offset_minutes) * 60 * 1000).div_euclid(DAY_MS)
+}
+
+fn ratio(correct: u32, attempts: u32) -> f32 {
+    if attempts == 0 {
+        0.0
+    } else {
+        correct as f32 / attempts as f32
+    }
+}
+
+impl ExerciseStats {
+    fn progress(&self, exercise: &str) -> ExerciseProgress {
+        let recent_correct = self.recent.iter().filter(|correct| **correct).count() as u32;
+        let recent_accuracy = ratio(recent_correct, self.recent.len() as u32);
+        let suggestion = if self.recent.len() < MIN_ATTEMPTS_FOR_SUGGESTION {
+            DifficultySuggestion::Keep
+        } else if recent_accuracy >= HARDER_ACCURACY {
+            DifficultySuggestion::Harder
+        } else if recent_accuracy < EASIER_ACCURACY {
+            DifficultySuggestion::Easier
+        } else {
+            DifficultySuggestion::Keep
+        };
+        ExerciseProgress {
+            exercise: exercise.to_string(),
+            attempts: self.attempts,
+            correct: self.correct,
+            accuracy: ratio(self.correct, self.attempts),
+            recent_accuracy,
+            average_response_ms: (self.timed > 0).then(|| self.total_response_ms / u64::from(self.timed)),
+            suggestion,
+        }
+    }
+}
+
+impl PracticeStats {
+    /// Load statistics from a file, starting empty if it is missing or unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let stats = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| match serde_json::from_str(&json) {
+                Ok(stats) => Some(stats),
+                Err(e) => {
+                    println!("[practice stats] Ignoring unreadable statistics {:?}: {}", path, e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self { path, stats }
+    }
+
+    /// Statistics stored in the standard file inside the app data directory
+    pub fn in_dir(data_dir: &Path) -> Self {
+        Self::load(data_dir.join(PRACTICE_STATS_NAME))
+    }
+
+    /// Count an attempt made now; returns the exercise's progress after it
+    pub fn record(&mut self, attempt: &AttemptRecord) -> Result<ExerciseProgress, String> {
+        self.record_at(attempt, now_ms())
+    }
+
+    fn record_at(&mut self, attempt: &AttemptRecord, at_ms: i64) -> Result<ExerciseProgress, String> {
+        let exercise = attempt.exercise.trim();
+        if exercise.is_empty() {
+            return Err("Exercise type cannot be empty".to_string());
+        }
+
+        let stats = self.stats.exercises.entry(exercise.to_string()).or_default();
+        stats.attempts += 1;
+        if attempt.correct {
+            stats.correct += 1;
+            stats.streak += 1;
+            stats.best_streak = stats.best_streak.max(stats.streak);
+        } else {
+            stats.streak = 0;
+        }
+        if let Some(response_ms) = attempt.response_ms {
+            stats.timed += 1;
+            stats.total_response_ms += response_ms;
+        }
+        stats.recent.push_back(attempt.correct);
+        if stats.recent.len() > RECENT_ATTEMPTS {
+            stats.recent.pop_front();
+        }
+        let progress = stats.progress(exercise);
+
+        self.stats.practice_days.insert(local_day(at_ms, attempt.utc_offset_minutes));
+        Ok(progress)
+    }
+
+    /// Progress in every exercise type practiced, by name
+    pub fn summary(&self) -> ProgressSummary {
+        let exercises: Vec<ExerciseProgress> =
+            self.stats.exercises.iter().map(|(exercise, stats)| stats.progress(exercise)).collect();
+        let attempts = exercises.iter().map(|progress| progress.attempts).sum();
+        let correct = exercises.iter().map(|progress| progress.correct).sum();
+        ProgressSummary {
+            exercises,
+            attempts,
+            correct,
+            accuracy: ratio(correct, attempts),
+            practice_days: self.stats.practice_days.len() as u32,
+        }
+    }
+
+    /// Streaks as of now in the student's time zone
+    pub fn streaks(&self, utc_offset_minutes: i32) -> Streaks {
+        self.streaks_on(local_day(now_ms(), utc_offset_minutes))
+    }
+
+    fn streaks_on(&self, today: i64) -> Streaks {
+        let days = &self.stats.practice_days;
+        let mut longest_days = 0;
+        let mut run = 0;
+        let mut previous = None;
+        for day in days {
+            run = if previous == Some(day - 1) { run + 1 } else { 1 };
+            longest_days = longest_days.max(run);
+            previous = Some(*day);
+        }
+
+        let practiced_today = days.contains(&today);
+        let mut day = if practiced_today { today } else { today - 1 };
+        let mut current_days = 0;
+        while days.contains(&day) {
+            current_days += 1;
+            day -= 1;
+        }
+
+        let exercises = self
+            .stats
+            .exercises
+            .iter()
+            .map(|(exercise, stats)| ExerciseStreak {
+                exercise: exercise.clone(),
+                current: stats.streak,
+                best: stats.best_streak,
+            })
+            .collect();
+        Streaks { current_days, longest_days, practiced_today, exercises }
+    }
+
+    /// Write the statistics to disk atomically (temp file + rename)
+    pub fn save(&self) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create data directory: {}", e))?;
+        }
+
+        let json = serde_json::to_string(&self.stats)
+            .map_err(|e| format!("Failed to serialize practice statistics: {}", e))?;
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, json).map_err(|e| format!("Failed to write practice statistics: {}", e))?;
+        fs::rename(&temp_path, &self.path).map_err(|e| format!("Failed to save practice statistics: {}", e))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn attempt(exercise: &str, correct: bool, response_ms: Option<u64>) -> AttemptRecord {
+        AttemptRecord { exercise: exercise.to_string(), correct, response_ms, utc_offset_minutes: 0 }
+    }
+
+    #[test]
+    fn test_accuracy_timing_and_suggestions_persist() {
+        let dir = TempDir::new().unwrap();
+        let mut stats = PracticeStats::in_dir(dir.path());
+        for index in 0..RECENT_ATTEMPTS {
+            stats.record_at(&attempt("interval", index >= 8, Some(1000 + index as u64 * 100)), 0).unwrap();
+        }
+        stats.record_at(&attempt("chord_quality", true, None), 0).unwrap();
+        assert!(stats.record_at(&attempt(" ", true, None), 0).is_err());
+
+        let interval = stats.summary().exercises[1].clone();
+        assert_eq!((interval.attempts, interval.correct), (20, 12));
+        assert_eq!(interval.average_response_ms, Some(1950));
+        assert_eq!(interval.suggestion, DifficultySuggestion::Keep);
+
+        // Recent accuracy forgets the early misses and eventually suggests a harder setting
+        let progress = (0..8).map(|_| stats.record_at(&attempt("interval", true, None), 0).unwrap()).last().unwrap();
+        assert_eq!(progress.recent_accuracy, 1.0);
+        assert_eq!(progress.accuracy, 20.0 / 28.0);
+        assert_eq!(progress.suggestion, DifficultySuggestion::Harder);
+        let missed = (0..9).map(|_| stats.record_at(&attempt("interval", false, None), 0).unwrap()).last().unwrap();
+        assert_eq!(missed.suggestion, DifficultySuggestion::Easier);
+
+        stats.save().unwrap();
+        let summary = PracticeStats::in_dir(dir.path()).summary();
+        assert_eq!(summary, stats.summary());
+        assert_eq!((summary.attempts, summary.correct), (38, 21));
+        assert_eq!(summary.exercises[0].exercise, "chord_quality");
+    }
+
+    #[test]
+    fn test_day_and_answer_streaks() {
+        let dir = TempDir::new().unwrap();
+        let mut stats = PracticeStats::in_dir(dir.path());
+        // Days 10-12 and 14-15; late evening of day 15 in UTC is day 16 two hours ahead
+        for day in [10, 11, 12, 14, 15] {
+            stats.record_at(&attempt("quiz", true, None), day * DAY_MS + 12 * 3_600_000).unwrap();
+        }
+        let late = AttemptRecord { utc_offset_minutes: 120, ..attempt("quiz", false, None) };
+        stats.record_at(&late, 15 * DAY_MS + 23 * 3_600_000).unwrap();
+        stats.record_at(&attempt("quiz", true, None), 16 * DAY_MS).unwrap();
+
+        let streaks = stats.streaks_on(17);
+        assert_eq!((streaks.current_days, streaks.longest_days, streaks.practiced_today), (3, 3, false));
+        assert_eq!(streaks.exercises, [ExerciseStreak { exercise: "quiz".to_string(), current: 1, best: 5 }]);
+        assert!(stats.streaks_on(16).practiced_today);
+        assert_eq!(stats.streaks_on(18).current_days, 0);
+        assert_eq!(stats.summary().practice_days, 6);
+    }
+}
@@ -0,0 +1,180 @@
+// Recently opened projects
+// Backs File → Open Recent; stored as JSON in the app config directory next to the settings
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name of the recent files list inside the app config directory
+pub const RECENT_FILES_NAME: &str = "recent_files.json";
+
+/// Unpinned projects kept; pinned ones are kept whatever their number
+pub const MAX_RECENT_PROJECTS: usize = 10;
+
+/// One project in the list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentProject {
+    pub path: String,
+    /// Name shown in the menu
+    pub name: String,
+    /// Unix timestamp (milliseconds) of the last time it was opened or saved
+    pub opened_at: u64,
+    pub pinned: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct RecentFiles {
+    /// Most recent first
+    projects: Vec<RecentProject>,
+}
+
+/// Recent files loaded from disk, written back after every change
+pub struct RecentFilesStore {
+    path: PathBuf,
+    recent: RecentFiles,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Same file, however the path was written ("a/./b.maestro", "a/b.maestro")
+fn same_file(a: &str, b: &str) -> bool {
+    Path::new(a) == Path::new(b)
+}
+
+impl RecentFilesStore {
+    /// Load the list from a file, starting empty if it is missing or unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let recent = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| match serde_json::from_str(&json) {
+                Ok(recent) => Some(recent),
+                Err(e) => {
+                    println!("[recent files] Ignoring unreadable recent files list {:?}: {}", path, e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self { path, recent }
+    }
+
+    /// Load the list from the standard file inside a config directory
+    pub fn load_from_dir(config_dir: &Path) -> Self {
+        Self::load(config_dir.join(RECENT_FILES_NAME))
+    }
+
+    /// Pinned projects first, then the rest, each most recent first
+    pub fn projects(&self) -> Vec<RecentProject> {
+        let (mut pinned, rest): (Vec<_>, Vec<_>) = self.recent.projects.iter().cloned().partition(|p| p.pinned);
+        pinned.extend(rest);
+        pinned
+    }
+
+    /// Move a project to the top of the list, adding it if it is new
+    /// Without a name, the file name is shown; the oldest unpinned projects past the limit are dropped
+    pub fn add(&mut self, path: &str, name: Option<&str>) -> Result<Vec<RecentProject>, String> {
+        let path = path.trim();
+        if path.is_empty() {
+            return Err("Project path cannot be empty".to_string());
+        }
+        let existing = self.recent.projects.iter().position(|p| same_file(&p.path, path));
+        let pinned = existing.is_some_and(|index| self.recent.projects.remove(index).pinned);
+        let name = name
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .or_else(|| Path::new(path).file_stem().map(|stem| stem.to_string_lossy().to_string()))
+            .unwrap_or_else(|| path.to_string());
+        self.recent.projects.insert(0, RecentProject { path: path.to_string(), name, opened_at: now_ms(), pinned });
+
+        let mut unpinned = 0;
+        self.recent.projects.retain(|p| {
+            unpinned += usize::from(!p.pinned);
+            p.pinned || unpinned <= MAX_RECENT_PROJECTS
+        });
+        self.save()?;
+        Ok(self.projects())
+    }
+
+    /// Pin a project to the top of the list, or unpin it
+    pub fn pin(&mut self, path: &str, pinned: bool) -> Result<Vec<RecentProject>, String> {
+        let project = self
+            .recent
+            .projects
+            .iter_mut()
+            .find(|p| same_file(&p.path, path.trim()))
+            .ok_or_else(|| format!("{} is not a recent project", path))?;
+        project.pinned = pinned;
+        self.save()?;
+        Ok(self.projects())
+    }
+
+    /// Write the list to disk atomically (temp file + rename)
+    fn save(&self) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+
+        let json = serde_json::to_string_pretty(&self.recent)
+            .map_err(|e| format!("Failed to serialize recent files: {}", e))?;
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, json).map_err(|e| format!("Failed to write recent files: {}", e))?;
+        fs::rename(&temp_path, &self.path).map_err(|e| format!("Failed to save recent files: {}", e))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn paths(projects: &[RecentProject]) -> Vec<&str> {
+        projects.iter().map(|p| p.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_add_dedupes_and_persists() {
+        let dir = TempDir::new().unwrap();
+        let mut store = RecentFilesStore::load_from_dir(dir.path());
+        store.add("/songs/blues.maestro", None).unwrap();
+        store.add("/songs/ballad.maestro", Some("Ballad")).unwrap();
+        let projects = store.add("/songs/./blues.maestro", None).unwrap();
+        assert_eq!(paths(&projects), ["/songs/./blues.maestro", "/songs/ballad.maestro"]);
+        assert_eq!(projects[0].name, "blues");
+        assert!(store.add(" ", None).is_err());
+
+        let reloaded = RecentFilesStore::load_from_dir(dir.path());
+        assert_eq!(reloaded.projects(), projects);
+    }
+
+    #[test]
+    fn test_pinned_projects_stay_on_top_and_past_the_limit() {
+        let dir = TempDir::new().unwrap();
+        let mut store = RecentFilesStore::load_from_dir(dir.path());
+        store.add("/pinned.maestro", None).unwrap();
+        store.pin("/pinned.maestro", true).unwrap();
+        for index in 0..MAX_RECENT_PROJECTS + 2 {
+            store.add(&format!("/{}.maestro", index), None).unwrap();
+        }
+
+        let projects = store.projects();
+        assert_eq!(projects.len(), MAX_RECENT_PROJECTS + 1);
+        assert_eq!(projects[0].path, "/pinned.maestro");
+        assert_eq!(projects[1].path, format!("/{}.maestro", MAX_RECENT_PROJECTS + 1));
+        assert!(!paths(&projects).contains(&"/0.maestro"));
+
+        // Reopening keeps the pin; unpinning returns it to its place by date
+        assert!(store.add("/pinned.maestro", None).unwrap()[0].pinned);
+        let projects = store.pin("/pinned.maestro", false).unwrap();
+        assert!(projects.iter().all(|p| !p.pinned));
+        assert!(store.pin("/unknown.maestro", true).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+// Autosaved render history
+// Keeps the most recent successfully rendered worksheets (SVG + config) on disk for restoring
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::types::versioned;
+use crate::types::worksheet::WorksheetConfig;
+
+/// Directory name of the history inside the app data directory
+pub const RENDER_HISTORY_DIR: &str = "render-history";
+
+/// Number of renders kept
+pub const MAX_RENDER_HISTORY: usize = 20;
+
+/// Summary of one saved render
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenderHistoryEntry {
+    pub id: String,
+    pub title: String,
+    /// Unix timestamp (milliseconds)
+    pub rendered_at: u64,
+}
+
+/// Metadata file contents: summary plus the config that produced the render
+/// The config is kept in its versioned envelope and only parsed on restore
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredRender {
+    entry: RenderHistoryEntry,
+    config: serde_json::Value,
+}
+
+/// A render restored from history
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoredRender {
+    pub entry: RenderHistoryEntry,
+    pub config: WorksheetConfig,
+    pub svg_content: String,
+}
+
+/// On-disk history, one metadata file and one SVG per render
+pub struct RenderHistory {
+    dir: PathBuf,
+    max_entries: usize,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Ids are generated by us; reject anything that could escape the history directory
+fn check_id(id: &str) -> Result<(), String> {
+    if Uuid::parse_str(id).is_ok() {
+        Ok(())
+    } else {
+        Err(format!("Invalid render history id: {}", id))
+    }
+}
+
+impl RenderHistory {
+    pub fn new(dir: PathBuf, max_entries: usize) -> Self {
+        Self { dir, max_entries }
+    }
+
+    /// History stored in the standard directory inside the app data directory
+    pub fn in_dir(data_dir: &Path) -> Self {
+        Self::new(data_dir.join(RENDER_HISTORY_DIR), MAX_RENDER_HISTORY)
+    }
+
+    fn meta_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    fn svg_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.svg", id))
+    }
+
+    /// Save a finished render, dropping the oldest entries beyond the limit
+    pub fn record(&self, config: &WorksheetConfig, svg_content: &str) -> Result<RenderHistoryEntry, String> {
+        fs::create_dir_all(&self.dir).map_err(|e| format!("Failed to create render history directory: {}", e))?;
+
+        let entry = RenderHistoryEntry {
+            id: Uuid::new_v4().to_string(),
+            title: config.title.clone(),
+            rendered_at: now_ms(),
+        };
+        let stored = StoredRender { entry: entry.clone(), config: versioned::to_value(config)? };
+        let json = serde_json::to_string_pretty(&stored).map_err(|e| format!("Failed to serialize render: {}", e))?;
+
+        // SVG first so a listed entry always has its content
+        fs::write(self.svg_path(&entry.id), svg_content).map_err(|e| format!("Failed to save render: {}", e))?;
+        fs::write(self.meta_path(&entry.id), json).map_err(|e| format!("Failed to save render: {}", e))?;
+
+        self.prune()?;
+        Ok(entry)
+    }
+
+    fn stored(&self) -> Vec<StoredRender> {
+        let Ok(dir) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+
+        let mut renders: Vec<StoredRender> = dir
+            .flatten()
+            .map(|f| f.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .filter_map(|json| serde_json::from_str(&json).ok())
+            .collect();
+        renders.sort_by_key(|r: &StoredRender| std::cmp::Reverse(r.entry.rendered_at));
+        renders
+    }
+
+    /// Saved renders, newest first
+    pub fn list(&self) -> Vec<RenderHistoryEntry> {
+        self.stored().into_iter().map(|s| s.entry).collect()
+    }
+
+    /// Load a saved render with its config and SVG
+    pub fn restore(&self, id: &str) -> Result<RestoredRender, String> {
+        check_id(id)?;
+        let json = fs::read_to_string(self.meta_path(id)).map_err(|_| format!("Render {} not found in history", id))?;
+        let stored: StoredRender =
+            serde_json::from_str(&json).map_err(|e| format!("Failed to read render {}: {}", id, e))?;
+        let svg_content = fs::read_to_string(self.svg_path(id)).map_err(|e| format!("Failed to read render {}: {}", id, e))?;
+        let config = versioned::from_value::<WorksheetConfig>(stored.config)?;
+
+        // Upgrade older saves in place so they only migrate once
+        if config.upgraded_from.is_some() {
+            let upgraded = StoredRender { entry: stored.entry.clone(), config: versioned::to_value(&config.value)? };
+            let json =
+                serde_json::to_string_pretty(&upgraded).map_err(|e| format!("Failed to serialize render: {}", e))?;
+            fs::write(self.meta_path(id), json).map_err(|e| format!("Failed to save render: {}", e))?;
+        }
+
+        Ok(RestoredRender { entry: stored.entry, config: config.value, svg_content })
+    }
+
+    fn prune(&self) -> Result<(), String> {
+        for old in self.stored().into_iter().skip(self.max_entries) {
+            let _ = fs::remove_file(self.svg_path(&old.entry.id));
+            fs::remove_file(self.meta_path(&old.entry.id)).map_err(|e| format!("Failed to prune render history: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::worksheet::*;
+    use tempfile::TempDir;
+
+    fn config(title: &str) -> WorksheetConfig {
+        WorksheetConfig {
+            id: "w".to_string(),
+            title: title.to_string(),
+            subtitle: None,
+            worksheet_type: WorksheetType::ChordNaming,
+            sections: Vec::new(),
+            global_settings: WorksheetGlobalSettings {
+                paper_size: PaperSize::Letter,
+                orientation: Orientation::Portrait,
+                show_answers: false,
+                answer_color: None,
+                font_size: 14,
+                locale: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_record_and_restore() {
+        let dir = TempDir::new().unwrap();
+        let history = RenderHistory::in_dir(dir.path());
+
+        let entry = history.record(&config("Triads"), "<svg/>").unwrap();
+        let restored = history.restore(&entry.id).unwrap();
+
+        assert_eq!(restored.entry, entry);
+        assert_eq!(restored.config.title, "Triads");
+        assert_eq!(restored.svg_content, "<svg/>");
+    }
+
+    #[test]
+    fn test_keeps_newest_entries() {
+        let dir = TempDir::new().unwrap();
+        let history = RenderHistory::new(dir.path().to_path_buf(), 2);
+
+        for title in ["one", "two", "three"] {
+            history.record(&config(title), "<svg/>").unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        let titles: Vec<String> = history.list().into_iter().map(|e| e.title).collect();
+        assert_eq!(titles, vec!["three", "two"]);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 4);
+    }
+
+    #[test]
+    fn test_restore_upgrades_unversioned_config_in_place() {
+        let dir = TempDir::new().unwrap();
+        let history = RenderHistory::in_dir(dir.path());
+        let entry = history.record(&config("Legacy"), "<svg/>").unwrap();
+
+        // Saves from before versioning stored the bare config
+        let legacy = serde_json::json!({ "entry": entry, "config": config("Legacy") });
+        fs::write(history.meta_path(&entry.id), legacy.to_string()).unwrap();
+
+        assert_eq!(history.restore(&entry.id).unwrap().config.title, "Legacy");
+        let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(history.meta_path(&entry.id)).unwrap()).unwrap();
+        assert_eq!(saved["config"]["version"], 1);
+        assert_eq!(saved["config"]["data"]["title"], "Legacy");
+    }
+
+    #[test]
+    fn test_restore_rejects_unknown_and_unsafe_ids() {
+        let dir = TempDir::new().unwrap();
+        let history = RenderHistory::in_dir(dir.path());
+        assert!(history.restore("../settings").is_err());
+        assert!(history.restore(&Uuid::new_v4().to_string()).is_err());
+    }
+}
@@ -0,0 +1,7 @@
+mod policy;
+mod store;
+mod vocabulary;
+
+pub use policy::{Feature, Policy};
+pub use store::{Settings, SettingsStore, SETTINGS_FILE_NAME};
+pub use vocabulary::ChordVocabulary;
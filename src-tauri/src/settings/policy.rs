@@ -0,0 +1,109 @@
+// Deployment policy for shared machines
+// An administrator-provided policy file can disable features that write files or change settings
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// File name of the policy inside the app config directory
+/// Administrators should make it read-only for student accounts
+pub const POLICY_FILE_NAME: &str = "policy.json";
+
+/// Features a policy can disable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    /// Writing PDFs, images and data exports to user-chosen paths
+    Export,
+    /// Changing persisted settings (favorites, aliases, recent chords)
+    Settings,
+    /// Enabling, exporting or clearing the research analytics log
+    Analytics,
+}
+
+impl Feature {
+    pub fn description(self) -> &'static str {
+        match self {
+            Feature::Export => "Exporting files",
+            Feature::Settings => "Changing settings",
+            Feature::Analytics => "Changing analytics",
+        }
+    }
+}
+
+/// Features disabled on this machine
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    /// Kiosk mode: disables every feature that writes to disk
+    pub read_only: bool,
+    pub disabled_features: Vec<Feature>,
+}
+
+impl Policy {
+    /// Load a policy file; no file means everything is allowed
+    /// A policy that exists but can't be read falls back to read-only rather than unlocking the app
+    pub fn load(path: &Path) -> Self {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                println!("[policy] Failed to read {:?}, using read-only mode: {}", path, e);
+                return Self::locked();
+            }
+        };
+
+        serde_json::from_str(&json).unwrap_or_else(|e| {
+            println!("[policy] Invalid policy {:?}, using read-only mode: {}", path, e);
+            Self::locked()
+        })
+    }
+
+    /// Load the policy from the standard file inside a config directory
+    pub fn load_from_dir(config_dir: &Path) -> Self {
+        Self::load(&config_dir.join(POLICY_FILE_NAME))
+    }
+
+    fn locked() -> Self {
+        Self { read_only: true, disabled_features: Vec::new() }
+    }
+
+    pub fn allows(&self, feature: Feature) -> bool {
+        !self.read_only && !self.disabled_features.contains(&feature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_policy_allows_everything() {
+        let dir = TempDir::new().unwrap();
+        let policy = Policy::load_from_dir(dir.path());
+        assert!(policy.allows(Feature::Export));
+        assert!(policy.allows(Feature::Settings));
+    }
+
+    #[test]
+    fn test_disabled_features() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(POLICY_FILE_NAME), r#"{"disabled_features": ["export"]}"#).unwrap();
+
+        let policy = Policy::load_from_dir(dir.path());
+        assert!(!policy.allows(Feature::Export));
+        assert!(policy.allows(Feature::Settings));
+    }
+
+    #[test]
+    fn test_read_only_and_invalid_policies_lock_everything() {
+        let read_only = Policy { read_only: true, disabled_features: Vec::new() };
+        assert!(!read_only.allows(Feature::Settings));
+
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(POLICY_FILE_NAME), "{ broken").unwrap();
+        let policy = Policy::load_from_dir(dir.path());
+        assert!(!policy.allows(Feature::Analytics));
+    }
+}
@@ -0,0 +1,129 @@
+// Persistent user settings
+// Stored as JSON in the app config directory; unknown or missing fields fall back to defaults
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::vocabulary::ChordVocabulary;
+use crate::music::aliases::AliasDictionary;
+use crate::music::tiers::TierThresholds;
+use crate::training::quiz::HighScore;
+
+/// File name of the settings store inside the app config directory
+pub const SETTINGS_FILE_NAME: &str = "settings.json";
+
+/// All persisted user settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Recently used and favorited chords/qualities
+    pub vocabulary: ChordVocabulary,
+    /// User chord suffix aliases merged with the built-in tables
+    pub aliases: AliasDictionary,
+    /// Name-that-chord quiz high scores (best first)
+    pub quiz_high_scores: Vec<HighScore>,
+    /// Opt-in local analytics log for research studies
+    pub analytics_enabled: bool,
+    /// Replace impractical generated spellings (B#, Fbb) with simpler enharmonics;
+    /// off means strict pedagogical spelling
+    pub simplify_spellings: bool,
+    /// Seconds a LilyPond render may run before it is stopped (None = the built-in default)
+    pub render_timeout_secs: Option<u64>,
+    /// Advanced audio: voices of earlier chords left ringing before the oldest are cut (None = the built-in default)
+    pub voice_limit: Option<usize>,
+    /// Transition probabilities separating Safe, Colorful and Bold chord blocks
+    pub tier_thresholds: TierThresholds,
+}
+
+/// Settings loaded from disk, written back after every change
+pub struct SettingsStore {
+    path: PathBuf,
+    settings: Settings,
+}
+
+impl SettingsStore {
+    /// Load settings from a file, starting fresh if it is missing or unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let settings = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| match serde_json::from_str(&json) {
+                Ok(settings) => Some(settings),
+                Err(e) => {
+                    println!("[settings] Ignoring unreadable settings file {:?}: {}", path, e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self { path, settings }
+    }
+
+    /// Check that a settings file can be read back: false when there is no file yet,
+    /// an error when load would ignore it and start fresh
+    pub fn verify(path: &Path) -> Result<bool, String> {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(format!("Failed to read settings: {}", e)),
+        };
+        serde_json::from_str::<Settings>(&json).map_err(|e| format!("Invalid settings file: {}", e))?;
+        Ok(true)
+    }
+
+    /// Load settings from the standard file inside a config directory
+    pub fn load_from_dir(config_dir: &Path) -> Self {
+        Self::load(config_dir.join(SETTINGS_FILE_NAME))
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Modify settings and persist the result
+    pub fn update<T>(&mut self, change: impl FnOnce(&mut Settings) -> T) -> Result<T, String> {
+        let result = change(&mut self.settings);
+        self.save()?;
+        Ok(result)
+    }
+
+    /// Write settings to disk atomically (temp file + rename)
+    pub fn save(&self) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create settings directory: {}", e))?;
+        }
+
+        let json = serde_json::to_string_pretty(&self.settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, json).map_err(|e| format!("Failed to write settings: {}", e))?;
+        fs::rename(&temp_path, &self.path).map_err(|e| format!("Failed to save settings: {}", e))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let mut store = SettingsStore::load_from_dir(dir.path());
+        store.update(|s| s.vocabulary.record_chord("Dm7")).unwrap();
+
+        let reloaded = SettingsStore::load_from_dir(dir.path());
+        assert_eq!(reloaded.settings().vocabulary.recent_chords, vec!["Dm7"]);
+    }
+
+    #[test]
+    fn test_corrupt_file_falls_back_to_defaults() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(SETTINGS_FILE_NAME), "{ not json").unwrap();
+
+        let store = SettingsStore::load_from_dir(dir.path());
+        assert!(store.settings().vocabulary.recent_chords.is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+// Recently used and favorite chords/qualities
+// Lets the palette surface the user's working vocabulary across sessions
+
+use serde::{Deserialize, Serialize};
+
+use crate::music::chords::parse_chord;
+
+/// Maximum number of recent chords and qualities kept
+pub const MAX_RECENT: usize = 24;
+
+/// User's chord vocabulary (most recent first)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChordVocabulary {
+    pub recent_chords: Vec<String>,
+    pub recent_qualities: Vec<String>,
+    pub favorite_chords: Vec<String>,
+    pub favorite_qualities: Vec<String>,
+}
+
+/// Move a value to the front of a most-recent-first list, capping its length
+fn push_recent(list: &mut Vec<String>, value: &str) {
+    list.retain(|existing| existing != value);
+    list.insert(0, value.to_string());
+    list.truncate(MAX_RECENT);
+}
+
+/// Add or remove a value from a favorites list (keeps insertion order)
+fn set_membership(list: &mut Vec<String>, value: &str, present: bool) {
+    let exists = list.iter().any(|existing| existing == value);
+    if present && !exists {
+        list.push(value.to_string());
+    } else if !present {
+        list.retain(|existing| existing != value);
+    }
+}
+
+impl ChordVocabulary {
+    /// Record that a chord was used; its quality is recorded too
+    pub fn record_chord(&mut self, chord: &str) {
+        let chord = chord.trim();
+        if chord.is_empty() {
+            return;
+        }
+
+        push_recent(&mut self.recent_chords, chord);
+        if let Ok(parsed) = parse_chord(chord) {
+            push_recent(&mut self.recent_qualities, &parsed.suffix);
+        }
+    }
+
+    pub fn set_favorite_chord(&mut self, chord: &str, favorite: bool) {
+        set_membership(&mut self.favorite_chords, chord.trim(), favorite);
+    }
+
+    pub fn set_favorite_quality(&mut self, quality: &str, favorite: bool) {
+        set_membership(&mut self.favorite_qualities, quality.trim(), favorite);
+    }
+
+    /// Clear recent history, keeping favorites
+    pub fn clear_recent(&mut self) {
+        self.recent_chords.clear();
+        self.recent_qualities.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_is_most_recent_first_without_duplicates() {
+        let mut vocab = ChordVocabulary::default();
+        vocab.record_chord("C");
+        vocab.record_chord("Am7");
+        vocab.record_chord("C");
+
+        assert_eq!(vocab.recent_chords, vec!["C", "Am7"]);
+        assert_eq!(vocab.recent_qualities, vec!["", "m7"]);
+    }
+
+    #[test]
+    fn test_recent_is_capped() {
+        let mut vocab = ChordVocabulary::default();
+        for i in 0..(MAX_RECENT + 5) {
+            vocab.record_chord(&format!("C{}", i));
+        }
+        assert_eq!(vocab.recent_chords.len(), MAX_RECENT);
+    }
+
+    #[test]
+    fn test_pin_and_unpin_favorites() {
+        let mut vocab = ChordVocabulary::default();
+        vocab.set_favorite_chord("Fmaj7", true);
+        vocab.set_favorite_chord("Fmaj7", true);
+        vocab.set_favorite_quality("m7b5", true);
+        assert_eq!(vocab.favorite_chords, vec!["Fmaj7"]);
+
+        vocab.set_favorite_chord("Fmaj7", false);
+        assert!(vocab.favorite_chords.is_empty());
+        assert_eq!(vocab.favorite_qualities, vec!["m7b5"]);
+    }
+}
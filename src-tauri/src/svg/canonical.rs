@@ -0,0 +1,221 @@
+// SVG canonicalization
+// Rewrites an SVG into a form that depends only on what it draws, so the same music rendered by
+// different LilyPond runs or platforms compares (and caches) byte for byte: comments, metadata and
+// point-and-click links (which carry versions, dates and temporary paths) are dropped, attributes
+// sorted, coordinates rounded, and whitespace between tags removed
+
+use quick_xml::events::{BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use std::io::Cursor;
+
+/// Decimal places coordinates are rounded to
+const COORDINATE_PRECISION: i32 = 3;
+
+/// Attributes holding lengths, coordinates or path data
+const GEOMETRY_ATTRIBUTES: &[&str] = &[
+    "x", "y", "x1", "y1", "x2", "y2", "cx", "cy", "r", "rx", "ry", "width", "height", "d", "points", "transform",
+    "viewBox", "stroke-width", "font-size",
+];
+
+/// Elements dropped along with their content
+const DROPPED_ELEMENTS: &[&[u8]] = &[b"metadata"];
+
+/// Elements whose whitespace is part of the content
+const TEXT_ELEMENTS: &[&[u8]] = &[b"text", b"tspan"];
+
+/// Link scheme of LilyPond's point-and-click, which points into the temporary input file
+const POINT_AND_CLICK_SCHEME: &str = "textedit:";
+
+fn format_number(value: f64) -> String {
+    let scale = 10f64.powi(COORDINATE_PRECISION);
+    let rounded = (value * scale).round() / scale;
+    // Rounding can leave -0, which would print differently from 0
+    if rounded == 0.0 {
+        "0".to_string()
+    } else {
+        rounded.to_string()
+    }
+}
+
+/// Length of the number at the start of `bytes` (sign, digits, fraction, exponent), if there is one
+fn number_length(bytes: &[u8]) -> Option<usize> {
+    let digits = |from: usize| bytes[from..].iter().take_while(|b| b.is_ascii_digit()).count();
+    let mut end = usize::from(matches!(bytes.first(), Some(b'-' | b'+')));
+    let whole = digits(end);
+    end += whole;
+    let mut fraction = 0;
+    if bytes.get(end) == Some(&b'.') {
+        fraction = digits(end + 1);
+        if fraction > 0 {
+            end += 1 + fraction;
+        }
+    }
+    if whole == 0 && fraction == 0 {
+        return None;
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(end + 1), Some(b'-' | b'+')));
+        let exponent = digits(end + 1 + sign);
+        if exponent > 0 {
+            end += 1 + sign + exponent;
+        }
+    }
+    Some(end)
+}
+
+/// Round every number in an attribute value, leaving separators, commands and units as they are
+/// Numbers that ran together in shorthand (".5.5") are separated, since "0.5" can't start with its dot
+fn round_numbers(value: &str) -> String {
+    let mut rounded = String::with_capacity(value.len());
+    let mut index = 0;
+    while index < value.len() {
+        let rest = &value[index..];
+        match number_length(rest.as_bytes()).and_then(|length| Some((length, rest[..length].parse().ok()?))) {
+            Some((length, number)) => {
+                if !rest.starts_with(['-', '+']) && rounded.ends_with(|c: char| c.is_ascii_digit() || c == '.') {
+                    rounded.push(' ');
+                }
+                rounded.push_str(&format_number(number));
+                index += length;
+            }
+            None => {
+                let c = rest.chars().next().unwrap_or_default();
+                rounded.push(c);
+                index += c.len_utf8();
+            }
+        }
+    }
+    rounded
+}
+
+/// Start tag with point-and-click links dropped, geometry rounded and attributes sorted
+fn canonical_element(element: &BytesStart) -> Result<BytesStart<'static>, String> {
+    let mut attributes: Vec<(String, String)> = Vec::new();
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(|e| format!("Invalid SVG attribute: {}", e))?;
+        let key = String::from_utf8_lossy(attribute.key.as_ref()).to_string();
+        let value = attribute
+            .unescape_value()
+            .map_err(|e| format!("Invalid SVG attribute: {}", e))?
+            .to_string();
+        if value.starts_with(POINT_AND_CLICK_SCHEME) {
+            continue;
+        }
+        let value = if GEOMETRY_ATTRIBUTES.contains(&key.as_str()) { round_numbers(&value) } else { value };
+        attributes.push((key, value));
+    }
+    attributes.sort();
+
+    let mut canonical = BytesStart::new(String::from_utf8_lossy(element.name().as_ref()).to_string());
+    for (key, value) in &attributes {
+        canonical.push_attribute((key.as_str(), value.as_str()));
+    }
+    Ok(canonical)
+}
+
+/// Canonical form of an SVG document
+pub fn canonicalize_svg(svg: &str) -> Result<String, String> {
+    let mut reader = Reader::from_str(svg);
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    // Open elements inside a dropped element, and inside text elements
+    let mut dropped_depth = 0usize;
+    let mut text_depth = 0usize;
+
+    loop {
+        let event = reader.read_event().map_err(|e| format!("Invalid SVG: {}", e))?;
+        let event = match event {
+            Event::Eof => break,
+            Event::Start(element) => {
+                if dropped_depth > 0 || DROPPED_ELEMENTS.contains(&element.name().as_ref()) {
+                    dropped_depth += 1;
+                    continue;
+                }
+                if TEXT_ELEMENTS.contains(&element.name().as_ref()) {
+                    text_depth += 1;
+                }
+                Event::Start(canonical_element(&element)?)
+            }
+            Event::End(element) => {
+                if dropped_depth > 0 {
+                    dropped_depth -= 1;
+                    continue;
+                }
+                if TEXT_ELEMENTS.contains(&element.name().as_ref()) {
+                    text_depth = text_depth.saturating_sub(1);
+                }
+                Event::End(element)
+            }
+            Event::Empty(element) => {
+                if dropped_depth > 0 || DROPPED_ELEMENTS.contains(&element.name().as_ref()) {
+                    continue;
+                }
+                Event::Empty(canonical_element(&element)?)
+            }
+            Event::Text(text) => {
+                if dropped_depth > 0 || (text_depth == 0 && text.iter().all(u8::is_ascii_whitespace)) {
+                    continue;
+                }
+                // Re-escape so character references and entities come out one way
+                let text = text.unescape().map_err(|e| format!("Invalid SVG text: {}", e))?;
+                Event::Text(BytesText::new(&text).into_owned())
+            }
+            Event::Comment(_) | Event::PI(_) | Event::DocType(_) => continue,
+            _ if dropped_depth > 0 => continue,
+            other => other,
+        };
+        writer.write_event(event).map_err(|e| format!("Failed to write SVG: {}", e))?;
+    }
+
+    String::from_utf8(writer.into_inner().into_inner()).map_err(|e| format!("Failed to write SVG: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LILYPOND_SVG: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- Creator: GNU LilyPond 2.24.3, 2026-10-15 09:12:44 -->
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="210.00mm" height="297.00mm" viewBox="0 -0.0000 119.5016 169.0094">
+<metadata><date>2026-10-15</date></metadata>
+<line y2="0" x1="0" transform="translate(5.00004, 10.0000)" stroke-width="0.1000" x2="100.0000" y1="0"/>
+<a xlink:href="textedit:///tmp/0f1c9e2a/input.ly:4:12:13">
+<path transform="translate(20.4999, -0.0004)" d="M0.12345-1.5L.5.5 2e-3 0"/>
+</a>
+<text font-size="2.82222"><tspan> </tspan>Title &#38; more</text>
+</svg>
+"#;
+
+    const OTHER_RUN_SVG: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg width="210mm" height="297mm" viewBox="0 0 119.50161 169.00941" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns="http://www.w3.org/2000/svg">
+  <line x1="0" y1="0" x2="100" y2="0" stroke-width="0.1" transform="translate(5, 10)"/>
+  <a xlink:href="textedit:///var/folders/x1/input.ly:4:12:13">
+    <path d="M0.12349-1.5L.5.5 0.002 0" transform="translate(20.49995, 0)"/>
+  </a>
+  <text font-size="2.822224"><tspan> </tspan>Title &amp; more</text>
+</svg>"#;
+
+    #[test]
+    fn test_round_numbers() {
+        assert_eq!(round_numbers("M0.12345-1.5L.5.5 2e-3 0"), "M0.123-1.5L0.5 0.5 0.002 0");
+        assert_eq!(round_numbers("translate(20.4999, -0.0004)"), "translate(20.5, 0)");
+        assert_eq!(round_numbers("210.00mm"), "210mm");
+        assert_eq!(round_numbers("1e21"), "1000000000000000000000");
+        assert_eq!(round_numbers("- . e5 ü"), "- . e5 ü");
+    }
+
+    #[test]
+    fn test_runs_of_the_same_music_are_identical() {
+        let canonical = canonicalize_svg(LILYPOND_SVG).unwrap();
+        assert_eq!(canonical, canonicalize_svg(OTHER_RUN_SVG).unwrap());
+        assert_eq!(canonical, canonicalize_svg(&canonical).unwrap());
+        assert!(!canonical.contains("Creator") && !canonical.contains("metadata") && !canonical.contains("textedit"));
+        assert!(canonical.contains(r#"<a><path d="M0.123-1.5L0.5 0.5 0.002 0" transform="translate(20.5, 0)"/></a>"#));
+        assert!(canonical.contains(r#"<text font-size="2.822"><tspan> </tspan>Title &amp; more</text>"#));
+        assert!(canonical.contains(r#"height="297mm" viewBox="0 0 119.502 169.009" width="210mm""#));
+    }
+
+    #[test]
+    fn test_rejects_malformed_svg() {
+        assert!(canonicalize_svg("<svg><g></svg>").is_err());
+    }
+}
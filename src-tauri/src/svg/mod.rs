@@ -0,0 +1,7 @@
+mod canonical;
+mod postprocess;
+mod theme;
+
+pub use canonical::canonicalize_svg;
+pub use postprocess::{postprocess_svg, safe_element_id, SvgOptions, ELEMENT_ID_ATTRIBUTE};
+pub use theme::{apply_theme, SvgTheme};
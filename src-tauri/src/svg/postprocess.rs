@@ -0,0 +1,228 @@
+// SVG post-processing for LilyPond output
+// Makes sheets scalable (viewBox only), gives worksheet elements stable ids, tags drawing
+// primitives with CSS classes for theming, applies color themes, and optionally minifies
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, Writer};
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use super::theme::{apply_theme, SvgTheme};
+
+/// Attribute LilyPond output-attributes use to mark the worksheet element a grob belongs to
+pub const ELEMENT_ID_ATTRIBUTE: &str = "data-element-id";
+
+/// Element id as written into the SVG, limited to characters safe in LilyPond strings and XML
+pub fn safe_element_id(element_id: &str) -> String {
+    element_id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        .collect()
+}
+
+/// Prefix of injected element ids, keeping them clear of ids LilyPond generates
+const ELEMENT_ID_PREFIX: &str = "ws-";
+
+/// Class added to the root element
+const SHEET_CLASS: &str = "maestro-sheet";
+/// Class added to every element tied to a worksheet element
+const ELEMENT_CLASS: &str = "maestro-element";
+
+/// Post-processing options
+#[derive(Debug, Clone, Default)]
+pub struct SvgOptions {
+    /// Drop comments and whitespace between tags
+    pub minify: bool,
+    /// Recolor the sheet (None keeps LilyPond's black on transparent)
+    pub theme: Option<SvgTheme>,
+}
+
+/// Theming class for a drawing primitive
+fn primitive_class(tag: &[u8]) -> Option<&'static str> {
+    match tag {
+        b"text" | b"tspan" => Some("maestro-text"),
+        b"path" => Some("maestro-path"),
+        b"line" | b"polyline" => Some("maestro-line"),
+        b"rect" => Some("maestro-rect"),
+        b"circle" | b"ellipse" | b"polygon" => Some("maestro-shape"),
+        _ => None,
+    }
+}
+
+/// Numeric part of a length ("210.00mm" → 210.0)
+fn parse_length(value: &str) -> Option<f64> {
+    let end = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+        .unwrap_or(value.len());
+    value[..end].parse().ok()
+}
+
+/// Rewrite one start tag
+fn rewrite_element(
+    element: &BytesStart,
+    is_root: bool,
+    id_counts: &mut HashMap<String, usize>,
+) -> Result<BytesStart<'static>, String> {
+    let name = String::from_utf8_lossy(element.name().as_ref()).to_string();
+    let mut attributes: Vec<(String, String)> = Vec::new();
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(|e| format!("Invalid SVG attribute: {}", e))?;
+        let key = String::from_utf8_lossy(attribute.key.as_ref()).to_string();
+        let value = attribute
+            .unescape_value()
+            .map_err(|e| format!("Invalid SVG attribute: {}", e))?
+            .to_string();
+        attributes.push((key, value));
+    }
+    let attribute = |attributes: &[(String, String)], key: &str| {
+        attributes.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone())
+    };
+
+    let mut classes: Vec<String> = attribute(&attributes, "class")
+        .map(|c| c.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default();
+
+    if is_root {
+        // Absolute sizes stop the sheet from scaling to its container; keep the proportions in a viewBox
+        if attribute(&attributes, "viewBox").is_none() {
+            let width = attribute(&attributes, "width").as_deref().and_then(parse_length);
+            let height = attribute(&attributes, "height").as_deref().and_then(parse_length);
+            if let (Some(width), Some(height)) = (width, height) {
+                attributes.push(("viewBox".to_string(), format!("0 0 {} {}", width, height)));
+            }
+        }
+        attributes.retain(|(k, _)| k != "width" && k != "height");
+        classes.push(SHEET_CLASS.to_string());
+    }
+
+    if let Some(element_id) = attribute(&attributes, ELEMENT_ID_ATTRIBUTE) {
+        // A worksheet element can produce several grobs (e.g. each note head of a chord)
+        let count = id_counts.entry(element_id.clone()).or_default();
+        let id = match *count {
+            0 => format!("{}{}", ELEMENT_ID_PREFIX, element_id),
+            n => format!("{}{}-{}", ELEMENT_ID_PREFIX, element_id, n),
+        };
+        *count += 1;
+        attributes.retain(|(k, _)| k != "id");
+        attributes.push(("id".to_string(), id));
+        classes.push(ELEMENT_CLASS.to_string());
+    }
+
+    if let Some(class) = primitive_class(name.as_bytes()) {
+        classes.push(class.to_string());
+    }
+
+    let mut deduped: Vec<String> = Vec::new();
+    for class in classes {
+        if !deduped.contains(&class) {
+            deduped.push(class);
+        }
+    }
+    attributes.retain(|(k, _)| k != "class");
+    if !deduped.is_empty() {
+        attributes.push(("class".to_string(), deduped.join(" ")));
+    }
+
+    let mut rewritten = BytesStart::new(name);
+    for (key, value) in &attributes {
+        rewritten.push_attribute((key.as_str(), value.as_str()));
+    }
+    Ok(rewritten)
+}
+
+/// Apply the post-processing pipeline to an SVG document
+pub fn postprocess_svg(svg: &str, options: &SvgOptions) -> Result<String, String> {
+    let mut reader = Reader::from_str(svg);
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let mut id_counts = HashMap::new();
+    let mut seen_root = false;
+
+    loop {
+        let event = reader.read_event().map_err(|e| format!("Invalid SVG: {}", e))?;
+        let event = match event {
+            Event::Eof => break,
+            Event::Start(element) => {
+                let is_root = !seen_root;
+                seen_root = true;
+                Event::Start(rewrite_element(&element, is_root, &mut id_counts)?)
+            }
+            Event::Empty(element) => {
+                let is_root = !seen_root;
+                seen_root = true;
+                Event::Empty(rewrite_element(&element, is_root, &mut id_counts)?)
+            }
+            Event::Comment(_) if options.minify => continue,
+            Event::Text(text) if options.minify && text.iter().all(u8::is_ascii_whitespace) => continue,
+            other => other,
+        };
+        writer.write_event(event).map_err(|e| format!("Failed to write SVG: {}", e))?;
+    }
+
+    let svg = String::from_utf8(writer.into_inner().into_inner()).map_err(|e| format!("Failed to write SVG: {}", e))?;
+    match &options.theme {
+        Some(theme) => apply_theme(&svg, theme),
+        None => Ok(svg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LILYPOND_SVG: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" version="1.2" width="210.00mm" height="297.00mm" viewBox="0 -0.0000 119.5016 169.0094">
+<!-- staff -->
+<line transform="translate(5, 10)" stroke-width="0.1" x1="0" y1="0" x2="100" y2="0"/>
+<g class="interactive-note" data-element-id="chord-0">
+<path transform="translate(20, 12)" d="M0 0"/>
+</g>
+<g class="interactive-note" data-element-id="chord-0"><path d="M0 1"/></g>
+<text font-size="2">Title &amp; more</text>
+</svg>
+"#;
+
+    #[test]
+    fn test_root_uses_viewbox_only() {
+        let svg = postprocess_svg(LILYPOND_SVG, &SvgOptions::default()).unwrap();
+        assert!(svg.contains(r#"viewBox="0 -0.0000 119.5016 169.0094""#));
+        assert!(!svg.contains("width=\"210.00mm\""));
+        assert!(!svg.contains("height="));
+        assert!(svg.contains(r#"class="maestro-sheet""#));
+    }
+
+    #[test]
+    fn test_viewbox_derived_from_size() {
+        let svg = postprocess_svg(r#"<svg width="100mm" height="50mm"></svg>"#, &SvgOptions::default()).unwrap();
+        assert_eq!(svg, r#"<svg viewBox="0 0 100 50" class="maestro-sheet"></svg>"#);
+    }
+
+    #[test]
+    fn test_element_ids_and_classes() {
+        let svg = postprocess_svg(LILYPOND_SVG, &SvgOptions::default()).unwrap();
+        assert!(svg.contains(r#"id="ws-chord-0" class="interactive-note maestro-element""#));
+        assert!(svg.contains(r#"id="ws-chord-0-1""#));
+        assert!(svg.contains(r#"class="maestro-line""#));
+        assert!(svg.contains(r#"<text font-size="2" class="maestro-text">Title &amp; more</text>"#));
+    }
+
+    #[test]
+    fn test_minify() {
+        let svg = postprocess_svg(LILYPOND_SVG, &SvgOptions { minify: true, theme: None }).unwrap();
+        assert!(!svg.contains("<!--"));
+        assert!(!svg.contains(">\n<"));
+        assert!(svg.contains("Title &amp; more"));
+    }
+
+    #[test]
+    fn test_theme_is_applied_last() {
+        let options = SvgOptions { minify: false, theme: Some(SvgTheme::dark()) };
+        let svg = postprocess_svg(LILYPOND_SVG, &options).unwrap();
+        assert!(svg.contains(r##"class="maestro-sheet" color="#f2f2f2""##));
+        assert!(svg.contains("maestro-background"));
+    }
+
+    #[test]
+    fn test_rejects_malformed_svg() {
+        assert!(postprocess_svg("<svg><g></svg>", &SvgOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+// Color themes for rendered sheets
+// Screen rendering can use any foreground/background; exports are always forced back to print colors
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, Writer};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+/// Class of the rectangle painting the sheet background
+const BACKGROUND_CLASS: &str = "maestro-background";
+
+/// Foreground and background colors of a rendered sheet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SvgTheme {
+    pub foreground: String,
+    pub background: String,
+}
+
+impl SvgTheme {
+    /// Black on white, used for every export
+    pub fn print() -> Self {
+        Self { foreground: "#000000".to_string(), background: "#ffffff".to_string() }
+    }
+
+    /// Light on dark for on-screen display
+    #[allow(dead_code)]
+    pub fn dark() -> Self {
+        Self { foreground: "#f2f2f2".to_string(), background: "#1e1e1e".to_string() }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for color in [&self.foreground, &self.background] {
+            if !is_valid_color(color) {
+                return Err(format!("Invalid theme color: {}", color));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for SvgTheme {
+    fn default() -> Self {
+        Self::print()
+    }
+}
+
+/// Hex (#rgb, #rrggbb) or a named color
+fn is_valid_color(color: &str) -> bool {
+    match color.strip_prefix('#') {
+        Some(hex) => matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => !color.is_empty() && color.chars().all(|c| c.is_ascii_alphabetic()),
+    }
+}
+
+/// Paint values that mean "the ink color" and follow the theme foreground
+fn is_ink(value: &str) -> bool {
+    matches!(value.trim().to_ascii_lowercase().as_str(), "black" | "#000" | "#000000" | "currentcolor")
+}
+
+/// Point ink colors in a style attribute at currentColor ("fill:black;stroke-width:1")
+fn theme_style(style: &str) -> String {
+    style
+        .split(';')
+        .map(|declaration| match declaration.split_once(':') {
+            Some((property, value)) if matches!(property.trim(), "fill" | "stroke") && is_ink(value) => {
+                format!("{}:currentColor", property.trim())
+            }
+            _ => declaration.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn attributes_of(element: &BytesStart) -> Result<Vec<(String, String)>, String> {
+    element
+        .attributes()
+        .map(|attribute| {
+            let attribute = attribute.map_err(|e| format!("Invalid SVG attribute: {}", e))?;
+            let value = attribute.unescape_value().map_err(|e| format!("Invalid SVG attribute: {}", e))?;
+            Ok((String::from_utf8_lossy(attribute.key.as_ref()).to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+fn build_element(name: &str, attributes: &[(String, String)]) -> BytesStart<'static> {
+    let mut element = BytesStart::new(name.to_string());
+    for (key, value) in attributes {
+        element.push_attribute((key.as_str(), value.as_str()));
+    }
+    element
+}
+
+fn set_attribute(attributes: &mut Vec<(String, String)>, key: &str, value: &str) {
+    match attributes.iter_mut().find(|(k, _)| k == key) {
+        Some(existing) => existing.1 = value.to_string(),
+        None => attributes.push((key.to_string(), value.to_string())),
+    }
+}
+
+/// Background rectangle covering the root's viewBox (or the whole viewport without one)
+fn background_rect(root: &[(String, String)], color: &str) -> BytesStart<'static> {
+    let view_box: Vec<String> = root
+        .iter()
+        .find(|(k, _)| k == "viewBox")
+        .map(|(_, v)| v.split(|c: char| c.is_whitespace() || c == ',').filter(|p| !p.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default();
+    let (x, y, width, height) = match view_box.as_slice() {
+        [x, y, w, h] => (x.clone(), y.clone(), w.clone(), h.clone()),
+        _ => ("0".to_string(), "0".to_string(), "100%".to_string(), "100%".to_string()),
+    };
+
+    let attributes = [
+        ("class", BACKGROUND_CLASS.to_string()),
+        ("x", x),
+        ("y", y),
+        ("width", width),
+        ("height", height),
+        ("fill", color.to_string()),
+    ];
+    build_element("rect", &attributes.map(|(k, v)| (k.to_string(), v)))
+}
+
+/// Recolor a sheet: ink follows the foreground color and a background rectangle is painted
+/// Applying a theme to an already themed sheet replaces the previous colors
+pub fn apply_theme(svg: &str, theme: &SvgTheme) -> Result<String, String> {
+    theme.validate()?;
+    let has_background = svg.contains(BACKGROUND_CLASS);
+
+    let mut reader = Reader::from_str(svg);
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let mut seen_root = false;
+
+    loop {
+        let event = reader.read_event().map_err(|e| format!("Invalid SVG: {}", e))?;
+        let (element, is_empty) = match event {
+            Event::Eof => break,
+            Event::Start(element) => (element, false),
+            Event::Empty(element) => (element, true),
+            other => {
+                writer.write_event(other).map_err(|e| format!("Failed to write SVG: {}", e))?;
+                continue;
+            }
+        };
+
+        let name = String::from_utf8_lossy(element.name().as_ref()).to_string();
+        let mut attributes = attributes_of(&element)?;
+        let is_root = !seen_root;
+        seen_root = true;
+
+        let is_background = attributes
+            .iter()
+            .any(|(k, v)| k == "class" && v.split_whitespace().any(|c| c == BACKGROUND_CLASS));
+        if is_background {
+            set_attribute(&mut attributes, "fill", &theme.background);
+        } else {
+            for (key, value) in attributes.iter_mut() {
+                match key.as_str() {
+                    "fill" | "stroke" if is_ink(value) => *value = "currentColor".to_string(),
+                    "style" => *value = theme_style(value),
+                    _ => {}
+                }
+            }
+        }
+
+        if is_root {
+            set_attribute(&mut attributes, "color", &theme.foreground);
+            if !attributes.iter().any(|(k, _)| k == "fill") {
+                attributes.push(("fill".to_string(), "currentColor".to_string()));
+            }
+        }
+
+        let rewritten = build_element(&name, &attributes);
+        let event = if is_empty { Event::Empty(rewritten) } else { Event::Start(rewritten) };
+        writer.write_event(event).map_err(|e| format!("Failed to write SVG: {}", e))?;
+
+        if is_root && !is_empty && !has_background {
+            writer
+                .write_event(Event::Empty(background_rect(&attributes, &theme.background)))
+                .map_err(|e| format!("Failed to write SVG: {}", e))?;
+        }
+    }
+
+    String::from_utf8(writer.into_inner().into_inner()).map_err(|e| format!("Failed to write SVG: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHEET: &str = r##"<svg viewBox="0 0 100 50"><path fill="currentColor" d="M0 0"/><line stroke="black"/><text style="fill:#000000;font-size:2">A</text><rect fill="#ff0000"/></svg>"##;
+
+    #[test]
+    fn test_dark_theme() {
+        let svg = apply_theme(SHEET, &SvgTheme::dark()).unwrap();
+        assert!(svg.starts_with(r##"<svg viewBox="0 0 100 50" color="#f2f2f2" fill="currentColor"><rect class="maestro-background" x="0" y="0" width="100" height="50" fill="#1e1e1e"/>"##));
+        assert!(svg.contains(r#"<line stroke="currentColor"/>"#));
+        assert!(svg.contains(r#"style="fill:currentColor;font-size:2""#));
+        // Non-ink colors are left alone
+        assert!(svg.contains(r##"<rect fill="#ff0000"/>"##));
+    }
+
+    #[test]
+    fn test_print_theme_restores_themed_sheet() {
+        let dark = apply_theme(SHEET, &SvgTheme::dark()).unwrap();
+        let print = apply_theme(&dark, &SvgTheme::print()).unwrap();
+
+        assert!(print.contains(r##"color="#000000""##));
+        assert!(print.contains(r##"class="maestro-background" x="0" y="0" width="100" height="50" fill="#ffffff""##));
+        assert_eq!(print.matches(BACKGROUND_CLASS).count(), 1);
+        assert!(!print.contains("f2f2f2") && !print.contains("1e1e1e"));
+    }
+
+    #[test]
+    fn test_rejects_invalid_colors() {
+        let theme = SvgTheme { foreground: "\"><script>".to_string(), background: "#fff".to_string() };
+        assert!(apply_theme(SHEET, &theme).is_err());
+        assert!(is_valid_color("white") && is_valid_color("#abc") && !is_valid_color("#abcd"));
+    }
+}
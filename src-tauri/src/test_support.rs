@@ -0,0 +1,68 @@
+// Test support
+// Golden files: generated output is compared with a copy checked in under tests/golden
+//
+// After an intended change to the output, rewrite the golden files and review their diff:
+//   UPDATE_GOLDEN=1 cargo test
+//   git diff tests/golden
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Environment variable that rewrites golden files from the current output instead of comparing
+pub const UPDATE_GOLDEN: &str = "UPDATE_GOLDEN";
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(name)
+}
+
+/// Index of the first line that differs, None when the texts are the same
+fn first_difference(expected: &str, actual: &str) -> Option<usize> {
+    if expected == actual {
+        return None;
+    }
+    let (expected, actual): (Vec<&str>, Vec<&str>) = (expected.split('\n').collect(), actual.split('\n').collect());
+    let differs = expected.iter().zip(&actual).position(|(e, a)| e != a);
+    Some(differs.unwrap_or(expected.len().min(actual.len())))
+}
+
+/// Compare output with its golden file (a path under tests/golden), showing the first line that differs
+pub fn assert_golden(name: &str, actual: &str) {
+    let path = golden_path(name);
+    if env::var_os(UPDATE_GOLDEN).is_some() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).expect("create golden file directory");
+        }
+        fs::write(&path, actual).expect("write golden file");
+        return;
+    }
+
+    let Ok(expected) = fs::read_to_string(&path) else {
+        panic!("Golden file {} is missing; run with {}=1 to create it", path.display(), UPDATE_GOLDEN);
+    };
+    if let Some(line) = first_difference(&expected, actual) {
+        let line_of = |text: &str| text.split('\n').nth(line).unwrap_or("<end of file>").to_string();
+        panic!(
+            "Output differs from {} at line {}\n  expected: {}\n    actual: {}\nRun with {}=1 to accept the new output",
+            path.display(),
+            line + 1,
+            line_of(&expected),
+            line_of(actual),
+            UPDATE_GOLDEN
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_difference() {
+        assert_eq!(first_difference("a\nb\nc", "a\nb\nc"), None);
+        assert_eq!(first_difference("a\nb\nc", "a\nx\nc"), Some(1));
+        // Extra or missing lines differ where the shorter text ends
+        assert_eq!(first_difference("a\nb", "a\nb\nc"), Some(2));
+        assert_eq!(first_difference("a\nb\n", "a\nb"), Some(2));
+    }
+}
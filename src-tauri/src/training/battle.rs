@@ -0,0 +1,346 @@
+// Two-player chord battle for the classroom
+// Both players hear the same chord and answer on their own input; each keeps an independent score,
+// and the first correct answer to a question earns a race bonus
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use uuid::Uuid;
+
+use super::quiz::{grade_quiz_answer, points_for, QuizConfig, QuizQuestion};
+use crate::midi::input::MidiMessage;
+use crate::midi::HeldNotes;
+use crate::music::identify::name_midi_chord;
+use crate::music::intervals::chord_to_notes;
+
+/// Bonus for the first correct answer to a question
+const RACE_BONUS: u32 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Player {
+    One,
+    Two,
+}
+
+impl Player {
+    const BOTH: [Player; 2] = [Player::One, Player::Two];
+
+    fn index(self) -> usize {
+        match self {
+            Player::One => 0,
+            Player::Two => 1,
+        }
+    }
+}
+
+/// Outcome of one player's answer
+#[derive(Debug, Clone, Serialize)]
+pub struct BattleAnswerResult {
+    pub player: Player,
+    pub correct: bool,
+    /// Includes the race bonus when this was the first correct answer
+    pub points: u32,
+    pub first: bool,
+    pub streak: u32,
+    pub score: u32,
+    pub elapsed_secs: f32,
+    /// The chord that was played, revealed once both players have answered
+    pub expected: Option<String>,
+    pub question_closed: bool,
+    pub finished: bool,
+}
+
+/// Running or final totals of one player
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PlayerSummary {
+    pub score: u32,
+    pub streak: u32,
+    pub best_streak: u32,
+    pub answered: u32,
+    pub correct: u32,
+    /// Questions this player answered correctly first
+    pub races_won: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BattleSummary {
+    pub players: [PlayerSummary; 2],
+    pub questions: u32,
+    /// Higher score; None on a tie
+    pub winner: Option<Player>,
+}
+
+struct BattleQuestion {
+    id: String,
+    chord: String,
+    asked_at: Instant,
+    answered: [bool; 2],
+    won: bool,
+}
+
+/// One running battle
+pub struct BattleGame {
+    config: QuizConfig,
+    current: Option<BattleQuestion>,
+    players: [PlayerSummary; 2],
+    asked: u32,
+    /// Questions both players answered or moved past
+    closed: u32,
+}
+
+impl BattleGame {
+    pub fn new(config: QuizConfig) -> Result<Self, String> {
+        Ok(Self {
+            config: config.with_known_qualities()?,
+            current: None,
+            players: Default::default(),
+            asked: 0,
+            closed: 0,
+        })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.config.question_count.is_some_and(|count| self.closed >= count)
+    }
+
+    /// Id of the question waiting for answers
+    pub fn current_question_id(&self) -> Option<&str> {
+        self.current.as_ref().map(|q| q.id.as_str())
+    }
+
+    /// Close the current question; a player who never answered loses their streak
+    fn close_question(&mut self) {
+        if let Some(question) = self.current.take() {
+            for player in Player::BOTH {
+                if !question.answered[player.index()] {
+                    self.players[player.index()].streak = 0;
+                }
+            }
+            self.closed += 1;
+        }
+    }
+
+    /// Pick the chord both players answer next; returns the question and the chord notes to play
+    pub fn next_question(&mut self, rng: &mut impl Rng) -> Result<(QuizQuestion, Vec<String>), String> {
+        self.close_question();
+        if self.is_finished() {
+            return Err("Battle is finished".to_string());
+        }
+
+        let (chord, notes) = self.config.random_chord(rng)?;
+        self.asked += 1;
+        let id = Uuid::new_v4().to_string();
+        self.current =
+            Some(BattleQuestion { id: id.clone(), chord, asked_at: Instant::now(), answered: [false; 2], won: false });
+
+        Ok((QuizQuestion { id, number: self.asked }, notes))
+    }
+
+    /// Notes of the current question, for replaying it
+    pub fn current_notes(&self) -> Option<Vec<String>> {
+        self.current.as_ref().and_then(|q| chord_to_notes(&q.chord).ok())
+    }
+
+    /// Grade one player's answer to the current question at the given moment
+    /// Each player answers once; the question closes when both have
+    pub fn answer_at(
+        &mut self,
+        player: Player,
+        question_id: &str,
+        answer: &str,
+        now: Instant,
+    ) -> Result<BattleAnswerResult, String> {
+        let require_root = self.config.require_root;
+        let question = match self.current.as_mut() {
+            Some(q) if q.id == question_id => q,
+            _ => return Err("No matching question is waiting for an answer".to_string()),
+        };
+        if question.answered[player.index()] {
+            return Err("This player has already answered".to_string());
+        }
+        question.answered[player.index()] = true;
+
+        let elapsed_secs = now.saturating_duration_since(question.asked_at).as_secs_f32();
+        let correct = grade_quiz_answer(answer, &question.chord, require_root).correct;
+        let first = correct && !question.won;
+        question.won |= correct;
+        let both_answered = question.answered.iter().all(|answered| *answered);
+        let expected = both_answered.then(|| question.chord.clone());
+
+        let totals = &mut self.players[player.index()];
+        totals.answered += 1;
+        let points = if correct {
+            let points = points_for(totals.streak, elapsed_secs) + if first { RACE_BONUS } else { 0 };
+            totals.correct += 1;
+            totals.streak += 1;
+            totals.best_streak = totals.best_streak.max(totals.streak);
+            totals.races_won += u32::from(first);
+            totals.score += points;
+            points
+        } else {
+            totals.streak = 0;
+            0
+        };
+        let (streak, score) = (totals.streak, totals.score);
+
+        if both_answered {
+            self.close_question();
+        }
+        Ok(BattleAnswerResult {
+            player,
+            correct,
+            points,
+            first,
+            streak,
+            score,
+            elapsed_secs,
+            expected,
+            question_closed: both_answered,
+            finished: self.is_finished(),
+        })
+    }
+
+    pub fn summary(&self) -> BattleSummary {
+        let [one, two] = &self.players;
+        let winner = match one.score.cmp(&two.score) {
+            std::cmp::Ordering::Greater => Some(Player::One),
+            std::cmp::Ordering::Less => Some(Player::Two),
+            std::cmp::Ordering::Equal => None,
+        };
+        BattleSummary { players: self.players.clone(), questions: self.closed, winner }
+    }
+}
+
+/// Which player a MIDI source answers for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BattleKeyMapping {
+    /// The whole keyboard belongs to one player (one device each)
+    Whole { player: Player },
+    /// One shared keyboard: notes below the split belong to player one, the rest to player two
+    Split { split_note: u8 },
+}
+
+/// Held notes of each player on one MIDI source
+#[derive(Debug)]
+pub struct BattleInput {
+    mapping: BattleKeyMapping,
+    held: [HeldNotes; 2],
+}
+
+impl BattleInput {
+    pub fn new(mapping: BattleKeyMapping) -> Self {
+        Self { mapping, held: Default::default() }
+    }
+
+    /// Players a message is for; the sustain pedal reaches both halves of a split keyboard
+    fn players_for(&self, message: MidiMessage) -> Vec<Player> {
+        match (self.mapping, message) {
+            (BattleKeyMapping::Whole { player }, _) => vec![player],
+            (BattleKeyMapping::Split { .. }, MidiMessage::Sustain(_)) => Player::BOTH.to_vec(),
+            (
+                BattleKeyMapping::Split { split_note },
+                MidiMessage::NoteOn { note, .. } | MidiMessage::NoteOff { note },
+            ) => vec![if note < split_note { Player::One } else { Player::Two }],
+        }
+    }
+
+    /// Apply a message; returns each player whose notes now form a chord, with the chord as their answer
+    /// Inversions are answered by their root chord
+    pub fn apply(&mut self, message: MidiMessage) -> Vec<(Player, String)> {
+        let mut answers = Vec::new();
+        for player in self.players_for(message) {
+            let held = &mut self.held[player.index()];
+            if !held.apply(message) {
+                continue;
+            }
+            if let Some(chord) = name_midi_chord(&held.sounding(), "C") {
+                answers.push((player, chord.split('/').next().unwrap_or(&chord).to_string()));
+            }
+        }
+        answers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::time::Duration;
+
+    fn minor_battle(question_count: Option<u32>) -> BattleGame {
+        let config = QuizConfig { qualities: vec!["m".to_string()], require_root: false, question_count };
+        BattleGame::new(config).unwrap()
+    }
+
+    #[test]
+    fn test_race_and_independent_scores() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut game = minor_battle(Some(2));
+        let (question, notes) = game.next_question(&mut rng).unwrap();
+        assert_eq!(notes.len(), 3);
+        let asked = Instant::now();
+
+        // Player two answers first but wrongly; player one's slower correct answer still wins the race
+        let two = game.answer_at(Player::Two, &question.id, "major", asked).unwrap();
+        assert!(!two.correct && !two.first && two.expected.is_none());
+        assert!(game.answer_at(Player::Two, &question.id, "minor", asked).is_err());
+        let one = game.answer_at(Player::One, &question.id, "minor", asked + Duration::from_secs(20)).unwrap();
+        assert!(one.correct && one.first && one.question_closed);
+        assert_eq!(one.points, 100 + RACE_BONUS);
+        assert!(one.expected.unwrap().ends_with('m'));
+
+        // Both right: only the faster one gets the bonus
+        let (question, _) = game.next_question(&mut rng).unwrap();
+        let two = game.answer_at(Player::Two, &question.id, "m", asked).unwrap();
+        let one = game.answer_at(Player::One, &question.id, "m", asked + Duration::from_secs(20)).unwrap();
+        assert!(two.first && !one.first);
+        assert_eq!(one.points, 110);
+        assert!(one.finished);
+
+        let summary = game.summary();
+        assert_eq!(summary.questions, 2);
+        assert_eq!((summary.players[0].score, summary.players[1].score), (260, 200));
+        assert_eq!((summary.players[0].races_won, summary.players[1].races_won), (1, 1));
+        assert_eq!(summary.winner, Some(Player::One));
+        assert!(game.next_question(&mut rng).is_err());
+    }
+
+    #[test]
+    fn test_skipped_question_breaks_streak() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut game = minor_battle(None);
+        let (question, _) = game.next_question(&mut rng).unwrap();
+        game.answer_at(Player::One, &question.id, "m", Instant::now()).unwrap();
+        game.answer_at(Player::Two, &question.id, "m", Instant::now()).unwrap();
+
+        let (question, _) = game.next_question(&mut rng).unwrap();
+        game.answer_at(Player::One, &question.id, "m", Instant::now()).unwrap();
+        game.next_question(&mut rng).unwrap();
+        assert!(game.answer_at(Player::Two, &question.id, "m", Instant::now()).is_err());
+
+        let summary = game.summary();
+        assert_eq!((summary.players[0].streak, summary.players[1].streak), (2, 0));
+        assert_eq!(summary.questions, 2);
+    }
+
+    #[test]
+    fn test_split_keyboard_answers() {
+        let mut input = BattleInput::new(BattleKeyMapping::Split { split_note: 60 });
+        let on = |note| MidiMessage::NoteOn { note, velocity: 90 };
+        assert!(input.apply(on(45)).is_empty());
+        assert!(input.apply(on(72)).is_empty());
+        assert!(input.apply(on(48)).is_empty());
+        // A-C-E below the split and first-inversion C above it
+        assert_eq!(input.apply(on(52)), vec![(Player::One, "Am".to_string())]);
+        assert!(input.apply(on(67)).is_empty());
+        assert_eq!(input.apply(on(64)), vec![(Player::Two, "C".to_string())]);
+
+        let mut whole = BattleInput::new(BattleKeyMapping::Whole { player: Player::Two });
+        whole.apply(on(40));
+        whole.apply(on(44));
+        assert_eq!(whole.apply(on(47)), vec![(Player::Two, "E".to_string())]);
+    }
+}
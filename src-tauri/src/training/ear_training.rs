@@ -0,0 +1,246 @@
+// Mixed ear training session
+// One session asks interval, chord quality and progression questions in any order through the
+// individual drills, grades answers on the backend so they never reach the frontend, and keeps
+// statistics across all three kinds
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+use super::intervals::{
+    IntervalAnswerResult, IntervalDrill, IntervalDrillConfig, IntervalQuestion, IntervalStat, Presentation, Tally,
+};
+use super::progressions::{ProgressionAnswerResult, ProgressionDrill, ProgressionDrillConfig, ProgressionQuestion};
+use super::quiz::{QuizAnswerResult, QuizConfig, QuizGame, QuizQuestion};
+use crate::music::types::AudioNote;
+
+/// Kind of question to ask next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EarTrainingKind {
+    Interval,
+    ChordQuality,
+    Progression,
+}
+
+/// Session configuration; each kind draws from its drill's settings
+/// Question counts of the drills are ignored in favor of the session's own
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EarTrainingConfig {
+    pub intervals: IntervalDrillConfig,
+    pub chord_qualities: QuizConfig,
+    pub progressions: ProgressionDrillConfig,
+    /// Number of questions in the session (None = endless)
+    pub question_count: Option<u32>,
+}
+
+/// Question sent to the frontend, tagged with its kind (the answer stays on the backend)
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EarTrainingQuestion {
+    Interval(IntervalQuestion),
+    ChordQuality(QuizQuestion),
+    Progression(ProgressionQuestion),
+}
+
+/// What to play for a question
+#[derive(Debug, Clone)]
+pub enum EarTrainingAudio {
+    Interval(Presentation, Vec<AudioNote>),
+    /// Notes of the chord, to be voiced
+    Chord(Vec<String>),
+    /// Chords of the phrase, to be voice-led
+    Progression(Vec<String>),
+}
+
+/// Outcome of an answer, tagged with the kind of question it answered
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EarTrainingResult {
+    Interval(IntervalAnswerResult),
+    ChordQuality(QuizAnswerResult),
+    Progression(ProgressionAnswerResult),
+}
+
+impl EarTrainingResult {
+    fn correct(&self) -> bool {
+        match self {
+            EarTrainingResult::Interval(result) => result.correct,
+            EarTrainingResult::ChordQuality(result) => result.correct,
+            EarTrainingResult::Progression(result) => result.correct,
+        }
+    }
+}
+
+/// Running statistics of a session
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EarTrainingStats {
+    pub interval: Tally,
+    pub chord_quality: Tally,
+    pub progression: Tally,
+    pub streak: u32,
+    pub best_streak: u32,
+    /// Per-interval breakdown, split by how each was heard
+    pub intervals: Vec<IntervalStat>,
+}
+
+/// A graded answer with the session's statistics after it
+#[derive(Debug, Clone, Serialize)]
+pub struct EarTrainingAnswer {
+    pub result: EarTrainingResult,
+    pub stats: EarTrainingStats,
+    pub finished: bool,
+}
+
+/// One running mixed session
+pub struct EarTrainingSession {
+    intervals: IntervalDrill,
+    chord_qualities: QuizGame,
+    progressions: ProgressionDrill,
+    question_count: Option<u32>,
+    /// Kind and id of the question waiting for an answer
+    current: Option<(EarTrainingKind, String)>,
+    answered: u32,
+    stats: EarTrainingStats,
+}
+
+impl EarTrainingSession {
+    pub fn new(config: EarTrainingConfig) -> Result<Self, String> {
+        Ok(Self {
+            intervals: IntervalDrill::new(IntervalDrillConfig { question_count: None, ..config.intervals })?,
+            chord_qualities: QuizGame::new(QuizConfig { question_count: None, ..config.chord_qualities })?,
+            progressions: ProgressionDrill::new(ProgressionDrillConfig {
+                question_count: None,
+                ..config.progressions
+            })?,
+            question_count: config.question_count,
+            current: None,
+            answered: 0,
+            stats: EarTrainingStats::default(),
+        })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.question_count.is_some_and(|count| self.answered >= count)
+    }
+
+    /// Ask a question of the given kind; returns the question and what to play for it
+    /// An unanswered question is replaced
+    pub fn next_question(
+        &mut self,
+        kind: EarTrainingKind,
+        rng: &mut impl Rng,
+    ) -> Result<(EarTrainingQuestion, EarTrainingAudio), String> {
+        if self.is_finished() {
+            return Err("Ear training session is finished".to_string());
+        }
+
+        let (id, question, audio) = match kind {
+            EarTrainingKind::Interval => {
+                let (question, notes) = self.intervals.next_question(rng)?;
+                let audio = EarTrainingAudio::Interval(question.presentation, notes);
+                (question.id.clone(), EarTrainingQuestion::Interval(question), audio)
+            }
+            EarTrainingKind::ChordQuality => {
+                let (question, notes) = self.chord_qualities.next_question(rng)?;
+                (question.id.clone(), EarTrainingQuestion::ChordQuality(question), EarTrainingAudio::Chord(notes))
+            }
+            EarTrainingKind::Progression => {
+                let (question, chords) = self.progressions.next_question(rng)?;
+                let audio = EarTrainingAudio::Progression(chords);
+                (question.id.clone(), EarTrainingQuestion::Progression(question), audio)
+            }
+        };
+        self.current = Some((kind, id));
+        Ok((question, audio))
+    }
+
+    /// Grade an answer to the current question and update the statistics
+    pub fn answer(&mut self, question_id: &str, answer: &str) -> Result<EarTrainingAnswer, String> {
+        let kind = match &self.current {
+            Some((kind, id)) if id == question_id => *kind,
+            _ => return Err("No matching question is waiting for an answer".to_string()),
+        };
+
+        let result = match kind {
+            EarTrainingKind::Interval => EarTrainingResult::Interval(self.intervals.answer(question_id, answer)?),
+            EarTrainingKind::ChordQuality => EarTrainingResult::ChordQuality(self.chord_qualities.answer_at(
+                question_id,
+                answer,
+                Instant::now(),
+            )?),
+            EarTrainingKind::Progression => {
+                EarTrainingResult::Progression(self.progressions.answer(question_id, answer)?)
+            }
+        };
+        self.current = None;
+        self.answered += 1;
+
+        let correct = result.correct();
+        let stats = &mut self.stats;
+        match kind {
+            EarTrainingKind::Interval => stats.interval.record(correct),
+            EarTrainingKind::ChordQuality => stats.chord_quality.record(correct),
+            EarTrainingKind::Progression => stats.progression.record(correct),
+        }
+        stats.streak = if correct { stats.streak + 1 } else { 0 };
+        stats.best_streak = stats.best_streak.max(stats.streak);
+
+        Ok(EarTrainingAnswer { result, stats: self.stats(), finished: self.is_finished() })
+    }
+
+    pub fn stats(&self) -> EarTrainingStats {
+        EarTrainingStats { intervals: self.intervals.stats(), ..self.stats.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_mixed_session_grades_and_tracks_each_kind() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let config = EarTrainingConfig {
+            chord_qualities: QuizConfig { qualities: vec!["m".to_string()], ..QuizConfig::default() },
+            question_count: Some(3),
+            ..EarTrainingConfig::default()
+        };
+        let mut session = EarTrainingSession::new(config).unwrap();
+
+        let (question, audio) = session.next_question(EarTrainingKind::ChordQuality, &mut rng).unwrap();
+        let EarTrainingQuestion::ChordQuality(question) = question else { panic!("expected a chord question") };
+        assert!(matches!(&audio, EarTrainingAudio::Chord(notes) if notes.len() == 3));
+        let answer = session.answer(&question.id, "minor").unwrap();
+        assert!(answer.result.correct());
+        assert!(session.answer(&question.id, "minor").is_err());
+
+        let (question, audio) = session.next_question(EarTrainingKind::Interval, &mut rng).unwrap();
+        let EarTrainingQuestion::Interval(question) = question else { panic!("expected an interval question") };
+        assert!(matches!(audio, EarTrainingAudio::Interval(Presentation::Melodic, notes) if notes.len() == 2));
+        let answer = session.answer(&question.id, "not an interval").unwrap();
+        assert!(!answer.result.correct());
+
+        // Asking again replaces the unanswered question
+        let (stale, _) = session.next_question(EarTrainingKind::Progression, &mut rng).unwrap();
+        let (question, _) = session.next_question(EarTrainingKind::Progression, &mut rng).unwrap();
+        let (EarTrainingQuestion::Progression(stale), EarTrainingQuestion::Progression(question)) = (stale, question)
+        else {
+            panic!("expected progression questions")
+        };
+        assert!(session.answer(&stale.id, "authentic").is_err());
+        let answer = session.answer(&question.id, "authentic").unwrap();
+        assert!(answer.finished);
+
+        let stats = answer.stats;
+        assert_eq!(stats.chord_quality, Tally { asked: 1, correct: 1 });
+        assert_eq!(stats.interval, Tally { asked: 1, correct: 0 });
+        assert_eq!(stats.progression.asked, 1);
+        assert_eq!(stats.best_streak, 1);
+        assert_eq!(stats.intervals.iter().map(|stat| stat.ascending.asked).sum::<u32>(), 1);
+        assert!(session.next_question(EarTrainingKind::Interval, &mut rng).is_err());
+    }
+}
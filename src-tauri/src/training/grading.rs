@@ -0,0 +1,356 @@
+// Lenient answer grading
+// Reads common student spellings of notes and chords ("Bflat", "f sharp", "a min") and explains mistakes
+
+use serde::{Deserialize, Serialize};
+
+use crate::music::chord_correction::edit_distance;
+use crate::music::chords::{parse_chord, textbook_violation};
+use crate::music::intervals::CHORD_INTERVAL_SPECS;
+use crate::music::notes::note_index;
+use crate::music::types::ParseMode;
+
+/// Spelled-out accidentals, matched with a one-letter typo allowance
+const ACCIDENTAL_WORDS: &[(&str, &str)] = &[("flat", "b"), ("sharp", "#")];
+
+/// Spelled-out qualities (spaces removed) and their canonical suffixes
+const QUALITY_WORDS: &[(&str, &str)] = &[
+    ("major", ""),
+    ("maj", ""),
+    ("minor", "m"),
+    ("min", "m"),
+    ("mi", "m"),
+    ("diminished", "dim"),
+    ("augmented", "aug"),
+    ("major7", "maj7"),
+    ("majorseventh", "maj7"),
+    ("minor7", "m7"),
+    ("minorseventh", "m7"),
+    ("dominant7", "7"),
+    ("dominantseventh", "7"),
+    ("seventh", "7"),
+    ("diminished7", "dim7"),
+    ("diminishedseventh", "dim7"),
+    ("halfdiminished", "m7b5"),
+    ("halfdiminished7", "m7b5"),
+    ("halfdiminishedseventh", "m7b5"),
+    ("suspended2", "sus2"),
+    ("suspendedsecond", "sus2"),
+    ("suspended4", "sus4"),
+    ("suspendedfourth", "sus4"),
+];
+
+/// Words shorter than this must match exactly
+const MIN_FUZZY_LENGTH: usize = 5;
+
+/// Kind of mistake in a wrong answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MistakeKind {
+    Unrecognized,
+    WrongRoot,
+    WrongAccidental,
+    Enharmonic,
+    WrongQuality,
+    /// Readable, but not written the textbook way (strict mode only)
+    NonStandardSymbol,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GradingOptions {
+    /// When false, naming the quality alone ("minor") is enough
+    pub require_root: bool,
+    /// Accept a differently spelled root with the same pitch (A# for Bb)
+    pub accept_enharmonics: bool,
+    /// Strict mode only accepts textbook chord symbols ("Bb", not "b flat") and exact quality names
+    pub mode: ParseMode,
+}
+
+impl Default for GradingOptions {
+    fn default() -> Self {
+        Self { require_root: true, accept_enharmonics: false, mode: ParseMode::Permissive }
+    }
+}
+
+/// Graded answer with feedback for the student
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GradeResult {
+    pub correct: bool,
+    /// How the answer was read ("Bb", "Am", "minor")
+    pub interpreted: Option<String>,
+    pub mistake: Option<MistakeKind>,
+    pub feedback: String,
+}
+
+/// Interval content of a quality, accepting long names like "minor"
+/// Returns None for unknown qualities rather than defaulting to major
+pub fn quality_intervals(quality: &str) -> Option<Vec<u8>> {
+    let canonical = canonicalize_quality(quality)?;
+    CHORD_INTERVAL_SPECS
+        .get(canonical.as_str())
+        .map(|specs| specs.iter().map(|(semitones, _)| *semitones).collect())
+}
+
+/// Closest entry of a word table, allowing a typo or two in longer words
+fn fuzzy_lookup<'a>(word: &str, table: &[(&str, &'a str)], min_length: usize) -> Option<&'a str> {
+    if let Some((_, value)) = table.iter().find(|(name, _)| *name == word) {
+        return Some(value);
+    }
+    if word.chars().count() < min_length {
+        return None;
+    }
+
+    let allowed = if word.len() >= 9 { 2 } else { 1 };
+    table
+        .iter()
+        .filter(|(name, _)| name.len() >= min_length)
+        .map(|(name, value)| (edit_distance(word, name), *value))
+        .filter(|(distance, _)| *distance <= allowed)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, value)| value)
+}
+
+/// Canonical chord suffix for a quality written in any common form
+/// "minor" → "m", "Maj 7th" → "maj7", "" → "" (major)
+pub fn canonicalize_quality(input: &str) -> Option<String> {
+    lookup_quality(input, true)
+}
+
+fn lookup_quality(input: &str, allow_typos: bool) -> Option<String> {
+    let trimmed = input.trim();
+    let compact: String = trimmed
+        .to_lowercase()
+        .replace("7th", "7")
+        .replace('♭', "b")
+        .replace('♯', "#")
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_' | '.'))
+        .collect();
+
+    // Spelled-out names map to the short suffix ("minor" → "m") even where the long form is also valid
+    if let Some((_, suffix)) = QUALITY_WORDS.iter().find(|(name, _)| *name == compact) {
+        return Some(suffix.to_string());
+    }
+    // Case matters for symbols like "M7"
+    if CHORD_INTERVAL_SPECS.contains_key(trimmed) {
+        return Some(trimmed.to_string());
+    }
+    if CHORD_INTERVAL_SPECS.contains_key(compact.as_str()) {
+        return Some(compact);
+    }
+
+    let min_length = if allow_typos { MIN_FUZZY_LENGTH } else { usize::MAX };
+    fuzzy_lookup(&compact, QUALITY_WORDS, min_length).map(str::to_string)
+}
+
+/// Read a quality-only answer ("minor", "aug")
+/// Exact quality names win over chord names, which win over misspelled qualities,
+/// so "augmented" is a quality but "c minor" is a chord; strict mode never reads misspellings
+fn quality_only_answer(answer: &str, mode: ParseMode) -> Option<String> {
+    if answer.trim().is_empty() {
+        return None;
+    }
+    let exact = lookup_quality(answer, false);
+    if mode == ParseMode::Strict {
+        return exact;
+    }
+    exact.or_else(|| match canonicalize_chord(answer) {
+        Some(_) => None,
+        None => canonicalize_quality(answer),
+    })
+}
+
+/// Canonical chord name for a note or chord written in any common form
+/// "Bflat" → "Bb", "f sharp" → "F#", "a min" → "Am", "E-flat major 7th" → "Ebmaj7"
+pub fn canonicalize_chord(input: &str) -> Option<String> {
+    let text = input.trim().replace('♭', "b").replace('♯', "#").replace(['-', '_'], " ");
+    let mut chars = text.chars();
+    let letter = chars.next().filter(|c| ('a'..='g').contains(&c.to_ascii_lowercase()))?;
+    let root_letter = letter.to_ascii_uppercase();
+    let rest: String = chars.collect();
+
+    // Accidental: a symbol or "b" right after the letter, or a spelled-out word
+    let (accidental, quality) = if let Some(after) = rest.strip_prefix('#') {
+        ("#", after.to_string())
+    } else if rest.starts_with('b') && !rest.to_lowercase().starts_with("b5") {
+        ("b", rest[1..].to_string())
+    } else {
+        let trimmed = rest.trim_start();
+        let word_end = trimmed.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(trimmed.len());
+        let word = trimmed[..word_end].to_lowercase();
+        match fuzzy_lookup(&word, ACCIDENTAL_WORDS, 4) {
+            Some(symbol) => (symbol, trimmed[word_end..].to_string()),
+            None => ("", rest),
+        }
+    };
+
+    let suffix = canonicalize_quality(&quality)?;
+    Some(format!("{}{}{}", root_letter, accidental, suffix))
+}
+
+/// Readable name of a quality for feedback text
+fn quality_name(suffix: &str) -> String {
+    match suffix {
+        "" => "major".to_string(),
+        "m" => "minor".to_string(),
+        "dim" => "diminished".to_string(),
+        "aug" => "augmented".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn unrecognized(answer: &str) -> GradeResult {
+    GradeResult {
+        correct: false,
+        interpreted: None,
+        mistake: Some(MistakeKind::Unrecognized),
+        feedback: format!("Couldn't read \"{}\" as a note or chord name", answer.trim()),
+    }
+}
+
+fn wrong(interpreted: String, mistake: MistakeKind, feedback: String) -> GradeResult {
+    GradeResult { correct: false, interpreted: Some(interpreted), mistake: Some(mistake), feedback }
+}
+
+/// Grade a free-text answer against the expected chord (or note) name
+pub fn grade_answer(answer: &str, expected: &str, options: &GradingOptions) -> GradeResult {
+    let Ok(expected_chord) = parse_chord(expected) else {
+        return unrecognized(expected);
+    };
+    let expected_intervals = quality_intervals(&expected_chord.suffix);
+
+    // Quality-only answers ("minor", "aug") when the root isn't required
+    if !options.require_root {
+        if let Some(suffix) = quality_only_answer(answer, options.mode) {
+            let interpreted = quality_name(&suffix);
+            return if quality_intervals(&suffix) == expected_intervals {
+                GradeResult { correct: true, interpreted: Some(interpreted), mistake: None, feedback: "Correct!".to_string() }
+            } else {
+                let feedback = format!("It's {}, not {}", quality_name(&expected_chord.suffix), interpreted);
+                wrong(interpreted, MistakeKind::WrongQuality, feedback)
+            };
+        }
+    }
+
+    let Some(canonical) = canonicalize_chord(answer) else {
+        return unrecognized(answer);
+    };
+    let Ok(answer_chord) = parse_chord(&canonical) else {
+        return unrecognized(answer);
+    };
+
+    if options.mode == ParseMode::Strict {
+        if let Some(violation) = textbook_violation(answer.trim()) {
+            return wrong(canonical, MistakeKind::NonStandardSymbol, violation.message);
+        }
+    }
+
+    let same_spelling = answer_chord.root == expected_chord.root;
+    let same_pitch = note_index(&answer_chord.root).ok() == note_index(&expected_chord.root).ok();
+    let same_letter = answer_chord.root.chars().next() == expected_chord.root.chars().next();
+
+    if !same_pitch {
+        let (mistake, feedback) = if same_letter {
+            (MistakeKind::WrongAccidental, format!("Right letter, wrong accidental: the root is {}", expected_chord.root))
+        } else {
+            (MistakeKind::WrongRoot, format!("The root is {}, not {}", expected_chord.root, answer_chord.root))
+        };
+        return wrong(canonical, mistake, feedback);
+    }
+
+    if quality_intervals(&answer_chord.suffix) != expected_intervals {
+        let feedback = format!(
+            "Right root, but the chord is {}, not {}",
+            quality_name(&expected_chord.suffix),
+            quality_name(&answer_chord.suffix)
+        );
+        return wrong(canonical, MistakeKind::WrongQuality, feedback);
+    }
+
+    if !same_spelling && !options.accept_enharmonics {
+        let feedback = format!(
+            "{} sounds the same, but here it's spelled {}",
+            answer_chord.root, expected_chord.root
+        );
+        return wrong(canonical, MistakeKind::Enharmonic, feedback);
+    }
+
+    let feedback = if canonical == answer.trim() { "Correct!".to_string() } else { format!("Correct! ({})", canonical) };
+    GradeResult { correct: true, interpreted: Some(canonical), mistake: None, feedback }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonicalize_common_spellings() {
+        assert_eq!(canonicalize_chord("Bflat").as_deref(), Some("Bb"));
+        assert_eq!(canonicalize_chord("f sharp").as_deref(), Some("F#"));
+        assert_eq!(canonicalize_chord("a min").as_deref(), Some("Am"));
+        assert_eq!(canonicalize_chord("E-flat major 7th").as_deref(), Some("Ebmaj7"));
+        assert_eq!(canonicalize_chord("c# diminshed").as_deref(), Some("C#dim"));
+        assert_eq!(canonicalize_chord("B♭m7").as_deref(), Some("Bbm7"));
+        assert_eq!(canonicalize_chord("Bm7b5").as_deref(), Some("Bm7b5"));
+        assert_eq!(canonicalize_chord("h minor"), None);
+        assert_eq!(canonicalize_chord("C purple"), None);
+    }
+
+    #[test]
+    fn test_canonicalize_quality() {
+        assert_eq!(canonicalize_quality("Minor").as_deref(), Some("m"));
+        assert_eq!(canonicalize_quality("augmentd").as_deref(), Some("aug"));
+        assert_eq!(canonicalize_quality("half diminished").as_deref(), Some("m7b5"));
+        assert_eq!(canonicalize_quality("").as_deref(), Some(""));
+        assert_eq!(canonicalize_quality("xyz"), None);
+    }
+
+    #[test]
+    fn test_grading_feedback() {
+        let strict = GradingOptions::default();
+
+        let result = grade_answer("b flat", "Bb", &strict);
+        assert!(result.correct);
+        assert_eq!(result.feedback, "Correct! (Bb)");
+
+        assert_eq!(grade_answer("B", "Bb", &strict).mistake, Some(MistakeKind::WrongAccidental));
+        assert_eq!(grade_answer("C", "Bb", &strict).mistake, Some(MistakeKind::WrongRoot));
+        assert_eq!(grade_answer("a sharp", "Bb", &strict).mistake, Some(MistakeKind::Enharmonic));
+        assert_eq!(grade_answer("a major", "Am", &strict).mistake, Some(MistakeKind::WrongQuality));
+        assert_eq!(grade_answer("???", "Am", &strict).mistake, Some(MistakeKind::Unrecognized));
+
+        let lenient = GradingOptions { require_root: true, accept_enharmonics: true, ..Default::default() };
+        assert!(grade_answer("A#", "Bb", &lenient).correct);
+    }
+
+    #[test]
+    fn test_quality_only_answers() {
+        let options = GradingOptions { require_root: false, accept_enharmonics: true, ..Default::default() };
+        assert!(grade_answer("minor", "Am", &options).correct);
+        assert!(grade_answer("aug", "Caug", &options).correct);
+        let result = grade_answer("major", "Am", &options);
+        assert_eq!(result.mistake, Some(MistakeKind::WrongQuality));
+        assert_eq!(result.feedback, "It's minor, not major");
+        // A full chord name is still graded as a chord
+        assert!(grade_answer("a min", "Am", &options).correct);
+        assert!(grade_answer("c minor", "Am", &options).mistake == Some(MistakeKind::WrongRoot));
+        assert!(grade_answer("augmented", "Eaug", &options).correct);
+    }
+
+    #[test]
+    fn test_strict_mode_reports_convention() {
+        let strict = GradingOptions { mode: ParseMode::Strict, ..Default::default() };
+        assert!(grade_answer("Bb", "Bb", &strict).correct);
+        assert!(grade_answer("Am7", "Am7", &strict).correct);
+
+        let result = grade_answer("b flat", "Bb", &strict);
+        assert_eq!(result.mistake, Some(MistakeKind::NonStandardSymbol));
+        assert_eq!(result.feedback, "Chord symbols are written without spaces");
+        assert_eq!(grade_answer("B♭", "Bb", &strict).mistake, Some(MistakeKind::NonStandardSymbol));
+        assert_eq!(grade_answer("Ami", "Am", &strict).feedback, "Write \"m\" rather than \"mi\"");
+
+        let quality_only = GradingOptions { require_root: false, ..strict };
+        assert!(grade_answer("minor", "Am", &quality_only).correct);
+        assert!(!grade_answer("augmentd", "Caug", &quality_only).correct);
+    }
+}
@@ -0,0 +1,438 @@
+// Interval ear training
+// Generates simple and compound intervals (up to a major thirteenth), played harmonically or melodically
+// in either direction, grades spoken-style answers and keeps per-interval statistics
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::music::intervals::spell_interval_with_degree;
+use crate::music::notes::{note_index, preferred_spelling, simplify_spelling, CHROMATIC_FLAT};
+use crate::music::tiers::MAJOR_SCALE;
+use crate::music::types::AudioNote;
+
+/// Largest interval drilled, a major thirteenth
+pub const MAX_INTERVAL: u8 = 21;
+
+/// Octave of the lower note for simple and compound intervals, keeping both notes in piano range
+const SIMPLE_BASE_OCTAVE: i8 = 4;
+const COMPOUND_BASE_OCTAVE: i8 = 3;
+
+/// Interval names by size in semitones: (abbreviation, name, diatonic number)
+const INTERVALS: [(&str, &str, u8); 22] = [
+    ("P1", "unison", 1),
+    ("m2", "minor second", 2),
+    ("M2", "major second", 2),
+    ("m3", "minor third", 3),
+    ("M3", "major third", 3),
+    ("P4", "perfect fourth", 4),
+    ("TT", "tritone", 4),
+    ("P5", "perfect fifth", 5),
+    ("m6", "minor sixth", 6),
+    ("M6", "major sixth", 6),
+    ("m7", "minor seventh", 7),
+    ("M7", "major seventh", 7),
+    ("P8", "octave", 8),
+    ("m9", "minor ninth", 9),
+    ("M9", "major ninth", 9),
+    ("m10", "minor tenth", 10),
+    ("M10", "major tenth", 10),
+    ("P11", "perfect eleventh", 11),
+    ("A11", "augmented eleventh", 11),
+    ("P12", "perfect twelfth", 12),
+    ("m13", "minor thirteenth", 13),
+    ("M13", "major thirteenth", 13),
+];
+
+/// Spelled-out interval numbers
+const NUMBER_WORDS: [(&str, u8); 14] = [
+    ("unison", 1),
+    ("second", 2),
+    ("third", 3),
+    ("fourth", 4),
+    ("fifth", 5),
+    ("sixth", 6),
+    ("seventh", 7),
+    ("octave", 8),
+    ("ninth", 9),
+    ("tenth", 10),
+    ("eleventh", 11),
+    ("twelfth", 12),
+    ("thirteenth", 13),
+    ("eighth", 8),
+];
+
+/// How the two notes are played
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Presentation {
+    /// Both notes together
+    Harmonic,
+    /// One after the other
+    Melodic,
+}
+
+/// Direction of a melodic interval
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+/// Interval drill configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IntervalDrillConfig {
+    /// Interval sizes in semitones to draw from (1 = minor second ... 21 = major thirteenth)
+    pub intervals: Vec<u8>,
+    /// Presentations to mix; one is picked per question
+    pub presentations: Vec<Presentation>,
+    /// Directions to mix for melodic questions
+    pub directions: Vec<Direction>,
+    /// Number of questions in a round (None = endless)
+    pub question_count: Option<u32>,
+}
+
+impl Default for IntervalDrillConfig {
+    fn default() -> Self {
+        Self {
+            intervals: (1..=12).collect(),
+            presentations: vec![Presentation::Melodic],
+            directions: vec![Direction::Ascending],
+            question_count: Some(10),
+        }
+    }
+}
+
+/// Question sent to the frontend (the interval itself stays on the backend)
+#[derive(Debug, Clone, Serialize)]
+pub struct IntervalQuestion {
+    pub id: String,
+    pub number: u32,
+    pub presentation: Presentation,
+    /// None for harmonic intervals
+    pub direction: Option<Direction>,
+}
+
+/// Outcome of a submitted answer
+#[derive(Debug, Clone, Serialize)]
+pub struct IntervalAnswerResult {
+    pub correct: bool,
+    /// Name of the interval that was played ("major tenth")
+    pub expected: String,
+    pub semitones: u8,
+    /// The two notes in the order they were played
+    pub notes: Vec<AudioNote>,
+    pub finished: bool,
+    pub feedback: String,
+}
+
+/// Answers for one way of hearing an interval
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Tally {
+    pub asked: u32,
+    pub correct: u32,
+}
+
+impl Tally {
+    pub fn record(&mut self, correct: bool) {
+        self.asked += 1;
+        self.correct += correct as u32;
+    }
+}
+
+/// Statistics for one interval, split by how it was heard
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IntervalStat {
+    pub semitones: u8,
+    pub name: String,
+    pub harmonic: Tally,
+    pub ascending: Tally,
+    pub descending: Tally,
+    /// Correct answers over all presentations, 0.0-1.0
+    pub accuracy: f32,
+}
+
+struct ActiveQuestion {
+    id: String,
+    semitones: u8,
+    presentation: Presentation,
+    direction: Option<Direction>,
+    notes: Vec<AudioNote>,
+}
+
+/// One running interval drill
+pub struct IntervalDrill {
+    config: IntervalDrillConfig,
+    current: Option<ActiveQuestion>,
+    asked: u32,
+    answered: u32,
+    stats: BTreeMap<u8, [Tally; 3]>,
+}
+
+/// Full name of an interval size ("minor ninth")
+pub fn interval_name(semitones: u8) -> Option<&'static str> {
+    INTERVALS.get(semitones as usize).map(|(_, name, _)| *name)
+}
+
+/// Read an interval answer: "m9", "M10", "P12", "TT", "minor ninth", "major 10th", "aug 11", "d5"
+/// Returns the size in semitones; enharmonic names (A4/d5) read the same, as they sound the same
+pub fn parse_interval_answer(answer: &str) -> Option<u8> {
+    let compact: String = answer.trim().chars().filter(|c| !c.is_whitespace() && !matches!(c, '-' | '_' | '.')).collect();
+    let lower = compact.to_lowercase();
+    if matches!(lower.as_str(), "tt" | "tritone") {
+        return Some(6);
+    }
+
+    // Split into quality and number, written as digits ("10th") or a word ("tenth")
+    let (quality, number) = match compact.find(|c: char| c.is_ascii_digit()) {
+        Some(start) => {
+            let end = compact[start..].find(|c: char| !c.is_ascii_digit()).map_or(compact.len(), |i| start + i);
+            if !matches!(&lower[end..], "" | "st" | "nd" | "rd" | "th") {
+                return None;
+            }
+            (&compact[..start], compact[start..end].parse::<u8>().ok()?)
+        }
+        None => {
+            let (word, number) = NUMBER_WORDS.iter().find(|(word, _)| lower.ends_with(word))?;
+            (&compact[..compact.len() - word.len()], *number)
+        }
+    };
+    if !(1..=15).contains(&number) {
+        return None;
+    }
+
+    let simple = (number - 1) % 7;
+    let perfect = matches!(simple, 0 | 3 | 4);
+    let base = (MAJOR_SCALE[simple as usize] + 12 * ((number - 1) / 7)) as i8;
+    let offset: i8 = match (quality, quality.to_lowercase().as_str()) {
+        ("M", _) | (_, "maj" | "major") if !perfect => 0,
+        ("m", _) | (_, "min" | "minor") if !perfect => -1,
+        (_, "p" | "perf" | "perfect") | (_, "") if perfect => 0,
+        (_, "a" | "aug" | "augmented") => 1,
+        (_, "d" | "dim" | "diminished") => if perfect { -1 } else { -2 },
+        _ => return None,
+    };
+    u8::try_from(base + offset).ok()
+}
+
+/// Octave a spelled note is written in when it sounds at the given semitone above C0
+/// B#3 sounds as C4, so its written octave is one lower than the sounding one
+pub fn written_octave(note: &str, absolute: i32) -> i8 {
+    let (_, shift) = simplify_spelling(note);
+    (absolute.div_euclid(12) - shift as i32) as i8
+}
+
+/// Spell the two notes of an interval above a root, lower note first
+pub fn interval_notes(root: &str, octave: i8, semitones: u8) -> Result<Vec<AudioNote>, String> {
+    let (_, _, number) = INTERVALS.get(semitones as usize).ok_or_else(|| format!("Unsupported interval: {}", semitones))?;
+    let degree = (number - 1) % 7 + 1;
+    let upper = spell_interval_with_degree(root, semitones % 12, degree)
+        .map(|note| preferred_spelling(&note))
+        .map_err(|e| format!("Failed to spell interval: {}", e))?;
+    let root_pc = note_index(root).map_err(|e| format!("Failed to spell interval: {}", e))? as i32;
+    let lower_abs = root_pc + 12 * octave as i32;
+
+    let note = |note: String, absolute: i32| AudioNote { octave: written_octave(&note, absolute), note, is_common_tone: false, cents: 0.0 };
+    Ok(vec![note(root.to_string(), lower_abs), note(upper, lower_abs + semitones as i32)])
+}
+
+impl IntervalDrill {
+    pub fn new(mut config: IntervalDrillConfig) -> Result<Self, String> {
+        config.intervals.retain(|semitones| (1..=MAX_INTERVAL).contains(semitones));
+        config.intervals.sort_unstable();
+        config.intervals.dedup();
+        if config.intervals.is_empty() {
+            return Err(format!("Interval drill needs at least one interval between 1 and {} semitones", MAX_INTERVAL));
+        }
+        if config.presentations.is_empty() {
+            config.presentations.push(Presentation::Melodic);
+        }
+        if config.directions.is_empty() {
+            config.directions.push(Direction::Ascending);
+        }
+
+        Ok(Self { config, current: None, asked: 0, answered: 0, stats: BTreeMap::new() })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.config.question_count.is_some_and(|count| self.answered >= count)
+    }
+
+    /// Pick a new random interval; returns the question and the notes in playing order
+    pub fn next_question(&mut self, rng: &mut impl Rng) -> Result<(IntervalQuestion, Vec<AudioNote>), String> {
+        if self.is_finished() {
+            return Err("Interval drill is finished".to_string());
+        }
+
+        let semitones = *self.config.intervals.choose(rng).ok_or("No intervals to drill")?;
+        let presentation = *self.config.presentations.choose(rng).unwrap_or(&Presentation::Melodic);
+        let direction = match presentation {
+            Presentation::Harmonic => None,
+            Presentation::Melodic => self.config.directions.choose(rng).copied(),
+        };
+
+        let root = CHROMATIC_FLAT[rng.gen_range(0..CHROMATIC_FLAT.len())];
+        let octave = if semitones > 12 { COMPOUND_BASE_OCTAVE } else { SIMPLE_BASE_OCTAVE };
+        let mut notes = interval_notes(root, octave, semitones)?;
+        if direction == Some(Direction::Descending) {
+            notes.reverse();
+        }
+
+        self.asked += 1;
+        let id = Uuid::new_v4().to_string();
+        self.current = Some(ActiveQuestion { id: id.clone(), semitones, presentation, direction, notes: notes.clone() });
+
+        Ok((IntervalQuestion { id, number: self.asked, presentation, direction }, notes))
+    }
+
+    /// Presentation and notes of the current question, for replaying it
+    pub fn current_notes(&self) -> Option<(Presentation, Vec<AudioNote>)> {
+        self.current.as_ref().map(|q| (q.presentation, q.notes.clone()))
+    }
+
+    /// Grade an answer to the current question
+    pub fn answer(&mut self, question_id: &str, answer: &str) -> Result<IntervalAnswerResult, String> {
+        let question = match self.current.take() {
+            Some(q) if q.id == question_id => q,
+            other => {
+                self.current = other;
+                return Err("No matching question is waiting for an answer".to_string());
+            }
+        };
+
+        let expected = interval_name(question.semitones).unwrap_or_default().to_string();
+        let given = parse_interval_answer(answer);
+        let correct = given == Some(question.semitones);
+        self.answered += 1;
+
+        let slot = match (question.presentation, question.direction) {
+            (Presentation::Harmonic, _) | (_, None) => 0,
+            (Presentation::Melodic, Some(Direction::Ascending)) => 1,
+            (Presentation::Melodic, Some(Direction::Descending)) => 2,
+        };
+        self.stats.entry(question.semitones).or_default()[slot].record(correct);
+
+        let feedback = match given.and_then(|g| interval_name(g).map(|name| (g, name))) {
+            _ if correct => "Correct!".to_string(),
+            None => format!("Couldn't read \"{}\" as an interval; it was a {}", answer.trim(), expected),
+            Some((g, name)) if g % 12 == question.semitones % 12 => {
+                format!("Right interval, wrong octave: it was a {}, not a {}", expected, name)
+            }
+            Some((_, name)) => format!("It was a {}, not a {}", expected, name),
+        };
+
+        Ok(IntervalAnswerResult {
+            correct,
+            expected,
+            semitones: question.semitones,
+            notes: question.notes,
+            finished: self.is_finished(),
+            feedback,
+        })
+    }
+
+    /// Per-interval statistics, smallest interval first
+    pub fn stats(&self) -> Vec<IntervalStat> {
+        self.stats
+            .iter()
+            .map(|(semitones, [harmonic, ascending, descending])| {
+                let asked = harmonic.asked + ascending.asked + descending.asked;
+                let correct = harmonic.correct + ascending.correct + descending.correct;
+                IntervalStat {
+                    semitones: *semitones,
+                    name: interval_name(*semitones).unwrap_or_default().to_string(),
+                    harmonic: *harmonic,
+                    ascending: *ascending,
+                    descending: *descending,
+                    accuracy: if asked == 0 { 0.0 } else { correct as f32 / asked as f32 },
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_parse_interval_answers() {
+        assert_eq!(parse_interval_answer("m9"), Some(13));
+        assert_eq!(parse_interval_answer("M10"), Some(16));
+        assert_eq!(parse_interval_answer("P12"), Some(19));
+        assert_eq!(parse_interval_answer("minor ninth"), Some(13));
+        assert_eq!(parse_interval_answer("Major 13th"), Some(21));
+        assert_eq!(parse_interval_answer("aug 11"), Some(18));
+        assert_eq!(parse_interval_answer("perfect octave"), Some(12));
+        assert_eq!(parse_interval_answer("d5"), Some(6));
+        assert_eq!(parse_interval_answer("tritone"), Some(6));
+        assert_eq!(parse_interval_answer("P4"), Some(5));
+        assert_eq!(parse_interval_answer("minor fifth"), None);
+        assert_eq!(parse_interval_answer("M9x"), None);
+    }
+
+    #[test]
+    fn test_compound_interval_spelling() {
+        let notes = interval_notes("C", 3, 16).unwrap();
+        assert_eq!((notes[0].note.as_str(), notes[0].octave), ("C", 3));
+        assert_eq!((notes[1].note.as_str(), notes[1].octave), ("E", 4));
+
+        let notes = interval_notes("Db", 3, 13).unwrap();
+        assert_eq!((notes[1].note.as_str(), notes[1].octave), ("Ebb", 4));
+
+        // Gb up a perfect fourth is Cb, written in the octave above B
+        let notes = interval_notes("Gb", 4, 5).unwrap();
+        assert_eq!((notes[1].note.as_str(), notes[1].octave), ("Cb", 5));
+    }
+
+    #[test]
+    fn test_drill_directions_and_stats() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let config = IntervalDrillConfig {
+            intervals: vec![14],
+            presentations: vec![Presentation::Melodic],
+            directions: vec![Direction::Descending],
+            question_count: Some(2),
+        };
+        let mut drill = IntervalDrill::new(config).unwrap();
+
+        let (question, notes) = drill.next_question(&mut rng).unwrap();
+        assert_eq!(question.direction, Some(Direction::Descending));
+        assert!(notes[0].octave > notes[1].octave);
+        let result = drill.answer(&question.id, "M9").unwrap();
+        assert!(result.correct);
+
+        let (question, _) = drill.next_question(&mut rng).unwrap();
+        let result = drill.answer(&question.id, "M2").unwrap();
+        assert!(!result.correct);
+        assert!(result.feedback.starts_with("Right interval, wrong octave"));
+        assert!(result.finished);
+
+        let stats = drill.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].name, "major ninth");
+        assert_eq!(stats[0].descending, Tally { asked: 2, correct: 1 });
+        assert_eq!(stats[0].accuracy, 0.5);
+    }
+
+    #[test]
+    fn test_harmonic_questions_have_no_direction() {
+        let mut rng = StdRng::seed_from_u64(9);
+        let config = IntervalDrillConfig {
+            presentations: vec![Presentation::Harmonic],
+            directions: vec![Direction::Descending],
+            ..Default::default()
+        };
+        let mut drill = IntervalDrill::new(config).unwrap();
+        let (question, notes) = drill.next_question(&mut rng).unwrap();
+        assert_eq!(question.direction, None);
+        assert!(notes[0].octave <= notes[1].octave);
+        assert!(IntervalDrill::new(IntervalDrillConfig { intervals: vec![0, 40], ..Default::default() }).is_err());
+    }
+}
@@ -0,0 +1,301 @@
+// Intonation training
+// Plays an interval or chord with one note detuned by a set number of cents and asks
+// which note is out of tune and whether it is sharp or flat
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::intervals::{interval_name, interval_notes, written_octave, Presentation, Tally, MAX_INTERVAL};
+use crate::music::intervals::{chord_to_notes, CHORD_INTERVAL_SPECS};
+use crate::music::notes::{note_index, CHROMATIC_FLAT};
+use crate::music::types::AudioNote;
+
+/// Largest detune offered; past a semitone the note is simply a different note
+pub const MAX_CENTS: f32 = 100.0;
+
+/// Octave the lowest note of a question is played in
+const BASE_OCTAVE: i8 = 4;
+
+/// Which way the detuned note is off
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tuning {
+    Sharp,
+    Flat,
+}
+
+/// Intonation drill configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IntonationDrillConfig {
+    /// Interval sizes in semitones to draw from
+    pub intervals: Vec<u8>,
+    /// Chord qualities to draw from ("", "m", "7", ...), built on random roots
+    pub chords: Vec<String>,
+    /// Size of the detune in cents (1-100)
+    pub cents: f32,
+    /// Presentations to mix; one is picked per question
+    pub presentations: Vec<Presentation>,
+    /// Number of questions in a round (None = endless)
+    pub question_count: Option<u32>,
+}
+
+impl Default for IntonationDrillConfig {
+    fn default() -> Self {
+        Self {
+            intervals: vec![3, 4, 5, 7, 12],
+            chords: vec![String::new(), "m".to_string()],
+            cents: 25.0,
+            presentations: vec![Presentation::Harmonic],
+            question_count: Some(10),
+        }
+    }
+}
+
+/// Question sent to the frontend (which note is detuned stays on the backend)
+#[derive(Debug, Clone, Serialize)]
+pub struct IntonationQuestion {
+    pub id: String,
+    pub number: u32,
+    pub presentation: Presentation,
+    /// What is played: an interval name ("major third") or a chord symbol ("Ebm")
+    pub label: String,
+    /// Notes lowest first, to choose the detuned one from ("E4")
+    pub notes: Vec<String>,
+    pub cents: f32,
+}
+
+/// Outcome of a submitted answer
+#[derive(Debug, Clone, Serialize)]
+pub struct IntonationAnswerResult {
+    pub correct: bool,
+    pub note_correct: bool,
+    pub direction_correct: bool,
+    /// Index of the detuned note, lowest first
+    pub detuned_index: usize,
+    pub detuned_note: String,
+    pub tuning: Tuning,
+    pub cents: f32,
+    /// Answers so far in this drill
+    pub score: Tally,
+    pub finished: bool,
+    pub feedback: String,
+}
+
+enum Material {
+    Interval(u8),
+    Chord(String),
+}
+
+struct ActiveQuestion {
+    id: String,
+    presentation: Presentation,
+    notes: Vec<AudioNote>,
+    detuned_index: usize,
+    tuning: Tuning,
+}
+
+/// One running intonation drill
+pub struct IntonationDrill {
+    config: IntonationDrillConfig,
+    current: Option<ActiveQuestion>,
+    asked: u32,
+    answered: u32,
+    tally: Tally,
+}
+
+/// Written name of a note with its octave ("Eb4")
+fn note_label(note: &AudioNote) -> String {
+    format!("{}{}", note.note, note.octave)
+}
+
+/// Spell a chord upwards from its root in close position, lowest note first
+fn chord_notes(symbol: &str, octave: i8) -> Result<Vec<AudioNote>, String> {
+    let names = chord_to_notes(symbol).map_err(|e| format!("Failed to parse chord: {}", e))?;
+    let mut notes = Vec::with_capacity(names.len());
+    let mut absolute: Option<i32> = None;
+
+    for name in names {
+        let pitch_class = note_index(&name).map_err(|e| format!("Failed to parse chord: {}", e))? as i32;
+        let next = match absolute {
+            None => pitch_class + 12 * octave as i32,
+            Some(previous) => {
+                let step = (pitch_class - previous).rem_euclid(12);
+                previous + if step == 0 { 12 } else { step }
+            }
+        };
+        absolute = Some(next);
+        notes.push(AudioNote { octave: written_octave(&name, next), note: name, is_common_tone: false, cents: 0.0 });
+    }
+
+    Ok(notes)
+}
+
+impl IntonationDrill {
+    pub fn new(mut config: IntonationDrillConfig) -> Result<Self, String> {
+        config.intervals.retain(|semitones| (1..=MAX_INTERVAL).contains(semitones));
+        config.intervals.sort_unstable();
+        config.intervals.dedup();
+        if let Some(unknown) = config.chords.iter().find(|quality| !CHORD_INTERVAL_SPECS.contains_key(quality.as_str())) {
+            return Err(format!("Unknown chord quality: {}", unknown));
+        }
+        if config.intervals.is_empty() && config.chords.is_empty() {
+            return Err("Intonation drill needs at least one interval or chord".to_string());
+        }
+        if !(config.cents > 0.0 && config.cents <= MAX_CENTS) {
+            return Err(format!("Detune must be between 1 and {} cents", MAX_CENTS));
+        }
+        if config.presentations.is_empty() {
+            config.presentations.push(Presentation::Harmonic);
+        }
+
+        Ok(Self { config, current: None, asked: 0, answered: 0, tally: Tally::default() })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.config.question_count.is_some_and(|count| self.answered >= count)
+    }
+
+    /// Pick a new interval or chord and detune one of its notes; returns the question and the notes in playing order
+    pub fn next_question(&mut self, rng: &mut impl Rng) -> Result<(IntonationQuestion, Vec<AudioNote>), String> {
+        if self.is_finished() {
+            return Err("Intonation drill is finished".to_string());
+        }
+
+        let pool: Vec<Material> = self
+            .config
+            .intervals
+            .iter()
+            .map(|semitones| Material::Interval(*semitones))
+            .chain(self.config.chords.iter().map(|quality| Material::Chord(quality.clone())))
+            .collect();
+        let material = pool.choose(rng).ok_or("Nothing to drill")?;
+        let presentation = *self.config.presentations.choose(rng).unwrap_or(&Presentation::Harmonic);
+
+        let root = CHROMATIC_FLAT[rng.gen_range(0..CHROMATIC_FLAT.len())];
+        let (label, mut notes) = match material {
+            Material::Interval(semitones) => (
+                interval_name(*semitones).unwrap_or_default().to_string(),
+                interval_notes(root, BASE_OCTAVE - (*semitones > 12) as i8, *semitones)?,
+            ),
+            Material::Chord(quality) => {
+                let symbol = format!("{}{}", root, quality);
+                let notes = chord_notes(&symbol, BASE_OCTAVE)?;
+                (symbol, notes)
+            }
+        };
+
+        let detuned_index = rng.gen_range(0..notes.len());
+        let tuning = if rng.gen_bool(0.5) { Tuning::Sharp } else { Tuning::Flat };
+        notes[detuned_index].cents = match tuning {
+            Tuning::Sharp => self.config.cents,
+            Tuning::Flat => -self.config.cents,
+        };
+
+        self.asked += 1;
+        let id = Uuid::new_v4().to_string();
+        let question = IntonationQuestion {
+            id: id.clone(),
+            number: self.asked,
+            presentation,
+            label,
+            notes: notes.iter().map(note_label).collect(),
+            cents: self.config.cents,
+        };
+        self.current = Some(ActiveQuestion { id, presentation, notes: notes.clone(), detuned_index, tuning });
+
+        Ok((question, notes))
+    }
+
+    /// Presentation and notes of the current question, for replaying it
+    pub fn current_notes(&self) -> Option<(Presentation, Vec<AudioNote>)> {
+        self.current.as_ref().map(|q| (q.presentation, q.notes.clone()))
+    }
+
+    /// Grade an answer to the current question: the index of the note heard as out of tune
+    /// (lowest first) and which way it was off
+    pub fn answer(&mut self, question_id: &str, note_index: usize, tuning: Tuning) -> Result<IntonationAnswerResult, String> {
+        let question = match self.current.take() {
+            Some(q) if q.id == question_id => q,
+            other => {
+                self.current = other;
+                return Err("No matching question is waiting for an answer".to_string());
+            }
+        };
+
+        let note_correct = note_index == question.detuned_index;
+        let direction_correct = tuning == question.tuning;
+        let correct = note_correct && direction_correct;
+        self.answered += 1;
+        self.tally.record(correct);
+
+        let detuned_note = note_label(&question.notes[question.detuned_index]);
+        let off = match question.tuning {
+            Tuning::Sharp => "sharp",
+            Tuning::Flat => "flat",
+        };
+        let feedback = match (note_correct, direction_correct) {
+            (true, true) => "Correct!".to_string(),
+            (true, false) => format!("Right note, but {} was {}", detuned_note, off),
+            (false, _) => format!("It was {} that was {}", detuned_note, off),
+        };
+
+        Ok(IntonationAnswerResult {
+            correct,
+            note_correct,
+            direction_correct,
+            detuned_index: question.detuned_index,
+            detuned_note,
+            tuning: question.tuning,
+            cents: self.config.cents,
+            score: self.tally,
+            finished: self.is_finished(),
+            feedback,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_chord_notes_stack_upwards() {
+        let notes = chord_notes("Bm7", 4).unwrap();
+        let labels: Vec<String> = notes.iter().map(note_label).collect();
+        assert_eq!(labels, vec!["B4", "D5", "F#5", "A5"]);
+    }
+
+    #[test]
+    fn test_one_note_is_detuned() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let config = IntonationDrillConfig { cents: 15.0, question_count: Some(1), ..Default::default() };
+        let mut drill = IntonationDrill::new(config).unwrap();
+
+        let (question, notes) = drill.next_question(&mut rng).unwrap();
+        let detuned: Vec<&AudioNote> = notes.iter().filter(|n| n.cents != 0.0).collect();
+        assert_eq!(detuned.len(), 1);
+        assert_eq!(detuned[0].cents.abs(), 15.0);
+        assert_eq!(question.notes.len(), notes.len());
+
+        let index = notes.iter().position(|n| n.cents != 0.0).unwrap();
+        let tuning = if notes[index].cents > 0.0 { Tuning::Sharp } else { Tuning::Flat };
+        let result = drill.answer(&question.id, index, tuning).unwrap();
+        assert!(result.correct);
+        assert!(result.finished);
+        assert_eq!(result.score, Tally { asked: 1, correct: 1 });
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(IntonationDrill::new(IntonationDrillConfig { cents: 0.0, ..Default::default() }).is_err());
+        assert!(IntonationDrill::new(IntonationDrillConfig { cents: 150.0, ..Default::default() }).is_err());
+        assert!(IntonationDrill::new(IntonationDrillConfig { chords: vec!["xyz".to_string()], ..Default::default() }).is_err());
+        let empty = IntonationDrillConfig { intervals: Vec::new(), chords: Vec::new(), ..Default::default() };
+        assert!(IntonationDrill::new(empty).is_err());
+    }
+}
@@ -0,0 +1,8 @@
+pub mod battle;
+pub mod ear_training;
+pub mod grading;
+pub mod intervals;
+pub mod intonation;
+pub mod progressions;
+pub mod quiz;
+pub mod tutorial;
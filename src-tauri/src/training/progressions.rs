@@ -0,0 +1,346 @@
+// Cadence and progression ear training
+// Plays a short voiced progression from the progression generator; the student names the final cadence
+// or the full numeral sequence, checked against the analysis engine
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+use crate::music::analysis::{analyze_progression, Cadence};
+use crate::music::completion::complete_progression;
+use crate::music::intervals::chord_to_notes;
+use crate::music::notes::note_index;
+use crate::music::roman::roman_numeral_to_chord;
+use crate::music::types::NumeralMode;
+
+/// Candidate phrases requested from the generator per question
+const GENERATED_CANDIDATES: usize = 32;
+/// Shortest and longest phrases that still end on a recognizable cadence
+const MIN_LENGTH: usize = 3;
+const MAX_LENGTH: usize = 8;
+
+/// Words students use for each cadence
+const CADENCE_WORDS: &[(&str, Cadence)] = &[
+    ("authentic", Cadence::Authentic),
+    ("perfect", Cadence::Authentic),
+    ("pac", Cadence::Authentic),
+    ("iac", Cadence::Authentic),
+    ("plagal", Cadence::Plagal),
+    ("amen", Cadence::Plagal),
+    ("deceptive", Cadence::Deceptive),
+    ("interrupted", Cadence::Deceptive),
+    ("half", Cadence::Half),
+    ("imperfect", Cadence::Half),
+];
+
+/// What the student is asked to identify
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProgressionAnswerKind {
+    /// The cadence the phrase ends on
+    Cadence,
+    /// Every chord as a Roman numeral
+    Numerals,
+}
+
+/// Progression drill configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgressionDrillConfig {
+    pub kind: ProgressionAnswerKind,
+    /// Major keys to draw from
+    pub keys: Vec<String>,
+    /// Chords per phrase
+    pub length: usize,
+    /// Cadences to end phrases on
+    pub cadences: Vec<Cadence>,
+    /// Number of questions in a round (None = endless)
+    pub question_count: Option<u32>,
+}
+
+impl Default for ProgressionDrillConfig {
+    fn default() -> Self {
+        Self {
+            kind: ProgressionAnswerKind::Cadence,
+            keys: vec!["C".to_string(), "G".to_string(), "F".to_string(), "D".to_string()],
+            length: 4,
+            cadences: vec![Cadence::Authentic, Cadence::Plagal, Cadence::Deceptive, Cadence::Half],
+            question_count: Some(10),
+        }
+    }
+}
+
+/// Question sent to the frontend (the chords stay on the backend)
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressionQuestion {
+    pub id: String,
+    pub number: u32,
+    pub kind: ProgressionAnswerKind,
+    /// Key the phrase is in, so numerals can be worked out
+    pub key: String,
+    pub length: usize,
+}
+
+/// Outcome of a submitted answer
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressionAnswerResult {
+    pub correct: bool,
+    pub chords: Vec<String>,
+    pub numerals: Vec<String>,
+    pub cadence: Option<Cadence>,
+    /// For numeral answers, whether each chord was named correctly
+    pub chord_results: Vec<bool>,
+    pub finished: bool,
+    pub feedback: String,
+}
+
+struct ActiveQuestion {
+    id: String,
+    key: String,
+    chords: Vec<String>,
+}
+
+/// One running progression drill
+pub struct ProgressionDrill {
+    config: ProgressionDrillConfig,
+    current: Option<ActiveQuestion>,
+    asked: u32,
+    answered: u32,
+    correct: u32,
+}
+
+fn cadence_name(cadence: Cadence) -> &'static str {
+    match cadence {
+        Cadence::Authentic => "authentic",
+        Cadence::Plagal => "plagal",
+        Cadence::Deceptive => "deceptive",
+        Cadence::Half => "half",
+    }
+}
+
+/// Read a cadence answer ("authentic", "PAC", "half cadence", "Amen")
+pub fn parse_cadence_answer(answer: &str) -> Option<Cadence> {
+    let lower = answer.trim().to_lowercase();
+    let word = lower.strip_suffix("cadence").unwrap_or(&lower).trim();
+    CADENCE_WORDS.iter().find(|(name, _)| *name == word).map(|(_, cadence)| *cadence)
+}
+
+/// Root pitch class and pitch-class set of a chord, for comparing spellings
+fn chord_sound(chord: &str) -> Option<(u8, BTreeSet<u8>)> {
+    let notes = chord_to_notes(chord).ok()?;
+    let root = note_index(notes.first()?).ok()?;
+    let pitch_classes = notes.iter().filter_map(|n| note_index(n).ok()).collect();
+    Some((root, pitch_classes))
+}
+
+/// Whether a numeral names the given chord in the key
+fn numeral_matches(numeral: &str, chord: &str, key: &str) -> bool {
+    let Ok(named) = roman_numeral_to_chord(numeral, key, NumeralMode::Major, true) else {
+        return false;
+    };
+    chord_sound(&named).is_some_and(|sound| Some(sound) == chord_sound(chord))
+}
+
+impl ProgressionDrill {
+    pub fn new(mut config: ProgressionDrillConfig) -> Result<Self, String> {
+        config.keys.retain(|key| note_index(key).is_ok());
+        if config.keys.is_empty() {
+            return Err("Progression drill needs at least one major key".to_string());
+        }
+        if !(MIN_LENGTH..=MAX_LENGTH).contains(&config.length) {
+            return Err(format!("Phrases must be {} to {} chords long", MIN_LENGTH, MAX_LENGTH));
+        }
+        if config.cadences.is_empty() {
+            config.cadences = ProgressionDrillConfig::default().cadences;
+        }
+
+        Ok(Self { config, current: None, asked: 0, answered: 0, correct: 0 })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.config.question_count.is_some_and(|count| self.answered >= count)
+    }
+
+    /// Generate a new phrase; returns the question and the chords to play
+    pub fn next_question(&mut self, rng: &mut impl Rng) -> Result<(ProgressionQuestion, Vec<String>), String> {
+        if self.is_finished() {
+            return Err("Progression drill is finished".to_string());
+        }
+
+        let key = self.config.keys.choose(rng).cloned().unwrap_or_default();
+        let cadence = *self.config.cadences.choose(rng).unwrap_or(&Cadence::Authentic);
+        let candidates = complete_progression(&[], &key, self.config.length, GENERATED_CANDIDATES)
+            .map_err(|e| format!("Failed to generate progression: {}", e))?;
+
+        // Prefer phrases the analysis engine hears as ending on the chosen cadence
+        let ends_on = |chords: &[String], cadence: Cadence| {
+            analyze_progression(chords, &key).last().and_then(|a| a.cadence) == Some(cadence)
+        };
+        let matching: Vec<&Vec<String>> =
+            candidates.iter().map(|c| &c.chords).filter(|chords| ends_on(chords, cadence)).collect();
+        let any_cadence: Vec<&Vec<String>> = candidates
+            .iter()
+            .map(|c| &c.chords)
+            .filter(|chords| self.config.cadences.iter().any(|c| ends_on(chords, *c)))
+            .collect();
+        let chords = matching
+            .choose(rng)
+            .or_else(|| any_cadence.choose(rng))
+            .map(|chords| chords.to_vec())
+            .ok_or("No progression ends on the requested cadences")?;
+
+        self.asked += 1;
+        let id = Uuid::new_v4().to_string();
+        self.current = Some(ActiveQuestion { id: id.clone(), key: key.clone(), chords: chords.clone() });
+
+        let question = ProgressionQuestion {
+            id,
+            number: self.asked,
+            kind: self.config.kind,
+            key,
+            length: self.config.length,
+        };
+        Ok((question, chords))
+    }
+
+    /// Chords of the current question, for replaying it
+    pub fn current_chords(&self) -> Option<Vec<String>> {
+        self.current.as_ref().map(|q| q.chords.clone())
+    }
+
+    /// Grade an answer: a cadence name, or numerals separated by spaces, commas or dashes
+    pub fn answer(&mut self, question_id: &str, answer: &str) -> Result<ProgressionAnswerResult, String> {
+        let question = match self.current.take() {
+            Some(q) if q.id == question_id => q,
+            other => {
+                self.current = other;
+                return Err("No matching question is waiting for an answer".to_string());
+            }
+        };
+
+        let analysis = analyze_progression(&question.chords, &question.key);
+        let numerals: Vec<String> = analysis.iter().map(|a| a.numeral.clone().unwrap_or_default()).collect();
+        let cadence = analysis.last().and_then(|a| a.cadence);
+
+        let (correct, chord_results, feedback) = match self.config.kind {
+            ProgressionAnswerKind::Cadence => {
+                let expected = cadence.map_or("no", cadence_name);
+                match parse_cadence_answer(answer) {
+                    Some(given) if Some(given) == cadence => (true, Vec::new(), "Correct!".to_string()),
+                    Some(given) => (
+                        false,
+                        Vec::new(),
+                        format!("It was a {} cadence, not a {} cadence", expected, cadence_name(given)),
+                    ),
+                    None => (
+                        false,
+                        Vec::new(),
+                        format!("Couldn't read \"{}\" as a cadence; it was a {} cadence", answer.trim(), expected),
+                    ),
+                }
+            }
+            ProgressionAnswerKind::Numerals => {
+                let given: Vec<&str> = answer
+                    .split(|c: char| c.is_whitespace() || matches!(c, ',' | '-' | '–'))
+                    .filter(|s| !s.is_empty())
+                    .collect();
+                let chord_results: Vec<bool> = question
+                    .chords
+                    .iter()
+                    .enumerate()
+                    .map(|(i, chord)| given.get(i).is_some_and(|n| numeral_matches(n, chord, &question.key)))
+                    .collect();
+                let correct = given.len() == question.chords.len() && chord_results.iter().all(|ok| *ok);
+                let feedback = if correct {
+                    "Correct!".to_string()
+                } else {
+                    let right = chord_results.iter().filter(|ok| **ok).count();
+                    format!("{} of {} chords right: it was {}", right, question.chords.len(), numerals.join(" – "))
+                };
+                (correct, chord_results, feedback)
+            }
+        };
+
+        self.answered += 1;
+        self.correct += correct as u32;
+
+        Ok(ProgressionAnswerResult {
+            correct,
+            chords: question.chords,
+            numerals,
+            cadence,
+            chord_results,
+            finished: self.is_finished(),
+            feedback,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_parse_cadence_answers() {
+        assert_eq!(parse_cadence_answer("Authentic"), Some(Cadence::Authentic));
+        assert_eq!(parse_cadence_answer("half cadence"), Some(Cadence::Half));
+        assert_eq!(parse_cadence_answer("Amen"), Some(Cadence::Plagal));
+        assert_eq!(parse_cadence_answer("interrupted"), Some(Cadence::Deceptive));
+        assert_eq!(parse_cadence_answer("sideways"), None);
+    }
+
+    #[test]
+    fn test_generated_phrase_ends_on_requested_cadence() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for cadence in [Cadence::Authentic, Cadence::Plagal, Cadence::Deceptive, Cadence::Half] {
+            let config = ProgressionDrillConfig { cadences: vec![cadence], ..Default::default() };
+            let mut drill = ProgressionDrill::new(config).unwrap();
+            let (question, chords) = drill.next_question(&mut rng).unwrap();
+            assert_eq!(chords.len(), 4);
+
+            let result = drill.answer(&question.id, cadence_name(cadence)).unwrap();
+            assert!(result.correct, "{:?} {:?}", cadence, result.chords);
+        }
+    }
+
+    #[test]
+    fn test_numeral_answers() {
+        let mut drill = ProgressionDrill::new(ProgressionDrillConfig {
+            kind: ProgressionAnswerKind::Numerals,
+            ..Default::default()
+        })
+        .unwrap();
+        drill.current = Some(ActiveQuestion {
+            id: "q".to_string(),
+            key: "C".to_string(),
+            chords: vec!["C".to_string(), "Am".to_string(), "F".to_string(), "G".to_string()],
+        });
+
+        let result = drill.answer("q", "I - vi - IV - V").unwrap();
+        assert!(result.correct);
+        assert_eq!(result.numerals, vec!["I", "vi", "IV", "V"]);
+        assert_eq!(result.cadence, Some(Cadence::Half));
+
+        drill.current = Some(ActiveQuestion {
+            id: "r".to_string(),
+            key: "C".to_string(),
+            chords: vec!["C".to_string(), "Am".to_string(), "F".to_string(), "G".to_string()],
+        });
+        let result = drill.answer("r", "I ii IV V").unwrap();
+        assert!(!result.correct);
+        assert_eq!(result.chord_results, vec![true, false, true, true]);
+        assert!(result.feedback.starts_with("3 of 4 chords right"));
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let config = ProgressionDrillConfig { length: 2, ..Default::default() };
+        assert!(ProgressionDrill::new(config).is_err());
+        let config = ProgressionDrillConfig { keys: vec!["H".to_string()], ..Default::default() };
+        assert!(ProgressionDrill::new(config).is_err());
+    }
+}
@@ -0,0 +1,328 @@
+// Name-that-chord quiz game
+// Picks random chords from a configurable quality pool, grades answers, and scores streaks with timing bonuses
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use uuid::Uuid;
+
+use super::grading::{grade_answer, quality_intervals, GradeResult, GradingOptions};
+use crate::music::intervals::chord_to_notes;
+use crate::music::notes::CHROMATIC_FLAT;
+
+/// Points for a correct answer before bonuses
+const BASE_POINTS: f32 = 100.0;
+/// Maximum timing bonus, earned by answering instantly
+const MAX_TIME_BONUS: f32 = 50.0;
+/// Answers slower than this (seconds) earn no timing bonus
+const TIME_BONUS_WINDOW: f32 = 10.0;
+/// Each streak step adds this multiplier, capped at STREAK_CAP steps
+const STREAK_STEP: f32 = 0.1;
+const STREAK_CAP: u32 = 10;
+/// Number of high scores kept
+pub const MAX_HIGH_SCORES: usize = 10;
+
+/// Quiz configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuizConfig {
+    /// Chord suffixes to draw from ("" = major, "m", "dim", "maj7", ...)
+    pub qualities: Vec<String>,
+    /// When true the answer must include the root, otherwise naming the quality is enough
+    pub require_root: bool,
+    /// Number of questions in a round (None = endless)
+    pub question_count: Option<u32>,
+}
+
+impl Default for QuizConfig {
+    fn default() -> Self {
+        Self {
+            qualities: vec!["".to_string(), "m".to_string(), "dim".to_string(), "aug".to_string()],
+            require_root: false,
+            question_count: Some(10),
+        }
+    }
+}
+
+impl QuizConfig {
+    /// Drop unknown qualities, failing when none are left
+    pub(crate) fn with_known_qualities(mut self) -> Result<Self, String> {
+        self.qualities.retain(|q| quality_intervals(q).is_some());
+        if self.qualities.is_empty() {
+            return Err("Quiz needs at least one known chord quality".to_string());
+        }
+        Ok(self)
+    }
+
+    /// Pick a random chord from the quality pool; returns the chord and its notes
+    pub(crate) fn random_chord(&self, rng: &mut impl Rng) -> Result<(String, Vec<String>), String> {
+        let root = CHROMATIC_FLAT[rng.gen_range(0..CHROMATIC_FLAT.len())];
+        let quality = self.qualities.choose(rng).cloned().unwrap_or_default();
+        let chord = format!("{}{}", root, quality);
+        let notes = chord_to_notes(&chord).map_err(|e| format!("Failed to build quiz chord: {}", e))?;
+        Ok((chord, notes))
+    }
+}
+
+/// Question sent to the frontend (the chord itself stays on the backend)
+#[derive(Debug, Clone, Serialize)]
+pub struct QuizQuestion {
+    pub id: String,
+    pub number: u32,
+}
+
+/// Outcome of a submitted answer
+#[derive(Debug, Clone, Serialize)]
+pub struct QuizAnswerResult {
+    pub correct: bool,
+    /// The chord that was played
+    pub expected: String,
+    pub points: u32,
+    pub streak: u32,
+    pub score: u32,
+    pub elapsed_secs: f32,
+    pub finished: bool,
+    /// Explanation of the answer ("Right root, but the chord is minor, not major")
+    pub feedback: String,
+}
+
+/// Final or running summary of a game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuizSummary {
+    pub score: u32,
+    pub best_streak: u32,
+    pub answered: u32,
+    pub correct: u32,
+}
+
+/// Persisted high score entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: u32,
+    pub best_streak: u32,
+    pub accuracy: f32,
+    /// Unix timestamp (seconds)
+    pub achieved_at: u64,
+}
+
+struct ActiveQuestion {
+    id: String,
+    chord: String,
+    asked_at: Instant,
+}
+
+/// One running quiz game
+pub struct QuizGame {
+    config: QuizConfig,
+    current: Option<ActiveQuestion>,
+    score: u32,
+    streak: u32,
+    best_streak: u32,
+    asked: u32,
+    answered: u32,
+    correct: u32,
+}
+
+/// Check an answer against the expected chord
+/// Chords are compared by root pitch class and interval content, so enharmonics and aliases match
+#[allow(dead_code)]
+pub fn answer_matches(answer: &str, expected: &str, require_root: bool) -> bool {
+    grade_quiz_answer(answer, expected, require_root).correct
+}
+
+pub(crate) fn grade_quiz_answer(answer: &str, expected: &str, require_root: bool) -> GradeResult {
+    grade_answer(answer, expected, &GradingOptions { require_root, accept_enharmonics: true, ..Default::default() })
+}
+
+/// Points for a correct answer given the streak it extends and the response time
+pub(crate) fn points_for(streak: u32, elapsed_secs: f32) -> u32 {
+    let time_bonus = MAX_TIME_BONUS * (1.0 - elapsed_secs / TIME_BONUS_WINDOW).clamp(0.0, 1.0);
+    let multiplier = 1.0 + streak.min(STREAK_CAP) as f32 * STREAK_STEP;
+    ((BASE_POINTS + time_bonus) * multiplier).round() as u32
+}
+
+impl QuizGame {
+    pub fn new(config: QuizConfig) -> Result<Self, String> {
+        Ok(Self {
+            config: config.with_known_qualities()?,
+            current: None,
+            score: 0,
+            streak: 0,
+            best_streak: 0,
+            asked: 0,
+            answered: 0,
+            correct: 0,
+        })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.config.question_count.is_some_and(|count| self.answered >= count)
+    }
+
+    /// Pick a new random chord; returns the question and the chord notes to play
+    pub fn next_question(&mut self, rng: &mut impl Rng) -> Result<(QuizQuestion, Vec<String>), String> {
+        if self.is_finished() {
+            return Err("Quiz is finished".to_string());
+        }
+
+        let (chord, notes) = self.config.random_chord(rng)?;
+        self.asked += 1;
+        let id = Uuid::new_v4().to_string();
+        self.current = Some(ActiveQuestion { id: id.clone(), chord, asked_at: Instant::now() });
+
+        Ok((QuizQuestion { id, number: self.asked }, notes))
+    }
+
+    /// Notes of the current question, for replaying it
+    pub fn current_notes(&self) -> Option<Vec<String>> {
+        self.current.as_ref().and_then(|q| chord_to_notes(&q.chord).ok())
+    }
+
+    /// Grade an answer to the current question at the given moment
+    pub fn answer_at(&mut self, question_id: &str, answer: &str, now: Instant) -> Result<QuizAnswerResult, String> {
+        let question = match self.current.take() {
+            Some(q) if q.id == question_id => q,
+            other => {
+                self.current = other;
+                return Err("No matching question is waiting for an answer".to_string());
+            }
+        };
+
+        let elapsed_secs = now.saturating_duration_since(question.asked_at).as_secs_f32();
+        let grade = grade_quiz_answer(answer, &question.chord, self.config.require_root);
+        let correct = grade.correct;
+        self.answered += 1;
+
+        let points = if correct {
+            let points = points_for(self.streak, elapsed_secs);
+            self.correct += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+            self.score += points;
+            points
+        } else {
+            self.streak = 0;
+            0
+        };
+
+        Ok(QuizAnswerResult {
+            correct,
+            expected: question.chord,
+            points,
+            streak: self.streak,
+            score: self.score,
+            elapsed_secs,
+            finished: self.is_finished(),
+            feedback: grade.feedback,
+        })
+    }
+
+    pub fn summary(&self) -> QuizSummary {
+        QuizSummary {
+            score: self.score,
+            best_streak: self.best_streak,
+            answered: self.answered,
+            correct: self.correct,
+        }
+    }
+}
+
+/// Insert a finished game into a high score table (best first)
+/// Returns the 0-based rank if it made the table
+pub fn record_high_score(table: &mut Vec<HighScore>, summary: &QuizSummary, achieved_at: u64) -> Option<usize> {
+    if summary.answered == 0 {
+        return None;
+    }
+
+    let entry = HighScore {
+        score: summary.score,
+        best_streak: summary.best_streak,
+        accuracy: summary.correct as f32 / summary.answered as f32,
+        achieved_at,
+    };
+
+    let rank = table.iter().position(|existing| entry.score > existing.score).unwrap_or(table.len());
+    if rank >= MAX_HIGH_SCORES {
+        return None;
+    }
+
+    table.insert(rank, entry);
+    table.truncate(MAX_HIGH_SCORES);
+    Some(rank)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::time::Duration;
+
+    #[test]
+    fn test_answer_matching() {
+        assert!(answer_matches("minor", "Am", false));
+        assert!(answer_matches("m", "Ebm", false));
+        assert!(answer_matches("D#m", "Ebm", true));
+        assert!(!answer_matches("minor", "Am", true));
+        assert!(!answer_matches("Bm", "Am", false));
+        assert!(!answer_matches("nonsense", "A", false));
+        assert!(answer_matches("e flat minor", "Ebm", true));
+    }
+
+    #[test]
+    fn test_streak_and_timing_bonus() {
+        assert_eq!(points_for(0, 0.0), 150);
+        assert_eq!(points_for(0, 20.0), 100);
+        assert_eq!(points_for(2, 20.0), 120);
+        assert_eq!(points_for(50, 20.0), 200);
+    }
+
+    #[test]
+    fn test_game_loop() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let config = QuizConfig { qualities: vec!["m".to_string()], require_root: false, question_count: Some(2) };
+        let mut game = QuizGame::new(config).unwrap();
+
+        let (question, notes) = game.next_question(&mut rng).unwrap();
+        assert_eq!(notes.len(), 3);
+        let result = game.answer_at(&question.id, "minor", Instant::now()).unwrap();
+        assert!(result.correct);
+        assert_eq!(result.streak, 1);
+
+        let (question, _) = game.next_question(&mut rng).unwrap();
+        let later = Instant::now() + Duration::from_secs(1);
+        let result = game.answer_at(&question.id, "major", later).unwrap();
+        assert!(!result.correct);
+        assert_eq!(result.streak, 0);
+        assert!(result.finished);
+        assert!(game.next_question(&mut rng).is_err());
+    }
+
+    #[test]
+    fn test_wrong_question_id_keeps_question() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut game = QuizGame::new(QuizConfig::default()).unwrap();
+        let (question, _) = game.next_question(&mut rng).unwrap();
+
+        assert!(game.answer_at("stale", "m", Instant::now()).is_err());
+        assert!(game.answer_at(&question.id, "m", Instant::now()).is_ok());
+    }
+
+    #[test]
+    fn test_high_score_table() {
+        let mut table = Vec::new();
+        let summary = |score| QuizSummary { score, best_streak: 1, answered: 2, correct: 1 };
+
+        assert_eq!(record_high_score(&mut table, &summary(100), 0), Some(0));
+        assert_eq!(record_high_score(&mut table, &summary(300), 0), Some(0));
+        assert_eq!(record_high_score(&mut table, &summary(200), 0), Some(1));
+        assert_eq!(table.iter().map(|h| h.score).collect::<Vec<_>>(), vec![300, 200, 100]);
+
+        for _ in 0..MAX_HIGH_SCORES {
+            record_high_score(&mut table, &summary(1000), 0);
+        }
+        assert_eq!(table.len(), MAX_HIGH_SCORES);
+        assert_eq!(record_high_score(&mut table, &summary(1), 0), None);
+    }
+}
@@ -0,0 +1,294 @@
+// Onboarding tutorial checks
+// Each interactive tutorial step is verified here against the theory and voicing engines, so the
+// frontend only reports what the user built and shows the feedback
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use crate::music::analysis::analyze_progression;
+use crate::music::chords::parse_chord;
+use crate::music::identify::name_midi_chord;
+use crate::music::intervals::chord_to_notes;
+use crate::music::notes::note_index;
+use crate::music::roman::parse_roman_numeral;
+use crate::music::voice_leading::midi_to_note;
+
+/// How a voiced chord must be laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoicingShape {
+    /// Every note within an octave
+    Close,
+    /// A close voicing with the second note from the top dropped an octave
+    Drop2,
+}
+
+/// What a step asks the user to build
+#[derive(Debug, Clone, Copy)]
+pub enum StepCheck {
+    /// A chord on the canvas with this root and these chord tones, however it is spelled
+    Chord { chord: &'static str },
+    /// Consecutive canvas chords reading as these numerals in the key; sevenths and extensions are allowed
+    Progression { key: &'static str, numerals: &'static [&'static str], at_end: bool },
+    /// Keyboard notes voicing the chord in the given shape, each chord tone once
+    Voicing { chord: &'static str, shape: VoicingShape },
+}
+
+/// One interactive tutorial step
+#[derive(Debug, Clone, Serialize)]
+pub struct TutorialStep {
+    pub id: &'static str,
+    pub title: &'static str,
+    /// Instruction shown to the user
+    pub prompt: &'static str,
+    #[serde(skip)]
+    pub check: StepCheck,
+}
+
+/// Onboarding steps in teaching order
+pub const TUTORIAL_STEPS: &[TutorialStep] = &[
+    TutorialStep {
+        id: "first-chord",
+        title: "Your first chord",
+        prompt: "Place a C major chord on the canvas",
+        check: StepCheck::Chord { chord: "C" },
+    },
+    TutorialStep {
+        id: "minor-seventh",
+        title: "Seventh chords",
+        prompt: "Place a D minor seventh chord on the canvas",
+        check: StepCheck::Chord { chord: "Dm7" },
+    },
+    TutorialStep {
+        id: "two-five-one",
+        title: "The ii–V–I",
+        prompt: "Build a ii–V–I in G",
+        check: StepCheck::Progression { key: "G", numerals: &["ii", "V", "I"], at_end: false },
+    },
+    TutorialStep {
+        id: "authentic-cadence",
+        title: "Cadences",
+        prompt: "End a progression in F with V–I",
+        check: StepCheck::Progression { key: "F", numerals: &["V", "I"], at_end: true },
+    },
+    TutorialStep {
+        id: "close-voicing",
+        title: "Close position",
+        prompt: "Voice Cmaj7 in close position on the keyboard",
+        check: StepCheck::Voicing { chord: "Cmaj7", shape: VoicingShape::Close },
+    },
+    TutorialStep {
+        id: "drop-2-voicing",
+        title: "Drop-2 voicings",
+        prompt: "Voice Cmaj7 in drop-2 on the keyboard",
+        check: StepCheck::Voicing { chord: "Cmaj7", shape: VoicingShape::Drop2 },
+    },
+];
+
+/// What the user has built so far, as reported by the frontend
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TutorialState {
+    /// Canvas chords in order
+    pub chords: Vec<String>,
+    /// MIDI notes held or placed on the keyboard
+    pub notes: Vec<u8>,
+}
+
+/// Outcome of checking a step
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepVerdict {
+    pub step_id: String,
+    pub passed: bool,
+    /// Confirmation when passed, otherwise a hint at what to change
+    pub feedback: String,
+}
+
+pub fn find_step(id: &str) -> Result<&'static TutorialStep, String> {
+    TUTORIAL_STEPS.iter().find(|step| step.id == id).ok_or_else(|| format!("Unknown tutorial step: {}", id))
+}
+
+/// Pitch classes of a chord symbol, ignoring any slash bass
+fn pitch_classes(chord: &str) -> Result<BTreeSet<u8>, String> {
+    let main = chord.split('/').next().unwrap_or(chord);
+    chord_to_notes(main)
+        .and_then(|notes| notes.iter().map(|note| note_index(note)).collect())
+        .map_err(|e| format!("Invalid chord {}: {}", chord, e))
+}
+
+fn root_index(chord: &str) -> Option<u8> {
+    parse_chord(chord.trim()).ok().and_then(|parsed| note_index(&parsed.root).ok())
+}
+
+fn check_chord(target: &str, state: &TutorialState) -> Result<(bool, String), String> {
+    let (root, tones) = (root_index(target), pitch_classes(target)?);
+    let found = state
+        .chords
+        .iter()
+        .find(|chord| root_index(chord) == root && pitch_classes(chord.trim()).ok().as_ref() == Some(&tones));
+    Ok(match found {
+        Some(chord) => (true, format!("{} is on the canvas", chord.trim())),
+        None if state.chords.is_empty() => (false, format!("Add {} to the canvas", target)),
+        None => (false, format!("None of the canvas chords is {} yet", target)),
+    })
+}
+
+/// Whether an analyzed numeral is the wanted one: same degree, quality and accidental
+/// "ii7" reads as "ii" and "Vmaj7" doesn't read as "v"
+fn numeral_matches(found: Option<&str>, wanted: &str) -> bool {
+    let found = found.and_then(|numeral| parse_roman_numeral(numeral).ok());
+    let (Some(found), Ok(wanted)) = (found, parse_roman_numeral(wanted)) else {
+        return false;
+    };
+    found.applied_to.is_none()
+        && (found.degree, found.is_minor, found.accidental) == (wanted.degree, wanted.is_minor, wanted.accidental)
+}
+
+fn check_progression(
+    key: &str,
+    numerals: &[&str],
+    at_end: bool,
+    state: &TutorialState,
+) -> Result<(bool, String), String> {
+    let wanted = numerals.join("–");
+    let missing = numerals.len().saturating_sub(state.chords.len());
+    if missing > 0 {
+        let plural = if missing == 1 { "" } else { "s" };
+        return Ok((false, format!("Add {} more chord{} to build {} in {}", missing, plural, wanted, key)));
+    }
+
+    let analysis = analyze_progression(&state.chords, key);
+    let last_start = analysis.len() - numerals.len();
+    let first_start = if at_end { last_start } else { 0 };
+    let found = (first_start..=last_start).find(|&start| {
+        let mut window = analysis[start..].iter().zip(numerals);
+        window.all(|(chord, numeral)| numeral_matches(chord.numeral.as_deref(), numeral))
+    });
+    if let Some(start) = found {
+        let chords: Vec<&str> = state.chords[start..start + numerals.len()].iter().map(|chord| chord.trim()).collect();
+        return Ok((true, format!("{} is {} in {}", chords.join("–"), wanted, key)));
+    }
+
+    let read: Vec<&str> = analysis[last_start..].iter().map(|chord| chord.numeral.as_deref().unwrap_or("?")).collect();
+    let goal = if at_end { format!("end on {}", wanted) } else { format!("build {}", wanted) };
+    Ok((false, format!("Your last chords read as {} in {}; {}", read.join("–"), key, goal)))
+}
+
+/// Whether sorted notes sit within an octave
+fn is_close(notes: &[u8]) -> bool {
+    match (notes.first(), notes.last()) {
+        (Some(low), Some(high)) => high - low < 12,
+        _ => false,
+    }
+}
+
+/// Whether sorted notes are a close voicing with the second note from the top dropped an octave
+fn is_drop2(notes: &[u8]) -> bool {
+    if notes.len() < 4 {
+        return false;
+    }
+    // The dropped note is now the lowest; taking it back up must restore a close voicing with it second from the top
+    let lowest = notes[0] + 12;
+    let mut raised = notes[1..].to_vec();
+    raised.push(lowest);
+    raised.sort_unstable();
+    is_close(&raised) && raised[raised.len() - 2] == lowest
+}
+
+fn note_names(notes: &[u8]) -> String {
+    notes.iter().map(|&midi| midi_to_note(midi).unwrap_or_default()).collect::<Vec<_>>().join(" ")
+}
+
+fn check_voicing(chord: &str, shape: VoicingShape, state: &TutorialState) -> Result<(bool, String), String> {
+    let tones = pitch_classes(chord)?;
+    let mut notes = state.notes.clone();
+    notes.sort_unstable();
+    if notes.is_empty() {
+        return Ok((false, format!("Play the notes of {} on the keyboard", chord)));
+    }
+
+    let played: BTreeSet<u8> = notes.iter().map(|midi| midi % 12).collect();
+    if played != tones {
+        let key = parse_chord(chord).map(|parsed| parsed.root).unwrap_or_default();
+        let feedback = match name_midi_chord(&notes, &key) {
+            Some(name) => format!("Those notes spell {}, not {}", name, chord),
+            None => format!("Those notes don't spell {}", chord),
+        };
+        return Ok((false, feedback));
+    }
+    if played.len() != notes.len() {
+        return Ok((false, format!("Play each note of {} once", chord)));
+    }
+
+    let (fits, name, hint) = match shape {
+        VoicingShape::Close => (is_close(&notes), "close position", "Keep every note within an octave"),
+        VoicingShape::Drop2 => (
+            is_drop2(&notes),
+            "drop-2",
+            "Start from a close voicing and drop the second note from the top down an octave",
+        ),
+    };
+    Ok(match fits {
+        true => (true, format!("{} is {} in {}", note_names(&notes), chord, name)),
+        false => (false, hint.to_string()),
+    })
+}
+
+/// Check a tutorial step against what the user has built
+pub fn verify_exercise(step_id: &str, state: &TutorialState) -> Result<StepVerdict, String> {
+    let step = find_step(step_id)?;
+    let (passed, feedback) = match step.check {
+        StepCheck::Chord { chord } => check_chord(chord, state)?,
+        StepCheck::Progression { key, numerals, at_end } => check_progression(key, numerals, at_end, state)?,
+        StepCheck::Voicing { chord, shape } => check_voicing(chord, shape, state)?,
+    };
+    Ok(StepVerdict { step_id: step.id.to_string(), passed, feedback })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chords(chords: &[&str]) -> TutorialState {
+        TutorialState { chords: chords.iter().map(|chord| chord.to_string()).collect(), notes: Vec::new() }
+    }
+
+    fn notes(notes: &[u8]) -> TutorialState {
+        TutorialState { chords: Vec::new(), notes: notes.to_vec() }
+    }
+
+    fn passed(step_id: &str, state: &TutorialState) -> bool {
+        verify_exercise(step_id, state).unwrap().passed
+    }
+
+    #[test]
+    fn test_chord_and_progression_steps() {
+        assert!(passed("minor-seventh", &chords(&["C", "Dm7"])));
+        assert!(!passed("minor-seventh", &chords(&["F6"])));
+
+        // Sevenths are fine, and the ii–V–I can sit anywhere
+        let verdict = verify_exercise("two-five-one", &chords(&["G", "Am7", "D7", "Gmaj7"])).unwrap();
+        assert!(verdict.passed);
+        assert_eq!(verdict.feedback, "Am7–D7–Gmaj7 is ii–V–I in G");
+        let verdict = verify_exercise("two-five-one", &chords(&["G", "C", "D"])).unwrap();
+        assert_eq!(verdict.feedback, "Your last chords read as I–IV–V in G; build ii–V–I");
+        assert!(!passed("two-five-one", &chords(&["Am", "D"])));
+
+        assert!(passed("authentic-cadence", &chords(&["F", "Bb", "C7", "F"])));
+        assert!(!passed("authentic-cadence", &chords(&["C7", "F", "Bb"])));
+        assert!(verify_exercise("no-such-step", &chords(&[])).is_err());
+    }
+
+    #[test]
+    fn test_voicing_steps() {
+        // C4 E4 G4 B4 in close position; drop G4 to G3 for drop-2
+        assert!(passed("close-voicing", &notes(&[60, 64, 67, 71])));
+        assert!(!passed("close-voicing", &notes(&[55, 60, 64, 71])));
+        assert!(passed("drop-2-voicing", &notes(&[55, 60, 64, 71])));
+        assert!(passed("drop-2-voicing", &notes(&[59, 64, 67, 72])));
+        assert!(!passed("drop-2-voicing", &notes(&[60, 64, 67, 71])));
+
+        let verdict = verify_exercise("drop-2-voicing", &notes(&[57, 60, 64, 67])).unwrap();
+        assert_eq!(verdict.feedback, "Those notes spell Am7, not Cmaj7");
+        assert!(!passed("drop-2-voicing", &notes(&[55, 60, 64, 71, 76])));
+    }
+}
@@ -1 +1,4 @@
-pub mod worksheet;
\ No newline at end of file
+pub mod project;
+pub mod song;
+pub mod versioned;
+pub mod worksheet;
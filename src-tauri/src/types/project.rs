@@ -0,0 +1,97 @@
+// Saved projects
+// The user's working state in one .maestro file: progressions, worksheets, key and voicing settings
+// Fields added later take their defaults when missing, so only renames and reshapes need a migration
+
+use serde::{Deserialize, Serialize};
+
+use super::versioned::{self, Migration, Versioned};
+use super::worksheet::WorksheetConfig;
+
+/// File extension of saved projects
+pub const PROJECT_EXTENSION: &str = "maestro";
+
+/// A named chord progression
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectProgression {
+    pub name: String,
+    pub chords: Vec<String>,
+}
+
+/// How chords are voiced for playback, as passed to play_chord
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoicingSettings {
+    /// "close", "wide" or "common-tone"
+    pub style: String,
+    pub base_octave: i8,
+    /// Voicing preset ("piano", "guitar-friendly", "satb"); None is the piano
+    pub preset: Option<String>,
+}
+
+impl Default for VoicingSettings {
+    fn default() -> Self {
+        Self { style: "close".to_string(), base_octave: 4, preset: None }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Project {
+    pub name: String,
+    /// Key the user is working in ("C", "F#m")
+    pub key: String,
+    pub progressions: Vec<ProjectProgression>,
+    /// Each worksheet keeps its own version and upgrades through the worksheet migrations
+    #[serde(with = "versioned::list")]
+    pub worksheets: Vec<WorksheetConfig>,
+    pub voicing: VoicingSettings,
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            key: "C".to_string(),
+            progressions: Vec::new(),
+            worksheets: Vec::new(),
+            voicing: VoicingSettings::default(),
+        }
+    }
+}
+
+/// Saved projects are versioned; add a migration here whenever the shape changes
+impl Versioned for Project {
+    const KIND: &'static str = "project";
+    const MIGRATIONS: &'static [Migration] = &[];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_round_trip() {
+        let project = Project {
+            name: "Blues".to_string(),
+            key: "Bb".to_string(),
+            progressions: vec![ProjectProgression { name: "Head".to_string(), chords: vec!["Bb7".to_string()] }],
+            worksheets: Vec::new(),
+            voicing: VoicingSettings { style: "wide".to_string(), base_octave: 3, preset: Some("satb".to_string()) },
+        };
+        let saved = versioned::to_string_pretty(&project).unwrap();
+        let loaded = versioned::from_str::<Project>(&saved).unwrap();
+        assert_eq!(loaded.upgraded_from, None);
+        assert_eq!(loaded.value.key, "Bb");
+        assert_eq!(loaded.value.progressions, project.progressions);
+        assert_eq!(loaded.value.voicing, project.voicing);
+    }
+
+    #[test]
+    fn test_missing_fields_take_defaults() {
+        let loaded = versioned::from_value::<Project>(json!({"version": 1, "data": {"name": "Sketch"}})).unwrap();
+        assert_eq!(loaded.value.key, "C");
+        assert_eq!(loaded.value.voicing, VoicingSettings::default());
+        assert!(versioned::from_value::<Project>(json!({"version": 2, "data": {}})).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+// Song arrangement
+// A song is a list of sections (verse, chorus, ...) each with its own progression, key and tempo
+
+use serde::{Deserialize, Serialize};
+
+use super::versioned::{Migration, Versioned};
+
+/// Role of a section in the song
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SectionKind {
+    Intro,
+    Verse,
+    PreChorus,
+    Chorus,
+    Bridge,
+    Solo,
+    Outro,
+    Other,
+}
+
+impl SectionKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            SectionKind::Intro => "Intro",
+            SectionKind::Verse => "Verse",
+            SectionKind::PreChorus => "Pre-Chorus",
+            SectionKind::Chorus => "Chorus",
+            SectionKind::Bridge => "Bridge",
+            SectionKind::Solo => "Solo",
+            SectionKind::Outro => "Outro",
+            SectionKind::Other => "Section",
+        }
+    }
+}
+
+/// A chord held for a number of beats
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SongChord {
+    pub chord: String,
+    pub beats: f32,
+}
+
+/// A labelled point inside a section ("Coda", "Fine", "Break")
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SongMarker {
+    /// Chord the marker is placed on
+    pub chord_index: usize,
+    pub label: String,
+}
+
+/// A tempo and/or meter change taking effect at the start of a measure
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MeasureChange {
+    /// Measure the change applies from, counted from 1
+    pub measure: u32,
+    #[serde(default)]
+    pub tempo: Option<f32>,
+    #[serde(default)]
+    pub time_signature: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SongSection {
+    pub id: String,
+    pub kind: SectionKind,
+    /// Shown instead of the kind's name when set ("Verse 2")
+    #[serde(default)]
+    pub label: Option<String>,
+    pub key: String,
+    pub tempo: f32,
+    /// Rehearsal mark printed at the start of the section ("A", "B")
+    #[serde(default)]
+    pub rehearsal_mark: Option<String>,
+    pub chords: Vec<SongChord>,
+    #[serde(default)]
+    pub markers: Vec<SongMarker>,
+    /// Changes within the section, measures counted from its first measure
+    /// A meter change holds into later sections; each section starts at its own tempo
+    #[serde(default)]
+    pub changes: Vec<MeasureChange>,
+    /// Print the harmonic analysis (numerals, functions and cadences) beneath the staff
+    #[serde(default)]
+    pub show_analysis: bool,
+}
+
+impl SongSection {
+    pub fn display_name(&self) -> &str {
+        self.label.as_deref().filter(|label| !label.is_empty()).unwrap_or(self.kind.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Song {
+    pub title: String,
+    pub time_signature: String,
+    pub sections: Vec<SongSection>,
+}
+
+impl Default for Song {
+    fn default() -> Self {
+        Self { title: String::new(), time_signature: "4/4".to_string(), sections: Vec::new() }
+    }
+}
+
+/// Saved songs are versioned; add a migration here whenever the shape changes
+impl Versioned for Song {
+    const KIND: &'static str = "song";
+    const MIGRATIONS: &'static [Migration] = &[];
+}
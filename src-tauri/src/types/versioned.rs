@@ -0,0 +1,186 @@
+// Versioned serialization for saved documents
+// Documents are written in a {version, data} envelope and upgraded through a migration registry on load
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// Upgrade a document's JSON from one version to the next
+pub type Migration = fn(Value) -> Result<Value, String>;
+
+/// A serialized type with a migration history
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// Name used in error messages ("worksheet")
+    const KIND: &'static str;
+    /// Registry of migrations in order: entry 0 upgrades v1 to v2, entry 1 upgrades v2 to v3, ...
+    /// The current version is one past the last migration
+    const MIGRATIONS: &'static [Migration];
+
+    fn current_version() -> u32 {
+        Self::MIGRATIONS.len() as u32 + 1
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    version: u32,
+    data: Value,
+}
+
+/// A loaded document and the version it was upgraded from
+#[derive(Debug, Clone)]
+pub struct Loaded<T> {
+    pub value: T,
+    /// Set when the document was saved at an older version or without an envelope;
+    /// callers should write it back so it is upgraded in place
+    pub upgraded_from: Option<u32>,
+}
+
+/// Wrap a document in an envelope at the current version
+pub fn to_value<T: Versioned>(value: &T) -> Result<Value, String> {
+    let data = serde_json::to_value(value).map_err(|e| format!("Failed to serialize {}: {}", T::KIND, e))?;
+    serde_json::to_value(Envelope { version: T::current_version(), data })
+        .map_err(|e| format!("Failed to serialize {}: {}", T::KIND, e))
+}
+
+pub fn to_string_pretty<T: Versioned>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(&to_value(value)?).map_err(|e| format!("Failed to serialize {}: {}", T::KIND, e))
+}
+
+/// Split a saved value into its version and data
+/// Documents saved before envelopes existed are bare data and count as version 1
+fn open_envelope(value: Value) -> Result<(u32, Value, bool), String> {
+    match value {
+        Value::Object(ref map) if map.len() == 2 && map.contains_key("version") && map.contains_key("data") => {
+            let envelope: Envelope = serde_json::from_value(value).map_err(|e| format!("Invalid envelope: {}", e))?;
+            Ok((envelope.version, envelope.data, true))
+        }
+        bare => Ok((1, bare, false)),
+    }
+}
+
+/// Load a document from JSON, running any migrations it needs
+pub fn from_value<T: Versioned>(value: Value) -> Result<Loaded<T>, String> {
+    let (version, mut data, enveloped) = open_envelope(value)?;
+    let current = T::current_version();
+    if version == 0 || version > current {
+        return Err(format!(
+            "{} version {} is not supported (current version is {})",
+            T::KIND, version, current
+        ));
+    }
+
+    for migration in &T::MIGRATIONS[(version - 1) as usize..] {
+        data = migration(data).map_err(|e| format!("Failed to upgrade {} from version {}: {}", T::KIND, version, e))?;
+    }
+
+    let value = serde_json::from_value(data).map_err(|e| format!("Invalid {}: {}", T::KIND, e))?;
+    Ok(Loaded { value, upgraded_from: (version < current || !enveloped).then_some(version) })
+}
+
+#[allow(dead_code)]
+pub fn from_str<T: Versioned>(json: &str) -> Result<Loaded<T>, String> {
+    from_value(serde_json::from_str(json).map_err(|e| format!("Invalid {}: {}", T::KIND, e))?)
+}
+
+pub fn from_slice<T: Versioned>(json: &[u8]) -> Result<Loaded<T>, String> {
+    from_value(serde_json::from_slice(json).map_err(|e| format!("Invalid {}: {}", T::KIND, e))?)
+}
+
+/// Serde adapter for versioned documents listed inside another, `#[serde(with = "versioned::list")]`
+/// Each keeps its own envelope, so it upgrades through its own migrations when the outer document loads
+pub mod list {
+    use serde::{de, ser};
+
+    use super::*;
+
+    pub fn serialize<T: Versioned, S: Serializer>(values: &[T], serializer: S) -> Result<S::Ok, S::Error> {
+        let values = values.iter().map(to_value).collect::<Result<Vec<_>, _>>().map_err(ser::Error::custom)?;
+        values.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Versioned, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<T>, D::Error> {
+        Vec::<Value>::deserialize(deserializer)?
+            .into_iter()
+            .map(|value| from_value(value).map(|loaded| loaded.value))
+            .collect::<Result<_, _>>()
+            .map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// v1: {"name"}, v2: {"title"}, v3: {"title", "tags"}
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Doc {
+        title: String,
+        tags: Vec<String>,
+    }
+
+    fn rename_name_to_title(mut value: Value) -> Result<Value, String> {
+        let map = value.as_object_mut().ok_or("expected an object")?;
+        let name = map.remove("name").ok_or("missing name")?;
+        map.insert("title".to_string(), name);
+        Ok(value)
+    }
+
+    fn add_tags(mut value: Value) -> Result<Value, String> {
+        value.as_object_mut().ok_or("expected an object")?.insert("tags".to_string(), json!([]));
+        Ok(value)
+    }
+
+    impl Versioned for Doc {
+        const KIND: &'static str = "doc";
+        const MIGRATIONS: &'static [Migration] = &[rename_name_to_title, add_tags];
+    }
+
+    #[test]
+    fn test_round_trip_at_current_version() {
+        let doc = Doc { title: "A".to_string(), tags: vec!["x".to_string()] };
+        let value = to_value(&doc).unwrap();
+        assert_eq!(value["version"], 3);
+
+        let loaded = from_value::<Doc>(value).unwrap();
+        assert_eq!(loaded.value, doc);
+        assert_eq!(loaded.upgraded_from, None);
+    }
+
+    #[test]
+    fn test_bare_legacy_document_is_version_one() {
+        let loaded = from_str::<Doc>(r#"{"name": "Old"}"#).unwrap();
+        assert_eq!(loaded.value, Doc { title: "Old".to_string(), tags: Vec::new() });
+        assert_eq!(loaded.upgraded_from, Some(1));
+    }
+
+    #[test]
+    fn test_migrates_from_middle_version() {
+        let loaded = from_value::<Doc>(json!({"version": 2, "data": {"title": "Mid"}})).unwrap();
+        assert_eq!(loaded.value.title, "Mid");
+        assert_eq!(loaded.upgraded_from, Some(2));
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Shelf {
+        #[serde(with = "list")]
+        docs: Vec<Doc>,
+    }
+
+    #[test]
+    fn test_listed_documents_keep_their_own_versions() {
+        let docs = json!([{"name": "Old"}, {"version": 3, "data": {"title": "New", "tags": []}}]);
+        let shelf: Shelf = serde_json::from_value(json!({ "docs": docs })).unwrap();
+        assert_eq!(shelf.docs[0].title, "Old");
+        assert_eq!(serde_json::to_value(&shelf).unwrap()["docs"][0]["version"], 3);
+        assert!(serde_json::from_value::<Shelf>(json!({"docs": [{"version": 9, "data": {}}]})).is_err());
+    }
+
+    #[test]
+    fn test_rejects_unknown_versions_and_failed_migrations() {
+        assert!(from_value::<Doc>(json!({"version": 4, "data": {}})).is_err());
+        assert!(from_value::<Doc>(json!({"version": 0, "data": {}})).is_err());
+        assert!(from_value::<Doc>(json!({"version": 1, "data": {"title": "no name"}})).is_err());
+    }
+}
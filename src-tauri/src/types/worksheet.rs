@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use super::versioned::{Migration, Versioned};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum WorksheetType {
@@ -29,6 +31,29 @@ pub struct EditableElement {
     pub content: String,
     pub is_answer: bool,
     pub is_interactive: bool,
+    #[serde(default)]
+    pub style: Option<ElementStyle>,
+    /// Help printed in small type beneath the element, for supported versions of a worksheet
+    #[serde(default)]
+    pub hint: Option<String>,
+    /// MIDI notes highlighted on a small keyboard drawn beneath the element, when shown
+    #[serde(default)]
+    pub keyboard: Option<Vec<u8>>,
+}
+
+/// Optional look of an element, e.g. colored demo answers or circled targets
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ElementStyle {
+    /// Hex color ("#c0392b" or "#c33") for note heads and chord names
+    pub color: Option<String>,
+    pub enclosure: Option<Enclosure>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Enclosure {
+    Circle,
+    Box,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,6 +79,26 @@ pub struct WorksheetSectionLayout {
     pub clef: Clef,
     pub time_signature: Option<String>,
     pub key_signature: Option<String>,
+    /// How answers hidden on the student copy are left blank
+    #[serde(rename = "answerBlank", default)]
+    pub answer_blank: AnswerBlank,
+}
+
+/// Space left for a hidden answer, to match the conventions of different curricula
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnswerBlank {
+    /// A quarter rest
+    #[default]
+    Rest,
+    /// A line below the staff to write on
+    Line,
+    /// An empty box below the staff
+    Box,
+    /// The staff shaded gray where the answer goes
+    Shaded,
+    /// A "?" in place of the note heads
+    Question,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,6 +120,12 @@ pub struct WorksheetConfig {
     pub global_settings: WorksheetGlobalSettings,
 }
 
+/// Saved worksheets are versioned; add a migration here whenever the shape changes
+impl Versioned for WorksheetConfig {
+    const KIND: &'static str = "worksheet";
+    const MIGRATIONS: &'static [Migration] = &[];
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorksheetGlobalSettings {
     #[serde(rename = "paperSize")]
@@ -82,8 +133,14 @@ pub struct WorksheetGlobalSettings {
     pub orientation: Orientation,
     #[serde(rename = "showAnswers")]
     pub show_answers: bool,
+    /// Hex color shown answers are drawn in, over their own style (None = as styled)
+    #[serde(rename = "answerColor", default)]
+    pub answer_color: Option<String>,
     #[serde(rename = "fontSize")]
     pub font_size: u32,
+    /// Locale of the generated text ("es", "ar"); right-to-left locales set the header direction
+    #[serde(default)]
+    pub locale: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -105,6 +162,9 @@ pub struct ChordNamingParams {
     pub chords: Vec<ChordDefinition>,
     pub instructions: Option<String>,
     pub layout: ChordLayout,
+    /// Locale of the generated titles and instructions (None = English)
+    #[serde(default)]
+    pub locale: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,7 +176,7 @@ pub struct ChordDefinition {
     pub show_answer: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ChordQuality {
     Major,
@@ -129,6 +189,39 @@ pub enum ChordQuality {
     Major7,
     #[serde(rename = "minor7")]
     Minor7,
+    #[serde(rename = "halfDiminished7")]
+    HalfDiminished7,
+}
+
+/// Chord naming drill over every diatonic chord of one key, in random order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WholeKeyChordParams {
+    /// Key name ("Eb", "F#m"); minor keys use the natural minor
+    pub key: String,
+    /// Add the diatonic seventh chords after the triads
+    #[serde(rename = "includeSevenths", default)]
+    pub include_sevenths: bool,
+    #[serde(rename = "showAnswers", default)]
+    pub show_answers: bool,
+    pub instructions: Option<String>,
+    pub layout: ChordLayout,
+    /// Locale of the generated titles and instructions (None = English)
+    #[serde(default)]
+    pub locale: Option<String>,
+}
+
+/// Printable reference chart of every diatonic triad and seventh chord, one page per key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceChartParams {
+    /// Key name ("Eb", "F#m"); None charts all twelve major keys
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Draw each chord on a small keyboard beneath the staff
+    #[serde(rename = "keyboardDiagrams", default)]
+    pub keyboard_diagrams: bool,
+    /// Locale of the generated titles (None = English)
+    #[serde(default)]
+    pub locale: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,4 +230,34 @@ pub struct ChordLayout {
     pub chords_per_line: u32,
     #[serde(rename = "showStaffLines")]
     pub show_staff_lines: bool,
-}
\ No newline at end of file
+}
+/// Note captured from a live keyboard performance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformedNote {
+    pub midi: u8,
+    /// Note-on time in milliseconds from any fixed origin
+    #[serde(rename = "startMs")]
+    pub start_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformanceParams {
+    pub notes: Vec<PerformedNote>,
+    #[serde(rename = "tempoBpm")]
+    pub tempo_bpm: f64,
+    #[serde(rename = "timeSignature")]
+    pub time_signature: Option<String>,
+    /// Quantization grid in beats (1 = every beat, 2 = every other beat, ...)
+    #[serde(rename = "gridBeats")]
+    pub grid_beats: Option<u32>,
+    /// Major key used to spell accidentals
+    pub key: Option<String>,
+    pub title: Option<String>,
+    pub instructions: Option<String>,
+    /// Mark captured elements as answers so they are hidden on the student copy
+    #[serde(rename = "asAnswers", default)]
+    pub as_answers: bool,
+    /// Locale of the generated titles and instructions (None = English)
+    #[serde(default)]
+    pub locale: Option<String>,
+}
@@ -14,6 +14,7 @@ interface PitchResult {
 interface ChordResponse {
   pitches: PitchResult[];
   display_name: string;
+  voicing: { note: string; octave: number; is_common_tone: boolean; cents: number }[];
 }
 
 interface ChordRequest {
@@ -21,6 +22,8 @@ interface ChordRequest {
   quality: string;
   root_octave: number;
   inversion?: string;
+  /** Explicit pitches for a hand-drawn chord; root, quality and inversion are then ignored */
+  pitches?: PitchResult[];
 }
 
 /**
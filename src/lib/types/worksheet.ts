@@ -28,6 +28,9 @@ export interface EditableElement {
   isInteractive: boolean; // Whether user can edit this element
 }
 
+// How a hidden answer is left blank on the student copy
+export type AnswerBlank = 'rest' | 'line' | 'box' | 'shaded' | 'question';
+
 export interface WorksheetSection {
   id: string;
   title: string;
@@ -39,6 +42,7 @@ export interface WorksheetSection {
     clef: 'treble' | 'bass' | 'both';
     timeSignature?: string;
     keySignature?: string;
+    answerBlank?: AnswerBlank;
   };
 }
 
@@ -52,6 +56,7 @@ export interface WorksheetConfig {
     paperSize: 'letter' | 'a4';
     orientation: 'portrait' | 'landscape';
     showAnswers: boolean;
+    answerColor?: string;
     fontSize: number;
   };
 }